@@ -0,0 +1,208 @@
+//! # Config builder
+//!
+//! Build a configuration from code, without any file, e.g.: when
+//! embedding the node as a library or in integration tests:
+//!
+//! ```
+//! use witnet_config::config::builder::ConfigBuilder;
+//! use witnet_data_structures::chain::Environment;
+//!
+//! let config = ConfigBuilder::new()
+//!     .environment(Environment::Testnet1)
+//!     .inbound_limit(100)
+//!     .db_path("/tmp/witnet")
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(config.connections.inbound_limit, 100);
+//! ```
+//!
+//! Params that are not set take the default value of the selected
+//! environment, and the result is validated just like a configuration
+//! read from a file.
+use super::consolidation::ConsolidationError;
+use super::{partial, Config, StorageBackend};
+use log::LevelFilter;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use witnet_data_structures::chain::Environment;
+
+/// Builder of a total configuration, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigBuilder {
+    config: partial::Config,
+}
+
+impl ConfigBuilder {
+    /// Create a builder with no params set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder starting from a partial configuration, e.g.:
+    /// one read by a loader
+    pub fn from_partial(config: partial::Config) -> Self {
+        Self { config }
+    }
+
+    /// Set the environment
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.config.environment = Some(environment);
+        self
+    }
+
+    /// Set `connections.server_addr` to a single address
+    pub fn server_addr(mut self, addr: SocketAddr) -> Self {
+        self.config.connections.server_addrs = Some(vec![addr]);
+        self
+    }
+
+    /// Set `connections.server_addr` to several addresses, the first of
+    /// which is announced to the peers
+    pub fn server_addrs(mut self, addrs: Vec<SocketAddr>) -> Self {
+        self.config.connections.server_addrs = Some(addrs);
+        self
+    }
+
+    /// Set `connections.magic_number`
+    pub fn magic_number(mut self, magic_number: u16) -> Self {
+        self.config.connections.magic_number = Some(magic_number);
+        self
+    }
+
+    /// Set `connections.inbound_limit`
+    pub fn inbound_limit(mut self, limit: u16) -> Self {
+        self.config.connections.inbound_limit = Some(limit);
+        self
+    }
+
+    /// Set `connections.outbound_limit`
+    pub fn outbound_limit(mut self, limit: u16) -> Self {
+        self.config.connections.outbound_limit = Some(limit);
+        self
+    }
+
+    /// Add a peer to `connections.known_peers`
+    pub fn known_peer(mut self, addr: SocketAddr) -> Self {
+        self.config.connections.known_peers.insert(addr);
+        self
+    }
+
+    /// Set `connections.handshake_timeout_seconds`
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connections.handshake_timeout = Some(timeout);
+        self
+    }
+
+    /// Set `storage.backend`
+    pub fn storage_backend(mut self, backend: StorageBackend) -> Self {
+        self.config.storage.backend = Some(backend);
+        self
+    }
+
+    /// Set `storage.db_path`
+    pub fn db_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.config.storage.db_path = Some(path.into());
+        self
+    }
+
+    /// Set `storage.max_db_size`, in bytes
+    pub fn max_db_size(mut self, size: u64) -> Self {
+        self.config.storage.max_db_size = Some(size);
+        self
+    }
+
+    /// Set `storage.cache_size`, in bytes
+    pub fn cache_size(mut self, size: u64) -> Self {
+        self.config.storage.cache_size = Some(size);
+        self
+    }
+
+    /// Set `consensus_constants.checkpoint_zero_timestamp`
+    pub fn checkpoint_zero_timestamp(mut self, timestamp: i64) -> Self {
+        self.config.consensus_constants.checkpoint_zero_timestamp = Some(timestamp);
+        self
+    }
+
+    /// Set `jsonrpc.enabled`
+    pub fn jsonrpc_enabled(mut self, enabled: bool) -> Self {
+        self.config.jsonrpc.enabled = Some(enabled);
+        self
+    }
+
+    /// Set `jsonrpc.server_address`
+    pub fn jsonrpc_server_address(mut self, addr: SocketAddr) -> Self {
+        self.config.jsonrpc.server_address = Some(addr);
+        self
+    }
+
+    /// Set `mining.enabled`
+    pub fn mining_enabled(mut self, enabled: bool) -> Self {
+        self.config.mining.enabled = Some(enabled);
+        self
+    }
+
+    /// Set `log.level`
+    pub fn log_level(mut self, level: LevelFilter) -> Self {
+        self.config.log.level = Some(level);
+        self
+    }
+
+    /// The partial configuration built so far
+    pub fn partial(&self) -> &partial::Config {
+        &self.config
+    }
+
+    /// Consolidate and validate the configuration, returning every
+    /// problem found (see `Config::try_from_partial`).
+    pub fn build(self) -> Result<Config, ConsolidationError> {
+        Config::try_from_partial(&self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::consolidation::Problem;
+    use crate::validation::ValidationError;
+
+    #[test]
+    fn test_build_default() {
+        assert_eq!(
+            ConfigBuilder::new().build(),
+            Ok(Config::from_partial(&partial::Config::default()))
+        );
+    }
+
+    #[test]
+    fn test_build() {
+        let peer: SocketAddr = "192.168.1.12:21337".parse().unwrap();
+        let config = ConfigBuilder::new()
+            .environment(Environment::Testnet1)
+            .inbound_limit(100)
+            .known_peer(peer)
+            .db_path("dbfiles")
+            .log_level(LevelFilter::Debug)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.environment, Environment::Testnet1);
+        assert_eq!(config.connections.inbound_limit, 100);
+        assert!(config.connections.known_peers.contains(&peer));
+        assert_eq!(config.storage.db_path, PathBuf::from("dbfiles"));
+        assert_eq!(config.log.level, LevelFilter::Debug);
+    }
+
+    #[test]
+    fn test_build_validates() {
+        let result = ConfigBuilder::new().outbound_limit(0).build();
+
+        assert_eq!(
+            result.unwrap_err().problems,
+            vec![Problem::Invalid(ValidationError::Zero {
+                param: "connections.outbound_limit"
+            })]
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! # Consolidation errors
+//!
+//! Turning a partial configuration into a total one can fail for
+//! several reasons at once: missing params that have no default,
+//! addresses that cannot be used, params identifying a network other
+//! than the selected environment, etc. Instead of
+//! stopping at the first one, all the problems are collected into a
+//! single `ConsolidationError`, so they can be fixed in one go.
+use super::partial;
+use crate::defaults::{self, Defaults};
+use crate::validation::ValidationError;
+use failure::Fail;
+use std::fmt;
+use std::net::SocketAddr;
+use witnet_data_structures::chain::Environment;
+
+/// A problem found when consolidating a partial configuration
+#[derive(Debug, Clone, PartialEq, Fail)]
+pub enum Problem {
+    /// The mainnet environment cannot be used yet
+    MainnetNotAllowed,
+    /// A param without default value in the selected environment is
+    /// missing
+    MissingParam {
+        /// Name of the environment
+        environment: String,
+        /// Name of the param, as written in the Toml file
+        param: &'static str,
+    },
+    /// A socket address cannot be used
+    InvalidAddress {
+        /// Name of the param, as written in the Toml file
+        param: &'static str,
+        /// The invalid address
+        addr: SocketAddr,
+        /// Reason why the address cannot be used
+        reason: &'static str,
+    },
+    /// A param identifying the network does not match the selected
+    /// environment
+    Inconsistent {
+        /// Name of the param, as written in the Toml file
+        param: &'static str,
+        /// Name of the environment
+        environment: String,
+        /// What does not match
+        reason: String,
+    },
+    /// The consolidated configuration is not valid
+    Invalid(ValidationError),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::MainnetNotAllowed => {
+                write!(
+                    f,
+                    "Config with mainnet environment is currently not allowed"
+                )
+            }
+            Problem::MissingParam { environment, param } => write!(
+                f,
+                "`{}` is required because environment `{}` has no default value for it",
+                param, environment
+            ),
+            Problem::InvalidAddress {
+                param,
+                addr,
+                reason,
+            } => write!(
+                f,
+                "`{}` contains an invalid address {}: {}",
+                param, addr, reason
+            ),
+            Problem::Inconsistent {
+                param,
+                environment,
+                reason,
+            } => write!(
+                f,
+                "`{}` is inconsistent with environment `{}`: {}",
+                param, environment, reason
+            ),
+            Problem::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Every problem found when consolidating a partial configuration
+#[derive(Debug, Clone, PartialEq, Fail)]
+pub struct ConsolidationError {
+    /// Problems found, in the order they were detected
+    pub problems: Vec<Problem>,
+}
+
+impl fmt::Display for ConsolidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Found {} problem(s) in the configuration:",
+            self.problems.len()
+        )?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check the socket addresses given in a partial configuration
+pub fn check_addresses(config: &partial::Config, problems: &mut Vec<Problem>) {
+    let server_addrs = config.connections.server_addrs.iter().flatten();
+    let listeners = server_addrs
+        .map(|addr| ("connections.server_addr", *addr))
+        .chain(
+            config
+                .jsonrpc
+                .server_address
+                .map(|addr| ("jsonrpc.server_address", addr)),
+        )
+        .chain(
+            config
+                .jsonrpc
+                .websocket_address
+                .map(|addr| ("jsonrpc.websocket_address", addr)),
+        )
+        .chain(
+            config
+                .rest
+                .server_address
+                .map(|addr| ("rest.server_address", addr)),
+        );
+    for (param, addr) in listeners {
+        if addr.port() == 0 {
+            problems.push(Problem::InvalidAddress {
+                param,
+                addr,
+                reason: "the port cannot be 0",
+            });
+        }
+    }
+
+    let mut known_peers: Vec<&SocketAddr> = config.connections.known_peers.iter().collect();
+    known_peers.sort();
+    for addr in known_peers {
+        let reason = if addr.port() == 0 {
+            Some("the port cannot be 0")
+        } else if addr.ip().is_unspecified() {
+            Some("peers cannot be reached at an unspecified IP")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            problems.push(Problem::InvalidAddress {
+                param: "connections.known_peers",
+                addr: *addr,
+                reason,
+            });
+        }
+    }
+}
+
+/// Check that the params identifying the network (magic number and
+/// genesis hash) match the selected environment. Built-in environments
+/// cannot change them, while custom environments cannot reuse the
+/// magic number of a built-in one.
+pub fn check_environment(config: &partial::Config, problems: &mut Vec<Problem>) {
+    let environment = config.environment.clone().unwrap_or_default();
+    let others: Vec<(Environment, Box<dyn Defaults>)> =
+        [Environment::Mainnet, Environment::Testnet1]
+            .iter()
+            .filter(|other| **other != environment)
+            .filter_map(|other| Some((other.clone(), defaults::for_environment(other)?)))
+            .collect();
+    let owner = |magic_number: u16| {
+        others
+            .iter()
+            .find(|(_, defaults)| defaults.connections_magic_number() == magic_number)
+            .map(|(other, _)| other)
+    };
+
+    match defaults::for_environment(&environment) {
+        Some(defaults) => {
+            if let Some(magic_number) = config.connections.magic_number {
+                let expected = defaults.connections_magic_number();
+                if magic_number != expected {
+                    let reason = match owner(magic_number) {
+                        Some(other) => format!(
+                            "{} is the magic number of `{}`, expected {}",
+                            magic_number, other, expected
+                        ),
+                        None => format!("expected {}, found {}", expected, magic_number),
+                    };
+                    problems.push(Problem::Inconsistent {
+                        param: "connections.magic_number",
+                        environment: environment.to_string(),
+                        reason,
+                    });
+                }
+            }
+            if let Some(genesis_hash) = config.consensus_constants.genesis_hash {
+                if genesis_hash != defaults.consensus_constants_genesis_hash() {
+                    problems.push(Problem::Inconsistent {
+                        param: "consensus_constants.genesis_hash",
+                        environment: environment.to_string(),
+                        reason: "it does not match the genesis block of the environment, \
+                                 use a custom environment to run a different network"
+                            .to_string(),
+                    });
+                }
+            }
+        }
+        None => {
+            if let Some(magic_number) = config.connections.magic_number {
+                if let Some(other) = owner(magic_number) {
+                    problems.push(Problem::Inconsistent {
+                        param: "connections.magic_number",
+                        environment: environment.to_string(),
+                        reason: format!(
+                            "{} is the magic number of `{}`, custom environments need their own",
+                            magic_number, other
+                        ),
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_data_structures::chain::Hash;
+
+    #[test]
+    fn test_check_addresses() {
+        let mut config = partial::Config::default();
+        config.connections.server_addrs = Some(vec![
+            "127.0.0.1:4001".parse().unwrap(),
+            "127.0.0.1:0".parse().unwrap(),
+        ]);
+        config.jsonrpc.server_address = Some("127.0.0.1:4000".parse().unwrap());
+        config
+            .connections
+            .known_peers
+            .insert("0.0.0.0:5000".parse().unwrap());
+        config
+            .connections
+            .known_peers
+            .insert("127.0.0.1:5000".parse().unwrap());
+
+        let mut problems = vec![];
+        check_addresses(&config, &mut problems);
+
+        assert_eq!(
+            problems,
+            vec![
+                Problem::InvalidAddress {
+                    param: "connections.server_addr",
+                    addr: "127.0.0.1:0".parse().unwrap(),
+                    reason: "the port cannot be 0",
+                },
+                Problem::InvalidAddress {
+                    param: "connections.known_peers",
+                    addr: "0.0.0.0:5000".parse().unwrap(),
+                    reason: "peers cannot be reached at an unspecified IP",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_consolidation_error_display() {
+        let error = ConsolidationError {
+            problems: vec![
+                Problem::MainnetNotAllowed,
+                Problem::MissingParam {
+                    environment: "custom:devnet".to_string(),
+                    param: "storage.db_path",
+                },
+            ],
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Found 2 problem(s) in the configuration:\n  \
+             - Config with mainnet environment is currently not allowed\n  \
+             - `storage.db_path` is required because environment `custom:devnet` has no default value for it"
+        );
+    }
+
+    #[test]
+    fn test_check_environment() {
+        let mut config = partial::Config::default();
+        config.environment = Some(Environment::Testnet1);
+        config.connections.magic_number = Some(0x5749);
+        config.consensus_constants.genesis_hash = Some(Hash::SHA256([1; 32]));
+
+        let mut problems = vec![];
+        check_environment(&config, &mut problems);
+
+        let params: Vec<&str> = problems
+            .iter()
+            .filter_map(|problem| match problem {
+                Problem::Inconsistent { param, .. } => Some(*param),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                "connections.magic_number",
+                "consensus_constants.genesis_hash"
+            ]
+        );
+        assert!(problems[0]
+            .to_string()
+            .contains("magic number of `mainnet`"));
+    }
+
+    #[test]
+    fn test_check_environment_defaults() {
+        let mut config = partial::Config::default();
+        config.connections.magic_number =
+            Some(crate::defaults::Testnet1.connections_magic_number());
+
+        let mut problems = vec![];
+        check_environment(&config, &mut problems);
+
+        assert_eq!(problems, vec![]);
+    }
+
+    #[test]
+    fn test_check_custom_environment() {
+        let mut config = partial::Config::default();
+        config.environment = Some(Environment::Custom("devnet".to_string()));
+        config.connections.magic_number = Some(0x5749);
+
+        let mut problems = vec![];
+        check_environment(&config, &mut problems);
+
+        assert_eq!(problems.len(), 1);
+
+        config.connections.magic_number = Some(0x1234);
+        problems.clear();
+        check_environment(&config, &mut problems);
+
+        assert_eq!(problems, vec![]);
+    }
+}
@@ -0,0 +1,170 @@
+//! # Config diff
+//!
+//! Compare two total configurations param by param. Params are
+//! addressed by their path in the Toml file, e.g.:
+//! `connections.inbound_limit`.
+use super::redaction::{is_sensitive, REDACTED};
+use super::Config;
+use std::fmt;
+use toml::value::{Table, Value};
+
+/// A param whose value differs between two configurations
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// Path of the param, e.g.: `connections.inbound_limit`
+    pub path: String,
+    /// Value in the old configuration
+    pub old: Value,
+    /// Value in the new configuration
+    pub new: Value,
+}
+
+/// The values of sensitive params are not printed (see
+/// `redaction::is_sensitive`)
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if is_sensitive(&self.path) {
+            write!(f, "{}: {} -> {}", self.path, REDACTED, REDACTED)
+        } else {
+            write!(f, "{}: {} -> {}", self.path, self.old, self.new)
+        }
+    }
+}
+
+impl Config {
+    /// List the params that differ between this configuration and
+    /// `other`, sorted by path. Lists of values, such as
+    /// `connections.known_peers`, are compared regardless of their
+    /// order.
+    pub fn diff(&self, other: &Config) -> Vec<FieldChange> {
+        let mut changes = vec![];
+        diff_tables("", &self.to_table(), &other.to_table(), &mut changes);
+
+        changes
+    }
+
+    /// The configuration as a Toml table, using the same params names
+    /// accepted by the Toml loader
+    pub(super) fn to_table(&self) -> Table {
+        match Value::try_from(self) {
+            Ok(Value::Table(table)) => table,
+            // The configuration is always serialized as a table
+            _ => Table::new(),
+        }
+    }
+}
+
+fn diff_tables(prefix: &str, old: &Table, new: &Table, changes: &mut Vec<FieldChange>) {
+    let empty = Value::Table(Table::new());
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let old_value = old.get(key).unwrap_or(&empty);
+        let new_value = new.get(key).unwrap_or(&empty);
+
+        match (old_value, new_value) {
+            (Value::Table(old_table), Value::Table(new_table)) => {
+                diff_tables(&path, old_table, new_table, changes)
+            }
+            (Value::Array(old_array), Value::Array(new_array))
+                if same_elements(old_array, new_array) => {}
+            (old_value, new_value) if old_value != new_value => changes.push(FieldChange {
+                path,
+                old: old_value.clone(),
+                new: new_value.clone(),
+            }),
+            _ => {}
+        }
+    }
+}
+
+/// Compare two arrays regardless of the order of their elements
+fn same_elements(a: &[Value], b: &[Value]) -> bool {
+    let sorted = |values: &[Value]| {
+        let mut values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        values.sort();
+        values
+    };
+
+    a.len() == b.len() && sorted(a) == sorted(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_diff_equal_configs() {
+        let mut a = Config::default();
+        let mut b = Config::default();
+        let peer_1 = "127.0.0.1:1000".parse().unwrap();
+        let peer_2 = "127.0.0.1:2000".parse().unwrap();
+        a.connections.known_peers.insert(peer_1);
+        a.connections.known_peers.insert(peer_2);
+        b.connections.known_peers.insert(peer_2);
+        b.connections.known_peers.insert(peer_1);
+
+        assert_eq!(a.diff(&b), vec![]);
+    }
+
+    #[test]
+    fn test_diff_changed_fields() {
+        let old = Config::default();
+        let mut new = Config::default();
+        new.connections.inbound_limit = 999;
+        new.connections.handshake_timeout = Duration::from_secs(21);
+        new.jsonrpc.enabled = false;
+
+        let changes = old.diff(&new);
+
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    path: "connections.handshake_timeout_seconds".to_string(),
+                    old: Value::Integer(old.connections.handshake_timeout.as_secs() as i64),
+                    new: Value::Integer(21),
+                },
+                FieldChange {
+                    path: "connections.inbound_limit".to_string(),
+                    old: Value::Integer(i64::from(old.connections.inbound_limit)),
+                    new: Value::Integer(999),
+                },
+                FieldChange {
+                    path: "jsonrpc.enabled".to_string(),
+                    old: Value::Boolean(true),
+                    new: Value::Boolean(false),
+                },
+            ]
+        );
+        assert_eq!(
+            changes[1].to_string(),
+            format!(
+                "connections.inbound_limit: {} -> 999",
+                old.connections.inbound_limit
+            )
+        );
+    }
+
+    #[test]
+    fn test_sensitive_change_display() {
+        let change = FieldChange {
+            path: "jsonrpc.auth_token".to_string(),
+            old: Value::String("old".to_string()),
+            new: Value::String("new".to_string()),
+        };
+
+        assert_eq!(
+            change.to_string(),
+            "jsonrpc.auth_token: <redacted> -> <redacted>"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! # Config fingerprint
+//!
+//! A hash of the effective configuration, which allows checking that
+//! two nodes run with identical configurations without comparing them
+//! param by param. The fingerprint does not depend on the order of
+//! lists of values, such as `connections.known_peers`.
+use super::Config;
+use toml::value::Value;
+use witnet_crypto::hash::calculate_sha256;
+use witnet_data_structures::chain::Hash;
+
+impl Config {
+    /// SHA-256 hash of the configuration written in a canonical form
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let canonical = canonicalize(Value::Table(self.to_table()));
+        let Hash::SHA256(hash) = calculate_sha256(canonical.to_string().as_bytes());
+
+        hash
+    }
+
+    /// The fingerprint written in hexadecimal
+    pub fn fingerprint_hex(&self) -> String {
+        self.fingerprint()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+/// Sort the values of every list. Tables are already sorted by key.
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Array(values) => {
+            let mut values: Vec<Value> = values.into_iter().map(canonicalize).collect();
+            values.sort_by_key(Value::to_string);
+            Value::Array(values)
+        }
+        Value::Table(table) => Value::Table(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, canonicalize(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        let mut a = Config::default();
+        let mut b = Config::default();
+        for peer in &["127.0.0.1:1001", "127.0.0.1:1002", "127.0.0.1:1003"] {
+            a.connections.known_peers.insert(peer.parse().unwrap());
+        }
+        for peer in &["127.0.0.1:1003", "127.0.0.1:1001", "127.0.0.1:1002"] {
+            b.connections.known_peers.insert(peer.parse().unwrap());
+        }
+
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_eq!(a.fingerprint_hex().len(), 64);
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_params() {
+        let a = Config::default();
+        let mut b = Config::default();
+        b.connections.inbound_limit += 1;
+
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+}
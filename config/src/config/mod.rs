@@ -39,22 +39,51 @@
 //! // Config::from_partial(&partial::Config::default_mainnet());
 //! ```
 
-use crate::defaults::{Defaults, Testnet1};
-use log::warn;
+use self::consolidation::{ConsolidationError, Problem};
+use crate::defaults::{self, Custom, Defaults};
+use crate::migrations;
+use crate::secret::Secret;
+use crate::validation;
+use log::{warn, LevelFilter};
+use serde::ser::{SerializeMap, Serializer};
 use std::collections::HashSet;
+use std::fmt;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 
-use witnet_data_structures::chain::{ConsensusConstants, Environment};
+use witnet_data_structures::chain::{Checkpoint, ConsensusConstants, Environment, Epoch, Hash};
+use witnet_util::net::IpNetwork;
 
 /// Module containing the partial configuration struct that is
 /// returned by the loaders.
 pub mod partial;
 
+/// Module to compare two total configurations.
+pub mod diff;
+
+/// Module containing the errors found when consolidating a partial
+/// configuration.
+pub mod consolidation;
+
+/// Module to reload the configuration while the node is running.
+pub mod reload;
+
+/// Module to compute a fingerprint of a total configuration.
+pub mod fingerprint;
+
+/// Module listing the optional subsystems of the node.
+pub mod subsystems;
+
+/// Module to build a configuration from code.
+pub mod builder;
+
+/// Module to hide sensitive params when printing a configuration.
+pub mod redaction;
+
 /// The total configuration object that contains all other, more
 /// specific, configuration objects (connections, storage, etc).
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Config {
     /// The "environment" in which the protocol will be deployed, eg:
     /// mainnet, testnet, etc.
@@ -67,19 +96,51 @@ pub struct Config {
     pub storage: Storage,
 
     /// Consensus-critical configuration
+    #[serde(serialize_with = "serialize_consensus_constants")]
     pub consensus_constants: ConsensusConstants,
 
     /// JSON-RPC API configuration
     pub jsonrpc: JsonRPC,
+
+    /// REST API configuration
+    pub rest: Rest,
+
+    /// Mining-related configuration
+    pub mining: Mining,
+
+    /// Wallet-related configuration
+    pub wallet: Wallet,
+
+    /// Metrics-related configuration
+    pub metrics: Metrics,
+
+    /// Bridge-related configuration
+    pub bridge: Bridge,
+
+    /// Node identity configuration
+    pub identity: Identity,
+
+    /// Logging configuration
+    pub log: Log,
+
+    /// Chain synchronization configuration
+    pub sync: Synchronization,
 }
 
+/// Scheme of the URL of the proxy, only SOCKS5 proxies are supported
+const PROXY_SCHEME: &str = "socks5://";
+
 /// Connection-specific configuration.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Connections {
-    /// Server address, that is, the socket address (interface ip and
-    /// port) to which the server accepting connections from other
-    /// peers should bind to
-    pub server_addr: SocketAddr,
+    /// Server addresses, that is, the socket addresses (interface ip
+    /// and port) to which the server accepting connections from other
+    /// peers should bind to. The first one is announced to the peers
+    #[serde(rename = "server_addr", serialize_with = "one_or_many")]
+    pub server_addrs: Vec<SocketAddr>,
+
+    /// Magic number identifying the network in protocol messages
+    pub magic_number: u16,
 
     /// Maximum number of concurrent connections the server should
     /// accept
@@ -89,51 +150,803 @@ pub struct Connections {
     /// (acting as a client) should maintain
     pub outbound_limit: u16,
 
+    /// Maximum number of inbound connections from the same IP address
+    pub max_connections_per_ip: u16,
+
+    /// Maximum number of inbound connections from the same subnet
+    /// (/24 for IPv4, /64 for IPv6)
+    pub max_connections_per_subnet: u16,
+
     /// List of other peer addresses this node knows at start, it is
     /// used as a bootstrap mechanism to gain access to the P2P
     /// network
     pub known_peers: HashSet<SocketAddr>,
 
+    /// Domain names resolved to discover peers, written as `host` or
+    /// `host:port` (the port of `server_addr` is used if missing)
+    pub dns_seeds: HashSet<String>,
+
+    /// IP addresses and networks allowed to connect to this node and
+    /// to be connected to. If empty, every peer is allowed
+    pub whitelist: HashSet<IpNetwork>,
+
+    /// IP addresses and networks never allowed to connect to this
+    /// node nor to be connected to
+    pub blacklist: HashSet<IpNetwork>,
+
+    /// IP addresses and networks of priority peers, which can connect
+    /// to this node even when `inbound_limit` is reached, taking the
+    /// slot of the inbound session of another peer
+    pub priority_peers: HashSet<IpNetwork>,
+
+    /// Period of the DNS seeds resolution task
+    #[serde(rename = "dns_seeds_period_seconds", serialize_with = "as_secs")]
+    pub dns_seeds_period: Duration,
+
     /// Period of the bootstrap peers task
+    #[serde(rename = "bootstrap_peers_period_seconds", serialize_with = "as_secs")]
     pub bootstrap_peers_period: Duration,
 
     /// Period of the persist peers task
+    #[serde(rename = "storage_peers_period_seconds", serialize_with = "as_secs")]
     pub storage_peers_period: Duration,
 
+    /// Time after which the stored peers expire if they are not
+    /// persisted again
+    #[serde(rename = "storage_peers_ttl_seconds", serialize_with = "as_secs")]
+    pub storage_peers_ttl: Duration,
+
     /// Period of the peers discovery task
+    #[serde(rename = "discovery_peers_period_seconds", serialize_with = "as_secs")]
     pub discovery_peers_period: Duration,
 
+    /// Period of the feeler connections task, `0` disables the
+    /// feeler connections
+    #[serde(rename = "feeler_peers_period_seconds", serialize_with = "as_secs")]
+    pub feeler_peers_period: Duration,
+
     /// Handshake timeout
+    #[serde(rename = "handshake_timeout_seconds", serialize_with = "as_secs")]
     pub handshake_timeout: Duration,
+
+    /// Time without receiving messages from a peer after which it is
+    /// pinged, `0` disables the pings
+    #[serde(rename = "ping_period_seconds", serialize_with = "as_secs")]
+    pub ping_period: Duration,
+
+    /// Time to wait for the answer to a ping before disconnecting
+    /// from the peer
+    #[serde(rename = "ping_timeout_seconds", serialize_with = "as_secs")]
+    pub ping_timeout: Duration,
+
+    /// Misbehavior score at which a peer gets banned, `0` disables
+    /// banning
+    pub ban_threshold: u32,
+
+    /// Time during which a banned peer cannot connect to this node
+    #[serde(rename = "ban_duration_seconds", serialize_with = "as_secs")]
+    pub ban_duration: Duration,
+
+    /// Maximum upload bandwidth of all the connections with the
+    /// peers, in kilobits per second, `0` means no limit
+    pub max_upload_kbps: u32,
+
+    /// Maximum download bandwidth of all the connections with the
+    /// peers, in kilobits per second, `0` means no limit
+    pub max_download_kbps: u32,
+
+    /// Maximum upload bandwidth of each connection with a peer, in
+    /// kilobits per second, `0` means no limit
+    pub max_peer_upload_kbps: u32,
+
+    /// Maximum download bandwidth of each connection with a peer, in
+    /// kilobits per second, `0` means no limit
+    pub max_peer_download_kbps: u32,
+
+    /// Ask the router to forward the port of `server_addr` to this
+    /// node, and announce the external address of the router to the
+    /// peers
+    pub upnp: bool,
+
+    /// Discover the peers in the local network, and announce this
+    /// node to them, using multicast DNS
+    pub mdns: bool,
+
+    /// Compression of the messages exchanged with the peers which
+    /// support it
+    pub compression: MessageCompression,
+
+    /// TLS termination of the inbound connections
+    pub tls: Tls,
+
+    /// Peers terminating TLS on their P2P listener, to which the
+    /// outbound connections are wrapped in TLS
+    pub tls_peers: Vec<TlsPeer>,
+
+    /// Path to the PEM file containing the certificates of the
+    /// authorities that sign the certificates of `tls_peers`, if any
+    pub tls_ca: Option<PathBuf>,
+
+    /// Maximum rates at which a peer can send each kind of message
+    pub rate_limits: RateLimits,
+
+    /// Cap of outbound connections with peers of the same network
+    /// group
+    pub diversity: Diversity,
+
+    /// Maximum sizes of the messages received from the peers, and
+    /// time they have to answer the requests of the node
+    pub limits: Limits,
+
+    /// Policy for relaying the blocks received from the peers to the
+    /// other peers
+    pub relay: Relay,
+
+    /// URL of the SOCKS5 proxy through which the outbound
+    /// connections are opened, if any, e.g.:
+    /// `socks5://127.0.0.1:9050`
+    pub proxy: Option<String>,
+}
+
+/// TLS termination of the inbound connections. TLS is enabled only if
+/// both a certificate and a private key are given.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tls {
+    /// Path to the PEM file containing the certificate chain of the
+    /// server, if any
+    pub cert: Option<PathBuf>,
+    /// Path to the PEM file containing the private key of the server,
+    /// if any
+    pub key: Option<PathBuf>,
+}
+
+/// Peer terminating TLS on its P2P listener
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsPeer {
+    /// Socket address of the peer
+    pub address: SocketAddr,
+    /// Name of the peer in its certificate, e.g.: `node.example.com`
+    pub server_name: String,
+}
+
+/// Maximum rates at which a peer can send each kind of message, in
+/// messages per minute. A peer can send a minute worth of messages
+/// at once, and the messages over the limit are dropped. `0` disables
+/// the limit of a kind of message.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RateLimits {
+    /// Maximum rate of `Inv` messages
+    pub inv: u32,
+
+    /// Maximum rate of `GetData` messages
+    pub get_data: u32,
+
+    /// Maximum rate of `GetPeers` and `Peers` messages
+    pub peers: u32,
+}
+
+/// Cap of outbound connections with peers of the same network group,
+/// to make it harder for an attacker controlling a few networks to
+/// take all the outbound slots of the node. The peers are grouped by
+/// the autonomous system announcing their address, if an AS map is
+/// given and the address is found in it, or by their network (/16
+/// for IPv4, /32 for IPv6) otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diversity {
+    /// Maximum number of outbound connections with peers of the
+    /// same network group, `0` disables the cap
+    pub max_outbound_per_group: u16,
+
+    /// Path to the file mapping the IP networks to the numbers of
+    /// the autonomous systems announcing them, one network per line,
+    /// e.g.: `192.0.2.0/24 64496`
+    pub asmap: Option<PathBuf>,
+}
+
+/// Maximum sizes of the messages received from the peers, in bytes,
+/// and time they have to answer the requests of the node. The sizes
+/// are those of the messages once decompressed. A frame bigger than
+/// `max_message_size` is rejected as soon as its length prefix
+/// arrives, and the peer is disconnected. A message over the size
+/// limit of its kind is dropped and the peer is penalized.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Limits {
+    /// Maximum size of any message
+    pub max_message_size: u64,
+
+    /// Maximum size of `Block` messages
+    pub max_block_size: u64,
+
+    /// Maximum size of `Headers` messages
+    pub max_headers_size: u64,
+
+    /// Maximum size of `Inv` and `GetData` messages
+    pub max_inventory_size: u64,
+
+    /// Maximum size of `Peers` messages
+    pub max_peers_size: u64,
+
+    /// Time a peer has to answer a `GetPeers` message before its
+    /// session is closed, `0` disables the timeout
+    #[serde(rename = "get_peers_timeout_seconds", serialize_with = "as_secs")]
+    pub get_peers_timeout: Duration,
+
+    /// Time a peer has to answer a `GetHeaders` message before its
+    /// session is closed, `0` disables the timeout
+    #[serde(rename = "get_headers_timeout_seconds", serialize_with = "as_secs")]
+    pub get_headers_timeout: Duration,
+}
+
+/// Policy for relaying the blocks received from the peers to the other
+/// peers. The blocks added by the node itself are always relayed, and
+/// the peers can only send blocks once their handshake is complete.
+/// A block which is not relayed is still added to the chain.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Relay {
+    /// Relay the blocks that the peers send without the node
+    /// requesting them
+    pub unsolicited: bool,
+
+    /// Minimum reputation score of the peers whose blocks are
+    /// relayed, if any. The peers of the inbound connections have a
+    /// score of zero, as they are not known
+    pub min_reputation: Option<i64>,
+
+    /// Relay the blocks which have not been validated through their
+    /// headers. Blocks are only validated through their headers if
+    /// `sync.headers_first` is enabled
+    pub unvalidated: bool,
 }
 
 /// Storage-specific configuration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Storage {
+    /// Backend used to store the data
+    pub backend: StorageBackend,
+
     /// Path to the directory that will contain the database files
     pub db_path: PathBuf,
+
+    /// Maximum size of the database files, in bytes (`0` means no
+    /// limit)
+    pub max_db_size: u64,
+
+    /// Size of the cache of recently read values, in bytes (`0` disables it)
+    pub cache_size: u64,
+
+    /// Open the database without writing to it, e.g.: to read the
+    /// data of another node
+    pub read_only: bool,
+
+    /// Wait for every write to reach the disk before returning,
+    /// trading write throughput for durability. Writes are synced at
+    /// the end of every epoch anyway.
+    pub sync_writes: bool,
+
+    /// Tuning of the RocksDB backend
+    pub rocksdb: RocksDB,
+
+    /// Encryption of the stored values
+    pub encryption: Encryption,
+
+    /// Pruning of old blocks
+    pub pruning: Pruning,
+
+    /// Scheduled backups of the storage
+    pub backup: Backup,
+
+    /// Offloading of old blocks to an archive
+    pub archive: Archive,
+}
+
+/// Tuning of the RocksDB storage backend, passed to RocksDB when the
+/// database is opened
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RocksDB {
+    /// Size of the block cache, in bytes
+    pub block_cache_size: u64,
+
+    /// Size of the memory buffer used for writes before flushing
+    /// them to disk, in bytes
+    pub write_buffer_size: u64,
+
+    /// Maximum number of files opened by the database (`-1` means no
+    /// limit)
+    pub max_open_files: i32,
+
+    /// Binary flag telling whether to write the changes to the
+    /// write-ahead log (WAL) before applying them. Without it, writes
+    /// are faster but the ones not flushed yet are lost if the node
+    /// crashes.
+    pub wal: bool,
+
+    /// Algorithm used to compress the database files
+    pub compression: Compression,
+
+    /// Strategy used to compact the database files
+    pub compaction_style: CompactionStyle,
+
+    /// Tuning of the column family of each data type
+    pub column_families: ColumnFamilies,
+}
+
+/// Column families in which each data type is stored, so each of
+/// them can be tuned independently
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnFamilies {
+    /// Blocks
+    pub blocks: ColumnFamily,
+
+    /// Transactions
+    pub transactions: ColumnFamily,
+
+    /// Unspent transaction outputs
+    pub utxos: ColumnFamily,
+
+    /// Known peers
+    pub peers: ColumnFamily,
+
+    /// Reputation of the identities
+    pub reputation: ColumnFamily,
+}
+
+impl ColumnFamilies {
+    /// Every column family with its name, as written in the
+    /// configuration
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &ColumnFamily)> {
+        vec![
+            ("blocks", &self.blocks),
+            ("transactions", &self.transactions),
+            ("utxos", &self.utxos),
+            ("peers", &self.peers),
+            ("reputation", &self.reputation),
+        ]
+        .into_iter()
+    }
+}
+
+/// Tuning of a RocksDB column family
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnFamily {
+    /// Size of the memory buffer used for writes before flushing
+    /// them to disk, in bytes
+    pub write_buffer_size: u64,
+
+    /// Algorithm used to compress the column family files
+    pub compression: Compression,
+
+    /// Strategy used to compact the column family files
+    pub compaction_style: CompactionStyle,
+}
+
+/// Compression algorithms supported by RocksDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// No compression
+    None,
+    /// Snappy
+    Snappy,
+    /// Zlib
+    Zlib,
+    /// Bzip2
+    Bz2,
+    /// LZ4
+    Lz4,
+    /// LZ4 with high compression
+    Lz4hc,
+    /// Zstandard
+    Zstd,
+}
+
+impl Compression {
+    /// Every algorithm, in the order they are documented
+    pub const ALL: [Compression; 7] = [
+        Compression::None,
+        Compression::Snappy,
+        Compression::Zlib,
+        Compression::Bz2,
+        Compression::Lz4,
+        Compression::Lz4hc,
+        Compression::Zstd,
+    ];
+
+    /// Name of the algorithm, as written in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Snappy => "snappy",
+            Compression::Zlib => "zlib",
+            Compression::Bz2 => "bz2",
+            Compression::Lz4 => "lz4",
+            Compression::Lz4hc => "lz4hc",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+/// Compression algorithms of the messages exchanged with the peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageCompression {
+    /// No compression
+    None,
+    /// Snappy
+    Snappy,
+}
+
+impl MessageCompression {
+    /// Every algorithm, in the order they are documented
+    pub const ALL: [MessageCompression; 2] = [MessageCompression::None, MessageCompression::Snappy];
+
+    /// Name of the algorithm, as written in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageCompression::None => "none",
+            MessageCompression::Snappy => "snappy",
+        }
+    }
+}
+
+/// Compaction styles supported by RocksDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompactionStyle {
+    /// Leveled compaction, best for read-heavy workloads
+    Level,
+    /// Universal compaction, best for write-heavy workloads
+    Universal,
+    /// FIFO compaction, which drops the oldest files
+    Fifo,
+}
+
+impl CompactionStyle {
+    /// Every style, in the order they are documented
+    pub const ALL: [CompactionStyle; 3] = [
+        CompactionStyle::Level,
+        CompactionStyle::Universal,
+        CompactionStyle::Fifo,
+    ];
+
+    /// Name of the style, as written in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            CompactionStyle::Level => "level",
+            CompactionStyle::Universal => "universal",
+            CompactionStyle::Fifo => "fifo",
+        }
+    }
+}
+
+/// Backends that can be used to store the data of the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// RocksDB database in `storage.db_path`
+    RocksDB,
+    /// Volatile memory, the data is lost when the node stops
+    Memory,
+}
+
+impl StorageBackend {
+    /// Every backend, in the order they are documented
+    pub const ALL: [StorageBackend; 2] = [StorageBackend::RocksDB, StorageBackend::Memory];
+
+    /// Name of the backend, as written in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            StorageBackend::RocksDB => "rocksdb",
+            StorageBackend::Memory => "memory",
+        }
+    }
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Backends that can be used to archive old blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveBackend {
+    /// One file per block in `storage.archive.path`
+    Directory,
+    // TODO Add an S3-compatible backend, the archive only supports directories for now
+}
+
+impl ArchiveBackend {
+    /// Every archive backend, in the order they are documented
+    pub const ALL: [ArchiveBackend; 1] = [ArchiveBackend::Directory];
+
+    /// Name of the backend, as written in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            ArchiveBackend::Directory => "directory",
+        }
+    }
+}
+
+impl fmt::Display for ArchiveBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// JsonRPC API configuration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct JsonRPC {
     /// Binary flag telling whether to enable the JSON-RPC interface or not
     pub enabled: bool,
     /// JSON-RPC server address, that is, the socket address (interface ip and
     /// port) for the JSON-RPC server
     pub server_address: SocketAddr,
+    /// Socket address of the WebSocket transport of the JSON-RPC server,
+    /// which is only enabled if set
+    pub websocket_address: Option<SocketAddr>,
+    /// Methods served, all of them if empty
+    pub enabled_methods: Vec<String>,
+    /// Methods not served, even if listed in `enabled_methods`, e.g.:
+    /// to serve only a read-only subset of the methods publicly
+    pub disabled_methods: Vec<String>,
+    /// Maximum number of requests per minute from each IP address,
+    /// which can be sent at once, 0 disables the limit
+    pub max_requests_per_minute: u32,
+    /// Maximum number of requests processed at once, from all the
+    /// clients, 0 disables the limit
+    pub max_requests_in_flight: u32,
+    /// Authentication of the clients
+    pub auth: Auth,
+    /// TLS termination of the connections of the clients, over TCP
+    /// and over WebSocket
+    pub tls: Tls,
+    /// Origins of the web pages allowed to open WebSocket connections
+    pub cors: Cors,
+}
+
+/// Authentication of the JSON-RPC clients. Clients have to
+/// authenticate with one of the keys only if there is any.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Auth {
+    /// API keys accepted by the `authenticate` method
+    pub keys: Vec<ApiKey>,
+}
+
+/// API key of the JSON-RPC clients, with the methods it gives access to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// The key itself
+    pub key: Secret,
+    /// Methods that the clients authenticated with this key can call,
+    /// all of them if it contains `"*"`
+    pub methods: Vec<String>,
+}
+
+/// Cross-origin access from web pages, for browser-based wallets and
+/// explorers. Clients which are not browsers send no origin and are
+/// always allowed.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Cors {
+    /// Origins allowed, e.g.: `http://localhost:8080`, or `*` for any.
+    /// No web page is allowed if empty
+    pub allowed_origins: Vec<String>,
+}
+
+/// REST API configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rest {
+    /// Binary flag telling whether to enable the REST interface or not
+    pub enabled: bool,
+    /// Socket address (interface ip and port) of the REST server
+    pub server_address: SocketAddr,
+    /// Origins of the web pages allowed to read the responses
+    pub cors: Cors,
+}
+
+/// Mining-specific configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mining {
+    /// Binary flag telling whether to enable mining or not
+    pub enabled: bool,
+}
+
+/// Wallet-specific configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Wallet {
+    /// Binary flag telling whether to enable the wallet or not
+    pub enabled: bool,
+}
+
+/// Metrics-specific configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metrics {
+    /// Binary flag telling whether to enable the metrics or not
+    pub enabled: bool,
+}
+
+/// Bridge-specific configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bridge {
+    /// Binary flag telling whether to enable the bridge or not
+    pub enabled: bool,
+}
+
+/// Synchronization of the chain with the peers: with `headers_first`,
+/// the block headers are downloaded and validated against the
+/// `checkpoints` before downloading the blocks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Synchronization {
+    /// Binary flag telling whether to download the headers first
+    pub headers_first: bool,
+
+    /// Blocks known to be part of the chain
+    #[serde(serialize_with = "serialize_checkpoints")]
+    pub checkpoints: Vec<Checkpoint>,
+
+    /// Maximum number of blocks requested to the peers and not
+    /// received yet, counting from the first missing block
+    pub download_window: u32,
+
+    /// Time a peer has to send the blocks requested to it
+    #[serde(rename = "download_timeout_seconds", serialize_with = "as_secs")]
+    pub download_timeout: Duration,
+}
+
+/// Pruning of old blocks: the transactions of the blocks older than
+/// `keep_blocks` epochs are deleted, while their headers and the
+/// UTXO set are kept.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pruning {
+    /// Binary flag telling whether to delete the data of old blocks
+    pub enabled: bool,
+
+    /// Number of epochs, counting back from the current one, whose
+    /// blocks are kept entirely
+    pub keep_blocks: u32,
+}
+
+/// Scheduled backups of the storage: every `interval`, the node
+/// writes a snapshot of the storage to `destination`, keeping the last
+/// `retention` ones.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Backup {
+    /// Binary flag telling whether to make backups
+    pub enabled: bool,
+
+    /// Period of the backups
+    #[serde(rename = "interval_seconds", serialize_with = "as_secs")]
+    pub interval: Duration,
+
+    /// Directory where the backups are written
+    pub destination: PathBuf,
+
+    /// Number of backups to keep, older ones are deleted
+    pub retention: u32,
+}
+
+/// Offloading of old blocks to an archive: the blocks older than
+/// `keep_blocks` epochs are moved from the database to the archive,
+/// where they can still be read.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Archive {
+    /// Binary flag telling whether to offload old blocks
+    pub enabled: bool,
+
+    /// Backend of the archive
+    pub backend: ArchiveBackend,
+
+    /// Directory where the archived blocks are written, when the
+    /// backend is `"directory"`
+    pub path: PathBuf,
+
+    /// Number of epochs, counting back from the current one, whose
+    /// blocks are kept in the database
+    pub keep_blocks: u32,
+}
+
+/// Encryption of the stored values. Values are encrypted only if a
+/// passphrase or a key file is given.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Encryption {
+    /// Passphrase from which the encryption key is derived, if any
+    pub passphrase: Option<Secret>,
+    /// Path to a file whose contents are used as the secret from
+    /// which the encryption key is derived, if any
+    pub keyfile: Option<PathBuf>,
+}
+
+/// Node identity configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Identity {
+    /// Path to the file containing the secp256k1 master key of the
+    /// node
+    pub master_key_path: PathBuf,
+    /// Binary flag telling whether to generate a new master key if
+    /// the file does not exist
+    pub create_if_missing: bool,
+    /// Mnemonic phrase from which to import the master key, if any
+    pub mnemonic: Option<Secret>,
+}
+
+/// Logging configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Log {
+    /// Maximum level of the log messages
+    #[serde(serialize_with = "serialize_level")]
+    pub level: LevelFilter,
 }
 
 impl Config {
+    /// Consolidate a partial configuration on top of the defaults of
+    /// its environment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the environment cannot be used or is missing params,
+    /// see `try_from_partial` for a version returning every problem.
     pub fn from_partial(config: &partial::Config) -> Self {
-        let defaults: Box<Defaults> = match config.environment {
-            Environment::Mainnet => {
-                panic!("Config with mainnet environment is currently not allowed");
+        match Self::resolve(config) {
+            Ok(config) => config,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Consolidate a partial configuration on top of the defaults of
+    /// its environment, and validate the result. Every problem found
+    /// (missing params, invalid addresses, failed validations) is
+    /// returned at once.
+    pub fn try_from_partial(config: &partial::Config) -> Result<Self, ConsolidationError> {
+        let mut problems = vec![];
+        consolidation::check_addresses(config, &mut problems);
+        consolidation::check_environment(config, &mut problems);
+
+        match Self::resolve(config) {
+            Ok(total) => {
+                if let Err(errors) = validation::validate(&total) {
+                    problems.extend(errors.into_iter().map(Problem::Invalid));
+                }
+                if problems.is_empty() {
+                    Ok(total)
+                } else {
+                    Err(ConsolidationError { problems })
+                }
+            }
+            Err(e) => {
+                problems.extend(e.problems);
+                Err(ConsolidationError { problems })
             }
-            Environment::Testnet1 => Box::new(Testnet1),
+        }
+    }
+
+    /// Merge a partial configuration with the defaults of its
+    /// environment, failing if they cannot be determined.
+    fn resolve(config: &partial::Config) -> Result<Self, ConsolidationError> {
+        let environment = config.environment.clone().unwrap_or_default();
+        if environment == Environment::Mainnet {
+            return Err(ConsolidationError {
+                problems: vec![Problem::MainnetNotAllowed],
+            });
+        }
+        let defaults: Box<dyn Defaults> = match defaults::for_environment(&environment) {
+            Some(defaults) => defaults,
+            None => match custom_defaults(config) {
+                Ok(custom) => Box::new(custom),
+                Err(missing) => {
+                    return Err(ConsolidationError {
+                        problems: missing
+                            .into_iter()
+                            .map(|param| Problem::MissingParam {
+                                environment: environment.to_string(),
+                                param,
+                            })
+                            .collect(),
+                    });
+                }
+            },
         };
 
-        let consensus_constants = match config.environment {
+        let consensus_constants = match environment {
             // When in mainnet, ignore the [consensus_constants] section of the configuration
             Environment::Mainnet => {
                 let consensus_constants_no_changes = partial::ConsensusConstants::default();
@@ -145,19 +958,116 @@ impl Config {
                 }
                 consensus_constants_from_partial(&consensus_constants_no_changes, &*defaults)
             }
-            // In testnet, allow to override the consensus constants
-            Environment::Testnet1 => {
+            // In testnets and custom environments, allow to override the consensus constants
+            Environment::Testnet1 | Environment::Custom(_) => {
                 consensus_constants_from_partial(&config.consensus_constants, &*defaults)
             }
         };
 
-        Config {
-            environment: config.environment.clone(),
+        Ok(Config {
+            environment,
             connections: Connections::from_partial(&config.connections, &*defaults),
             storage: Storage::from_partial(&config.storage, &*defaults),
             consensus_constants,
             jsonrpc: JsonRPC::from_partial(&config.jsonrpc, &*defaults),
+            rest: Rest::from_partial(&config.rest, &*defaults),
+            mining: Mining::from_partial(&config.mining, &*defaults),
+            wallet: Wallet::from_partial(&config.wallet, &*defaults),
+            metrics: Metrics::from_partial(&config.metrics, &*defaults),
+            bridge: Bridge::from_partial(&config.bridge, &*defaults),
+            identity: Identity::from_partial(&config.identity, &*defaults),
+            log: Log::from_partial(&config.log, &*defaults),
+            sync: Synchronization::from_partial(&config.sync, &*defaults),
+        })
+    }
+
+    /// Serialize the configuration in Toml format, using the same
+    /// params names that are accepted by the Toml loader. This is
+    /// useful for dumping the effective configuration, that is, the
+    /// result of merging all the configuration sources on top of the
+    /// environment defaults.
+    ///
+    /// The resulting string contains the values of sensitive params,
+    /// use `to_redacted_toml_string` for logging it.
+    pub fn to_toml_string(&self) -> Result<String, toml::ser::Error> {
+        // Serializing through a `toml::Value` takes care of writing
+        // the plain values before the tables, as required by Toml
+        toml::to_string(&self.to_toml_value()?)
+    }
+
+    /// The configuration as a Toml document, including its version
+    fn to_toml_value(&self) -> Result<toml::Value, toml::ser::Error> {
+        let mut value = toml::Value::try_from(self)?;
+        if let Some(table) = value.as_table_mut() {
+            table.insert(
+                migrations::VERSION_KEY.to_string(),
+                toml::Value::Integer(i64::from(migrations::CURRENT_VERSION)),
+            );
         }
+
+        Ok(value)
+    }
+}
+
+/// Defaults of a custom environment, taken from the configuration.
+/// Returns the names of the environment-specific params missing from
+/// the configuration, if any.
+pub fn custom_defaults(config: &partial::Config) -> Result<Custom, Vec<&'static str>> {
+    let mut missing = vec![];
+    let connections_server_addr = config
+        .connections
+        .server_addrs
+        .as_ref()
+        .and_then(|addrs| addrs.first().cloned());
+    if connections_server_addr.is_none() {
+        missing.push("connections.server_addr");
+    }
+    if config.connections.magic_number.is_none() {
+        missing.push("connections.magic_number");
+    }
+    // The database path defaults to the data directory of the
+    // environment
+    let storage_db_path = config.storage.db_path.clone().or_else(|| {
+        config
+            .environment
+            .as_ref()
+            .map(defaults::environment_db_path)
+    });
+    if storage_db_path.is_none() {
+        missing.push("storage.db_path");
+    }
+    if config
+        .consensus_constants
+        .checkpoint_zero_timestamp
+        .is_none()
+    {
+        missing.push("consensus_constants.checkpoint_zero_timestamp");
+    }
+    if config.jsonrpc.server_address.is_none() {
+        missing.push("jsonrpc.server_address");
+    }
+
+    match (
+        connections_server_addr,
+        config.connections.magic_number,
+        storage_db_path,
+        config.consensus_constants.checkpoint_zero_timestamp,
+        config.jsonrpc.server_address,
+    ) {
+        (
+            Some(connections_server_addr),
+            Some(connections_magic_number),
+            Some(storage_db_path),
+            Some(consensus_constants_checkpoint_zero_timestamp),
+            Some(jsonrpc_server_address),
+        ) => Ok(Custom {
+            connections_server_addr,
+            connections_magic_number,
+            storage_db_path,
+            consensus_constants_checkpoint_zero_timestamp,
+            jsonrpc_server_address,
+        }),
+        _ => Err(missing),
     }
 }
 
@@ -198,10 +1108,13 @@ impl Default for Config {
 impl Connections {
     pub fn from_partial(config: &partial::Connections, defaults: &Defaults) -> Self {
         Connections {
-            server_addr: config
-                .server_addr
+            server_addrs: config
+                .server_addrs
                 .to_owned()
-                .unwrap_or_else(|| defaults.connections_server_addr()),
+                .unwrap_or_else(|| vec![defaults.connections_server_addr()]),
+            magic_number: config
+                .magic_number
+                .unwrap_or_else(|| defaults.connections_magic_number()),
             inbound_limit: config
                 .inbound_limit
                 .to_owned()
@@ -210,11 +1123,40 @@ impl Connections {
                 .outbound_limit
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_outbound_limit()),
+            max_connections_per_ip: config
+                .max_connections_per_ip
+                .unwrap_or_else(|| defaults.connections_max_connections_per_ip()),
+            max_connections_per_subnet: config
+                .max_connections_per_subnet
+                .unwrap_or_else(|| defaults.connections_max_connections_per_subnet()),
             known_peers: config
                 .known_peers
                 .union(&defaults.connections_known_peers())
                 .cloned()
                 .collect(),
+            dns_seeds: config
+                .dns_seeds
+                .union(&defaults.connections_dns_seeds())
+                .cloned()
+                .collect(),
+            whitelist: config
+                .whitelist
+                .union(&defaults.connections_whitelist())
+                .cloned()
+                .collect(),
+            blacklist: config
+                .blacklist
+                .union(&defaults.connections_blacklist())
+                .cloned()
+                .collect(),
+            priority_peers: config
+                .priority_peers
+                .union(&defaults.connections_priority_peers())
+                .cloned()
+                .collect(),
+            dns_seeds_period: config
+                .dns_seeds_period
+                .unwrap_or_else(|| defaults.connections_dns_seeds_period()),
             bootstrap_peers_period: config
                 .bootstrap_peers_period
                 .to_owned()
@@ -223,24 +1165,188 @@ impl Connections {
                 .storage_peers_period
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_storage_peers_period()),
+            storage_peers_ttl: config
+                .storage_peers_ttl
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_storage_peers_ttl()),
             discovery_peers_period: config
                 .discovery_peers_period
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_discovery_peers_period()),
+            feeler_peers_period: config
+                .feeler_peers_period
+                .unwrap_or_else(|| defaults.connections_feeler_peers_period()),
             handshake_timeout: config
                 .handshake_timeout
                 .unwrap_or_else(|| defaults.connections_handshake_timeout()),
+            ping_period: config
+                .ping_period
+                .unwrap_or_else(|| defaults.connections_ping_period()),
+            ping_timeout: config
+                .ping_timeout
+                .unwrap_or_else(|| defaults.connections_ping_timeout()),
+            ban_threshold: config
+                .ban_threshold
+                .unwrap_or_else(|| defaults.connections_ban_threshold()),
+            ban_duration: config
+                .ban_duration
+                .unwrap_or_else(|| defaults.connections_ban_duration()),
+            max_upload_kbps: config
+                .max_upload_kbps
+                .unwrap_or_else(|| defaults.connections_max_upload_kbps()),
+            max_download_kbps: config
+                .max_download_kbps
+                .unwrap_or_else(|| defaults.connections_max_download_kbps()),
+            max_peer_upload_kbps: config
+                .max_peer_upload_kbps
+                .unwrap_or_else(|| defaults.connections_max_peer_upload_kbps()),
+            max_peer_download_kbps: config
+                .max_peer_download_kbps
+                .unwrap_or_else(|| defaults.connections_max_peer_download_kbps()),
+            upnp: config.upnp.unwrap_or_else(|| defaults.connections_upnp()),
+            mdns: config.mdns.unwrap_or_else(|| defaults.connections_mdns()),
+            compression: config
+                .compression
+                .unwrap_or_else(|| defaults.connections_compression()),
+            tls: Tls::from_partial(&config.tls),
+            tls_peers: config.tls_peers.to_owned().unwrap_or_default(),
+            tls_ca: config.tls_ca.to_owned(),
+            rate_limits: RateLimits::from_partial(&config.rate_limits, defaults),
+            diversity: Diversity::from_partial(&config.diversity, defaults),
+            limits: Limits::from_partial(&config.limits, defaults),
+            relay: Relay::from_partial(&config.relay, defaults),
+            proxy: config.proxy.to_owned(),
+        }
+    }
+
+    /// Socket address of the SOCKS5 proxy in `proxy`, if any
+    pub fn proxy_address(&self) -> Result<Option<SocketAddr>, String> {
+        match &self.proxy {
+            None => Ok(None),
+            Some(proxy) => {
+                if !proxy.starts_with(PROXY_SCHEME) {
+                    return Err(format!(
+                        "`{}` does not start with `{}`",
+                        proxy, PROXY_SCHEME
+                    ));
+                }
+                proxy[PROXY_SCHEME.len()..]
+                    .trim_end_matches('/')
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("`{}` is not written as `{}ip:port`", proxy, PROXY_SCHEME))
+            }
+        }
+    }
+
+    /// Server address announced to the peers, which is the first of
+    /// `server_addrs`
+    pub fn server_addr(&self) -> SocketAddr {
+        self.server_addrs[0]
+    }
+
+    /// Split a DNS seed into the host to resolve and the port of the
+    /// peers, which is the port of `server_addr` if the seed has none
+    pub fn parse_dns_seed(&self, seed: &str) -> Result<(String, u16), String> {
+        let (host, port) = match seed.rfind(':') {
+            Some(i) => {
+                let port = seed[i + 1..]
+                    .parse()
+                    .map_err(|_| format!("invalid port in `{}`", seed))?;
+                (&seed[..i], port)
+            }
+            None => (seed, self.server_addr().port()),
+        };
+        if host.is_empty() {
+            return Err(format!("missing host in `{}`", seed));
         }
+
+        Ok((host.to_string(), port))
     }
 }
 
 impl Storage {
     pub fn from_partial(config: &partial::Storage, defaults: &Defaults) -> Self {
         Storage {
+            backend: config.backend.unwrap_or_else(|| defaults.storage_backend()),
             db_path: config
                 .db_path
                 .to_owned()
                 .unwrap_or_else(|| defaults.storage_db_path()),
+            max_db_size: config
+                .max_db_size
+                .unwrap_or_else(|| defaults.storage_max_db_size()),
+            cache_size: config
+                .cache_size
+                .unwrap_or_else(|| defaults.storage_cache_size()),
+            read_only: config
+                .read_only
+                .unwrap_or_else(|| defaults.storage_read_only()),
+            sync_writes: config
+                .sync_writes
+                .unwrap_or_else(|| defaults.storage_sync_writes()),
+            rocksdb: RocksDB::from_partial(&config.rocksdb, defaults),
+            encryption: Encryption::from_partial(&config.encryption),
+            pruning: Pruning::from_partial(&config.pruning, defaults),
+            backup: Backup::from_partial(&config.backup, defaults),
+            archive: Archive::from_partial(&config.archive, defaults),
+        }
+    }
+}
+
+impl RocksDB {
+    pub fn from_partial(config: &partial::RocksDB, defaults: &dyn Defaults) -> Self {
+        let write_buffer_size = config
+            .write_buffer_size
+            .unwrap_or_else(|| defaults.storage_rocksdb_write_buffer_size());
+        let compression = config
+            .compression
+            .unwrap_or_else(|| defaults.storage_rocksdb_compression());
+        let compaction_style = config
+            .compaction_style
+            .unwrap_or_else(|| defaults.storage_rocksdb_compaction_style());
+        // Column families use the tuning of the whole database unless
+        // they override it
+        let global = ColumnFamily {
+            write_buffer_size,
+            compression,
+            compaction_style,
+        };
+
+        RocksDB {
+            block_cache_size: config
+                .block_cache_size
+                .unwrap_or_else(|| defaults.storage_rocksdb_block_cache_size()),
+            write_buffer_size,
+            max_open_files: config
+                .max_open_files
+                .unwrap_or_else(|| defaults.storage_rocksdb_max_open_files()),
+            wal: config.wal.unwrap_or_else(|| defaults.storage_rocksdb_wal()),
+            compression,
+            compaction_style,
+            column_families: ColumnFamilies::from_partial(&config.column_families, &global),
+        }
+    }
+}
+
+impl ColumnFamilies {
+    pub fn from_partial(config: &partial::ColumnFamilies, global: &ColumnFamily) -> Self {
+        ColumnFamilies {
+            blocks: ColumnFamily::from_partial(&config.blocks, global),
+            transactions: ColumnFamily::from_partial(&config.transactions, global),
+            utxos: ColumnFamily::from_partial(&config.utxos, global),
+            peers: ColumnFamily::from_partial(&config.peers, global),
+            reputation: ColumnFamily::from_partial(&config.reputation, global),
+        }
+    }
+}
+
+impl ColumnFamily {
+    pub fn from_partial(config: &partial::ColumnFamily, global: &ColumnFamily) -> Self {
+        ColumnFamily {
+            write_buffer_size: config.write_buffer_size.unwrap_or(global.write_buffer_size),
+            compression: config.compression.unwrap_or(global.compression),
+            compaction_style: config.compaction_style.unwrap_or(global.compaction_style),
         }
     }
 }
@@ -256,13 +1362,384 @@ impl JsonRPC {
                 .server_address
                 .to_owned()
                 .unwrap_or_else(|| defaults.jsonrpc_server_address()),
+            websocket_address: config.websocket_address,
+            enabled_methods: config.enabled_methods.to_owned().unwrap_or_default(),
+            disabled_methods: config.disabled_methods.to_owned().unwrap_or_default(),
+            max_requests_per_minute: config
+                .max_requests_per_minute
+                .unwrap_or_else(|| defaults.jsonrpc_max_requests_per_minute()),
+            max_requests_in_flight: config
+                .max_requests_in_flight
+                .unwrap_or_else(|| defaults.jsonrpc_max_requests_in_flight()),
+            auth: Auth::from_partial(&config.auth),
+            tls: Tls::from_partial(&config.tls),
+            cors: Cors::from_partial(&config.cors),
+        }
+    }
+}
+
+impl Auth {
+    pub fn from_partial(config: &partial::Auth) -> Self {
+        Auth {
+            keys: config.keys.to_owned().unwrap_or_default(),
+        }
+    }
+
+    /// Whether the clients have to authenticate
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+}
+
+impl Cors {
+    pub fn from_partial(config: &partial::Cors) -> Self {
+        Cors {
+            allowed_origins: config.allowed_origins.to_owned().unwrap_or_default(),
+        }
+    }
+
+    /// Whether a web page of the given origin is allowed. Origins are
+    /// compared without case, as browsers send them in lowercase
+    pub fn allows(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+}
+
+impl Rest {
+    pub fn from_partial(config: &partial::Rest, defaults: &dyn Defaults) -> Self {
+        Rest {
+            enabled: config.enabled.unwrap_or_else(|| defaults.rest_enabled()),
+            server_address: config
+                .server_address
+                .unwrap_or_else(|| defaults.rest_server_address()),
+            cors: Cors::from_partial(&config.cors),
+        }
+    }
+}
+
+impl Mining {
+    pub fn from_partial(config: &partial::Mining, defaults: &dyn Defaults) -> Self {
+        Mining {
+            enabled: config.enabled.unwrap_or_else(|| defaults.mining_enabled()),
+        }
+    }
+}
+
+impl Wallet {
+    pub fn from_partial(config: &partial::Wallet, defaults: &dyn Defaults) -> Self {
+        Wallet {
+            enabled: config.enabled.unwrap_or_else(|| defaults.wallet_enabled()),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn from_partial(config: &partial::Metrics, defaults: &dyn Defaults) -> Self {
+        Metrics {
+            enabled: config.enabled.unwrap_or_else(|| defaults.metrics_enabled()),
+        }
+    }
+}
+
+impl Bridge {
+    pub fn from_partial(config: &partial::Bridge, defaults: &dyn Defaults) -> Self {
+        Bridge {
+            enabled: config.enabled.unwrap_or_else(|| defaults.bridge_enabled()),
+        }
+    }
+}
+
+impl Synchronization {
+    pub fn from_partial(config: &partial::Synchronization, defaults: &dyn Defaults) -> Self {
+        Synchronization {
+            headers_first: config
+                .headers_first
+                .unwrap_or_else(|| defaults.sync_headers_first()),
+            checkpoints: config
+                .checkpoints
+                .clone()
+                .unwrap_or_else(|| defaults.sync_checkpoints()),
+            download_window: config
+                .download_window
+                .unwrap_or_else(|| defaults.sync_download_window()),
+            download_timeout: config
+                .download_timeout
+                .unwrap_or_else(|| defaults.sync_download_timeout()),
         }
     }
 }
 
+impl Pruning {
+    pub fn from_partial(config: &partial::Pruning, defaults: &dyn Defaults) -> Self {
+        Pruning {
+            enabled: config
+                .enabled
+                .unwrap_or_else(|| defaults.storage_pruning_enabled()),
+            keep_blocks: config
+                .keep_blocks
+                .unwrap_or_else(|| defaults.storage_pruning_keep_blocks()),
+        }
+    }
+}
+
+impl Backup {
+    pub fn from_partial(config: &partial::Backup, defaults: &dyn Defaults) -> Self {
+        Backup {
+            enabled: config
+                .enabled
+                .unwrap_or_else(|| defaults.storage_backup_enabled()),
+            interval: config
+                .interval
+                .unwrap_or_else(|| defaults.storage_backup_interval()),
+            destination: config
+                .destination
+                .to_owned()
+                .unwrap_or_else(|| defaults.storage_backup_destination()),
+            retention: config
+                .retention
+                .unwrap_or_else(|| defaults.storage_backup_retention()),
+        }
+    }
+}
+
+impl Archive {
+    pub fn from_partial(config: &partial::Archive, defaults: &dyn Defaults) -> Self {
+        Archive {
+            enabled: config
+                .enabled
+                .unwrap_or_else(|| defaults.storage_archive_enabled()),
+            backend: config
+                .backend
+                .unwrap_or_else(|| defaults.storage_archive_backend()),
+            path: config
+                .path
+                .to_owned()
+                .unwrap_or_else(|| defaults.storage_archive_path()),
+            keep_blocks: config
+                .keep_blocks
+                .unwrap_or_else(|| defaults.storage_archive_keep_blocks()),
+        }
+    }
+}
+
+impl RateLimits {
+    pub fn from_partial(config: &partial::RateLimits, defaults: &dyn Defaults) -> Self {
+        RateLimits {
+            inv: config
+                .inv
+                .unwrap_or_else(|| defaults.connections_rate_limits_inv()),
+            get_data: config
+                .get_data
+                .unwrap_or_else(|| defaults.connections_rate_limits_get_data()),
+            peers: config
+                .peers
+                .unwrap_or_else(|| defaults.connections_rate_limits_peers()),
+        }
+    }
+}
+
+impl Diversity {
+    pub fn from_partial(config: &partial::Diversity, defaults: &dyn Defaults) -> Self {
+        Diversity {
+            max_outbound_per_group: config
+                .max_outbound_per_group
+                .unwrap_or_else(|| defaults.connections_diversity_max_outbound_per_group()),
+            asmap: config.asmap.to_owned(),
+        }
+    }
+}
+
+impl Limits {
+    pub fn from_partial(config: &partial::Limits, defaults: &dyn Defaults) -> Self {
+        Limits {
+            max_message_size: config
+                .max_message_size
+                .unwrap_or_else(|| defaults.connections_limits_max_message_size()),
+            max_block_size: config
+                .max_block_size
+                .unwrap_or_else(|| defaults.connections_limits_max_block_size()),
+            max_headers_size: config
+                .max_headers_size
+                .unwrap_or_else(|| defaults.connections_limits_max_headers_size()),
+            max_inventory_size: config
+                .max_inventory_size
+                .unwrap_or_else(|| defaults.connections_limits_max_inventory_size()),
+            max_peers_size: config
+                .max_peers_size
+                .unwrap_or_else(|| defaults.connections_limits_max_peers_size()),
+            get_peers_timeout: config
+                .get_peers_timeout
+                .unwrap_or_else(|| defaults.connections_limits_get_peers_timeout()),
+            get_headers_timeout: config
+                .get_headers_timeout
+                .unwrap_or_else(|| defaults.connections_limits_get_headers_timeout()),
+        }
+    }
+}
+
+impl Relay {
+    pub fn from_partial(config: &partial::Relay, defaults: &dyn Defaults) -> Self {
+        Relay {
+            unsolicited: config
+                .unsolicited
+                .unwrap_or_else(|| defaults.connections_relay_unsolicited()),
+            min_reputation: config.min_reputation,
+            unvalidated: config
+                .unvalidated
+                .unwrap_or_else(|| defaults.connections_relay_unvalidated()),
+        }
+    }
+}
+
+impl Tls {
+    pub fn from_partial(config: &partial::Tls) -> Self {
+        Tls {
+            cert: config.cert.to_owned(),
+            key: config.key.to_owned(),
+        }
+    }
+
+    /// Whether the inbound connections have to be terminated with TLS
+    pub fn is_enabled(&self) -> bool {
+        self.cert.is_some() && self.key.is_some()
+    }
+}
+
+impl Encryption {
+    pub fn from_partial(config: &partial::Encryption) -> Self {
+        Encryption {
+            passphrase: config.passphrase.to_owned(),
+            keyfile: config.keyfile.to_owned(),
+        }
+    }
+
+    /// Whether the stored values have to be encrypted
+    pub fn is_enabled(&self) -> bool {
+        self.passphrase.is_some() || self.keyfile.is_some()
+    }
+}
+
+impl Identity {
+    pub fn from_partial(config: &partial::Identity, defaults: &dyn Defaults) -> Self {
+        Identity {
+            master_key_path: config
+                .master_key_path
+                .to_owned()
+                .unwrap_or_else(|| defaults.identity_master_key_path()),
+            create_if_missing: config
+                .create_if_missing
+                .unwrap_or_else(|| defaults.identity_create_if_missing()),
+            mnemonic: config.mnemonic.to_owned(),
+        }
+    }
+}
+
+impl Log {
+    pub fn from_partial(config: &partial::Log, defaults: &dyn Defaults) -> Self {
+        Log {
+            level: config.level.unwrap_or_else(|| defaults.log_level()),
+        }
+    }
+}
+
+/// Consensus constants as written in the Toml file
+#[derive(Serialize)]
+struct ConsensusConstantsParams<'a> {
+    checkpoint_zero_timestamp: i64,
+    checkpoints_period_seconds: u16,
+    #[serde(serialize_with = "serialize_hash")]
+    genesis_hash: &'a Hash,
+    reputation_demurrage: f64,
+    reputation_punishment: f64,
+}
+
+// Serialize the consensus constants using the params names of the Toml file
+fn serialize_consensus_constants<S>(
+    consensus_constants: &ConsensusConstants,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serde::Serialize::serialize(
+        &ConsensusConstantsParams {
+            checkpoint_zero_timestamp: consensus_constants.checkpoint_zero_timestamp,
+            checkpoints_period_seconds: consensus_constants.checkpoints_period,
+            genesis_hash: &consensus_constants.genesis_hash,
+            reputation_demurrage: consensus_constants.reputation_demurrage,
+            reputation_punishment: consensus_constants.reputation_punishment,
+        },
+        serializer,
+    )
+}
+
+// Serialize a hash as a table with the hash function as the only key
+fn serialize_hash<S>(hash: &&Hash, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(1))?;
+    match hash {
+        Hash::SHA256(bytes) => map.serialize_entry("SHA256", &bytes[..])?,
+    }
+    map.end()
+}
+
+#[derive(Serialize)]
+struct CheckpointParams<'a> {
+    epoch: Epoch,
+    #[serde(serialize_with = "serialize_hash")]
+    hash: &'a Hash,
+}
+
+// Serialize the checkpoints as tables with the hash written like the
+// genesis hash
+fn serialize_checkpoints<S>(checkpoints: &[Checkpoint], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(checkpoints.iter().map(|checkpoint| CheckpointParams {
+        epoch: checkpoint.epoch,
+        hash: &checkpoint.hash,
+    }))
+}
+
+// Serialize a log level filter as its lowercase name
+fn serialize_level<S>(level: &LevelFilter, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&level.to_string().to_lowercase())
+}
+
+// Serialize a list of socket addresses as a single address if it has
+// only one, so it can be written as it is usually configured
+fn one_or_many<S>(addrs: &[SocketAddr], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if addrs.len() == 1 {
+        serializer.collect_str(&addrs[0])
+    } else {
+        serializer.collect_seq(addrs)
+    }
+}
+
+// Serialize a duration as a u64 representing seconds
+fn as_secs<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u64(duration.as_secs())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::defaults::Testnet1;
+    use crate::validation::ValidationError;
 
     #[test]
     fn test_storage_default_from_partial() {
@@ -270,6 +1747,7 @@ mod tests {
         let partial_config = partial::Storage::default();
         let config = Storage::from_partial(&partial_config, &*defaults);
 
+        assert_eq!(config.backend, StorageBackend::RocksDB);
         assert_eq!(config.db_path.to_str(), Testnet1.storage_db_path().to_str());
     }
 
@@ -277,11 +1755,67 @@ mod tests {
     fn test_storage_from_partial() {
         let defaults: Box<Defaults> = Box::new(Testnet1);
         let partial_config = partial::Storage {
+            backend: Some(StorageBackend::Memory),
             db_path: Some(PathBuf::from("other")),
+            max_db_size: Some(1 << 30),
+            ..partial::Storage::default()
         };
         let config = Storage::from_partial(&partial_config, &*defaults);
 
+        assert_eq!(config.backend, StorageBackend::Memory);
         assert_eq!(config.db_path.to_str(), Some("other"));
+        assert_eq!(config.max_db_size, 1 << 30);
+        assert_eq!(config.cache_size, Testnet1.storage_cache_size());
+    }
+
+    #[test]
+    fn test_rocksdb_from_partial() {
+        let defaults: Box<Defaults> = Box::new(Testnet1);
+        let partial_config = partial::RocksDB {
+            write_buffer_size: Some(128 * 1024 * 1024),
+            compression: Some(Compression::Lz4),
+            ..partial::RocksDB::default()
+        };
+        let config = RocksDB::from_partial(&partial_config, &*defaults);
+
+        assert_eq!(config.write_buffer_size, 128 * 1024 * 1024);
+        assert_eq!(config.compression, Compression::Lz4);
+        assert_eq!(
+            config.block_cache_size,
+            Testnet1.storage_rocksdb_block_cache_size()
+        );
+        assert_eq!(config.compaction_style, CompactionStyle::Level);
+    }
+
+    #[test]
+    fn test_column_families_from_partial() {
+        let defaults: Box<Defaults> = Box::new(Testnet1);
+        let partial_config = partial::RocksDB {
+            compression: Some(Compression::Lz4),
+            column_families: partial::ColumnFamilies {
+                blocks: partial::ColumnFamily {
+                    compression: Some(Compression::Zstd),
+                    compaction_style: Some(CompactionStyle::Universal),
+                    ..partial::ColumnFamily::default()
+                },
+                ..partial::ColumnFamilies::default()
+            },
+            ..partial::RocksDB::default()
+        };
+        let config = RocksDB::from_partial(&partial_config, &*defaults);
+        let column_families = &config.column_families;
+
+        assert_eq!(column_families.blocks.compression, Compression::Zstd);
+        assert_eq!(
+            column_families.blocks.compaction_style,
+            CompactionStyle::Universal
+        );
+        assert_eq!(column_families.peers.compression, Compression::Lz4);
+        assert_eq!(
+            column_families.peers.write_buffer_size,
+            Testnet1.storage_rocksdb_write_buffer_size()
+        );
+        assert_eq!(column_families.iter().count(), 5);
     }
 
     #[test]
@@ -290,7 +1824,11 @@ mod tests {
         let partial_config = partial::Connections::default();
         let config = Connections::from_partial(&partial_config, &*defaults);
 
-        assert_eq!(config.server_addr, Testnet1.connections_server_addr());
+        assert_eq!(
+            config.server_addrs,
+            vec![Testnet1.connections_server_addr()]
+        );
+        assert_eq!(config.magic_number, Testnet1.connections_magic_number());
         assert_eq!(config.inbound_limit, Testnet1.connections_inbound_limit());
         assert_eq!(config.outbound_limit, Testnet1.connections_outbound_limit());
         assert_eq!(config.known_peers, Testnet1.connections_known_peers());
@@ -306,36 +1844,156 @@ mod tests {
             config.discovery_peers_period,
             Testnet1.connections_discovery_peers_period()
         );
+        assert_eq!(
+            config.feeler_peers_period,
+            Testnet1.connections_feeler_peers_period()
+        );
         assert_eq!(
             config.handshake_timeout,
             Testnet1.connections_handshake_timeout()
         );
+        assert_eq!(config.ping_period, Testnet1.connections_ping_period());
+        assert_eq!(config.ping_timeout, Testnet1.connections_ping_timeout());
     }
 
     #[test]
     fn test_connections_from_partial() {
         let defaults: Box<Defaults> = Box::new(Testnet1);
         let addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let other_addr: SocketAddr = "[::]:3000".parse().unwrap();
         let partial_config = partial::Connections {
-            server_addr: Some(addr),
+            server_addrs: Some(vec![addr, other_addr]),
+            magic_number: Some(7),
             inbound_limit: Some(3),
             outbound_limit: Some(4),
+            max_connections_per_ip: Some(1),
+            max_connections_per_subnet: Some(2),
             known_peers: [addr].iter().cloned().collect(),
+            dns_seeds: ["seed.example.com".to_string()].iter().cloned().collect(),
+            whitelist: ["10.0.0.0/8".parse().unwrap()].iter().cloned().collect(),
+            blacklist: ["10.0.0.1".parse().unwrap()].iter().cloned().collect(),
+            priority_peers: ["192.168.1.10".parse().unwrap()].iter().cloned().collect(),
+            dns_seeds_period: Some(Duration::from_secs(1800)),
             bootstrap_peers_period: Some(Duration::from_secs(10)),
             storage_peers_period: Some(Duration::from_secs(60)),
+            storage_peers_ttl: Some(Duration::from_secs(600)),
             discovery_peers_period: Some(Duration::from_secs(100)),
+            feeler_peers_period: Some(Duration::from_secs(200)),
             handshake_timeout: Some(Duration::from_secs(3)),
+            ping_period: Some(Duration::from_secs(0)),
+            ping_timeout: Some(Duration::from_secs(10)),
+            ban_threshold: Some(50),
+            ban_duration: Some(Duration::from_secs(3600)),
+            max_upload_kbps: Some(1000),
+            max_download_kbps: Some(2000),
+            max_peer_upload_kbps: Some(100),
+            max_peer_download_kbps: None,
+            upnp: Some(true),
+            mdns: Some(true),
+            compression: Some(MessageCompression::Snappy),
+            tls: partial::Tls {
+                cert: Some(PathBuf::from("cert.pem")),
+                key: Some(PathBuf::from("key.pem")),
+            },
+            tls_peers: Some(vec![TlsPeer {
+                address: addr,
+                server_name: "node.example.com".to_string(),
+            }]),
+            tls_ca: Some(PathBuf::from("ca.pem")),
+            rate_limits: partial::RateLimits {
+                inv: Some(10),
+                get_data: Some(0),
+                peers: None,
+            },
+            diversity: partial::Diversity {
+                max_outbound_per_group: Some(1),
+                asmap: Some(PathBuf::from("asmap.txt")),
+            },
+            limits: partial::Limits {
+                max_message_size: Some(32 * 1024),
+                max_peers_size: Some(1024),
+                get_headers_timeout: Some(Duration::from_secs(0)),
+                ..partial::Limits::default()
+            },
+            relay: partial::Relay {
+                unsolicited: Some(false),
+                min_reputation: Some(-10),
+                unvalidated: None,
+            },
+            proxy: Some("socks5://127.0.0.1:9050".to_string()),
         };
         let config = Connections::from_partial(&partial_config, &*defaults);
 
-        assert_eq!(config.server_addr, addr);
+        assert_eq!(config.server_addrs, vec![addr, other_addr]);
+        assert_eq!(config.server_addr(), addr);
+        assert_eq!(config.magic_number, 7);
         assert_eq!(config.inbound_limit, 3);
         assert_eq!(config.outbound_limit, 4);
+        assert_eq!(config.max_connections_per_ip, 1);
+        assert_eq!(config.max_connections_per_subnet, 2);
         assert!(config.known_peers.contains(&addr));
+        assert!(config.dns_seeds.contains("seed.example.com"));
+        assert!(config.whitelist.contains(&"10.0.0.0/8".parse().unwrap()));
+        assert!(config.blacklist.contains(&"10.0.0.1".parse().unwrap()));
+        assert!(config
+            .priority_peers
+            .contains(&"192.168.1.10".parse().unwrap()));
+        assert_eq!(config.dns_seeds_period, Duration::from_secs(1800));
         assert_eq!(config.bootstrap_peers_period, Duration::from_secs(10));
         assert_eq!(config.storage_peers_period, Duration::from_secs(60));
+        assert_eq!(config.storage_peers_ttl, Duration::from_secs(600));
         assert_eq!(config.discovery_peers_period, Duration::from_secs(100));
+        assert_eq!(config.feeler_peers_period, Duration::from_secs(200));
         assert_eq!(config.handshake_timeout, Duration::from_secs(3));
+        assert_eq!(config.ping_period, Duration::from_secs(0));
+        assert_eq!(config.ping_timeout, Duration::from_secs(10));
+        assert_eq!(config.ban_threshold, 50);
+        assert_eq!(config.ban_duration, Duration::from_secs(3600));
+        assert_eq!(config.max_upload_kbps, 1000);
+        assert_eq!(config.max_download_kbps, 2000);
+        assert_eq!(config.max_peer_upload_kbps, 100);
+        assert_eq!(
+            config.max_peer_download_kbps,
+            Testnet1.connections_max_peer_download_kbps()
+        );
+        assert!(config.upnp);
+        assert!(config.mdns);
+        assert_eq!(config.compression, MessageCompression::Snappy);
+        assert!(config.tls.is_enabled());
+        assert_eq!(config.tls.cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(config.tls.key, Some(PathBuf::from("key.pem")));
+        assert_eq!(config.tls_peers[0].address, addr);
+        assert_eq!(config.tls_peers[0].server_name, "node.example.com");
+        assert_eq!(config.tls_ca, Some(PathBuf::from("ca.pem")));
+        assert_eq!(config.rate_limits.inv, 10);
+        assert_eq!(config.rate_limits.get_data, 0);
+        assert_eq!(
+            config.rate_limits.peers,
+            Testnet1.connections_rate_limits_peers()
+        );
+        assert_eq!(config.diversity.max_outbound_per_group, 1);
+        assert_eq!(config.diversity.asmap, Some(PathBuf::from("asmap.txt")));
+        assert_eq!(config.limits.max_message_size, 32 * 1024);
+        assert_eq!(config.limits.max_peers_size, 1024);
+        assert_eq!(
+            config.limits.max_block_size,
+            Testnet1.connections_limits_max_block_size()
+        );
+        assert_eq!(
+            config.limits.get_peers_timeout,
+            Testnet1.connections_limits_get_peers_timeout()
+        );
+        assert_eq!(config.limits.get_headers_timeout, Duration::from_secs(0));
+        assert!(!config.relay.unsolicited);
+        assert_eq!(config.relay.min_reputation, Some(-10));
+        assert_eq!(
+            config.relay.unvalidated,
+            Testnet1.connections_relay_unvalidated()
+        );
+        assert_eq!(
+            config.proxy_address(),
+            Ok(Some("127.0.0.1:9050".parse().unwrap()))
+        );
     }
 
     #[test]
@@ -345,19 +2003,108 @@ mod tests {
         let config = JsonRPC::from_partial(&partial_config, &*defaults);
 
         assert_eq!(config.server_address, Testnet1.jsonrpc_server_address());
+        assert_eq!(config.websocket_address, None);
+        assert!(!config.auth.is_enabled());
     }
 
     #[test]
     fn test_jsonrpc_from_partial() {
         let defaults: Box<Defaults> = Box::new(Testnet1);
         let addr: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+        let websocket_addr: SocketAddr = "127.0.0.1:4001".parse().unwrap();
         let partial_config = partial::JsonRPC {
             enabled: None,
             server_address: Some(addr),
+            websocket_address: Some(websocket_addr),
+            enabled_methods: None,
+            disabled_methods: Some(vec!["banPeer".to_string()]),
+            max_requests_per_minute: Some(0),
+            max_requests_in_flight: None,
+            auth: partial::Auth::default(),
+            tls: partial::Tls {
+                cert: Some(PathBuf::from("/etc/witnet/rpc/cert.pem")),
+                key: None,
+            },
+            cors: partial::Cors::default(),
         };
         let config = JsonRPC::from_partial(&partial_config, &*defaults);
 
         assert_eq!(config.server_address, addr);
+        assert_eq!(config.websocket_address, Some(websocket_addr));
+        assert!(config.enabled_methods.is_empty());
+        assert_eq!(config.disabled_methods, vec!["banPeer"]);
+        assert_eq!(config.max_requests_per_minute, 0);
+        assert_eq!(
+            config.max_requests_in_flight,
+            Testnet1.jsonrpc_max_requests_in_flight()
+        );
+        assert!(!config.tls.is_enabled());
+    }
+
+    #[test]
+    fn test_jsonrpc_auth_from_toml() {
+        let partial_config: partial::JsonRPC = toml::from_str(
+            r#"
+[[auth.keys]]
+key = "s3cr3t"
+methods = ["getPeers", "getConfig"]
+
+[[auth.keys]]
+key = "adm1n"
+methods = ["*"]
+"#,
+        )
+        .unwrap();
+        let config = Auth::from_partial(&partial_config.auth);
+
+        assert!(config.is_enabled());
+        assert_eq!(config.keys.len(), 2);
+        assert_eq!(config.keys[0].key.expose(), "s3cr3t");
+        assert_eq!(config.keys[0].methods, vec!["getPeers", "getConfig"]);
+        assert_eq!(config.keys[1].methods, vec!["*"]);
+    }
+
+    #[test]
+    fn test_rest_from_partial() {
+        let defaults: Box<Defaults> = Box::new(Testnet1);
+        let config = Rest::from_partial(&partial::Rest::default(), &*defaults);
+
+        assert!(!config.enabled);
+        assert_eq!(config.server_address, "127.0.0.1:21339".parse().unwrap());
+
+        let addr: SocketAddr = "0.0.0.0:8080".parse().unwrap();
+        let partial_config = partial::Rest {
+            enabled: Some(true),
+            server_address: Some(addr),
+            cors: partial::Cors::default(),
+        };
+        let config = Rest::from_partial(&partial_config, &*defaults);
+
+        assert!(config.enabled);
+        assert_eq!(config.server_address, addr);
+    }
+
+    #[test]
+    fn test_cors_allows() {
+        let partial_config: partial::Rest = toml::from_str(
+            r#"
+[cors]
+allowed_origins = ["http://localhost:8080"]
+"#,
+        )
+        .unwrap();
+        let config = Cors::from_partial(&partial_config.cors);
+
+        assert!(config.allows("http://localhost:8080"));
+        assert!(config.allows("HTTP://LOCALHOST:8080"));
+        assert!(!config.allows("http://localhost:3000"));
+
+        let config = Cors {
+            allowed_origins: vec!["*".to_string()],
+        };
+        assert!(config.allows("https://explorer.example.com"));
+
+        assert!(!Cors::from_partial(&partial::Cors::default()).allows("http://localhost:8080"));
     }
 
     #[test]
@@ -367,7 +2114,7 @@ mod tests {
 
         assert_eq!(config.environment, Environment::Testnet1);
         assert_eq!(
-            config.connections.server_addr,
+            config.connections.server_addr(),
             Testnet1.connections_server_addr()
         );
         assert_eq!(
@@ -404,4 +2151,196 @@ mod tests {
             Testnet1.jsonrpc_server_address()
         );
     }
+
+    #[test]
+    fn test_config_to_toml_string() {
+        let mut partial_config = partial::Config::default();
+        partial_config.connections.inbound_limit = Some(999);
+        partial_config.connections.handshake_timeout = Some(Duration::from_secs(21));
+        partial_config.storage.db_path = Some(PathBuf::from("dbfiles"));
+        let config = Config::from_partial(&partial_config);
+
+        let contents = config.to_toml_string().unwrap();
+        let value: toml::Value = contents.parse().unwrap();
+
+        assert_eq!(value["environment"].as_str(), Some("testnet-1"));
+        assert_eq!(
+            value["config_version"].as_integer(),
+            Some(i64::from(migrations::CURRENT_VERSION))
+        );
+        assert_eq!(
+            value["connections"]["inbound_limit"].as_integer(),
+            Some(999)
+        );
+        assert_eq!(
+            value["connections"]["handshake_timeout_seconds"].as_integer(),
+            Some(21)
+        );
+        assert_eq!(value["storage"]["db_path"].as_str(), Some("dbfiles"));
+        assert_eq!(
+            value["consensus_constants"]["checkpoints_period_seconds"].as_integer(),
+            Some(90)
+        );
+        assert_eq!(
+            value["jsonrpc"]["server_address"].as_str(),
+            Some(config.jsonrpc.server_address.to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn test_sync_from_partial() {
+        let defaults: Box<Defaults> = Box::new(Testnet1);
+        let config =
+            Synchronization::from_partial(&partial::Synchronization::default(), &*defaults);
+
+        assert_eq!(config.headers_first, Testnet1.sync_headers_first());
+        assert_eq!(config.checkpoints, Testnet1.sync_checkpoints());
+
+        let checkpoints = vec![Checkpoint {
+            epoch: 10,
+            hash: Hash::SHA256([1; 32]),
+        }];
+        let partial_config = partial::Synchronization {
+            headers_first: Some(false),
+            checkpoints: Some(checkpoints.clone()),
+            download_window: Some(64),
+            download_timeout: Some(Duration::from_secs(5)),
+        };
+        let config = Synchronization::from_partial(&partial_config, &*defaults);
+
+        assert!(!config.headers_first);
+        assert_eq!(config.checkpoints, checkpoints);
+        assert_eq!(config.download_window, 64);
+        assert_eq!(config.download_timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_sync_checkpoints_toml_round_trip() {
+        let mut partial_config = partial::Config::default();
+        partial_config.sync.checkpoints = Some(vec![Checkpoint {
+            epoch: 10,
+            hash: Hash::SHA256([1; 32]),
+        }]);
+        let config = Config::from_partial(&partial_config);
+
+        let contents = config.to_toml_string().unwrap();
+        let reloaded: partial::Config = toml::from_str(&contents).unwrap();
+
+        assert_eq!(reloaded.sync.checkpoints, partial_config.sync.checkpoints);
+    }
+
+    #[test]
+    fn test_config_custom_environment_from_partial() {
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let jsonrpc_addr: SocketAddr = "127.0.0.1:5001".parse().unwrap();
+        let mut partial_config = partial::Config::default();
+        partial_config.environment = Some(Environment::Custom("devnet".to_string()));
+        partial_config.connections.server_addrs = Some(vec![addr]);
+        partial_config.connections.magic_number = Some(0x1234);
+        partial_config.storage.db_path = Some(PathBuf::from(".witnet-devnet"));
+        partial_config.consensus_constants.checkpoint_zero_timestamp = Some(1_000);
+        partial_config.consensus_constants.checkpoints_period = Some(10);
+        partial_config.jsonrpc.server_address = Some(jsonrpc_addr);
+
+        let config = Config::from_partial(&partial_config);
+
+        assert_eq!(
+            config.environment,
+            Environment::Custom("devnet".to_string())
+        );
+        assert_eq!(config.connections.server_addrs, vec![addr]);
+        assert_eq!(config.connections.magic_number, 0x1234);
+        assert_eq!(
+            config.connections.inbound_limit,
+            Testnet1.connections_inbound_limit()
+        );
+        assert_eq!(config.storage.db_path, PathBuf::from(".witnet-devnet"));
+        assert_eq!(config.consensus_constants.checkpoint_zero_timestamp, 1_000);
+        assert_eq!(config.consensus_constants.checkpoints_period, 10);
+        assert_eq!(config.jsonrpc.server_address, jsonrpc_addr);
+    }
+
+    #[test]
+    fn test_custom_defaults_db_path() {
+        let mut partial_config = partial::Config::default();
+        partial_config.environment = Some(Environment::Custom("devnet".to_string()));
+        partial_config.connections.server_addrs = Some(vec!["127.0.0.1:3000".parse().unwrap()]);
+        partial_config.connections.magic_number = Some(7);
+        partial_config.consensus_constants.checkpoint_zero_timestamp = Some(0);
+        partial_config.jsonrpc.server_address = Some("127.0.0.1:3001".parse().unwrap());
+
+        let custom = custom_defaults(&partial_config).unwrap();
+
+        assert_eq!(
+            custom.storage_db_path,
+            crate::dirs::environment_data_path("devnet").join("db")
+        );
+    }
+
+    #[test]
+    fn test_custom_defaults_missing_params() {
+        let mut partial_config = partial::Config::default();
+        partial_config.environment = Some(Environment::Custom("devnet".to_string()));
+        partial_config.storage.db_path = Some(PathBuf::from(".witnet-devnet"));
+
+        let missing = custom_defaults(&partial_config).err().unwrap();
+
+        assert_eq!(
+            missing,
+            vec![
+                "connections.server_addr",
+                "connections.magic_number",
+                "consensus_constants.checkpoint_zero_timestamp",
+                "jsonrpc.server_address",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_from_partial_reports_all_problems() {
+        let mut partial_config = partial::Config::default();
+        partial_config.environment = Some(Environment::Custom("devnet".to_string()));
+        partial_config.storage.db_path = Some(PathBuf::from(".witnet-devnet"));
+        partial_config
+            .connections
+            .known_peers
+            .insert("0.0.0.0:5000".parse().unwrap());
+
+        let problems = Config::try_from_partial(&partial_config)
+            .unwrap_err()
+            .problems;
+
+        assert_eq!(problems.len(), 5);
+        match &problems[0] {
+            Problem::InvalidAddress { param, .. } => assert_eq!(*param, "connections.known_peers"),
+            other => panic!("Unexpected problem: {:?}", other),
+        }
+        assert!(problems.contains(&Problem::MissingParam {
+            environment: "custom:devnet".to_string(),
+            param: "connections.magic_number",
+        }));
+    }
+
+    #[test]
+    fn test_try_from_partial_includes_validation_errors() {
+        let addr: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+        let mut partial_config = partial::Config::default();
+        partial_config.connections.server_addrs = Some(vec![addr]);
+        partial_config.connections.outbound_limit = Some(0);
+        partial_config.jsonrpc.server_address = Some(addr);
+
+        let problems = Config::try_from_partial(&partial_config)
+            .unwrap_err()
+            .problems;
+
+        assert_eq!(problems.len(), 2);
+        assert!(
+            problems.contains(&Problem::Invalid(ValidationError::AddressClash {
+                param: "connections.server_addr",
+                other: "jsonrpc.server_address",
+                addr,
+            }))
+        );
+        assert!(Config::try_from_partial(&partial::Config::default()).is_ok());
+    }
 }
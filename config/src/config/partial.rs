@@ -8,21 +8,48 @@
 //! later, the `config` module will use this partial config object and
 //! the environment-specific defaults (see the `environment` module)
 //! to produce a __total__ (no `Option` fields) configuration object.
-use std::collections::HashSet;
+//!
+//! Partial configurations coming from different sources can be
+//! layered with `Config::merge`, e.g.: a file, the environment and the
+//! command line:
+//!
+//! ```
+//! use witnet_config::config::partial::Config;
+//!
+//! let file = Config::default();
+//! let env = Config::default();
+//! let cli = Config::default();
+//!
+//! let config = file.merge(env).merge(cli);
+//! ```
+use log::LevelFilter;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::time::Duration;
-use witnet_data_structures::chain::{Environment, Hash};
+use witnet_data_structures::chain::{Checkpoint, Environment, Epoch, Hash, SHA256};
+use witnet_util::net::IpNetwork;
+
+use super::{
+    ApiKey, ArchiveBackend, CompactionStyle, Compression, MessageCompression, StorageBackend,
+    TlsPeer,
+};
+use crate::secret::Secret;
 
 /// The partial configuration object that contains all other, more
 /// specific, configuration objects (connections, storage, etc).
-#[derive(Deserialize, Default, Debug, PartialEq)]
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct Config {
+    /// Version of the layout of the configuration, see the
+    /// `migrations` module
+    #[serde(default)]
+    pub config_version: Option<u32>,
+
     /// The "environment" in which the protocol will be deployed, eg:
     /// mainnet, testnet, etc.
     #[serde(default)]
-    pub environment: Environment,
+    pub environment: Option<Environment>,
 
     /// Connections-related configuration
     #[serde(default)]
@@ -39,15 +66,60 @@ pub struct Config {
     /// JSON-RPC API configuration
     #[serde(default)]
     pub jsonrpc: JsonRPC,
+
+    /// REST API configuration
+    #[serde(default)]
+    pub rest: Rest,
+
+    /// Mining-related configuration
+    #[serde(default)]
+    pub mining: Mining,
+
+    /// Wallet-related configuration
+    #[serde(default)]
+    pub wallet: Wallet,
+
+    /// Metrics-related configuration
+    #[serde(default)]
+    pub metrics: Metrics,
+
+    /// Bridge-related configuration
+    #[serde(default)]
+    pub bridge: Bridge,
+
+    /// Node identity configuration
+    #[serde(default)]
+    pub identity: Identity,
+
+    /// Logging configuration
+    #[serde(default)]
+    pub log: Log,
+
+    /// Chain synchronization configuration
+    #[serde(default)]
+    pub sync: Synchronization,
+
+    /// Named profiles (`[profile.<name>]` sections), which are partial
+    /// configurations that can be selected with `select_profile` to
+    /// be merged over this one
+    #[serde(default)]
+    #[serde(rename = "profile")]
+    pub profiles: HashMap<String, Config>,
 }
 
 /// Connection-specific partial configuration.
 #[derive(Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct Connections {
-    /// Server address, that is, the socket address (interface ip and
-    /// port) to which the server accepting connections from other
-    /// peers should bind to
-    pub server_addr: Option<SocketAddr>,
+    /// Server addresses, that is, the socket addresses (interface ip
+    /// and port) to which the server accepting connections from other
+    /// peers should bind to, written as a single address or as a list
+    #[serde(default)]
+    #[serde(deserialize_with = "from_one_or_many")]
+    #[serde(rename = "server_addr")]
+    pub server_addrs: Option<Vec<SocketAddr>>,
+
+    /// Magic number identifying the network in protocol messages
+    pub magic_number: Option<u16>,
 
     /// Maximum number of concurrent connections the server should
     /// accept
@@ -57,12 +129,49 @@ pub struct Connections {
     /// (acting as a client) should maintain
     pub outbound_limit: Option<u16>,
 
+    /// Maximum number of inbound connections from the same IP address
+    pub max_connections_per_ip: Option<u16>,
+
+    /// Maximum number of inbound connections from the same subnet
+    /// (/24 for IPv4, /64 for IPv6)
+    pub max_connections_per_subnet: Option<u16>,
+
     /// List of other peer addresses this node knows at start, it is
     /// used as a bootstrap mechanism to gain access to the P2P
     /// network
     #[serde(default)]
     pub known_peers: HashSet<SocketAddr>,
 
+    /// Domain names resolved to discover peers, written as `host` or
+    /// `host:port` (the port of `server_addr` is used if missing)
+    #[serde(default)]
+    pub dns_seeds: HashSet<String>,
+
+    /// IP addresses and networks allowed to connect to this node and
+    /// to be connected to, written as `ip` or in CIDR notation
+    /// (`ip/prefix_len`)
+    #[serde(default)]
+    pub whitelist: HashSet<IpNetwork>,
+
+    /// IP addresses and networks never allowed to connect to this
+    /// node nor to be connected to, written as `ip` or in CIDR
+    /// notation (`ip/prefix_len`)
+    #[serde(default)]
+    pub blacklist: HashSet<IpNetwork>,
+
+    /// IP addresses and networks of priority peers, such as other
+    /// nodes of the operator, which can connect to this node even
+    /// when `inbound_limit` is reached, written as `ip` or in CIDR
+    /// notation (`ip/prefix_len`)
+    #[serde(default)]
+    pub priority_peers: HashSet<IpNetwork>,
+
+    /// Period of the DNS seeds resolution task
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "dns_seeds_period_seconds")]
+    pub dns_seeds_period: Option<Duration>,
+
     /// Period of the bootstrap peers task
     #[serde(default)]
     #[serde(deserialize_with = "from_secs")]
@@ -75,25 +184,369 @@ pub struct Connections {
     #[serde(rename = "storage_peers_period_seconds")]
     pub storage_peers_period: Option<Duration>,
 
+    /// Time after which the stored peers expire if they are not
+    /// persisted again
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "storage_peers_ttl_seconds")]
+    pub storage_peers_ttl: Option<Duration>,
+
     /// Period of the peers discovery task
     #[serde(default)]
     #[serde(deserialize_with = "from_secs")]
     #[serde(rename = "discovery_peers_period_seconds")]
     pub discovery_peers_period: Option<Duration>,
 
+    /// Period of the feeler connections task, `0` disables the
+    /// feeler connections
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "feeler_peers_period_seconds")]
+    pub feeler_peers_period: Option<Duration>,
+
     /// Handshake timeout
     #[serde(default)]
     #[serde(deserialize_with = "from_secs")]
     #[serde(rename = "handshake_timeout_seconds")]
     pub handshake_timeout: Option<Duration>,
+
+    /// Time without receiving messages from a peer after which it is
+    /// pinged, `0` disables the pings
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "ping_period_seconds")]
+    pub ping_period: Option<Duration>,
+
+    /// Time to wait for the answer to a ping before disconnecting
+    /// from the peer
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "ping_timeout_seconds")]
+    pub ping_timeout: Option<Duration>,
+
+    /// Misbehavior score at which a peer gets banned, `0` disables
+    /// banning
+    #[serde(default)]
+    pub ban_threshold: Option<u32>,
+
+    /// Time during which a banned peer cannot connect to this node
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "ban_duration_seconds")]
+    pub ban_duration: Option<Duration>,
+
+    /// Maximum upload bandwidth of all the connections with the
+    /// peers, in kilobits per second, `0` means no limit
+    #[serde(default)]
+    pub max_upload_kbps: Option<u32>,
+
+    /// Maximum download bandwidth of all the connections with the
+    /// peers, in kilobits per second, `0` means no limit
+    #[serde(default)]
+    pub max_download_kbps: Option<u32>,
+
+    /// Maximum upload bandwidth of each connection with a peer, in
+    /// kilobits per second, `0` means no limit
+    #[serde(default)]
+    pub max_peer_upload_kbps: Option<u32>,
+
+    /// Maximum download bandwidth of each connection with a peer, in
+    /// kilobits per second, `0` means no limit
+    #[serde(default)]
+    pub max_peer_download_kbps: Option<u32>,
+
+    /// Ask the router to forward the port of `server_addr` to this
+    /// node, and announce the external address of the router to the
+    /// peers
+    #[serde(default)]
+    pub upnp: Option<bool>,
+
+    /// Discover the peers in the local network, and announce this
+    /// node to them, using multicast DNS
+    #[serde(default)]
+    pub mdns: Option<bool>,
+
+    /// Compression of the messages exchanged with the peers which
+    /// support it
+    #[serde(default)]
+    pub compression: Option<MessageCompression>,
+
+    /// TLS termination of the inbound connections
+    #[serde(default)]
+    pub tls: Tls,
+
+    /// Peers terminating TLS on their P2P listener, written as
+    /// `[[connections.tls_peers]]` tables with an `address` and a
+    /// `server_name`
+    #[serde(default)]
+    pub tls_peers: Option<Vec<TlsPeer>>,
+
+    /// Path to the PEM file containing the certificates of the
+    /// authorities that sign the certificates of `tls_peers`
+    #[serde(default)]
+    pub tls_ca: Option<PathBuf>,
+
+    /// Maximum rates at which a peer can send each kind of message
+    #[serde(default)]
+    pub rate_limits: RateLimits,
+
+    /// Cap of outbound connections with peers of the same network
+    /// group
+    #[serde(default)]
+    pub diversity: Diversity,
+
+    /// Maximum sizes of the messages received from the peers, and
+    /// time they have to answer the requests of the node
+    #[serde(default)]
+    pub limits: Limits,
+
+    /// Policy for relaying the blocks received from the peers to the
+    /// other peers
+    #[serde(default)]
+    pub relay: Relay,
+
+    /// URL of the SOCKS5 proxy through which the outbound
+    /// connections are opened
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+/// TLS termination of the inbound connections
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Tls {
+    /// Path to the PEM file containing the certificate chain of the
+    /// server
+    pub cert: Option<PathBuf>,
+    /// Path to the PEM file containing the private key of the server
+    pub key: Option<PathBuf>,
+}
+
+/// Maximum rates at which a peer can send each kind of message, in
+/// messages per minute
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct RateLimits {
+    /// Maximum rate of `Inv` messages
+    #[serde(default)]
+    pub inv: Option<u32>,
+
+    /// Maximum rate of `GetData` messages
+    #[serde(default)]
+    pub get_data: Option<u32>,
+
+    /// Maximum rate of `GetPeers` and `Peers` messages
+    #[serde(default)]
+    pub peers: Option<u32>,
+}
+
+/// Cap of outbound connections with peers of the same network group
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Diversity {
+    /// Maximum number of outbound connections with peers of the
+    /// same network group
+    #[serde(default)]
+    pub max_outbound_per_group: Option<u16>,
+
+    /// Path to the file mapping the IP networks to the autonomous
+    /// systems announcing them
+    #[serde(default)]
+    pub asmap: Option<PathBuf>,
+}
+
+/// Maximum sizes of the messages received from the peers, and time
+/// they have to answer the requests of the node
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Limits {
+    /// Maximum size of any message, in bytes
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub max_message_size: Option<u64>,
+
+    /// Maximum size of `Block` messages, in bytes
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub max_block_size: Option<u64>,
+
+    /// Maximum size of `Headers` messages, in bytes
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub max_headers_size: Option<u64>,
+
+    /// Maximum size of `Inv` and `GetData` messages, in bytes
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub max_inventory_size: Option<u64>,
+
+    /// Maximum size of `Peers` messages, in bytes
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub max_peers_size: Option<u64>,
+
+    /// Time a peer has to answer a `GetPeers` message
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "get_peers_timeout_seconds")]
+    pub get_peers_timeout: Option<Duration>,
+
+    /// Time a peer has to answer a `GetHeaders` message
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "get_headers_timeout_seconds")]
+    pub get_headers_timeout: Option<Duration>,
+}
+
+/// Policy for relaying the blocks received from the peers to the
+/// other peers
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Relay {
+    /// Relay the blocks that the peers send without the node
+    /// requesting them
+    #[serde(default)]
+    pub unsolicited: Option<bool>,
+
+    /// Minimum reputation score of the peers whose blocks are relayed
+    #[serde(default)]
+    pub min_reputation: Option<i64>,
+
+    /// Relay the blocks which have not been validated through their
+    /// headers
+    #[serde(default)]
+    pub unvalidated: Option<bool>,
 }
 
 /// Storage-specific configuration
 #[derive(Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct Storage {
+    /// Backend used to store the data: `"rocksdb"` or `"memory"`
+    #[serde(default)]
+    pub backend: Option<StorageBackend>,
+
     #[serde(default)]
     /// Path to the directory that will contain the database files
     pub db_path: Option<PathBuf>,
+
+    /// Maximum size of the database files, in bytes. It can be
+    /// written as a byte size string, e.g.: `"2GiB"`
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub max_db_size: Option<u64>,
+
+    /// Size of the cache of recently read values, in bytes, or `0` to
+    /// disable it. It can be written as a byte size string, e.g.:
+    /// `"512MB"`
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub cache_size: Option<u64>,
+
+    /// Open the database without writing to it
+    #[serde(default)]
+    pub read_only: Option<bool>,
+
+    /// Wait for every write to reach the disk before returning
+    #[serde(default)]
+    pub sync_writes: Option<bool>,
+
+    /// Tuning of the RocksDB backend
+    #[serde(default)]
+    pub rocksdb: RocksDB,
+
+    /// Encryption of the stored values
+    #[serde(default)]
+    pub encryption: Encryption,
+
+    /// Pruning of old blocks
+    #[serde(default)]
+    pub pruning: Pruning,
+
+    /// Scheduled backups of the storage
+    #[serde(default)]
+    pub backup: Backup,
+
+    /// Offloading of old blocks to an archive
+    #[serde(default)]
+    pub archive: Archive,
+}
+
+/// Tuning of the RocksDB storage backend
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct RocksDB {
+    /// Size of the block cache, in bytes. It can be written as a byte
+    /// size string, e.g.: `"512MB"`
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub block_cache_size: Option<u64>,
+
+    /// Size of the memory buffer used for writes, in bytes. It can be
+    /// written as a byte size string, e.g.: `"64MiB"`
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub write_buffer_size: Option<u64>,
+
+    /// Maximum number of files opened by the database (`-1` means no
+    /// limit)
+    #[serde(default)]
+    pub max_open_files: Option<i32>,
+
+    /// Binary flag telling whether to write the changes to the
+    /// write-ahead log (WAL)
+    #[serde(default)]
+    pub wal: Option<bool>,
+
+    /// Algorithm used to compress the database files, e.g.:
+    /// `"snappy"`
+    #[serde(default)]
+    pub compression: Option<Compression>,
+
+    /// Strategy used to compact the database files: `"level"`,
+    /// `"universal"` or `"fifo"`
+    #[serde(default)]
+    pub compaction_style: Option<CompactionStyle>,
+
+    /// Tuning of the column family of each data type, overriding the
+    /// params above
+    #[serde(default)]
+    pub column_families: ColumnFamilies,
+}
+
+/// Column families in which each data type is stored
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct ColumnFamilies {
+    /// Blocks
+    #[serde(default)]
+    pub blocks: ColumnFamily,
+
+    /// Transactions
+    #[serde(default)]
+    pub transactions: ColumnFamily,
+
+    /// Unspent transaction outputs
+    #[serde(default)]
+    pub utxos: ColumnFamily,
+
+    /// Known peers
+    #[serde(default)]
+    pub peers: ColumnFamily,
+
+    /// Reputation of the identities
+    #[serde(default)]
+    pub reputation: ColumnFamily,
+}
+
+/// Tuning of a RocksDB column family
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct ColumnFamily {
+    /// Size of the memory buffer used for writes, in bytes. It can be
+    /// written as a byte size string, e.g.: `"64MiB"`
+    #[serde(default)]
+    #[serde(deserialize_with = "from_byte_size")]
+    pub write_buffer_size: Option<u64>,
+
+    /// Algorithm used to compress the column family files
+    #[serde(default)]
+    pub compression: Option<Compression>,
+
+    /// Strategy used to compact the column family files
+    #[serde(default)]
+    pub compaction_style: Option<CompactionStyle>,
 }
 
 /// Consensus-critical configuration
@@ -109,7 +562,7 @@ pub struct ConsensusConstants {
     pub checkpoints_period: Option<u16>,
 
     /// Genesis block hash value
-    #[serde(default)]
+    #[serde(default, deserialize_with = "from_hash_table")]
     pub genesis_hash: Option<Hash>,
 
     /// Decay value for reputation demurrage function
@@ -129,25 +582,907 @@ pub struct JsonRPC {
     /// JSON-RPC server address, that is, the socket address (interface ip and
     /// port) for the JSON-RPC server
     pub server_address: Option<SocketAddr>,
+    /// Socket address of the WebSocket transport of the JSON-RPC server
+    pub websocket_address: Option<SocketAddr>,
+    /// Methods served, all of them if empty
+    #[serde(default)]
+    pub enabled_methods: Option<Vec<String>>,
+    /// Methods not served, even if listed in `enabled_methods`
+    #[serde(default)]
+    pub disabled_methods: Option<Vec<String>>,
+    /// Maximum number of requests per minute from each IP address
+    #[serde(default)]
+    pub max_requests_per_minute: Option<u32>,
+    /// Maximum number of requests processed at once, from all the
+    /// clients
+    #[serde(default)]
+    pub max_requests_in_flight: Option<u32>,
+    /// Authentication of the clients
+    #[serde(default)]
+    pub auth: Auth,
+    /// TLS termination of the connections of the clients
+    #[serde(default)]
+    pub tls: Tls,
+    /// Origins of the web pages allowed to open WebSocket connections
+    #[serde(default)]
+    pub cors: Cors,
+}
+
+/// Authentication of the JSON-RPC clients
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Auth {
+    /// API keys, written as `[[jsonrpc.auth.keys]]` tables with a
+    /// `key` and the `methods` it gives access to
+    #[serde(default)]
+    pub keys: Option<Vec<ApiKey>>,
+}
+
+/// Cross-origin access from web pages
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Cors {
+    /// Origins allowed, e.g.: `http://localhost:8080`, or `*` for any
+    #[serde(default)]
+    pub allowed_origins: Option<Vec<String>>,
+}
+
+/// REST API configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Rest {
+    /// Binary flag telling whether to enable the REST interface or not
+    pub enabled: Option<bool>,
+    /// Socket address (interface ip and port) of the REST server
+    pub server_address: Option<SocketAddr>,
+    /// Origins of the web pages allowed to read the responses
+    #[serde(default)]
+    pub cors: Cors,
+}
+
+/// Mining-related configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Mining {
+    /// Binary flag telling whether to enable mining or not
+    pub enabled: Option<bool>,
+}
+
+/// Wallet-related configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Wallet {
+    /// Binary flag telling whether to enable the wallet or not
+    pub enabled: Option<bool>,
+}
+
+/// Metrics-related configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Metrics {
+    /// Binary flag telling whether to enable the metrics or not
+    pub enabled: Option<bool>,
+}
+
+/// Bridge-related configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Bridge {
+    /// Binary flag telling whether to enable the bridge or not
+    pub enabled: Option<bool>,
+}
+
+/// Chain synchronization configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Synchronization {
+    /// Download and validate the block headers before the blocks,
+    /// with the peers that support it
+    #[serde(default)]
+    pub headers_first: Option<bool>,
+
+    /// Blocks known to be part of the chain, written as
+    /// `[[sync.checkpoints]]` tables with an `epoch` and a `hash`
+    #[serde(default, deserialize_with = "from_checkpoint_tables")]
+    pub checkpoints: Option<Vec<Checkpoint>>,
+
+    /// Maximum number of blocks requested to the peers and not
+    /// received yet, counting from the first missing block
+    #[serde(default)]
+    pub download_window: Option<u32>,
+
+    /// Time a peer has to send the blocks requested to it before they
+    /// are requested to another peer
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "download_timeout_seconds")]
+    pub download_timeout: Option<Duration>,
+}
+
+/// Pruning of old blocks
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Pruning {
+    /// Binary flag telling whether to delete the data of old blocks
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Number of epochs whose blocks are kept entirely
+    #[serde(default)]
+    pub keep_blocks: Option<u32>,
+}
+
+/// Scheduled backups of the storage
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Backup {
+    /// Binary flag telling whether to make backups
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Period of the backups
+    #[serde(default)]
+    #[serde(deserialize_with = "from_secs")]
+    #[serde(rename = "interval_seconds")]
+    pub interval: Option<Duration>,
+
+    /// Directory where the backups are written
+    #[serde(default)]
+    pub destination: Option<PathBuf>,
+
+    /// Number of backups to keep, older ones are deleted
+    #[serde(default)]
+    pub retention: Option<u32>,
+}
+
+/// Offloading of old blocks to an archive
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Archive {
+    /// Binary flag telling whether to offload old blocks
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Backend of the archive: `"directory"`
+    #[serde(default)]
+    pub backend: Option<ArchiveBackend>,
+
+    /// Directory where the archived blocks are written
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// Number of epochs whose blocks are kept in the database
+    #[serde(default)]
+    pub keep_blocks: Option<u32>,
+}
+
+/// Encryption of the stored values
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Encryption {
+    /// Passphrase from which the encryption key is derived
+    pub passphrase: Option<Secret>,
+    /// Path to a file whose contents are used as the secret from
+    /// which the encryption key is derived
+    pub keyfile: Option<PathBuf>,
+}
+
+/// Node identity configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Identity {
+    /// Path to the file containing the secp256k1 master key of the
+    /// node
+    pub master_key_path: Option<PathBuf>,
+    /// Binary flag telling whether to generate a new master key if
+    /// the file does not exist
+    pub create_if_missing: Option<bool>,
+    /// Mnemonic phrase from which to import the master key
+    pub mnemonic: Option<Secret>,
+}
+
+/// Logging configuration
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Log {
+    /// Maximum level of the log messages, e.g.: `"info"`
+    #[serde(default)]
+    #[serde(deserialize_with = "from_level")]
+    pub level: Option<LevelFilter>,
 }
 
 impl Config {
     pub fn default_mainnet() -> Self {
         let mut default = Config::default();
-        default.environment = Environment::Mainnet;
+        default.environment = Some(Environment::Mainnet);
         default
     }
+
+    /// Merge another partial configuration on top of this one, so
+    /// later layers win: params that are `Some` in `other` take
+    /// precedence over the ones in `self`, while params that are
+    /// `None` in `other` keep the value in `self`. Sets of values,
+    /// such as `connections.known_peers`, `connections.dns_seeds` or
+    /// `connections.blacklist`, are joined.
+    pub fn merge(self, other: Config) -> Config {
+        let mut profiles = self.profiles;
+        for (name, profile) in other.profiles {
+            let profile = match profiles.remove(&name) {
+                Some(previous) => previous.merge(profile),
+                None => profile,
+            };
+            profiles.insert(name, profile);
+        }
+
+        Config {
+            config_version: other.config_version.or(self.config_version),
+            environment: other.environment.or(self.environment),
+            connections: self.connections.merge(other.connections),
+            storage: self.storage.merge(other.storage),
+            consensus_constants: self.consensus_constants.merge(other.consensus_constants),
+            jsonrpc: self.jsonrpc.merge(other.jsonrpc),
+            rest: self.rest.merge(other.rest),
+            mining: self.mining.merge(other.mining),
+            wallet: self.wallet.merge(other.wallet),
+            metrics: self.metrics.merge(other.metrics),
+            bridge: self.bridge.merge(other.bridge),
+            identity: self.identity.merge(other.identity),
+            log: self.log.merge(other.log),
+            sync: self.sync.merge(other.sync),
+            profiles,
+        }
+    }
+
+    /// Merge the profile with the given name over this configuration.
+    /// The resulting configuration contains no profiles. Returns
+    /// `None` if there is no profile with that name.
+    pub fn select_profile(mut self, name: &str) -> Option<Config> {
+        let mut profile = self.profiles.remove(name)?;
+        profile.profiles.clear();
+        self.profiles.clear();
+
+        Some(self.merge(profile))
+    }
+}
+
+impl Connections {
+    /// Merge another partial connections configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: Connections) -> Connections {
+        Connections {
+            server_addrs: other.server_addrs.or(self.server_addrs),
+            magic_number: other.magic_number.or(self.magic_number),
+            inbound_limit: other.inbound_limit.or(self.inbound_limit),
+            outbound_limit: other.outbound_limit.or(self.outbound_limit),
+            max_connections_per_ip: other.max_connections_per_ip.or(self.max_connections_per_ip),
+            max_connections_per_subnet: other
+                .max_connections_per_subnet
+                .or(self.max_connections_per_subnet),
+            known_peers: self
+                .known_peers
+                .union(&other.known_peers)
+                .cloned()
+                .collect(),
+            dns_seeds: self.dns_seeds.union(&other.dns_seeds).cloned().collect(),
+            whitelist: self.whitelist.union(&other.whitelist).cloned().collect(),
+            blacklist: self.blacklist.union(&other.blacklist).cloned().collect(),
+            priority_peers: self
+                .priority_peers
+                .union(&other.priority_peers)
+                .cloned()
+                .collect(),
+            dns_seeds_period: other.dns_seeds_period.or(self.dns_seeds_period),
+            bootstrap_peers_period: other.bootstrap_peers_period.or(self.bootstrap_peers_period),
+            storage_peers_period: other.storage_peers_period.or(self.storage_peers_period),
+            storage_peers_ttl: other.storage_peers_ttl.or(self.storage_peers_ttl),
+            discovery_peers_period: other.discovery_peers_period.or(self.discovery_peers_period),
+            feeler_peers_period: other.feeler_peers_period.or(self.feeler_peers_period),
+            handshake_timeout: other.handshake_timeout.or(self.handshake_timeout),
+            ping_period: other.ping_period.or(self.ping_period),
+            ping_timeout: other.ping_timeout.or(self.ping_timeout),
+            ban_threshold: other.ban_threshold.or(self.ban_threshold),
+            ban_duration: other.ban_duration.or(self.ban_duration),
+            max_upload_kbps: other.max_upload_kbps.or(self.max_upload_kbps),
+            max_download_kbps: other.max_download_kbps.or(self.max_download_kbps),
+            max_peer_upload_kbps: other.max_peer_upload_kbps.or(self.max_peer_upload_kbps),
+            max_peer_download_kbps: other.max_peer_download_kbps.or(self.max_peer_download_kbps),
+            upnp: other.upnp.or(self.upnp),
+            mdns: other.mdns.or(self.mdns),
+            compression: other.compression.or(self.compression),
+            tls: self.tls.merge(other.tls),
+            tls_peers: other.tls_peers.or(self.tls_peers),
+            tls_ca: other.tls_ca.or(self.tls_ca),
+            rate_limits: self.rate_limits.merge(other.rate_limits),
+            diversity: self.diversity.merge(other.diversity),
+            limits: self.limits.merge(other.limits),
+            relay: self.relay.merge(other.relay),
+            proxy: other.proxy.or(self.proxy),
+        }
+    }
+}
+
+impl Tls {
+    /// Merge another partial TLS configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Tls) -> Tls {
+        Tls {
+            cert: other.cert.or(self.cert),
+            key: other.key.or(self.key),
+        }
+    }
+}
+
+impl RateLimits {
+    /// Merge another partial rate limits configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: RateLimits) -> RateLimits {
+        RateLimits {
+            inv: other.inv.or(self.inv),
+            get_data: other.get_data.or(self.get_data),
+            peers: other.peers.or(self.peers),
+        }
+    }
+}
+
+impl Diversity {
+    /// Merge another partial diversity configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: Diversity) -> Diversity {
+        Diversity {
+            max_outbound_per_group: other.max_outbound_per_group.or(self.max_outbound_per_group),
+            asmap: other.asmap.or(self.asmap),
+        }
+    }
+}
+
+impl Limits {
+    /// Merge another partial limits configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Limits) -> Limits {
+        Limits {
+            max_message_size: other.max_message_size.or(self.max_message_size),
+            max_block_size: other.max_block_size.or(self.max_block_size),
+            max_headers_size: other.max_headers_size.or(self.max_headers_size),
+            max_inventory_size: other.max_inventory_size.or(self.max_inventory_size),
+            max_peers_size: other.max_peers_size.or(self.max_peers_size),
+            get_peers_timeout: other.get_peers_timeout.or(self.get_peers_timeout),
+            get_headers_timeout: other.get_headers_timeout.or(self.get_headers_timeout),
+        }
+    }
+}
+
+impl Relay {
+    /// Merge another partial relay configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Relay) -> Relay {
+        Relay {
+            unsolicited: other.unsolicited.or(self.unsolicited),
+            min_reputation: other.min_reputation.or(self.min_reputation),
+            unvalidated: other.unvalidated.or(self.unvalidated),
+        }
+    }
+}
+
+impl Storage {
+    /// Merge another partial storage configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: Storage) -> Storage {
+        Storage {
+            backend: other.backend.or(self.backend),
+            db_path: other.db_path.or(self.db_path),
+            max_db_size: other.max_db_size.or(self.max_db_size),
+            cache_size: other.cache_size.or(self.cache_size),
+            read_only: other.read_only.or(self.read_only),
+            sync_writes: other.sync_writes.or(self.sync_writes),
+            rocksdb: self.rocksdb.merge(other.rocksdb),
+            encryption: self.encryption.merge(other.encryption),
+            pruning: self.pruning.merge(other.pruning),
+            backup: self.backup.merge(other.backup),
+            archive: self.archive.merge(other.archive),
+        }
+    }
+}
+
+impl RocksDB {
+    /// Merge another partial RocksDB configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: RocksDB) -> RocksDB {
+        RocksDB {
+            block_cache_size: other.block_cache_size.or(self.block_cache_size),
+            write_buffer_size: other.write_buffer_size.or(self.write_buffer_size),
+            max_open_files: other.max_open_files.or(self.max_open_files),
+            wal: other.wal.or(self.wal),
+            compression: other.compression.or(self.compression),
+            compaction_style: other.compaction_style.or(self.compaction_style),
+            column_families: self.column_families.merge(other.column_families),
+        }
+    }
+}
+
+impl ColumnFamilies {
+    /// Merge the column families of another partial RocksDB
+    /// configuration on top of these ones (see `Config::merge`).
+    pub fn merge(self, other: ColumnFamilies) -> ColumnFamilies {
+        ColumnFamilies {
+            blocks: self.blocks.merge(other.blocks),
+            transactions: self.transactions.merge(other.transactions),
+            utxos: self.utxos.merge(other.utxos),
+            peers: self.peers.merge(other.peers),
+            reputation: self.reputation.merge(other.reputation),
+        }
+    }
+}
+
+impl ColumnFamily {
+    /// Merge another partial column family configuration on top of
+    /// this one (see `Config::merge`).
+    pub fn merge(self, other: ColumnFamily) -> ColumnFamily {
+        ColumnFamily {
+            write_buffer_size: other.write_buffer_size.or(self.write_buffer_size),
+            compression: other.compression.or(self.compression),
+            compaction_style: other.compaction_style.or(self.compaction_style),
+        }
+    }
+}
+
+impl ConsensusConstants {
+    /// Merge another partial consensus constants configuration on top
+    /// of this one (see `Config::merge`).
+    pub fn merge(self, other: ConsensusConstants) -> ConsensusConstants {
+        ConsensusConstants {
+            checkpoint_zero_timestamp: other
+                .checkpoint_zero_timestamp
+                .or(self.checkpoint_zero_timestamp),
+            checkpoints_period: other.checkpoints_period.or(self.checkpoints_period),
+            genesis_hash: other.genesis_hash.or(self.genesis_hash),
+            reputation_demurrage: other.reputation_demurrage.or(self.reputation_demurrage),
+            reputation_punishment: other.reputation_punishment.or(self.reputation_punishment),
+        }
+    }
+}
+
+impl JsonRPC {
+    /// Merge another partial JSON-RPC configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: JsonRPC) -> JsonRPC {
+        JsonRPC {
+            enabled: other.enabled.or(self.enabled),
+            server_address: other.server_address.or(self.server_address),
+            websocket_address: other.websocket_address.or(self.websocket_address),
+            enabled_methods: other.enabled_methods.or(self.enabled_methods),
+            disabled_methods: other.disabled_methods.or(self.disabled_methods),
+            max_requests_per_minute: other
+                .max_requests_per_minute
+                .or(self.max_requests_per_minute),
+            max_requests_in_flight: other.max_requests_in_flight.or(self.max_requests_in_flight),
+            auth: self.auth.merge(other.auth),
+            tls: self.tls.merge(other.tls),
+            cors: self.cors.merge(other.cors),
+        }
+    }
+}
+
+impl Auth {
+    /// Merge another partial authentication configuration on top of
+    /// this one (see `Config::merge`). The keys of `other`, if any,
+    /// replace the keys of this one.
+    pub fn merge(self, other: Auth) -> Auth {
+        Auth {
+            keys: other.keys.or(self.keys),
+        }
+    }
+}
+
+impl Cors {
+    /// Merge another partial CORS configuration on top of this one
+    /// (see `Config::merge`). The origins of `other`, if any, replace
+    /// the origins of this one.
+    pub fn merge(self, other: Cors) -> Cors {
+        Cors {
+            allowed_origins: other.allowed_origins.or(self.allowed_origins),
+        }
+    }
+}
+
+impl Rest {
+    /// Merge another partial REST configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Rest) -> Rest {
+        Rest {
+            enabled: other.enabled.or(self.enabled),
+            server_address: other.server_address.or(self.server_address),
+            cors: self.cors.merge(other.cors),
+        }
+    }
+}
+
+impl Mining {
+    /// Merge another partial mining configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Mining) -> Mining {
+        Mining {
+            enabled: other.enabled.or(self.enabled),
+        }
+    }
+}
+
+impl Wallet {
+    /// Merge another partial wallet configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Wallet) -> Wallet {
+        Wallet {
+            enabled: other.enabled.or(self.enabled),
+        }
+    }
+}
+
+impl Metrics {
+    /// Merge another partial metrics configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Metrics) -> Metrics {
+        Metrics {
+            enabled: other.enabled.or(self.enabled),
+        }
+    }
+}
+
+impl Bridge {
+    /// Merge another partial bridge configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Bridge) -> Bridge {
+        Bridge {
+            enabled: other.enabled.or(self.enabled),
+        }
+    }
+}
+
+impl Synchronization {
+    /// Merge another partial synchronization configuration on top of
+    /// this one (see `Config::merge`). The checkpoints of `other`, if
+    /// any, replace the previous ones.
+    pub fn merge(self, other: Synchronization) -> Synchronization {
+        Synchronization {
+            headers_first: other.headers_first.or(self.headers_first),
+            checkpoints: other.checkpoints.or(self.checkpoints),
+            download_window: other.download_window.or(self.download_window),
+            download_timeout: other.download_timeout.or(self.download_timeout),
+        }
+    }
+}
+
+impl Pruning {
+    /// Merge another partial pruning configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Pruning) -> Pruning {
+        Pruning {
+            enabled: other.enabled.or(self.enabled),
+            keep_blocks: other.keep_blocks.or(self.keep_blocks),
+        }
+    }
+}
+
+impl Backup {
+    /// Merge another partial backup configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Backup) -> Backup {
+        Backup {
+            enabled: other.enabled.or(self.enabled),
+            interval: other.interval.or(self.interval),
+            destination: other.destination.or(self.destination),
+            retention: other.retention.or(self.retention),
+        }
+    }
+}
+
+impl Archive {
+    /// Merge another partial archive configuration on top of this one
+    /// (see `Config::merge`).
+    pub fn merge(self, other: Archive) -> Archive {
+        Archive {
+            enabled: other.enabled.or(self.enabled),
+            backend: other.backend.or(self.backend),
+            path: other.path.or(self.path),
+            keep_blocks: other.keep_blocks.or(self.keep_blocks),
+        }
+    }
+}
+
+impl Encryption {
+    /// Merge another partial encryption configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: Encryption) -> Encryption {
+        Encryption {
+            passphrase: other.passphrase.or(self.passphrase),
+            keyfile: other.keyfile.or(self.keyfile),
+        }
+    }
+}
+
+impl Identity {
+    /// Merge another partial identity configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: Identity) -> Identity {
+        Identity {
+            master_key_path: other.master_key_path.or(self.master_key_path),
+            create_if_missing: other.create_if_missing.or(self.create_if_missing),
+            mnemonic: other.mnemonic.or(self.mnemonic),
+        }
+    }
 }
 
+impl Log {
+    /// Merge another partial logging configuration on top of this
+    /// one (see `Config::merge`).
+    pub fn merge(self, other: Log) -> Log {
+        Log {
+            level: other.level.or(self.level),
+        }
+    }
+}
+
+use serde::de::{self, SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer};
+use std::fmt;
 
 // Create a duration type from a u64 representing seconds
 fn from_secs<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    Ok(match u64::deserialize(deserializer) {
-        Ok(secs) => Some(Duration::from_secs(secs)),
-        Err(_) => None,
+    u64::deserialize(deserializer).map(|secs| Some(Duration::from_secs(secs)))
+}
+
+// Hash written as a table with the hash function as the only key,
+// e.g.: `{ SHA256 = [...] }`. Unlike the `Hash` enum, it can also be
+// read from a `[section]` table, which is how the Toml serializer
+// writes it (see `Config::to_toml_string`)
+#[derive(Deserialize)]
+struct HashTable {
+    #[serde(rename = "SHA256")]
+    sha256: SHA256,
+}
+
+#[derive(Deserialize)]
+struct CheckpointTable {
+    epoch: Epoch,
+    hash: HashTable,
+}
+
+// Create a hash from a table with the hash function as the only key
+fn from_hash_table<'de, D>(deserializer: D) -> Result<Option<Hash>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    HashTable::deserialize(deserializer).map(|hash| Some(Hash::SHA256(hash.sha256)))
+}
+
+// Create a list of checkpoints from tables with an epoch and a hash
+// table
+fn from_checkpoint_tables<'de, D>(deserializer: D) -> Result<Option<Vec<Checkpoint>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<CheckpointTable>::deserialize(deserializer).map(|checkpoints| {
+        Some(
+            checkpoints
+                .into_iter()
+                .map(|checkpoint| Checkpoint {
+                    epoch: checkpoint.epoch,
+                    hash: Hash::SHA256(checkpoint.hash.sha256),
+                })
+                .collect(),
+        )
     })
 }
+
+// Create a non-empty list of socket addresses from either a single
+// address or a list of addresses
+fn from_one_or_many<'de, D>(deserializer: D) -> Result<Option<Vec<SocketAddr>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct OneOrManyVisitor;
+
+    impl<'de> Visitor<'de> for OneOrManyVisitor {
+        type Value = Vec<SocketAddr>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a socket address or a non-empty list of socket addresses")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Vec<SocketAddr>, E> {
+            value
+                .parse()
+                .map(|addr| vec![addr])
+                .map_err(|_| E::custom(format!("invalid socket address `{}`", value)))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<SocketAddr>, A::Error> {
+            let mut addrs = vec![];
+            while let Some(addr) = seq.next_element()? {
+                addrs.push(addr);
+            }
+            if addrs.is_empty() {
+                return Err(de::Error::invalid_length(0, &self));
+            }
+
+            Ok(addrs)
+        }
+    }
+
+    deserializer.deserialize_any(OneOrManyVisitor).map(Some)
+}
+
+// Create a log level filter from its name, e.g.: "info"
+fn from_level<'de, D>(deserializer: D) -> Result<Option<LevelFilter>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let level = String::deserialize(deserializer)?;
+    level
+        .parse()
+        .map(Some)
+        .map_err(|_| de::Error::custom(format!("invalid log level `{}`", level)))
+}
+
+// Create a number of bytes from either an integer or a byte size
+// string such as "512MB" or "2GiB"
+fn from_byte_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ByteSizeVisitor;
+
+    impl<'de> Visitor<'de> for ByteSizeVisitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a number of bytes or a byte size string such as \"512MB\"")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<u64, E> {
+            if value < 0 {
+                Err(E::custom(format!("negative byte size: {}", value)))
+            } else {
+                Ok(value as u64)
+            }
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+            parse_byte_size(value).map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(ByteSizeVisitor).map(Some)
+}
+
+/// Parse a byte size string, i.e.: an integer followed by an optional
+/// unit. Both decimal (`KB`, `MB`, `GB`, `TB`) and binary (`KiB`,
+/// `MiB`, `GiB`, `TiB`) units are supported, case-insensitively.
+pub fn parse_byte_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or_else(|| value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid byte size: \"{}\"", value))?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return Err(format!("invalid byte size unit: \"{}\"", unit.trim())),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("byte size too big: \"{}\"", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_later_layers_win() {
+        let mut base = Config::default_mainnet();
+        base.connections.inbound_limit = Some(10);
+        base.connections.outbound_limit = Some(4);
+        base.storage.db_path = Some(PathBuf::from("base"));
+
+        let mut layer = Config::default();
+        layer.connections.inbound_limit = Some(999);
+        layer.jsonrpc.enabled = Some(false);
+
+        let config = base.merge(layer);
+
+        assert_eq!(config.environment, Some(Environment::Mainnet));
+        assert_eq!(config.connections.inbound_limit, Some(999));
+        assert_eq!(config.connections.outbound_limit, Some(4));
+        assert_eq!(config.storage.db_path, Some(PathBuf::from("base")));
+        assert_eq!(config.jsonrpc.enabled, Some(false));
+    }
+
+    #[test]
+    fn test_merge_empty_layers() {
+        let mut base = Config::default();
+        base.consensus_constants.checkpoints_period = Some(10);
+
+        assert_eq!(base.clone().merge(Config::default()), base);
+        assert_eq!(Config::default().merge(base.clone()), base);
+    }
+
+    #[test]
+    fn test_merge_known_peers() {
+        let a: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let b: SocketAddr = "127.0.0.1:2000".parse().unwrap();
+        let mut base = Config::default();
+        base.connections.known_peers.insert(a);
+        let mut layer = Config::default();
+        layer.connections.known_peers.insert(b);
+
+        let config = base.merge(layer);
+
+        assert_eq!(config.connections.known_peers.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("1024"), Ok(1024));
+        assert_eq!(parse_byte_size("512MB"), Ok(512_000_000));
+        assert_eq!(parse_byte_size("2GiB"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_byte_size(" 10 kib "), Ok(10 * 1024));
+        assert!(parse_byte_size("MB").is_err());
+        assert!(parse_byte_size("10 parsecs").is_err());
+        assert!(parse_byte_size("-10MB").is_err());
+        assert!(parse_byte_size("99999999999TiB").is_err());
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let mut local = Config::default();
+        local.storage.db_path = Some(PathBuf::from("local"));
+        let mut base = Config::default();
+        base.storage.db_path = Some(PathBuf::from("base"));
+        base.connections.inbound_limit = Some(10);
+        base.profiles.insert("local-dev".to_string(), local);
+
+        let config = base.clone().select_profile("local-dev").unwrap();
+
+        assert_eq!(config.storage.db_path, Some(PathBuf::from("local")));
+        assert_eq!(config.connections.inbound_limit, Some(10));
+        assert!(config.profiles.is_empty());
+        assert_eq!(base.select_profile("mainnet"), None);
+    }
+    #[test]
+    fn test_hash_tables() {
+        let inline: Config = toml::from_str(
+            r#"
+[consensus_constants]
+genesis_hash = { SHA256 = [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1] }
+"#,
+        )
+        .unwrap();
+        let section: Config = toml::from_str(
+            r#"
+[consensus_constants.genesis_hash]
+SHA256 = [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
+
+[[sync.checkpoints]]
+epoch = 10
+
+[sync.checkpoints.hash]
+SHA256 = [2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2]
+"#,
+        )
+        .unwrap();
+
+        let genesis_hash = Some(Hash::SHA256([1; 32]));
+        assert_eq!(inline.consensus_constants.genesis_hash, genesis_hash);
+        assert_eq!(section.consensus_constants.genesis_hash, genesis_hash);
+        assert_eq!(
+            section.sync.checkpoints,
+            Some(vec![Checkpoint {
+                epoch: 10,
+                hash: Hash::SHA256([2; 32]),
+            }])
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! # Redaction
+//!
+//! Params holding private keys, passwords or tokens must never end up
+//! in logs, which are often shared when reporting issues. Sensitive
+//! params are recognized by their name, and their values are replaced
+//! by `REDACTED` when printing the configuration. Paths to files
+//! holding secrets (params ending in `_path` or `_file`) and
+//! references to where a [secret](secret) is stored (`env:...` or
+//! `file:...`) are not sensitive, so they are kept.
+use super::Config;
+use crate::secret::{ENV_PREFIX, FILE_PREFIX};
+use toml::value::Value;
+
+/// Text replacing the values of sensitive params
+pub const REDACTED: &str = "<redacted>";
+
+/// Words that make a param sensitive when they appear in its name
+const SENSITIVE_WORDS: [&str; 7] = [
+    "key",
+    "password",
+    "passphrase",
+    "secret",
+    "token",
+    "mnemonic",
+    "auth",
+];
+
+/// Whether the param with the given path (e.g.: `identity.master_key`)
+/// holds a sensitive value
+pub fn is_sensitive(path: &str) -> bool {
+    let name = path.rsplit('.').next().unwrap_or(path).to_lowercase();
+    if name.ends_with("_path") || name.ends_with("_file") {
+        return false;
+    }
+
+    name.split('_')
+        .any(|word| SENSITIVE_WORDS.contains(&word.trim_end_matches('s')))
+}
+
+/// Replace the values of the sensitive params in a Toml document
+pub fn redact(value: &mut Value) {
+    if let Value::Table(table) = value {
+        for (key, value) in table.iter_mut() {
+            if is_sensitive(key) {
+                redact_value(value);
+            } else {
+                redact(value);
+            }
+        }
+    }
+}
+
+/// Replace a sensitive value, keeping references to secrets
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(s) if s.starts_with(ENV_PREFIX) || s.starts_with(FILE_PREFIX) => {}
+        Value::Array(values) => values.iter_mut().for_each(redact_value),
+        Value::Table(table) => table.values_mut().for_each(redact_value),
+        _ => *value = Value::String(REDACTED.to_string()),
+    }
+}
+
+impl Config {
+    /// The configuration as a Toml document with the values of the
+    /// sensitive params redacted
+    pub fn redacted(&self) -> Result<Value, toml::ser::Error> {
+        let mut value = self.to_toml_value()?;
+        redact(&mut value);
+
+        Ok(value)
+    }
+
+    /// Serialize the configuration in Toml format (see
+    /// `to_toml_string`) with the values of the sensitive params
+    /// redacted, so it can be safely logged.
+    pub fn to_redacted_toml_string(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(&self.redacted()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_sensitive() {
+        assert!(is_sensitive("identity.master_key"));
+        assert!(is_sensitive("jsonrpc.auth_token"));
+        assert!(is_sensitive("vault.passwords"));
+        assert!(!is_sensitive("identity.master_key_path"));
+        assert!(is_sensitive("password"));
+        assert!(!is_sensitive("connections.known_peers"));
+        assert!(!is_sensitive("storage.db_path"));
+        assert!(!is_sensitive("monkey"));
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut value: Value = r#"
+[connections]
+inbound_limit = 3
+[vault]
+token = "s3cr3t"
+api_key = "env:VAULT_API_KEY"
+passwords = ["a", "file:/run/secrets/b"]
+"#
+        .parse()
+        .unwrap();
+
+        redact(&mut value);
+
+        assert_eq!(value["connections"]["inbound_limit"].as_integer(), Some(3));
+        assert_eq!(value["vault"]["token"].as_str(), Some(REDACTED));
+        assert_eq!(
+            value["vault"]["api_key"].as_str(),
+            Some("env:VAULT_API_KEY")
+        );
+        assert_eq!(value["vault"]["passwords"][0].as_str(), Some(REDACTED));
+        assert_eq!(
+            value["vault"]["passwords"][1].as_str(),
+            Some("file:/run/secrets/b")
+        );
+    }
+
+    #[test]
+    fn test_default_config_has_nothing_to_redact() {
+        let config = Config::default();
+        let redacted = config.to_redacted_toml_string().unwrap();
+
+        assert_eq!(redacted, config.to_toml_string().unwrap());
+    }
+}
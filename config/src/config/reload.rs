@@ -0,0 +1,123 @@
+//! # Hot reload
+//!
+//! The configuration can be reloaded while the node is running, but
+//! only some params can be applied without restarting it. Changes to
+//! any other param are reported and ignored until the next restart.
+use super::diff::FieldChange;
+use super::Config;
+
+/// Params that can be changed while the node is running, addressed
+/// by their path in the Toml file
+pub const HOT_RELOADABLE: [&str; 4] = [
+    "connections.inbound_limit",
+    "connections.outbound_limit",
+    "jsonrpc.enabled",
+    "log.level",
+];
+
+/// Hot-reloadable params that can also be changed through the
+/// JSON-RPC API, addressed by their path in the Toml file
+pub const RUNTIME_TUNABLE: [&str; 3] = [
+    "connections.inbound_limit",
+    "connections.outbound_limit",
+    "log.level",
+];
+
+/// Whether the param with the given path can be changed while the
+/// node is running
+pub fn is_hot_reloadable(path: &str) -> bool {
+    HOT_RELOADABLE.contains(&path)
+}
+
+/// Whether the param with the given path can be changed through the
+/// JSON-RPC API
+pub fn is_runtime_tunable(path: &str) -> bool {
+    RUNTIME_TUNABLE.contains(&path)
+}
+
+/// Result of reloading the configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reload {
+    /// The configuration to use from now on: the running one with
+    /// the hot-reloadable params updated
+    pub config: Config,
+    /// Changes to hot-reloadable params, which have been applied
+    pub applied: Vec<FieldChange>,
+    /// Changes that require restarting the node, which have been
+    /// ignored
+    pub ignored: Vec<FieldChange>,
+}
+
+impl Config {
+    /// Reload this configuration from a new one, keeping only the
+    /// changes to hot-reloadable params.
+    pub fn reload(&self, new: &Config) -> Reload {
+        let (applied, ignored) = self
+            .diff(new)
+            .into_iter()
+            .partition(|change| is_hot_reloadable(&change.path));
+
+        let mut config = self.clone();
+        config.connections.inbound_limit = new.connections.inbound_limit;
+        config.connections.outbound_limit = new.connections.outbound_limit;
+        config.jsonrpc.enabled = new.jsonrpc.enabled;
+        config.log.level = new.log.level;
+
+        Reload {
+            config,
+            applied,
+            ignored,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_reload() {
+        let running = Config::default();
+        let mut new = Config::default();
+        new.connections.inbound_limit = 3;
+        new.log.level = LevelFilter::Debug;
+        new.storage.db_path = PathBuf::from("other");
+
+        let reload = running.reload(&new);
+
+        assert_eq!(reload.config.connections.inbound_limit, 3);
+        assert_eq!(reload.config.log.level, LevelFilter::Debug);
+        assert_eq!(reload.config.storage.db_path, running.storage.db_path);
+        let applied: Vec<&str> = reload.applied.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(applied, vec!["connections.inbound_limit", "log.level"]);
+        let ignored: Vec<&str> = reload.ignored.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(ignored, vec!["storage.db_path"]);
+    }
+
+    #[test]
+    fn test_runtime_tunable_params_are_hot_reloadable() {
+        for path in RUNTIME_TUNABLE.iter() {
+            assert!(is_hot_reloadable(path), "{} is not hot-reloadable", path);
+        }
+    }
+
+    #[test]
+    fn test_hot_reloadable_params_exist() {
+        let paths: Vec<String> = Config::default()
+            .diff(&{
+                let mut config = Config::default();
+                config.connections.inbound_limit += 1;
+                config.connections.outbound_limit += 1;
+                config.jsonrpc.enabled = !config.jsonrpc.enabled;
+                config.log.level = LevelFilter::Off;
+                config
+            })
+            .into_iter()
+            .map(|change| change.path)
+            .collect();
+
+        assert_eq!(paths, HOT_RELOADABLE.to_vec());
+    }
+}
@@ -0,0 +1,111 @@
+//! # Subsystems
+//!
+//! Optional parts of the node, each one configured in its own section
+//! with an `enabled` param, e.g.: `[mining] enabled = true`.
+use super::Config;
+use std::fmt;
+
+/// An optional subsystem of the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    /// JSON-RPC server
+    JsonRpc,
+    /// REST server
+    Rest,
+    /// Mining
+    Mining,
+    /// Wallet
+    Wallet,
+    /// Metrics
+    Metrics,
+    /// Bridge
+    Bridge,
+}
+
+impl Subsystem {
+    /// Every subsystem
+    pub const ALL: [Subsystem; 6] = [
+        Subsystem::JsonRpc,
+        Subsystem::Rest,
+        Subsystem::Mining,
+        Subsystem::Wallet,
+        Subsystem::Metrics,
+        Subsystem::Bridge,
+    ];
+
+    /// Name of the section configuring the subsystem
+    pub fn section(self) -> &'static str {
+        match self {
+            Subsystem::JsonRpc => "jsonrpc",
+            Subsystem::Rest => "rest",
+            Subsystem::Mining => "mining",
+            Subsystem::Wallet => "wallet",
+            Subsystem::Metrics => "metrics",
+            Subsystem::Bridge => "bridge",
+        }
+    }
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.section())
+    }
+}
+
+impl Config {
+    /// Whether the given subsystem is enabled
+    pub fn is_enabled(&self, subsystem: Subsystem) -> bool {
+        match subsystem {
+            Subsystem::JsonRpc => self.jsonrpc.enabled,
+            Subsystem::Rest => self.rest.enabled,
+            Subsystem::Mining => self.mining.enabled,
+            Subsystem::Wallet => self.wallet.enabled,
+            Subsystem::Metrics => self.metrics.enabled,
+            Subsystem::Bridge => self.bridge.enabled,
+        }
+    }
+
+    /// The subsystems enabled in this configuration
+    pub fn enabled_subsystems(&self) -> Vec<Subsystem> {
+        Subsystem::ALL
+            .iter()
+            .cloned()
+            .filter(|subsystem| self.is_enabled(*subsystem))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled_subsystems() {
+        let mut config = Config::default();
+
+        assert_eq!(config.enabled_subsystems(), vec![Subsystem::JsonRpc]);
+
+        config.jsonrpc.enabled = false;
+        config.mining.enabled = true;
+        config.bridge.enabled = true;
+
+        assert_eq!(
+            config.enabled_subsystems(),
+            vec![Subsystem::Mining, Subsystem::Bridge]
+        );
+    }
+
+    #[test]
+    fn test_every_section_has_enabled_param() {
+        let schema = crate::schema::json_schema();
+
+        for subsystem in &Subsystem::ALL {
+            assert_eq!(
+                schema["properties"][subsystem.section()]["properties"]["enabled"]["type"],
+                "boolean",
+                "Missing `{}.enabled`",
+                subsystem
+            );
+        }
+    }
+}
@@ -1,13 +1,24 @@
 //! # Default per-environment values
 //!
 //! This module contains per-environment default values for the Witnet
-//! protocol params.
+//! protocol params. Every built-in environment has its own struct
+//! implementing `Defaults`; adding a new testnet only requires a new
+//! struct and an arm in `for_environment`. Custom environments take
+//! all their environment-specific values from the configuration, see
+//! `Custom`.
+use log::LevelFilter;
 use std::collections::HashSet;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::time::Duration;
 
-use witnet_data_structures::chain::Hash;
+use crate::config::{
+    ArchiveBackend, CompactionStyle, Compression, MessageCompression, StorageBackend,
+};
+use crate::dirs;
+use witnet_data_structures::builders::MAGIC;
+use witnet_data_structures::chain::{Checkpoint, Environment, Hash};
+use witnet_util::net::IpNetwork;
 
 // When changing the defaults, remember to update the documentation!
 // https://github.com/witnet/witnet-rust/blob/master/docs/configuration/toml-file.md
@@ -20,6 +31,9 @@ pub trait Defaults {
     /// Default server addr
     fn connections_server_addr(&self) -> SocketAddr;
 
+    /// Magic number identifying the network in protocol messages
+    fn connections_magic_number(&self) -> u16;
+
     /// Default inbound limit for connections: `128`
     fn connections_inbound_limit(&self) -> u16 {
         128
@@ -30,14 +44,166 @@ pub trait Defaults {
         8
     }
 
-    /// Default known peers: none
-    fn connections_known_peers(&self) -> HashSet<SocketAddr> {
+    /// Default maximum number of inbound connections from the same IP
+    /// address: `4`
+    fn connections_max_connections_per_ip(&self) -> u16 {
+        4
+    }
+
+    /// Default maximum number of inbound connections from the same
+    /// subnet: `16`
+    fn connections_max_connections_per_subnet(&self) -> u16 {
+        16
+    }
+
+    /// Bootstrap peers of the network, which are always known
+    fn connections_known_peers(&self) -> HashSet<SocketAddr>;
+
+    /// DNS seeds of the network, resolved to find bootstrap peers
+    fn connections_dns_seeds(&self) -> HashSet<String>;
+
+    /// Default whitelist: none, every peer is allowed
+    fn connections_whitelist(&self) -> HashSet<IpNetwork> {
+        HashSet::new()
+    }
+
+    /// Default blacklist: none
+    fn connections_blacklist(&self) -> HashSet<IpNetwork> {
+        HashSet::new()
+    }
+
+    /// Default priority peers: none
+    fn connections_priority_peers(&self) -> HashSet<IpNetwork> {
         HashSet::new()
     }
 
-    /// Default path for the database
+    /// Default period for resolving the DNS seeds: 1 hour
+    fn connections_dns_seeds_period(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    /// Default storage backend: `rocksdb`
+    fn storage_backend(&self) -> StorageBackend {
+        StorageBackend::RocksDB
+    }
+
+    /// Default path for the database: `db` inside the data directory
+    /// of the environment, e.g.: `.witnet/testnet-1/db`, relative to
+    /// the working directory, or to `%APPDATA%\witnet` on Windows
     fn storage_db_path(&self) -> PathBuf;
 
+    /// Default maximum size of the database: `0` (no limit)
+    fn storage_max_db_size(&self) -> u64 {
+        0
+    }
+
+    /// Default size of the cache of recently read values: `8MiB`
+    fn storage_cache_size(&self) -> u64 {
+        8 * 1024 * 1024
+    }
+
+    /// The database is written by default
+    fn storage_read_only(&self) -> bool {
+        false
+    }
+
+    /// Writes are not synced to disk one by one by default, only at
+    /// the end of every epoch
+    fn storage_sync_writes(&self) -> bool {
+        false
+    }
+
+    /// Default size of the RocksDB block cache: `8MiB`
+    fn storage_rocksdb_block_cache_size(&self) -> u64 {
+        8 * 1024 * 1024
+    }
+
+    /// Default size of the RocksDB write buffer: `64MiB`
+    fn storage_rocksdb_write_buffer_size(&self) -> u64 {
+        64 * 1024 * 1024
+    }
+
+    /// Default maximum number of files opened by RocksDB: `-1` (no
+    /// limit)
+    fn storage_rocksdb_max_open_files(&self) -> i32 {
+        -1
+    }
+
+    /// The RocksDB write-ahead log is enabled by default
+    fn storage_rocksdb_wal(&self) -> bool {
+        true
+    }
+
+    /// Default RocksDB compression algorithm: `snappy`
+    fn storage_rocksdb_compression(&self) -> Compression {
+        Compression::Snappy
+    }
+
+    /// Default RocksDB compaction style: `level`
+    fn storage_rocksdb_compaction_style(&self) -> CompactionStyle {
+        CompactionStyle::Level
+    }
+
+    /// Pruning of old blocks is disabled by default
+    fn storage_pruning_enabled(&self) -> bool {
+        false
+    }
+
+    /// Default number of epochs whose blocks are kept when pruning:
+    /// `10_000` (about 10 days)
+    fn storage_pruning_keep_blocks(&self) -> u32 {
+        10_000
+    }
+
+    /// Scheduled backups are disabled by default
+    fn storage_backup_enabled(&self) -> bool {
+        false
+    }
+
+    /// Default period of the backups: 1 day
+    fn storage_backup_interval(&self) -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
+    /// Default directory for the backups: next to the database, with
+    /// the `-backups` suffix
+    fn storage_backup_destination(&self) -> PathBuf {
+        let mut path = self.storage_db_path().into_os_string();
+        path.push("-backups");
+
+        PathBuf::from(path)
+    }
+
+    /// Default number of backups to keep: `7`
+    fn storage_backup_retention(&self) -> u32 {
+        7
+    }
+
+    /// Offloading of old blocks to an archive is disabled by default
+    fn storage_archive_enabled(&self) -> bool {
+        false
+    }
+
+    /// Default archive backend: `directory`
+    fn storage_archive_backend(&self) -> ArchiveBackend {
+        ArchiveBackend::Directory
+    }
+
+    /// Default directory for the archive: next to the database, with
+    /// the `-archive` suffix
+    fn storage_archive_path(&self) -> PathBuf {
+        let mut path = self.storage_db_path().into_os_string();
+        path.push("-archive");
+
+        PathBuf::from(path)
+    }
+
+    /// Default number of epochs whose blocks are kept in the database
+    /// when archiving: `100_000` (about 100 days)
+    fn storage_archive_keep_blocks(&self) -> u32 {
+        100_000
+    }
+
     /// Default period for bootstrap peers
     fn connections_bootstrap_peers_period(&self) -> Duration {
         Duration::from_secs(5)
@@ -48,16 +214,160 @@ pub trait Defaults {
         Duration::from_secs(30)
     }
 
+    /// Default time after which the stored peers expire: 1 day
+    fn connections_storage_peers_ttl(&self) -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
     /// Default period for discovering peers
     fn connections_discovery_peers_period(&self) -> Duration {
         Duration::from_secs(30)
     }
 
+    /// Default period for the feeler connections: 2 minutes
+    fn connections_feeler_peers_period(&self) -> Duration {
+        Duration::from_secs(2 * 60)
+    }
+
+    /// Default maximum upload bandwidth of all the connections: no
+    /// limit
+    fn connections_max_upload_kbps(&self) -> u32 {
+        0
+    }
+
+    /// Default maximum download bandwidth of all the connections: no
+    /// limit
+    fn connections_max_download_kbps(&self) -> u32 {
+        0
+    }
+
+    /// Default maximum upload bandwidth of each connection: no limit
+    fn connections_max_peer_upload_kbps(&self) -> u32 {
+        0
+    }
+
+    /// Default maximum download bandwidth of each connection: no
+    /// limit
+    fn connections_max_peer_download_kbps(&self) -> u32 {
+        0
+    }
+
+    /// Default maximum rate of `Inv` messages from a peer: 600 per
+    /// minute
+    fn connections_rate_limits_inv(&self) -> u32 {
+        600
+    }
+
+    /// Default maximum rate of `GetData` messages from a peer: 600
+    /// per minute
+    fn connections_rate_limits_get_data(&self) -> u32 {
+        600
+    }
+
+    /// Default maximum rate of `GetPeers` and `Peers` messages from a
+    /// peer: 10 per minute
+    fn connections_rate_limits_peers(&self) -> u32 {
+        10
+    }
+
+    /// Default maximum number of outbound connections with peers of
+    /// the same network group: `2`
+    fn connections_diversity_max_outbound_per_group(&self) -> u16 {
+        2
+    }
+
+    /// Default maximum size of any message received from a peer:
+    /// 8 MiB
+    fn connections_limits_max_message_size(&self) -> u64 {
+        8 * 1024 * 1024
+    }
+
+    /// Default maximum size of `Block` messages: 2 MiB
+    fn connections_limits_max_block_size(&self) -> u64 {
+        2 * 1024 * 1024
+    }
+
+    /// Default maximum size of `Headers` messages: 1 MiB
+    fn connections_limits_max_headers_size(&self) -> u64 {
+        1024 * 1024
+    }
+
+    /// Default maximum size of `Inv` and `GetData` messages: 1 MiB
+    fn connections_limits_max_inventory_size(&self) -> u64 {
+        1024 * 1024
+    }
+
+    /// Default maximum size of `Peers` messages: 64 KiB
+    fn connections_limits_max_peers_size(&self) -> u64 {
+        64 * 1024
+    }
+
+    /// Default time a peer has to answer a `GetPeers` message: 30
+    /// seconds
+    fn connections_limits_get_peers_timeout(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    /// Default time a peer has to answer a `GetHeaders` message: 60
+    /// seconds
+    fn connections_limits_get_headers_timeout(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    /// Default relay of the blocks that the peers send without the
+    /// node requesting them: enabled
+    fn connections_relay_unsolicited(&self) -> bool {
+        true
+    }
+
+    /// Default relay of the blocks which have not been validated
+    /// through their headers: enabled
+    fn connections_relay_unvalidated(&self) -> bool {
+        true
+    }
+
     /// Default handshake timeout
     fn connections_handshake_timeout(&self) -> Duration {
         Duration::from_secs(5)
     }
 
+    /// Default time without receiving messages from a peer after
+    /// which it is pinged: 1 minute
+    fn connections_ping_period(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    /// Default time to wait for the answer to a ping
+    fn connections_ping_timeout(&self) -> Duration {
+        Duration::from_secs(20)
+    }
+
+    /// Default misbehavior score at which a peer gets banned: `100`
+    fn connections_ban_threshold(&self) -> u32 {
+        100
+    }
+
+    /// Default time during which a banned peer cannot connect: 1 day
+    fn connections_ban_duration(&self) -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
+    /// Port forwarding is disabled by default
+    fn connections_upnp(&self) -> bool {
+        false
+    }
+
+    /// Local peer discovery is disabled by default
+    fn connections_mdns(&self) -> bool {
+        false
+    }
+
+    /// The messages exchanged with the peers are not compressed by
+    /// default
+    fn connections_compression(&self) -> MessageCompression {
+        MessageCompression::None
+    }
+
     /// Timestamp at the start of epoch 0
     fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64;
 
@@ -91,6 +401,85 @@ pub trait Defaults {
 
     /// Default JSON-RPC server addr
     fn jsonrpc_server_address(&self) -> SocketAddr;
+
+    /// Default maximum number of JSON-RPC requests per minute from
+    /// each IP address
+    fn jsonrpc_max_requests_per_minute(&self) -> u32 {
+        600
+    }
+
+    /// Default maximum number of JSON-RPC requests processed at once
+    fn jsonrpc_max_requests_in_flight(&self) -> u32 {
+        100
+    }
+
+    /// REST server disabled by default
+    fn rest_enabled(&self) -> bool {
+        false
+    }
+
+    /// Default REST server addr: the port following the JSON-RPC one
+    fn rest_server_address(&self) -> SocketAddr {
+        let jsonrpc_server_address = self.jsonrpc_server_address();
+        SocketAddr::new(
+            jsonrpc_server_address.ip(),
+            jsonrpc_server_address.port().saturating_add(1),
+        )
+    }
+
+    /// Mining disabled by default
+    fn mining_enabled(&self) -> bool {
+        false
+    }
+
+    /// Wallet disabled by default
+    fn wallet_enabled(&self) -> bool {
+        false
+    }
+
+    /// Metrics disabled by default
+    fn metrics_enabled(&self) -> bool {
+        false
+    }
+
+    /// Bridge disabled by default
+    fn bridge_enabled(&self) -> bool {
+        false
+    }
+
+    /// Default path for the master key of the node: `master.key`
+    /// inside the database directory
+    fn identity_master_key_path(&self) -> PathBuf {
+        self.storage_db_path().join("master.key")
+    }
+
+    /// Generate a new master key if missing by default
+    fn identity_create_if_missing(&self) -> bool {
+        true
+    }
+
+    /// Default maximum level of log messages: `info`
+    fn log_level(&self) -> LevelFilter {
+        LevelFilter::Info
+    }
+
+    /// Download the block headers first by default
+    fn sync_headers_first(&self) -> bool {
+        true
+    }
+
+    /// Blocks hardcoded as part of the chain of the network
+    fn sync_checkpoints(&self) -> Vec<Checkpoint>;
+
+    /// Default maximum number of blocks in flight: `1024`
+    fn sync_download_window(&self) -> u32 {
+        1024
+    }
+
+    /// Default time a peer has to send the requested blocks: 30 seconds
+    fn sync_download_timeout(&self) -> Duration {
+        Duration::from_secs(30)
+    }
 }
 
 /// Struct that will implement all the mainnet defaults
@@ -99,17 +488,64 @@ pub struct Mainnet;
 /// Struct that will implement all the testnet-1 defaults
 pub struct Testnet1;
 
+/// Struct that will implement the defaults of a custom environment:
+/// the environment-specific values have no default, they must be
+/// given in the configuration and are stored here (except the
+/// database path, which defaults to the data directory of the
+/// environment), while the rest of params use the generic defaults.
+pub struct Custom {
+    /// Server addr
+    pub connections_server_addr: SocketAddr,
+    /// Magic number
+    pub connections_magic_number: u16,
+    /// Path for the database
+    pub storage_db_path: PathBuf,
+    /// Timestamp at the start of epoch 0
+    pub consensus_constants_checkpoint_zero_timestamp: i64,
+    /// JSON-RPC server addr
+    pub jsonrpc_server_address: SocketAddr,
+}
+
+/// Default database path of an environment, e.g.:
+/// `.witnet/testnet-1/db`
+pub fn environment_db_path(environment: &Environment) -> PathBuf {
+    dirs::environment_data_path(environment.name()).join(dirs::DB_DIRNAME)
+}
+
+/// Defaults of a built-in environment, or `None` for custom
+/// environments, whose defaults depend on the configuration.
+pub fn for_environment(environment: &Environment) -> Option<Box<dyn Defaults>> {
+    match environment {
+        Environment::Mainnet => Some(Box::new(Mainnet)),
+        Environment::Testnet1 => Some(Box::new(Testnet1)),
+        Environment::Custom(_) => None,
+    }
+}
+
 impl Defaults for Mainnet {
     fn connections_server_addr(&self) -> SocketAddr {
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 11337)
     }
 
+    fn connections_magic_number(&self) -> u16 {
+        0x5749
+    }
+
+    fn connections_known_peers(&self) -> HashSet<SocketAddr> {
+        // Mainnet has not been launched yet, so it has no bootstrap nodes
+        HashSet::new()
+    }
+
+    fn connections_dns_seeds(&self) -> HashSet<String> {
+        HashSet::new()
+    }
+
     fn jsonrpc_server_address(&self) -> SocketAddr {
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 11338)
     }
 
     fn storage_db_path(&self) -> PathBuf {
-        PathBuf::from(".witnet-rust-mainnet")
+        environment_db_path(&Environment::Mainnet)
     }
 
     fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64 {
@@ -117,6 +553,11 @@ impl Defaults for Mainnet {
         // `EpochZeroInTheFuture`
         19_999_999_999_999
     }
+
+    fn sync_checkpoints(&self) -> Vec<Checkpoint> {
+        // There is no mainnet chain yet
+        vec![]
+    }
 }
 
 impl Defaults for Testnet1 {
@@ -124,12 +565,25 @@ impl Defaults for Testnet1 {
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 21337)
     }
 
+    fn connections_magic_number(&self) -> u16 {
+        MAGIC
+    }
+
+    fn connections_known_peers(&self) -> HashSet<SocketAddr> {
+        // Testnet-1 nodes are still started with their own `known_peers`
+        HashSet::new()
+    }
+
+    fn connections_dns_seeds(&self) -> HashSet<String> {
+        HashSet::new()
+    }
+
     fn jsonrpc_server_address(&self) -> SocketAddr {
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 21338)
     }
 
     fn storage_db_path(&self) -> PathBuf {
-        PathBuf::from(".witnet-rust-testnet-1")
+        environment_db_path(&Environment::Testnet1)
     }
 
     fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64 {
@@ -137,4 +591,45 @@ impl Defaults for Testnet1 {
         // `EpochZeroInTheFuture`
         9_999_999_999_999
     }
+
+    fn sync_checkpoints(&self) -> Vec<Checkpoint> {
+        // The chain of testnet-1 has no history worth protecting yet
+        vec![]
+    }
+}
+
+impl Defaults for Custom {
+    fn connections_server_addr(&self) -> SocketAddr {
+        self.connections_server_addr
+    }
+
+    fn connections_magic_number(&self) -> u16 {
+        self.connections_magic_number
+    }
+
+    // The bootstrap peers and checkpoints of custom environments are
+    // the ones given in the configuration
+    fn connections_known_peers(&self) -> HashSet<SocketAddr> {
+        HashSet::new()
+    }
+
+    fn connections_dns_seeds(&self) -> HashSet<String> {
+        HashSet::new()
+    }
+
+    fn sync_checkpoints(&self) -> Vec<Checkpoint> {
+        vec![]
+    }
+
+    fn jsonrpc_server_address(&self) -> SocketAddr {
+        self.jsonrpc_server_address
+    }
+
+    fn storage_db_path(&self) -> PathBuf {
+        self.storage_db_path.clone()
+    }
+
+    fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64 {
+        self.consensus_constants_checkpoint_zero_timestamp
+    }
 }
@@ -0,0 +1,429 @@
+//! # Configuration file discovery
+//!
+//! This module resolves which configuration file should be loaded by
+//! searching a well-defined list of locations, in order:
+//!
+//! 1. The path given in the command line (`--config`), which is used
+//!    even if it does not exist so the error can be reported.
+//! 2. The path in the `WITNET_CONFIG` environment variable.
+//! 3. `$XDG_CONFIG_HOME/witnet/witnet.toml`, or
+//!    `$HOME/.config/witnet/witnet.toml` if `XDG_CONFIG_HOME` is not
+//!    set. On Windows, `%APPDATA%\witnet\witnet.toml`.
+//! 4. `/etc/witnet/witnet.toml`. On Windows,
+//!    `%PROGRAMDATA%\witnet\witnet.toml`.
+//! 5. `witnet.toml` in the current working directory.
+//!
+//! The first file that exists is selected. Every location that was
+//! checked is recorded in the search trace, so it can be logged.
+//!
+//! It also resolves the directory where the node keeps its data by
+//! default: the working directory on Unix, and `%APPDATA%\witnet` on
+//! Windows, where the working directory is usually not writable.
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Default configuration filename
+pub const CONFIG_FILENAME: &str = "witnet.toml";
+
+/// Environment variable containing the path to the configuration file
+pub const CONFIG_ENV_VAR: &str = "WITNET_CONFIG";
+
+/// Name of the directory containing the configuration file inside the
+/// user and system configuration directories
+pub const CONFIG_DIRNAME: &str = "witnet";
+
+/// Name of the directory containing the data of every environment,
+/// relative to the working directory
+pub const DATA_DIRNAME: &str = ".witnet";
+
+/// Name of the database directory inside the data directory of an
+/// environment
+pub const DB_DIRNAME: &str = "db";
+
+/// Operating system families with different directory conventions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Platform {
+    /// Linux, macOS and other Unix-like systems
+    Unix,
+    /// Windows
+    Windows,
+}
+
+impl Platform {
+    /// Platform the node is running on
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Unix
+        }
+    }
+}
+
+/// Locations where the configuration file is searched for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Location {
+    /// Path given in the command line
+    CommandLine,
+    /// Path given in the `WITNET_CONFIG` environment variable
+    EnvVar,
+    /// User configuration directory
+    UserConfigDir,
+    /// System-wide configuration directory
+    SystemConfigDir,
+    /// Current working directory
+    WorkingDir,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Location::CommandLine => "command line",
+            Location::EnvVar => CONFIG_ENV_VAR,
+            Location::UserConfigDir => "user config dir",
+            Location::SystemConfigDir => "system config dir",
+            Location::WorkingDir => "working dir",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A path checked during the search
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// Where the path comes from
+    pub location: Location,
+    /// Path to the configuration file
+    pub path: PathBuf,
+    /// Whether the file exists
+    pub exists: bool,
+}
+
+/// Result of searching for the configuration file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    /// Path of the selected configuration file, if any
+    pub found: Option<PathBuf>,
+    /// Every path checked, in order
+    pub trace: Vec<Candidate>,
+}
+
+impl Search {
+    /// Path of the selected configuration file or, if none was found,
+    /// the default one in the working directory.
+    pub fn path(&self) -> PathBuf {
+        self.found
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(CONFIG_FILENAME))
+    }
+}
+
+/// Search for the configuration file, giving priority to the path
+/// given in the command line, if any.
+pub fn find_config_file(command_line: Option<&Path>) -> Search {
+    search(
+        candidates(command_line, Platform::current(), |name| env::var_os(name)),
+        |path| path.is_file(),
+    )
+}
+
+/// Directory where the node keeps the data of an environment by
+/// default, e.g.: `.witnet/testnet-1`, so the databases of different
+/// environments are never mixed.
+pub fn environment_data_path(environment: &str) -> PathBuf {
+    data_path(environment, Platform::current(), |name| env::var_os(name))
+}
+
+/// Directory where older releases kept the data of an environment by
+/// default, e.g.: `.witnet-rust-testnet-1`
+pub fn legacy_data_path(environment: &str) -> PathBuf {
+    legacy_path(environment, Platform::current(), |name| env::var_os(name))
+}
+
+/// Default directory for the data of `environment` in `platform`,
+/// using `getenv` to read environment variables. Relative to the
+/// working directory unless the platform has a dedicated data
+/// directory.
+fn data_path<F>(environment: &str, platform: Platform, getenv: F) -> PathBuf
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    match platform_data_dir(platform, getenv) {
+        Some(dir) => dir.join(environment),
+        None => PathBuf::from(DATA_DIRNAME).join(environment),
+    }
+}
+
+/// Directory used by older releases for the data of `environment` in
+/// `platform`
+fn legacy_path<F>(environment: &str, platform: Platform, getenv: F) -> PathBuf
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let name = format!(".witnet-rust-{}", environment);
+    match platform_data_dir(platform, getenv) {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Dedicated data directory of the platform, if any
+fn platform_data_dir<F>(platform: Platform, getenv: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    match platform {
+        Platform::Unix => None,
+        Platform::Windows => getenv("APPDATA")
+            .filter(|value| !value.is_empty())
+            .map(|app_data| PathBuf::from(app_data).join(CONFIG_DIRNAME)),
+    }
+}
+
+/// Locations to search, in order, in `platform`, using `getenv` to
+/// read environment variables.
+fn candidates<F>(
+    command_line: Option<&Path>,
+    platform: Platform,
+    getenv: F,
+) -> Vec<(Location, PathBuf)>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let non_empty = |name: &str| getenv(name).filter(|value| !value.is_empty());
+    let mut candidates = vec![];
+
+    if let Some(path) = command_line {
+        candidates.push((Location::CommandLine, path.to_path_buf()));
+    }
+    if let Some(path) = non_empty(CONFIG_ENV_VAR) {
+        candidates.push((Location::EnvVar, PathBuf::from(path)));
+    }
+    let (user_config_dir, system_config_dir) = match platform {
+        Platform::Unix => (
+            non_empty("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| non_empty("HOME").map(|home| PathBuf::from(home).join(".config"))),
+            Some(PathBuf::from("/etc")),
+        ),
+        Platform::Windows => (
+            non_empty("APPDATA").map(PathBuf::from),
+            non_empty("PROGRAMDATA").map(PathBuf::from),
+        ),
+    };
+    if let Some(dir) = user_config_dir {
+        candidates.push((
+            Location::UserConfigDir,
+            dir.join(CONFIG_DIRNAME).join(CONFIG_FILENAME),
+        ));
+    }
+    if let Some(dir) = system_config_dir {
+        candidates.push((
+            Location::SystemConfigDir,
+            dir.join(CONFIG_DIRNAME).join(CONFIG_FILENAME),
+        ));
+    }
+    candidates.push((Location::WorkingDir, PathBuf::from(CONFIG_FILENAME)));
+
+    candidates
+}
+
+/// Check the candidates in order until one exists. A path given in the
+/// command line is always selected.
+fn search<F>(candidates: Vec<(Location, PathBuf)>, exists: F) -> Search
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut trace = vec![];
+    let mut found = None;
+
+    for (location, path) in candidates {
+        let path_exists = exists(&path);
+        trace.push(Candidate {
+            location,
+            path: path.clone(),
+            exists: path_exists,
+        });
+        if path_exists || location == Location::CommandLine {
+            found = Some(path);
+            break;
+        }
+    }
+
+    Search { found, trace }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_with(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_candidates_order() {
+        let candidates = candidates(
+            Some(Path::new("cli.toml")),
+            Platform::Unix,
+            env_with(&[("WITNET_CONFIG", "env.toml"), ("HOME", "/home/witnet")]),
+        );
+        let locations: Vec<Location> = candidates.iter().map(|(l, _)| *l).collect();
+
+        assert_eq!(
+            locations,
+            vec![
+                Location::CommandLine,
+                Location::EnvVar,
+                Location::UserConfigDir,
+                Location::SystemConfigDir,
+                Location::WorkingDir,
+            ]
+        );
+        assert_eq!(
+            candidates[2].1,
+            PathBuf::from("/home/witnet/.config/witnet/witnet.toml")
+        );
+    }
+
+    #[test]
+    fn test_xdg_config_home() {
+        let candidates = candidates(
+            None,
+            Platform::Unix,
+            env_with(&[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/witnet")]),
+        );
+
+        assert_eq!(
+            candidates[0],
+            (
+                Location::UserConfigDir,
+                PathBuf::from("/xdg/witnet/witnet.toml")
+            )
+        );
+    }
+
+    #[test]
+    fn test_search_first_existing() {
+        let candidates = candidates(
+            None,
+            Platform::Unix,
+            env_with(&[("WITNET_CONFIG", "env.toml")]),
+        );
+        let result = search(candidates, |path| {
+            path == Path::new("/etc/witnet/witnet.toml")
+        });
+
+        assert_eq!(result.found, Some(PathBuf::from("/etc/witnet/witnet.toml")));
+        assert_eq!(result.trace.len(), 2);
+        assert!(!result.trace[0].exists);
+    }
+
+    #[test]
+    fn test_search_command_line_always_selected() {
+        let candidates = candidates(
+            Some(Path::new("missing.toml")),
+            Platform::Unix,
+            env_with(&[]),
+        );
+        let result = search(candidates, |_| false);
+
+        assert_eq!(result.found, Some(PathBuf::from("missing.toml")));
+        assert_eq!(result.trace.len(), 1);
+    }
+
+    #[test]
+    fn test_search_nothing_found() {
+        let result = search(candidates(None, Platform::Unix, env_with(&[])), |_| false);
+
+        assert_eq!(result.found, None);
+        assert_eq!(result.path(), PathBuf::from(CONFIG_FILENAME));
+    }
+
+    #[test]
+    fn test_windows_candidates() {
+        let candidates = candidates(
+            None,
+            Platform::Windows,
+            env_with(&[
+                ("APPDATA", r"C:\Users\witnet\AppData\Roaming"),
+                ("PROGRAMDATA", r"C:\ProgramData"),
+                ("HOME", "/home/witnet"),
+            ]),
+        );
+
+        assert_eq!(
+            candidates,
+            vec![
+                (
+                    Location::UserConfigDir,
+                    PathBuf::from(r"C:\Users\witnet\AppData\Roaming")
+                        .join("witnet")
+                        .join("witnet.toml")
+                ),
+                (
+                    Location::SystemConfigDir,
+                    PathBuf::from(r"C:\ProgramData")
+                        .join("witnet")
+                        .join("witnet.toml")
+                ),
+                (Location::WorkingDir, PathBuf::from("witnet.toml")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_windows_candidates_without_env() {
+        let candidates = candidates(None, Platform::Windows, env_with(&[]));
+
+        assert_eq!(
+            candidates,
+            vec![(Location::WorkingDir, PathBuf::from("witnet.toml"))]
+        );
+    }
+
+    #[test]
+    fn test_data_path() {
+        let env = env_with(&[("APPDATA", r"C:\Users\witnet\AppData\Roaming")]);
+
+        assert_eq!(
+            data_path("mainnet", Platform::Unix, &env),
+            PathBuf::from(".witnet").join("mainnet")
+        );
+        assert_eq!(
+            data_path("mainnet", Platform::Windows, &env),
+            PathBuf::from(r"C:\Users\witnet\AppData\Roaming")
+                .join("witnet")
+                .join("mainnet")
+        );
+        assert_eq!(
+            data_path("mainnet", Platform::Windows, env_with(&[])),
+            PathBuf::from(".witnet").join("mainnet")
+        );
+    }
+
+    #[test]
+    fn test_legacy_path() {
+        let env = env_with(&[("APPDATA", r"C:\Users\witnet\AppData\Roaming")]);
+
+        assert_eq!(
+            legacy_path("mainnet", Platform::Unix, &env),
+            PathBuf::from(".witnet-rust-mainnet")
+        );
+        assert_eq!(
+            legacy_path("mainnet", Platform::Windows, &env),
+            PathBuf::from(r"C:\Users\witnet\AppData\Roaming")
+                .join("witnet")
+                .join(".witnet-rust-mainnet")
+        );
+    }
+}
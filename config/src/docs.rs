@@ -0,0 +1,281 @@
+//! # Sample configuration
+//!
+//! This module generates an annotated sample `witnet.toml` file from
+//! the [configuration schema](schema), so it always lists every param
+//! with its description and its default value in each environment.
+//!
+//! All params are commented out except `config_version`, so loading
+//! the sample results in the default configuration. Starter
+//! configurations with some params already set can be generated with
+//! `sample_toml_for`.
+use crate::defaults::{Defaults, Mainnet, Testnet1};
+use crate::schema::{self, is_section};
+use serde_json::{Map, Value};
+use std::fmt::Write;
+use witnet_data_structures::chain::Environment;
+
+/// Top-level params that are not written in the sample
+const SKIPPED_PARAMS: [&str; 1] = ["profile"];
+
+/// Params written without commenting them out
+const UNCOMMENTED_PARAMS: [&str; 1] = ["config_version"];
+
+/// Environments whose defaults are listed in the sample, starting
+/// with the given one, whose defaults are used for the commented out
+/// values.
+fn environments(first: &Environment) -> Vec<(&'static str, Box<dyn Defaults>)> {
+    let mut environments = vec![
+        ("testnet-1", Box::new(Testnet1) as Box<dyn Defaults>),
+        ("mainnet", Box::new(Mainnet)),
+    ];
+    if let Some(position) = environments
+        .iter()
+        .position(|(name, _)| *name == first.name())
+    {
+        let environment = environments.remove(position);
+        environments.insert(0, environment);
+    }
+
+    environments
+}
+
+/// Generate an annotated sample configuration file in Toml format.
+pub fn sample_toml() -> String {
+    sample_toml_for(&Environment::default(), &[])
+}
+
+/// Generate an annotated sample configuration file in Toml format
+/// using the defaults of the given environment for the commented out
+/// values, and setting the given params (addressed by their path,
+/// e.g.: `storage.db_path`) to the given values.
+pub fn sample_toml_for(environment: &Environment, values: &[(&str, toml::Value)]) -> String {
+    let schemas: Vec<(&str, Value)> = environments(environment)
+        .into_iter()
+        .map(|(name, defaults)| (name, schema::json_schema_with_defaults(&*defaults)))
+        .collect();
+    let properties: Vec<(&str, &Map<String, Value>)> = schemas
+        .iter()
+        .filter_map(|(name, schema)| Some((*name, schema["properties"].as_object()?)))
+        .collect();
+
+    let mut sample = String::new();
+    sample.push_str("# Witnet node configuration\n");
+    sample.push_str("#\n");
+    sample.push_str("# Every param is commented out and set to its default value, uncomment\n");
+    sample.push_str("# it to change it. Named profiles can be added in `[profile.<name>]`\n");
+    sample.push_str("# sections. You can read more about all the configuration params in\n");
+    sample.push_str("# https://docs.witnet.io/configuration/toml-file/\n");
+
+    // Plain params must be written before any section
+    let (sections, params): (Vec<&String>, Vec<&String>) = properties[0]
+        .1
+        .keys()
+        .filter(|key| !SKIPPED_PARAMS.contains(&key.as_str()))
+        .partition(|key| is_section(&properties[0].1[key.as_str()]));
+
+    for key in params {
+        let param: Vec<(&str, &Value)> = properties
+            .iter()
+            .map(|(name, properties)| (*name, &properties[key.as_str()]))
+            .collect();
+        sample.push('\n');
+        write_param(&mut sample, key, &param, value_of(values, key));
+    }
+
+    for section in sections {
+        let schemas: Vec<(&str, &Value)> = properties
+            .iter()
+            .map(|(name, properties)| (*name, &properties[section.as_str()]))
+            .collect();
+        write_section(&mut sample, section, &schemas, values);
+    }
+
+    sample
+}
+
+/// Write a section with all its params, given the schema of the
+/// section for each environment. Subsections, e.g.:
+/// `[storage.rocksdb]`, are written after the params of the section.
+fn write_section(
+    sample: &mut String,
+    path: &str,
+    schemas: &[(&str, &Value)],
+    values: &[(&str, toml::Value)],
+) {
+    let schema = schemas[0].1;
+    sample.push('\n');
+    write_comment(sample, &schema["description"]);
+    writeln!(sample, "[{}]", path).unwrap();
+
+    let (subsections, keys): (Vec<&String>, Vec<&String>) = schema["properties"]
+        .as_object()
+        .into_iter()
+        .flat_map(Map::keys)
+        .partition(|key| is_section(&schema["properties"][key.as_str()]));
+
+    for key in keys {
+        let param: Vec<(&str, &Value)> = schemas
+            .iter()
+            .map(|(name, schema)| (*name, &schema["properties"][key.as_str()]))
+            .collect();
+        let param_path = format!("{}.{}", path, key);
+        sample.push('\n');
+        write_param(sample, key, &param, value_of(values, &param_path));
+    }
+
+    for key in subsections {
+        let subsection: Vec<(&str, &Value)> = schemas
+            .iter()
+            .map(|(name, schema)| (*name, &schema["properties"][key.as_str()]))
+            .collect();
+        write_section(sample, &format!("{}.{}", path, key), &subsection, values);
+    }
+}
+
+/// Value given for the param with the given path, if any
+fn value_of<'a>(values: &'a [(&str, toml::Value)], path: &str) -> Option<&'a toml::Value> {
+    values
+        .iter()
+        .find(|(param, _)| *param == path)
+        .map(|(_, value)| value)
+}
+
+/// Write a description as a Toml comment
+fn write_comment(sample: &mut String, description: &Value) {
+    if let Some(description) = description.as_str() {
+        writeln!(sample, "# {}", description).unwrap();
+    }
+}
+
+/// Write a param with its description and its default value in every
+/// environment, given the schema of the param for each environment.
+/// The param is commented out unless a value is given.
+fn write_param(
+    sample: &mut String,
+    key: &str,
+    schemas: &[(&str, &Value)],
+    value: Option<&toml::Value>,
+) {
+    write_comment(sample, &schemas[0].1["description"]);
+
+    let defaults: Vec<(&str, Option<toml::Value>)> = schemas
+        .iter()
+        .map(|(name, schema)| (*name, to_toml(&schema["default"])))
+        .collect();
+    let default = match (&defaults[0].1, value) {
+        (Some(default), _) => default,
+        (None, Some(value)) => {
+            writeln!(sample, "{} = {}", key, value).unwrap();
+            return;
+        }
+        (None, None) => {
+            writeln!(sample, "# (no default value)").unwrap();
+            return;
+        }
+    };
+
+    if defaults
+        .iter()
+        .any(|(_, value)| value.as_ref() != Some(default))
+    {
+        let per_environment: Vec<String> = defaults
+            .iter()
+            .filter_map(|(name, value)| Some(format!("{} ({})", value.as_ref()?, name)))
+            .collect();
+        writeln!(sample, "# Default: {}", per_environment.join(", ")).unwrap();
+    }
+
+    if let Some(value) = value {
+        writeln!(sample, "{} = {}", key, value).unwrap();
+    } else if UNCOMMENTED_PARAMS.contains(&key) {
+        writeln!(sample, "{} = {}", key, default).unwrap();
+    } else {
+        writeln!(sample, "# {} = {}", key, default).unwrap();
+    }
+}
+
+/// Convert a default value in the schema to a Toml value
+fn to_toml(value: &Value) -> Option<toml::Value> {
+    if value.is_null() {
+        None
+    } else {
+        toml::Value::try_from(value).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{partial, Config};
+    use crate::loaders;
+
+    #[test]
+    fn test_sample_loads_default_config() {
+        let sample = sample_toml();
+        let config = loaders::toml::from_str_strict(&sample).unwrap();
+
+        assert_eq!(Config::from_partial(&config), Config::default());
+    }
+
+    #[test]
+    fn test_uncommented_sample_loads_default_config() {
+        // Uncomment every `# key = value` line
+        let uncommented: String = sample_toml()
+            .lines()
+            .map(|line| {
+                if line.starts_with("# ") && line.contains(" = ") {
+                    &line[2..]
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let config = loaders::toml::from_str_strict(&uncommented).unwrap();
+
+        assert_eq!(
+            Config::from_partial(&config),
+            Config::from_partial(&partial::Config::default())
+        );
+    }
+
+    #[test]
+    fn test_sample_lists_every_param() {
+        let sample = sample_toml();
+
+        for line in &[
+            "[connections]",
+            "# server_addr = \"127.0.0.1:21337\"",
+            "# Default: \"127.0.0.1:21337\" (testnet-1), \"127.0.0.1:11337\" (mainnet)",
+            "# inbound_limit = 128",
+            "[storage]",
+            "[storage.rocksdb]",
+            "# compression = \"snappy\"",
+            "[consensus_constants]",
+            "[jsonrpc]",
+            "[log]",
+            "# level = \"info\"",
+        ] {
+            assert!(sample.lines().any(|l| l == *line), "Missing line: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_sample_for_environment_with_values() {
+        let sample = sample_toml_for(
+            &Environment::Mainnet,
+            &[
+                ("environment", toml::Value::String("mainnet".to_string())),
+                ("storage.db_path", toml::Value::String("/data".to_string())),
+            ],
+        );
+        let config = loaders::toml::from_str_strict(&sample).unwrap();
+
+        assert_eq!(config.environment, Some(Environment::Mainnet));
+        assert_eq!(config.storage.db_path, Some("/data".into()));
+        assert!(sample
+            .lines()
+            .any(|l| l == "# server_addr = \"127.0.0.1:11337\""));
+    }
+}
@@ -12,6 +12,7 @@
 //! you use in your Rust code to interact with the loaded
 //! configuration.
 #![cfg_attr(test, allow(dead_code, unused_macros, unused_imports))]
+#![recursion_limit = "256"]
 
 #[macro_use]
 extern crate serde_derive;
@@ -20,4 +21,10 @@ extern crate failure;
 
 pub mod config;
 pub mod defaults;
+pub mod dirs;
+pub mod docs;
 pub mod loaders;
+pub mod migrations;
+pub mod schema;
+pub mod secret;
+pub mod validation;
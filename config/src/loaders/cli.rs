@@ -0,0 +1,118 @@
+//! Load configuration overrides given as command-line arguments
+//!
+//! Each override has the form `section.param=value` (or just
+//! `param=value` for top-level params such as `environment`), e.g.:
+//! `--set connections.inbound_limit=999`. Values are parsed with the
+//! same rules used by the [env](loaders::env) loader.
+
+use super::{insert, parse_value, Loader};
+use crate::config::partial::Config;
+use toml::value::{Table, Value};
+use witnet_util::error::WitnetError;
+
+pub use super::{Error, Result};
+
+/// Loader building a configuration out of a list of overrides, see
+/// `from_overrides`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overrides(pub Vec<String>);
+
+impl Loader for Overrides {
+    fn load(&self) -> Result<Config> {
+        from_overrides(&self.0)
+    }
+}
+
+/// Build a partial configuration out of a list of overrides written
+/// as `section.param=value`.
+pub fn from_overrides<I, S>(overrides: I) -> Result<Config>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut table = Table::new();
+
+    for item in overrides {
+        let item = item.as_ref();
+        let (path, value) = parse_override(item)
+            .ok_or_else(|| WitnetError::from(Error::InvalidOverride(item.to_string())))?;
+        insert(&mut table, &path, parse_value(value));
+    }
+
+    Value::Table(table)
+        .try_into()
+        .map_err(|e| WitnetError::from(Error::ParseError(e.into())))
+}
+
+/// Apply a list of overrides on top of an already loaded partial
+/// configuration.
+pub fn apply<I, S>(config: Config, overrides: I) -> Result<Config>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    Ok(config.merge(from_overrides(overrides)?))
+}
+
+/// Split an override into the path of keys and the raw value
+fn parse_override(item: &str) -> Option<(Vec<String>, &str)> {
+    let mut parts = item.splitn(2, '=');
+    let key = parts.next()?.trim();
+    let value = parts.next()?.trim();
+    let path: Vec<String> = key.split('.').map(|k| k.trim().to_string()).collect();
+
+    if path.iter().any(String::is_empty) {
+        None
+    } else {
+        Some((path, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::partial::*;
+    use witnet_data_structures::chain::Environment;
+
+    #[test]
+    fn test_no_overrides() {
+        let config = super::from_overrides(Vec::<String>::new()).unwrap();
+
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let config = super::from_overrides(&[
+            "environment=mainnet",
+            "connections.inbound_limit=999",
+            "jsonrpc.server_address=127.0.0.1:1234",
+        ])
+        .unwrap();
+
+        assert_eq!(config.environment, Some(Environment::Mainnet));
+        assert_eq!(config.connections.inbound_limit, Some(999));
+        assert_eq!(
+            config.jsonrpc.server_address,
+            Some("127.0.0.1:1234".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        assert!(super::from_overrides(&["connections.inbound_limit"]).is_err());
+        assert!(super::from_overrides(&["connections..inbound_limit=1"]).is_err());
+        assert!(super::from_overrides(&["connections.inbound_limit=many"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut loaded = Config::default();
+        loaded.connections.inbound_limit = Some(10);
+        loaded.connections.outbound_limit = Some(4);
+
+        let config = super::apply(loaded, &["connections.inbound_limit=999"]).unwrap();
+
+        assert_eq!(config.connections.inbound_limit, Some(999));
+        assert_eq!(config.connections.outbound_limit, Some(4));
+    }
+}
@@ -0,0 +1,195 @@
+//! Load the configuration from environment variables prefixed with `WITNET_`
+//!
+//! Each variable maps to a configuration param by stripping the
+//! prefix, lowercasing the rest of the name and using a double
+//! underscore (`__`) as the section separator, e.g.:
+//! `WITNET_CONNECTIONS__INBOUND_LIMIT=999` is equivalent to setting
+//! `inbound_limit = 999` inside the `[connections]` section of the
+//! Toml file.
+//!
+//! Values are parsed as Toml values (numbers, booleans, arrays,
+//! quoted strings) and fall back to plain strings when they cannot be
+//! parsed, so `WITNET_ENVIRONMENT=mainnet` works as expected.
+//!
+//! The prefix and the separator can be changed, so several nodes
+//! running on the same host can use different variables, e.g.:
+//! `ORACLE_A__CONNECTIONS__INBOUND_LIMIT=999` with prefix `ORACLE_A__`.
+
+use super::{insert, parse_value, Loader};
+use crate::config::partial::Config;
+use std::env;
+use toml::value::{Table, Value};
+use witnet_util::error::WitnetError;
+
+pub use super::{Error, Result};
+
+/// Prefix that environment variables must have to be considered
+/// configuration params
+pub const PREFIX: &str = "WITNET_";
+
+/// Separator between section and param names in a variable name
+pub const SEPARATOR: &str = "__";
+
+/// Loader reading the environment variables of the current process,
+/// see `from_env`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Env {
+    /// Prefix that environment variables must have to be considered
+    /// configuration params
+    pub prefix: String,
+    /// Separator between section and param names
+    pub separator: String,
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Env::new(PREFIX, SEPARATOR)
+    }
+}
+
+impl Env {
+    /// Loader for the variables starting with `prefix`, using
+    /// `separator` between section and param names
+    pub fn new(prefix: &str, separator: &str) -> Self {
+        Env {
+            prefix: prefix.to_string(),
+            separator: separator.to_string(),
+        }
+    }
+}
+
+impl Loader for Env {
+    fn load(&self) -> Result<Config> {
+        from_vars_with(env::vars(), &self.prefix, &self.separator)
+    }
+}
+
+/// Load configuration from the environment variables of the current
+/// process.
+pub fn from_env() -> Result<Config> {
+    from_vars(env::vars())
+}
+
+/// Load configuration from an iterator of `(name, value)` pairs, as
+/// returned by `std::env::vars`. Pairs whose name does not start with
+/// `PREFIX` are ignored.
+pub fn from_vars<I>(vars: I) -> Result<Config>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    from_vars_with(vars, PREFIX, SEPARATOR)
+}
+
+/// Like `from_vars`, but using the given prefix and separator instead
+/// of `PREFIX` and `SEPARATOR`. The separator cannot be empty.
+pub fn from_vars_with<I>(vars: I, prefix: &str, separator: &str) -> Result<Config>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    if separator.is_empty() {
+        return Err(WitnetError::from(Error::Custom(failure::err_msg(
+            "The separator of environment variables cannot be empty",
+        ))));
+    }
+    let mut table = Table::new();
+
+    for (name, value) in vars {
+        if !name.starts_with(prefix) {
+            continue;
+        }
+        let path: Vec<String> = name[prefix.len()..]
+            .split(separator)
+            .map(str::to_lowercase)
+            .collect();
+        insert(&mut table, &path, parse_value(&value));
+    }
+
+    Value::Table(table)
+        .try_into()
+        .map_err(|e| WitnetError::from(Error::ParseError(e.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::partial::*;
+    use std::path::PathBuf;
+    use witnet_data_structures::chain::Environment;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_load_empty_config() {
+        let config = super::from_vars(vars(&[("PATH", "/usr/bin")])).unwrap();
+
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_load_config_from_vars() {
+        let config = super::from_vars(vars(&[
+            ("WITNET_ENVIRONMENT", "mainnet"),
+            ("WITNET_CONNECTIONS__INBOUND_LIMIT", "999"),
+            ("WITNET_CONNECTIONS__KNOWN_PEERS", "['192.168.1.12:1234']"),
+            ("WITNET_CONNECTIONS__HANDSHAKE_TIMEOUT_SECONDS", "21"),
+            ("WITNET_STORAGE__DB_PATH", "dbfiles"),
+            ("WITNET_JSONRPC__ENABLED", "false"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.environment, Some(Environment::Mainnet));
+        assert_eq!(config.connections.inbound_limit, Some(999));
+        assert_eq!(config.connections.known_peers.len(), 1);
+        assert_eq!(
+            config.connections.handshake_timeout,
+            Some(std::time::Duration::from_secs(21))
+        );
+        assert_eq!(config.storage.db_path, Some(PathBuf::from("dbfiles")));
+        assert_eq!(config.jsonrpc.enabled, Some(false));
+    }
+
+    #[test]
+    fn test_load_wrong_value() {
+        let result = super::from_vars(vars(&[("WITNET_ENVIRONMENT", "wrong")]));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_load_config_with_prefix() {
+        let config = super::from_vars_with(
+            vars(&[
+                ("ORACLE_A__CONNECTIONS__INBOUND_LIMIT", "999"),
+                ("ORACLE_B__CONNECTIONS__INBOUND_LIMIT", "111"),
+                ("WITNET_CONNECTIONS__OUTBOUND_LIMIT", "3"),
+            ]),
+            "ORACLE_A__",
+            "__",
+        )
+        .unwrap();
+
+        assert_eq!(config.connections.inbound_limit, Some(999));
+        assert_eq!(config.connections.outbound_limit, None);
+    }
+
+    #[test]
+    fn test_load_config_with_separator() {
+        let config = super::from_vars_with(
+            vars(&[("NODE.CONNECTIONS.INBOUND_LIMIT", "999")]),
+            "NODE.",
+            ".",
+        )
+        .unwrap();
+
+        assert_eq!(config.connections.inbound_limit, Some(999));
+    }
+
+    #[test]
+    fn test_empty_separator() {
+        assert!(super::from_vars_with(vars(&[]), "NODE_", "").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Load the configuration from a remote document served over HTTP(S)
+//!
+//! The document can be written either in Toml or Json format. The
+//! format is guessed from the `Content-Type` header of the response
+//! or, if it is not conclusive, from the extension of the URL path,
+//! defaulting to Toml.
+//!
+//! This loader is only available when the `http-loader` feature is
+//! enabled. The non-blocking `from_url_async` also requires the
+//! `async-loaders` feature.
+
+use super::{json, toml};
+use crate::config::partial::Config;
+#[cfg(feature = "async-loaders")]
+use futures::{Future, Stream};
+use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE};
+use witnet_util::error::WitnetError;
+
+pub use super::{Error, Result};
+
+/// Formats supported for remote configuration documents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// [Toml format](https://en.wikipedia.org/wiki/TOML)
+    Toml,
+    /// [Json format](https://en.wikipedia.org/wiki/JSON)
+    Json,
+}
+
+/// Fetch the configuration from the given URL. If `auth` is given,
+/// it is sent verbatim as the value of the `Authorization` header,
+/// e.g.: `"Bearer <token>"`.
+pub fn from_url(url: &str, auth: Option<&str>) -> Result<Config> {
+    let client = reqwest::Client::new();
+    let mut request = client.get(url);
+    if let Some(auth) = auth {
+        request = request.header(AUTHORIZATION, auth);
+    }
+
+    let mut response = request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| WitnetError::from(Error::HttpError(e)))?;
+    let content_type = content_type(response.headers());
+    let contents = response
+        .text()
+        .map_err(|e| WitnetError::from(Error::HttpError(e)))?;
+
+    parse(url, content_type, &contents)
+}
+
+/// Fetch the configuration from the given URL without blocking the
+/// current thread (see `from_url`). The returned future must be run
+/// inside a Tokio runtime, such as the one of an Actix system.
+#[cfg(feature = "async-loaders")]
+pub fn from_url_async(
+    url: &str,
+    auth: Option<&str>,
+) -> impl Future<Item = Config, Error = WitnetError<Error>> {
+    let client = reqwest::r#async::Client::new();
+    let mut request = client.get(url);
+    if let Some(auth) = auth {
+        request = request.header(AUTHORIZATION, auth);
+    }
+    let url = url.to_string();
+
+    request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| {
+            let content_type = content_type(response.headers());
+            response
+                .into_body()
+                .concat2()
+                .map(move |body| (content_type, body))
+        })
+        .map_err(|e| WitnetError::from(Error::HttpError(e)))
+        .and_then(move |(content_type, body)| {
+            parse(&url, content_type, &String::from_utf8_lossy(&body))
+        })
+}
+
+/// Value of the `Content-Type` header, if any
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Parse a remote document in the format guessed from its content
+/// type and its URL
+fn parse(url: &str, content_type: Option<String>, contents: &str) -> Result<Config> {
+    match detect_format(url, content_type.as_ref().map(String::as_str)) {
+        Format::Toml => toml::from_str(contents),
+        Format::Json => json::from_str(contents),
+    }
+}
+
+/// Guess the format of a remote document from its content type and
+/// its URL.
+pub fn detect_format(url: &str, content_type: Option<&str>) -> Format {
+    match content_type {
+        Some(content_type) if content_type.contains("json") => Format::Json,
+        Some(content_type) if content_type.contains("toml") => Format::Toml,
+        _ => {
+            let path = url.split(|c| c == '?' || c == '#').next().unwrap_or("");
+            if path.ends_with(".json") {
+                Format::Json
+            } else {
+                Format::Toml
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format_from_content_type() {
+        assert_eq!(
+            detect_format("https://example.com/config", Some("application/json")),
+            Format::Json
+        );
+        assert_eq!(
+            detect_format("https://example.com/config.json", Some("application/toml")),
+            Format::Toml
+        );
+    }
+
+    #[test]
+    fn test_detect_format_from_url() {
+        assert_eq!(
+            detect_format("https://example.com/witnet.json?v=2", Some("text/plain")),
+            Format::Json
+        );
+        assert_eq!(
+            detect_format("https://example.com/witnet.toml", None),
+            Format::Toml
+        );
+        assert_eq!(detect_format("https://example.com/", None), Format::Toml);
+    }
+}
@@ -0,0 +1,230 @@
+//! # Variable interpolation
+//!
+//! String values in configuration files can contain variables that
+//! are replaced when the configuration is loaded, so a single file can
+//! be shared by many machines:
+//!
+//! * `${env:NAME}`: the value of the `NAME` environment variable.
+//! * `${hostname}`: the host name of the machine.
+//!
+//! For example, `db_path = "${env:HOME}/.witnet"` or
+//! `server_addr = "0.0.0.0:${env:PORT}"`. A literal `${` can be
+//! written as `$${`.
+use failure::Fail;
+use std::env;
+use std::fmt;
+
+/// Prefix of variables referencing environment variables
+pub const ENV_PREFIX: &str = "env:";
+
+/// Variable holding the host name of the machine
+pub const HOSTNAME: &str = "hostname";
+
+/// Errors that can happen when interpolating variables
+#[derive(Debug, Clone, PartialEq, Fail)]
+pub enum Error {
+    /// A `${` is not closed by a `}`
+    Unterminated(String),
+    /// The variable is not one of the supported ones
+    UnknownVariable(String),
+    /// The variable is supported but has no value
+    Undefined(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Unterminated(value) => write!(f, "Unterminated variable in `{}`", value),
+            Error::UnknownVariable(name) => write!(
+                f,
+                "Unknown variable `${{{}}}`, expected `${{{}NAME}}` or `${{{}}}`",
+                name, ENV_PREFIX, HOSTNAME
+            ),
+            Error::Undefined(name) => write!(f, "Variable `${{{}}}` is not defined", name),
+        }
+    }
+}
+
+/// Value of a variable on this machine, e.g.: `env:HOME`
+pub fn lookup(name: &str) -> Option<String> {
+    if name.starts_with(ENV_PREFIX) {
+        env::var(&name[ENV_PREFIX.len()..]).ok()
+    } else if name == HOSTNAME {
+        hostname::get_hostname()
+    } else {
+        None
+    }
+}
+
+/// Replace the variables in every string of a Toml document, using
+/// `lookup` to get their values. Returns whether any string changed.
+pub fn interpolate_toml<F>(value: &mut toml::Value, lookup: &F) -> Result<bool, Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut changed = false;
+    match value {
+        toml::Value::String(s) => changed |= replace(s, lookup)?,
+        toml::Value::Array(values) => {
+            for value in values {
+                changed |= interpolate_toml(value, lookup)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for value in table.values_mut() {
+                changed |= interpolate_toml(value, lookup)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(changed)
+}
+
+/// Replace the variables in every string of a Json document, using
+/// `lookup` to get their values. Returns whether any string changed.
+pub fn interpolate_json<F>(value: &mut serde_json::Value, lookup: &F) -> Result<bool, Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut changed = false;
+    match value {
+        serde_json::Value::String(s) => changed |= replace(s, lookup)?,
+        serde_json::Value::Array(values) => {
+            for value in values {
+                changed |= interpolate_json(value, lookup)?;
+            }
+        }
+        serde_json::Value::Object(object) => {
+            for value in object.values_mut() {
+                changed |= interpolate_json(value, lookup)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(changed)
+}
+
+/// Replace the variables in a string in place, returning whether it
+/// changed.
+fn replace<F>(s: &mut String, lookup: &F) -> Result<bool, Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if !s.contains("${") {
+        return Ok(false);
+    }
+    *s = interpolate(s, lookup)?;
+
+    Ok(true)
+}
+
+/// Replace the variables in a string, using `lookup` to get their
+/// values.
+pub fn interpolate<F>(s: &str, lookup: &F) -> Result<String, Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        // `$${` is an escaped `${`
+        if rest[..start].ends_with('$') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        result.push_str(&rest[..start]);
+
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| Error::Unterminated(s.to_string()))?
+            + start;
+        let name = &rest[start + 2..end];
+        if !name.starts_with(ENV_PREFIX) && name != HOSTNAME {
+            return Err(Error::UnknownVariable(name.to_string()));
+        }
+        let value = lookup(name).ok_or_else(|| Error::Undefined(name.to_string()))?;
+        result.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "env:HOME" => Some("/home/witnet".to_string()),
+            "env:PORT" => Some("21337".to_string()),
+            "hostname" => Some("node-1".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(
+            interpolate("${env:HOME}/.witnet-${hostname}", &lookup),
+            Ok("/home/witnet/.witnet-node-1".to_string())
+        );
+        assert_eq!(
+            interpolate("0.0.0.0:${env:PORT}", &lookup),
+            Ok("0.0.0.0:21337".to_string())
+        );
+        assert_eq!(
+            interpolate("no variables", &lookup),
+            Ok("no variables".to_string())
+        );
+        assert_eq!(
+            interpolate("$${env:HOME}", &lookup),
+            Ok("${env:HOME}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_interpolate_errors() {
+        assert_eq!(
+            interpolate("${env:HOME", &lookup),
+            Err(Error::Unterminated("${env:HOME".to_string()))
+        );
+        assert_eq!(
+            interpolate("${home}", &lookup),
+            Err(Error::UnknownVariable("home".to_string()))
+        );
+        assert_eq!(
+            interpolate("${env:UNDEFINED}", &lookup),
+            Err(Error::Undefined("env:UNDEFINED".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_interpolate_toml() {
+        let mut document: toml::Value = r#"
+[connections]
+server_addr = "0.0.0.0:${env:PORT}"
+known_peers = ["${hostname}:1234"]
+inbound_limit = 3
+"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(interpolate_toml(&mut document, &lookup), Ok(true));
+        assert_eq!(
+            document["connections"]["server_addr"].as_str(),
+            Some("0.0.0.0:21337")
+        );
+        assert_eq!(
+            document["connections"]["known_peers"][0].as_str(),
+            Some("node-1:1234")
+        );
+        assert_eq!(interpolate_toml(&mut document, &lookup), Ok(false));
+    }
+}
@@ -0,0 +1,137 @@
+//! Load the configuration from a file or a `String` written in [Json format](https://en.wikipedia.org/wiki/JSON)
+//!
+//! The Json document has the same structure as the Toml one: every
+//! Toml section is a nested object with the same param names.
+//! Variables in string values are replaced and documents written for
+//! older versions of the configuration layout are migrated, as in the
+//! Toml loader.
+
+use super::{interpolation, read_file_contents, Loader};
+use crate::config::partial::Config;
+use crate::migrations;
+use std::path::{Path, PathBuf};
+use witnet_util::error::WitnetError;
+
+pub use super::{Error, Result};
+
+/// Loader reading a Json file, see `from_file`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonFile(pub PathBuf);
+
+impl Loader for JsonFile {
+    fn load(&self) -> Result<Config> {
+        from_file(&self.0)
+    }
+}
+
+/// Load configuration from a file written in Json format.
+pub fn from_file(file: &Path) -> Result<Config> {
+    let mut contents = String::new();
+    read_file_contents(file, &mut contents).map_err(Error::IOError)?;
+    from_str(&contents)
+}
+
+/// Load configuration from a string written in Json format.
+pub fn from_str(contents: &str) -> Result<Config> {
+    let mut document: serde_json::Value =
+        serde_json::from_str(contents).map_err(|e| WitnetError::from(Error::JsonParseError(e)))?;
+    interpolation::interpolate_json(&mut document, &interpolation::lookup)
+        .map_err(Error::InterpolationError)?;
+
+    // Migrations work on Toml documents. Json documents that cannot be
+    // written as Toml (e.g.: containing `null` values) cannot be
+    // migrated, and are loaded as they are
+    if let Ok(mut migrated) = toml::Value::try_from(&document) {
+        let report = migrations::migrate(&mut migrated).map_err(Error::MigrationError)?;
+        if !report.is_empty() {
+            report.log();
+            return migrated
+                .try_into()
+                .map_err(|e| WitnetError::from(Error::ParseError(e.into())));
+        }
+    }
+
+    serde_json::from_value(document).map_err(|e| WitnetError::from(Error::JsonParseError(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::partial::*;
+    use std::path::{Path, PathBuf};
+    use witnet_data_structures::chain::Environment;
+
+    #[test]
+    fn test_load_empty_config() {
+        let config = super::from_str("{}").unwrap();
+
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_load_config_from_file() {
+        crate::loaders::FILE_CONTENTS.with(|cell| {
+            cell.set(
+                r#"
+{
+    "environment": "testnet-1",
+    "connections": { "inbound_limit": 999 }
+}
+"#,
+            )
+        });
+        let filename = Path::new("config.json");
+        let config = super::from_file(&filename).unwrap();
+
+        assert_eq!(config.environment, Some(Environment::Testnet1));
+        assert_eq!(config.connections.inbound_limit, Some(999));
+    }
+
+    #[test]
+    fn test_configure_environment() {
+        let config = super::from_str(r#"{ "environment": "mainnet" }"#).unwrap();
+        let result = super::from_str(r#"{ "environment": "wrong" }"#);
+
+        assert_eq!(config.environment, Some(Environment::Mainnet));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_migrate_old_version() {
+        let config =
+            super::from_str(r#"{ "logging": { "stdout_log_level": "Warning" } }"#).unwrap();
+        let result = super::from_str(r#"{ "config_version": 1000 }"#);
+
+        assert_eq!(config.log.level, Some(log::LevelFilter::Warn));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_configure_sections() {
+        let config = super::from_str(
+            r#"
+{
+    "connections": {
+        "server_addr": "127.0.0.1:1234",
+        "known_peers": ["192.168.1.12:1234"],
+        "handshake_timeout_seconds": 21
+    },
+    "storage": { "db_path": "dbfiles" },
+    "jsonrpc": { "enabled": false }
+}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.connections.server_addrs,
+            Some(vec!["127.0.0.1:1234".parse().unwrap()])
+        );
+        assert_eq!(config.connections.known_peers.len(), 1);
+        assert_eq!(
+            config.connections.handshake_timeout,
+            Some(std::time::Duration::from_secs(21))
+        );
+        assert_eq!(config.storage.db_path, Some(PathBuf::from("dbfiles")));
+        assert_eq!(config.jsonrpc.enabled, Some(false));
+    }
+}
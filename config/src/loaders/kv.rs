@@ -0,0 +1,393 @@
+//! Load the configuration from a Consul or etcd key-value store
+//!
+//! Every key under the configured prefix maps to a configuration
+//! param, using `/` as the section separator, e.g.: the key
+//! `witnet/connections/inbound_limit` under the prefix `witnet` is
+//! equivalent to setting `inbound_limit` inside the `[connections]`
+//! section of the Toml file, while `witnet2/connections/inbound_limit`
+//! is not under that prefix. Values are parsed with the same rules
+//! used by the [env](loaders::env) loader.
+//!
+//! Consul is accessed through its HTTP API (`/v1/kv`) and etcd
+//! through the JSON gateway of its v3 API (`/v3/kv/range`).
+//!
+//! This loader is only available when the `kv-loader` feature is
+//! enabled.
+
+use super::{insert, parse_value, Loader};
+use crate::config::partial::Config;
+use log::{debug, warn};
+use std::thread;
+use std::time::Duration;
+use toml::value::{Table, Value};
+use witnet_util::error::WitnetError;
+
+pub use super::{Error, Result};
+
+/// Key-value stores supported by this loader
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    /// [Consul](https://www.consul.io/api/kv.html)
+    Consul,
+    /// [etcd](https://etcd.io/docs/v3.3/dev-guide/api_grpc_gateway/)
+    Etcd,
+}
+
+/// Location of the configuration inside a key-value store
+#[derive(Debug, Clone, PartialEq)]
+pub struct KvSource {
+    /// Store holding the configuration
+    pub backend: Backend,
+    /// Base URL of the store API, e.g.: `http://127.0.0.1:8500`
+    pub endpoint: String,
+    /// Prefix under which the configuration keys are stored
+    pub prefix: String,
+}
+
+impl KvSource {
+    /// Configuration stored in Consul under the given prefix
+    pub fn consul(endpoint: &str, prefix: &str) -> Self {
+        Self {
+            backend: Backend::Consul,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        }
+    }
+
+    /// Configuration stored in etcd under the given prefix
+    pub fn etcd(endpoint: &str, prefix: &str) -> Self {
+        Self {
+            backend: Backend::Etcd,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        }
+    }
+
+    /// Prefix of the keys holding configuration params, which ends at
+    /// a `/` so that e.g. `witnet2/...` is not read for `witnet`.
+    fn key_prefix(&self) -> String {
+        if self.prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", self.prefix)
+        }
+    }
+
+    /// Fetch all the key-value pairs under the prefix, together with
+    /// the version of those keys. When `wait_index` is given, Consul
+    /// blocks until the keys change or `wait` elapses.
+    fn fetch(
+        &self,
+        wait_index: Option<u64>,
+        wait: Duration,
+    ) -> Result<(Vec<(String, String)>, Option<Version>)> {
+        match self.backend {
+            Backend::Consul => self.fetch_consul(wait_index, wait),
+            Backend::Etcd => self.fetch_etcd(),
+        }
+    }
+
+    fn fetch_consul(
+        &self,
+        wait_index: Option<u64>,
+        wait: Duration,
+    ) -> Result<(Vec<(String, String)>, Option<Version>)> {
+        let mut url = format!("{}/v1/kv/{}?recurse=true", self.endpoint, self.key_prefix());
+        if let Some(index) = wait_index {
+            url.push_str(&format!("&index={}&wait={}s", index, wait.as_secs()));
+        }
+
+        let mut response = reqwest::get(&url).map_err(http_error)?;
+        let index = response
+            .headers()
+            .get("X-Consul-Index")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .map(Version::Index);
+        // Consul answers with 404 when there are no keys under the prefix
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok((vec![], index));
+        }
+        let entries: Vec<ConsulEntry> = response
+            .error_for_status()
+            .and_then(|mut response| response.json())
+            .map_err(http_error)?;
+
+        let mut pairs = vec![];
+        for entry in entries {
+            if let Some(value) = entry.value {
+                pairs.push((entry.key, decode(&value)?));
+            }
+        }
+
+        Ok((pairs, index))
+    }
+
+    fn fetch_etcd(&self) -> Result<(Vec<(String, String)>, Option<Version>)> {
+        let prefix = self.key_prefix();
+        let body = EtcdRangeRequest {
+            key: base64::encode(&prefix),
+            range_end: base64::encode(&prefix_range_end(prefix.as_bytes())),
+        };
+        let response: EtcdRangeResponse = reqwest::Client::new()
+            .post(&format!("{}/v3/kv/range", self.endpoint))
+            .json(&body)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json())
+            .map_err(http_error)?;
+
+        etcd_pairs(response)
+    }
+
+    /// Build a partial configuration from key-value pairs read from
+    /// the store.
+    fn to_config(&self, pairs: Vec<(String, String)>) -> Result<Config> {
+        let mut table = Table::new();
+
+        for (key, value) in pairs {
+            match self.param_path(&key) {
+                Some(path) => insert(&mut table, &path, parse_value(&value)),
+                None => debug!(
+                    "Ignoring key `{}`, which is not under the prefix `{}`",
+                    key, self.prefix
+                ),
+            }
+        }
+
+        Value::Table(table)
+            .try_into()
+            .map_err(|e| WitnetError::from(Error::ParseError(e.into())))
+    }
+
+    /// Path of the param stored in `key`, or `None` when `key` is not
+    /// under the prefix.
+    fn param_path(&self, key: &str) -> Option<Vec<String>> {
+        let path = key.strip_prefix(self.key_prefix().as_str())?;
+
+        Some(
+            path.split('/')
+                .filter(|k| !k.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+}
+
+impl Loader for KvSource {
+    fn load(&self) -> Result<Config> {
+        from_kv(self)
+    }
+}
+
+/// Load configuration from a key-value store.
+pub fn from_kv(source: &KvSource) -> Result<Config> {
+    let (pairs, _) = source.fetch(None, Duration::from_secs(0))?;
+    source.to_config(pairs)
+}
+
+/// Watch the key-value store for changes in a background thread,
+/// calling `on_change` with the new configuration every time the
+/// contents under the prefix change. Consul is watched using blocking
+/// queries while etcd is polled every `period`.
+pub fn watch<F>(source: KvSource, period: Duration, on_change: F) -> thread::JoinHandle<()>
+where
+    F: Fn(Result<Config>) + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut last_version: Option<Version> = None;
+        loop {
+            let wait_index = match last_version {
+                Some(Version::Index(index)) => Some(index),
+                _ => None,
+            };
+            match source.fetch(wait_index, period) {
+                Ok((pairs, version)) => {
+                    if version.is_none() || version != last_version {
+                        debug!("Configuration changed in {:?} store", source.backend);
+                        last_version = version;
+                        on_change(source.to_config(pairs));
+                    }
+                }
+                Err(e) => {
+                    warn!("Error watching {:?} store: {}", source.backend, e);
+                    thread::sleep(period);
+                    continue;
+                }
+            }
+            // Consul blocking queries already wait for changes
+            if source.backend == Backend::Etcd || last_version.is_none() {
+                thread::sleep(period);
+            }
+        }
+    })
+}
+
+/// Version of the keys under the prefix, which changes every time any
+/// of them is created, updated or deleted
+#[derive(Debug, Clone, PartialEq)]
+enum Version {
+    /// `X-Consul-Index` of a recursive query, the index of the last
+    /// change under the prefix
+    Index(u64),
+    /// `mod_revision` of every key under the prefix (etcd). The store
+    /// revision is not used because it changes with any write to the
+    /// store, even outside the prefix.
+    Revisions(Vec<(String, u64)>),
+}
+
+/// Entry returned by the Consul KV API
+#[derive(Deserialize)]
+struct ConsulEntry {
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "Value")]
+    value: Option<String>,
+}
+
+/// Body of a range request to the etcd v3 API
+#[derive(Serialize)]
+struct EtcdRangeRequest {
+    key: String,
+    range_end: String,
+}
+
+/// Response of a range request to the etcd v3 API
+#[derive(Deserialize)]
+struct EtcdRangeResponse {
+    #[serde(default)]
+    kvs: Vec<EtcdKeyValue>,
+}
+
+/// Key-value pair returned by the etcd v3 API, which encodes 64-bit
+/// integers as strings
+#[derive(Deserialize)]
+struct EtcdKeyValue {
+    key: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    mod_revision: String,
+}
+
+/// Decode the pairs of an etcd range response, using the revisions in
+/// which its keys were last modified as the version.
+fn etcd_pairs(response: EtcdRangeResponse) -> Result<(Vec<(String, String)>, Option<Version>)> {
+    let mut pairs = vec![];
+    let mut revisions = vec![];
+    for kv in response.kvs {
+        let key = decode(&kv.key)?;
+        revisions.push((key.clone(), kv.mod_revision.parse().unwrap_or(0)));
+        pairs.push((key, decode(&kv.value)?));
+    }
+    revisions.sort();
+
+    Ok((pairs, Some(Version::Revisions(revisions))))
+}
+
+fn http_error(e: reqwest::Error) -> WitnetError<Error> {
+    WitnetError::from(Error::HttpError(e))
+}
+
+/// Decode a base64-encoded UTF-8 string as returned by both stores
+fn decode(encoded: &str) -> Result<String> {
+    base64::decode(encoded)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| WitnetError::from(Error::InvalidKvEntry(encoded.to_string())))
+}
+
+/// Smallest key that is greater than all the keys starting with
+/// `prefix`, used as the end of an etcd range request.
+fn prefix_range_end(prefix: &[u8]) -> Vec<u8> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < 0xff {
+            end.push(last + 1);
+            return end;
+        }
+    }
+    // Range the whole keyspace
+    vec![0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_range_end() {
+        assert_eq!(prefix_range_end(b"witnet"), b"witneu".to_vec());
+        assert_eq!(prefix_range_end(&[0x61, 0xff]), vec![0x62]);
+        assert_eq!(prefix_range_end(&[0xff]), vec![0]);
+    }
+
+    #[test]
+    fn test_pairs_to_config() {
+        let source = KvSource::consul("http://127.0.0.1:8500/", "/witnet/");
+        let config = source
+            .to_config(vec![
+                ("witnet/environment".to_string(), "mainnet".to_string()),
+                (
+                    "witnet/connections/inbound_limit".to_string(),
+                    "999".to_string(),
+                ),
+            ])
+            .unwrap();
+
+        assert_eq!(source.endpoint, "http://127.0.0.1:8500");
+        assert_eq!(
+            config.environment,
+            Some(witnet_data_structures::chain::Environment::Mainnet)
+        );
+        assert_eq!(config.connections.inbound_limit, Some(999));
+    }
+
+    #[test]
+    fn test_ignore_keys_outside_prefix() {
+        let source = KvSource::etcd("http://127.0.0.1:2379", "witnet");
+        let config = source
+            .to_config(vec![
+                (
+                    "witnet2/connections/inbound_limit".to_string(),
+                    "999".to_string(),
+                ),
+                (
+                    "witnet/connections/outbound_limit".to_string(),
+                    "9".to_string(),
+                ),
+            ])
+            .unwrap();
+
+        assert_eq!(config.connections.inbound_limit, None);
+        assert_eq!(config.connections.outbound_limit, Some(9));
+        assert_eq!(source.param_path("witnetconnections/inbound_limit"), None);
+    }
+
+    #[test]
+    fn test_etcd_version_ignores_store_revision() {
+        let response = |revision: &str, mod_revision: &str| {
+            serde_json::from_str(&format!(
+                r#"{{"header": {{"revision": "{}"}},
+                    "kvs": [{{"key": "d2l0bmV0L2E=", "value": "OTk5", "mod_revision": "{}"}}]}}"#,
+                revision, mod_revision
+            ))
+            .unwrap()
+        };
+
+        let (pairs, version) = etcd_pairs(response("10", "7")).unwrap();
+        assert_eq!(pairs, vec![("witnet/a".to_string(), "999".to_string())]);
+        // Writes outside the prefix only bump the store revision
+        assert_eq!(etcd_pairs(response("11", "7")).unwrap().1, version);
+        assert_ne!(etcd_pairs(response("12", "12")).unwrap().1, version);
+        // Deleting every key changes the version as well
+        let empty = serde_json::from_str(r#"{"header": {"revision": "13"}}"#).unwrap();
+        assert_ne!(etcd_pairs(empty).unwrap().1, version);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("OTk5").unwrap(), "999");
+        assert!(decode("not base64!").is_err());
+    }
+}
@@ -2,5 +2,323 @@
 //!
 //! Each sub-module in this module is specialized in reading the
 //! configuration in a specific format
+//!
+//! Every source of configuration implements the `Loader` trait, which
+//! can also be implemented by crates embedding the node to read the
+//! configuration from other sources (a database, a vault, etc.), and
+//! layers of sources can be merged with `load_all`.
+//!
+//! When the `async-loaders` feature is enabled, loaders can also be
+//! run without blocking the calling thread, which is needed when
+//! loading the configuration from inside an actor or a future.
+
+use crate::config::partial::Config;
+use ::toml::value::{Table, Value};
+use failure::Fail;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use witnet_util::error::{WitnetError, WitnetResult};
+
+#[cfg(feature = "async-loaders")]
+use futures::{sync::oneshot, Future};
+#[cfg(feature = "async-loaders")]
+use std::thread;
+
+#[cfg(not(test))]
+use std::fs::{self, File};
+#[cfg(not(test))]
+use std::io::Read;
 
+#[cfg(test)]
+use std::cell::{Cell, RefCell};
+#[cfg(test)]
+use std::collections::HashMap;
+#[cfg(test)]
+use std::path::Component;
+
+pub mod cli;
+pub mod env;
+#[cfg(feature = "http-loader")]
+pub mod http;
+pub mod interpolation;
+pub mod json;
+#[cfg(feature = "kv-loader")]
+pub mod kv;
+pub mod parse_error;
 pub mod toml;
+
+pub use self::parse_error::ParseError;
+
+/// Errors that can happen when loading the configuration. Parsing
+/// errors depend on the format of the source, but loading that
+/// configuration from a file might also fail with a `std::io::Error`.
+#[derive(Debug, Fail)]
+pub enum Error {
+    /// Indicates there was an error when trying to load configuration from a file.
+    IOError(io::Error),
+    /// Indicates there was an error when trying to build a
+    /// `witnet_config::config::partial::Config` instance out of the Toml string given.
+    ParseError(ParseError),
+    /// Indicates there was an error when trying to build a
+    /// `witnet_config::config::partial::Config` instance out of the Json string given.
+    JsonParseError(serde_json::Error),
+    /// Indicates that a command-line override is not written as
+    /// `section.param=value`.
+    InvalidOverride(String),
+    /// Indicates that a configuration file includes itself, directly
+    /// or through other included files.
+    IncludeCycle(PathBuf),
+    /// Indicates that the configuration contains sections or params
+    /// that are not recognized, only when loading in strict mode.
+    UnknownKeys(Vec<String>),
+    /// Indicates that the configuration could not be upgraded to the
+    /// current version of the configuration layout.
+    MigrationError(crate::migrations::Error),
+    /// Indicates that the selected profile does not exist in the
+    /// configuration.
+    UnknownProfile(String),
+    /// Indicates that a variable in a string value could not be
+    /// replaced.
+    InterpolationError(interpolation::Error),
+    /// Indicates there was an error in a `Loader` implemented outside
+    /// of this crate.
+    Custom(failure::Error),
+    /// Indicates there was an error when trying to fetch the
+    /// configuration from a remote location.
+    #[cfg(feature = "http-loader")]
+    HttpError(reqwest::Error),
+    /// Indicates that an entry read from a key-value store could not
+    /// be decoded.
+    #[cfg(feature = "kv-loader")]
+    InvalidKvEntry(String),
+}
+
+/// Formats the error in a user-friendly manners. Suitable for telling
+/// the user what error happened when loading/parsing the
+/// configuration.
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::IOError(e) => e.fmt(f),
+            Error::ParseError(e) => e.fmt(f),
+            Error::JsonParseError(e) => e.fmt(f),
+            Error::InvalidOverride(s) => write!(
+                f,
+                "Invalid override `{}`, expected `section.param=value`",
+                s
+            ),
+            Error::IncludeCycle(path) => write!(
+                f,
+                "Configuration file `{}` includes itself",
+                path.to_string_lossy()
+            ),
+            Error::UnknownKeys(keys) => {
+                write!(f, "Unknown configuration keys: {}", keys.join(", "))
+            }
+            Error::MigrationError(e) => e.fmt(f),
+            Error::UnknownProfile(name) => write!(f, "Unknown configuration profile `{}`", name),
+            Error::InterpolationError(e) => e.fmt(f),
+            Error::Custom(e) => e.fmt(f),
+            #[cfg(feature = "http-loader")]
+            Error::HttpError(e) => e.fmt(f),
+            #[cfg(feature = "kv-loader")]
+            Error::InvalidKvEntry(s) => write!(f, "Invalid key-value store entry `{}`", s),
+        }
+    }
+}
+
+/// Just like `std::result::Result` but withe error param fixed to
+/// `Error` type in this module.
+pub type Result<T> = WitnetResult<T, Error>;
+
+/// A source of configuration
+pub trait Loader {
+    /// Load a partial configuration from this source
+    fn load(&self) -> Result<Config>;
+}
+
+/// Any function returning a partial configuration is a loader
+impl<F> Loader for F
+where
+    F: Fn() -> Result<Config>,
+{
+    fn load(&self) -> Result<Config> {
+        self()
+    }
+}
+
+/// Load the configuration from several sources and merge them in the
+/// given order, so later sources take precedence over earlier ones
+/// (see `Config::merge`).
+pub fn load_all(loaders: &[&dyn Loader]) -> Result<Config> {
+    loaders
+        .iter()
+        .try_fold(Config::default(), |config, loader| {
+            Ok(config.merge(loader.load()?))
+        })
+}
+
+/// Run a loader in a background thread, returning a future that
+/// resolves to the loaded configuration without blocking the thread
+/// polling it.
+#[cfg(feature = "async-loaders")]
+pub fn spawn<L>(loader: L) -> impl Future<Item = Config, Error = WitnetError<Error>>
+where
+    L: Loader + Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    thread::spawn(move || {
+        // The receiver may have been dropped if nobody is waiting for
+        // the configuration anymore
+        let _ = sender.send(loader.load());
+    });
+
+    receiver.then(|result| match result {
+        Ok(config) => config,
+        Err(oneshot::Canceled) => Err(WitnetError::from(Error::IOError(io::Error::new(
+            io::ErrorKind::Other,
+            "the thread loading the configuration panicked",
+        )))),
+    })
+}
+
+/// Merge the profile with the given name over a loaded configuration,
+/// failing if there is no profile with that name.
+pub fn select_profile(config: Config, name: &str) -> Result<Config> {
+    config
+        .select_profile(name)
+        .ok_or_else(|| WitnetError::from(Error::UnknownProfile(name.to_string())))
+}
+
+/// Parse a raw value coming from a variable or an argument as a Toml
+/// value, falling back to a string if it is not valid Toml.
+pub(crate) fn parse_value(raw: &str) -> Value {
+    format!("value = {}", raw)
+        .parse::<Value>()
+        .ok()
+        .and_then(|mut parsed| {
+            parsed
+                .as_table_mut()
+                .and_then(|table| table.remove("value"))
+        })
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Insert a value in a (possibly nested) table following the given
+/// path of keys, creating the intermediate tables when needed.
+pub(crate) fn insert(table: &mut Table, path: &[String], value: Value) {
+    match path.split_first() {
+        None => {}
+        Some((key, [])) => {
+            table.insert(key.clone(), value);
+        }
+        Some((key, rest)) => {
+            let entry = table
+                .entry(key.clone())
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            if let Value::Table(inner) = entry {
+                insert(inner, rest, value);
+            }
+        }
+    }
+}
+
+#[cfg(not(test))]
+pub(crate) fn read_file_contents(file: &Path, contents: &mut String) -> io::Result<usize> {
+    let mut file = File::open(file)?;
+    file.read_to_string(contents)
+}
+
+#[cfg(not(test))]
+pub(crate) fn file_exists(file: &Path) -> bool {
+    file.is_file()
+}
+
+/// Absolute path of a file with all the symbolic links resolved, or the
+/// path itself if the file cannot be found (reading it will fail later).
+#[cfg(not(test))]
+pub(crate) fn canonical_path(file: &Path) -> PathBuf {
+    fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf())
+}
+
+#[cfg(test)]
+thread_local!(pub(crate) static FILE_CONTENTS: Cell<&'static str> = Cell::new(""));
+
+#[cfg(test)]
+thread_local!(pub(crate) static NAMED_FILE_CONTENTS: RefCell<HashMap<PathBuf, &'static str>> = RefCell::new(HashMap::new()));
+
+#[cfg(test)]
+pub(crate) fn read_file_contents(filename: &Path, contents: &mut String) -> io::Result<usize> {
+    let filename = canonical_path(filename);
+    if let Some(value) = NAMED_FILE_CONTENTS.with(|map| map.borrow().get(&filename).cloned()) {
+        contents.insert_str(0, value);
+        return Ok(value.len());
+    }
+    FILE_CONTENTS.with(|cell| {
+        let value = cell.get();
+        contents.insert_str(0, value);
+        Ok(value.len())
+    })
+}
+
+#[cfg(test)]
+pub(crate) fn file_exists(filename: &Path) -> bool {
+    NAMED_FILE_CONTENTS.with(|map| map.borrow().contains_key(&canonical_path(filename)))
+}
+
+/// Mock files have no symbolic links, so resolving `.` and `..` is
+/// enough to find their canonical path.
+#[cfg(test)]
+pub(crate) fn canonical_path(filename: &Path) -> PathBuf {
+    let mut path = PathBuf::new();
+    for component in filename.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                path.pop();
+            }
+            component => path.push(component),
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_all() {
+        let file = |config: &'static str| move || super::toml::from_str(config);
+        let overrides = cli::Overrides(vec!["connections.outbound_limit=2".to_string()]);
+
+        let config = load_all(&[
+            &file("[connections]\ninbound_limit = 3\noutbound_limit = 4"),
+            &file("[connections]\ninbound_limit = 5"),
+            &overrides,
+        ])
+        .unwrap();
+
+        assert_eq!(config.connections.inbound_limit, Some(5));
+        assert_eq!(config.connections.outbound_limit, Some(2));
+    }
+
+    #[test]
+    fn test_custom_loader_error() {
+        let failing = || -> Result<Config> {
+            Err(WitnetError::from(Error::Custom(failure::err_msg(
+                "vault is sealed",
+            ))))
+        };
+
+        match load_all(&[&failing]) {
+            Err(e) => assert_eq!(e.to_string(), "vault is sealed"),
+            Ok(_) => panic!("The error of the loader should be returned"),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! # Toml parse errors
+//!
+//! The errors returned by the `toml` crate only contain a message.
+//! `ParseError` wraps them together with the position of the error
+//! in the source, a snippet of the surrounding lines and, for some
+//! common mistakes, a hint on how to fix them:
+//!
+//! ```text
+//! expected newline, found a period at line 3
+//!   --> line 3, column 20
+//!    |
+//!  2 | [connections]
+//!  3 | server_addr = 127.0.0.1:1234
+//!    |                    ^
+//!  4 | inbound_limit = 128
+//!    = hint: socket addresses must be written between quotes, e.g.: `server_addr = "127.0.0.1:21337"`
+//! ```
+use failure::Fail;
+use std::fmt;
+
+/// Number of lines shown before and after the offending one
+const CONTEXT_LINES: usize = 1;
+
+/// Hint for socket addresses written without quotes
+const HINT_UNQUOTED_ADDRESS: &str =
+    "socket addresses must be written between quotes, e.g.: `server_addr = \"127.0.0.1:21337\"`";
+
+/// Hint for durations written with units or as strings
+const HINT_SECONDS: &str = "durations are written as an integer number of seconds, \
+                            e.g.: `handshake_timeout_seconds = 5`";
+
+/// Error parsing a Toml document, with its location in the source
+#[derive(Debug, Fail)]
+pub struct ParseError {
+    /// Error returned by the Toml parser
+    pub error: toml::de::Error,
+    /// Line of the error, starting at 1
+    pub line: Option<usize>,
+    /// Column of the error, starting at 1
+    pub column: Option<usize>,
+    /// Lines surrounding the error, with their line numbers
+    pub snippet: Vec<(usize, String)>,
+    /// Suggestion on how to fix the error
+    pub hint: Option<&'static str>,
+}
+
+impl ParseError {
+    /// Locate an error in the Toml source it comes from
+    pub fn new(error: toml::de::Error, source: &str) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
+        let (line, column) = match error.line_col() {
+            Some((line, column)) => (Some(line), Some(column + 1)),
+            None => (
+                key_in_message(&error.to_string()).and_then(|key| find_key(&lines, key)),
+                None,
+            ),
+        };
+        let line = line.filter(|line| *line < lines.len());
+
+        let snippet = match line {
+            Some(line) => {
+                let first = line.saturating_sub(CONTEXT_LINES);
+                let last = (line + CONTEXT_LINES).min(lines.len() - 1);
+                (first..=last)
+                    .map(|n| (n + 1, lines[n].to_string()))
+                    .collect()
+            }
+            None => vec![],
+        };
+        let hint = line.and_then(|line| hint(lines[line]));
+
+        ParseError {
+            error,
+            line: line.map(|line| line + 1),
+            column,
+            snippet,
+            hint,
+        }
+    }
+}
+
+/// Errors without source only contain the message
+impl From<toml::de::Error> for ParseError {
+    fn from(error: toml::de::Error) -> Self {
+        ParseError {
+            error,
+            line: None,
+            column: None,
+            snippet: vec![],
+            hint: None,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+
+        if let Some(line) = self.line {
+            match self.column {
+                Some(column) => write!(f, "\n  --> line {}, column {}", line, column)?,
+                None => write!(f, "\n  --> line {}", line)?,
+            }
+            let width = self
+                .snippet
+                .last()
+                .map_or(1, |(number, _)| number.to_string().len());
+            write!(f, "\n {:width$} |", "", width = width)?;
+            for (number, text) in &self.snippet {
+                write!(f, "\n {:>width$} | {}", number, text, width = width)?;
+                match self.column {
+                    Some(column) if *number == line => write!(
+                        f,
+                        "\n {:width$} | {:>column$}",
+                        "",
+                        "^",
+                        width = width,
+                        column = column
+                    )?,
+                    _ => {}
+                }
+            }
+        }
+        if let Some(hint) = self.hint {
+            write!(f, "\n  = hint: {}", hint)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Extract the key from messages such as ``... for key `connections.inbound_limit` ``
+fn key_in_message(message: &str) -> Option<&str> {
+    let start = message.find("for key `")? + "for key `".len();
+    let end = message[start..].find('`')? + start;
+
+    message[start..end].rsplit('.').next()
+}
+
+/// Index of the first line assigning a value to the given key
+fn find_key(lines: &[&str], key: &str) -> Option<usize> {
+    lines
+        .iter()
+        .position(|line| split_assignment(line).map_or(false, |(k, _)| k == key))
+}
+
+/// Split a `key = value` line
+fn split_assignment(line: &str) -> Option<(&str, &str)> {
+    let mut parts = line.splitn(2, '=');
+    let key = parts.next()?.trim();
+    let value = parts.next()?.trim();
+
+    Some((key, value))
+}
+
+/// Look for common mistakes in the offending line
+fn hint(line: &str) -> Option<&'static str> {
+    let (key, value) = split_assignment(line)?;
+    let value = value.split('#').next().unwrap_or("").trim();
+
+    let looks_like_address = value.contains(':')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() || c == '.' || c == ':' || c == '[' || c == ']');
+    let is_duration =
+        key.ends_with("_seconds") || key.ends_with("_period") || key.ends_with("_timeout");
+
+    if looks_like_address {
+        Some(HINT_UNQUOTED_ADDRESS)
+    } else if is_duration && value.parse::<u64>().is_err() {
+        Some(HINT_SECONDS)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::partial::Config;
+
+    fn parse_error(source: &str) -> ParseError {
+        ParseError::new(toml::from_str::<Config>(source).unwrap_err(), source)
+    }
+
+    #[test]
+    fn test_unquoted_address() {
+        let error = parse_error("[connections]\nserver_addr = 127.0.0.1:1234\ninbound_limit = 3\n");
+
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.snippet.len(), 3);
+        assert_eq!(
+            error.snippet[1],
+            (2, "server_addr = 127.0.0.1:1234".to_string())
+        );
+        assert_eq!(error.hint, Some(HINT_UNQUOTED_ADDRESS));
+    }
+
+    #[test]
+    fn test_duration_with_units() {
+        let error = parse_error("[connections]\nhandshake_timeout_seconds = \"5s\"\n");
+
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.hint, Some(HINT_SECONDS));
+        assert!(error
+            .to_string()
+            .contains("handshake_timeout_seconds = \"5s\""));
+    }
+
+    #[test]
+    fn test_no_hint() {
+        let error = parse_error("[connections]\ninbound_limit = \"many\"\n");
+
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.hint, None);
+    }
+
+    #[test]
+    fn test_display() {
+        let error = ParseError {
+            line: Some(2),
+            column: Some(15),
+            snippet: vec![
+                (1, "[connections]".to_string()),
+                (2, "server_addr = 127.0.0.1:1234".to_string()),
+            ],
+            hint: Some(HINT_UNQUOTED_ADDRESS),
+            ..ParseError::from(toml::from_str::<Config>("[").unwrap_err())
+        };
+
+        let message = error.to_string();
+        let lines: Vec<&str> = message.lines().skip(1).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "  --> line 2, column 15",
+                "   |",
+                " 1 | [connections]",
+                " 2 | server_addr = 127.0.0.1:1234",
+                "   |               ^",
+                &format!("  = hint: {}", HINT_UNQUOTED_ADDRESS),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_in_message() {
+        assert_eq!(
+            key_in_message(
+                "invalid type: string \"a\", expected u16 for key `connections.inbound_limit`"
+            ),
+            Some("inbound_limit")
+        );
+        assert_eq!(key_in_message("expected newline"), None);
+    }
+}
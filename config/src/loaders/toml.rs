@@ -1,72 +1,226 @@
 //! Load the configuration from a file or a `String` written in [Toml format](Tomlhttps://en.wikipedia.org/wiki/TOML)
+//!
+//! A Toml document can include other Toml files using a top-level
+//! `include` key, e.g.: `include = ["connections.toml",
+//! "storage.toml"]`. Included files are loaded recursively and merged
+//! in the given order, and the params of the including document take
+//! precedence over the included ones. Relative paths are resolved
+//! against the directory of the including file.
+//!
+//! Documents written for older versions of the configuration layout
+//! are upgraded with the [migrations](migrations) before being loaded.
+//!
+//! When loading a file, a sibling "local" file (e.g.:
+//! `witnet.local.toml` next to `witnet.toml`) is merged over it if it
+//! exists, so personal overrides can be kept out of a shared file.
+//!
+//! Variables in string values, such as `${env:HOME}`, are replaced
+//! (see [interpolation](loaders::interpolation)).
 
+use super::{canonical_path, file_exists, interpolation, read_file_contents, Loader, ParseError};
 use crate::config::partial::Config;
-use failure::Fail;
-use std::fmt;
-use std::fs::File;
-use std::io::{self, Read};
-use std::path::Path;
+use crate::migrations;
+use log::info;
+use serde::Deserializer;
+use std::path::{Path, PathBuf};
 use toml;
-use witnet_util::error::{WitnetError, WitnetResult};
+use witnet_util::error::WitnetError;
 
-#[cfg(test)]
-use std::cell::Cell;
-
-/// `toml::de::Error`, but loading that configuration from a file
-/// might also fail with a `std::io::Error`.
-#[derive(Debug, Fail)]
-pub enum Error {
-    /// Indicates there was an error when trying to load configuration from a file.
-    IOError(io::Error),
-    /// Indicates there was an error when trying to build a
-    /// `witnet_config::config::partial::Config` instance out of the Toml string given.
-    ParseError(toml::de::Error),
+pub use super::{Error, Result};
+
+/// Top-level key listing the files included by a Toml document
+const INCLUDE_KEY: &str = "include";
+
+/// Files included by a Toml document
+#[derive(Deserialize)]
+struct Includes {
+    #[serde(default)]
+    include: Vec<PathBuf>,
 }
 
-/// Formats the error in a user-friendly manners. Suitable for telling
-/// the user what error happened when loading/parsing the
-/// configuration.
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Error::IOError(e) => e.fmt(f),
-            Error::ParseError(e) => e.fmt(f),
-        }
+/// Loader reading a Toml file, see `from_file` and `from_file_strict`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TomlFile {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Whether to fail on unknown sections and params
+    pub strict: bool,
+}
+
+impl Loader for TomlFile {
+    fn load(&self) -> Result<Config> {
+        from_file_with_local(&self.path, self.strict)
     }
 }
 
-/// Just like `std::result::Result` but withe error param fixed to
-/// `Error` type in this module.
-pub type Result<T> = WitnetResult<T, Error>;
+/// Suffix added to the name of a configuration file to get the name
+/// of its local overlay
+pub const LOCAL_SUFFIX: &str = "local";
 
-/// Load configuration from a file written in Toml format.
+/// Load configuration from a file written in Toml format, merging its
+/// local overlay over it if it exists.
 pub fn from_file(file: &Path) -> Result<Config> {
+    from_file_with_local(file, false)
+}
+
+/// Load configuration from a file written in Toml format in a
+/// background thread (see `from_file` and `loaders::spawn`).
+#[cfg(feature = "async-loaders")]
+pub fn from_file_async(
+    file: &Path,
+) -> impl futures::Future<Item = Config, Error = WitnetError<Error>> {
+    super::spawn(TomlFile {
+        path: file.to_path_buf(),
+        strict: false,
+    })
+}
+
+/// Load configuration from a string written in Toml format. Included
+/// files are resolved relative to the current working directory.
+pub fn from_str(contents: &str) -> Result<Config> {
+    from_str_with_includes(contents, Path::new(""), &mut vec![], false)
+}
+
+/// Load configuration from a file written in Toml format, merging its
+/// local overlay over it if it exists, and failing with
+/// `Error::UnknownKeys` if any of the files contains sections or
+/// params that are not recognized.
+pub fn from_file_strict(file: &Path) -> Result<Config> {
+    from_file_with_local(file, true)
+}
+
+/// Load configuration from a string written in Toml format, failing
+/// with `Error::UnknownKeys` if it (or any of the files it includes)
+/// contains sections or params that are not recognized.
+pub fn from_str_strict(contents: &str) -> Result<Config> {
+    from_str_with_includes(contents, Path::new(""), &mut vec![], true)
+}
+
+/// Path of the local overlay of a configuration file: the same file
+/// name with `LOCAL_SUFFIX` inserted before the extension, e.g.:
+/// `witnet.local.toml` for `witnet.toml`.
+pub fn local_path(file: &Path) -> PathBuf {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match file.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, LOCAL_SUFFIX, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, LOCAL_SUFFIX),
+    };
+
+    file.with_file_name(name)
+}
+
+/// Load a file and merge its local overlay over it, if it exists
+fn from_file_with_local(file: &Path, strict: bool) -> Result<Config> {
+    let config = from_file_with_includes(file, &mut vec![], strict)?;
+
+    let local = local_path(file);
+    if local != file && file_exists(&local) {
+        info!(
+            "Merging local configuration overrides from file: {}",
+            local.to_string_lossy()
+        );
+        Ok(config.merge(from_file_with_includes(&local, &mut vec![], strict)?))
+    } else {
+        Ok(config)
+    }
+}
+
+/// Load a file keeping track of the canonical paths of the chain of
+/// files that included it, in order to detect include cycles even when
+/// the same file is reached through different paths (`./a.toml`,
+/// `conf/../a.toml` or a symbolic link).
+fn from_file_with_includes(
+    file: &Path,
+    including: &mut Vec<PathBuf>,
+    strict: bool,
+) -> Result<Config> {
+    // Report the canonical path, as the path written in the include
+    // directive may not look like the path of the including file
+    let canonical = canonical_path(file);
+    if including.contains(&canonical) {
+        return Err(WitnetError::from(Error::IncludeCycle(canonical)));
+    }
+
     let mut contents = String::new();
     read_file_contents(file, &mut contents).map_err(Error::IOError)?;
-    from_str(&contents)
+
+    including.push(canonical);
+    let base_dir = file.parent().unwrap_or_else(|| Path::new(""));
+    let config = from_str_with_includes(&contents, base_dir, including, strict);
+    including.pop();
+
+    config
 }
 
-#[cfg(not(test))]
-fn read_file_contents(file: &Path, contents: &mut String) -> io::Result<usize> {
-    let mut file = File::open(file)?;
-    file.read_to_string(contents)
+fn from_str_with_includes(
+    contents: &str,
+    base_dir: &Path,
+    including: &mut Vec<PathBuf>,
+    strict: bool,
+) -> Result<Config> {
+    let includes: Includes = toml::from_str(contents)
+        .map_err(|e| WitnetError::from(Error::ParseError(ParseError::new(e, contents))))?;
+    let config = parse(contents, strict)?;
+
+    let mut included = Config::default();
+    for path in includes.include {
+        included = included.merge(from_file_with_includes(
+            &base_dir.join(path),
+            including,
+            strict,
+        )?);
+    }
+
+    Ok(included.merge(config))
 }
 
-#[cfg(test)]
-thread_local!(static FILE_CONTENTS: Cell<&'static str> = Cell::new(""));
+/// Parse a Toml string, migrating it first if it was written for an
+/// older version of the configuration layout, and replacing the
+/// variables in its string values.
+fn parse(contents: &str, strict: bool) -> Result<Config> {
+    let mut document: toml::Value = contents
+        .parse()
+        .map_err(|e| WitnetError::from(Error::ParseError(ParseError::new(e, contents))))?;
+    let report = migrations::migrate(&mut document).map_err(Error::MigrationError)?;
+    report.log();
+    let interpolated = interpolation::interpolate_toml(&mut document, &interpolation::lookup)
+        .map_err(Error::InterpolationError)?;
 
-#[cfg(test)]
-fn read_file_contents(_filename: &Path, contents: &mut String) -> io::Result<usize> {
-    FILE_CONTENTS.with(|cell| {
-        let value = cell.get();
-        contents.insert_str(0, value);
-        Ok(value.len())
-    })
+    if report.is_empty() && !interpolated {
+        // Deserialize from the string to keep the position of errors
+        let mut deserializer = toml::de::Deserializer::new(contents);
+        deserialize(&mut deserializer, contents, strict)
+    } else {
+        deserialize(document, contents, strict)
+    }
 }
 
-/// Load configuration from a string written in Toml format.
-pub fn from_str(contents: &str) -> Result<Config> {
-    toml::from_str(contents).map_err(|e| WitnetError::from(Error::ParseError(e)))
+/// Deserialize a configuration. In strict mode, collect the paths of
+/// all the keys that are not part of the configuration and fail if
+/// there is any. The source `contents` are used to locate errors.
+fn deserialize<'de, D>(deserializer: D, contents: &str, strict: bool) -> Result<Config>
+where
+    D: Deserializer<'de, Error = toml::de::Error>,
+{
+    if !strict {
+        return serde::Deserialize::deserialize(deserializer)
+            .map_err(|e| WitnetError::from(Error::ParseError(ParseError::new(e, contents))));
+    }
+
+    let mut unknown_keys = vec![];
+    let config: Config = serde_ignored::deserialize(deserializer, |path| {
+        let path = path.to_string();
+        if path != INCLUDE_KEY {
+            unknown_keys.push(path);
+        }
+    })
+    .map_err(|e| WitnetError::from(Error::ParseError(ParseError::new(e, contents))))?;
+
+    if unknown_keys.is_empty() {
+        Ok(config)
+    } else {
+        Err(WitnetError::from(Error::UnknownKeys(unknown_keys)))
+    }
 }
 
 #[cfg(test)]
@@ -84,7 +238,7 @@ mod tests {
 
     #[test]
     fn test_load_empty_config_from_file() {
-        super::FILE_CONTENTS.with(|cell| cell.set(""));
+        crate::loaders::FILE_CONTENTS.with(|cell| cell.set(""));
         let filename = Path::new("config.toml");
         let config = super::from_file(&filename).unwrap();
 
@@ -93,7 +247,7 @@ mod tests {
 
     #[test]
     fn test_load_config_from_file() {
-        super::FILE_CONTENTS.with(|cell| {
+        crate::loaders::FILE_CONTENTS.with(|cell| {
             cell.set(
                 r"
 environment = 'testnet-1'
@@ -105,16 +259,21 @@ inbound_limit = 999
         let filename = Path::new("config.toml");
         let config = super::from_file(&filename).unwrap();
 
-        assert_eq!(config.environment, Environment::Testnet1);
+        assert_eq!(config.environment, Some(Environment::Testnet1));
         assert_eq!(config.connections.inbound_limit, Some(999));
     }
 
     #[test]
     fn test_configure_environment() {
         let config = super::from_str("environment = 'mainnet'").unwrap();
+        let custom = super::from_str("environment = 'custom:devnet'").unwrap();
         let result = super::from_str("environment = 'wrong'");
 
-        assert_eq!(config.environment, Environment::Mainnet);
+        assert_eq!(config.environment, Some(Environment::Mainnet));
+        assert_eq!(
+            custom.environment,
+            Some(Environment::Custom("devnet".to_string()))
+        );
         assert!(result.is_err());
     }
 
@@ -133,12 +292,33 @@ known_peers = ['192.168.1.12:1234']
         assert_eq!(empty_config.connections, Connections::default());
         assert_eq!(empty_config.connections.known_peers.len(), 0);
         assert_eq!(
-            config.connections.server_addr,
-            Some("127.0.0.1:1234".parse().unwrap())
+            config.connections.server_addrs,
+            Some(vec!["127.0.0.1:1234".parse().unwrap()])
         );
         assert_eq!(config.connections.known_peers.len(), 1);
     }
 
+    #[test]
+    fn test_configure_several_server_addresses() {
+        let config = super::from_str(
+            r"
+[connections]
+server_addr = ['127.0.0.1:1234', '[::]:1235']
+",
+        )
+        .unwrap();
+        let empty_list = super::from_str("[connections]\nserver_addr = []");
+
+        assert_eq!(
+            config.connections.server_addrs,
+            Some(vec![
+                "127.0.0.1:1234".parse().unwrap(),
+                "[::]:1235".parse().unwrap(),
+            ])
+        );
+        assert!(empty_list.is_err());
+    }
+
     #[test]
     fn test_configure_storage() {
         let empty_config = super::from_str("[storage]").unwrap();
@@ -154,6 +334,37 @@ db_path = 'dbfiles'
         assert_eq!(config.storage.db_path, Some(PathBuf::from("dbfiles")));
     }
 
+    #[test]
+    fn test_configure_storage_pruning() {
+        let config = super::from_str(
+            r"
+[storage]
+pruning = { enabled = true, keep_blocks = 100 }
+",
+        )
+        .unwrap();
+
+        assert_eq!(config.storage.pruning.enabled, Some(true));
+        assert_eq!(config.storage.pruning.keep_blocks, Some(100));
+    }
+
+    #[test]
+    fn test_load_byte_sizes() {
+        let config = super::from_str(
+            r"
+[storage]
+max_db_size = '2GiB'
+cache_size = 1048576
+",
+        )
+        .unwrap();
+        let result = super::from_str("[storage]\ncache_size = '2 lightyears'");
+
+        assert_eq!(config.storage.max_db_size, Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(config.storage.cache_size, Some(1_048_576));
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_load_durations() {
         use std::time::Duration;
@@ -223,4 +434,227 @@ enabled = false
         );
         assert_eq!(config_disabled.jsonrpc.enabled, Some(false),);
     }
+
+    #[test]
+    fn test_include_files() {
+        crate::loaders::NAMED_FILE_CONTENTS.with(|map| {
+            let mut map = map.borrow_mut();
+            map.insert(
+                PathBuf::from("conf/witnet.toml"),
+                r"
+include = ['connections.toml', 'storage.toml']
+[connections]
+outbound_limit = 4
+",
+            );
+            map.insert(
+                PathBuf::from("conf/connections.toml"),
+                r"
+include = ['nested/jsonrpc.toml']
+[connections]
+inbound_limit = 999
+outbound_limit = 16
+",
+            );
+            map.insert(
+                PathBuf::from("conf/nested/jsonrpc.toml"),
+                "[jsonrpc]\nenabled = false",
+            );
+            map.insert(
+                PathBuf::from("conf/storage.toml"),
+                "[storage]\ndb_path = 'dbfiles'",
+            );
+        });
+        let config = super::from_file(Path::new("conf/witnet.toml")).unwrap();
+
+        assert_eq!(config.connections.inbound_limit, Some(999));
+        assert_eq!(config.connections.outbound_limit, Some(4));
+        assert_eq!(config.storage.db_path, Some(PathBuf::from("dbfiles")));
+        assert_eq!(config.jsonrpc.enabled, Some(false));
+    }
+
+    #[test]
+    fn test_local_path() {
+        assert_eq!(
+            super::local_path(Path::new("conf/witnet.toml")),
+            PathBuf::from("conf/witnet.local.toml")
+        );
+        assert_eq!(
+            super::local_path(Path::new("witnet")),
+            PathBuf::from("witnet.local")
+        );
+    }
+
+    #[test]
+    fn test_local_overlay() {
+        crate::loaders::NAMED_FILE_CONTENTS.with(|map| {
+            let mut map = map.borrow_mut();
+            map.insert(
+                PathBuf::from("shared/witnet.toml"),
+                r"
+[connections]
+inbound_limit = 999
+known_peers = ['192.168.1.12:1234']
+[storage]
+db_path = 'dbfiles'
+",
+            );
+            map.insert(
+                PathBuf::from("shared/witnet.local.toml"),
+                "[storage]\ndb_path = '/tmp/wit'",
+            );
+        });
+        let config = super::from_file(Path::new("shared/witnet.toml")).unwrap();
+
+        assert_eq!(config.connections.inbound_limit, Some(999));
+        assert_eq!(config.storage.db_path, Some(PathBuf::from("/tmp/wit")));
+    }
+
+    #[test]
+    fn test_interpolate_variables() {
+        std::env::set_var("WITNET_TEST_TOML_PORT", "4000");
+        let config = super::from_str(
+            r#"
+[connections]
+server_addr = "127.0.0.1:${env:WITNET_TEST_TOML_PORT}"
+"#,
+        )
+        .unwrap();
+        let result = super::from_str("[storage]\ndb_path = '${env:WITNET_TEST_UNDEFINED}'");
+
+        assert_eq!(
+            config.connections.server_addrs,
+            Some(vec!["127.0.0.1:4000".parse().unwrap()])
+        );
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "async-loaders")]
+    #[test]
+    fn test_load_config_from_file_async() {
+        use futures::Future;
+
+        // File contents are mocked per thread, so the background thread
+        // reads an empty file
+        let config = super::from_file_async(Path::new("config.toml"))
+            .wait()
+            .unwrap();
+
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        crate::loaders::NAMED_FILE_CONTENTS.with(|map| {
+            let mut map = map.borrow_mut();
+            map.insert(PathBuf::from("a.toml"), "include = ['b.toml']");
+            map.insert(PathBuf::from("b.toml"), "include = ['a.toml']");
+        });
+        let result = super::from_file(Path::new("a.toml"));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_include_cycle_through_different_paths() {
+        crate::loaders::NAMED_FILE_CONTENTS.with(|map| {
+            let mut map = map.borrow_mut();
+            map.insert(
+                PathBuf::from("conf/a.toml"),
+                "include = ['nested/../b.toml']",
+            );
+            map.insert(PathBuf::from("conf/b.toml"), "include = ['./a.toml']");
+        });
+
+        match super::from_file(Path::new("conf/a.toml")) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "Configuration file `conf/a.toml` includes itself"
+            ),
+            Ok(_) => panic!("The include cycle should be detected"),
+        }
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let contents = r"
+include = []
+[conections]
+inbound_limit = 999
+[storage]
+db_pth = 'dbfiles'
+";
+        let config = super::from_str(contents).unwrap();
+        let result = super::from_str_strict(contents);
+
+        assert_eq!(config, Config::default());
+        match result {
+            Err(e) => {
+                let message = e.to_string();
+                assert!(message.contains("conections"));
+                assert!(message.contains("storage.db_pth"));
+            }
+            Ok(_) => panic!("Strict mode should reject unknown keys"),
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_known_keys() {
+        let config = super::from_str_strict(
+            r"
+environment = 'testnet-1'
+[connections]
+inbound_limit = 999
+handshake_timeout_seconds = 21
+",
+        )
+        .unwrap();
+
+        assert_eq!(config.connections.inbound_limit, Some(999));
+    }
+
+    #[test]
+    fn test_load_old_config_version() {
+        let config = super::from_str_strict(
+            r"
+[logging]
+stdout_log_level = 'Warning'
+log_to_file = true
+",
+        )
+        .unwrap();
+        let result = super::from_str("config_version = 1000");
+
+        assert_eq!(config.log.level, Some(log::LevelFilter::Warn));
+        assert_eq!(
+            config.config_version,
+            Some(crate::migrations::CURRENT_VERSION)
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_load_profiles() {
+        let config = super::from_str_strict(
+            r"
+[connections]
+inbound_limit = 10
+outbound_limit = 4
+
+[profile.local-dev.connections]
+outbound_limit = 1
+
+[profile.mainnet]
+environment = 'mainnet'
+",
+        )
+        .unwrap();
+        let local = config.clone().select_profile("local-dev").unwrap();
+        let mainnet = config.select_profile("mainnet").unwrap();
+
+        assert_eq!(local.connections.inbound_limit, Some(10));
+        assert_eq!(local.connections.outbound_limit, Some(1));
+        assert_eq!(mainnet.environment, Some(Environment::Mainnet));
+        assert_eq!(mainnet.connections.outbound_limit, Some(4));
+    }
 }
@@ -0,0 +1,308 @@
+//! # Configuration migrations
+//!
+//! The layout of the configuration file changes over time: params get
+//! renamed and sections get moved. Every configuration file carries a
+//! `config_version` param (files without it are considered to be
+//! version `0`), and this module upgrades configuration documents
+//! written for older versions to the current layout, so operators do
+//! not need to edit their files by hand after every breaking change.
+//!
+//! Migrations work on the raw Toml document, before it is deserialized
+//! into a partial [Config](config::partial::Config), and produce a
+//! report with every change applied.
+//!
+//! When making breaking changes to the configuration layout, bump
+//! `CURRENT_VERSION` and add a migration for it to `MIGRATIONS`.
+//! Adding new params is not a breaking change and does not need a new
+//! version. Documents written for a version newer than the current one
+//! are refused, since they may use a layout this node does not know.
+use failure::Fail;
+use log::warn;
+use std::fmt;
+use toml::value::{Table, Value};
+
+/// Name of the param holding the version of the configuration layout
+pub const VERSION_KEY: &str = "config_version";
+
+/// Current version of the configuration layout
+pub const CURRENT_VERSION: u32 = 1;
+
+/// A migration modifies a configuration document written for a
+/// version so it follows the layout of the next version, recording
+/// the changes it applies.
+type Migration = fn(&mut Table, &mut Vec<String>);
+
+/// Migrations from every version to the next one: the migration at
+/// index `n` upgrades from version `n` to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Errors that can happen when migrating a configuration document
+#[derive(Debug, Fail)]
+pub enum Error {
+    /// The document is not a table
+    NotATable,
+    /// The version param is not a non-negative integer
+    InvalidVersion(Value),
+    /// The version is newer than the current one
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotATable => write!(f, "The configuration is not a table"),
+            Error::InvalidVersion(value) => {
+                write!(f, "Invalid configuration version `{}`", value)
+            }
+            Error::UnsupportedVersion(version) => write!(
+                f,
+                "Configuration version {} is newer than the supported version {}, \
+                 please upgrade the node",
+                version, CURRENT_VERSION
+            ),
+        }
+    }
+}
+
+/// Summary of a migration
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    /// Version of the document before the migration
+    pub from_version: u32,
+    /// Version of the document after the migration
+    pub to_version: u32,
+    /// Human-readable description of every change applied
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    /// Whether the document was modified by the migration
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Log a warning listing the changes applied, if any, so the
+    /// operator can update the configuration
+    pub fn log(&self) {
+        if self.is_empty() {
+            return;
+        }
+        warn!(
+            "The configuration was written for version {} and has been migrated to version {}, \
+             please update it:",
+            self.from_version, self.to_version
+        );
+        for change in &self.changes {
+            warn!("    {}", change);
+        }
+    }
+}
+
+/// Read the version of the configuration layout of a document
+pub fn version(document: &Value) -> Result<u32, Error> {
+    let table = document.as_table().ok_or(Error::NotATable)?;
+
+    match table.get(VERSION_KEY) {
+        None => Ok(0),
+        Some(Value::Integer(version))
+            if *version >= 0 && *version <= i64::from(u32::max_value()) =>
+        {
+            Ok(*version as u32)
+        }
+        Some(other) => Err(Error::InvalidVersion(other.clone())),
+    }
+}
+
+/// Upgrade a configuration document to the current version of the
+/// configuration layout.
+pub fn migrate(document: &mut Value) -> Result<MigrationReport, Error> {
+    let from_version = version(document)?;
+    if from_version > CURRENT_VERSION {
+        return Err(Error::UnsupportedVersion(from_version));
+    }
+
+    let table = document.as_table_mut().ok_or(Error::NotATable)?;
+    let mut changes = vec![];
+    for migration in &MIGRATIONS[from_version as usize..] {
+        migration(table, &mut changes);
+    }
+    if !changes.is_empty() {
+        table.insert(
+            VERSION_KEY.to_string(),
+            Value::Integer(i64::from(CURRENT_VERSION)),
+        );
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: CURRENT_VERSION,
+        changes,
+    })
+}
+
+/// Log level of the `[logging]` section of version 0, as a level of
+/// `log.level`
+fn log_level_v0(level: &str) -> Option<&'static str> {
+    match level.to_lowercase().as_str() {
+        "critical" | "error" => Some("error"),
+        "warning" => Some("warn"),
+        "info" => Some("info"),
+        "debug" => Some("debug"),
+        "trace" => Some("trace"),
+        _ => None,
+    }
+}
+
+/// Version 1: the `[logging]` section of the `witnet.toml` file shipped
+/// with the first releases is replaced by `[log]`, whose `level` is the
+/// level of `stdout_log_level`. The node only logs to the standard
+/// output, so the params about the log file are removed.
+fn migrate_v0_to_v1(document: &mut Table, changes: &mut Vec<String>) {
+    let logging = match document.remove("logging") {
+        Some(Value::Table(logging)) => logging,
+        Some(_) => {
+            changes.push("Removed `logging`, which is not a section".to_string());
+            return;
+        }
+        None => return,
+    };
+
+    for (param, value) in logging {
+        let level = match (param.as_str(), &value) {
+            ("stdout_log_level", Value::String(level)) => log_level_v0(level),
+            _ => None,
+        };
+        let log = match document
+            .entry("log".to_string())
+            .or_insert_with(|| Value::Table(Table::new()))
+        {
+            Value::Table(log) => log,
+            _ => {
+                changes.push(format!(
+                    "Removed `logging.{}`, `log` is not a section",
+                    param
+                ));
+                continue;
+            }
+        };
+        match level {
+            Some(level) if !log.contains_key("level") => {
+                log.insert("level".to_string(), Value::String(level.to_string()));
+                changes.push(format!(
+                    "Moved `logging.{}` to `log.level`, as `\"{}\"`",
+                    param, level
+                ));
+            }
+            _ => changes.push(format!(
+                "Removed `logging.{}`, which is not supported",
+                param
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version() {
+        let missing: Value = "".parse().unwrap();
+        let current: Value = "config_version = 1".parse().unwrap();
+        let invalid: Value = "config_version = 'one'".parse().unwrap();
+
+        assert_eq!(version(&missing).unwrap(), 0);
+        assert_eq!(version(&current).unwrap(), 1);
+        assert!(version(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_migrate_v0() {
+        // The `witnet.toml` file shipped with the first releases
+        let mut document: Value = r#"
+[connections]
+server_addr = "0.0.0.0:21337"
+
+[storage]
+db_path = ".wit"
+
+[consensus_constants]
+checkpoint_zero_timestamp = 1542203073
+
+[logging]
+log_to_stdout = true
+stdout_log_level = "Debug"
+log_to_file = true
+file_log_level = "Debug"
+log_file_path = "wit.log"
+log_file_append = true
+"#
+        .parse()
+        .unwrap();
+
+        let report = migrate(&mut document).unwrap();
+
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, CURRENT_VERSION);
+        assert_eq!(report.changes.len(), 6);
+        assert!(document.get("logging").is_none());
+        assert_eq!(document["log"]["level"].as_str(), Some("debug"));
+        assert_eq!(
+            document["connections"]["server_addr"].as_str(),
+            Some("0.0.0.0:21337")
+        );
+        assert_eq!(
+            document[VERSION_KEY].as_integer(),
+            Some(i64::from(CURRENT_VERSION))
+        );
+
+        // The migrated document is a valid configuration
+        let config: crate::config::partial::Config = document.try_into().unwrap();
+        assert_eq!(config.log.level, Some(log::LevelFilter::Debug));
+    }
+
+    #[test]
+    fn test_migrate_v0_keeps_log_level() {
+        let mut document: Value = "[log]\nlevel = 'info'\n[logging]\nstdout_log_level = 'Warning'"
+            .parse()
+            .unwrap();
+
+        let report = migrate(&mut document).unwrap();
+
+        assert_eq!(
+            report.changes,
+            vec!["Removed `logging.stdout_log_level`, which is not supported".to_string()]
+        );
+        assert_eq!(document["log"]["level"].as_str(), Some("info"));
+    }
+
+    #[test]
+    fn test_migrate_v0_without_changes() {
+        let mut document: Value = "[connections]\ninbound_limit = 3".parse().unwrap();
+
+        let report = migrate(&mut document).unwrap();
+
+        assert!(report.is_empty());
+        assert!(document.get(VERSION_KEY).is_none());
+    }
+
+    #[test]
+    fn test_migrate_current_version() {
+        let mut document: Value = "config_version = 1\n[connections]\ninbound_limit = 3"
+            .parse()
+            .unwrap();
+        let original = document.clone();
+
+        let report = migrate(&mut document).unwrap();
+
+        assert!(report.is_empty());
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn test_migrate_unsupported_version() {
+        let mut document: Value = "config_version = 1000".parse().unwrap();
+
+        assert!(migrate(&mut document).is_err());
+    }
+}
@@ -0,0 +1,936 @@
+//! # Configuration schema
+//!
+//! This module generates a [JSON Schema](https://json-schema.org/)
+//! document describing the format accepted by the configuration
+//! loaders: every section, param, type and default value. The same
+//! schema can be used to validate both Toml and Json configuration
+//! files, e.g.: from an IDE or a CI pipeline, before deploying them.
+//!
+//! Default values depend on the environment, so the schema is always
+//! generated for a given set of [defaults](defaults).
+//!
+//! When adding new configuration params, remember to also add them
+//! here!
+use crate::config::{
+    ArchiveBackend, CompactionStyle, Compression, MessageCompression, StorageBackend,
+};
+use crate::defaults::{Defaults, Testnet1};
+use crate::migrations;
+use serde_json::{json, Value};
+
+/// URI of the JSON Schema draft used by the generated schema
+pub const JSON_SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// JSON Schema of the configuration using the defaults of the default
+/// environment (`testnet-1`).
+pub fn json_schema() -> Value {
+    json_schema_with_defaults(&Testnet1)
+}
+
+/// JSON Schema of the configuration using the given environment
+/// defaults.
+pub fn json_schema_with_defaults(defaults: &dyn Defaults) -> Value {
+    json!({
+        "$schema": JSON_SCHEMA_DRAFT,
+        "title": "Witnet node configuration",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "config_version": {
+                "description": "Version of the layout of the configuration",
+                "type": "integer",
+                "minimum": 0,
+                "maximum": migrations::CURRENT_VERSION,
+                "default": migrations::CURRENT_VERSION,
+            },
+            "environment": {
+                "description": "The environment in which the protocol will be deployed: \
+                                \"mainnet\", \"testnet-1\" or \"custom:<name>\" for a custom environment",
+                "type": "string",
+                "pattern": "^(mainnet|testnet-1|custom:.+)$",
+                "default": "testnet-1",
+            },
+            "include": {
+                "description": "Other configuration files to load and merge into this one",
+                "type": "array",
+                "items": { "type": "string" },
+                "default": [],
+            },
+            "profile": {
+                "description": "Named profiles that can be merged over this configuration",
+                "type": "object",
+                "additionalProperties": { "$ref": "#" },
+                "default": {},
+            },
+            "connections": section("Connections-related configuration", json!({
+                "server_addr": socket_addrs(
+                    "Server socket address, or list of addresses, to which it should bind to",
+                    &defaults.connections_server_addr().to_string(),
+                ),
+                "magic_number": integer(
+                    "Magic number identifying the network in protocol messages",
+                    u64::from(defaults.connections_magic_number()),
+                    u64::from(u16::max_value()),
+                ),
+                "inbound_limit": integer(
+                    "Maximum number of concurrent connections the server should accept",
+                    u64::from(defaults.connections_inbound_limit()),
+                    u64::from(u16::max_value()),
+                ),
+                "outbound_limit": integer(
+                    "Maximum number of opened connections to other peers this node has",
+                    u64::from(defaults.connections_outbound_limit()),
+                    u64::from(u16::max_value()),
+                ),
+                "max_connections_per_ip": integer(
+                    "Maximum number of inbound connections from the same IP address",
+                    u64::from(defaults.connections_max_connections_per_ip()),
+                    u64::from(u16::max_value()),
+                ),
+                "max_connections_per_subnet": integer(
+                    "Maximum number of inbound connections from the same /24 (IPv4) or /64 (IPv6) \
+                     subnet",
+                    u64::from(defaults.connections_max_connections_per_subnet()),
+                    u64::from(u16::max_value()),
+                ),
+                "known_peers": {
+                    "description": "Other peer addresses this node knows about at start",
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                    "default": defaults
+                        .connections_known_peers()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>(),
+                },
+                "dns_seeds": {
+                    "description": "Domain names resolved to discover peers, as host or host:port",
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                    "default": defaults
+                        .connections_dns_seeds()
+                        .into_iter()
+                        .collect::<Vec<_>>(),
+                },
+                "whitelist": {
+                    "description": "IP addresses or networks (in CIDR notation) allowed to \
+                                    connect and to be connected to, all of them if empty",
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                    "default": defaults
+                        .connections_whitelist()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>(),
+                },
+                "blacklist": {
+                    "description": "IP addresses or networks (in CIDR notation) never allowed \
+                                    to connect nor to be connected to",
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                    "default": defaults
+                        .connections_blacklist()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>(),
+                },
+                "priority_peers": {
+                    "description": "IP addresses or networks (in CIDR notation) of priority \
+                                    peers, which can connect even when the inbound limit is \
+                                    reached",
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                    "default": defaults
+                        .connections_priority_peers()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>(),
+                },
+                "dns_seeds_period_seconds": seconds(
+                    "Period of the DNS seeds resolution process (in seconds)",
+                    defaults.connections_dns_seeds_period().as_secs(),
+                ),
+                "bootstrap_peers_period_seconds": seconds(
+                    "Period of the outbound peer bootstrapping process (in seconds)",
+                    defaults.connections_bootstrap_peers_period().as_secs(),
+                ),
+                "storage_peers_period_seconds": seconds(
+                    "Period of the known peers backup into storage process (in seconds)",
+                    defaults.connections_storage_peers_period().as_secs(),
+                ),
+                "storage_peers_ttl_seconds": seconds(
+                    "Time after which the stored peers expire if not persisted again (in seconds)",
+                    defaults.connections_storage_peers_ttl().as_secs(),
+                ),
+                "discovery_peers_period_seconds": seconds(
+                    "Period of the peers discovery process (in seconds)",
+                    defaults.connections_discovery_peers_period().as_secs(),
+                ),
+                "feeler_peers_period_seconds": seconds(
+                    "Period of the feeler connections testing the peers not tried yet (in seconds), 0 disables them",
+                    defaults.connections_feeler_peers_period().as_secs(),
+                ),
+                "handshake_timeout_seconds": seconds(
+                    "Timeout for the handshake process (in seconds)",
+                    defaults.connections_handshake_timeout().as_secs(),
+                ),
+                "ping_period_seconds": seconds(
+                    "Time without receiving messages from a peer after which it is pinged (in seconds), 0 disables the pings",
+                    defaults.connections_ping_period().as_secs(),
+                ),
+                "ping_timeout_seconds": seconds(
+                    "Time to wait for the answer to a ping before disconnecting from the peer (in seconds)",
+                    defaults.connections_ping_timeout().as_secs(),
+                ),
+                "ban_threshold": integer(
+                    "Misbehavior score at which a peer gets banned, 0 disables banning",
+                    u64::from(defaults.connections_ban_threshold()),
+                    u64::from(u32::max_value()),
+                ),
+                "ban_duration_seconds": seconds(
+                    "Time during which a banned peer cannot connect to this node (in seconds)",
+                    defaults.connections_ban_duration().as_secs(),
+                ),
+                "max_upload_kbps": integer(
+                    "Maximum upload bandwidth of all the connections with the peers (in kilobits \
+                     per second), 0 means no limit",
+                    u64::from(defaults.connections_max_upload_kbps()),
+                    u64::from(u32::max_value()),
+                ),
+                "max_download_kbps": integer(
+                    "Maximum download bandwidth of all the connections with the peers (in \
+                     kilobits per second), 0 means no limit",
+                    u64::from(defaults.connections_max_download_kbps()),
+                    u64::from(u32::max_value()),
+                ),
+                "max_peer_upload_kbps": integer(
+                    "Maximum upload bandwidth of each connection with a peer (in kilobits per \
+                     second), 0 means no limit",
+                    u64::from(defaults.connections_max_peer_upload_kbps()),
+                    u64::from(u32::max_value()),
+                ),
+                "max_peer_download_kbps": integer(
+                    "Maximum download bandwidth of each connection with a peer (in kilobits per \
+                     second), 0 means no limit",
+                    u64::from(defaults.connections_max_peer_download_kbps()),
+                    u64::from(u32::max_value()),
+                ),
+                "upnp": {
+                    "description": "Ask the router to forward the port of the server to this node",
+                    "type": "boolean",
+                    "default": defaults.connections_upnp(),
+                },
+                "mdns": {
+                    "description": "Discover the peers in the local network using multicast DNS",
+                    "type": "boolean",
+                    "default": defaults.connections_mdns(),
+                },
+                "compression": {
+                    "description": "Compression of the messages exchanged with the peers which \
+                                    support it",
+                    "type": "string",
+                    "enum": MessageCompression::ALL.iter().map(|c| c.name()).collect::<Vec<_>>(),
+                    "default": defaults.connections_compression().name(),
+                },
+                "proxy": {
+                    "description": "URL of the SOCKS5 proxy through which the outbound \
+                                    connections are opened, as socks5://ip:port",
+                    "type": "string",
+                },
+                "tls": section("TLS termination of the inbound connections", json!({
+                    "cert": {
+                        "description": "PEM file containing the certificate chain of the server",
+                        "type": "string",
+                    },
+                    "key": {
+                        "description": "PEM file containing the private key of the server",
+                        "type": "string",
+                    },
+                })),
+                "tls_peers": {
+                    "description": "Peers terminating TLS on their P2P listener, to which the \
+                                    outbound connections are wrapped in TLS",
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "required": ["address", "server_name"],
+                        "properties": {
+                            "address": {
+                                "type": "string",
+                                "pattern": r"^(\[[0-9a-fA-F:.]+\]|[0-9.]+):[0-9]{1,5}$",
+                            },
+                            "server_name": { "type": "string", "minLength": 1 },
+                        },
+                    },
+                    "default": [],
+                },
+                "tls_ca": {
+                    "description": "PEM file containing the certificates of the authorities \
+                                    that sign the certificates of the TLS peers",
+                    "type": "string",
+                },
+                "rate_limits": section(
+                    "Maximum rates at which a peer can send each kind of message (in messages \
+                     per minute), 0 disables a limit",
+                    json!({
+                        "inv": integer(
+                            "Maximum rate of Inv messages",
+                            u64::from(defaults.connections_rate_limits_inv()),
+                            u64::from(u32::max_value()),
+                        ),
+                        "get_data": integer(
+                            "Maximum rate of GetData messages",
+                            u64::from(defaults.connections_rate_limits_get_data()),
+                            u64::from(u32::max_value()),
+                        ),
+                        "peers": integer(
+                            "Maximum rate of GetPeers and Peers messages",
+                            u64::from(defaults.connections_rate_limits_peers()),
+                            u64::from(u32::max_value()),
+                        ),
+                    }),
+                ),
+                "diversity": section(
+                    "Cap of outbound connections with peers of the same network group: the \
+                     same autonomous system, or the same /16 (IPv4) or /32 (IPv6) network",
+                    json!({
+                        "max_outbound_per_group": integer(
+                            "Maximum number of outbound connections with peers of the same \
+                             network group, 0 disables the cap",
+                            u64::from(defaults.connections_diversity_max_outbound_per_group()),
+                            u64::from(u16::max_value()),
+                        ),
+                        "asmap": {
+                            "description": "File mapping the IP networks to the autonomous \
+                                            systems announcing them, one `network asn` per line",
+                            "type": "string",
+                        },
+                    }),
+                ),
+                "limits": section(
+                    "Maximum sizes of the messages received from the peers (once decompressed), \
+                     and time they have to answer the requests of the node",
+                    json!({
+                        "max_message_size": byte_size(
+                            "Maximum size of any message, bigger frames are rejected as soon as \
+                             their length prefix arrives",
+                            defaults.connections_limits_max_message_size(),
+                        ),
+                        "max_block_size": byte_size(
+                            "Maximum size of Block messages",
+                            defaults.connections_limits_max_block_size(),
+                        ),
+                        "max_headers_size": byte_size(
+                            "Maximum size of Headers messages",
+                            defaults.connections_limits_max_headers_size(),
+                        ),
+                        "max_inventory_size": byte_size(
+                            "Maximum size of Inv and GetData messages",
+                            defaults.connections_limits_max_inventory_size(),
+                        ),
+                        "max_peers_size": byte_size(
+                            "Maximum size of Peers messages",
+                            defaults.connections_limits_max_peers_size(),
+                        ),
+                        "get_peers_timeout_seconds": seconds(
+                            "Time a peer has to answer a GetPeers message (in seconds), 0 \
+                             disables the timeout",
+                            defaults.connections_limits_get_peers_timeout().as_secs(),
+                        ),
+                        "get_headers_timeout_seconds": seconds(
+                            "Time a peer has to answer a GetHeaders message (in seconds), 0 \
+                             disables the timeout",
+                            defaults.connections_limits_get_headers_timeout().as_secs(),
+                        ),
+                    }),
+                ),
+                "relay": section(
+                    "Policy for relaying the blocks received from the peers to the other peers",
+                    json!({
+                        "unsolicited": {
+                            "description": "Relay the blocks that the peers send without the \
+                                            node requesting them",
+                            "type": "boolean",
+                            "default": defaults.connections_relay_unsolicited(),
+                        },
+                        "min_reputation": {
+                            "description": "Minimum reputation score of the peers whose blocks \
+                                            are relayed, the peers of inbound connections have \
+                                            a score of 0",
+                            "type": "integer",
+                        },
+                        "unvalidated": {
+                            "description": "Relay the blocks which have not been validated \
+                                            through their headers",
+                            "type": "boolean",
+                            "default": defaults.connections_relay_unvalidated(),
+                        },
+                    }),
+                ),
+            })),
+            "storage": section("Storage-related configuration", json!({
+                "backend": {
+                    "description": "Backend used to store the data",
+                    "type": "string",
+                    "enum": StorageBackend::ALL.iter().map(|b| b.name()).collect::<Vec<_>>(),
+                    "default": defaults.storage_backend().name(),
+                },
+                "db_path": {
+                    "description": "Directory containing the database files",
+                    "type": "string",
+                    "default": defaults.storage_db_path().to_string_lossy(),
+                },
+                "max_db_size": byte_size(
+                    "Maximum size of the database files (0 means no limit)",
+                    defaults.storage_max_db_size(),
+                ),
+                "cache_size": byte_size(
+                    "Size of the cache of recently read values (`0` disables it)",
+                    defaults.storage_cache_size(),
+                ),
+                "read_only": {
+                    "description": "Open the database without writing to it",
+                    "type": "boolean",
+                    "default": defaults.storage_read_only(),
+                },
+                "sync_writes": {
+                    "description": "Wait for every write to reach the disk before returning",
+                    "type": "boolean",
+                    "default": defaults.storage_sync_writes(),
+                },
+                "rocksdb": section("Tuning of the RocksDB backend", json!({
+                    "block_cache_size": byte_size(
+                        "Size of the block cache",
+                        defaults.storage_rocksdb_block_cache_size(),
+                    ),
+                    "write_buffer_size": byte_size(
+                        "Size of the memory buffer used for writes before flushing them to disk",
+                        defaults.storage_rocksdb_write_buffer_size(),
+                    ),
+                    "max_open_files": {
+                        "description": "Maximum number of files opened by the database \
+                                        (-1 means no limit)",
+                        "type": "integer",
+                        "minimum": -1,
+                        "default": defaults.storage_rocksdb_max_open_files(),
+                    },
+                    "wal": {
+                        "description": "Write the changes to the write-ahead log before \
+                                        applying them, so they survive a crash",
+                        "type": "boolean",
+                        "default": defaults.storage_rocksdb_wal(),
+                    },
+                    "compression": {
+                        "description": "Algorithm used to compress the database files",
+                        "type": "string",
+                        "enum": Compression::ALL.iter().map(|c| c.name()).collect::<Vec<_>>(),
+                        "default": defaults.storage_rocksdb_compression().name(),
+                    },
+                    "compaction_style": {
+                        "description": "Strategy used to compact the database files",
+                        "type": "string",
+                        "enum": CompactionStyle::ALL.iter().map(|c| c.name()).collect::<Vec<_>>(),
+                        "default": defaults.storage_rocksdb_compaction_style().name(),
+                    },
+                    "column_families": section(
+                        "Tuning of the column family of each data type, overriding the \
+                         tuning of the whole database",
+                        json!({
+                            "blocks": column_family("Column family of the blocks", defaults),
+                            "transactions": column_family(
+                                "Column family of the transactions",
+                                defaults,
+                            ),
+                            "utxos": column_family(
+                                "Column family of the unspent transaction outputs",
+                                defaults,
+                            ),
+                            "peers": column_family("Column family of the known peers", defaults),
+                            "reputation": column_family(
+                                "Column family of the reputation of the identities",
+                                defaults,
+                            ),
+                        }),
+                    ),
+                })),
+                "pruning": section("Pruning of old blocks", json!({
+                    "enabled": {
+                        "description": "Delete the transactions of old blocks, keeping their \
+                                        headers and the UTXO set",
+                        "type": "boolean",
+                        "default": defaults.storage_pruning_enabled(),
+                    },
+                    "keep_blocks": integer(
+                        "Number of epochs whose blocks are kept entirely",
+                        u64::from(defaults.storage_pruning_keep_blocks()),
+                        u64::from(u32::max_value()),
+                    ),
+                })),
+                "backup": section("Scheduled backups of the storage", json!({
+                    "enabled": {
+                        "description": "Write a backup of the storage periodically",
+                        "type": "boolean",
+                        "default": defaults.storage_backup_enabled(),
+                    },
+                    "interval_seconds": seconds(
+                        "Period of the backups (in seconds)",
+                        defaults.storage_backup_interval().as_secs(),
+                    ),
+                    "destination": {
+                        "description": "Directory where the backups are written",
+                        "type": "string",
+                        "default": defaults.storage_backup_destination().to_string_lossy(),
+                    },
+                    "retention": integer(
+                        "Number of backups to keep, older ones are deleted",
+                        u64::from(defaults.storage_backup_retention()),
+                        u64::from(u32::max_value()),
+                    ),
+                })),
+                "archive": section("Offloading of old blocks to an archive", json!({
+                    "enabled": {
+                        "description": "Move old blocks from the database to an archive, where \
+                                        they can still be read",
+                        "type": "boolean",
+                        "default": defaults.storage_archive_enabled(),
+                    },
+                    "backend": {
+                        "description": "Backend of the archive",
+                        "type": "string",
+                        "enum": ArchiveBackend::ALL.iter().map(|b| b.name()).collect::<Vec<_>>(),
+                        "default": defaults.storage_archive_backend().name(),
+                    },
+                    "path": {
+                        "description": "Directory where the archived blocks are written",
+                        "type": "string",
+                        "default": defaults.storage_archive_path().to_string_lossy(),
+                    },
+                    "keep_blocks": integer(
+                        "Number of epochs whose blocks are kept in the database",
+                        u64::from(defaults.storage_archive_keep_blocks()),
+                        u64::from(u32::max_value()),
+                    ),
+                })),
+                "encryption": section("Encryption of the stored values", json!({
+                    "passphrase": {
+                        "description": "Passphrase from which the encryption key is derived \
+                                        (a secret value)",
+                        "type": "string",
+                    },
+                    "keyfile": {
+                        "description": "File whose contents are used to derive the encryption key",
+                        "type": "string",
+                    },
+                })),
+            })),
+            "consensus_constants": section("Consensus-critical configuration", json!({
+                "checkpoint_zero_timestamp": {
+                    "description": "Timestamp at checkpoint 0 (the start of epoch 0)",
+                    "type": "integer",
+                    "default": defaults.consensus_constants_checkpoint_zero_timestamp(),
+                },
+                "checkpoints_period_seconds": integer(
+                    "Seconds between the start of an epoch and the start of the next one",
+                    u64::from(defaults.consensus_constants_checkpoints_period()),
+                    u64::from(u16::max_value()),
+                ),
+                "genesis_hash": {
+                    "description": "Genesis block hash value",
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "SHA256": {
+                            "type": "array",
+                            "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+                            "minItems": 32,
+                            "maxItems": 32,
+                        },
+                    },
+                },
+                "reputation_demurrage": {
+                    "description": "Decay value for reputation demurrage function",
+                    "type": "number",
+                    "default": defaults.consensus_constants_reputation_demurrage(),
+                },
+                "reputation_punishment": {
+                    "description": "Punishment value for claims out of the consensus bounds",
+                    "type": "number",
+                    "default": defaults.consensus_constants_reputation_punishment(),
+                },
+            })),
+            "jsonrpc": section("JSON-RPC API configuration", json!({
+                "enabled": {
+                    "description": "Enable JSON-RPC server",
+                    "type": "boolean",
+                    "default": defaults.jsonrpc_enabled(),
+                },
+                "server_address": socket_addr(
+                    "JSON-RPC server socket address",
+                    &defaults.jsonrpc_server_address().to_string(),
+                ),
+                "websocket_address": {
+                    "description": "Socket address of the WebSocket transport of the JSON-RPC \
+                                    server, which is only enabled if set",
+                    "type": "string",
+                    "pattern": r"^(\[[0-9a-fA-F:.]+\]|[0-9.]+):[0-9]{1,5}$",
+                },
+                "enabled_methods": {
+                    "description": "Methods served by the JSON-RPC server, all of them if empty",
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                    "default": [],
+                },
+                "disabled_methods": {
+                    "description": "Methods not served by the JSON-RPC server, even if enabled",
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "uniqueItems": true,
+                    "default": [],
+                },
+                "max_requests_per_minute": integer(
+                    "Maximum number of requests per minute from each IP address, 0 disables the \
+                     limit",
+                    u64::from(defaults.jsonrpc_max_requests_per_minute()),
+                    u64::from(u32::max_value()),
+                ),
+                "max_requests_in_flight": integer(
+                    "Maximum number of requests processed at once, 0 disables the limit",
+                    u64::from(defaults.jsonrpc_max_requests_in_flight()),
+                    u64::from(u32::max_value()),
+                ),
+                "auth": section("Authentication of the JSON-RPC clients", json!({
+                    "keys": {
+                        "description": "API keys accepted by the `authenticate` method, each \
+                                        with the methods it gives access to (`*` for all of \
+                                        them), no authentication is required if empty",
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["key", "methods"],
+                            "properties": {
+                                "key": { "type": "string", "minLength": 1 },
+                                "methods": {
+                                    "type": "array",
+                                    "items": { "type": "string" },
+                                    "minItems": 1,
+                                },
+                            },
+                        },
+                        "default": [],
+                    },
+                })),
+                "tls": section("TLS termination of the connections of the clients", json!({
+                    "cert": {
+                        "description": "PEM file containing the certificate chain of the server",
+                        "type": "string",
+                    },
+                    "key": {
+                        "description": "PEM file containing the private key of the server",
+                        "type": "string",
+                    },
+                })),
+                "cors": cors("Origins of the web pages allowed to open WebSocket connections"),
+            })),
+            "rest": section("REST API configuration", json!({
+                "enabled": {
+                    "description": "Enable the REST server",
+                    "type": "boolean",
+                    "default": defaults.rest_enabled(),
+                },
+                "server_address": socket_addr(
+                    "REST server socket address",
+                    &defaults.rest_server_address().to_string(),
+                ),
+                "cors": cors("Origins of the web pages allowed to read the responses"),
+            })),
+            "mining": section("Mining configuration", json!({
+                "enabled": {
+                    "description": "Enable mining",
+                    "type": "boolean",
+                    "default": defaults.mining_enabled(),
+                },
+            })),
+            "wallet": section("Wallet configuration", json!({
+                "enabled": {
+                    "description": "Enable the wallet",
+                    "type": "boolean",
+                    "default": defaults.wallet_enabled(),
+                },
+            })),
+            "metrics": section("Metrics configuration", json!({
+                "enabled": {
+                    "description": "Enable the metrics",
+                    "type": "boolean",
+                    "default": defaults.metrics_enabled(),
+                },
+            })),
+            "bridge": section("Bridge configuration", json!({
+                "enabled": {
+                    "description": "Enable the bridge",
+                    "type": "boolean",
+                    "default": defaults.bridge_enabled(),
+                },
+            })),
+            "identity": section("Node identity configuration", json!({
+                "master_key_path": {
+                    "description": "File containing the secp256k1 master key of the node",
+                    "type": "string",
+                    "default": defaults.identity_master_key_path().to_string_lossy(),
+                },
+                "create_if_missing": {
+                    "description": "Generate a new master key if the file does not exist",
+                    "type": "boolean",
+                    "default": defaults.identity_create_if_missing(),
+                },
+                "mnemonic": {
+                    "description": "Mnemonic phrase from which to import the master key \
+                                    (a secret value)",
+                    "type": "string",
+                },
+            })),
+            "log": section("Logging configuration", json!({
+                "level": {
+                    "description": "Maximum level of the log messages",
+                    "type": "string",
+                    "enum": ["off", "error", "warn", "info", "debug", "trace"],
+                    "default": defaults.log_level().to_string().to_lowercase(),
+                },
+            })),
+            "sync": section("Chain synchronization configuration", json!({
+                "headers_first": {
+                    "description": "Download and validate the block headers before the blocks",
+                    "type": "boolean",
+                    "default": defaults.sync_headers_first(),
+                },
+                "checkpoints": {
+                    "description": "Blocks known to be part of the chain, which the headers received from the peers must contain",
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "required": ["epoch", "hash"],
+                        "properties": {
+                            "epoch": { "type": "integer", "minimum": 0 },
+                            "hash": {
+                                "type": "object",
+                                "additionalProperties": false,
+                                "properties": {
+                                    "SHA256": {
+                                        "type": "array",
+                                        "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+                                        "minItems": 32,
+                                        "maxItems": 32,
+                                    },
+                                },
+                            },
+                        },
+                    },
+                    "default": defaults.sync_checkpoints(),
+                },
+                "download_window": integer(
+                    "Maximum number of blocks requested to the peers and not received yet",
+                    u64::from(defaults.sync_download_window()),
+                    u64::from(u32::max_value()),
+                ),
+                "download_timeout_seconds": seconds(
+                    "Time a peer has to send the blocks requested to it before they are requested to another peer",
+                    defaults.sync_download_timeout().as_secs(),
+                ),
+            })),
+        },
+    })
+}
+
+/// Schema of a configuration section with the given params. Sections
+/// are marked with the `x-section` keyword, as some params are objects
+/// too (e.g.: `consensus_constants.genesis_hash`)
+fn section(description: &str, properties: Value) -> Value {
+    json!({
+        "description": description,
+        "type": "object",
+        "additionalProperties": false,
+        "properties": properties,
+        "x-section": true,
+    })
+}
+
+/// Whether the schema describes a section of the configuration, which
+/// is written as a `[section]` table, instead of a param
+pub fn is_section(schema: &Value) -> bool {
+    schema["x-section"] == true
+}
+
+/// Schema of the cross-origin access from web pages to a server
+fn cors(description: &str) -> Value {
+    section(
+        description,
+        json!({
+            "allowed_origins": {
+                "description": "Origins allowed, e.g.: `http://localhost:8080`, or `*` for any, \
+                                no web page is allowed if empty",
+                "type": "array",
+                "items": { "type": "string", "pattern": r"^(\*|[^:/]+://[^/]+)$" },
+                "uniqueItems": true,
+                "default": [],
+            },
+        }),
+    )
+}
+
+/// Schema of the tuning of a RocksDB column family, whose defaults are
+/// the tuning of the whole database
+fn column_family(description: &str, defaults: &dyn Defaults) -> Value {
+    section(
+        description,
+        json!({
+            "write_buffer_size": byte_size(
+                "Size of the memory buffer used for writes before flushing them to disk",
+                defaults.storage_rocksdb_write_buffer_size(),
+            ),
+            "compression": {
+                "description": "Algorithm used to compress the column family files",
+                "type": "string",
+                "enum": Compression::ALL.iter().map(|c| c.name()).collect::<Vec<_>>(),
+                "default": defaults.storage_rocksdb_compression().name(),
+            },
+            "compaction_style": {
+                "description": "Strategy used to compact the column family files",
+                "type": "string",
+                "enum": CompactionStyle::ALL.iter().map(|c| c.name()).collect::<Vec<_>>(),
+                "default": defaults.storage_rocksdb_compaction_style().name(),
+            },
+        }),
+    )
+}
+
+/// Schema of a socket address param, written as `"ip:port"`
+fn socket_addr(description: &str, default: &str) -> Value {
+    json!({
+        "description": description,
+        "type": "string",
+        "pattern": r"^(\[[0-9a-fA-F:.]+\]|[0-9.]+):[0-9]{1,5}$",
+        "default": default,
+    })
+}
+
+/// Schema of a param representing one or more socket addresses,
+/// written either as a single `"ip:port"` or as a non-empty list
+fn socket_addrs(description: &str, default: &str) -> Value {
+    let pattern = r"^(\[[0-9a-fA-F:.]+\]|[0-9.]+):[0-9]{1,5}$";
+
+    json!({
+        "description": description,
+        "oneOf": [
+            { "type": "string", "pattern": pattern },
+            {
+                "type": "array",
+                "items": { "type": "string", "pattern": pattern },
+                "minItems": 1,
+            },
+        ],
+        "default": default,
+    })
+}
+
+/// Schema of a non-negative integer param
+fn integer(description: &str, default: u64, maximum: u64) -> Value {
+    json!({
+        "description": description,
+        "type": "integer",
+        "minimum": 0,
+        "maximum": maximum,
+        "default": default,
+    })
+}
+
+/// Schema of a param representing a number of bytes, written either
+/// as an integer or as a byte size string such as `"512MB"`
+fn byte_size(description: &str, default: u64) -> Value {
+    json!({
+        "description": description,
+        "oneOf": [
+            { "type": "integer", "minimum": 0 },
+            { "type": "string", "pattern": r"^\s*[0-9]+\s*([bB]|[kKmMgGtT][iI]?[bB])?\s*$" },
+        ],
+        "default": default,
+    })
+}
+
+/// Schema of a param representing a duration in seconds
+fn seconds(description: &str, default: u64) -> Value {
+    json!({
+        "description": description,
+        "type": "integer",
+        "minimum": 0,
+        "default": default,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defaults::Mainnet;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_schema_sections() {
+        let schema = json_schema();
+
+        assert_eq!(schema["$schema"], JSON_SCHEMA_DRAFT);
+        for section in &[
+            "connections",
+            "storage",
+            "consensus_constants",
+            "jsonrpc",
+            "rest",
+            "mining",
+            "wallet",
+            "metrics",
+            "bridge",
+            "identity",
+            "log",
+            "sync",
+        ] {
+            assert_eq!(schema["properties"][section]["type"], "object");
+            assert!(is_section(&schema["properties"][section]));
+        }
+        assert!(!is_section(
+            &schema["properties"]["consensus_constants"]["properties"]["genesis_hash"]
+        ));
+        assert_eq!(
+            schema["properties"]["connections"]["properties"]["handshake_timeout_seconds"]["type"],
+            "integer"
+        );
+    }
+
+    #[test]
+    fn test_schema_defaults() {
+        let testnet = json_schema();
+        let mainnet = json_schema_with_defaults(&Mainnet);
+
+        assert_eq!(
+            testnet["properties"]["connections"]["properties"]["inbound_limit"]["default"],
+            u64::from(Testnet1.connections_inbound_limit())
+        );
+        assert_eq!(
+            testnet["properties"]["storage"]["properties"]["db_path"]["default"],
+            PathBuf::from(".witnet")
+                .join("testnet-1")
+                .join("db")
+                .to_string_lossy()
+                .into_owned()
+        );
+        assert_eq!(
+            mainnet["properties"]["jsonrpc"]["properties"]["server_address"]["default"],
+            "127.0.0.1:11338"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! # Secret values
+//!
+//! Sensitive params, such as private keys or credentials, should not
+//! be written in plain text in configuration files. Params of type
+//! `Secret` accept, besides a plain value, a reference to where the
+//! actual value can be found:
+//!
+//! * `"env:WITNET_NODE_KEY"`: the value of the `WITNET_NODE_KEY`
+//!   environment variable.
+//! * `"file:/run/secrets/node_key"`: the contents of the
+//!   `/run/secrets/node_key` file, without the trailing newline.
+//!
+//! References are resolved when the configuration is loaded, but the
+//! reference (and not the resolved value) is what gets written when
+//! the configuration is serialized back.
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// Prefix of references to environment variables
+pub const ENV_PREFIX: &str = "env:";
+
+/// Prefix of references to files
+pub const FILE_PREFIX: &str = "file:";
+
+/// Where the value of a secret comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretSource {
+    /// Written in plain text in the configuration
+    Plain,
+    /// Read from the environment variable with the given name
+    Env(String),
+    /// Read from the file with the given path
+    File(PathBuf),
+}
+
+/// A sensitive configuration value together with its source
+#[derive(Clone, PartialEq)]
+pub struct Secret {
+    source: SecretSource,
+    value: String,
+}
+
+impl Secret {
+    /// Create a secret from a plain value
+    pub fn plain(value: &str) -> Self {
+        Self {
+            source: SecretSource::Plain,
+            value: value.to_string(),
+        }
+    }
+
+    /// Create a secret from a plain value or a reference (`env:` or
+    /// `file:`), resolving it.
+    pub fn resolve(raw: &str) -> Result<Self, String> {
+        if raw.starts_with(ENV_PREFIX) {
+            let name = &raw[ENV_PREFIX.len()..];
+            let value = env::var(name)
+                .map_err(|e| format!("cannot read environment variable `{}`: {}", name, e))?;
+
+            Ok(Self {
+                source: SecretSource::Env(name.to_string()),
+                value,
+            })
+        } else if raw.starts_with(FILE_PREFIX) {
+            let path = PathBuf::from(&raw[FILE_PREFIX.len()..]);
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| format!("cannot read file `{}`: {}", path.to_string_lossy(), e))?;
+            let value = contents.trim_end_matches(|c| c == '\n' || c == '\r');
+
+            Ok(Self {
+                value: value.to_string(),
+                source: SecretSource::File(path),
+            })
+        } else {
+            Ok(Self::plain(raw))
+        }
+    }
+
+    /// The resolved value of the secret
+    pub fn expose(&self) -> &str {
+        &self.value
+    }
+
+    /// Where the value of the secret comes from
+    pub fn source(&self) -> &SecretSource {
+        &self.source
+    }
+
+    /// The secret as written in the configuration: the reference if
+    /// it has one, or the plain value otherwise.
+    pub fn to_raw(&self) -> String {
+        match &self.source {
+            SecretSource::Plain => self.value.clone(),
+            SecretSource::Env(name) => format!("{}{}", ENV_PREFIX, name),
+            SecretSource::File(path) => format!("{}{}", FILE_PREFIX, path.to_string_lossy()),
+        }
+    }
+}
+
+/// The resolved value is never printed
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret({:?})", self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        Secret::resolve(&raw).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_raw())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_secret() {
+        let secret = Secret::resolve("s3cr3t").unwrap();
+
+        assert_eq!(secret.expose(), "s3cr3t");
+        assert_eq!(secret.source(), &SecretSource::Plain);
+        assert_eq!(secret.to_raw(), "s3cr3t");
+    }
+
+    #[test]
+    fn test_env_secret() {
+        env::set_var("WITNET_TEST_SECRET_VALUE", "s3cr3t");
+        let secret = Secret::resolve("env:WITNET_TEST_SECRET_VALUE").unwrap();
+
+        assert_eq!(secret.expose(), "s3cr3t");
+        assert_eq!(secret.to_raw(), "env:WITNET_TEST_SECRET_VALUE");
+        assert!(Secret::resolve("env:WITNET_TEST_SECRET_MISSING").is_err());
+    }
+
+    #[test]
+    fn test_file_secret() {
+        let path = env::temp_dir().join("witnet_test_secret_value");
+        fs::write(&path, "s3cr3t\n").unwrap();
+        let raw = format!("file:{}", path.to_string_lossy());
+        let secret = Secret::resolve(&raw).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(secret.expose(), "s3cr3t");
+        assert_eq!(secret.to_raw(), raw);
+        assert!(Secret::resolve("file:/nonexistent/witnet_secret").is_err());
+    }
+
+    #[test]
+    fn test_secret_is_not_printed() {
+        let secret = Secret::plain("s3cr3t");
+
+        assert!(!format!("{:?}", secret).contains("s3cr3t"));
+    }
+}
@@ -0,0 +1,1330 @@
+//! # Configuration validation
+//!
+//! The loaders only check that every param has the right type. This
+//! module contains a semantic validation pass over the __total__
+//! [Config](config::Config) that checks the invariants that involve
+//! values of the params, or several params at once, so that all the
+//! problems of a configuration can be reported at once when the node
+//! starts instead of failing at runtime.
+use crate::config::{ArchiveBackend, Config, Cors, StorageBackend, Tls};
+use failure::Fail;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+/// Problems found when validating a configuration
+#[derive(Debug, Clone, PartialEq, Fail)]
+pub enum ValidationError {
+    /// A param that must be greater than zero is zero
+    Zero {
+        /// Name of the param, as written in the Toml file
+        param: &'static str,
+    },
+    /// Two params are configured to bind to the same socket address
+    AddressClash {
+        /// Name of the first param, as written in the Toml file
+        param: &'static str,
+        /// Name of the second param, as written in the Toml file
+        other: &'static str,
+        /// Socket address of the first param
+        addr: SocketAddr,
+    },
+    /// A list of peers contains the address of this node
+    OwnAddress {
+        /// Name of the list of peers, as written in the Toml file
+        param: &'static str,
+        /// Name of the param with the address of this node, as
+        /// written in the Toml file
+        own: &'static str,
+        /// The address of this node found in the list
+        addr: SocketAddr,
+    },
+    /// The value of a param does not make sense given the value of
+    /// another one
+    Conflict {
+        /// Name of the param, as written in the Toml file
+        param: &'static str,
+        /// Name of the other param, as written in the Toml file
+        other: &'static str,
+        /// What is wrong and how to fix it
+        reason: &'static str,
+    },
+    /// The value of a param does not have the expected format
+    Malformed {
+        /// Name of the param, as written in the Toml file
+        param: &'static str,
+        /// What is wrong with the value
+        reason: String,
+    },
+    /// The database path cannot be written
+    NotWritable {
+        /// Name of the param, as written in the Toml file
+        param: &'static str,
+        /// Reason why the path cannot be written
+        reason: String,
+    },
+}
+
+/// Formats the error in a user-friendly manner, naming the params
+/// involved.
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::Zero { param } => write!(f, "`{}` must be greater than 0", param),
+            ValidationError::AddressClash { param, other, addr } => write!(
+                f,
+                "`{}` ({}) clashes with `{}`, they cannot bind to the same address",
+                param, addr, other
+            ),
+            ValidationError::OwnAddress { param, own, addr } => write!(
+                f,
+                "`{}` contains {}, which is the address of this node (`{}`), remove it from the list",
+                param, addr, own
+            ),
+            ValidationError::Conflict {
+                param,
+                other,
+                reason,
+            } => write!(f, "`{}` conflicts with `{}`: {}", param, other, reason),
+            ValidationError::Malformed { param, reason } => {
+                write!(f, "`{}` is malformed: {}", param, reason)
+            }
+            ValidationError::NotWritable { param, reason } => {
+                write!(f, "`{}` is not writable: {}", param, reason)
+            }
+        }
+    }
+}
+
+/// Check all the semantic invariants of a configuration, returning
+/// every problem found.
+pub fn validate(config: &Config) -> Result<(), Vec<ValidationError>> {
+    let mut errors = vec![];
+
+    check_greater_than_zero(
+        &mut errors,
+        "connections.outbound_limit",
+        u64::from(config.connections.outbound_limit),
+    );
+    check_greater_than_zero(
+        &mut errors,
+        "connections.max_connections_per_ip",
+        u64::from(config.connections.max_connections_per_ip),
+    );
+    if config.connections.max_connections_per_subnet < config.connections.max_connections_per_ip {
+        errors.push(ValidationError::Conflict {
+            param: "connections.max_connections_per_subnet",
+            other: "connections.max_connections_per_ip",
+            reason: "the connections from an IP address count for its subnet too, \
+                     set a subnet limit not lower than the IP address limit",
+        });
+    }
+    check_greater_than_zero(
+        &mut errors,
+        "connections.handshake_timeout_seconds",
+        config.connections.handshake_timeout.as_secs(),
+    );
+    check_greater_than_zero(
+        &mut errors,
+        "sync.download_window",
+        u64::from(config.sync.download_window),
+    );
+    check_greater_than_zero(
+        &mut errors,
+        "sync.download_timeout_seconds",
+        config.sync.download_timeout.as_secs(),
+    );
+    if config.connections.ban_threshold > 0 {
+        check_greater_than_zero(
+            &mut errors,
+            "connections.ban_duration_seconds",
+            config.connections.ban_duration.as_secs(),
+        );
+    }
+    let limits = &config.connections.limits;
+    for (param, size) in &[
+        (
+            "connections.limits.max_message_size",
+            limits.max_message_size,
+        ),
+        ("connections.limits.max_block_size", limits.max_block_size),
+        (
+            "connections.limits.max_headers_size",
+            limits.max_headers_size,
+        ),
+        (
+            "connections.limits.max_inventory_size",
+            limits.max_inventory_size,
+        ),
+        ("connections.limits.max_peers_size", limits.max_peers_size),
+    ] {
+        check_greater_than_zero(&mut errors, param, *size);
+    }
+    if config.connections.storage_peers_ttl <= config.connections.storage_peers_period {
+        errors.push(ValidationError::Conflict {
+            param: "connections.storage_peers_ttl_seconds",
+            other: "connections.storage_peers_period_seconds",
+            reason: "the stored peers would expire before being persisted again, \
+                     set a TTL longer than the period",
+        });
+    }
+    check_greater_than_zero(
+        &mut errors,
+        "storage.rocksdb.write_buffer_size",
+        config.storage.rocksdb.write_buffer_size,
+    );
+    let column_families = &config.storage.rocksdb.column_families;
+    for (param, column_family) in &[
+        (
+            "storage.rocksdb.column_families.blocks.write_buffer_size",
+            &column_families.blocks,
+        ),
+        (
+            "storage.rocksdb.column_families.transactions.write_buffer_size",
+            &column_families.transactions,
+        ),
+        (
+            "storage.rocksdb.column_families.utxos.write_buffer_size",
+            &column_families.utxos,
+        ),
+        (
+            "storage.rocksdb.column_families.peers.write_buffer_size",
+            &column_families.peers,
+        ),
+        (
+            "storage.rocksdb.column_families.reputation.write_buffer_size",
+            &column_families.reputation,
+        ),
+    ] {
+        check_greater_than_zero(&mut errors, param, column_family.write_buffer_size);
+    }
+    if config.storage.pruning.enabled {
+        check_greater_than_zero(
+            &mut errors,
+            "storage.pruning.keep_blocks",
+            u64::from(config.storage.pruning.keep_blocks),
+        );
+    }
+    if config.storage.backup.enabled {
+        check_greater_than_zero(
+            &mut errors,
+            "storage.backup.interval_seconds",
+            config.storage.backup.interval.as_secs(),
+        );
+        check_greater_than_zero(
+            &mut errors,
+            "storage.backup.retention",
+            u64::from(config.storage.backup.retention),
+        );
+    }
+    if config.storage.archive.enabled {
+        check_greater_than_zero(
+            &mut errors,
+            "storage.archive.keep_blocks",
+            u64::from(config.storage.archive.keep_blocks),
+        );
+    }
+    check_greater_than_zero(
+        &mut errors,
+        "consensus_constants.checkpoints_period_seconds",
+        u64::from(config.consensus_constants.checkpoints_period),
+    );
+
+    let server_addrs = &config.connections.server_addrs;
+    for (i, server_addr) in server_addrs.iter().enumerate() {
+        if server_addrs[..i]
+            .iter()
+            .any(|other| addresses_clash(other, server_addr))
+        {
+            errors.push(ValidationError::AddressClash {
+                param: "connections.server_addr",
+                other: "connections.server_addr",
+                addr: *server_addr,
+            });
+        }
+        if config.jsonrpc.enabled && addresses_clash(server_addr, &config.jsonrpc.server_address) {
+            errors.push(ValidationError::AddressClash {
+                param: "connections.server_addr",
+                other: "jsonrpc.server_address",
+                addr: *server_addr,
+            });
+        }
+    }
+
+    if let (true, Some(websocket_address)) =
+        (config.jsonrpc.enabled, config.jsonrpc.websocket_address)
+    {
+        let clashes = server_addrs
+            .iter()
+            .map(|server_addr| ("connections.server_addr", server_addr))
+            .chain(Some((
+                "jsonrpc.server_address",
+                &config.jsonrpc.server_address,
+            )))
+            .filter(|(_, addr)| addresses_clash(addr, &websocket_address));
+        for (other, _) in clashes {
+            errors.push(ValidationError::AddressClash {
+                param: "jsonrpc.websocket_address",
+                other,
+                addr: websocket_address,
+            });
+        }
+    }
+
+    if config.rest.enabled {
+        let mut listeners: Vec<(&'static str, SocketAddr)> = server_addrs
+            .iter()
+            .map(|server_addr| ("connections.server_addr", *server_addr))
+            .collect();
+        if config.jsonrpc.enabled {
+            listeners.push(("jsonrpc.server_address", config.jsonrpc.server_address));
+            listeners.extend(
+                config
+                    .jsonrpc
+                    .websocket_address
+                    .map(|addr| ("jsonrpc.websocket_address", addr)),
+            );
+        }
+        for (other, addr) in listeners {
+            if addresses_clash(&addr, &config.rest.server_address) {
+                errors.push(ValidationError::AddressClash {
+                    param: "rest.server_address",
+                    other,
+                    addr: config.rest.server_address,
+                });
+            }
+        }
+    }
+
+    let mut known_peers: Vec<&SocketAddr> = config.connections.known_peers.iter().collect();
+    known_peers.sort();
+    for addr in known_peers {
+        if server_addrs
+            .iter()
+            .any(|server_addr| is_own_address(server_addr, addr))
+        {
+            errors.push(ValidationError::OwnAddress {
+                param: "connections.known_peers",
+                own: "connections.server_addr",
+                addr: *addr,
+            });
+        }
+    }
+
+    let connections = &config.connections;
+    if connections.known_peers.iter().any(|addr| {
+        connections
+            .blacklist
+            .iter()
+            .any(|net| net.contains(addr.ip()))
+    }) {
+        errors.push(ValidationError::Conflict {
+            param: "connections.known_peers",
+            other: "connections.blacklist",
+            reason: "some known peers are blacklisted, remove them from one of the lists",
+        });
+    }
+    if !connections.whitelist.is_empty()
+        && connections.known_peers.iter().any(|addr| {
+            !connections
+                .whitelist
+                .iter()
+                .any(|net| net.contains(addr.ip()))
+        })
+    {
+        errors.push(ValidationError::Conflict {
+            param: "connections.known_peers",
+            other: "connections.whitelist",
+            reason: "some known peers are not whitelisted, add them to the whitelist \
+                     or remove them from the known peers",
+        });
+    }
+
+    if connections.priority_peers.iter().any(|priority| {
+        connections
+            .blacklist
+            .iter()
+            .any(|net| net.contains(priority.address()))
+    }) {
+        errors.push(ValidationError::Conflict {
+            param: "connections.priority_peers",
+            other: "connections.blacklist",
+            reason: "some priority peers are blacklisted, remove them from one of the lists",
+        });
+    }
+
+    let mut dns_seeds: Vec<&String> = config.connections.dns_seeds.iter().collect();
+    dns_seeds.sort();
+    for seed in dns_seeds {
+        if let Err(reason) = config.connections.parse_dns_seed(seed) {
+            errors.push(ValidationError::Malformed {
+                param: "connections.dns_seeds",
+                reason,
+            });
+        }
+    }
+    if !config.connections.dns_seeds.is_empty() {
+        check_greater_than_zero(
+            &mut errors,
+            "connections.dns_seeds_period_seconds",
+            config.connections.dns_seeds_period.as_secs(),
+        );
+    }
+
+    if config.mining.enabled && config.connections.inbound_limit == 0 {
+        errors.push(ValidationError::Conflict {
+            param: "connections.inbound_limit",
+            other: "mining.enabled",
+            reason: "a mining node must accept inbound connections, \
+                     set the limit to a value greater than 0 or disable mining",
+        });
+    }
+
+    if let Err(reason) = config.connections.proxy_address() {
+        errors.push(ValidationError::Malformed {
+            param: "connections.proxy",
+            reason,
+        });
+    }
+
+    check_tls(
+        &mut errors,
+        &config.connections.tls,
+        ("connections.tls.cert", "connections.tls.key"),
+    );
+    check_tls(
+        &mut errors,
+        &config.jsonrpc.tls,
+        ("jsonrpc.tls.cert", "jsonrpc.tls.key"),
+    );
+    check_tls_peers(&mut errors, config);
+
+    check_identity(&mut errors, config);
+    check_api_keys(&mut errors, config);
+    check_cors(
+        &mut errors,
+        &config.jsonrpc.cors,
+        "jsonrpc.cors.allowed_origins",
+    );
+    check_cors(&mut errors, &config.rest.cors, "rest.cors.allowed_origins");
+
+    if config.storage.encryption.passphrase.is_some() && config.storage.encryption.keyfile.is_some()
+    {
+        errors.push(ValidationError::Conflict {
+            param: "storage.encryption.passphrase",
+            other: "storage.encryption.keyfile",
+            reason: "the encryption key is derived either from a passphrase or from a key file, \
+                     remove one of them",
+        });
+    }
+
+    if config.storage.read_only && config.storage.backend == StorageBackend::Memory {
+        errors.push(ValidationError::Conflict {
+            param: "storage.read_only",
+            other: "storage.backend",
+            reason: "a memory storage starts empty, so it cannot be read-only",
+        });
+    }
+
+    if config.storage.sync_writes
+        && !config.storage.rocksdb.wal
+        && config.storage.backend == StorageBackend::RocksDB
+    {
+        errors.push(ValidationError::Conflict {
+            param: "storage.sync_writes",
+            other: "storage.rocksdb.wal",
+            reason: "RocksDB syncs the writes through the write-ahead log, \
+                     enable it or disable `sync_writes`",
+        });
+    }
+
+    // The memory backend does not write to `db_path`, and read-only
+    // storages do not need to write to it
+    if config.storage.backend != StorageBackend::Memory && !config.storage.read_only {
+        if let Err(reason) = check_writable(&config.storage.db_path) {
+            errors.push(ValidationError::NotWritable {
+                param: "storage.db_path",
+                reason,
+            });
+        }
+    }
+
+    if config.storage.backup.enabled {
+        if config.storage.backup.destination == config.storage.db_path {
+            errors.push(ValidationError::Conflict {
+                param: "storage.backup.destination",
+                other: "storage.db_path",
+                reason: "backups cannot be written to the database directory",
+            });
+        } else if let Err(reason) = check_writable(&config.storage.backup.destination) {
+            errors.push(ValidationError::NotWritable {
+                param: "storage.backup.destination",
+                reason,
+            });
+        }
+    }
+
+    let archive = &config.storage.archive;
+    if archive.enabled {
+        match archive.backend {
+            ArchiveBackend::Directory if archive.path == config.storage.db_path => {
+                errors.push(ValidationError::Conflict {
+                    param: "storage.archive.path",
+                    other: "storage.db_path",
+                    reason: "blocks cannot be archived to the database directory",
+                });
+            }
+            ArchiveBackend::Directory => {
+                if let Err(reason) = check_writable(&archive.path) {
+                    errors.push(ValidationError::NotWritable {
+                        param: "storage.archive.path",
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_greater_than_zero(errors: &mut Vec<ValidationError>, param: &'static str, value: u64) {
+    if value == 0 {
+        errors.push(ValidationError::Zero { param });
+    }
+}
+
+/// Number of words accepted in a mnemonic phrase
+const MNEMONIC_LENGTHS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Check that the master key of the node can be loaded, imported or
+/// generated, and only in one way.
+fn check_identity(errors: &mut Vec<ValidationError>, config: &Config) {
+    let identity = &config.identity;
+    let key_exists = identity.master_key_path.exists();
+
+    match &identity.mnemonic {
+        Some(mnemonic) => {
+            let words = mnemonic.expose().split_whitespace().count();
+            if !MNEMONIC_LENGTHS.contains(&words) {
+                errors.push(ValidationError::Malformed {
+                    param: "identity.mnemonic",
+                    reason: format!("expected 12, 15, 18, 21 or 24 words but found {}", words),
+                });
+            }
+            if key_exists {
+                errors.push(ValidationError::Conflict {
+                    param: "identity.mnemonic",
+                    other: "identity.master_key_path",
+                    reason: "the master key file already exists, \
+                             remove the mnemonic or the file",
+                });
+            }
+        }
+        None if !key_exists && !identity.create_if_missing => {
+            errors.push(ValidationError::Conflict {
+                param: "identity.master_key_path",
+                other: "identity.create_if_missing",
+                reason: "the master key file does not exist and cannot be generated, \
+                         create it, give a mnemonic or set `create_if_missing` to true",
+            });
+        }
+        None => {}
+    }
+}
+
+/// Check that a TLS certificate is not given without its private key,
+/// nor the other way around, given the names of both params.
+fn check_tls(
+    errors: &mut Vec<ValidationError>,
+    tls: &Tls,
+    (cert_param, key_param): (&'static str, &'static str),
+) {
+    match (&tls.cert, &tls.key) {
+        (Some(_), None) => errors.push(ValidationError::Conflict {
+            param: cert_param,
+            other: key_param,
+            reason: "a TLS certificate cannot be used without its private key, \
+                     add the key or remove the certificate",
+        }),
+        (None, Some(_)) => errors.push(ValidationError::Conflict {
+            param: key_param,
+            other: cert_param,
+            reason: "a TLS private key cannot be used without its certificate, \
+                     add the certificate or remove the key",
+        }),
+        _ => {}
+    }
+}
+
+/// Check that the certificates of the TLS peers can be verified, and
+/// that their names can be found in a certificate.
+fn check_tls_peers(errors: &mut Vec<ValidationError>, config: &Config) {
+    let tls_peers = &config.connections.tls_peers;
+    if !tls_peers.is_empty() && config.connections.tls_ca.is_none() {
+        errors.push(ValidationError::Conflict {
+            param: "connections.tls_peers",
+            other: "connections.tls_ca",
+            reason: "the certificates of the TLS peers cannot be verified without the \
+                     certificates of their authorities, add them",
+        });
+    }
+    for tls_peer in tls_peers {
+        let name = &tls_peer.server_name;
+        let valid = name.parse::<IpAddr>().is_err()
+            && name.split('.').all(|label| {
+                !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if !valid {
+            errors.push(ValidationError::Malformed {
+                param: "connections.tls_peers",
+                reason: format!(
+                    "`{}` (server name of {}) is not a DNS name",
+                    name, tls_peer.address
+                ),
+            });
+        }
+    }
+}
+
+/// Check that every API key of the JSON-RPC clients can be told apart
+/// from the others and gives access to some method.
+fn check_api_keys(errors: &mut Vec<ValidationError>, config: &Config) {
+    let keys = &config.jsonrpc.auth.keys;
+    for (i, api_key) in keys.iter().enumerate() {
+        if api_key.key.expose().is_empty() {
+            errors.push(ValidationError::Malformed {
+                param: "jsonrpc.auth.keys",
+                reason: format!("key #{} is empty", i + 1),
+            });
+        } else if keys[..i]
+            .iter()
+            .any(|other| other.key.expose() == api_key.key.expose())
+        {
+            errors.push(ValidationError::Malformed {
+                param: "jsonrpc.auth.keys",
+                reason: format!("key #{} is repeated", i + 1),
+            });
+        }
+        if api_key.methods.is_empty() {
+            errors.push(ValidationError::Malformed {
+                param: "jsonrpc.auth.keys",
+                reason: format!("key #{} does not give access to any method", i + 1),
+            });
+        }
+    }
+}
+
+/// Check that every allowed origin is `*` or looks like the origin
+/// sent by browsers: a scheme and a host, with an optional port but
+/// without any path, given the name of the param.
+fn check_cors(errors: &mut Vec<ValidationError>, cors: &Cors, param: &'static str) {
+    for origin in &cors.allowed_origins {
+        if origin == "*" {
+            continue;
+        }
+        let host = origin
+            .find("://")
+            .filter(|&i| i > 0)
+            .map(|i| &origin[i + 3..]);
+        match host {
+            Some(host) if !host.is_empty() && !host.contains('/') => {}
+            _ => errors.push(ValidationError::Malformed {
+                param,
+                reason: format!(
+                    "origin `{}` is not `*` nor a scheme and a host, e.g.: \
+                     `http://localhost:8080`",
+                    origin
+                ),
+            }),
+        }
+    }
+}
+
+/// Two socket addresses clash if they use the same port and the same
+/// IP, or any of them binds to all the interfaces.
+fn addresses_clash(a: &SocketAddr, b: &SocketAddr) -> bool {
+    a.port() == b.port() && (a.ip() == b.ip() || a.ip().is_unspecified() || b.ip().is_unspecified())
+}
+
+/// Whether a peer address points to this node, given the address the
+/// node binds to. When binding to all the interfaces, any loopback
+/// address with the same port points to this node.
+fn is_own_address(server_addr: &SocketAddr, peer: &SocketAddr) -> bool {
+    server_addr == peer
+        || (server_addr.ip().is_unspecified()
+            && server_addr.port() == peer.port()
+            && peer.ip().is_loopback())
+}
+
+/// Check that a directory can be written, or that it can be created
+/// if it does not exist yet.
+fn check_writable(path: &Path) -> Result<(), String> {
+    // Find the closest ancestor that exists, the rest of the
+    // directories will be created by the storage backend
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.as_os_str().is_empty() || ancestor.exists());
+    let existing = match existing {
+        Some(ancestor) if ancestor.as_os_str().is_empty() => Path::new("."),
+        Some(ancestor) => ancestor,
+        None => return Ok(()),
+    };
+
+    let metadata = fs::metadata(existing)
+        .map_err(|e| format!("cannot access {}: {}", existing.to_string_lossy(), e))?;
+    if !metadata.is_dir() {
+        Err(format!("{} is not a directory", existing.to_string_lossy()))
+    } else if metadata.permissions().readonly() {
+        Err(format!("{} is read-only", existing.to_string_lossy()))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ApiKey, TlsPeer};
+    use crate::secret::Secret;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_validate_default_config() {
+        assert_eq!(validate(&Config::default()), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_storage_peers_ttl() {
+        let mut config = Config::default();
+        config.connections.storage_peers_ttl = config.connections.storage_peers_period;
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::Conflict {
+                param: "connections.storage_peers_ttl_seconds",
+                other: "connections.storage_peers_period_seconds",
+                reason: "the stored peers would expire before being persisted again, \
+                         set a TTL longer than the period",
+            }])
+        );
+    }
+
+    #[test]
+    fn test_validate_ban_duration() {
+        let mut config = Config::default();
+        config.connections.ban_duration = Duration::from_secs(0);
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::Zero {
+                param: "connections.ban_duration_seconds"
+            }])
+        );
+
+        // The duration does not matter if banning is disabled
+        config.connections.ban_threshold = 0;
+
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_message_limits() {
+        let mut config = Config::default();
+        config.connections.limits.max_message_size = 0;
+        config.connections.limits.max_peers_size = 0;
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![
+                ValidationError::Zero {
+                    param: "connections.limits.max_message_size"
+                },
+                ValidationError::Zero {
+                    param: "connections.limits.max_peers_size"
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_max_connections_per_subnet() {
+        let mut config = Config::default();
+        config.connections.max_connections_per_ip = 8;
+        config.connections.max_connections_per_subnet = 4;
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::Conflict {
+                param: "connections.max_connections_per_subnet",
+                other: "connections.max_connections_per_ip",
+                reason: "the connections from an IP address count for its subnet too, \
+                         set a subnet limit not lower than the IP address limit",
+            }])
+        );
+    }
+
+    #[test]
+    fn test_validate_dns_seeds() {
+        let mut config = Config::default();
+        config
+            .connections
+            .dns_seeds
+            .insert("seed.example.com".to_string());
+        config
+            .connections
+            .dns_seeds
+            .insert("seed.example.com:21337".to_string());
+
+        assert_eq!(validate(&config), Ok(()));
+
+        config.connections.dns_seeds.insert(":21337".to_string());
+        config
+            .connections
+            .dns_seeds
+            .insert("seed.example.com:port".to_string());
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![
+                ValidationError::Malformed {
+                    param: "connections.dns_seeds",
+                    reason: "missing host in `:21337`".to_string(),
+                },
+                ValidationError::Malformed {
+                    param: "connections.dns_seeds",
+                    reason: "invalid port in `seed.example.com:port`".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_all_errors() {
+        let mut config = Config::default();
+        config.connections.outbound_limit = 0;
+        config.connections.handshake_timeout = Duration::from_secs(0);
+        config.connections.server_addrs = vec!["0.0.0.0:4000".parse().unwrap()];
+        config.jsonrpc.server_address = "127.0.0.1:4000".parse().unwrap();
+
+        let errors = validate(&config).unwrap_err();
+
+        assert_eq!(errors.len(), 3);
+        assert!(errors.contains(&ValidationError::Zero {
+            param: "connections.outbound_limit"
+        }));
+        assert!(errors.contains(&ValidationError::AddressClash {
+            param: "connections.server_addr",
+            other: "jsonrpc.server_address",
+            addr: config.connections.server_addr(),
+        }));
+    }
+
+    #[test]
+    fn test_address_clash_ignored_when_jsonrpc_disabled() {
+        let mut config = Config::default();
+        config.jsonrpc.enabled = false;
+        config.jsonrpc.server_address = config.connections.server_addr();
+
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_websocket_address_clash() {
+        let mut config = Config::default();
+        config.jsonrpc.websocket_address = Some(config.jsonrpc.server_address);
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::AddressClash {
+                param: "jsonrpc.websocket_address",
+                other: "jsonrpc.server_address",
+                addr: config.jsonrpc.server_address,
+            }])
+        );
+
+        config.jsonrpc.websocket_address = Some("127.0.0.1:4100".parse().unwrap());
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_rest_address_clash() {
+        let mut config = Config::default();
+        config.rest.enabled = true;
+        config.rest.server_address = config.jsonrpc.server_address;
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::AddressClash {
+                param: "rest.server_address",
+                other: "jsonrpc.server_address",
+                addr: config.jsonrpc.server_address,
+            }])
+        );
+
+        // The address of a disabled REST server does not clash
+        config.rest.enabled = false;
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_server_addresses_clash() {
+        let mut config = Config::default();
+        config.jsonrpc.enabled = false;
+        config.connections.server_addrs = vec![
+            "0.0.0.0:4000".parse().unwrap(),
+            "192.168.1.2:4000".parse().unwrap(),
+            "192.168.1.2:4001".parse().unwrap(),
+        ];
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::AddressClash {
+                param: "connections.server_addr",
+                other: "connections.server_addr",
+                addr: "192.168.1.2:4000".parse().unwrap(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_addresses_clash() {
+        let a: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+        let b: SocketAddr = "192.168.1.1:4000".parse().unwrap();
+        let c: SocketAddr = "0.0.0.0:4000".parse().unwrap();
+        let d: SocketAddr = "0.0.0.0:4001".parse().unwrap();
+
+        assert!(addresses_clash(&a, &a));
+        assert!(!addresses_clash(&a, &b));
+        assert!(addresses_clash(&a, &c));
+        assert!(!addresses_clash(&c, &d));
+    }
+
+    #[test]
+    fn test_read_only_memory_storage() {
+        let mut config = Config::default();
+        config.storage.backend = StorageBackend::Memory;
+        config.storage.read_only = true;
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::Conflict {
+                param: "storage.read_only",
+                other: "storage.backend",
+                reason: "a memory storage starts empty, so it cannot be read-only",
+            }])
+        );
+    }
+
+    #[test]
+    fn test_known_peers_contain_own_address() {
+        let mut config = Config::default();
+        config.connections.server_addrs = vec![
+            "192.168.1.2:4001".parse().unwrap(),
+            "0.0.0.0:4000".parse().unwrap(),
+        ];
+        config.jsonrpc.enabled = false;
+        config
+            .connections
+            .known_peers
+            .insert("127.0.0.1:4000".parse().unwrap());
+        config
+            .connections
+            .known_peers
+            .insert("192.168.1.1:4000".parse().unwrap());
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::OwnAddress {
+                param: "connections.known_peers",
+                own: "connections.server_addr",
+                addr: "127.0.0.1:4000".parse().unwrap(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_known_peers_contain_own_address_ipv6() {
+        let mut config = Config::default();
+        config.connections.server_addrs = vec!["[::]:4000".parse().unwrap()];
+        config.jsonrpc.enabled = false;
+        config
+            .connections
+            .known_peers
+            .insert("[::1]:4000".parse().unwrap());
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::OwnAddress {
+                param: "connections.known_peers",
+                own: "connections.server_addr",
+                addr: "[::1]:4000".parse().unwrap(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_mining_without_inbound_connections() {
+        let mut config = Config::default();
+        config.connections.inbound_limit = 0;
+
+        assert_eq!(validate(&config), Ok(()));
+
+        config.mining.enabled = true;
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "connections.inbound_limit");
+                assert_eq!(*other, "mining.enabled");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn test_db_path_not_a_directory() {
+        let mut config = Config::default();
+        config.storage.db_path = PathBuf::from("Cargo.toml/db");
+
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::NotWritable { param, .. }] => assert_eq!(*param, "storage.db_path"),
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn test_validate_known_peers_access_lists() {
+        let mut config = Config::default();
+        config.connections.known_peers.clear();
+        config
+            .connections
+            .known_peers
+            .insert("10.0.0.1:21337".parse().unwrap());
+        config
+            .connections
+            .whitelist
+            .insert("192.168.0.0/16".parse().unwrap());
+        config
+            .connections
+            .blacklist
+            .insert("10.0.0.0/24".parse().unwrap());
+
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict {
+                other: blacklist, ..
+            }, ValidationError::Conflict {
+                other: whitelist, ..
+            }] => {
+                assert_eq!(*blacklist, "connections.blacklist");
+                assert_eq!(*whitelist, "connections.whitelist");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+
+        config.connections.blacklist.clear();
+        config
+            .connections
+            .whitelist
+            .insert("10.0.0.0/8".parse().unwrap());
+
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_priority_peers_blacklisted() {
+        let mut config = Config::default();
+        config
+            .connections
+            .priority_peers
+            .insert("10.0.0.1".parse().unwrap());
+        config
+            .connections
+            .blacklist
+            .insert("10.0.0.0/24".parse().unwrap());
+
+        match &validate(&config).unwrap_err()[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "connections.priority_peers");
+                assert_eq!(*other, "connections.blacklist");
+            }
+            errors => panic!("Unexpected errors: {:?}", errors),
+        }
+
+        config.connections.blacklist.clear();
+
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_proxy() {
+        let mut config = Config::default();
+        config.connections.proxy = Some("socks5://127.0.0.1:9050".to_string());
+
+        assert_eq!(validate(&config), Ok(()));
+
+        for proxy in &[
+            "127.0.0.1:9050",
+            "socks5://localhost:9050",
+            "http://127.0.0.1:8080",
+        ] {
+            config.connections.proxy = Some(proxy.to_string());
+            let errors = validate(&config).unwrap_err();
+
+            match &errors[..] {
+                [ValidationError::Malformed { param, .. }] => {
+                    assert_eq!(*param, "connections.proxy")
+                }
+                _ => panic!("Unexpected errors: {:?}", errors),
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_tls() {
+        let mut config = Config::default();
+        config.connections.tls.cert = Some(PathBuf::from("/etc/witnet/cert.pem"));
+
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "connections.tls.cert");
+                assert_eq!(*other, "connections.tls.key");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+
+        config.connections.tls.key = Some(PathBuf::from("/etc/witnet/key.pem"));
+
+        assert_eq!(validate(&config), Ok(()));
+
+        config.jsonrpc.tls.key = Some(PathBuf::from("/etc/witnet/rpc/key.pem"));
+
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "jsonrpc.tls.key");
+                assert_eq!(*other, "jsonrpc.tls.cert");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn test_validate_tls_peers() {
+        let mut config = Config::default();
+        config.connections.tls_peers = vec![
+            TlsPeer {
+                address: "203.0.113.7:21337".parse().unwrap(),
+                server_name: "node.example.com".to_string(),
+            },
+            TlsPeer {
+                address: "203.0.113.8:21337".parse().unwrap(),
+                server_name: "203.0.113.8".to_string(),
+            },
+        ];
+
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }, ValidationError::Malformed { reason, .. }] =>
+            {
+                assert_eq!(*param, "connections.tls_peers");
+                assert_eq!(*other, "connections.tls_ca");
+                assert!(reason.contains("203.0.113.8:21337"));
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+
+        config.connections.tls_peers.pop();
+        config.connections.tls_ca = Some(PathBuf::from("/etc/witnet/ca.pem"));
+
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_api_keys() {
+        let api_key = |key: &str, methods: &[&str]| ApiKey {
+            key: Secret::plain(key),
+            methods: methods.iter().map(ToString::to_string).collect(),
+        };
+        let mut config = Config::default();
+        config.jsonrpc.auth.keys = vec![api_key("s3cr3t", &["getPeers"]), api_key("adm1n", &["*"])];
+
+        assert_eq!(validate(&config), Ok(()));
+
+        config.jsonrpc.auth.keys = vec![
+            api_key("s3cr3t", &["getPeers"]),
+            api_key("", &["getPeers"]),
+            api_key("s3cr3t", &[]),
+        ];
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![
+                ValidationError::Malformed {
+                    param: "jsonrpc.auth.keys",
+                    reason: "key #2 is empty".to_string(),
+                },
+                ValidationError::Malformed {
+                    param: "jsonrpc.auth.keys",
+                    reason: "key #3 is repeated".to_string(),
+                },
+                ValidationError::Malformed {
+                    param: "jsonrpc.auth.keys",
+                    reason: "key #3 does not give access to any method".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_cors() {
+        let mut config = Config::default();
+        config.jsonrpc.cors.allowed_origins = vec![
+            "http://localhost:8080".to_string(),
+            "https://explorer.example.com".to_string(),
+        ];
+        config.rest.cors.allowed_origins = vec!["*".to_string()];
+
+        assert_eq!(validate(&config), Ok(()));
+
+        config.rest.cors.allowed_origins = vec![
+            "localhost:8080".to_string(),
+            "http://localhost:8080/".to_string(),
+        ];
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![
+                ValidationError::Malformed {
+                    param: "rest.cors.allowed_origins",
+                    reason: "origin `localhost:8080` is not `*` nor a scheme and a host, e.g.: \
+                             `http://localhost:8080`"
+                        .to_string(),
+                },
+                ValidationError::Malformed {
+                    param: "rest.cors.allowed_origins",
+                    reason: "origin `http://localhost:8080/` is not `*` nor a scheme and a host, \
+                             e.g.: `http://localhost:8080`"
+                        .to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_encryption_passphrase_and_keyfile() {
+        let mut config = Config::default();
+        config.storage.encryption.passphrase = Some(Secret::plain("passphrase"));
+
+        assert_eq!(validate(&config), Ok(()));
+
+        config.storage.encryption.keyfile = Some(PathBuf::from("/run/secrets/db.key"));
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "storage.encryption.passphrase");
+                assert_eq!(*other, "storage.encryption.keyfile");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn test_backup_destination() {
+        let mut config = Config::default();
+        config.storage.backup.enabled = true;
+        config.storage.backup.destination = config.storage.db_path.clone();
+
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "storage.backup.destination");
+                assert_eq!(*other, "storage.db_path");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn test_sync_writes_without_wal() {
+        let mut config = Config::default();
+        config.storage.sync_writes = true;
+        assert_eq!(validate(&config), Ok(()));
+
+        config.storage.rocksdb.wal = false;
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "storage.sync_writes");
+                assert_eq!(*other, "storage.rocksdb.wal");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn test_archive() {
+        let mut config = Config::default();
+        config.storage.archive.enabled = true;
+        config.storage.archive.path = config.storage.db_path.clone();
+
+        let errors = validate(&config).unwrap_err();
+
+        match &errors[..] {
+            [ValidationError::Conflict { param, other, .. }] => {
+                assert_eq!(*param, "storage.archive.path");
+                assert_eq!(*other, "storage.db_path");
+            }
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+
+        config.storage.archive.path = config.storage.db_path.join("archive");
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_identity() {
+        let mut config = Config::default();
+        config.identity.master_key_path = PathBuf::from("/nonexistent/master.key");
+        config.identity.create_if_missing = false;
+
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::Conflict {
+                param: "identity.master_key_path",
+                other: "identity.create_if_missing",
+                reason: "the master key file does not exist and cannot be generated, \
+                         create it, give a mnemonic or set `create_if_missing` to true",
+            }])
+        );
+
+        config.identity.mnemonic = Some(Secret::plain(&["abandon"; 12].join(" ")));
+        assert_eq!(validate(&config), Ok(()));
+
+        config.identity.mnemonic = Some(Secret::plain("abandon abandon"));
+        assert_eq!(
+            validate(&config),
+            Err(vec![ValidationError::Malformed {
+                param: "identity.mnemonic",
+                reason: "expected 12, 15, 18, 21 or 24 words but found 2".to_string(),
+            }])
+        );
+    }
+}
@@ -6,7 +6,9 @@ use crate::actors::epoch_manager::{
 };
 
 use crate::actors::blocks_manager::{
+    download::BlockDownload,
     handlers::{EpochPayload, EveryEpochPayload},
+    headers::HeaderChain,
     BlocksManager,
 };
 
@@ -18,6 +20,8 @@ use crate::actors::{
 
 use witnet_data_structures::chain::{ChainInfo, CheckpointBeacon};
 
+use witnet_p2p::relay::RelayPolicy;
+
 use log::{debug, error, info};
 
 /// Implement Actor trait for `BlocksManager`
@@ -76,6 +80,39 @@ impl Actor for BlocksManager {
             let environment = (&config.environment).clone();
             let consensus_constants = (&config.consensus_constants).clone();
 
+            // Prune old blocks on every epoch if enabled, and when the storage asks for it
+            act.keep_blocks = Some(config.storage.pruning.keep_blocks);
+            act.pruning_enabled = config.storage.pruning.enabled;
+
+            // Move old blocks to the archive on every epoch if enabled
+            if config.storage.archive.enabled {
+                act.archive_keep_blocks = Some(config.storage.archive.keep_blocks);
+            }
+
+            // Nothing is persisted into a read-only storage
+            act.read_only = config.storage.read_only;
+
+            // Set which of the blocks received from the peers are relayed to the other peers
+            let relay = &config.connections.relay;
+            act.relay_policy = RelayPolicy {
+                unsolicited: relay.unsolicited,
+                min_reputation: relay.min_reputation,
+                unvalidated: relay.unvalidated,
+            };
+
+            // Validate the headers against the checkpoints before downloading the blocks, from
+            // several peers at once, taking the blocks from the peers which stall
+            let headers_first = config.sync.headers_first;
+            let checkpoints = config.sync.checkpoints.clone();
+            if headers_first {
+                let download_timeout = config.sync.download_timeout;
+                act.download = Some(BlockDownload::new(
+                    config.sync.download_window as usize,
+                    download_timeout,
+                ));
+                ctx.run_interval(download_timeout / 2, |act, ctx| act.expire_downloads(ctx));
+            }
+
             // Get storage manager actor address
             let storage_manager_addr = System::current().registry().get::<StorageManager>();
             storage_manager_addr
@@ -148,6 +185,17 @@ impl Actor for BlocksManager {
                         };
                         act.chain_info = Some(chain_info);
                     }
+
+                    // The header chain starts at the tip of the local chain
+                    if headers_first {
+                        if let Some(chain_info) = &act.chain_info {
+                            act.header_chain = Some(HeaderChain::new(
+                                chain_info.highest_block_checkpoint,
+                                &checkpoints,
+                            ));
+                        }
+                    }
+
                     actix::fut::ok(())
                 })
                 .wait(ctx);
@@ -0,0 +1,370 @@
+//! Download of the blocks of the validated headers from several peers at once
+//!
+//! The blocks to download are split into ranges of consecutive blocks, which are assigned to the
+//! outbound peers as they ask for work. Only the ranges within a sliding window after the first
+//! missing block are assigned, so the node does not get far ahead of the blocks it can process.
+//! A range not completed before the timeout is taken from its peer and assigned to another one.
+//! A range which every peer failed to complete is reported as unavailable, as its headers are most
+//! likely fake.
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use witnet_data_structures::chain::Hash;
+
+/// Maximum number of blocks requested to a peer in a single `GetData` message
+pub const BLOCKS_PER_REQUEST: usize = 64;
+
+/// Range of consecutive blocks requested to a single peer
+#[derive(Debug)]
+struct BlockRange {
+    /// Hashes of the blocks of the range, in chain order
+    hashes: Vec<Hash>,
+    /// Hashes of the blocks not received yet
+    missing: HashSet<Hash>,
+    /// Peer downloading the range, and when the range was assigned to it
+    assigned: Option<(SocketAddr, Instant)>,
+    /// Peers which did not complete the range in time, which are not given the range again
+    stalled_by: HashSet<SocketAddr>,
+}
+
+impl BlockRange {
+    fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    fn is_assigned_to(&self, peer: SocketAddr) -> bool {
+        self.assigned.map_or(false, |(address, _)| address == peer)
+    }
+}
+
+/// Blocks to download and the peers downloading them
+#[derive(Debug)]
+pub struct BlockDownload {
+    /// Ranges of blocks not completed yet, or completed after the first incomplete range
+    ranges: VecDeque<BlockRange>,
+    /// Maximum number of blocks between the first missing block and the last requested one
+    window: usize,
+    /// Time a peer has to send all the blocks of its range
+    timeout: Duration,
+    /// Connected peers which have asked for ranges
+    peers: HashSet<SocketAddr>,
+}
+
+impl BlockDownload {
+    /// Create a download allowing `window` blocks in flight, which each peer must send within
+    /// `timeout`
+    pub fn new(window: usize, timeout: Duration) -> Self {
+        BlockDownload {
+            ranges: VecDeque::new(),
+            window: window.max(BLOCKS_PER_REQUEST),
+            timeout,
+            peers: HashSet::new(),
+        }
+    }
+
+    /// Queue the blocks with these hashes, in chain order
+    pub fn add(&mut self, hashes: &[Hash]) {
+        for chunk in hashes.chunks(BLOCKS_PER_REQUEST) {
+            self.ranges.push_back(BlockRange {
+                hashes: chunk.to_vec(),
+                missing: chunk.iter().cloned().collect(),
+                assigned: None,
+                stalled_by: HashSet::new(),
+            });
+        }
+    }
+
+    /// Assign the next range of the window to `peer`, unless it is still downloading one
+    /// Returns the hashes of the blocks of the range which are still missing, in chain order
+    pub fn assign(&mut self, peer: SocketAddr, now: Instant) -> Option<Vec<Hash>> {
+        self.peers.insert(peer);
+        if self
+            .ranges
+            .iter()
+            .any(|range| !range.is_complete() && range.is_assigned_to(peer))
+        {
+            return None;
+        }
+
+        let window = self.window;
+        let mut blocks = 0;
+        let range = self
+            .ranges
+            .iter_mut()
+            .take_while(|range| {
+                blocks += range.hashes.len();
+                blocks <= window
+            })
+            .find(|range| {
+                !range.is_complete()
+                    && range.assigned.is_none()
+                    && !range.stalled_by.contains(&peer)
+            })?;
+        range.assigned = Some((peer, now));
+
+        Some(
+            range
+                .hashes
+                .iter()
+                .filter(|hash| range.missing.contains(*hash))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Count a block as received, sliding the window over the completed ranges
+    /// Returns the peer that completed its range with this block, if any
+    pub fn received(&mut self, hash: Hash) -> Option<SocketAddr> {
+        let mut completed_by = None;
+        if let Some(range) = self
+            .ranges
+            .iter_mut()
+            .find(|range| range.missing.contains(&hash))
+        {
+            range.missing.remove(&hash);
+            if range.is_complete() {
+                completed_by = range.assigned.map(|(address, _)| address);
+            }
+        }
+        while self.ranges.front().map_or(false, BlockRange::is_complete) {
+            self.ranges.pop_front();
+        }
+
+        completed_by
+    }
+
+    /// Take the ranges whose peers did not complete them in time, so they can be assigned to other
+    /// peers. Returns the peers which stalled.
+    pub fn expire(&mut self, now: Instant) -> Vec<SocketAddr> {
+        let timeout = self.timeout;
+        let mut stalled = vec![];
+        for range in self.ranges.iter_mut().filter(|range| !range.is_complete()) {
+            if let Some((address, assigned_at)) = range.assigned {
+                if now.duration_since(assigned_at) >= timeout {
+                    range.assigned = None;
+                    range.stalled_by.insert(address);
+                    stalled.push(address);
+                }
+            }
+        }
+
+        stalled
+    }
+
+    /// Take the ranges which every connected peer failed to complete, giving them another chance
+    /// with every peer. Returns the hash of the first missing block of each of them, in chain
+    /// order.
+    pub fn take_unavailable(&mut self) -> Vec<Hash> {
+        if self.peers.is_empty() {
+            return vec![];
+        }
+
+        let peers = &self.peers;
+        let mut unavailable = vec![];
+        for range in self.ranges.iter_mut().filter(|range| {
+            !range.is_complete()
+                && range.assigned.is_none()
+                && peers.iter().all(|peer| range.stalled_by.contains(peer))
+        }) {
+            range.stalled_by.clear();
+            if let Some(hash) = range
+                .hashes
+                .iter()
+                .find(|hash| range.missing.contains(*hash))
+            {
+                unavailable.push(*hash);
+            }
+        }
+
+        unavailable
+    }
+
+    /// Stop downloading the blocks with these hashes, e.g.: when their headers are discarded
+    pub fn discard(&mut self, hashes: &[Hash]) {
+        let hashes: HashSet<&Hash> = hashes.iter().collect();
+        for range in self.ranges.iter_mut() {
+            range.hashes.retain(|hash| !hashes.contains(hash));
+            range.missing.retain(|hash| !hashes.contains(hash));
+        }
+        self.ranges.retain(|range| !range.hashes.is_empty());
+        while self.ranges.front().map_or(false, BlockRange::is_complete) {
+            self.ranges.pop_front();
+        }
+    }
+
+    /// Peer downloading the block with this hash, if it is missing and assigned
+    pub fn assigned_to(&self, hash: Hash) -> Option<SocketAddr> {
+        self.ranges
+            .iter()
+            .find(|range| range.missing.contains(&hash))
+            .and_then(|range| range.assigned.map(|(address, _)| address))
+    }
+
+    /// Take the range of a peer which is no longer connected, so it can be assigned to others
+    /// Returns whether the peer was downloading a range
+    pub fn release(&mut self, peer: SocketAddr) -> bool {
+        self.peers.remove(&peer);
+        let mut released = false;
+        for range in self
+            .ranges
+            .iter_mut()
+            .filter(|range| range.is_assigned_to(peer))
+        {
+            range.assigned = None;
+            released = true;
+        }
+
+        released
+    }
+
+    /// Number of blocks still missing
+    pub fn missing_len(&self) -> usize {
+        self.ranges.iter().map(|range| range.missing.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(n: u8) -> Vec<Hash> {
+        (0..n)
+            .map(|i| {
+                let mut bytes = [0; 32];
+                bytes[0] = i;
+                bytes[1] = 1;
+                Hash::SHA256(bytes)
+            })
+            .collect()
+    }
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn assign_ranges_to_several_peers() {
+        let mut download = BlockDownload::new(1000, Duration::from_secs(10));
+        let hashes = hashes(100);
+        download.add(&hashes);
+        let now = Instant::now();
+
+        assert_eq!(
+            download.assign(peer(1), now),
+            Some(hashes[..BLOCKS_PER_REQUEST].to_vec())
+        );
+        // A peer downloading a range does not get another one
+        assert_eq!(download.assign(peer(1), now), None);
+        assert_eq!(
+            download.assign(peer(2), now),
+            Some(hashes[BLOCKS_PER_REQUEST..].to_vec())
+        );
+        // Every range is assigned
+        assert_eq!(download.assign(peer(3), now), None);
+    }
+
+    #[test]
+    fn slide_window_over_completed_ranges() {
+        // Only the first range fits in the window
+        let mut download = BlockDownload::new(BLOCKS_PER_REQUEST, Duration::from_secs(10));
+        let hashes = hashes(100);
+        download.add(&hashes);
+        let now = Instant::now();
+
+        assert!(download.assign(peer(1), now).is_some());
+        assert_eq!(download.assign(peer(2), now), None);
+
+        for hash in &hashes[..BLOCKS_PER_REQUEST - 1] {
+            assert_eq!(download.received(*hash), None);
+        }
+        assert_eq!(
+            download.received(hashes[BLOCKS_PER_REQUEST - 1]),
+            Some(peer(1))
+        );
+
+        assert_eq!(
+            download.assign(peer(2), now),
+            Some(hashes[BLOCKS_PER_REQUEST..].to_vec())
+        );
+        assert_eq!(download.missing_len(), 100 - BLOCKS_PER_REQUEST);
+    }
+
+    #[test]
+    fn reassign_stalled_ranges() {
+        let mut download = BlockDownload::new(1000, Duration::from_secs(10));
+        let hashes = hashes(10);
+        download.add(&hashes);
+        let now = Instant::now();
+
+        assert!(download.assign(peer(1), now).is_some());
+        download.received(hashes[0]);
+        assert!(download.expire(now + Duration::from_secs(5)).is_empty());
+        assert_eq!(
+            download.expire(now + Duration::from_secs(10)),
+            vec![peer(1)]
+        );
+
+        // The stalled peer does not get the range again, the missing blocks go to another peer
+        assert_eq!(download.assign(peer(1), now), None);
+        assert_eq!(download.assign(peer(2), now), Some(hashes[1..].to_vec()));
+    }
+
+    #[test]
+    fn release_ranges_of_disconnected_peers() {
+        let mut download = BlockDownload::new(1000, Duration::from_secs(10));
+        let hashes = hashes(10);
+        download.add(&hashes);
+        let now = Instant::now();
+
+        assert!(download.assign(peer(1), now).is_some());
+        assert!(download.release(peer(1)));
+        assert!(!download.release(peer(1)));
+        assert_eq!(download.assign(peer(2), now), Some(hashes));
+    }
+
+    #[test]
+    fn report_ranges_stalled_by_every_peer() {
+        let mut download = BlockDownload::new(1000, Duration::from_secs(10));
+        let hashes = hashes(10);
+        download.add(&hashes);
+        let now = Instant::now();
+        let later = now + Duration::from_secs(10);
+
+        assert!(download.assign(peer(1), now).is_some());
+        assert_eq!(download.assign(peer(2), now), None);
+        download.received(hashes[0]);
+        download.expire(later);
+        // Another peer can still try
+        assert!(download.take_unavailable().is_empty());
+
+        assert_eq!(download.assign(peer(2), now), Some(hashes[1..].to_vec()));
+        assert_eq!(download.assigned_to(hashes[1]), Some(peer(2)));
+        assert_eq!(download.assigned_to(hashes[0]), None);
+        download.expire(later);
+        assert_eq!(download.take_unavailable(), vec![hashes[1]]);
+
+        // Every peer gets another chance if the range is not discarded
+        assert!(download.take_unavailable().is_empty());
+        assert!(download.assign(peer(1), now).is_some());
+    }
+
+    #[test]
+    fn discard_blocks() {
+        let mut download = BlockDownload::new(BLOCKS_PER_REQUEST, Duration::from_secs(10));
+        let hashes = hashes(100);
+        download.add(&hashes);
+        let now = Instant::now();
+
+        assert!(download.assign(peer(1), now).is_some());
+        download.discard(&hashes[1..BLOCKS_PER_REQUEST + 10]);
+        assert_eq!(download.missing_len(), 100 - BLOCKS_PER_REQUEST - 9);
+
+        // The first range only waits for its first block, so the window slides once it arrives
+        assert_eq!(download.received(hashes[0]), Some(peer(1)));
+        assert_eq!(
+            download.assign(peer(1), now),
+            Some(hashes[BLOCKS_PER_REQUEST + 10..].to_vec())
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use actix::{Context, Handler, System};
+use actix::{Context, Handler, MessageResult, System};
 
-use crate::actors::blocks_manager::{BlocksManager, BlocksManagerError};
+use crate::actors::blocks_manager::{block_hash, BlocksManager, BlocksManagerError};
 use crate::actors::epoch_manager::messages::EpochNotification;
 
 use witnet_data_structures::{
@@ -8,13 +8,19 @@ use witnet_data_structures::{
     error::{ChainInfoError, ChainInfoErrorKind, ChainInfoResult},
 };
 
+use witnet_p2p::bans::Misbehavior;
 use witnet_util::error::WitnetError;
 
-use log::{debug, error};
+use log::{debug, error, warn};
+use std::time::Instant;
 
-use super::messages::{AddNewBlock, GetBlock, GetBlocksEpochRange, GetHighestCheckpointBeacon};
+use super::messages::{
+    AddHeaders, AddNewBlock, AssignBlocks, GetBlock, GetBlocksEpochRange, GetEpochBlockHashes,
+    GetHeadersAfter, GetHeadersBeacon, GetHighestCheckpointBeacon, PersistChainInfo, PruneBlocks,
+    ReleaseBlocks, SubscribeNewBlocks, UnsubscribeNewBlocks,
+};
 
-use crate::actors::session::messages::AnnounceItems;
+use crate::actors::session::messages::{AnnounceItems, RequestBlocks};
 use crate::actors::sessions_manager::{messages::Broadcast, SessionsManager};
 
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -41,8 +47,41 @@ impl Handler<EpochNotification<EpochPayload>> for BlocksManager {
 impl Handler<EpochNotification<EveryEpochPayload>> for BlocksManager {
     type Result = ();
 
-    fn handle(&mut self, msg: EpochNotification<EveryEpochPayload>, _ctx: &mut Context<Self>) {
+    fn handle(&mut self, msg: EpochNotification<EveryEpochPayload>, ctx: &mut Context<Self>) {
         debug!("Periodic epoch notification received {:?}", msg.checkpoint);
+        self.current_epoch = Some(msg.checkpoint);
+
+        if let (true, Some(keep_blocks)) = (self.pruning_enabled, self.keep_blocks) {
+            self.prune(ctx, msg.checkpoint, keep_blocks);
+        }
+        if let Some(keep_blocks) = self.archive_keep_blocks {
+            self.archive(ctx, msg.checkpoint, keep_blocks);
+        }
+
+        self.flush_storage(ctx);
+    }
+}
+
+/// Handler for PruneBlocks message
+impl Handler<PruneBlocks> for BlocksManager {
+    type Result = ();
+
+    fn handle(&mut self, _msg: PruneBlocks, ctx: &mut Context<Self>) {
+        if let (Some(current_epoch), Some(keep_blocks)) = (self.current_epoch, self.keep_blocks) {
+            self.prune(ctx, current_epoch, keep_blocks);
+        }
+    }
+}
+
+/// Handler for PersistChainInfo message
+impl Handler<PersistChainInfo> for BlocksManager {
+    type Result = ();
+
+    fn handle(&mut self, _msg: PersistChainInfo, ctx: &mut Context<Self>) {
+        // The chain info may not have been initialized yet
+        if self.chain_info.is_some() {
+            self.persist_chain_info(ctx);
+        }
     }
 }
 
@@ -73,12 +112,38 @@ impl Handler<AddNewBlock> for BlocksManager {
 
     fn handle(
         &mut self,
-        msg: AddNewBlock,
-        _ctx: &mut Context<Self>,
+        AddNewBlock { block, origin }: AddNewBlock,
+        ctx: &mut Context<Self>,
     ) -> Result<Hash, BlocksManagerError> {
-        let res = self.process_new_block(msg.block);
+        // The peer asked for the block of a validated header may show that its hash was fake
+        if let Some(supplier) = origin
+            .as_ref()
+            .and_then(|origin| self.fake_header_supplier(&block, origin.address))
+        {
+            self.discard_headers_of(ctx, supplier, Misbehavior::InvalidHeaders);
+        }
+
+        // The header of the block leaves the header chain once the block is added
+        let validated = block_hash(&block)
+            .map(|hash| self.has_validated_header(hash))
+            .unwrap_or(false);
+        let res = self.process_new_block(block);
         match res {
+            Ok(hash) if !self.relay_policy.allows(origin.as_ref(), validated) => {
+                // Persist the block with all its transactions, without announcing it
+                self.persist_block(ctx, hash);
+                self.notify_new_block(hash);
+                debug!(
+                    "Not relaying block {:?} from peer {:?} under the relay policy",
+                    hash,
+                    origin.map(|origin| origin.address)
+                );
+            }
             Ok(hash) => {
+                // Persist the block with all its transactions
+                self.persist_block(ctx, hash);
+                self.notify_new_block(hash);
+
                 // Get SessionsManager's address
                 let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
 
@@ -91,6 +156,9 @@ impl Handler<AddNewBlock> for BlocksManager {
             Err(BlocksManagerError::BlockAlreadyExists) => {
                 debug!("Block already exists");
             }
+            Err(BlocksManagerError::BlockNotInHeaderChain) => {
+                warn!("Block before the last checkpoint without a validated header");
+            }
             Err(BlocksManagerError::StorageError(_)) => {
                 debug!("Error when serializing block");
             }
@@ -103,6 +171,29 @@ impl Handler<AddNewBlock> for BlocksManager {
     }
 }
 
+/// Handler for SubscribeNewBlocks message
+impl Handler<SubscribeNewBlocks> for BlocksManager {
+    type Result = u64;
+
+    fn handle(&mut self, msg: SubscribeNewBlocks, _ctx: &mut Context<Self>) -> Self::Result {
+        let subscription = self.next_subscription;
+        self.next_subscription += 1;
+        self.block_subscribers.insert(subscription, msg.recipient);
+        debug!("Subscription {} to the new blocks", subscription);
+
+        subscription
+    }
+}
+
+/// Handler for UnsubscribeNewBlocks message
+impl Handler<UnsubscribeNewBlocks> for BlocksManager {
+    type Result = bool;
+
+    fn handle(&mut self, msg: UnsubscribeNewBlocks, _ctx: &mut Context<Self>) -> Self::Result {
+        self.block_subscribers.remove(&msg.subscription).is_some()
+    }
+}
+
 /// Handler for GetBlock message
 impl Handler<GetBlock> for BlocksManager {
     type Result = Result<Block, BlocksManagerError>;
@@ -136,3 +227,110 @@ impl Handler<GetBlocksEpochRange> for BlocksManager {
         Ok(hashes)
     }
 }
+
+/// Handler for GetEpochBlockHashes message
+impl Handler<GetEpochBlockHashes> for BlocksManager {
+    type Result = MessageResult<GetEpochBlockHashes>;
+
+    fn handle(&mut self, msg: GetEpochBlockHashes, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.epoch_block_hashes(&msg.range, msg.limit))
+    }
+}
+
+/// Handler for GetHeadersBeacon message
+impl Handler<GetHeadersBeacon> for BlocksManager {
+    type Result = Option<CheckpointBeacon>;
+
+    fn handle(&mut self, _msg: GetHeadersBeacon, _ctx: &mut Context<Self>) -> Self::Result {
+        self.header_chain
+            .as_ref()
+            .map(|header_chain| header_chain.tip())
+    }
+}
+
+/// Handler for GetHeadersAfter message
+impl Handler<GetHeadersAfter> for BlocksManager {
+    type Result = MessageResult<GetHeadersAfter>;
+
+    fn handle(&mut self, msg: GetHeadersAfter, _ctx: &mut Context<Self>) -> Self::Result {
+        let headers = self.headers_after(msg.beacon);
+        debug!("Serving {} headers after {:?}", headers.len(), msg.beacon);
+
+        MessageResult(headers)
+    }
+}
+
+/// Handler for AddHeaders message
+impl Handler<AddHeaders> for BlocksManager {
+    type Result = Result<Vec<Hash>, BlocksManagerError>;
+
+    fn handle(&mut self, msg: AddHeaders, _ctx: &mut Context<Self>) -> Self::Result {
+        let blocks = &self.blocks;
+        let pruned_headers = &self.pruned_headers;
+        let hashes: Vec<Hash> = match &mut self.header_chain {
+            // The blocks of the headers sent again after discarding the headers of a peer may have
+            // been received already
+            Some(header_chain) => header_chain
+                .add_headers(&msg.headers, msg.address)
+                .map_err(BlocksManagerError::InvalidHeaders)?
+                .into_iter()
+                .filter(|hash| {
+                    if blocks.contains_key(hash) || pruned_headers.contains_key(hash) {
+                        header_chain.take_header(*hash);
+                        false
+                    } else {
+                        true
+                    }
+                })
+                .collect(),
+            // Headers are not requested when headers-first synchronization is disabled
+            None => return Ok(vec![]),
+        };
+
+        // Queue the blocks of the new headers, which can be downloaded by any session
+        if let (Some(download), false) = (&mut self.download, hashes.is_empty()) {
+            download.add(&hashes);
+            let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+            sessions_manager_addr.do_send(Broadcast {
+                command: RequestBlocks,
+            });
+        }
+
+        Ok(hashes)
+    }
+}
+
+/// Handler for AssignBlocks message
+impl Handler<AssignBlocks> for BlocksManager {
+    type Result = MessageResult<AssignBlocks>;
+
+    fn handle(&mut self, msg: AssignBlocks, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(
+            self.download
+                .as_mut()
+                .and_then(|download| download.assign(msg.address, Instant::now())),
+        )
+    }
+}
+
+/// Handler for ReleaseBlocks message
+impl Handler<ReleaseBlocks> for BlocksManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReleaseBlocks, _ctx: &mut Context<Self>) {
+        let released = self
+            .download
+            .as_mut()
+            .map_or(false, |download| download.release(msg.address));
+        if released {
+            debug!(
+                "Blocks requested to peer {} will be requested to others",
+                msg.address
+            );
+            let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+            sessions_manager_addr.do_send(Broadcast {
+                command: RequestBlocks,
+            });
+        }
+    }
+}
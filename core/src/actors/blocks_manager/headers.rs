@@ -0,0 +1,558 @@
+//! Chain of block headers downloaded from the peers before their blocks
+//!
+//! With headers-first synchronization, the node asks its outbound peers for the headers following
+//! the tip of its header chain, checks that they link to each other and that they match the
+//! configured checkpoints, and only then requests their blocks. The headers stay here until their
+//! blocks arrive.
+//!
+//! The hash of a header is claimed by the peer which sent it, it can only be checked once the block
+//! arrives. The chain remembers which peer supplied each header, so the headers of a peer whose
+//! blocks never arrive, or arrive with another hash, can be discarded back to the last good header.
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::net::SocketAddr;
+
+use witnet_data_structures::chain::{
+    BlockHeaderWithProof, Checkpoint, CheckpointBeacon, Epoch, Hash, HashedBlockHeader,
+};
+
+/// Upper bound of the size of a header in a `Headers` message: a signed header whose flatbuffers
+/// tables do not share any vtable with the other headers takes a bit more than 400 bytes
+const MAX_ENCODED_HEADER_SIZE: usize = 512;
+
+/// Upper bound of the size of a `Headers` message besides its headers
+const HEADERS_MESSAGE_OVERHEAD: usize = 512;
+
+/// Maximum number of headers sent in a single `Headers` message, so that the message always fits
+/// in a frame of the `P2PCodec`, whose length is a `u16`
+pub const MAX_HEADERS_PER_MESSAGE: usize =
+    (std::u16::MAX as usize - HEADERS_MESSAGE_OVERHEAD) / MAX_ENCODED_HEADER_SIZE;
+
+/// Reasons for rejecting a batch of headers received from a peer
+#[derive(Debug, PartialEq)]
+pub enum HeadersError {
+    /// The batch has more than `MAX_HEADERS_PER_MESSAGE` headers
+    TooManyHeaders(usize),
+    /// The first new header does not extend the tip of the header chain
+    DoesNotConnect(Hash),
+    /// A header does not point to the previous one
+    NotLinked(Hash),
+    /// The epoch of a header is not greater than the epoch of the previous one
+    EpochNotIncreasing(Hash),
+    /// The header at the epoch of a checkpoint does not have the hash of the checkpoint
+    CheckpointMismatch(Epoch),
+    /// The headers jump over the epoch of a checkpoint
+    CheckpointSkipped(Epoch),
+}
+
+impl fmt::Display for HeadersError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeadersError::TooManyHeaders(len) => write!(
+                f,
+                "{} headers received, the maximum is {}",
+                len, MAX_HEADERS_PER_MESSAGE
+            ),
+            HeadersError::DoesNotConnect(hash) => {
+                write!(f, "Header {:?} does not extend the header chain", hash)
+            }
+            HeadersError::NotLinked(hash) => {
+                write!(f, "Header {:?} does not point to the previous header", hash)
+            }
+            HeadersError::EpochNotIncreasing(hash) => {
+                write!(f, "Header {:?} is not after the previous header", hash)
+            }
+            HeadersError::CheckpointMismatch(epoch) => {
+                write!(f, "Header at epoch {} does not match the checkpoint", epoch)
+            }
+            HeadersError::CheckpointSkipped(epoch) => {
+                write!(f, "Headers skip the checkpoint at epoch {}", epoch)
+            }
+        }
+    }
+}
+
+/// Header of the chain, and the peer which supplied it
+#[derive(Debug)]
+struct ChainedHeader {
+    hash: Hash,
+    epoch: Epoch,
+    supplier: SocketAddr,
+}
+
+impl ChainedHeader {
+    fn beacon(&self) -> CheckpointBeacon {
+        CheckpointBeacon {
+            checkpoint: self.epoch,
+            hash_prev_block: self.hash,
+        }
+    }
+}
+
+/// Validated block headers whose blocks have not been received yet
+#[derive(Debug, Default)]
+pub struct HeaderChain {
+    /// Hashes of the blocks known to be part of the chain, by epoch
+    checkpoints: BTreeMap<Epoch, Hash>,
+    /// Epoch and hash of the last validated header
+    tip: CheckpointBeacon,
+    /// Validated headers whose blocks are still missing, by block hash
+    pending: HashMap<Hash, BlockHeaderWithProof>,
+    /// Headers from the first one whose block is missing up to the tip, in chain order
+    chain: VecDeque<ChainedHeader>,
+    /// Epoch and hash of the header before the first one of `chain`
+    base: CheckpointBeacon,
+    /// Hash of the pending header following each hash
+    next: HashMap<Hash, Hash>,
+}
+
+impl HeaderChain {
+    /// Create a header chain starting at `tip`, which must match `checkpoints`
+    pub fn new(tip: CheckpointBeacon, checkpoints: &[Checkpoint]) -> Self {
+        HeaderChain {
+            checkpoints: checkpoints
+                .iter()
+                .map(|checkpoint| (checkpoint.epoch, checkpoint.hash))
+                .collect(),
+            tip,
+            pending: HashMap::new(),
+            chain: VecDeque::new(),
+            base: tip,
+            next: HashMap::new(),
+        }
+    }
+
+    /// Epoch and hash of the last validated header, to be sent in `GetHeaders` messages
+    pub fn tip(&self) -> CheckpointBeacon {
+        self.tip
+    }
+
+    /// Validate a batch of headers received from a peer and add them to the chain. Either all the
+    /// new headers are added or none of them. The headers already in the chain are skipped, as
+    /// several peers may answer with the same headers. The new headers are recorded as supplied by
+    /// `supplier`.
+    ///
+    /// Returns the hashes of the blocks of the new headers, which can be requested to the peer.
+    pub fn add_headers(
+        &mut self,
+        headers: &[HashedBlockHeader],
+        supplier: SocketAddr,
+    ) -> Result<Vec<Hash>, HeadersError> {
+        if headers.len() > MAX_HEADERS_PER_MESSAGE {
+            return Err(HeadersError::TooManyHeaders(headers.len()));
+        }
+
+        let mut tip = self.tip;
+        let mut new_headers = vec![];
+        for HashedBlockHeader { hash, header } in headers {
+            let beacon = header.block_header.beacon;
+            if new_headers.is_empty() && self.is_known(*hash) {
+                continue;
+            }
+            if beacon.hash_prev_block != tip.hash_prev_block {
+                return Err(if new_headers.is_empty() {
+                    HeadersError::DoesNotConnect(*hash)
+                } else {
+                    HeadersError::NotLinked(*hash)
+                });
+            }
+            if beacon.checkpoint <= tip.checkpoint {
+                return Err(HeadersError::EpochNotIncreasing(*hash));
+            }
+            self.check_checkpoints(tip.checkpoint, beacon.checkpoint, *hash)?;
+
+            tip = CheckpointBeacon {
+                checkpoint: beacon.checkpoint,
+                hash_prev_block: *hash,
+            };
+            new_headers.push((*hash, header.clone()));
+        }
+
+        self.tip = tip;
+        let mut hashes = Vec::with_capacity(new_headers.len());
+        for (hash, header) in new_headers {
+            let beacon = header.block_header.beacon;
+            self.chain.push_back(ChainedHeader {
+                hash,
+                epoch: beacon.checkpoint,
+                supplier,
+            });
+            self.next.insert(beacon.hash_prev_block, hash);
+            self.pending.insert(hash, header);
+            hashes.push(hash);
+        }
+
+        Ok(hashes)
+    }
+
+    /// Check whether a block can be added to the chain: the blocks up to the last checkpoint must
+    /// have been validated through their headers, the later ones are not checked yet
+    pub fn accepts_block(&self, hash: Hash, epoch: Epoch) -> bool {
+        match self.checkpoints.keys().next_back() {
+            Some(last_checkpoint) if epoch <= *last_checkpoint => self.pending.contains_key(&hash),
+            _ => true,
+        }
+    }
+
+    /// Remove the header of a block which has been received
+    pub fn take_header(&mut self, hash: Hash) -> Option<BlockHeaderWithProof> {
+        let header = self.pending.remove(&hash)?;
+        self.next
+            .remove(&header.block_header.beacon.hash_prev_block);
+        // The headers before the first missing block are no longer needed to roll back
+        while let Some(first) = self.chain.front() {
+            if self.pending.contains_key(&first.hash) {
+                break;
+            }
+            self.base = first.beacon();
+            self.chain.pop_front();
+        }
+
+        Some(header)
+    }
+
+    /// Hash and header of the pending header at the position of `beacon`, i.e. the header with
+    /// the same epoch and previous block
+    pub fn header_at(&self, beacon: CheckpointBeacon) -> Option<(Hash, &BlockHeaderWithProof)> {
+        let hash = self.next.get(&beacon.hash_prev_block)?;
+        self.pending
+            .get(hash)
+            .filter(|header| header.block_header.beacon.checkpoint == beacon.checkpoint)
+            .map(|header| (*hash, header))
+    }
+
+    /// Peer which supplied the pending header of the block with this hash
+    pub fn supplier(&self, hash: Hash) -> Option<SocketAddr> {
+        if !self.pending.contains_key(&hash) {
+            return None;
+        }
+        self.chain
+            .iter()
+            .find(|header| header.hash == hash)
+            .map(|header| header.supplier)
+    }
+
+    /// Discard the headers from the first pending header supplied by `supplier` up to the tip,
+    /// which also drops the headers of other peers extending them, and move the tip back to the
+    /// header before them, so the following headers are requested again.
+    ///
+    /// Returns the hashes of the discarded headers whose blocks were still missing.
+    pub fn discard_supplier(&mut self, supplier: SocketAddr) -> Vec<Hash> {
+        let start = match self.chain.iter().position(|header| {
+            header.supplier == supplier && self.pending.contains_key(&header.hash)
+        }) {
+            Some(start) => start,
+            None => return vec![],
+        };
+        self.tip = match start {
+            0 => self.base,
+            _ => self.chain[start - 1].beacon(),
+        };
+
+        let discarded: Vec<ChainedHeader> = self.chain.drain(start..).collect();
+        let mut hashes = vec![];
+        for ChainedHeader { hash, .. } in discarded {
+            if let Some(header) = self.pending.remove(&hash) {
+                self.next
+                    .remove(&header.block_header.beacon.hash_prev_block);
+                hashes.push(hash);
+            }
+        }
+
+        hashes
+    }
+
+    /// Check whether the header of the block with this hash has been validated and its block is
+    /// still missing
+    pub fn has_header(&self, hash: Hash) -> bool {
+        self.pending.contains_key(&hash)
+    }
+
+    /// Number of validated headers whose blocks are still missing
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Check whether the header of the block with this hash is already in the chain
+    fn is_known(&self, hash: Hash) -> bool {
+        hash == self.tip.hash_prev_block || self.pending.contains_key(&hash)
+    }
+
+    /// Check that the header with hash `hash` at epoch `epoch`, following a header at epoch
+    /// `prev_epoch`, neither contradicts nor skips a checkpoint
+    fn check_checkpoints(
+        &self,
+        prev_epoch: Epoch,
+        epoch: Epoch,
+        hash: Hash,
+    ) -> Result<(), HeadersError> {
+        if let Some((skipped, _)) = self.checkpoints.range(prev_epoch + 1..epoch).next() {
+            return Err(HeadersError::CheckpointSkipped(*skipped));
+        }
+        match self.checkpoints.get(&epoch) {
+            Some(checkpoint_hash) if *checkpoint_hash != hash => {
+                Err(HeadersError::CheckpointMismatch(epoch))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::codec::{BytesMut, P2PCodec};
+    use tokio::codec::Encoder;
+    use witnet_data_structures::chain::{
+        BlockHeader, LeadershipProof, Secp256k1Signature, Signature,
+    };
+    use witnet_data_structures::types::Message as WitnetMessage;
+
+    fn hashed_header(epoch: Epoch, hash: u8, prev_hash: u8) -> HashedBlockHeader {
+        HashedBlockHeader {
+            hash: Hash::SHA256([hash; 32]),
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint: epoch,
+                        hash_prev_block: Hash::SHA256([prev_hash; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([0; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 0,
+                },
+            },
+        }
+    }
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn genesis() -> CheckpointBeacon {
+        CheckpointBeacon {
+            checkpoint: 0,
+            hash_prev_block: Hash::SHA256([0; 32]),
+        }
+    }
+
+    #[test]
+    fn add_linked_headers() {
+        let mut chain = HeaderChain::new(genesis(), &[]);
+        let headers = vec![hashed_header(1, 1, 0), hashed_header(3, 2, 1)];
+
+        assert_eq!(
+            chain.add_headers(&headers, peer(1)),
+            Ok(vec![Hash::SHA256([1; 32]), Hash::SHA256([2; 32])])
+        );
+        assert_eq!(
+            chain.tip(),
+            CheckpointBeacon {
+                checkpoint: 3,
+                hash_prev_block: Hash::SHA256([2; 32]),
+            }
+        );
+        assert_eq!(chain.pending_len(), 2);
+    }
+
+    #[test]
+    fn skip_known_headers() {
+        let mut chain = HeaderChain::new(genesis(), &[]);
+        chain
+            .add_headers(&[hashed_header(1, 1, 0)], peer(1))
+            .unwrap();
+
+        // Another peer answered the same request with more headers
+        let headers = vec![hashed_header(1, 1, 0), hashed_header(2, 2, 1)];
+        assert_eq!(
+            chain.add_headers(&headers, peer(1)),
+            Ok(vec![Hash::SHA256([2; 32])])
+        );
+        assert_eq!(chain.add_headers(&headers, peer(1)), Ok(vec![]));
+    }
+
+    #[test]
+    fn reject_unlinked_headers() {
+        let mut chain = HeaderChain::new(genesis(), &[]);
+
+        assert_eq!(
+            chain.add_headers(&[hashed_header(1, 1, 9)], peer(1)),
+            Err(HeadersError::DoesNotConnect(Hash::SHA256([1; 32])))
+        );
+        assert_eq!(
+            chain.add_headers(&[hashed_header(1, 1, 0), hashed_header(2, 2, 9)], peer(1)),
+            Err(HeadersError::NotLinked(Hash::SHA256([2; 32])))
+        );
+        assert_eq!(
+            chain.add_headers(&[hashed_header(2, 1, 0), hashed_header(2, 2, 1)], peer(1)),
+            Err(HeadersError::EpochNotIncreasing(Hash::SHA256([2; 32])))
+        );
+
+        // Nothing was added
+        assert_eq!(chain.tip(), genesis());
+        assert_eq!(chain.pending_len(), 0);
+    }
+
+    #[test]
+    fn reject_too_many_headers() {
+        let mut chain = HeaderChain::new(genesis(), &[]);
+        let headers = vec![hashed_header(1, 1, 0); MAX_HEADERS_PER_MESSAGE + 1];
+
+        assert_eq!(
+            chain.add_headers(&headers, peer(1)),
+            Err(HeadersError::TooManyHeaders(MAX_HEADERS_PER_MESSAGE + 1))
+        );
+    }
+
+    #[test]
+    fn full_batch_fits_in_a_frame() {
+        // The biggest headers: signed, and without default values omitted by flatbuffers
+        let headers = (1..=MAX_HEADERS_PER_MESSAGE as Epoch)
+            .map(|epoch| {
+                let mut hashed_header = hashed_header(epoch, 0xff, 0xff);
+                hashed_header.header.block_header.version = u32::max_value();
+                hashed_header.header.proof = LeadershipProof {
+                    block_sig: Some(Signature::Secp256k1(Secp256k1Signature {
+                        r: [0xff; 32],
+                        s: [0xff; 32],
+                        v: 0xff,
+                    })),
+                    influence: u64::max_value(),
+                };
+                hashed_header
+            })
+            .collect();
+        let bytes: Vec<u8> = WitnetMessage::build_headers(headers).into();
+
+        let mut dst = BytesMut::new();
+        assert!(P2PCodec::default()
+            .encode(BytesMut::from(bytes), &mut dst)
+            .is_ok());
+    }
+
+    #[test]
+    fn validate_checkpoints() {
+        let checkpoints = [Checkpoint {
+            epoch: 2,
+            hash: Hash::SHA256([2; 32]),
+        }];
+        let mut chain = HeaderChain::new(genesis(), &checkpoints);
+
+        assert_eq!(
+            chain.add_headers(&[hashed_header(1, 1, 0), hashed_header(2, 7, 1)], peer(1)),
+            Err(HeadersError::CheckpointMismatch(2))
+        );
+        assert_eq!(
+            chain.add_headers(&[hashed_header(1, 1, 0), hashed_header(3, 3, 1)], peer(1)),
+            Err(HeadersError::CheckpointSkipped(2))
+        );
+        assert!(chain
+            .add_headers(&[hashed_header(1, 1, 0), hashed_header(2, 2, 1)], peer(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn accept_blocks_of_validated_headers() {
+        let checkpoints = [Checkpoint {
+            epoch: 2,
+            hash: Hash::SHA256([2; 32]),
+        }];
+        let mut chain = HeaderChain::new(genesis(), &checkpoints);
+        chain
+            .add_headers(&[hashed_header(1, 1, 0), hashed_header(2, 2, 1)], peer(1))
+            .unwrap();
+
+        // Blocks up to the last checkpoint must have a validated header
+        assert!(chain.accepts_block(Hash::SHA256([1; 32]), 1));
+        assert!(!chain.accepts_block(Hash::SHA256([7; 32]), 1));
+        // Later blocks are not checked
+        assert!(chain.accepts_block(Hash::SHA256([7; 32]), 3));
+
+        assert!(chain.has_header(Hash::SHA256([1; 32])));
+        assert!(chain.take_header(Hash::SHA256([1; 32])).is_some());
+        assert!(chain.take_header(Hash::SHA256([1; 32])).is_none());
+        assert!(!chain.has_header(Hash::SHA256([1; 32])));
+        assert_eq!(chain.pending_len(), 1);
+    }
+
+    #[test]
+    fn find_pending_header_at_beacon() {
+        let mut chain = HeaderChain::new(genesis(), &[]);
+        chain
+            .add_headers(&[hashed_header(1, 1, 0), hashed_header(3, 2, 1)], peer(1))
+            .unwrap();
+
+        let beacon = CheckpointBeacon {
+            checkpoint: 3,
+            hash_prev_block: Hash::SHA256([1; 32]),
+        };
+        assert_eq!(
+            chain.header_at(beacon).map(|(hash, _)| hash),
+            Some(Hash::SHA256([2; 32]))
+        );
+        // Another epoch after the same block is another position
+        assert!(chain
+            .header_at(CheckpointBeacon {
+                checkpoint: 2,
+                ..beacon
+            })
+            .is_none());
+        assert_eq!(chain.supplier(Hash::SHA256([2; 32])), Some(peer(1)));
+
+        chain.take_header(Hash::SHA256([2; 32]));
+        assert!(chain.header_at(beacon).is_none());
+        assert_eq!(chain.supplier(Hash::SHA256([2; 32])), None);
+    }
+
+    #[test]
+    fn discard_headers_of_supplier() {
+        let mut chain = HeaderChain::new(genesis(), &[]);
+        chain
+            .add_headers(&[hashed_header(1, 1, 0), hashed_header(2, 2, 1)], peer(1))
+            .unwrap();
+        chain
+            .add_headers(&[hashed_header(3, 3, 2), hashed_header(4, 4, 3)], peer(2))
+            .unwrap();
+        chain.take_header(Hash::SHA256([1; 32]));
+        // A block received out of order does not move the rollback point
+        chain.take_header(Hash::SHA256([4; 32]));
+
+        // The headers of the peer are discarded back to the last header of the other peer
+        assert_eq!(chain.discard_supplier(peer(2)), vec![Hash::SHA256([3; 32])]);
+        assert_eq!(
+            chain.tip(),
+            CheckpointBeacon {
+                checkpoint: 2,
+                hash_prev_block: Hash::SHA256([2; 32]),
+            }
+        );
+        assert_eq!(chain.discard_supplier(peer(2)), vec![]);
+
+        // The headers extending the headers of a discarded supplier are discarded too
+        chain
+            .add_headers(&[hashed_header(3, 3, 2)], peer(3))
+            .unwrap();
+        assert_eq!(
+            chain.discard_supplier(peer(1)),
+            vec![Hash::SHA256([2; 32]), Hash::SHA256([3; 32])]
+        );
+        // Back to the last header whose block was received
+        assert_eq!(
+            chain.tip(),
+            CheckpointBeacon {
+                checkpoint: 1,
+                hash_prev_block: Hash::SHA256([1; 32]),
+            }
+        );
+        assert_eq!(chain.pending_len(), 0);
+
+        // Other peers can send the following headers again
+        assert_eq!(
+            chain.add_headers(&[hashed_header(2, 2, 1)], peer(3)),
+            Ok(vec![Hash::SHA256([2; 32])])
+        );
+    }
+}
@@ -1,11 +1,13 @@
-use actix::Message;
+use actix::{Message, Recipient};
+use std::net::SocketAddr;
 use std::ops::RangeInclusive;
 
 use crate::actors::blocks_manager::BlocksManagerError;
 use witnet_data_structures::{
-    chain::{Block, CheckpointBeacon, Epoch, Hash, InvVector},
+    chain::{Block, CheckpointBeacon, Epoch, Hash, HashedBlockHeader, InvVector},
     error::ChainInfoResult,
 };
+use witnet_p2p::relay::ItemOrigin;
 
 /// Message to obtain the highest block checkpoint managed by the `BlocksManager`
 /// actor.
@@ -19,6 +21,8 @@ impl Message for GetHighestCheckpointBeacon {
 pub struct AddNewBlock {
     /// Block
     pub block: Block,
+    /// Peer from which the block was received, or `None` if the block comes from the node itself
+    pub origin: Option<ItemOrigin>,
 }
 
 impl Message for AddNewBlock {
@@ -44,3 +48,121 @@ pub struct GetBlocksEpochRange {
 impl Message for GetBlocksEpochRange {
     type Result = Result<Vec<InvVector>, BlocksManagerError>;
 }
+
+/// Ask for the hashes of the blocks of a range of epochs, with their epoch, ordered by epoch.
+/// Epochs without blocks are skipped, and the hashes of pruned blocks are included.
+pub struct GetEpochBlockHashes {
+    /// Range of epochs
+    pub range: RangeInclusive<Epoch>,
+    /// Maximum number of hashes returned
+    pub limit: usize,
+}
+
+impl Message for GetEpochBlockHashes {
+    type Result = Vec<(Epoch, Hash)>;
+}
+
+/// Prune the blocks older than `storage.pruning.keep_blocks` epochs, even if pruning is not
+/// enabled, e.g.: because the database is approaching its maximum size
+pub struct PruneBlocks;
+
+impl Message for PruneBlocks {
+    type Result = ();
+}
+
+/// Persist the chain info into storage right away, e.g.: when the node is shutting down
+pub struct PersistChainInfo;
+
+impl Message for PersistChainInfo {
+    type Result = ();
+}
+
+/// Ask for the tip of the chain of validated headers, to request the following headers to a peer.
+/// Returns `None` if headers-first synchronization is disabled.
+pub struct GetHeadersBeacon;
+
+impl Message for GetHeadersBeacon {
+    type Result = Option<CheckpointBeacon>;
+}
+
+/// Ask for the headers of the blocks following a beacon, to answer a `GetHeaders` message
+pub struct GetHeadersAfter {
+    /// Epoch and hash of the last header known to the peer
+    pub beacon: CheckpointBeacon,
+}
+
+impl Message for GetHeadersAfter {
+    type Result = Vec<HashedBlockHeader>;
+}
+
+/// Validate the headers received from a peer and add them to the chain of validated headers.
+/// Returns the hashes of the blocks of the new headers.
+pub struct AddHeaders {
+    /// Headers received from the peer
+    pub headers: Vec<HashedBlockHeader>,
+    /// Address of the peer, which is blamed if the headers turn out to be fake
+    pub address: SocketAddr,
+}
+
+impl Message for AddHeaders {
+    type Result = Result<Vec<Hash>, BlocksManagerError>;
+}
+
+/// Ask for a range of blocks to download from a peer. Returns `None` if the peer is still
+/// downloading a range or there are no blocks left to assign.
+pub struct AssignBlocks {
+    /// Address of the peer
+    pub address: SocketAddr,
+}
+
+impl Message for AssignBlocks {
+    type Result = Option<Vec<Hash>>;
+}
+
+/// Let other peers download the blocks assigned to a peer which is no longer connected
+pub struct ReleaseBlocks {
+    /// Address of the peer
+    pub address: SocketAddr,
+}
+
+impl Message for ReleaseBlocks {
+    type Result = ();
+}
+
+/// Message to subscribe to the new blocks added to the chain. The subscription ends when the
+/// recipient stops. Returns the identifier of the subscription
+pub struct SubscribeNewBlocks {
+    /// Recipient of the new blocks
+    pub recipient: Recipient<NewBlockNotification>,
+}
+
+impl Message for SubscribeNewBlocks {
+    type Result = u64;
+}
+
+/// Message to end a subscription to the new blocks. Returns whether the subscription existed
+pub struct UnsubscribeNewBlocks {
+    /// Identifier of the subscription
+    pub subscription: u64,
+}
+
+impl Message for UnsubscribeNewBlocks {
+    type Result = bool;
+}
+
+/// New block added to the chain, sent to the subscribers of the new blocks
+#[derive(Clone, Debug)]
+pub struct NewBlockNotification {
+    /// Identifier of the subscription
+    pub subscription: u64,
+
+    /// Hash of the block
+    pub hash: Hash,
+
+    /// Block
+    pub block: Block,
+}
+
+impl Message for NewBlockNotification {
+    type Result = ();
+}
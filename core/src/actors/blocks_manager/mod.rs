@@ -13,30 +13,68 @@
 //! * Having a method for letting other components get the epoch of the current tip of the
 //! blockchain (e.g. the last epoch field required for the handshake in the Witnet network
 //! protocol).
+//! * Pruning the blocks older than `storage.pruning.keep_blocks` epochs, if pruning is enabled or
+//! the database is approaching its maximum size, keeping only their headers.
+//! * Moving the blocks older than `storage.archive.keep_blocks` epochs to the archive, if enabled.
+//! * Validating the block headers received from the peers against the checkpoints before their
+//! blocks are downloaded, if `sync.headers_first` is enabled.
+//! * Splitting the download of the blocks of the validated headers among the outbound peers.
+//! * Relaying the new blocks to the peers, unless the relay policy of `connections.relay` rejects
+//! the blocks received from a peer.
+//! * Notifying the new blocks to their subscribers, e.g.: the JSON-RPC connections subscribed to
+//! the `newBlocks` topic.
 use actix::{
-    ActorFuture, Context, ContextFutureSpawner, Supervised, System, SystemService, WrapFuture,
+    ActorFuture, Context, ContextFutureSpawner, Recipient, Supervised, System, SystemService,
+    WrapFuture,
 };
 
 use witnet_data_structures::chain::ChainInfo;
 
+use self::download::BlockDownload;
+use self::headers::{HeaderChain, HeadersError, MAX_HEADERS_PER_MESSAGE};
+use self::messages::NewBlockNotification;
 use crate::actors::{
-    storage_keys::CHAIN_KEY,
-    storage_manager::{messages::Put, StorageManager},
+    session::messages::{RequestBlocks, RequestHeaders},
+    sessions_manager::{
+        messages::{Broadcast, DisconnectPeers, ReportMisbehavior},
+        SessionsManager,
+    },
+    storage_keys::{block_key, transaction_key, CHAIN_KEY},
+    storage_manager::{
+        messages::{Archive, Flush, Put, Write},
+        StorageManager,
+    },
 };
 
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use witnet_data_structures::chain::{Block, Epoch, Hash};
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
+use std::time::Instant;
+use witnet_data_structures::chain::{
+    Block, BlockHeaderWithProof, CheckpointBeacon, Epoch, Hash, HashedBlockHeader,
+};
 
-use witnet_storage::{error::StorageError, storage::Storable};
+use witnet_storage::{
+    error::{StorageError, StorageResult},
+    storage::{Storable, WriteBatch},
+};
 
 use witnet_crypto::hash::calculate_sha256;
+use witnet_p2p::bans::Misbehavior;
+use witnet_p2p::relay::RelayPolicy;
 use witnet_util::error::WitnetError;
 
 mod actor;
 mod handlers;
 
+/// Download of the blocks of the validated headers from several peers
+pub mod download;
+
+/// Chain of validated block headers for headers-first synchronization
+pub mod headers;
+
 /// Messages for BlocksManager
 pub mod messages;
 
@@ -47,6 +85,12 @@ pub enum BlocksManagerError {
     BlockAlreadyExists,
     /// A block does not exist
     BlockDoesNotExist,
+    /// A block was pruned, only its header is available
+    BlockPruned,
+    /// A block before the last checkpoint whose header has not been validated
+    BlockNotInHeaderChain,
+    /// Headers received from a peer were rejected
+    InvalidHeaders(HeadersError),
     /// StorageError
     StorageError(WitnetError<StorageError>),
 }
@@ -70,6 +114,30 @@ pub struct BlocksManager {
     epoch_to_block_hash: HashMap<Epoch, HashSet<Hash>>,
     /// Map that stores blocks by their hash
     blocks: HashMap<Hash, Block>,
+    /// Map that stores the headers of the pruned blocks by their hash
+    pruned_headers: HashMap<Hash, BlockHeaderWithProof>,
+    /// Number of epochs whose blocks are kept entirely when pruning
+    keep_blocks: Option<Epoch>,
+    /// Whether old blocks are pruned on every epoch
+    pruning_enabled: bool,
+    /// Last epoch notified by the `EpochManager`
+    current_epoch: Option<Epoch>,
+    /// Number of epochs whose blocks are kept in the database, if old blocks are archived
+    archive_keep_blocks: Option<Epoch>,
+    /// Epoch before which every block has already been archived
+    archived_until: Epoch,
+    /// Whether the storage is read-only, so nothing is persisted
+    read_only: bool,
+    /// Validated headers whose blocks are missing, if headers-first synchronization is enabled
+    header_chain: Option<HeaderChain>,
+    /// Blocks of the validated headers being downloaded from the peers
+    download: Option<BlockDownload>,
+    /// Policy for relaying the blocks received from the peers
+    relay_policy: RelayPolicy,
+    /// Recipients of the new blocks, by subscription identifier
+    block_subscribers: HashMap<u64, Recipient<NewBlockNotification>>,
+    /// Identifier of the next subscription to the new blocks
+    next_subscription: u64,
 }
 
 /// Required trait for being able to retrieve BlocksManager address from registry
@@ -82,6 +150,10 @@ impl SystemService for BlocksManager {}
 impl BlocksManager {
     /// Method to persist chain_info into storage
     fn persist_chain_info(&self, ctx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+
         // Get StorageManager address
         let storage_manager_addr = System::current().registry().get::<StorageManager>();
 
@@ -115,14 +187,164 @@ impl BlocksManager {
             .wait(ctx);
     }
 
+    /// Method to persist a block and all its transactions into storage in a single atomic write,
+    /// so a crash never leaves a block partially stored
+    fn persist_block(&self, ctx: &mut Context<Self>, hash: Hash) {
+        if self.read_only {
+            return;
+        }
+
+        let batch = match self.blocks.get(&hash).map(|block| block_batch(hash, block)) {
+            Some(Ok(batch)) => batch,
+            Some(Err(e)) => {
+                error!("Error when serializing block {:?}: {}", hash, e);
+                return;
+            }
+            None => {
+                error!("Trying to persist an unknown block {:?}", hash);
+                return;
+            }
+        };
+
+        // Get StorageManager address
+        let storage_manager_addr = System::current().registry().get::<StorageManager>();
+        storage_manager_addr
+            .send(Write::new(batch))
+            .into_actor(self)
+            .then(move |res, _act, _ctx| {
+                match res {
+                    Ok(Ok(_)) => debug!("BlocksManager successfully persisted block {:?}", hash),
+                    Ok(Err(e)) => error!("BlocksManager failed to persist block {:?}: {}", hash, e),
+                    Err(e) => error!("Unsuccessful communication with storage manager: {}", e),
+                }
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    /// Method to send a new block to its subscribers, dropping the subscriptions whose recipients
+    /// stopped
+    fn notify_new_block(&mut self, hash: Hash) {
+        if self.block_subscribers.is_empty() {
+            return;
+        }
+        let block = match self.blocks.get(&hash) {
+            Some(block) => block,
+            None => return,
+        };
+
+        self.block_subscribers.retain(|subscription, recipient| {
+            recipient
+                .do_send(NewBlockNotification {
+                    subscription: *subscription,
+                    hash,
+                    block: block.clone(),
+                })
+                .is_ok()
+        });
+    }
+
+    /// Method to check whether a block has been validated through its header, which is the case
+    /// of the blocks of the header chain until they are added
+    fn has_validated_header(&self, hash: Hash) -> bool {
+        self.header_chain
+            .as_ref()
+            .map_or(false, |header_chain| header_chain.has_header(hash))
+    }
+
+    /// Method to find the supplier of a fake header hash: when the peer asked for the block of a
+    /// validated header sends a block with that same header but another hash, the hash claimed by
+    /// the supplier of the header was not the hash of its block
+    fn fake_header_supplier(&self, block: &Block, sender: SocketAddr) -> Option<SocketAddr> {
+        let header_chain = self.header_chain.as_ref()?;
+        let (hash, header) = header_chain.header_at(block.header.block_header.beacon)?;
+        if *header != block.header || block_hash(block).ok()? == hash {
+            return None;
+        }
+        let requested = self
+            .download
+            .as_ref()
+            .and_then(|download| download.assigned_to(hash))
+            == Some(sender);
+
+        if requested {
+            header_chain.supplier(hash)
+        } else {
+            None
+        }
+    }
+
+    /// Method to discard the headers supplied by a peer back to the last good header, penalize the
+    /// peer and let the sessions request the following headers again
+    fn discard_headers_of(
+        &mut self,
+        ctx: &mut Context<Self>,
+        supplier: SocketAddr,
+        misbehavior: Misbehavior,
+    ) {
+        let discarded = match &mut self.header_chain {
+            Some(header_chain) => header_chain.discard_supplier(supplier),
+            None => return,
+        };
+        if let Some(download) = &mut self.download {
+            download.discard(&discarded);
+        }
+        warn!(
+            "Discarding {} headers supplied by peer {}: {:?}",
+            discarded.len(),
+            supplier,
+            misbehavior
+        );
+
+        let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+        sessions_manager_addr
+            .send(ReportMisbehavior {
+                address: supplier,
+                misbehavior,
+            })
+            .into_actor(self)
+            .then(move |res, _act, _ctx| {
+                match res {
+                    Ok(true) => {
+                        info!("Peer {} was banned, closing its session", supplier);
+                        let sessions_manager_addr =
+                            System::current().registry().get::<SessionsManager>();
+                        sessions_manager_addr.do_send(DisconnectPeers {
+                            addresses: vec![supplier],
+                        });
+                    }
+                    Ok(false) => {}
+                    Err(e) => error!("Unsuccessful communication with sessions manager: {}", e),
+                }
+                actix::fut::ok(())
+            })
+            .spawn(ctx);
+
+        sessions_manager_addr.do_send(Broadcast {
+            command: RequestHeaders,
+        });
+    }
+
     fn process_new_block(&mut self, block: Block) -> Result<Hash, BlocksManagerError> {
         // Calculate the hash of the block
-        let hash = calculate_sha256(&block.to_bytes()?);
+        let hash = block_hash(&block)?;
 
         // Check if we already have a block with that hash
         if let Some(_block) = self.blocks.get(&hash) {
             Err(BlocksManagerError::BlockAlreadyExists)
         } else {
+            // Blocks before the last checkpoint must have been validated through their headers
+            if let Some(header_chain) = &mut self.header_chain {
+                let epoch = block.header.block_header.beacon.checkpoint;
+                if !header_chain.accepts_block(hash, epoch) {
+                    return Err(BlocksManagerError::BlockNotInHeaderChain);
+                }
+                header_chain.take_header(hash);
+            }
+            if let Some(download) = &mut self.download {
+                download.received(hash);
+            }
+
             // This is a new block, insert it into the internal maps
             {
                 // Insert the new block into the map that relates epochs to block hashes
@@ -147,13 +369,283 @@ impl BlocksManager {
         }
     }
 
+    /// Headers of the blocks of the chain following `beacon`, up to `MAX_HEADERS_PER_MESSAGE`.
+    /// The headers of pruned blocks are included.
+    fn headers_after(&self, beacon: CheckpointBeacon) -> Vec<HashedBlockHeader> {
+        let mut epochs: Vec<Epoch> = self
+            .epoch_to_block_hash
+            .keys()
+            .filter(|epoch| **epoch > beacon.checkpoint)
+            .cloned()
+            .collect();
+        epochs.sort();
+
+        let mut headers = vec![];
+        let mut prev_hash = beacon.hash_prev_block;
+        for epoch in epochs {
+            let next = self.epoch_to_block_hash[&epoch].iter().find_map(|hash| {
+                self.blocks
+                    .get(hash)
+                    .map(|block| &block.header)
+                    .or_else(|| self.pruned_headers.get(hash))
+                    .filter(|header| header.block_header.beacon.hash_prev_block == prev_hash)
+                    .map(|header| HashedBlockHeader {
+                        hash: *hash,
+                        header: header.clone(),
+                    })
+            });
+            if let Some(hashed_header) = next {
+                prev_hash = hashed_header.hash;
+                headers.push(hashed_header);
+                if headers.len() == MAX_HEADERS_PER_MESSAGE {
+                    break;
+                }
+            }
+        }
+
+        headers
+    }
+
+    /// Hashes of the blocks of the epochs in `range`, with their epoch, up to `limit` of them.
+    /// The hashes of the blocks of the same epoch are sorted, so the result does not depend on
+    /// the order of the maps.
+    fn epoch_block_hashes(
+        &self,
+        range: &RangeInclusive<Epoch>,
+        limit: usize,
+    ) -> Vec<(Epoch, Hash)> {
+        let mut epochs: Vec<Epoch> = self
+            .epoch_to_block_hash
+            .keys()
+            .filter(|epoch| range.start() <= epoch && *epoch <= range.end())
+            .cloned()
+            .collect();
+        epochs.sort();
+
+        let mut hashes = vec![];
+        for epoch in epochs {
+            let mut epoch_hashes: Vec<Hash> =
+                self.epoch_to_block_hash[&epoch].iter().cloned().collect();
+            epoch_hashes.sort_by_key(|Hash::SHA256(bytes)| *bytes);
+            for hash in epoch_hashes {
+                if hashes.len() == limit {
+                    return hashes;
+                }
+                hashes.push((epoch, hash));
+            }
+        }
+
+        hashes
+    }
+
     fn try_to_get_block(&mut self, hash: Hash) -> Result<Block, BlocksManagerError> {
         // Check if we have a block with that hash
-        self.blocks.get(&hash).map_or_else(
-            || Err(BlocksManagerError::BlockDoesNotExist),
-            |block| Ok(block.clone()),
-        )
+        match self.blocks.get(&hash) {
+            Some(block) => Ok(block.clone()),
+            None if self.pruned_headers.contains_key(&hash) => Err(BlocksManagerError::BlockPruned),
+            None => Err(BlocksManagerError::BlockDoesNotExist),
+        }
+    }
+
+    /// Prune the blocks older than `keep_blocks` epochs before `current_epoch`, deleting their
+    /// transactions from the storage
+    fn prune(&mut self, ctx: &mut Context<Self>, current_epoch: Epoch, keep_blocks: Epoch) {
+        let pruned = self.prune_blocks(current_epoch, keep_blocks);
+        if pruned.is_empty() {
+            return;
+        }
+        info!(
+            "Pruned {} blocks older than {} epochs",
+            pruned.len(),
+            keep_blocks
+        );
+        if self.read_only {
+            return;
+        }
+
+        let batch = match pruned_transactions_batch(&pruned) {
+            Ok(batch) => batch,
+            Err(e) => {
+                error!("Error when serializing pruned transactions: {}", e);
+                return;
+            }
+        };
+        let storage_manager_addr = System::current().registry().get::<StorageManager>();
+        storage_manager_addr
+            .send(Write::new(batch))
+            .into_actor(self)
+            .then(|res, _act, _ctx| {
+                match res {
+                    Ok(Ok(_)) => debug!("BlocksManager deleted the pruned transactions"),
+                    Ok(Err(e)) => {
+                        error!("BlocksManager failed to delete pruned transactions: {}", e)
+                    }
+                    Err(e) => error!("Unsuccessful communication with storage manager: {}", e),
+                }
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    /// Take the blocks requested to the peers which did not send them in time, and let the
+    /// sessions request them to other peers. The headers of the blocks which no peer could send
+    /// are discarded.
+    fn expire_downloads(&mut self, ctx: &mut Context<Self>) {
+        let (stalled, unavailable) = match &mut self.download {
+            Some(download) => {
+                let stalled = download.expire(Instant::now());
+                (stalled, download.take_unavailable())
+            }
+            None => return,
+        };
+        for address in &stalled {
+            warn!("Peer {} did not send the requested blocks in time", address);
+        }
+
+        // The headers of the first missing blocks are most likely fake. Once the headers of their
+        // supplier are discarded, the following hashes are no longer in the header chain
+        for hash in unavailable {
+            let supplier = self
+                .header_chain
+                .as_ref()
+                .and_then(|header_chain| header_chain.supplier(hash));
+            if let Some(supplier) = supplier {
+                self.discard_headers_of(ctx, supplier, Misbehavior::UnavailableBlocks);
+            }
+        }
+
+        if stalled.is_empty() {
+            return;
+        }
+        let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+        sessions_manager_addr.do_send(Broadcast {
+            command: RequestBlocks,
+        });
+    }
+
+    /// Make sure that the writes of the last epoch have reached the disk, whatever the value of
+    /// `storage.sync_writes`, so a crash loses at most the current epoch
+    fn flush_storage(&self, ctx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+
+        let storage_manager_addr = System::current().registry().get::<StorageManager>();
+        storage_manager_addr
+            .send(Flush)
+            .into_actor(self)
+            .then(|res, _act, _ctx| {
+                match res {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => error!("BlocksManager failed to flush the storage: {}", e),
+                    Err(e) => error!("Unsuccessful communication with storage manager: {}", e),
+                }
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    /// Move the blocks older than `keep_blocks` epochs before `current_epoch` from the database to
+    /// the archive, where the storage can still read them
+    fn archive(&mut self, ctx: &mut Context<Self>, current_epoch: Epoch, keep_blocks: Epoch) {
+        if self.read_only {
+            return;
+        }
+        let keys: Vec<Vec<u8>> = self
+            .blocks_to_archive(current_epoch, keep_blocks)
+            .iter()
+            .map(block_key)
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+
+        let storage_manager_addr = System::current().registry().get::<StorageManager>();
+        storage_manager_addr
+            .send(Archive::new(keys))
+            .into_actor(self)
+            .then(move |res, _act, _ctx| {
+                match res {
+                    Ok(Ok(archived)) => info!(
+                        "Archived {} blocks older than {} epochs",
+                        archived, keep_blocks
+                    ),
+                    Ok(Err(e)) => error!("BlocksManager failed to archive old blocks: {}", e),
+                    Err(e) => error!("Unsuccessful communication with storage manager: {}", e),
+                }
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    /// Hashes of the blocks older than `keep_blocks` epochs before `current_epoch` which have not
+    /// been archived yet. They are considered archived from then on.
+    fn blocks_to_archive(&mut self, current_epoch: Epoch, keep_blocks: Epoch) -> Vec<Hash> {
+        let oldest_kept_epoch = current_epoch.saturating_sub(keep_blocks);
+        if oldest_kept_epoch <= self.archived_until {
+            return vec![];
+        }
+        let archived_until = self.archived_until;
+        let hashes = self
+            .epoch_to_block_hash
+            .iter()
+            .filter(|(epoch, _)| **epoch >= archived_until && **epoch < oldest_kept_epoch)
+            .flat_map(|(_, hashes)| hashes.iter().cloned())
+            .collect();
+        self.archived_until = oldest_kept_epoch;
+
+        hashes
+    }
+
+    /// Remove the blocks older than `keep_blocks` epochs before `current_epoch`, keeping their
+    /// headers. Returns the pruned blocks.
+    fn prune_blocks(&mut self, current_epoch: Epoch, keep_blocks: Epoch) -> Vec<Block> {
+        let oldest_kept_epoch = current_epoch.saturating_sub(keep_blocks);
+        let old_hashes: Vec<Hash> = self
+            .epoch_to_block_hash
+            .iter()
+            .filter(|(epoch, _)| **epoch < oldest_kept_epoch)
+            .flat_map(|(_, hashes)| hashes.iter().cloned())
+            .collect();
+
+        let mut pruned = vec![];
+        for hash in old_hashes {
+            if let Some(block) = self.blocks.remove(&hash) {
+                self.pruned_headers.insert(hash, block.header.clone());
+                pruned.push(block);
+            }
+        }
+
+        pruned
+    }
+}
+
+/// Hash identifying a block
+pub fn block_hash(block: &Block) -> Result<Hash, BlocksManagerError> {
+    Ok(calculate_sha256(&block.to_bytes()?))
+}
+
+/// Batch with the writes needed to persist a block: the block itself and each of its transactions
+fn block_batch(hash: Hash, block: &Block) -> StorageResult<WriteBatch<Vec<u8>, Vec<u8>>> {
+    let mut batch = WriteBatch::new();
+    batch.put_t(block_key(&hash), block)?;
+    for transaction in &block.txns {
+        let transaction_hash = calculate_sha256(&transaction.to_bytes()?);
+        batch.put_t(transaction_key(&transaction_hash), transaction)?;
     }
+
+    Ok(batch)
+}
+
+/// Batch deleting the transactions of pruned blocks from the storage
+fn pruned_transactions_batch(blocks: &[Block]) -> StorageResult<WriteBatch<Vec<u8>, Vec<u8>>> {
+    let mut batch = WriteBatch::new();
+    for transaction in blocks.iter().flat_map(|block| &block.txns) {
+        let transaction_hash = calculate_sha256(&transaction.to_bytes()?);
+        batch.delete(transaction_key(&transaction_hash));
+    }
+
+    Ok(batch)
 }
 
 #[cfg(test)]
@@ -316,6 +808,375 @@ mod tests {
         assert_eq!(stored_block, block_a);
     }
 
+    #[test]
+    fn prune_old_blocks() {
+        let mut bm = BlocksManager::default();
+
+        use witnet_data_structures::chain::*;
+        let block_at = |checkpoint| Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint,
+                        hash_prev_block: Hash::SHA256([4; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let old_block = block_at(2);
+        let hash_old = bm.process_new_block(old_block.clone()).unwrap();
+        let hash_new = bm.process_new_block(block_at(8)).unwrap();
+
+        // Keep the blocks of the last 5 epochs, that is, from epoch 5 on
+        assert_eq!(bm.prune_blocks(10, 5), vec![old_block.clone()]);
+        assert!(bm.prune_blocks(10, 5).is_empty());
+
+        // The header of the pruned block is kept, and it is still indexed by epoch
+        assert_eq!(bm.pruned_headers[&hash_old], old_block.header);
+        assert!(bm.epoch_to_block_hash[&2].contains(&hash_old));
+        match bm.try_to_get_block(hash_old) {
+            Err(BlocksManagerError::BlockPruned) => {}
+            _ => panic!("Expected a pruned block"),
+        }
+        assert!(bm.try_to_get_block(hash_new).is_ok());
+    }
+
+    #[test]
+    fn archive_old_blocks() {
+        let mut bm = BlocksManager::default();
+
+        use witnet_data_structures::chain::*;
+        let block_at = |checkpoint| Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint,
+                        hash_prev_block: Hash::SHA256([4; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let hash_old = bm.process_new_block(block_at(2)).unwrap();
+        let hash_new = bm.process_new_block(block_at(8)).unwrap();
+
+        // Keep the blocks of the last 5 epochs, that is, from epoch 5 on
+        assert_eq!(bm.blocks_to_archive(10, 5), vec![hash_old]);
+        // Each block is archived only once
+        assert!(bm.blocks_to_archive(10, 5).is_empty());
+        assert!(bm.blocks_to_archive(12, 5).is_empty());
+        assert_eq!(bm.blocks_to_archive(14, 5), vec![hash_new]);
+
+        // Archived blocks are still available
+        assert!(bm.try_to_get_block(hash_old).is_ok());
+    }
+
+    #[test]
+    fn block_batch_contains_transactions() {
+        use witnet_data_structures::chain::*;
+        use witnet_storage::storage::WriteOp;
+        let block = Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint: 2,
+                        hash_prev_block: Hash::SHA256([4; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let hash = calculate_sha256(&block.to_bytes().unwrap());
+
+        let ops: Vec<_> = block_batch(hash, &block).unwrap().into_iter().collect();
+
+        // The block and its only transaction
+        assert_eq!(ops.len(), 2);
+        assert_eq!(
+            ops[0],
+            WriteOp::Put(block_key(&hash), block.to_bytes().unwrap())
+        );
+        match &ops[1] {
+            WriteOp::Put(key, _) => assert!(key.starts_with(b"transaction-")),
+            op => panic!("Unexpected operation {:?}", op),
+        }
+
+        // Pruning the block deletes its transaction
+        let ops: Vec<_> = pruned_transactions_batch(&[block])
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(ops.len(), 1);
+        match &ops[0] {
+            WriteOp::Delete(key) => assert!(key.starts_with(b"transaction-")),
+            op => panic!("Unexpected operation {:?}", op),
+        }
+    }
+
+    #[test]
+    fn get_headers_after_beacon() {
+        let mut bm = BlocksManager::default();
+
+        use witnet_data_structures::chain::*;
+        let block_at = |checkpoint, hash_prev_block| Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint,
+                        hash_prev_block,
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let genesis = CheckpointBeacon {
+            checkpoint: 0,
+            hash_prev_block: Hash::SHA256([4; 32]),
+        };
+        let hash_1 = bm
+            .process_new_block(block_at(1, genesis.hash_prev_block))
+            .unwrap();
+        // A block of another chain is not included
+        bm.process_new_block(block_at(2, Hash::SHA256([9; 32])))
+            .unwrap();
+        let hash_3 = bm.process_new_block(block_at(3, hash_1)).unwrap();
+        // The headers of pruned blocks are included
+        bm.prune_blocks(2, 0);
+
+        let hashes: Vec<Hash> = bm
+            .headers_after(genesis)
+            .into_iter()
+            .map(|hashed_header| hashed_header.hash)
+            .collect();
+        assert_eq!(hashes, vec![hash_1, hash_3]);
+
+        let after_first = CheckpointBeacon {
+            checkpoint: 1,
+            hash_prev_block: hash_1,
+        };
+        assert_eq!(bm.headers_after(after_first)[0].hash, hash_3);
+    }
+
+    #[test]
+    fn get_epoch_block_hashes() {
+        let mut bm = BlocksManager::default();
+
+        use witnet_data_structures::chain::*;
+        let block_at = |checkpoint, influence| Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint,
+                        hash_prev_block: Hash::SHA256([4; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let hash_1 = bm.process_new_block(block_at(1, 99999)).unwrap();
+        let hash_3 = bm.process_new_block(block_at(3, 99999)).unwrap();
+        let hash_4 = bm.process_new_block(block_at(4, 99999)).unwrap();
+        // Two blocks of the same epoch, sorted by hash
+        let hash_5a = bm.process_new_block(block_at(5, 99999)).unwrap();
+        let hash_5b = bm.process_new_block(block_at(5, 12345)).unwrap();
+        let (hash_5a, hash_5b) = match (hash_5a, hash_5b) {
+            (Hash::SHA256(a), Hash::SHA256(b)) if a > b => (hash_5b, hash_5a),
+            _ => (hash_5a, hash_5b),
+        };
+        // The hashes of pruned blocks are included
+        bm.prune_blocks(4, 0);
+
+        assert_eq!(
+            bm.epoch_block_hashes(&(0..=Epoch::max_value()), 10),
+            vec![
+                (1, hash_1),
+                (3, hash_3),
+                (4, hash_4),
+                (5, hash_5a),
+                (5, hash_5b)
+            ]
+        );
+        assert_eq!(
+            bm.epoch_block_hashes(&(2..=5), 3),
+            vec![(3, hash_3), (4, hash_4), (5, hash_5a)]
+        );
+        assert_eq!(bm.epoch_block_hashes(&(2..=2), 10), vec![]);
+    }
+
+    #[test]
+    fn reject_blocks_not_in_header_chain() {
+        use witnet_data_structures::chain::*;
+        let block = Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint: 2,
+                        hash_prev_block: Hash::SHA256([4; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let hash = calculate_sha256(&block.to_bytes().unwrap());
+        let genesis = CheckpointBeacon {
+            checkpoint: 0,
+            hash_prev_block: Hash::SHA256([4; 32]),
+        };
+        let checkpoints = [Checkpoint { epoch: 2, hash }];
+        let mut bm = BlocksManager::default();
+        bm.header_chain = Some(HeaderChain::new(genesis, &checkpoints));
+
+        match bm.process_new_block(block.clone()) {
+            Err(BlocksManagerError::BlockNotInHeaderChain) => {}
+            _ => panic!("Expected a block not in the header chain"),
+        }
+
+        let hashed_header = HashedBlockHeader {
+            hash,
+            header: block.header.clone(),
+        };
+        bm.header_chain
+            .as_mut()
+            .unwrap()
+            .add_headers(&[hashed_header], "127.0.0.1:21337".parse().unwrap())
+            .unwrap();
+        assert_eq!(bm.process_new_block(block).unwrap(), hash);
+    }
+
+    #[test]
+    fn received_blocks_complete_download() {
+        use std::time::Duration;
+        use witnet_data_structures::chain::*;
+        let block = Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint: 2,
+                        hash_prev_block: Hash::SHA256([4; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let hash = calculate_sha256(&block.to_bytes().unwrap());
+        let mut bm = BlocksManager::default();
+        let mut download = BlockDownload::new(1024, Duration::from_secs(30));
+        download.add(&[hash]);
+        bm.download = Some(download);
+
+        bm.process_new_block(block).unwrap();
+
+        assert_eq!(bm.download.unwrap().missing_len(), 0);
+    }
+
+    #[test]
+    fn find_supplier_of_fake_header_hash() {
+        use std::time::Duration;
+        use witnet_data_structures::chain::*;
+        let block = Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint: 2,
+                        hash_prev_block: Hash::SHA256([4; 32]),
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        };
+        let fake_hash = Hash::SHA256([7; 32]);
+        let supplier: SocketAddr = "127.0.0.1:21337".parse().unwrap();
+        let sender: SocketAddr = "127.0.0.1:21338".parse().unwrap();
+        let genesis = CheckpointBeacon {
+            checkpoint: 0,
+            hash_prev_block: Hash::SHA256([4; 32]),
+        };
+        let mut header_chain = HeaderChain::new(genesis, &[]);
+        let hashed_header = HashedBlockHeader {
+            hash: fake_hash,
+            header: block.header.clone(),
+        };
+        header_chain
+            .add_headers(&[hashed_header], supplier)
+            .unwrap();
+        let mut download = BlockDownload::new(1024, Duration::from_secs(30));
+        download.add(&[fake_hash]);
+        let mut bm = BlocksManager::default();
+        bm.header_chain = Some(header_chain);
+        bm.download = Some(download);
+
+        // Only the peer asked for the block can show that the hash of its header is fake
+        assert_eq!(bm.fake_header_supplier(&block, sender), None);
+        bm.download
+            .as_mut()
+            .unwrap()
+            .assign(sender, Instant::now())
+            .unwrap();
+        assert_eq!(bm.fake_header_supplier(&block, sender), Some(supplier));
+
+        // A block with another header may just be another block
+        let mut other_block = block.clone();
+        other_block.header.proof.influence = 1;
+        assert_eq!(bm.fake_header_supplier(&other_block, sender), None);
+    }
+
     #[test]
     fn get_non_existent_block() {
         // Create empty BlocksManager
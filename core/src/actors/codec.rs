@@ -32,8 +32,28 @@ pub type BytesMut = bytes::BytesMut;
 /// The message format is described in the file [schemas/protocol.fbs][protocol]
 ///
 /// [protocol]: https://github.com/witnet/witnet-rust/blob/master/schemas/protocol.fbs
+///
+/// The frames bigger than `max_frame_size` are rejected as soon as their header arrives, before
+/// buffering the rest of them.
 #[derive(Debug, Message, Eq, PartialEq, Clone)]
-pub struct P2PCodec;
+pub struct P2PCodec {
+    /// Maximum size of the frames received, without the header
+    max_frame_size: usize,
+}
+
+impl P2PCodec {
+    /// Create a codec receiving frames of at most `max_frame_size` bytes
+    pub fn new(max_frame_size: usize) -> Self {
+        P2PCodec { max_frame_size }
+    }
+}
+
+/// Codec receiving frames of any size allowed by the header
+impl Default for P2PCodec {
+    fn default() -> Self {
+        P2PCodec::new(u16::max_value() as usize)
+    }
+}
 
 /// Implement decoder trait for P2P codec
 impl Decoder for P2PCodec {
@@ -47,9 +67,21 @@ impl Decoder for P2PCodec {
         if msg_len >= HEADER_SIZE {
             let mut header_vec = Cursor::new(&src[0..HEADER_SIZE]);
             let msg_size = header_vec.read_u16::<BigEndian>().unwrap() as usize;
+            if msg_size > self.max_frame_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Message size {} bytes exceeds the maximum of {} bytes",
+                        msg_size, self.max_frame_size
+                    ),
+                ));
+            }
             if msg_len >= msg_size + HEADER_SIZE {
                 src.split_to(HEADER_SIZE);
                 ftb = Some(src.split_to(msg_size));
+            } else {
+                // Make room for the rest of the message, which is known to be within the limit
+                src.reserve(msg_size + HEADER_SIZE - msg_len);
             }
         }
         // If the message is incomplete, return without consuming anything.
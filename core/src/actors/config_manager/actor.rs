@@ -1,21 +1,52 @@
-use super::ConfigManager;
+use super::{ConfigManager, AVAILABLE_SUBSYSTEMS};
 use actix::{Actor, Context};
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use std::sync::Arc;
-use witnet_config::config::Config;
-use witnet_config::loaders::toml;
 
 impl Actor for ConfigManager {
     type Context = Context<Self>;
 
     fn started(&mut self, _ctx: &mut Self::Context) {
         debug!("Config Manager actor has been started!");
+        for candidate in &self.config_file_search {
+            debug!(
+                "Looking for configuration file in {}: {} ({})",
+                candidate.location,
+                candidate.path.to_string_lossy(),
+                if candidate.exists {
+                    "found"
+                } else {
+                    "not found"
+                }
+            );
+        }
+        match self.load() {
+            Ok(config) => self.config = Arc::new(config),
+            Err(errors) => {
+                for e in &errors {
+                    error!("Invalid configuration: {}", e);
+                }
+                panic!("Found {} errors in the configuration", errors.len());
+            }
+        }
+        self.apply_log_level();
         info!(
-            "Reading configuration from file: {}",
-            self.config_file.to_string_lossy()
+            "Configuration fingerprint: {}",
+            self.config.fingerprint_hex()
         );
-        self.config = Arc::new(Config::from_partial(
-            &toml::from_file(&self.config_file).unwrap(),
-        ))
+        for subsystem in self.config.enabled_subsystems() {
+            if AVAILABLE_SUBSYSTEMS.contains(&subsystem) {
+                info!("Subsystem enabled: {}", subsystem);
+            } else {
+                warn!(
+                    "Subsystem `{}` is enabled but is not available in this node yet, ignoring it",
+                    subsystem
+                );
+            }
+        }
+        match self.config.to_redacted_toml_string() {
+            Ok(contents) => debug!("Effective configuration:\n{}", contents),
+            Err(e) => debug!("Could not serialize the effective configuration: {}", e),
+        }
     }
 }
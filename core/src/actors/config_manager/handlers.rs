@@ -1,9 +1,14 @@
-use actix::{Context, Handler};
+use actix::{Context, Handler, System};
+use log::{error, info, warn};
+use std::sync::Arc;
+use witnet_config::config::{diff::FieldChange, reload, Config};
 
 use super::{
-    messages::{ConfigResult, GetConfig},
+    messages::{ConfigResult, GetConfig, ReloadConfig, SetConfig, SetConfigResult},
     ConfigManager,
 };
+use crate::actors::json_rpc::{messages::SetEnabled, JsonRpcServer};
+use crate::actors::sessions_manager::{messages::SetLimits, SessionsManager};
 
 impl Handler<GetConfig> for ConfigManager {
     type Result = ConfigResult;
@@ -12,3 +17,88 @@ impl Handler<GetConfig> for ConfigManager {
         Ok(self.config.clone())
     }
 }
+
+impl Handler<ReloadConfig> for ConfigManager {
+    type Result = ();
+
+    fn handle(&mut self, _msg: ReloadConfig, _ctx: &mut Context<Self>) {
+        let new_config = match self.load() {
+            Ok(config) => config,
+            Err(errors) => {
+                for e in &errors {
+                    error!("Invalid configuration: {}", e);
+                }
+                error!("Configuration not reloaded, keeping the previous one");
+                return;
+            }
+        };
+
+        self.apply(&new_config);
+    }
+}
+
+impl Handler<SetConfig> for ConfigManager {
+    type Result = SetConfigResult;
+
+    fn handle(&mut self, msg: SetConfig, _ctx: &mut Context<Self>) -> Self::Result {
+        let not_tunable: Vec<String> = msg
+            .params
+            .iter()
+            .filter(|(path, _)| !reload::is_runtime_tunable(path))
+            .map(|(path, _)| format!("`{}` cannot be changed at runtime", path))
+            .collect();
+        if !not_tunable.is_empty() {
+            return Err(not_tunable);
+        }
+
+        let mut runtime_overrides = self.runtime_overrides.clone();
+        runtime_overrides.extend(
+            msg.params
+                .iter()
+                .map(|(path, value)| format!("{}={}", path, value)),
+        );
+        // The new params go through the same loading and validation
+        // as the configuration file
+        let new_config = self.load_with(&runtime_overrides)?;
+        self.runtime_overrides = runtime_overrides;
+
+        Ok(self.apply(&new_config))
+    }
+}
+
+impl ConfigManager {
+    /// Apply the hot-reloadable params of a new configuration,
+    /// notifying the affected subsystems, and return the changes
+    /// applied.
+    fn apply(&mut self, new_config: &Config) -> Vec<FieldChange> {
+        let reload = self.config.reload(new_config);
+        for change in &reload.ignored {
+            warn!("Ignoring change until the node is restarted: {}", change);
+        }
+        if reload.applied.is_empty() {
+            info!("Configuration reloaded, no changes to apply");
+            return reload.applied;
+        }
+        for change in &reload.applied {
+            info!("Applying configuration change: {}", change);
+        }
+        self.config = Arc::new(reload.config);
+        info!(
+            "Configuration fingerprint: {}",
+            self.config.fingerprint_hex()
+        );
+
+        // Notify the subsystems affected by the changes
+        self.apply_log_level();
+        let registry = System::current().registry();
+        registry.get::<SessionsManager>().do_send(SetLimits {
+            inbound_limit: self.config.connections.inbound_limit,
+            outbound_limit: self.config.connections.outbound_limit,
+        });
+        registry.get::<JsonRpcServer>().do_send(SetEnabled {
+            enabled: self.config.jsonrpc.enabled,
+        });
+
+        reload.applied
+    }
+}
@@ -3,7 +3,7 @@ use actix::Message;
 use std::io;
 
 use std::sync::Arc;
-use witnet_config::config::Config;
+use witnet_config::config::{diff::FieldChange, Config};
 
 /// Message to obtain the configuration managed by the `ConfigManager`
 /// actor.
@@ -15,3 +15,28 @@ pub type ConfigResult = Result<Arc<Config>, io::Error>;
 impl Message for GetConfig {
     type Result = ConfigResult;
 }
+
+/// Message to reload the configuration from its file, applying the
+/// hot-reloadable params (see `witnet_config::config::reload`).
+pub struct ReloadConfig;
+
+impl Message for ReloadConfig {
+    type Result = ();
+}
+
+/// Message to change some params of the configuration while the node
+/// is running. Only the params in
+/// `witnet_config::config::reload::RUNTIME_TUNABLE` can be changed.
+pub struct SetConfig {
+    /// Params to change, as `(path, value)` pairs, e.g.:
+    /// `("log.level", "debug")`
+    pub params: Vec<(String, String)>,
+}
+
+/// Result of the SetConfig message handling: the changes applied or
+/// every error found
+pub type SetConfigResult = Result<Vec<FieldChange>, Vec<String>>;
+
+impl Message for SetConfig {
+    type Result = SetConfigResult;
+}
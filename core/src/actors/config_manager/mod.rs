@@ -3,10 +3,14 @@ use actix::{
     Supervised, System, SystemService, WrapFuture,
 };
 
-use log::error;
+use log::{error, info};
+use std::env;
 use std::path::PathBuf;
 use std::sync::Arc;
+use witnet_config::config::subsystems::Subsystem;
 use witnet_config::config::Config;
+use witnet_config::dirs::{self, Candidate};
+use witnet_config::loaders::{self, cli, toml};
 
 // Internal Actor implementation for ConfigManager
 mod actor;
@@ -18,7 +22,11 @@ mod handlers;
 pub mod messages;
 
 /// Default configuration filename
-pub const CONFIG_DEFAULT_FILENAME: &str = "witnet.toml";
+pub const CONFIG_DEFAULT_FILENAME: &str = dirs::CONFIG_FILENAME;
+
+/// Subsystems implemented by the node, which are started only if
+/// they are enabled in the configuration
+pub const AVAILABLE_SUBSYSTEMS: [Subsystem; 2] = [Subsystem::JsonRpc, Subsystem::Rest];
 
 /// Config manager actor: manages the application configuration
 ///
@@ -33,6 +41,21 @@ pub struct ConfigManager {
     /// Configuration file from which to read the configuration when
     /// the actor starts
     config_file: PathBuf,
+
+    /// Locations checked when searching for the configuration file
+    config_file_search: Vec<Candidate>,
+
+    /// Name of the profile to merge over the configuration read from
+    /// the file
+    profile: Option<String>,
+
+    /// Command-line overrides (`section.param=value`) applied on top
+    /// of the configuration read from the file
+    overrides: Vec<String>,
+
+    /// Overrides (`section.param=value`) set through the JSON-RPC API,
+    /// applied on top of the command-line ones
+    runtime_overrides: Vec<String>,
 }
 
 impl Default for ConfigManager {
@@ -40,20 +63,80 @@ impl Default for ConfigManager {
         Self {
             config: Arc::new(Config::default()),
             config_file: PathBuf::from(CONFIG_DEFAULT_FILENAME),
+            config_file_search: vec![],
+            profile: None,
+            overrides: vec![],
+            runtime_overrides: vec![],
         }
     }
 }
 
 impl ConfigManager {
     /// Create a new ConfigManager instance that will try to read the
-    /// given configuration file name.
+    /// given configuration file name or, if none is given, the first
+    /// configuration file found in the standard locations (see
+    /// `witnet_config::dirs`).
     pub fn new(config_file: Option<PathBuf>) -> Self {
+        let search = dirs::find_config_file(config_file.as_ref().map(PathBuf::as_path));
+
         Self {
             config: Arc::new(Config::default()),
-            config_file: match config_file {
-                Some(path) => path,
-                None => PathBuf::from(CONFIG_DEFAULT_FILENAME),
-            },
+            config_file: search.path(),
+            config_file_search: search.trace,
+            profile: None,
+            overrides: vec![],
+            runtime_overrides: vec![],
+        }
+    }
+
+    /// Set the name of the profile that will be merged over the
+    /// configuration read from the file.
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Set the overrides (`section.param=value`) that will be applied
+    /// on top of the configuration read from the file.
+    pub fn with_overrides(mut self, overrides: Vec<String>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Read the configuration file, select the profile, apply the
+    /// overrides and validate the result, returning every error found.
+    fn load(&self) -> Result<Config, Vec<String>> {
+        self.load_with(&self.runtime_overrides)
+    }
+
+    /// Like `load`, but using the given runtime overrides
+    fn load_with(&self, runtime_overrides: &[String]) -> Result<Config, Vec<String>> {
+        info!(
+            "Reading configuration from file: {}",
+            self.config_file.to_string_lossy()
+        );
+        let mut partial_config =
+            toml::from_file(&self.config_file).map_err(|e| vec![e.to_string()])?;
+        if let Some(profile) = &self.profile {
+            info!("Using configuration profile: {}", profile);
+            partial_config = loaders::select_profile(partial_config, profile)
+                .map_err(|e| vec![e.to_string()])?;
+        }
+        let partial_config = cli::apply(
+            partial_config,
+            self.overrides.iter().chain(runtime_overrides),
+        )
+        .map_err(|e| vec![e.to_string()])?;
+
+        Config::try_from_partial(&partial_config)
+            .map_err(|e| e.problems.iter().map(ToString::to_string).collect())
+    }
+
+    /// Set the maximum level of log messages, unless the `RUST_LOG`
+    /// environment variable is set, which takes precedence.
+    fn apply_log_level(&self) {
+        if env::var_os("RUST_LOG").is_none() {
+            log::set_max_level(self.config.log.level);
         }
     }
 }
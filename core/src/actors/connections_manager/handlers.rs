@@ -1,12 +1,16 @@
 use actix::{
     actors::resolver::{ConnectAddr, Resolver},
-    ActorFuture, ContextFutureSpawner, Handler, SystemService, WrapFuture,
+    ActorFuture, ContextFutureSpawner, Handler, MessageResult, SystemService, WrapFuture,
 };
+use log::{debug, error, info, warn};
+use tokio::timer::Timeout;
 
 use witnet_p2p::sessions::SessionType;
 
+use crate::actors::stream::PeerStream;
+
 use super::{
-    messages::{InboundTcpConnect, OutboundTcpConnect},
+    messages::{GetMetrics, InboundTcpConnect, OutboundTcpConnect, StopConnections},
     ConnectionsManager,
 };
 
@@ -16,9 +20,43 @@ impl Handler<InboundTcpConnect> for ConnectionsManager {
     type Result = ();
 
     /// Method to handle the InboundTcpConnect message
-    fn handle(&mut self, msg: InboundTcpConnect, _ctx: &mut Self::Context) {
-        // Request the creation of a new session actor from connection
-        ConnectionsManager::request_session_creation(msg.stream, SessionType::Inbound);
+    fn handle(&mut self, msg: InboundTcpConnect, ctx: &mut Self::Context) {
+        // Dropping the stream closes the connection
+        if self.stopped {
+            debug!("Refusing inbound connection, the node is shutting down");
+            return;
+        }
+        self.metrics.inbound_connections += 1;
+
+        match &self.tls_acceptor {
+            // Request the creation of a new session actor from connection
+            None => ConnectionsManager::request_session_creation(
+                PeerStream::Tcp(msg.stream),
+                SessionType::Inbound,
+            ),
+            // Complete the TLS handshake before creating the session, closing the connections
+            // which fail it or take too long
+            Some(acceptor) => {
+                let peer_addr = msg.stream.peer_addr();
+                Timeout::new(acceptor.accept(msg.stream), self.handshake_timeout)
+                    .into_actor(self)
+                    .then(move |res, act, _ctx| {
+                        match res {
+                            Ok(stream) => ConnectionsManager::request_session_creation(
+                                PeerStream::Tls(stream),
+                                SessionType::Inbound,
+                            ),
+                            Err(e) => {
+                                warn!("TLS handshake with peer {:?} failed: {}", peer_addr, e);
+                                act.metrics.failed_tls_handshakes += 1;
+                            }
+                        }
+
+                        actix::fut::ok(())
+                    })
+                    .spawn(ctx);
+            }
+        }
     }
 }
 
@@ -29,11 +67,97 @@ impl Handler<OutboundTcpConnect> for ConnectionsManager {
 
     /// Method to handle the OutboundTcpConnect message
     fn handle(&mut self, msg: OutboundTcpConnect, ctx: &mut Self::Context) {
-        // Get resolver from registry and send a ConnectAddr message to it
+        if self.stopped {
+            debug!(
+                "Not connecting to peer {}, the node is shutting down",
+                msg.address
+            );
+            return;
+        }
+        let address = msg.address;
+        let session_type = msg.session_type;
+        let proxy = match self.proxy {
+            None => {
+                // Get resolver from registry and send a ConnectAddr message to it
+                Resolver::from_registry()
+                    .send(ConnectAddr(address))
+                    .into_actor(self)
+                    .then(move |res, act, ctx| {
+                        act.process_connect_addr_response(ctx, res, address, session_type)
+                    })
+                    .wait(ctx);
+
+                return;
+            }
+            Some(proxy) => proxy,
+        };
+
+        // Connect to the proxy, and ask it to connect to the peer
         Resolver::from_registry()
-            .send(ConnectAddr(msg.address))
+            .send(ConnectAddr(proxy))
             .into_actor(self)
-            .then(|res, _act, _ctx| ConnectionsManager::process_connect_addr_response(res))
+            .then(move |res, act, ctx| {
+                match res {
+                    Err(e) => error!("Unsuccessful communication with resolver: {}", e),
+                    Ok(Err(e)) => {
+                        warn!("Error while trying to connect to proxy {}: {}", proxy, e);
+                        act.metrics.failed_connections += 1;
+                    }
+                    Ok(Ok(stream)) => Timeout::new(
+                        ConnectionsManager::socks_connect(stream, proxy, address),
+                        act.handshake_timeout,
+                    )
+                    .into_actor(act)
+                    .then(move |res, act, ctx| {
+                        match res {
+                            Ok(stream) => {
+                                info!("Connected to peer {} through proxy {}", address, proxy);
+                                act.metrics.outbound_connections += 1;
+
+                                // Request the creation of a new session actor from connection
+                                act.request_outbound_session_creation(
+                                    ctx,
+                                    stream,
+                                    address,
+                                    Some(proxy),
+                                    session_type,
+                                );
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "Error while trying to connect to the peer {} through proxy {}: {}",
+                                    address, proxy, e
+                                );
+                                act.report_unreachable_peer(address, session_type);
+                            }
+                        }
+
+                        actix::fut::ok(())
+                    })
+                    .spawn(ctx),
+                }
+
+                actix::fut::ok(())
+            })
             .wait(ctx);
     }
 }
+
+/// Handler for GetMetrics messages
+impl Handler<GetMetrics> for ConnectionsManager {
+    type Result = MessageResult<GetMetrics>;
+
+    fn handle(&mut self, _msg: GetMetrics, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.metrics)
+    }
+}
+
+/// Handler for StopConnections messages
+impl Handler<StopConnections> for ConnectionsManager {
+    type Result = ();
+
+    fn handle(&mut self, _msg: StopConnections, _: &mut Self::Context) {
+        info!("Stop accepting and opening connections");
+        self.stopped = true;
+    }
+}
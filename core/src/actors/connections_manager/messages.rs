@@ -3,6 +3,10 @@ use std::net::SocketAddr;
 use actix::{actors::resolver::ResolverError, Message};
 use tokio::net::TcpStream;
 
+use witnet_p2p::sessions::SessionType;
+
+use super::metrics::ConnectionsMetrics;
+
 /// Actor message that holds the TCP stream from an inbound TCP connection
 #[derive(Message)]
 pub struct InboundTcpConnect {
@@ -22,7 +26,24 @@ impl InboundTcpConnect {
 pub struct OutboundTcpConnect {
     /// Address of the outbound connection
     pub address: SocketAddr,
+    /// Type of the session created from the connection: outbound or feeler
+    pub session_type: SessionType,
 }
 
 /// Returned type by the Resolver actor for the ConnectAddr message
 pub type ResolverResult = Result<TcpStream, ResolverError>;
+
+/// Actor message to stop accepting inbound connections and opening outbound ones, when the node is
+/// shutting down
+pub struct StopConnections;
+
+impl Message for StopConnections {
+    type Result = ();
+}
+
+/// Actor message to obtain the metrics of the connections
+pub struct GetMetrics;
+
+impl Message for GetMetrics {
+    type Result = ConnectionsMetrics;
+}
@@ -0,0 +1,20 @@
+//! # Connections metrics
+//!
+//! The connections manager counts the connections accepted by its
+//! listeners and the outbound connections it opens or fails to open,
+//! so operators can tell whether the node is reachable and whether its
+//! peers are.
+use serde_derive::Serialize;
+
+/// Connections counted by the connections manager since the node started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ConnectionsMetrics {
+    /// Inbound connections accepted by the listeners
+    pub inbound_connections: u64,
+    /// Inbound connections closed because their TLS handshake failed
+    pub failed_tls_handshakes: u64,
+    /// Outbound connections opened, directly or through the proxy
+    pub outbound_connections: u64,
+    /// Outbound connections which could not be opened
+    pub failed_connections: u64,
+}
@@ -1,24 +1,91 @@
-use actix::{fut::FutureResult, Actor, AsyncContext, MailboxError, System, SystemService};
-use futures::Stream;
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, SocketAddr},
+    thread,
+    time::Duration,
+};
+
+use actix::{
+    fut::FutureResult, Actor, ActorFuture, AsyncContext, ContextFutureSpawner, MailboxError,
+    System, SystemService, WrapFuture,
+};
+use futures::{Future, Stream};
 use log::{debug, error, info, warn};
-use tokio::net::{TcpListener, TcpStream};
+use net2::TcpBuilder;
+use tokio::{
+    io::{read_exact, write_all},
+    net::{TcpListener, TcpStream},
+    reactor::Handle,
+    timer::Timeout,
+};
+use tokio_rustls::{webpki::DNSNameRef, TlsAcceptor, TlsConnector};
 
 use crate::actors::{
     config_manager::send_get_config_request,
-    sessions_manager::{messages::Create, SessionsManager},
+    peers_manager::{
+        messages::{AddFailedPeers, RemovePeers},
+        PeersManager,
+    },
+    sessions_manager::{
+        messages::{Create, SetExternalAddress},
+        SessionsManager,
+    },
+    stream::{load_tls_acceptor, load_tls_connector, PeerStream},
 };
 
+use self::metrics::ConnectionsMetrics;
 use witnet_config::config::Config;
-use witnet_p2p::sessions::SessionType;
+use witnet_p2p::{
+    nat::{error::NatResult, upnp::Igd, NatPmp},
+    sessions::SessionType,
+    socks,
+};
+
+/// Lifetime requested for the port mapping, which is renewed when half of it has passed
+const PORT_MAPPING_LIFETIME: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// Maximum number of pending connections of the dual-stack listener
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Time to wait before requesting the port mapping again after an error
+const PORT_MAPPING_RETRY: Duration = Duration::from_secs(5 * 60);
+
+/// Time to wait for the gateways in the local network to answer a UPnP search
+const UPNP_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
 
 mod actor;
 mod handlers;
 /// Messages to hold the TCP stream from an inbound TCP connection
 pub mod messages;
+/// Metrics of the connections
+pub mod metrics;
 
 /// Connections manager actor
 #[derive(Default)]
-pub struct ConnectionsManager;
+pub struct ConnectionsManager {
+    /// Acceptor of the TLS connections, if the server terminates TLS
+    tls_acceptor: Option<TlsAcceptor>,
+
+    /// Connector of the TLS connections to the peers terminating TLS, if any
+    tls_connector: Option<TlsConnector>,
+
+    /// Names in the certificates of the peers terminating TLS, by their address
+    tls_peers: HashMap<SocketAddr, String>,
+
+    /// Address of the SOCKS5 proxy for the outbound connections, if any
+    proxy: Option<SocketAddr>,
+
+    /// Time limit of the TLS handshake of the connections, and of the SOCKS5 handshake of the
+    /// outbound connections
+    handshake_timeout: Duration,
+
+    /// Connections counted since the node started
+    metrics: ConnectionsMetrics,
+
+    /// Whether the node is shutting down, so no connections are accepted or opened
+    stopped: bool,
+}
 
 /// Required trait for being able to retrieve connections manager address from system registry
 impl actix::Supervised for ConnectionsManager {}
@@ -36,8 +103,8 @@ impl ConnectionsManager {
         send_get_config_request(self, ctx, ConnectionsManager::process_config);
     }
 
-    /// Method to request the creation of a session actor from a TCP stream
-    fn request_session_creation(stream: TcpStream, session_type: SessionType) {
+    /// Method to request the creation of a session actor from a stream
+    fn request_session_creation(stream: PeerStream, session_type: SessionType) {
         // Get sessions manager address
         let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
 
@@ -48,9 +115,101 @@ impl ConnectionsManager {
         });
     }
 
+    /// Method to request the creation of a session actor from the stream of an outbound connection
+    /// to `address`, opened through `proxy` if any. The TLS handshake is completed first if the
+    /// peer terminates TLS, and the peer is reported as unreachable if it fails
+    fn request_outbound_session_creation(
+        &mut self,
+        ctx: &mut <Self as Actor>::Context,
+        stream: TcpStream,
+        address: SocketAddr,
+        proxy: Option<SocketAddr>,
+        session_type: SessionType,
+    ) {
+        let server_name = match self.tls_peers.get(&address) {
+            Some(server_name) => server_name.clone(),
+            None => {
+                let stream = match proxy {
+                    Some(_) => PeerStream::Socks(stream, address),
+                    None => PeerStream::Tcp(stream),
+                };
+                ConnectionsManager::request_session_creation(stream, session_type);
+
+                return;
+            }
+        };
+        // The server names have already been validated
+        let (connector, server_name) = match (
+            &self.tls_connector,
+            DNSNameRef::try_from_ascii_str(&server_name),
+        ) {
+            (Some(connector), Ok(server_name)) => (connector, server_name),
+            _ => {
+                warn!(
+                    "Not connecting to peer {}, its TLS certificate cannot be verified",
+                    address
+                );
+                self.report_unreachable_peer(address, session_type);
+
+                return;
+            }
+        };
+
+        Timeout::new(
+            connector.connect(server_name, stream),
+            self.handshake_timeout,
+        )
+        .into_actor(self)
+        .then(move |res, act, _ctx| {
+            match res {
+                Ok(stream) => ConnectionsManager::request_session_creation(
+                    PeerStream::TlsClient(stream, address),
+                    session_type,
+                ),
+                Err(e) => {
+                    warn!("TLS handshake with peer {} failed: {}", address, e);
+                    act.report_unreachable_peer(address, session_type);
+                }
+            }
+
+            actix::fut::ok(())
+        })
+        .spawn(ctx);
+    }
+
+    /// Method to report a peer which could not be reached to the peers manager: it is removed
+    /// from the peers list if a feeler connection tested it, otherwise the node backs off before
+    /// connecting to it again
+    fn report_unreachable_peer(&mut self, address: SocketAddr, session_type: SessionType) {
+        self.metrics.failed_connections += 1;
+
+        // Get peers manager address
+        let peers_manager_addr = System::current().registry().get::<PeersManager>();
+
+        match session_type {
+            SessionType::Feeler => {
+                info!(
+                    "Peer {} is unreachable, removing it from the peers list",
+                    address
+                );
+                peers_manager_addr.do_send(RemovePeers {
+                    addresses: vec![address],
+                });
+            }
+            SessionType::Outbound => peers_manager_addr.do_send(AddFailedPeers {
+                addresses: vec![address],
+            }),
+            SessionType::Inbound => {}
+        }
+    }
+
     /// Method to process resolver ConnectAddr response
     fn process_connect_addr_response(
+        &mut self,
+        ctx: &mut <Self as Actor>::Context,
         response: Result<messages::ResolverResult, MailboxError>,
+        address: SocketAddr,
+        session_type: SessionType,
     ) -> FutureResult<(), (), Self> {
         // Process the Result<ResolverResult, MailboxError>
         match response {
@@ -62,14 +221,25 @@ impl ConnectionsManager {
                 // Process the ResolverResult
                 match res {
                     Err(e) => {
-                        warn!("Error while trying to connect to the peer: {}", e);
+                        warn!(
+                            "Error while trying to connect to the peer {}: {}",
+                            address, e
+                        );
+                        self.report_unreachable_peer(address, session_type);
                         actix::fut::err(())
                     }
                     Ok(stream) => {
                         info!("Connected to peer {:?}", stream.peer_addr());
+                        self.metrics.outbound_connections += 1;
 
                         // Request the creation of a new session actor from connection
-                        ConnectionsManager::request_session_creation(stream, SessionType::Outbound);
+                        self.request_outbound_session_creation(
+                            ctx,
+                            stream,
+                            address,
+                            None,
+                            session_type,
+                        );
 
                         actix::fut::ok(())
                     }
@@ -80,21 +250,187 @@ impl ConnectionsManager {
 
     /// Method to process the configuration received from the ConfigManager
     fn process_config(&mut self, ctx: &mut <Self as Actor>::Context, config: &Config) {
-        // Bind TCP listener to this address
-        // FIXME(#72): decide what to do with actor when server cannot be started
-        let listener = TcpListener::bind(&config.connections.server_addr).unwrap();
-
-        // Add message stream which will return a InboundTcpConnect for each incoming TCP connection
-        ctx.add_message_stream(
-            listener
-                .incoming()
-                .map_err(|_| ())
-                .map(messages::InboundTcpConnect::new),
-        );
-
-        info!(
-            "P2P server has been started at {:?}",
-            &config.connections.server_addr
-        );
+        self.handshake_timeout = config.connections.handshake_timeout;
+
+        // The proxy address has already been validated
+        self.proxy = config.connections.proxy_address().unwrap_or(None);
+        if let Some(proxy) = self.proxy {
+            info!(
+                "Outbound connections will be opened through proxy {}",
+                proxy
+            );
+        }
+
+        // Load the certificates of the authorities of the peers terminating TLS. The connections
+        // to those peers are not opened without them
+        self.tls_peers = config
+            .connections
+            .tls_peers
+            .iter()
+            .map(|tls_peer| (tls_peer.address, tls_peer.server_name.clone()))
+            .collect();
+        self.tls_connector = match &config.connections.tls_ca {
+            Some(tls_ca) if !self.tls_peers.is_empty() => match load_tls_connector(tls_ca) {
+                Ok(connector) => Some(connector),
+                Err(e) => {
+                    error!("Connections to TLS peers cannot be opened: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        // Load the certificate and key of the server if it terminates TLS. The server is not
+        // started without them, as peers would connect to it without encryption
+        if config.connections.tls.is_enabled() {
+            match load_tls_acceptor(&config.connections.tls) {
+                Ok(acceptor) => self.tls_acceptor = Some(acceptor),
+                Err(e) => {
+                    error!("P2P server has not been started: {}", e);
+                    return;
+                }
+            }
+        }
+
+        // Bind a TCP listener to each of the server addresses. All of them feed the same sessions
+        // manager
+        for server_addr in &config.connections.server_addrs {
+            // FIXME(#72): decide what to do with actor when server cannot be started
+            let listener = ConnectionsManager::bind(*server_addr).unwrap();
+
+            // Add message stream which will return a InboundTcpConnect for each incoming TCP
+            // connection
+            ctx.add_message_stream(
+                listener
+                    .incoming()
+                    .map_err(|_| ())
+                    .map(messages::InboundTcpConnect::new),
+            );
+
+            info!(
+                "P2P server has been started at {:?}{}",
+                server_addr,
+                if self.tls_acceptor.is_some() {
+                    " (TLS)"
+                } else {
+                    ""
+                }
+            );
+        }
+
+        // Ask the router to forward the port of the announced server address to this node
+        if config.connections.upnp {
+            ConnectionsManager::map_port(config.connections.server_addr().port());
+        }
+    }
+
+    /// Method to bind the TCP listener of the server. A listener bound to all the IPv6 interfaces
+    /// (`[::]`) also accepts IPv4 connections, whatever the default of the operating system is
+    fn bind(address: SocketAddr) -> io::Result<TcpListener> {
+        match address {
+            SocketAddr::V6(v6) if v6.ip().is_unspecified() => {
+                let listener = TcpBuilder::new_v6()?
+                    .only_v6(false)?
+                    .reuse_address(true)?
+                    .bind(address)?
+                    .listen(LISTEN_BACKLOG)?;
+
+                TcpListener::from_std(listener, &Handle::default())
+            }
+            _ => TcpListener::bind(&address),
+        }
+    }
+
+    /// Method to open a connection to the peer at `address` through the SOCKS5 proxy at the other
+    /// end of `stream`
+    fn socks_connect(
+        stream: TcpStream,
+        proxy: SocketAddr,
+        address: SocketAddr,
+    ) -> impl Future<Item = TcpStream, Error = String> {
+        let io_error = |e: io::Error| e.to_string();
+
+        write_all(stream, socks::greeting())
+            .and_then(|(stream, _)| read_exact(stream, [0; socks::METHOD_SELECTION_LEN]))
+            .map_err(io_error)
+            .and_then(move |(stream, selection)| {
+                socks::check_method_selection(proxy, &selection)
+                    .map(|_| stream)
+                    .map_err(|e| e.to_string())
+            })
+            .and_then(move |stream| {
+                write_all(stream, socks::connect_request(address))
+                    .and_then(|(stream, _)| read_exact(stream, [0; socks::REPLY_HEADER_LEN]))
+                    .map_err(io_error)
+            })
+            .and_then(move |(stream, header)| {
+                socks::check_reply_header(proxy, &header)
+                    .map(|len| (stream, len))
+                    .map_err(|e| e.to_string())
+            })
+            // Skip the address to which the proxy bound the connection, which is not used
+            .and_then(move |(stream, len)| read_exact(stream, vec![0; len]).map_err(io_error))
+            .map(|(stream, _)| stream)
+    }
+
+    /// Method to keep the router forwarding `port` to this node, telling the SessionsManager the
+    /// external address to announce to the peers. The requests to the router block, so they are
+    /// sent from their own thread.
+    fn map_port(port: u16) {
+        let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+        thread::spawn(move || loop {
+            let wait = match ConnectionsManager::request_port_mapping(port) {
+                Ok((address, lifetime)) => {
+                    info!("Port {} forwarded from {} by the router", port, address);
+                    sessions_manager_addr.do_send(SetExternalAddress { address });
+
+                    // Gateways may grant a very short lifetime, do not flood them
+                    (lifetime / 2).max(Duration::from_secs(60))
+                }
+                Err(e) => {
+                    warn!("The router did not forward port {}: {}", port, e);
+
+                    PORT_MAPPING_RETRY
+                }
+            };
+            thread::sleep(wait);
+        });
+    }
+
+    /// Method to request the router to forward `port` to this node, returning the external
+    /// address of the router and the lifetime of the mapping. NAT-PMP is tried first, and UPnP
+    /// if the router does not speak NAT-PMP or its address is not found in the routing table.
+    fn request_port_mapping(port: u16) -> NatResult<(SocketAddr, Duration)> {
+        ConnectionsManager::request_nat_pmp_mapping(port).or_else(|e| {
+            debug!("NAT-PMP port mapping failed, trying UPnP: {}", e);
+            ConnectionsManager::request_upnp_mapping(port)
+        })
+    }
+
+    /// Method to request a port mapping with NAT-PMP to the default gateway
+    fn request_nat_pmp_mapping(port: u16) -> NatResult<(SocketAddr, Duration)> {
+        let gateway = NatPmp::discover()?;
+        debug!("Requesting port mapping to gateway {}", gateway.gateway());
+        let mapping = gateway.map_tcp_port(port, PORT_MAPPING_LIFETIME)?;
+        let external_ip = gateway.external_address()?;
+
+        Ok((
+            SocketAddr::new(IpAddr::V4(external_ip), mapping.external_port),
+            mapping.lifetime,
+        ))
+    }
+
+    /// Method to request a port mapping with UPnP to the gateway found in the local network
+    fn request_upnp_mapping(port: u16) -> NatResult<(SocketAddr, Duration)> {
+        let gateway = Igd::discover(UPNP_DISCOVERY_TIMEOUT)?;
+        debug!("Requesting port mapping to gateway {}", gateway.gateway());
+        let mapping = gateway.map_tcp_port(port, PORT_MAPPING_LIFETIME)?;
+        let external_ip = gateway.external_address()?;
+
+        Ok((
+            SocketAddr::new(IpAddr::V4(external_ip), mapping.external_port),
+            mapping.lifetime,
+        ))
     }
 }
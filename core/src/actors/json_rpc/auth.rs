@@ -0,0 +1,509 @@
+use crypto::util::fixed_time_eq;
+use jsonrpc_core::{Error, ErrorCode};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use witnet_config::config::{redaction::REDACTED, Auth, Cors, JsonRPC};
+
+/// Name of the method called by the clients to authenticate
+pub const AUTHENTICATE_METHOD: &str = "authenticate";
+
+/// Code of the errors returned to the requests which are not authorized
+pub const UNAUTHORIZED: i64 = -32001;
+
+/// Methods that a connection is allowed to call
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Permissions {
+    /// Every method
+    All,
+    /// Only the listed methods
+    Methods(HashSet<String>),
+}
+
+impl Permissions {
+    /// Permissions given by the methods of an API key, where `*` means all of them
+    fn from_methods(methods: &[String]) -> Self {
+        if methods.iter().any(|method| method == "*") {
+            Permissions::All
+        } else {
+            Permissions::Methods(methods.iter().cloned().collect())
+        }
+    }
+
+    /// Whether the method with the given name can be called
+    pub fn allows(&self, method: &str) -> bool {
+        match self {
+            Permissions::All => true,
+            Permissions::Methods(methods) => methods.contains(method),
+        }
+    }
+}
+
+/// API keys accepted from the clients, with the permissions each of them gives
+#[derive(Debug, Default)]
+pub struct ApiKeys(Vec<(String, Permissions)>);
+
+impl ApiKeys {
+    /// API keys of the `jsonrpc.auth` section of the configuration
+    pub fn from_config(auth: &Auth) -> Self {
+        ApiKeys(
+            auth.keys
+                .iter()
+                .map(|api_key| {
+                    (
+                        api_key.key.expose().to_string(),
+                        Permissions::from_methods(&api_key.methods),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Permissions of the connections which have not authenticated: every method if there are no
+    /// keys, none otherwise
+    pub fn unauthenticated(&self) -> Permissions {
+        if self.0.is_empty() {
+            Permissions::All
+        } else {
+            Permissions::Methods(HashSet::new())
+        }
+    }
+
+    /// Permissions given by a key, if it is valid
+    fn permissions(&self, key: &str) -> Option<Permissions> {
+        // Every key is compared in constant time, so the time taken to answer does not reveal how
+        // much of a key was guessed
+        self.0
+            .iter()
+            .filter(|(api_key, _)| fixed_time_eq(api_key.as_bytes(), key.as_bytes()))
+            .map(|(_, permissions)| permissions.clone())
+            .next()
+    }
+}
+
+/// Methods served by the node, as configured in `jsonrpc.enabled_methods` and
+/// `jsonrpc.disabled_methods`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MethodFilter {
+    /// Methods served, all of them if empty
+    enabled: HashSet<String>,
+    /// Methods not served, even if enabled
+    disabled: HashSet<String>,
+}
+
+impl MethodFilter {
+    /// Whether the method with the given name is served
+    pub fn allows(&self, method: &str) -> bool {
+        (self.enabled.is_empty() || self.enabled.contains(method))
+            && !self.disabled.contains(method)
+    }
+}
+
+/// Access of the clients to the JSON-RPC methods: the methods served by the node, the API keys
+/// which give access to them, and the web pages which can connect over WebSocket
+#[derive(Debug, Default)]
+pub struct Access {
+    /// Methods served by the node
+    pub methods: MethodFilter,
+    /// API keys accepted from the clients
+    pub api_keys: ApiKeys,
+    /// Origins of the web pages allowed to open WebSocket connections
+    pub cors: Cors,
+}
+
+impl Access {
+    /// Access of the clients as configured in the `jsonrpc` section of the configuration
+    pub fn from_config(jsonrpc: &JsonRPC) -> Self {
+        Access {
+            methods: MethodFilter {
+                enabled: jsonrpc.enabled_methods.iter().cloned().collect(),
+                disabled: jsonrpc.disabled_methods.iter().cloned().collect(),
+            },
+            api_keys: ApiKeys::from_config(&jsonrpc.auth),
+            cors: jsonrpc.cors.clone(),
+        }
+    }
+}
+
+/// Result of checking a request against the permissions of a connection
+#[derive(Debug, Eq, PartialEq)]
+pub enum Authorization {
+    /// The request has to be forwarded to the JSON-RPC methods
+    Allowed,
+    /// The request has already been answered, with the response to send back, if any
+    Answered(Option<String>),
+}
+
+/// Check a request against the methods served by the node and the permissions of the connection
+/// which received it. Calls to `authenticate` are answered here, replacing the permissions of the
+/// connection with the ones of the given key. Calls to methods which are not served are answered
+/// as if the methods did not exist.
+///
+/// The calls of a batch are either all forwarded or all rejected, and `authenticate` has to be
+/// called on its own.
+pub fn authorize(request: &str, access: &Access, permissions: &mut Permissions) -> Authorization {
+    // Requests which cannot be parsed are forwarded, so they are answered with a parse error
+    let request: Value = match serde_json::from_str(request) {
+        Ok(request) => request,
+        Err(_) => return Authorization::Allowed,
+    };
+
+    match request {
+        Value::Array(calls) => {
+            let errors: Vec<Option<Error>> = calls
+                .iter()
+                .map(|call| match method(call) {
+                    Some(AUTHENTICATE_METHOD) => Some(Error {
+                        code: ErrorCode::ServerError(UNAUTHORIZED),
+                        message: format!("`{}` cannot be called in a batch", AUTHENTICATE_METHOD),
+                        data: None,
+                    }),
+                    Some(method) => reject(method, access, permissions),
+                    None => None,
+                })
+                .collect();
+            if errors.iter().all(Option::is_none) {
+                return Authorization::Allowed;
+            }
+
+            let outputs: Vec<Value> = calls
+                .iter()
+                .zip(errors)
+                .filter_map(|(call, error)| {
+                    failure(
+                        call,
+                        error.unwrap_or_else(|| Error {
+                            code: ErrorCode::ServerError(UNAUTHORIZED),
+                            message: "Other calls of the batch are not allowed".to_string(),
+                            data: None,
+                        }),
+                    )
+                })
+                .collect();
+            Authorization::Answered(if outputs.is_empty() {
+                None
+            } else {
+                Some(Value::Array(outputs).to_string())
+            })
+        }
+        call => match method(&call) {
+            Some(AUTHENTICATE_METHOD) => {
+                let result = authenticate(&call, &access.api_keys, permissions);
+                Authorization::Answered(output(&call, result).map(|output| output.to_string()))
+            }
+            Some(method) => match reject(method, access, permissions) {
+                Some(error) => {
+                    Authorization::Answered(failure(&call, error).map(|output| output.to_string()))
+                }
+                None => Authorization::Allowed,
+            },
+            None => Authorization::Allowed,
+        },
+    }
+}
+
+/// Error returned to the calls to a method, if the connection cannot call it
+fn reject(method: &str, access: &Access, permissions: &Permissions) -> Option<Error> {
+    if !access.methods.allows(method) {
+        Some(Error::method_not_found())
+    } else if !permissions.allows(method) {
+        Some(unauthorized(method))
+    } else {
+        None
+    }
+}
+
+/// Authenticate the connection with the key given in the params of a call
+fn authenticate(
+    call: &Value,
+    api_keys: &ApiKeys,
+    permissions: &mut Permissions,
+) -> Result<Value, Error> {
+    let key = call["params"][0]
+        .as_str()
+        .ok_or_else(|| Error::invalid_params("Expected the API key as the only param"))?;
+
+    match api_keys.permissions(key) {
+        Some(key_permissions) => {
+            *permissions = key_permissions;
+            Ok(Value::Bool(true))
+        }
+        None => {
+            // A failed attempt drops the permissions given by previous ones
+            *permissions = api_keys.unauthenticated();
+            Err(Error {
+                code: ErrorCode::ServerError(UNAUTHORIZED),
+                message: "Invalid API key".to_string(),
+                data: None,
+            })
+        }
+    }
+}
+
+/// Request as written to the logs, with the params of the calls to `authenticate`, which contain
+/// an API key, redacted. Requests which cannot be parsed are redacted entirely if they mention
+/// `authenticate`.
+pub fn redacted_request(request: &str) -> String {
+    let mut parsed: Value = match serde_json::from_str(request) {
+        Ok(parsed) => parsed,
+        Err(_) if request.contains(AUTHENTICATE_METHOD) => return REDACTED.to_string(),
+        Err(_) => return request.to_string(),
+    };
+
+    let mut redacted = false;
+    let calls: Vec<&mut Value> = match &mut parsed {
+        Value::Array(calls) => calls.iter_mut().collect(),
+        call => vec![call],
+    };
+    for call in calls {
+        if method(call) == Some(AUTHENTICATE_METHOD) {
+            if let Some(params) = call.get_mut("params") {
+                *params = Value::String(REDACTED.to_string());
+                redacted = true;
+            }
+        }
+    }
+
+    if redacted {
+        parsed.to_string()
+    } else {
+        request.to_string()
+    }
+}
+
+/// Name of the method of a call, if it has one
+fn method(call: &Value) -> Option<&str> {
+    call.get("method").and_then(Value::as_str)
+}
+
+/// Error returned to the calls to a method that the connection is not allowed to call
+fn unauthorized(method: &str) -> Error {
+    Error {
+        code: ErrorCode::ServerError(UNAUTHORIZED),
+        message: format!("Not authorized to call `{}`", method),
+        data: None,
+    }
+}
+
+/// Response to a call, unless it is a notification
+fn output(call: &Value, result: Result<Value, Error>) -> Option<Value> {
+    let id = call.get("id")?;
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(error) => json!({ "jsonrpc": "2.0", "error": error, "id": id }),
+    })
+}
+
+/// Error response to a call, unless it is a notification
+pub(super) fn failure(call: &Value, error: Error) -> Option<Value> {
+    output(call, Err(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_config::config::ApiKey;
+    use witnet_config::secret::Secret;
+
+    fn access() -> Access {
+        Access {
+            methods: MethodFilter::default(),
+            api_keys: ApiKeys::from_config(&Auth {
+                keys: vec![
+                    ApiKey {
+                        key: Secret::plain("explorer"),
+                        methods: vec!["getPeers".to_string()],
+                    },
+                    ApiKey {
+                        key: Secret::plain("admin"),
+                        methods: vec!["*".to_string()],
+                    },
+                ],
+            }),
+            cors: Cors::default(),
+        }
+    }
+
+    #[test]
+    fn no_keys_allow_everything() {
+        let access = Access::default();
+        let mut permissions = access.api_keys.unauthenticated();
+
+        assert_eq!(permissions, Permissions::All);
+        assert_eq!(
+            authorize(
+                r#"{"jsonrpc":"2.0","method":"banPeer","params":[],"id":1}"#,
+                &access,
+                &mut permissions
+            ),
+            Authorization::Allowed
+        );
+    }
+
+    #[test]
+    fn methods_need_authentication() {
+        let access = access();
+        let mut permissions = access.api_keys.unauthenticated();
+
+        assert_eq!(
+            authorize(
+                r#"{"jsonrpc":"2.0","method":"getPeers","id":1}"#,
+                &access,
+                &mut permissions
+            ),
+            Authorization::Answered(Some(
+                r#"{"error":{"code":-32001,"message":"Not authorized to call `getPeers`"},"id":1,"jsonrpc":"2.0"}"#
+                    .to_string()
+            ))
+        );
+        // Notifications are never answered
+        assert_eq!(
+            authorize(
+                r#"{"jsonrpc":"2.0","method":"getPeers"}"#,
+                &access,
+                &mut permissions
+            ),
+            Authorization::Answered(None)
+        );
+        // Requests which cannot be parsed are answered by the methods handler
+        assert_eq!(
+            authorize("{", &access, &mut permissions),
+            Authorization::Allowed
+        );
+    }
+
+    #[test]
+    fn authenticate_with_key() {
+        let access = access();
+        let mut permissions = access.api_keys.unauthenticated();
+
+        assert_eq!(
+            authorize(
+                r#"{"jsonrpc":"2.0","method":"authenticate","params":["explorer"],"id":1}"#,
+                &access,
+                &mut permissions
+            ),
+            Authorization::Answered(Some(
+                r#"{"id":1,"jsonrpc":"2.0","result":true}"#.to_string()
+            ))
+        );
+        assert!(permissions.allows("getPeers"));
+        assert!(!permissions.allows("banPeer"));
+        assert_eq!(
+            authorize(
+                r#"[{"jsonrpc":"2.0","method":"getPeers","id":1}]"#,
+                &access,
+                &mut permissions
+            ),
+            Authorization::Allowed
+        );
+
+        // The calls of a batch are rejected if any of them is not allowed
+        match authorize(
+            r#"[{"jsonrpc":"2.0","method":"getPeers","id":1},{"jsonrpc":"2.0","method":"banPeer","id":2}]"#,
+            &access,
+            &mut permissions,
+        ) {
+            Authorization::Answered(Some(response)) => {
+                let response: Value = serde_json::from_str(&response).unwrap();
+                assert_eq!(
+                    response[0]["error"]["message"],
+                    "Other calls of the batch are not allowed"
+                );
+                assert_eq!(
+                    response[1]["error"]["message"],
+                    "Not authorized to call `banPeer`"
+                );
+                assert_eq!(response[1]["id"], 2);
+            }
+            authorization => panic!("Unexpected authorization: {:?}", authorization),
+        }
+
+        authorize(
+            r#"{"jsonrpc":"2.0","method":"authenticate","params":["admin"],"id":2}"#,
+            &access,
+            &mut permissions,
+        );
+        assert_eq!(permissions, Permissions::All);
+    }
+
+    #[test]
+    fn invalid_key_drops_permissions() {
+        let access = access();
+        let mut permissions = Permissions::All;
+
+        match authorize(
+            r#"{"jsonrpc":"2.0","method":"authenticate","params":["admin2"],"id":1}"#,
+            &access,
+            &mut permissions,
+        ) {
+            Authorization::Answered(Some(response)) => {
+                assert!(response.contains("Invalid API key"))
+            }
+            authorization => panic!("Unexpected authorization: {:?}", authorization),
+        }
+        assert_eq!(permissions, access.api_keys.unauthenticated());
+    }
+
+    #[test]
+    fn methods_not_served() {
+        let access = Access {
+            methods: MethodFilter {
+                enabled: ["getPeers", "banPeer"]
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                disabled: ["banPeer"].iter().map(ToString::to_string).collect(),
+            },
+            api_keys: ApiKeys::default(),
+            cors: Cors::default(),
+        };
+        let mut permissions = access.api_keys.unauthenticated();
+
+        assert!(access.methods.allows("getPeers"));
+        assert!(!access.methods.allows("banPeer"));
+        assert!(!access.methods.allows("getConfig"));
+        assert_eq!(
+            authorize(
+                r#"{"jsonrpc":"2.0","method":"getPeers","id":1}"#,
+                &access,
+                &mut permissions
+            ),
+            Authorization::Allowed
+        );
+        assert_eq!(
+            authorize(
+                r#"{"jsonrpc":"2.0","method":"banPeer","id":1}"#,
+                &access,
+                &mut permissions
+            ),
+            Authorization::Answered(Some(
+                r#"{"error":{"code":-32601,"message":"Method not found"},"id":1,"jsonrpc":"2.0"}"#
+                    .to_string()
+            ))
+        );
+    }
+    #[test]
+    fn redact_api_keys() {
+        assert_eq!(
+            redacted_request(
+                r#"{"jsonrpc":"2.0","method":"authenticate","params":["admin"],"id":1}"#
+            ),
+            r#"{"id":1,"jsonrpc":"2.0","method":"authenticate","params":"<redacted>"}"#
+        );
+        assert_eq!(
+            redacted_request(
+                r#"[{"jsonrpc":"2.0","method":"getPeers","id":1},{"jsonrpc":"2.0","method":"authenticate","params":["admin"],"id":2}]"#
+            ),
+            r#"[{"id":1,"jsonrpc":"2.0","method":"getPeers"},{"id":2,"jsonrpc":"2.0","method":"authenticate","params":"<redacted>"}]"#
+        );
+        assert_eq!(
+            redacted_request(r#"{"method":"authenticate","params":["admin""#),
+            "<redacted>"
+        );
+
+        // Other requests are logged as they are
+        let request = r#"{"jsonrpc": "2.0", "method": "getPeers", "id": 1}"#;
+        assert_eq!(redacted_request(request), request);
+    }
+}
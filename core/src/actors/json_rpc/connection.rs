@@ -1,27 +1,44 @@
+use super::auth::{authorize, redacted_request, Access, Authorization, Permissions};
+use super::json_rpc_methods::{new_block_notification, session_event_notification, Subscriber};
+use super::limits::{rejection, RequestLimits};
 use super::newline_codec::NewLineCodec;
 use super::server::JsonRpcServer;
 use super::server::Unregister;
+use crate::actors::blocks_manager::messages::NewBlockNotification;
+use crate::actors::sessions_manager::messages::SessionEventNotification;
+use crate::actors::stream::PeerStream;
 use actix::{
-    io::FramedWrite, io::WriteHandler, Actor, Addr, AsyncContext, Context, Running, StreamHandler,
+    io::FramedWrite, io::WriteHandler, Actor, ActorFuture, Addr, AsyncContext, Context, Handler,
+    Running, StreamHandler, WrapFuture,
 };
 use bytes;
 use bytes::BytesMut;
-use jsonrpc_core::IoHandler;
+use jsonrpc_core::{IoHandler, MetaIoHandler};
 use log::*;
+use std::cell::RefCell;
 use std::io;
+use std::net::IpAddr;
 use std::rc::Rc;
+use std::time::Instant;
 use tokio::io::WriteHalf;
-use tokio::net::TcpStream;
 
 /// A single JSON-RPC connection
 pub struct JsonRpc {
     /// Stream
-    pub framed: FramedWrite<WriteHalf<TcpStream>, NewLineCodec>,
+    pub framed: FramedWrite<WriteHalf<PeerStream>, NewLineCodec>,
     /// Reference to parent
     // Needed to send the `Unregister` message when the connection closes
     pub parent: Addr<JsonRpcServer>,
     /// IoHandler
-    pub jsonrpc_io: Rc<IoHandler<()>>,
+    pub jsonrpc_io: Rc<IoHandler<Subscriber>>,
+    /// Methods served and API keys accepted from the client
+    pub access: Rc<Access>,
+    /// Methods that the client is allowed to call
+    pub permissions: Permissions,
+    /// IP address of the client
+    pub client: IpAddr,
+    /// Limits of the requests, shared by all the connections
+    pub limits: Rc<RefCell<RequestLimits>>,
 }
 
 impl Actor for JsonRpc {
@@ -43,33 +60,94 @@ impl WriteHandler<io::Error> for JsonRpc {}
 /// Implement `StreamHandler` trait in order to use `Framed` with an actor
 impl StreamHandler<BytesMut, io::Error> for JsonRpc {
     /// This is main event loop for client requests
-    fn handle(&mut self, bytes: BytesMut, _ctx: &mut Self::Context) {
-        info!("Got JSON-RPC message");
-        let msg = match String::from_utf8(bytes.to_vec()) {
-            Ok(msg) => {
-                // A valid utf8 string is forwarded to the JSON-RPC parser
-                // The message is assumed to be a valid JSON-RPC, otherwise an
-                // error is returned through the socket.
-                // For example, an empty string results in a JSON-RPC ParseError (-32700).
-                debug!("{}", msg);
-                msg
-            }
-            Err(e) => {
-                // When the input is not a valid utf8 string, a
-                // ParseError (-32700) is returned thought the socket
-                // and the message is printed in the debug logs for further inspection.
-                error!("Invalid UTF8 in JSON-RPC input");
-                debug!("{:?}", e);
+    fn handle(&mut self, bytes: BytesMut, ctx: &mut Self::Context) {
+        let msg = request_string(&bytes);
 
-                // Generate a ParseError later by trying to parse an empty string
-                "".to_string()
+        // Requests over the limits are rejected before anything else
+        let in_flight = match RequestLimits::start(&self.limits, self.client, Instant::now()) {
+            Ok(in_flight) => in_flight,
+            Err(e) => {
+                debug!("JSON-RPC request rejected: {}", e.message);
+                if let Some(response) = rejection(&msg, e) {
+                    self.framed.write(BytesMut::from(response));
+                }
+                return;
             }
         };
 
-        // Handle response synchronously
-        let response = self.jsonrpc_io.handle_request_sync(&msg);
-        if let Some(response) = response {
-            self.framed.write(BytesMut::from(response));
+        // Requests to methods which are not served or which the client
+        // is not allowed to call, and calls to `authenticate`, are
+        // answered right away
+        if let Authorization::Answered(response) =
+            authorize(&msg, &self.access, &mut self.permissions)
+        {
+            if let Some(response) = response {
+                self.framed.write(BytesMut::from(response));
+            }
+            return;
+        }
+
+        // Handle response asynchronously, as some methods need to
+        // wait for other actors. The notifications of the subscriptions
+        // made by the request are sent to this connection
+        let jsonrpc_io: &MetaIoHandler<Subscriber> = &self.jsonrpc_io;
+        let subscriber = Subscriber::new(ctx.address().recipient(), ctx.address().recipient());
+        let response = jsonrpc_io
+            .handle_request(&msg, subscriber)
+            .into_actor(self)
+            .map(move |response, act, _ctx| {
+                // The request stops being counted once it is answered
+                drop(in_flight);
+                if let Some(response) = response {
+                    act.framed.write(BytesMut::from(response));
+                }
+            });
+        ctx.spawn(response);
+    }
+}
+
+/// Handler for the session events the connection is subscribed to
+impl Handler<SessionEventNotification> for JsonRpc {
+    type Result = ();
+
+    fn handle(&mut self, msg: SessionEventNotification, _ctx: &mut Self::Context) {
+        let notification = session_event_notification(&msg).to_string();
+        self.framed.write(BytesMut::from(notification));
+    }
+}
+
+/// Handler for the new blocks the connection is subscribed to
+impl Handler<NewBlockNotification> for JsonRpc {
+    type Result = ();
+
+    fn handle(&mut self, msg: NewBlockNotification, _ctx: &mut Self::Context) {
+        let notification = new_block_notification(&msg).to_string();
+        self.framed.write(BytesMut::from(notification));
+    }
+}
+
+/// Convert the bytes of a request into the string parsed by the JSON-RPC handler
+pub(super) fn request_string(bytes: &[u8]) -> String {
+    info!("Got JSON-RPC message");
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(msg) => {
+            // A valid utf8 string is forwarded to the JSON-RPC parser
+            // The message is assumed to be a valid JSON-RPC, otherwise an
+            // error is returned through the socket.
+            // For example, an empty string results in a JSON-RPC ParseError (-32700).
+            // The API keys given to `authenticate` are not logged.
+            debug!("{}", redacted_request(&msg));
+            msg
+        }
+        Err(e) => {
+            // When the input is not a valid utf8 string, a
+            // ParseError (-32700) is returned thought the socket
+            // and the message is printed in the debug logs for further inspection.
+            error!("Invalid UTF8 in JSON-RPC input");
+            debug!("{:?}", e);
+
+            // Generate a ParseError later by trying to parse an empty string
+            "".to_string()
         }
     }
 }
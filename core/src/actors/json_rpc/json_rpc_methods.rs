@@ -1,19 +1,191 @@
 #[cfg(test)]
 use self::mock_actix::System;
-use crate::actors::blocks_manager::{messages::AddNewBlock, BlocksManager};
+use crate::actors::blocks_manager::{
+    messages::{
+        AddNewBlock, GetBlock, GetEpochBlockHashes, NewBlockNotification, SubscribeNewBlocks,
+        UnsubscribeNewBlocks,
+    },
+    BlocksManager, BlocksManagerError,
+};
+use crate::actors::config_manager::{
+    messages::{GetConfig, SetConfig},
+    ConfigManager,
+};
+use crate::actors::connections_manager::{
+    messages::GetMetrics as GetConnectionsMetrics, ConnectionsManager,
+};
+use crate::actors::peers_manager::{
+    messages::{AddPeers, GetMetrics as GetPeersMetrics, RemovePeers},
+    PeersManager,
+};
+use crate::actors::sessions_manager::{
+    messages::{
+        BanPeer, DisconnectPeers, GetConnectedPeers, GetMetrics as GetSessionsMetrics,
+        SessionEventNotification, SubscribeSessionEvents, UnsubscribeSessionEvents,
+        UpdateBlacklist,
+    },
+    SessionsManager,
+};
+use crate::actors::storage_manager::{messages::GetMetrics, StorageManager};
+use actix::Recipient;
 #[cfg(not(test))]
 use actix::System;
-use jsonrpc_core::{IoHandler, Params, Value};
+use futures::{
+    future::{self, Either},
+    Future,
+};
+use jsonrpc_core::{ErrorCode, IoHandler, Metadata, Params, Value};
 use log::info;
 use serde_derive::{Deserialize, Serialize};
-use witnet_data_structures::chain::Block;
+use serde_json::{json, Map};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
+use witnet_data_structures::chain::{Block, Epoch, Hash};
+use witnet_p2p::sessions::{events::SessionEvent, ConnectedPeer, SessionType};
+use witnet_util::net::IpNetwork;
+
+/// Maximum number of block hashes returned by `getBlockchain`
+pub const MAX_BLOCKCHAIN_LIMIT: usize = 1000;
+
+/// Result of the JSON-RPC methods that need to wait for other actors
+pub type JsonRpcResultAsync = Box<dyn Future<Item = Value, Error = jsonrpc_core::Error> + Send>;
+
+/// Metadata of a JSON-RPC request: the connection which received it, to which the notifications
+/// of the subscriptions made by the request are sent. Requests without a connection cannot
+/// subscribe
+#[derive(Clone, Default)]
+pub struct Subscriber(Option<Recipients>);
+
+/// Recipients of the notifications of each topic, all of them the same connection
+#[derive(Clone)]
+struct Recipients {
+    session_events: Recipient<SessionEventNotification>,
+    new_blocks: Recipient<NewBlockNotification>,
+}
+
+impl Subscriber {
+    /// Create the metadata of the requests received by a connection
+    pub fn new(
+        session_events: Recipient<SessionEventNotification>,
+        new_blocks: Recipient<NewBlockNotification>,
+    ) -> Self {
+        Subscriber(Some(Recipients {
+            session_events,
+            new_blocks,
+        }))
+    }
+
+    /// Recipients of the connection, or an error if the request was not received by a connection
+    fn recipients(self) -> Result<Recipients, jsonrpc_core::Error> {
+        self.0.ok_or_else(|| jsonrpc_core::Error {
+            code: ErrorCode::InvalidRequest,
+            message: "Subscriptions need a persistent connection".to_string(),
+            data: None,
+        })
+    }
+}
+
+/// Topics which a connection can subscribe to
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Topic {
+    /// Lifecycle events of the sessions, as with `subscribeSessionEvents`
+    SessionEvents,
+    /// New blocks added to the chain
+    NewBlocks,
+    /// New transactions added to the transactions pool
+    NewTransactions,
+    /// Data requests whose result has been included in a block
+    DataRequestResolved,
+}
+
+impl Topic {
+    /// Name of the topic, as written in the params of `subscribe` and in the notifications
+    pub fn name(self) -> &'static str {
+        match self {
+            Topic::SessionEvents => "sessionEvents",
+            Topic::NewBlocks => "newBlocks",
+            Topic::NewTransactions => "newTransactions",
+            Topic::DataRequestResolved => "dataRequestResolved",
+        }
+    }
+}
+
+impl Metadata for Subscriber {}
 
 /// Define the JSON-RPC interface:
 /// All the methods available through JSON-RPC
-pub fn jsonrpc_io_handler() -> IoHandler<()> {
+pub fn jsonrpc_io_handler() -> IoHandler<Subscriber> {
     let mut io = IoHandler::new();
 
     io.add_method("inventory", |params: Params| inventory(params.parse()?));
+    io.add_method("getConfig", |_params: Params| get_config());
+    io.add_method("setConfig", |params: Params| match params.parse() {
+        Ok(params) => set_config(params),
+        Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+    });
+    io.add_method("getBlock", |params: Params| match params.parse() {
+        Ok((block,)) => get_block(block),
+        Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+    });
+    io.add_method("getBlockchain", |params: Params| match params.parse() {
+        Ok((from_epoch, limit)) => get_blockchain(from_epoch, limit),
+        Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+    });
+    io.add_method("getStorageMetrics", |_params: Params| get_storage_metrics());
+    io.add_method("getNetworkMetrics", |_params: Params| get_network_metrics());
+    io.add_method("getPeers", |_params: Params| get_peers());
+    io.add_method("addPeer", |params: Params| match params.parse() {
+        Ok(addresses) => add_peers(addresses),
+        Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+    });
+    io.add_method("removePeer", |params: Params| match params.parse() {
+        Ok(addresses) => remove_peers(addresses),
+        Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+    });
+    io.add_method("banPeer", |params: Params| match params.parse() {
+        Ok(params) => ban_peer(params),
+        Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+    });
+    io.add_method("getBlacklist", |_params: Params| {
+        update_blacklist(vec![], vec![])
+    });
+    io.add_method("addToBlacklist", |params: Params| match params.parse() {
+        Ok(networks) => update_blacklist(networks, vec![]),
+        Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+    });
+    io.add_method("removeFromBlacklist", |params: Params| {
+        match params.parse() {
+            Ok(networks) => update_blacklist(vec![], networks),
+            Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+        }
+    });
+    io.add_method_with_meta(
+        "subscribeSessionEvents",
+        |_params: Params, subscriber: Subscriber| subscribe_session_events(subscriber),
+    );
+    io.add_method_with_meta(
+        "unsubscribeSessionEvents",
+        |params: Params, _subscriber: Subscriber| match params.parse() {
+            Ok((subscription,)) => unsubscribe_session_events(subscription),
+            Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+        },
+    );
+    io.add_method_with_meta(
+        "subscribe",
+        |params: Params, subscriber: Subscriber| match params.parse() {
+            Ok((topic,)) => subscribe(topic, subscriber),
+            Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+        },
+    );
+    io.add_method_with_meta(
+        "unsubscribe",
+        |params: Params, _subscriber: Subscriber| match params.parse() {
+            Ok((topic, subscription)) => unsubscribe(topic, subscription),
+            Err(e) => Box::new(future::err(e)) as JsonRpcResultAsync,
+        },
+    );
 
     io
 }
@@ -55,7 +227,10 @@ pub fn inventory(inv_elem: InventoryItem) -> Result<Value, jsonrpc_core::Error>
             let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
             // If this function was called asynchronously, it could wait for the result
             // But it's not so we just assume success
-            blocks_manager_addr.do_send(AddNewBlock { block });
+            blocks_manager_addr.do_send(AddNewBlock {
+                block,
+                origin: None,
+            });
 
             // Returns a boolean indicating success
             Ok(Value::Bool(true))
@@ -72,6 +247,614 @@ pub fn inventory(inv_elem: InventoryItem) -> Result<Value, jsonrpc_core::Error>
     }
 }
 
+/// Get the effective configuration of the node, with the values of
+/// sensitive params redacted.
+///
+/// Returns the configuration as an object with the same structure as
+/// the Toml file.
+/* Test string:
+{"jsonrpc": "2.0", "method": "getConfig", "id": 1}
+*/
+pub fn get_config() -> JsonRpcResultAsync {
+    let config_manager_addr = System::current().registry().get::<ConfigManager>();
+
+    Box::new(config_manager_addr.send(GetConfig).then(|response| {
+        match response {
+            Ok(Ok(config)) => config
+                .redacted()
+                .map_err(internal_error)
+                .and_then(|value| serde_json::to_value(value).map_err(internal_error)),
+            Ok(Err(e)) => Err(internal_error(e)),
+            Err(e) => Err(internal_error(e)),
+        }
+    }))
+}
+
+/// Change some params of the configuration while the node is running.
+/// Only the params listed in `witnet_config::config::reload::RUNTIME_TUNABLE`
+/// can be changed, and the resulting configuration is validated just
+/// like the configuration file.
+///
+/// Input: an object with the paths of the params as keys and their
+/// new values
+///
+/// Returns the list of changes applied.
+/* Test string:
+{"jsonrpc": "2.0", "method": "setConfig", "params": {"log.level": "debug", "connections.inbound_limit": 16}, "id": 1}
+*/
+pub fn set_config(params: Map<String, Value>) -> JsonRpcResultAsync {
+    let config_manager_addr = System::current().registry().get::<ConfigManager>();
+
+    Box::new(
+        config_manager_addr
+            .send(SetConfig {
+                params: config_params(params),
+            })
+            .then(|response| match response {
+                Ok(Ok(changes)) => Ok(Value::Array(
+                    changes
+                        .iter()
+                        .map(|change| Value::String(change.to_string()))
+                        .collect(),
+                )),
+                Ok(Err(errors)) => Err(jsonrpc_core::Error {
+                    code: ErrorCode::InvalidParams,
+                    message: errors.join("; "),
+                    data: Some(Value::Array(
+                        errors.into_iter().map(Value::String).collect(),
+                    )),
+                }),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Get the metrics of the storage: the size of the database files, the
+/// number of keys in each column family and the latencies of the
+/// storage operations.
+///
+/// Returns the metrics as an object.
+/* Test string:
+{"jsonrpc": "2.0", "method": "getStorageMetrics", "id": 1}
+*/
+pub fn get_storage_metrics() -> JsonRpcResultAsync {
+    let storage_manager_addr = System::current().registry().get::<StorageManager>();
+
+    Box::new(
+        storage_manager_addr
+            .send(GetMetrics)
+            .then(|response| match response {
+                Ok(Ok(metrics)) => serde_json::to_value(metrics).map_err(internal_error),
+                Ok(Err(e)) => Err(internal_error(e)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Get the metrics of the network: the connections opened and accepted,
+/// the sessions with the peers, the handshakes which failed, the bans,
+/// the bytes transferred and the peers known to the node.
+///
+/// Returns the metrics as an object with the `connections`, `sessions`
+/// and `peers` fields.
+/* Test string:
+{"jsonrpc": "2.0", "method": "getNetworkMetrics", "id": 1}
+*/
+pub fn get_network_metrics() -> JsonRpcResultAsync {
+    let connections_manager_addr = System::current().registry().get::<ConnectionsManager>();
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+    let peers_manager_addr = System::current().registry().get::<PeersManager>();
+
+    Box::new(
+        connections_manager_addr
+            .send(GetConnectionsMetrics)
+            .join3(
+                sessions_manager_addr.send(GetSessionsMetrics),
+                peers_manager_addr.send(GetPeersMetrics),
+            )
+            .then(|response| match response {
+                Ok((connections, sessions, Ok(peers))) => Ok(json!({
+                    "connections": connections,
+                    "sessions": sessions,
+                    "peers": peers,
+                })),
+                Ok((_, _, Err(e))) => Err(internal_error(e)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Get the peers of the consolidated sessions of the node, with the
+/// protocol version and features negotiated with them and the
+/// round-trip time of the last ping they answered.
+///
+/// Returns a list of objects, one for each peer.
+/* Test string:
+{"jsonrpc": "2.0", "method": "getPeers", "id": 1}
+*/
+pub fn get_peers() -> JsonRpcResultAsync {
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    Box::new(
+        sessions_manager_addr
+            .send(GetConnectedPeers)
+            .then(|response| match response {
+                Ok(peers) => Ok(Value::Array(peers.iter().map(connected_peer).collect())),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Add one or more peer addresses to the known peers of the node, to
+/// which it opens outbound sessions when it needs more of them.
+///
+/// Input: a list of socket addresses.
+///
+/// Returns a boolean indicating success.
+/* Test string:
+{"jsonrpc": "2.0", "method": "addPeer", "params": ["192.168.1.10:21337"], "id": 1}
+*/
+pub fn add_peers(addresses: Vec<SocketAddr>) -> JsonRpcResultAsync {
+    let peers_manager_addr = System::current().registry().get::<PeersManager>();
+
+    Box::new(
+        peers_manager_addr
+            .send(AddPeers { addresses })
+            .then(|response| match response {
+                Ok(Ok(_)) => Ok(Value::Bool(true)),
+                Ok(Err(e)) => Err(internal_error(e)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Remove one or more peer addresses from the known peers of the node
+/// and close the sessions with them. The peers can still connect to
+/// the node, and be gossiped to it again.
+///
+/// Input: a list of socket addresses, as returned by `getPeers`.
+///
+/// Returns the number of sessions closed.
+/* Test string:
+{"jsonrpc": "2.0", "method": "removePeer", "params": ["192.168.1.10:21337"], "id": 1}
+*/
+pub fn remove_peers(addresses: Vec<SocketAddr>) -> JsonRpcResultAsync {
+    let peers_manager_addr = System::current().registry().get::<PeersManager>();
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    Box::new(
+        peers_manager_addr
+            .send(RemovePeers {
+                addresses: addresses.clone(),
+            })
+            .join(sessions_manager_addr.send(DisconnectPeers { addresses }))
+            .then(|response| match response {
+                Ok((Ok(_), closed_sessions)) => Ok(Value::from(closed_sessions)),
+                Ok((Err(e), _)) => Err(internal_error(e)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Params of `banPeer`: the address of the peer, and optionally the
+/// duration of the ban in seconds
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum BanPeerParams {
+    /// Address and duration of the ban in seconds
+    WithDuration(SocketAddr, u64),
+    /// Address, banned for `connections.ban_duration_seconds`
+    Address((SocketAddr,)),
+}
+
+/// Ban a peer right away, whatever its misbehavior score. Its IP
+/// address cannot connect to the node and is not connected to until
+/// the ban expires, its sessions are closed and its address is removed
+/// from the known peers. The ban is persisted.
+///
+/// Input: the socket address of the peer, as returned by `getPeers`,
+/// and optionally the duration of the ban in seconds, which defaults to
+/// `connections.ban_duration_seconds` and cannot be greater than `i64::MAX`.
+///
+/// Returns the timestamp at which the ban expires.
+/* Test string:
+{"jsonrpc": "2.0", "method": "banPeer", "params": ["192.168.1.10:21337", 3600], "id": 1}
+*/
+pub fn ban_peer(params: BanPeerParams) -> JsonRpcResultAsync {
+    let (address, duration) = match params {
+        BanPeerParams::WithDuration(_, seconds) if seconds > i64::max_value() as u64 => {
+            return Box::new(future::err(jsonrpc_core::Error::invalid_params(format!(
+                "The duration of the ban cannot be greater than {} seconds",
+                i64::max_value()
+            ))));
+        }
+        BanPeerParams::WithDuration(address, seconds) => {
+            (address, Some(Duration::from_secs(seconds)))
+        }
+        BanPeerParams::Address((address,)) => (address, None),
+    };
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    Box::new(
+        sessions_manager_addr
+            .send(BanPeer { address, duration })
+            .then(|response| match response {
+                Ok(until) => Ok(Value::from(until)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Add IP addresses or networks to the blacklist of the node and remove
+/// them from it. Blacklisted peers cannot connect to the node and are
+/// not connected to. The blacklist is persisted, and merged with
+/// `connections.blacklist` when the node starts.
+///
+/// Input (`addToBlacklist` and `removeFromBlacklist`): a list of IP
+/// addresses or networks in CIDR notation. `getBlacklist` takes no
+/// params and does not change the blacklist.
+///
+/// Returns the resulting blacklist.
+/* Test string:
+{"jsonrpc": "2.0", "method": "addToBlacklist", "params": ["10.0.0.0/8", "192.168.1.10"], "id": 1}
+*/
+pub fn update_blacklist(add: Vec<IpNetwork>, remove: Vec<IpNetwork>) -> JsonRpcResultAsync {
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    Box::new(
+        sessions_manager_addr
+            .send(UpdateBlacklist { add, remove })
+            .then(|response| match response {
+                Ok(blacklist) => serde_json::to_value(blacklist).map_err(internal_error),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Param of `getBlock`: the hash of a block, or the epoch of a block
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum BlockId {
+    /// Hash of the block, as serialized in the blocks and in the
+    /// `newBlocks` notifications
+    Hash(Hash),
+    /// Epoch of the block
+    Epoch(Epoch),
+}
+
+/// Get a block with its transactions, by its hash or by its epoch. An
+/// epoch with several candidate blocks is ambiguous, so the hashes of
+/// its blocks are returned in the `data` of the error instead.
+///
+/// Input: the hash of the block, or its epoch.
+///
+/// Returns an object with the `hash` and the `block`, as in the
+/// `newBlocks` notifications.
+/* Test string:
+{"jsonrpc": "2.0", "method": "getBlock", "params": [2], "id": 1}
+*/
+pub fn get_block(block: BlockId) -> JsonRpcResultAsync {
+    let hash = match block {
+        BlockId::Hash(hash) => Either::A(future::ok(hash)),
+        BlockId::Epoch(epoch) => {
+            let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+
+            Either::B(
+                blocks_manager_addr
+                    .send(GetEpochBlockHashes {
+                        range: epoch..=epoch,
+                        limit: usize::max_value(),
+                    })
+                    .then(move |response| match response {
+                        Ok(hashes) => epoch_block_hash(epoch, &hashes),
+                        Err(e) => Err(internal_error(e)),
+                    }),
+            )
+        }
+    };
+
+    Box::new(hash.and_then(|hash| {
+        let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+
+        blocks_manager_addr
+            .send(GetBlock { hash })
+            .then(move |response| match response {
+                Ok(Ok(block)) => Ok(json!({ "hash": hash, "block": block })),
+                Ok(Err(BlocksManagerError::BlockDoesNotExist)) => {
+                    Err(jsonrpc_core::Error::invalid_params("Block not found"))
+                }
+                Ok(Err(BlocksManagerError::BlockPruned)) => Err(
+                    jsonrpc_core::Error::invalid_params("Block pruned, only its header is kept"),
+                ),
+                Ok(Err(e)) => Err(internal_error(format!("{:?}", e))),
+                Err(e) => Err(internal_error(e)),
+            })
+    }))
+}
+
+/// Get the hashes of the blocks of the chain, starting at an epoch.
+/// Epochs without blocks are skipped, and epochs with several
+/// candidate blocks have one entry for each of them. The hashes of
+/// pruned blocks are included, even if `getBlock` only knows their
+/// headers.
+///
+/// Input: the first epoch, and the maximum number of hashes returned,
+/// up to `MAX_BLOCKCHAIN_LIMIT`.
+///
+/// Returns a list of `[epoch, hash]` pairs, ordered by epoch.
+/* Test string:
+{"jsonrpc": "2.0", "method": "getBlockchain", "params": [0, 100], "id": 1}
+*/
+pub fn get_blockchain(from_epoch: Epoch, limit: usize) -> JsonRpcResultAsync {
+    if limit > MAX_BLOCKCHAIN_LIMIT {
+        return Box::new(future::err(jsonrpc_core::Error::invalid_params(format!(
+            "The limit cannot be greater than {}",
+            MAX_BLOCKCHAIN_LIMIT
+        ))));
+    }
+    let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+
+    Box::new(
+        blocks_manager_addr
+            .send(GetEpochBlockHashes {
+                range: from_epoch..=Epoch::max_value(),
+                limit,
+            })
+            .then(|response| match response {
+                Ok(hashes) => Ok(json!(hashes)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Subscribe the connection to the lifecycle events of the sessions
+/// of the node: a session is connected, completes the handshake or is
+/// disconnected, or a peer is banned. Each event is sent to the
+/// connection as a `sessionEvent` notification, until the subscription
+/// is cancelled with `unsubscribeSessionEvents` or the connection is
+/// closed.
+///
+/// Returns the identifier of the subscription.
+/* Test string:
+{"jsonrpc": "2.0", "method": "subscribeSessionEvents", "id": 1}
+*/
+pub fn subscribe_session_events(subscriber: Subscriber) -> JsonRpcResultAsync {
+    let recipient = match subscriber.recipients() {
+        Ok(recipients) => recipients.session_events,
+        Err(e) => return Box::new(future::err(e)),
+    };
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    Box::new(
+        sessions_manager_addr
+            .send(SubscribeSessionEvents { recipient })
+            .then(|response| match response {
+                Ok(subscription) => Ok(Value::from(subscription)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Cancel a subscription to the session events.
+///
+/// Input: the identifier of the subscription, as returned by
+/// `subscribeSessionEvents`.
+///
+/// Returns whether the subscription existed.
+/* Test string:
+{"jsonrpc": "2.0", "method": "unsubscribeSessionEvents", "params": [0], "id": 1}
+*/
+pub fn unsubscribe_session_events(subscription: u64) -> JsonRpcResultAsync {
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    Box::new(
+        sessions_manager_addr
+            .send(UnsubscribeSessionEvents { subscription })
+            .then(|response| match response {
+                Ok(existed) => Ok(Value::Bool(existed)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Subscribe the connection to a topic. Each item of the topic is sent
+/// to the connection as a notification, `newBlocks` for the new blocks
+/// and `sessionEvent` for the session events, until the subscription
+/// is cancelled with `unsubscribe` or the connection is closed. The `newTransactions` and
+/// `dataRequestResolved` topics are not available until the node
+/// processes transactions and data requests.
+///
+/// Input: the name of the topic: `sessionEvents`, `newBlocks`,
+/// `newTransactions` or `dataRequestResolved`.
+///
+/// Returns the identifier of the subscription.
+/* Test string:
+{"jsonrpc": "2.0", "method": "subscribe", "params": ["newBlocks"], "id": 1}
+*/
+pub fn subscribe(topic: Topic, subscriber: Subscriber) -> JsonRpcResultAsync {
+    let recipient = match (topic, subscriber.recipients()) {
+        (Topic::SessionEvents, Ok(recipients)) => {
+            return subscribe_session_events(Subscriber(Some(recipients)));
+        }
+        (Topic::NewBlocks, Ok(recipients)) => recipients.new_blocks,
+        (Topic::NewTransactions, _) | (Topic::DataRequestResolved, _) => {
+            return Box::new(future::err(unavailable_topic(topic)));
+        }
+        (_, Err(e)) => return Box::new(future::err(e)),
+    };
+    let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+
+    Box::new(
+        blocks_manager_addr
+            .send(SubscribeNewBlocks { recipient })
+            .then(|response| match response {
+                Ok(subscription) => Ok(Value::from(subscription)),
+                Err(e) => Err(internal_error(e)),
+            }),
+    )
+}
+
+/// Cancel a subscription to a topic.
+///
+/// Input: the name of the topic and the identifier of the
+/// subscription, as returned by `subscribe`.
+///
+/// Returns whether the subscription existed.
+/* Test string:
+{"jsonrpc": "2.0", "method": "unsubscribe", "params": ["newBlocks", 0], "id": 1}
+*/
+pub fn unsubscribe(topic: Topic, subscription: u64) -> JsonRpcResultAsync {
+    match topic {
+        Topic::SessionEvents => unsubscribe_session_events(subscription),
+        Topic::NewBlocks => {
+            let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+
+            Box::new(
+                blocks_manager_addr
+                    .send(UnsubscribeNewBlocks { subscription })
+                    .then(|response| match response {
+                        Ok(existed) => Ok(Value::Bool(existed)),
+                        Err(e) => Err(internal_error(e)),
+                    }),
+            )
+        }
+        Topic::NewTransactions | Topic::DataRequestResolved => {
+            Box::new(future::err(unavailable_topic(topic)))
+        }
+    }
+}
+
+/// JSON-RPC notification carrying a new block to its subscriber, which
+/// does not expect any response
+pub fn new_block_notification(notification: &NewBlockNotification) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": Topic::NewBlocks.name(),
+        "params": {
+            "subscription": notification.subscription,
+            "result": {
+                "hash": notification.hash,
+                "block": notification.block,
+            },
+        },
+    })
+}
+
+/// JSON-RPC notification carrying a session event to its subscriber,
+/// which does not expect any response
+pub fn session_event_notification(notification: &SessionEventNotification) -> Value {
+    let event = &notification.event;
+    let mut result = json!({
+        "event": event.name(),
+        "address": event.address().to_string(),
+        "timestamp": notification.timestamp,
+    });
+    match event {
+        SessionEvent::Connected { session_type, .. } => {
+            result["type"] = session_type_name(*session_type);
+        }
+        SessionEvent::Handshaked {
+            session_type,
+            capabilities,
+            ..
+        } => {
+            result["type"] = session_type_name(*session_type);
+            result["version"] = Value::from(capabilities.version);
+            result["features"] = Value::from(capabilities.features);
+        }
+        SessionEvent::Disconnected {
+            session_type,
+            reason,
+            ..
+        } => {
+            result["type"] = session_type_name(*session_type);
+            result["reason"] = Value::from(reason.name());
+        }
+        SessionEvent::Banned { until, .. } => {
+            result["until"] = Value::from(*until);
+        }
+    }
+
+    json!({
+        "jsonrpc": "2.0",
+        "method": "sessionEvent",
+        "params": {
+            "subscription": notification.subscription,
+            "result": result,
+        },
+    })
+}
+
+/// Convert the params of `setConfig` into `(path, value)` pairs,
+/// writing the values as they would be written in a `--set` override
+fn config_params(params: Map<String, Value>) -> Vec<(String, String)> {
+    params
+        .into_iter()
+        .map(|(path, value)| match value {
+            Value::String(s) => (path, s),
+            other => (path, other.to_string()),
+        })
+        .collect()
+}
+
+/// JSON object describing a peer of a consolidated session, with its
+/// latency in milliseconds
+fn connected_peer(peer: &ConnectedPeer) -> Value {
+    json!({
+        "address": peer.address.to_string(),
+        "type": session_type_name(peer.session_type),
+        "version": peer.capabilities.version,
+        "features": peer.capabilities.features,
+        "latency_ms": peer.latency.map(|latency| {
+            latency.as_secs() * 1000 + u64::from(latency.subsec_millis())
+        }),
+    })
+}
+
+/// Name of a session type, as written in the JSON objects
+fn session_type_name(session_type: SessionType) -> Value {
+    Value::from(format!("{:?}", session_type).to_lowercase())
+}
+
+/// Hash of the only block of an epoch, given the hashes of its blocks
+fn epoch_block_hash(epoch: Epoch, hashes: &[(Epoch, Hash)]) -> Result<Hash, jsonrpc_core::Error> {
+    match hashes {
+        [] => Err(jsonrpc_core::Error::invalid_params(format!(
+            "No block found in epoch {}",
+            epoch
+        ))),
+        [(_, hash)] => Ok(*hash),
+        _ => Err(jsonrpc_core::Error {
+            code: ErrorCode::InvalidParams,
+            message: format!(
+                "Epoch {} has several candidate blocks, get one of them by hash",
+                epoch
+            ),
+            data: Some(json!(hashes
+                .iter()
+                .map(|(_, hash)| hash)
+                .collect::<Vec<_>>())),
+        }),
+    }
+}
+
+/// Error for the topics which nothing publishes to yet
+fn unavailable_topic(topic: Topic) -> jsonrpc_core::Error {
+    jsonrpc_core::Error::invalid_params(format!(
+        "Topic {} is not available: the node does not process transactions or data requests yet",
+        topic.name()
+    ))
+}
+
+/// Internal error with the given message
+fn internal_error<E: fmt::Display>(e: E) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: ErrorCode::InternalError,
+        message: e.to_string(),
+        data: None,
+    }
+}
+
 #[cfg(test)]
 mod mock_actix {
     pub struct System;
@@ -97,6 +880,13 @@ mod mock_actix {
 
     impl Addr {
         pub fn do_send<T>(&self, _msg: T) {}
+
+        pub fn send<M: actix::Message>(
+            &self,
+            _msg: M,
+        ) -> futures::future::Empty<M::Result, actix::MailboxError> {
+            futures::future::empty()
+        }
     }
 }
 
@@ -182,6 +972,287 @@ mod tests {
         assert_eq!(response, Some(expected));
     }
 
+    #[test]
+    fn set_config_params() {
+        let params: Map<String, Value> =
+            serde_json::from_str(r#"{"log.level": "debug", "connections.inbound_limit": 16}"#)
+                .unwrap();
+
+        let mut params = config_params(params);
+        params.sort();
+
+        assert_eq!(
+            params,
+            vec![
+                ("connections.inbound_limit".to_string(), "16".to_string()),
+                ("log.level".to_string(), "debug".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_config_invalid_params() {
+        // The params of setConfig must be an object
+        let msg = r#"{"jsonrpc":"2.0","method":"setConfig","params":[1],"id":1}"#;
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32602"#.to_string();
+        let io = jsonrpc_io_handler();
+        let response = io.handle_request_sync(&msg);
+        // Compare only the first N characters
+        let response =
+            response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn add_to_blacklist_invalid_params() {
+        // The params of addToBlacklist must be a list of IP addresses or networks
+        let msg = r#"{"jsonrpc":"2.0","method":"addToBlacklist","params":["10.0.0.0/33"],"id":1}"#;
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32602"#.to_string();
+        let io = jsonrpc_io_handler();
+        let response = io.handle_request_sync(&msg);
+        // Compare only the first N characters
+        let response =
+            response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn add_peer_invalid_params() {
+        // The params of addPeer must be a list of socket addresses
+        let msg = r#"{"jsonrpc":"2.0","method":"addPeer","params":["127.0.0.1"],"id":1}"#;
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32602"#.to_string();
+        let io = jsonrpc_io_handler();
+        let response = io.handle_request_sync(&msg);
+        // Compare only the first N characters
+        let response =
+            response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn get_block_params() {
+        match serde_json::from_str(r#"[2]"#).unwrap() {
+            (BlockId::Epoch(2),) => {}
+            params => panic!("Unexpected params: {:?}", params),
+        }
+        let hash = format!(r#"[{{"SHA256":{:?}}}]"#, [7u8; 32]);
+        match serde_json::from_str(&hash).unwrap() {
+            (BlockId::Hash(Hash::SHA256(bytes)),) => assert_eq!(bytes, [7; 32]),
+            params => panic!("Unexpected params: {:?}", params),
+        }
+        assert!(serde_json::from_str::<(BlockId,)>(r#"["0a0b"]"#).is_err());
+    }
+
+    #[test]
+    fn get_block_epoch_hash() {
+        let hash_a = Hash::SHA256([1; 32]);
+        let hash_b = Hash::SHA256([2; 32]);
+
+        assert_eq!(epoch_block_hash(2, &[(2, hash_a)]), Ok(hash_a));
+        assert_eq!(
+            epoch_block_hash(2, &[]).unwrap_err().message,
+            "No block found in epoch 2"
+        );
+        let error = epoch_block_hash(2, &[(2, hash_a), (2, hash_b)]).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidParams);
+        assert_eq!(error.data, Some(json!([hash_a, hash_b])));
+    }
+
+    #[test]
+    fn get_blockchain_limit() {
+        let msg = format!(
+            r#"{{"jsonrpc":"2.0","method":"getBlockchain","params":[0,{}],"id":1}}"#,
+            MAX_BLOCKCHAIN_LIMIT + 1
+        );
+        let expected = format!(
+            r#"{{"jsonrpc":"2.0","error":{{"code":-32602,"message":"The limit cannot be greater than {}"#,
+            MAX_BLOCKCHAIN_LIMIT
+        );
+        let io = jsonrpc_io_handler();
+        let response = io.handle_request_sync(&msg);
+        // Compare only the first N characters
+        let response =
+            response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn ban_peer_params() {
+        // The duration of the ban is optional
+        let address: SocketAddr = "127.0.0.1:21337".parse().unwrap();
+        match serde_json::from_str(r#"["127.0.0.1:21337", 60]"#).unwrap() {
+            BanPeerParams::WithDuration(a, 60) => assert_eq!(a, address),
+            params => panic!("Unexpected params: {:?}", params),
+        }
+        match serde_json::from_str(r#"["127.0.0.1:21337"]"#).unwrap() {
+            BanPeerParams::Address((a,)) => assert_eq!(a, address),
+            params => panic!("Unexpected params: {:?}", params),
+        }
+        assert!(serde_json::from_str::<BanPeerParams>(r#"["127.0.0.1", 60]"#).is_err());
+    }
+
+    #[test]
+    fn ban_peer_duration_overflow() {
+        let msg = format!(
+            r#"{{"jsonrpc":"2.0","method":"banPeer","params":["127.0.0.1:21337",{}],"id":1}}"#,
+            u64::max_value()
+        );
+        let expected = format!(
+            r#"{{"jsonrpc":"2.0","error":{{"code":-32602,"message":"The duration of the ban cannot be greater than {} seconds"#,
+            i64::max_value()
+        );
+        let io = jsonrpc_io_handler();
+        let response = io.handle_request_sync(&msg);
+        // Compare only the first N characters
+        let response =
+            response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn connected_peer_json() {
+        use std::time::Duration;
+        use witnet_p2p::sessions::{capabilities::Capabilities, SessionType};
+
+        let peer = ConnectedPeer {
+            address: "127.0.0.1:21337".parse().unwrap(),
+            session_type: SessionType::Outbound,
+            capabilities: Capabilities {
+                version: 1,
+                features: 1,
+            },
+            latency: Some(Duration::from_micros(1_250_500)),
+        };
+        let expected = r#"{"address":"127.0.0.1:21337","features":1,"latency_ms":1250,"type":"outbound","version":1}"#;
+        assert_eq!(connected_peer(&peer).to_string(), expected);
+
+        let peer = ConnectedPeer {
+            latency: None,
+            ..peer
+        };
+        assert_eq!(connected_peer(&peer)["latency_ms"], Value::Null);
+    }
+
+    #[test]
+    fn subscribe_without_connection() {
+        // Subscriptions need a connection to send the notifications to
+        let msg = r#"{"jsonrpc":"2.0","method":"subscribeSessionEvents","id":1}"#;
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32600"#.to_string();
+        let io = jsonrpc_io_handler();
+        let response = io.handle_request_sync(&msg);
+        // Compare only the first N characters
+        let response =
+            response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn subscribe_topics() {
+        // Subscriptions need a connection to send the notifications to
+        let msg = r#"{"jsonrpc":"2.0","method":"subscribe","params":["newBlocks"],"id":1}"#;
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32600"#.to_string();
+        let io = jsonrpc_io_handler();
+        let response = io.handle_request_sync(&msg);
+        // Compare only the first N characters
+        let response =
+            response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+        assert_eq!(response, Some(expected));
+
+        // Unknown topics and topics without items are invalid params
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32602"#.to_string();
+        for topic in &["newHeaders", "newTransactions", "dataRequestResolved"] {
+            let msg = format!(
+                r#"{{"jsonrpc":"2.0","method":"subscribe","params":["{}"],"id":1}}"#,
+                topic
+            );
+            let response = io.handle_request_sync(&msg);
+            let response =
+                response.map(|s| s.chars().take(expected.chars().count()).collect::<String>());
+            assert_eq!(response, Some(expected.clone()));
+        }
+    }
+
+    #[test]
+    fn topic_names() {
+        for topic in &[
+            Topic::SessionEvents,
+            Topic::NewBlocks,
+            Topic::NewTransactions,
+            Topic::DataRequestResolved,
+        ] {
+            let parsed: Topic = serde_json::from_value(Value::from(topic.name())).unwrap();
+            assert_eq!(parsed, *topic);
+        }
+    }
+
+    #[test]
+    fn new_block_json() {
+        use witnet_data_structures::chain::*;
+
+        let notification = NewBlockNotification {
+            subscription: 2,
+            hash: Hash::SHA256([1; 32]),
+            block: Block {
+                header: BlockHeaderWithProof {
+                    block_header: BlockHeader {
+                        version: 1,
+                        beacon: CheckpointBeacon {
+                            checkpoint: 2,
+                            hash_prev_block: Hash::SHA256([4; 32]),
+                        },
+                        hash_merkle_root: Hash::SHA256([3; 32]),
+                    },
+                    proof: LeadershipProof {
+                        block_sig: None,
+                        influence: 99999,
+                    },
+                },
+                txn_count: 0,
+                txns: vec![],
+            },
+        };
+        let notification = new_block_notification(&notification);
+        assert_eq!(notification["method"], "newBlocks");
+        assert_eq!(notification["params"]["subscription"], 2);
+        assert_eq!(
+            notification["params"]["result"]["block"]["header"]["block_header"]["beacon"]
+                ["checkpoint"],
+            2
+        );
+        assert!(notification["params"]["result"]["hash"].is_object());
+    }
+
+    #[test]
+    fn session_event_json() {
+        use witnet_p2p::sessions::events::DisconnectReason;
+
+        let notification = SessionEventNotification {
+            subscription: 3,
+            timestamp: 1_550_000_000,
+            event: SessionEvent::Disconnected {
+                address: "127.0.0.1:21337".parse().unwrap(),
+                session_type: SessionType::Inbound,
+                reason: DisconnectReason::PingTimeout,
+            },
+        };
+        let expected = r#"{"jsonrpc":"2.0","method":"sessionEvent","params":{"result":{"address":"127.0.0.1:21337","event":"disconnected","reason":"ping_timeout","timestamp":1550000000,"type":"inbound"},"subscription":3}}"#;
+        assert_eq!(
+            session_event_notification(&notification).to_string(),
+            expected
+        );
+
+        let notification = SessionEventNotification {
+            event: SessionEvent::Banned {
+                address: "127.0.0.1:21337".parse().unwrap(),
+                until: 1_550_086_400,
+            },
+            ..notification
+        };
+        let result = &session_event_notification(&notification)["params"]["result"];
+        assert_eq!(result["event"], "banned");
+        assert_eq!(result["until"], 1_550_086_400);
+    }
+
     #[test]
     fn serialize_block() {
         // Check that the serialization of `Block` doesn't change
@@ -0,0 +1,196 @@
+use super::auth::failure;
+use jsonrpc_core::{Error, ErrorCode};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use witnet_p2p::rate_limits::TokenBucket;
+
+/// Code of the errors returned to the requests rejected by the limits
+pub const LIMIT_EXCEEDED: i64 = -32005;
+
+/// Time after which the bucket of an IP address is full again, so it can be forgotten
+const BUCKET_REFILL: Duration = Duration::from_secs(60);
+
+/// Limits of the requests of the clients: the rate of the requests from each IP address, and the
+/// number of requests processed at once. Each message counts as one request, even if it is a batch
+#[derive(Debug, Default)]
+pub struct RequestLimits {
+    /// Maximum number of requests per minute from each IP address, 0 if not limited
+    per_minute: u32,
+    /// Maximum number of requests processed at once, 0 if not limited
+    max_in_flight: usize,
+    /// Bucket of the requests of each IP address, with the time of its last request
+    buckets: HashMap<IpAddr, (TokenBucket, Instant)>,
+    /// Number of requests being processed
+    in_flight: usize,
+}
+
+/// Request being processed, which is counted until this is dropped, either because the request
+/// has been answered or because its connection has been closed
+pub struct InFlight(Rc<RefCell<RequestLimits>>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut limits = self.0.borrow_mut();
+        limits.in_flight = limits.in_flight.saturating_sub(1);
+    }
+}
+
+impl RequestLimits {
+    /// Create the limits allowing `per_minute` requests per minute from each IP address, and
+    /// `max_in_flight` requests at once, where 0 disables a limit
+    pub fn new(per_minute: u32, max_in_flight: u32) -> Self {
+        RequestLimits {
+            per_minute,
+            max_in_flight: max_in_flight as usize,
+            ..RequestLimits::default()
+        }
+    }
+
+    /// Check whether a request from `ip` received at `now` is within the limits, counting it as
+    /// in flight until the returned value is dropped
+    pub fn start(
+        limits: &Rc<RefCell<RequestLimits>>,
+        ip: IpAddr,
+        now: Instant,
+    ) -> Result<InFlight, Error> {
+        let mut this = limits.borrow_mut();
+        if this.per_minute > 0 {
+            let per_minute = this.per_minute;
+            let (bucket, last_request) = this
+                .buckets
+                .entry(ip)
+                .or_insert_with(|| (TokenBucket::new(per_minute, now), now));
+            *last_request = now;
+            if !bucket.try_take(now) {
+                return Err(limit_exceeded(format!("Too many requests from {}", ip)));
+            }
+        }
+        if this.max_in_flight > 0 && this.in_flight >= this.max_in_flight {
+            return Err(limit_exceeded(
+                "Too many requests being processed, try again later".to_string(),
+            ));
+        }
+        this.in_flight += 1;
+
+        Ok(InFlight(Rc::clone(limits)))
+    }
+
+    /// Forget the IP addresses which have not sent any request for a minute, as their buckets are
+    /// full again
+    pub fn prune(&mut self, now: Instant) {
+        self.buckets
+            .retain(|_, (_, last_request)| now.duration_since(*last_request) < BUCKET_REFILL);
+    }
+
+    /// Number of requests being processed
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+}
+
+/// Error returned to the requests rejected by the limits
+fn limit_exceeded(message: String) -> Error {
+    Error {
+        code: ErrorCode::ServerError(LIMIT_EXCEEDED),
+        message,
+        data: None,
+    }
+}
+
+/// Response rejecting every call of a request with the same error, if any of them has to be
+/// answered. Requests which cannot be parsed are answered with a null id
+pub fn rejection(request: &str, error: Error) -> Option<String> {
+    let response = match serde_json::from_str(request) {
+        Ok(Value::Array(calls)) => {
+            let outputs: Vec<Value> = calls
+                .iter()
+                .filter_map(|call| failure(call, error.clone()))
+                .collect();
+            if outputs.is_empty() {
+                return None;
+            }
+            Value::Array(outputs)
+        }
+        Ok(call) => failure(&call, error)?,
+        Err(_) => json!({ "jsonrpc": "2.0", "error": error, "id": null }),
+    };
+
+    Some(response.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_of_each_ip() {
+        let limits = Rc::new(RefCell::new(RequestLimits::new(2, 0)));
+        let now = Instant::now();
+        let client = "127.0.0.1".parse().unwrap();
+        let other_client = "127.0.0.2".parse().unwrap();
+
+        assert!(RequestLimits::start(&limits, client, now).is_ok());
+        assert!(RequestLimits::start(&limits, client, now).is_ok());
+        assert_eq!(
+            RequestLimits::start(&limits, client, now)
+                .err()
+                .map(|e| e.code),
+            Some(ErrorCode::ServerError(LIMIT_EXCEEDED))
+        );
+        assert!(RequestLimits::start(&limits, other_client, now).is_ok());
+
+        // The buckets are forgotten once they are full again
+        limits.borrow_mut().prune(now + Duration::from_secs(30));
+        assert_eq!(limits.borrow().buckets.len(), 2);
+        limits.borrow_mut().prune(now + BUCKET_REFILL);
+        assert!(limits.borrow().buckets.is_empty());
+    }
+
+    #[test]
+    fn requests_in_flight() {
+        let limits = Rc::new(RefCell::new(RequestLimits::new(0, 2)));
+        let now = Instant::now();
+        let client = "127.0.0.1".parse().unwrap();
+
+        let first = RequestLimits::start(&limits, client, now).unwrap();
+        let _second = RequestLimits::start(&limits, client, now).unwrap();
+        assert!(RequestLimits::start(&limits, client, now).is_err());
+        assert_eq!(limits.borrow().in_flight(), 2);
+
+        // A request stops being counted when it is answered
+        drop(first);
+        assert_eq!(limits.borrow().in_flight(), 1);
+        assert!(RequestLimits::start(&limits, client, now).is_ok());
+    }
+
+    #[test]
+    fn rejection_of_each_call() {
+        let error = limit_exceeded("Too many requests".to_string());
+
+        assert_eq!(
+            rejection(
+                r#"{"jsonrpc":"2.0","method":"getPeers","id":1}"#,
+                error.clone()
+            ),
+            Some(
+                r#"{"error":{"code":-32005,"message":"Too many requests"},"id":1,"jsonrpc":"2.0"}"#
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            rejection(r#"[{"jsonrpc":"2.0","method":"getPeers"}]"#, error.clone()),
+            None
+        );
+        assert_eq!(
+            rejection("{", error),
+            Some(
+                r#"{"error":{"code":-32005,"message":"Too many requests"},"id":null,"jsonrpc":"2.0"}"#
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,12 @@
+use actix::Message;
+
+/// Message to start or stop listening for JSON-RPC connections.
+/// Connections already open are not closed when stopping.
+pub struct SetEnabled {
+    /// Whether the JSON-RPC server should accept connections
+    pub enabled: bool,
+}
+
+impl Message for SetEnabled {
+    type Result = ();
+}
@@ -1,7 +1,15 @@
+/// Authentication of the clients and methods served to them
+pub mod auth;
 mod connection;
 /// JSON-RPC methods
 pub mod json_rpc_methods;
+/// Limits of the requests of the clients
+pub mod limits;
+/// Messages for the JSON-RPC server
+pub mod messages;
 mod newline_codec;
 mod server;
+mod websocket_codec;
+mod websocket_connection;
 
 pub use self::server::JsonRpcServer;
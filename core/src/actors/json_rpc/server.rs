@@ -1,86 +1,243 @@
+use super::auth::Access;
 use super::connection::JsonRpc;
-use super::json_rpc_methods::jsonrpc_io_handler;
+use super::json_rpc_methods::{jsonrpc_io_handler, Subscriber};
+use super::limits::RequestLimits;
+use super::messages::SetEnabled;
 use super::newline_codec::NewLineCodec;
+use super::websocket_codec::WebSocketCodec;
+use super::websocket_connection::WebSocketJsonRpc;
 use crate::actors::config_manager::send_get_config_request;
 use crate::actors::connections_manager::messages::InboundTcpConnect;
+use crate::actors::stream::{load_tls_acceptor, PeerStream};
 use actix::{
-    io::FramedWrite, Actor, ActorContext, Addr, AsyncContext, Context, Handler, Message,
-    StreamHandler,
+    io::FramedWrite, Actor, ActorFuture, Addr, AsyncContext, Context, ContextFutureSpawner,
+    Handler, Message, SpawnHandle, StreamHandler, Supervised, SystemService, WrapFuture,
 };
 use bytes;
 use futures::Stream;
 use jsonrpc_core::IoHandler;
 use log::*;
+use std::cell::RefCell;
 use std::collections::HashSet;
+use std::io;
 use std::net::SocketAddr;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::timer::Timeout;
 use tokio::{codec::FramedRead, io::AsyncRead};
+use tokio_rustls::TlsAcceptor;
 use witnet_config::config::Config;
 
+/// Time limit of the TLS handshake of the clients
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Period of the pruning of the IP addresses whose requests are no longer limited
+const LIMITS_PRUNING_PERIOD: Duration = Duration::from_secs(60);
+
 /// JSON RPC server
 #[derive(Default)]
 pub struct JsonRpcServer {
     /// Server address
     server_addr: Option<SocketAddr>,
+    /// Handle of the stream of incoming connections, if listening
+    listener: Option<SpawnHandle>,
     /// Open connections, stored as instances of the `JsonRpc` actor
     open_connections: HashSet<Addr<JsonRpc>>,
+    /// WebSocket server address, if the WebSocket transport is enabled
+    websocket_addr: Option<SocketAddr>,
+    /// Handle of the stream of incoming WebSocket connections, if listening
+    websocket_listener: Option<SpawnHandle>,
+    /// Open WebSocket connections, stored as instances of the `WebSocketJsonRpc` actor
+    open_websockets: HashSet<Addr<WebSocketJsonRpc>>,
     /// JSON-RPC methods
     // Stored as an `Rc` to avoid creating a new handler for each connection
-    jsonrpc_io: Option<Rc<IoHandler<()>>>,
+    jsonrpc_io: Option<Rc<IoHandler<Subscriber>>>,
+    /// Methods served and API keys accepted from the clients
+    access: Rc<Access>,
+    /// Acceptor of the TLS connections, if the server terminates TLS
+    tls_acceptor: Option<TlsAcceptor>,
+    /// Limits of the requests, shared by all the connections
+    limits: Rc<RefCell<RequestLimits>>,
+}
+
+/// Message built from each incoming WebSocket connection, as the TCP connections of both
+/// listeners need different stream handlers
+pub struct InboundWebSocketConnect {
+    /// Tcp stream of the connection
+    pub stream: TcpStream,
+}
+
+impl Message for InboundWebSocketConnect {
+    type Result = ();
 }
 
 impl JsonRpcServer {
     /// Method to process the configuration received from ConfigManager
     fn process_config(&mut self, ctx: &mut <Self as Actor>::Context, config: &Config) {
-        let enabled = config.jsonrpc.enabled;
+        self.server_addr = Some(config.jsonrpc.server_address);
+        self.websocket_addr = config.jsonrpc.websocket_address;
+        self.access = Rc::new(Access::from_config(&config.jsonrpc));
+        self.limits = Rc::new(RefCell::new(RequestLimits::new(
+            config.jsonrpc.max_requests_per_minute,
+            config.jsonrpc.max_requests_in_flight,
+        )));
 
         // Do not start the server if enabled = false
-        if !enabled {
+        if !config.jsonrpc.enabled {
             debug!("JSON-RPC interface explicitly disabled by configuration.");
-            ctx.stop();
             return;
         }
 
-        debug!("Starting JSON-RPC interface.");
-        let server_addr = config.jsonrpc.server_address;
-        self.server_addr = Some(server_addr);
+        // Load the certificate and key of the server if it terminates TLS. The server is not
+        // started without them, as clients would send their requests without encryption
+        if config.jsonrpc.tls.is_enabled() {
+            match load_tls_acceptor(&config.jsonrpc.tls) {
+                Ok(acceptor) => self.tls_acceptor = Some(acceptor),
+                Err(e) => {
+                    error!("JSON-RPC server has not been started: {}", e);
+                    return;
+                }
+            }
+        }
+
+        if let Err(e) = self.start_listening(ctx) {
+            // Shutdown the entire system on error
+            // For example, when the server_addr is already in use
+            // FIXME(#72): gracefully stop the system?
+            error!("Could not start JSON-RPC server: {:?}", e);
+            panic!("Could not start JSON-RPC server: {:?}", e);
+        }
+    }
+
+    /// Start accepting connections, if not already doing it
+    fn start_listening(&mut self, ctx: &mut <Self as Actor>::Context) -> io::Result<()> {
         // Create and store the JSON-RPC method handler
-        let jsonrpc_io = jsonrpc_io_handler();
-        self.jsonrpc_io = Some(Rc::new(jsonrpc_io));
+        if self.jsonrpc_io.is_none() {
+            self.jsonrpc_io = Some(Rc::new(jsonrpc_io_handler()));
+        }
+
+        self.start_websocket_listening(ctx)?;
+
+        let server_addr = match (self.server_addr, &self.listener) {
+            (Some(server_addr), None) => server_addr,
+            _ => return Ok(()),
+        };
+
+        debug!("Starting JSON-RPC interface.");
 
         // Bind TCP listener to this address
         // FIXME(#176): running `yes | nc 127.0.0.1 1234` freezes the entire actor system
-        let listener = match TcpListener::bind(&server_addr) {
-            Ok(listener) => listener,
-            Err(e) => {
-                // Shutdown the entire system on error
-                // For example, when the server_addr is already in use
-                // FIXME(#72): gracefully stop the system?
-                error!("Could not start JSON-RPC server: {:?}", e);
-                panic!("Could not start JSON-RPC server: {:?}", e);
+        let listener = TcpListener::bind(&server_addr)?;
+
+        // Add stream which will return a InboundTcpConnect for each incoming TCP connection
+        self.listener = Some(
+            ctx.add_stream(
+                listener
+                    .incoming()
+                    .map_err(|_| ())
+                    .map(InboundTcpConnect::new),
+            ),
+        );
+
+        debug!(
+            "JSON-RPC interface is now running at {}{}",
+            server_addr,
+            if self.tls_acceptor.is_some() {
+                " (TLS)"
+            } else {
+                ""
             }
+        );
+
+        Ok(())
+    }
+
+    /// Start accepting WebSocket connections, if enabled and not already doing it
+    fn start_websocket_listening(&mut self, ctx: &mut <Self as Actor>::Context) -> io::Result<()> {
+        let websocket_addr = match (self.websocket_addr, &self.websocket_listener) {
+            (Some(websocket_addr), None) => websocket_addr,
+            _ => return Ok(()),
         };
 
-        // Add message stream which will return a InboundTcpConnect for each incoming TCP connection
-        ctx.add_message_stream(
-            listener
-                .incoming()
-                .map_err(|_| ())
-                .map(InboundTcpConnect::new),
+        let listener = TcpListener::bind(&websocket_addr)?;
+        self.websocket_listener = Some(
+            ctx.add_stream(
+                listener
+                    .incoming()
+                    .map_err(|_| ())
+                    .map(|stream| InboundWebSocketConnect { stream }),
+            ),
         );
 
-        debug!("JSON-RPC interface is now running at {}", server_addr);
+        debug!(
+            "JSON-RPC WebSocket interface is now running at {}",
+            websocket_addr
+        );
+
+        Ok(())
     }
 
-    fn add_connection(&mut self, parent: Addr<JsonRpcServer>, stream: TcpStream) {
+    /// Stop accepting connections, closing the listening sockets
+    fn stop_listening(&mut self, ctx: &mut <Self as Actor>::Context) {
+        if let Some(listener) = self.websocket_listener.take() {
+            ctx.cancel_future(listener);
+            debug!("JSON-RPC WebSocket interface has been stopped");
+        }
+        if let Some(listener) = self.listener.take() {
+            ctx.cancel_future(listener);
+            debug!("JSON-RPC interface has been stopped");
+        }
+    }
+
+    /// Complete the TLS handshake of an inbound connection if the server terminates TLS, closing
+    /// the connections which fail it or take too long, and then add it with `add`
+    fn accept(
+        &mut self,
+        ctx: &mut <Self as Actor>::Context,
+        stream: TcpStream,
+        add: fn(&mut Self, Addr<JsonRpcServer>, PeerStream),
+    ) {
+        match &self.tls_acceptor {
+            None => add(self, ctx.address(), PeerStream::Tcp(stream)),
+            Some(acceptor) => {
+                let peer_addr = stream.peer_addr();
+                Timeout::new(acceptor.accept(stream), TLS_HANDSHAKE_TIMEOUT)
+                    .into_actor(self)
+                    .then(move |res, act, ctx| {
+                        match res {
+                            Ok(stream) => add(act, ctx.address(), PeerStream::Tls(stream)),
+                            Err(e) => debug!(
+                                "TLS handshake with JSON-RPC client {:?} failed: {}",
+                                peer_addr, e
+                            ),
+                        }
+
+                        actix::fut::ok(())
+                    })
+                    .spawn(ctx);
+            }
+        }
+    }
+
+    fn add_connection(&mut self, parent: Addr<JsonRpcServer>, stream: PeerStream) {
         debug!(
             "Add session (currently {} open connections)",
             1 + self.open_connections.len()
         );
 
+        let client = match stream.peer_addr() {
+            Ok(addr) => addr.ip(),
+            Err(e) => {
+                debug!("Cannot get the address of a JSON-RPC client: {}", e);
+                return;
+            }
+        };
+
         // Get a reference to the JSON-RPC method handler
         let jsonrpc_io = Rc::clone(self.jsonrpc_io.as_ref().unwrap());
+        let access = Rc::clone(&self.access);
+        let limits = Rc::clone(&self.limits);
 
         // Create a new `JsonRpc` actor which will listen to this stream
         let addr = JsonRpc::create(|ctx| {
@@ -90,6 +247,10 @@ impl JsonRpcServer {
                 framed: FramedWrite::new(w, NewLineCodec, ctx),
                 parent,
                 jsonrpc_io,
+                permissions: access.api_keys.unauthenticated(),
+                access,
+                client,
+                limits,
             }
         });
 
@@ -100,6 +261,44 @@ impl JsonRpcServer {
     fn remove_connection(&mut self, addr: &Addr<JsonRpc>) {
         self.open_connections.remove(addr);
     }
+
+    fn add_websocket(&mut self, parent: Addr<JsonRpcServer>, stream: PeerStream) {
+        debug!(
+            "Add WebSocket session (currently {} open connections)",
+            1 + self.open_websockets.len()
+        );
+
+        let client = match stream.peer_addr() {
+            Ok(addr) => addr.ip(),
+            Err(e) => {
+                debug!("Cannot get the address of a JSON-RPC client: {}", e);
+                return;
+            }
+        };
+
+        // Get a reference to the JSON-RPC method handler
+        let jsonrpc_io = Rc::clone(self.jsonrpc_io.as_ref().unwrap());
+        let access = Rc::clone(&self.access);
+        let limits = Rc::clone(&self.limits);
+
+        // Create a new `WebSocketJsonRpc` actor which will listen to this stream
+        let addr = WebSocketJsonRpc::create(|ctx| {
+            let (r, w) = stream.split();
+            WebSocketJsonRpc::add_stream(FramedRead::new(r, WebSocketCodec::default()), ctx);
+            WebSocketJsonRpc {
+                framed: FramedWrite::new(w, WebSocketCodec::default(), ctx),
+                parent,
+                jsonrpc_io,
+                permissions: access.api_keys.unauthenticated(),
+                access,
+                client,
+                limits,
+            }
+        });
+
+        // Store the actor address
+        self.open_websockets.insert(addr);
+    }
 }
 
 impl Actor for JsonRpcServer {
@@ -109,17 +308,50 @@ impl Actor for JsonRpcServer {
     fn started(&mut self, ctx: &mut Self::Context) {
         // Send message to config manager and process its response
         send_get_config_request(self, ctx, JsonRpcServer::process_config);
+
+        // Forget the IP addresses which have not sent requests for a while
+        ctx.run_interval(LIMITS_PRUNING_PERIOD, |act, _ctx| {
+            act.limits.borrow_mut().prune(Instant::now());
+        });
     }
 }
 
-/// Handler for InboundTcpConnect messages (built from inbound connections)
-impl Handler<InboundTcpConnect> for JsonRpcServer {
-    /// Response for message, which is defined by `ResponseType` trait
-    type Result = ();
-
+/// Stream handler for InboundTcpConnect messages (built from inbound connections)
+impl StreamHandler<InboundTcpConnect, ()> for JsonRpcServer {
     /// Method to handle the InboundTcpConnect message
     fn handle(&mut self, msg: InboundTcpConnect, ctx: &mut Self::Context) {
-        self.add_connection(ctx.address(), msg.stream);
+        self.accept(ctx, msg.stream, JsonRpcServer::add_connection);
+    }
+
+    /// Keep the server running when the listener stops
+    fn finished(&mut self, _ctx: &mut Self::Context) {
+        self.listener = None;
+    }
+}
+
+/// Stream handler for InboundWebSocketConnect messages (built from inbound WebSocket connections)
+impl StreamHandler<InboundWebSocketConnect, ()> for JsonRpcServer {
+    /// Method to handle the InboundWebSocketConnect message
+    fn handle(&mut self, msg: InboundWebSocketConnect, ctx: &mut Self::Context) {
+        self.accept(ctx, msg.stream, JsonRpcServer::add_websocket);
+    }
+
+    /// Keep the server running when the listener stops
+    fn finished(&mut self, _ctx: &mut Self::Context) {
+        self.websocket_listener = None;
+    }
+}
+
+/// Handler for SetEnabled messages
+impl Handler<SetEnabled> for JsonRpcServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetEnabled, ctx: &mut Self::Context) {
+        if !msg.enabled {
+            self.stop_listening(ctx);
+        } else if let Err(e) = self.start_listening(ctx) {
+            error!("Could not start JSON-RPC server: {:?}", e);
+        }
     }
 }
 
@@ -137,3 +369,24 @@ impl Handler<Unregister> for JsonRpcServer {
         self.remove_connection(&msg.addr);
     }
 }
+
+#[derive(Message)]
+/// Unregister a closed WebSocket connection from the list of open WebSocket connections
+pub struct UnregisterWebSocket {
+    pub addr: Addr<WebSocketJsonRpc>,
+}
+
+impl Handler<UnregisterWebSocket> for JsonRpcServer {
+    type Result = ();
+
+    /// Method to remove a finished WebSocket session
+    fn handle(&mut self, msg: UnregisterWebSocket, _ctx: &mut Context<Self>) -> Self::Result {
+        self.open_websockets.remove(&msg.addr);
+    }
+}
+
+/// Required traits for being able to retrieve the actor address from
+/// the registry.
+impl Supervised for JsonRpcServer {}
+
+impl SystemService for JsonRpcServer {}
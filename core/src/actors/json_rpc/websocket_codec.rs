@@ -0,0 +1,439 @@
+use bytes::{BufMut, BytesMut};
+use crypto::digest::Digest;
+use crypto::sha1::Sha1;
+use std::io;
+use tokio::codec::{Decoder, Encoder};
+
+/// GUID appended to the key of the client to build the accept key of the handshake (RFC 6455)
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Maximum size of the HTTP request opening the connection
+const MAX_HANDSHAKE_SIZE: usize = 8 * 1024;
+
+/// Maximum size of a message, adding up all its fragments
+pub const MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+
+/// Items decoded from a WebSocket connection
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum WebSocketFrame {
+    /// HTTP request opening the connection, with the key of the client and the origin of the page
+    /// which opened it, if sent by a browser
+    Handshake {
+        /// Value of the `Sec-WebSocket-Key` header
+        key: String,
+        /// Value of the `Origin` header
+        origin: Option<String>,
+    },
+    /// HTTP request which is not a valid WebSocket handshake
+    BadRequest,
+    /// Text or binary message, with its fragments joined
+    Message(BytesMut),
+    /// Ping, with its payload
+    Ping(BytesMut),
+    /// Close frame
+    Close,
+}
+
+/// Items encoded into a WebSocket connection
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum WebSocketMessage {
+    /// HTTP response accepting the connection, with the accept key derived from the client key
+    Accept(String),
+    /// HTTP response rejecting the connection
+    Reject,
+    /// HTTP response rejecting the connection from a web page whose origin is not allowed
+    Forbidden,
+    /// Text message
+    Text(BytesMut),
+    /// Pong, with the payload of the ping
+    Pong(BytesMut),
+    /// Close frame
+    Close,
+}
+
+/// Codec for the WebSocket transport of the JSON-RPC interface (RFC 6455)
+///
+/// The HTTP request opening the connection is decoded first, and the frames after it. The frames
+/// sent by the server are not masked, and the frames received must be masked.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct WebSocketCodec {
+    /// Whether the opening handshake has been decoded
+    handshaked: bool,
+    /// Fragments of the message being received, if any
+    fragments: Option<BytesMut>,
+}
+
+/// Implement decoder trait for WebSocketCodec
+impl Decoder for WebSocketCodec {
+    type Item = WebSocketFrame;
+    type Error = io::Error;
+
+    /// Method to decode bytes to a handshake or a frame
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if !self.handshaked {
+            return self.decode_handshake(src);
+        }
+
+        // Fragments and pongs are consumed without returning an item
+        loop {
+            let (fin, opcode, payload) = match decode_frame(src)? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+
+            match opcode {
+                // Continuation
+                0x0 => {
+                    let mut fragments = self
+                        .fragments
+                        .take()
+                        .ok_or_else(|| invalid_data("Continuation frame without a message"))?;
+                    if fragments.len() + payload.len() > MAX_MESSAGE_SIZE {
+                        return Err(invalid_data("Message too big"));
+                    }
+                    fragments.extend_from_slice(&payload);
+                    if fin {
+                        return Ok(Some(WebSocketFrame::Message(fragments)));
+                    }
+                    self.fragments = Some(fragments);
+                }
+                // Text and binary
+                0x1 | 0x2 => {
+                    if self.fragments.is_some() {
+                        return Err(invalid_data("New message before the last one ended"));
+                    }
+                    if fin {
+                        return Ok(Some(WebSocketFrame::Message(payload)));
+                    }
+                    self.fragments = Some(payload);
+                }
+                0x8 => return Ok(Some(WebSocketFrame::Close)),
+                0x9 => return Ok(Some(WebSocketFrame::Ping(payload))),
+                // Pongs are not expected, as the server does not send pings
+                0xA => {}
+                _ => return Err(invalid_data("Unknown opcode")),
+            }
+        }
+    }
+}
+
+impl WebSocketCodec {
+    /// Method to decode the HTTP request opening the connection
+    fn decode_handshake(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<WebSocketFrame>, io::Error> {
+        let end = match src.windows(4).position(|window| window == b"\r\n\r\n") {
+            Some(end) => end,
+            None if src.len() > MAX_HANDSHAKE_SIZE => {
+                return Err(invalid_data("Handshake too big"));
+            }
+            None => return Ok(None),
+        };
+        let request = src.split_to(end + 4);
+        self.handshaked = true;
+
+        let request = match std::str::from_utf8(&request) {
+            Ok(request) => request,
+            Err(_) => return Ok(Some(WebSocketFrame::BadRequest)),
+        };
+        let mut lines = request.split("\r\n");
+        if !lines.next().map_or(false, |line| {
+            line.starts_with("GET ") && line.ends_with(" HTTP/1.1")
+        }) {
+            return Ok(Some(WebSocketFrame::BadRequest));
+        }
+
+        let mut upgrade = false;
+        let mut key = None;
+        let mut origin = None;
+        for line in lines {
+            let mut header = line.splitn(2, ':');
+            let (name, value) = match (header.next(), header.next()) {
+                (Some(name), Some(value)) => (name.trim(), value.trim()),
+                _ => continue,
+            };
+            if name.eq_ignore_ascii_case("Upgrade") {
+                upgrade = value.eq_ignore_ascii_case("websocket");
+            } else if name.eq_ignore_ascii_case("Sec-WebSocket-Key") {
+                key = Some(value.to_string());
+            } else if name.eq_ignore_ascii_case("Origin") {
+                origin = Some(value.to_string());
+            }
+        }
+
+        Ok(Some(match key {
+            Some(key) if upgrade => WebSocketFrame::Handshake { key, origin },
+            _ => WebSocketFrame::BadRequest,
+        }))
+    }
+}
+
+/// Implement encoder trait for WebSocketCodec
+impl Encoder for WebSocketCodec {
+    type Item = WebSocketMessage;
+    type Error = io::Error;
+
+    /// Method to encode a handshake response or a frame into bytes
+    fn encode(&mut self, msg: WebSocketMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let (opcode, payload) = match msg {
+            WebSocketMessage::Accept(accept) => {
+                let response = format!(
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                    accept
+                );
+                dst.extend_from_slice(response.as_bytes());
+                return Ok(());
+            }
+            WebSocketMessage::Reject => {
+                dst.extend_from_slice(
+                    b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+                );
+                return Ok(());
+            }
+            WebSocketMessage::Forbidden => {
+                dst.extend_from_slice(
+                    b"HTTP/1.1 403 Forbidden\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+                );
+                return Ok(());
+            }
+            WebSocketMessage::Text(payload) => (0x1, payload),
+            WebSocketMessage::Pong(payload) => (0xA, payload),
+            WebSocketMessage::Close => (0x8, BytesMut::new()),
+        };
+
+        let len = payload.len();
+        dst.reserve(len + 10);
+        // A single final frame, not masked
+        dst.put_u8(0x80 | opcode);
+        if len < 126 {
+            dst.put_u8(len as u8);
+        } else if len <= usize::from(u16::max_value()) {
+            dst.put_u8(126);
+            dst.put_u16_be(len as u16);
+        } else {
+            dst.put_u8(127);
+            dst.put_u64_be(len as u64);
+        }
+        dst.extend_from_slice(&payload);
+
+        Ok(())
+    }
+}
+
+/// Accept key of the handshake response: the Base64 encoding of the SHA-1 hash of the key of the
+/// client followed by the WebSocket GUID
+pub fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.input_str(key);
+    hasher.input_str(WEBSOCKET_GUID);
+    let mut hash = [0; 20];
+    hasher.result(&mut hash);
+
+    base64::encode(&hash)
+}
+
+/// Decode a frame, returning whether it is the final fragment of its message, its opcode and its
+/// unmasked payload, or `None` if the frame is incomplete
+fn decode_frame(src: &mut BytesMut) -> Result<Option<(bool, u8, BytesMut)>, io::Error> {
+    if src.len() < 2 {
+        return Ok(None);
+    }
+    let fin = src[0] & 0x80 != 0;
+    let opcode = src[0] & 0x0F;
+    if src[1] & 0x80 == 0 {
+        return Err(invalid_data("Frames from the client must be masked"));
+    }
+
+    let (len, len_size) = match src[1] & 0x7F {
+        126 if src.len() >= 4 => (u64::from(u16::from_be_bytes([src[2], src[3]])), 2),
+        127 if src.len() >= 10 => {
+            let mut len = [0; 8];
+            len.copy_from_slice(&src[2..10]);
+            (u64::from_be_bytes(len), 8)
+        }
+        126 | 127 => return Ok(None),
+        len => (u64::from(len), 0),
+    };
+    if len > MAX_MESSAGE_SIZE as u64 {
+        return Err(invalid_data("Message too big"));
+    }
+
+    let header_len = 2 + len_size + 4;
+    let frame_len = header_len + len as usize;
+    if src.len() < frame_len {
+        src.reserve(frame_len - src.len());
+        return Ok(None);
+    }
+
+    let frame = src.split_to(frame_len);
+    let mut mask = [0; 4];
+    mask.copy_from_slice(&frame[header_len - 4..header_len]);
+    let mut payload = BytesMut::from(&frame[header_len..]);
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+
+    Ok(Some((fin, opcode, payload)))
+}
+
+/// Error for the bytes that do not follow the WebSocket protocol
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame sent by a client, masked with a fixed key
+    fn client_frame(first_byte: u8, payload: &[u8]) -> BytesMut {
+        let mask = [1, 2, 3, 4];
+        let mut frame = vec![first_byte];
+        if payload.len() < 126 {
+            frame.push(0x80 | payload.len() as u8);
+        } else {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+
+        BytesMut::from(frame)
+    }
+
+    fn handshaked() -> WebSocketCodec {
+        WebSocketCodec {
+            handshaked: true,
+            fragments: None,
+        }
+    }
+
+    #[test]
+    fn accept_key_example() {
+        // Example of RFC 6455, section 1.3
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn decode_handshake() {
+        let request = "GET /ws HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nOrigin: http://localhost:8080\r\nSec-WebSocket-Version: 13\r\n\r\n";
+        let mut codec = WebSocketCodec::default();
+
+        // The handshake is not decoded until the whole request arrives
+        let mut input = BytesMut::from(&request.as_bytes()[..20]);
+        assert_eq!(codec.decode(&mut input).unwrap(), None);
+
+        let mut input = BytesMut::from(request.as_bytes());
+        input.extend_from_slice(&client_frame(0x81, b"{}"));
+        assert_eq!(
+            codec.decode(&mut input).unwrap(),
+            Some(WebSocketFrame::Handshake {
+                key: "dGhlIHNhbXBsZSBub25jZQ==".to_string(),
+                origin: Some("http://localhost:8080".to_string()),
+            })
+        );
+        // The frames following the handshake are decoded
+        assert_eq!(
+            codec.decode(&mut input).unwrap(),
+            Some(WebSocketFrame::Message(BytesMut::from(&b"{}"[..])))
+        );
+    }
+
+    #[test]
+    fn decode_bad_request() {
+        let request = "GET / HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n";
+        let mut input = BytesMut::from(request.as_bytes());
+        let mut codec = WebSocketCodec::default();
+        assert_eq!(
+            codec.decode(&mut input).unwrap(),
+            Some(WebSocketFrame::BadRequest)
+        );
+    }
+
+    #[test]
+    fn decode_fragmented_message() {
+        let mut input = client_frame(0x01, b"{\"jsonrpc\":");
+        // A ping between the fragments of a message
+        input.extend_from_slice(&client_frame(0x89, b"ping"));
+        input.extend_from_slice(&client_frame(0x80, b"\"2.0\"}"));
+        let mut codec = handshaked();
+
+        assert_eq!(
+            codec.decode(&mut input).unwrap(),
+            Some(WebSocketFrame::Ping(BytesMut::from(&b"ping"[..])))
+        );
+        assert_eq!(
+            codec.decode(&mut input).unwrap(),
+            Some(WebSocketFrame::Message(BytesMut::from(
+                &b"{\"jsonrpc\":\"2.0\"}"[..]
+            )))
+        );
+        assert_eq!(codec.decode(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_incomplete_frame() {
+        let payload = vec![b'a'; 300];
+        let frame = client_frame(0x81, &payload);
+        let mut codec = handshaked();
+
+        let mut input = BytesMut::from(&frame[..100]);
+        assert_eq!(codec.decode(&mut input).unwrap(), None);
+        input.extend_from_slice(&frame[100..]);
+        assert_eq!(
+            codec.decode(&mut input).unwrap(),
+            Some(WebSocketFrame::Message(BytesMut::from(payload)))
+        );
+    }
+
+    #[test]
+    fn reject_unmasked_frames() {
+        let mut input = BytesMut::from(&[0x81, 0x02, b'{', b'}'][..]);
+        let mut codec = handshaked();
+        assert!(codec.decode(&mut input).is_err());
+    }
+
+    #[test]
+    fn reject_oversized_messages() {
+        let mut input = BytesMut::from(&[0x81, 0xFF][..]);
+        input.extend_from_slice(&(MAX_MESSAGE_SIZE as u64 + 1).to_be_bytes());
+        let mut codec = handshaked();
+        assert!(codec.decode(&mut input).is_err());
+    }
+
+    #[test]
+    fn encode_frames() {
+        let mut codec = WebSocketCodec::default();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(WebSocketMessage::Text(BytesMut::from(&b"{}"[..])), &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..], &[0x81, 0x02, b'{', b'}']);
+
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                WebSocketMessage::Text(BytesMut::from(vec![0; 300])),
+                &mut buf,
+            )
+            .unwrap();
+        assert_eq!(&buf[..4], &[0x81, 126, 0x01, 0x2C]);
+        assert_eq!(buf.len(), 4 + 300);
+
+        let mut buf = BytesMut::new();
+        codec.encode(WebSocketMessage::Close, &mut buf).unwrap();
+        assert_eq!(&buf[..], &[0x88, 0x00]);
+    }
+
+    #[test]
+    fn encode_forbidden() {
+        let mut codec = WebSocketCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(WebSocketMessage::Forbidden, &mut buf).unwrap();
+        assert!(buf.starts_with(b"HTTP/1.1 403 Forbidden\r\n"));
+    }
+}
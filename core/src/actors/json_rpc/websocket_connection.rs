@@ -0,0 +1,167 @@
+use super::auth::{authorize, Access, Authorization, Permissions};
+use super::connection::request_string;
+use super::json_rpc_methods::{new_block_notification, session_event_notification, Subscriber};
+use super::limits::{rejection, RequestLimits};
+use super::server::JsonRpcServer;
+use super::server::UnregisterWebSocket;
+use super::websocket_codec::{accept_key, WebSocketCodec, WebSocketFrame, WebSocketMessage};
+use crate::actors::blocks_manager::messages::NewBlockNotification;
+use crate::actors::sessions_manager::messages::SessionEventNotification;
+use crate::actors::stream::PeerStream;
+use actix::{
+    io::FramedWrite, io::WriteHandler, Actor, ActorFuture, Addr, AsyncContext, Context, Handler,
+    Running, StreamHandler, WrapFuture,
+};
+use bytes::BytesMut;
+use jsonrpc_core::{IoHandler, MetaIoHandler};
+use log::*;
+use std::cell::RefCell;
+use std::io;
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::time::Instant;
+use tokio::io::WriteHalf;
+
+/// A single JSON-RPC connection over WebSocket
+pub struct WebSocketJsonRpc {
+    /// Stream
+    pub framed: FramedWrite<WriteHalf<PeerStream>, WebSocketCodec>,
+    /// Reference to parent
+    // Needed to send the `UnregisterWebSocket` message when the connection closes
+    pub parent: Addr<JsonRpcServer>,
+    /// IoHandler
+    pub jsonrpc_io: Rc<IoHandler<Subscriber>>,
+    /// Methods served and API keys accepted from the client
+    pub access: Rc<Access>,
+    /// Methods that the client is allowed to call
+    pub permissions: Permissions,
+    /// IP address of the client
+    pub client: IpAddr,
+    /// Limits of the requests, shared by all the connections
+    pub limits: Rc<RefCell<RequestLimits>>,
+}
+
+impl WebSocketJsonRpc {
+    /// Method to send a response or a notification to the client as a text message
+    fn write_text(&mut self, text: String) {
+        self.framed
+            .write(WebSocketMessage::Text(BytesMut::from(text)));
+    }
+}
+
+impl Actor for WebSocketJsonRpc {
+    type Context = Context<Self>;
+
+    /// Method to be executed when the actor is stopping
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        // Unregister connection from JsonRpcServer
+        self.parent.do_send(UnregisterWebSocket {
+            addr: ctx.address(),
+        });
+
+        Running::Stop
+    }
+}
+
+impl WriteHandler<io::Error> for WebSocketJsonRpc {}
+
+/// Implement `StreamHandler` trait in order to use `Framed` with an actor
+impl StreamHandler<WebSocketFrame, io::Error> for WebSocketJsonRpc {
+    /// This is main event loop for client requests
+    fn handle(&mut self, frame: WebSocketFrame, ctx: &mut Self::Context) {
+        match frame {
+            WebSocketFrame::Handshake { key, origin } => match origin {
+                // Browsers send the origin of the page opening the connection, other clients
+                // send none and are always accepted
+                Some(ref origin) if !self.access.cors.allows(origin) => {
+                    debug!("WebSocket connection from origin {} rejected", origin);
+                    // The connection stops once the response is written
+                    self.framed.write(WebSocketMessage::Forbidden);
+                    self.framed.close();
+                }
+                _ => {
+                    self.framed
+                        .write(WebSocketMessage::Accept(accept_key(&key)));
+                }
+            },
+            WebSocketFrame::BadRequest => {
+                debug!("Invalid WebSocket handshake");
+                // The connection stops once the response is written
+                self.framed.write(WebSocketMessage::Reject);
+                self.framed.close();
+            }
+            WebSocketFrame::Message(bytes) => {
+                let msg = request_string(&bytes);
+
+                // Requests over the limits are rejected before anything else
+                let in_flight =
+                    match RequestLimits::start(&self.limits, self.client, Instant::now()) {
+                        Ok(in_flight) => in_flight,
+                        Err(e) => {
+                            debug!("JSON-RPC request rejected: {}", e.message);
+                            if let Some(response) = rejection(&msg, e) {
+                                self.write_text(response);
+                            }
+                            return;
+                        }
+                    };
+
+                // Requests to methods which are not served or which the
+                // client is not allowed to call, and calls to
+                // `authenticate`, are answered right away
+                if let Authorization::Answered(response) =
+                    authorize(&msg, &self.access, &mut self.permissions)
+                {
+                    if let Some(response) = response {
+                        self.write_text(response);
+                    }
+                    return;
+                }
+
+                // Handle response asynchronously, as some methods need to
+                // wait for other actors. The notifications of the subscriptions
+                // made by the request are sent to this connection
+                let jsonrpc_io: &MetaIoHandler<Subscriber> = &self.jsonrpc_io;
+                let subscriber =
+                    Subscriber::new(ctx.address().recipient(), ctx.address().recipient());
+                let response = jsonrpc_io
+                    .handle_request(&msg, subscriber)
+                    .into_actor(self)
+                    .map(move |response, act, _ctx| {
+                        // The request stops being counted once it is answered
+                        drop(in_flight);
+                        if let Some(response) = response {
+                            act.write_text(response);
+                        }
+                    });
+                ctx.spawn(response);
+            }
+            WebSocketFrame::Ping(payload) => {
+                self.framed.write(WebSocketMessage::Pong(payload));
+            }
+            WebSocketFrame::Close => {
+                // Answer the close frame and close the connection
+                self.framed.write(WebSocketMessage::Close);
+                self.framed.close();
+            }
+        }
+    }
+}
+
+/// Handler for the session events the connection is subscribed to
+impl Handler<SessionEventNotification> for WebSocketJsonRpc {
+    type Result = ();
+
+    fn handle(&mut self, msg: SessionEventNotification, _ctx: &mut Self::Context) {
+        self.write_text(session_event_notification(&msg).to_string());
+    }
+}
+
+/// Handler for the new blocks the connection is subscribed to
+impl Handler<NewBlockNotification> for WebSocketJsonRpc {
+    type Result = ();
+
+    fn handle(&mut self, msg: NewBlockNotification, _ctx: &mut Self::Context) {
+        self.write_text(new_block_notification(&msg).to_string());
+    }
+}
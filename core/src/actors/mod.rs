@@ -1,6 +1,12 @@
 /// Codec module
 pub mod codec;
 
+/// Stream of the connections with peers
+pub mod stream;
+
+/// Bandwidth throttling of the connections with peers
+pub mod throttle;
+
 /// Module running system actor
 pub mod node;
 
@@ -39,3 +45,6 @@ pub mod utxo_manager;
 
 /// JSON RPC server
 pub mod json_rpc;
+
+/// REST server
+pub mod rest;
@@ -2,31 +2,70 @@ use std::io;
 use std::path::PathBuf;
 use std::process::exit;
 use std::result::Result;
-
-use actix::{Actor, System};
-use log::info;
-
-use crate::actors::blocks_manager::BlocksManager;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actix::{msgs::Execute, Actor, Addr, Arbiter, MailboxError, System};
+use futures::{
+    future::{self, Either, Loop},
+    Future,
+};
+use log::{debug, error, info, warn};
+#[cfg(unix)]
+use signal_hook::{iterator::Signals, SIGHUP, SIGTERM};
+use tokio::timer::{Delay, Timeout};
+
+use crate::actors::blocks_manager::{messages::PersistChainInfo, BlocksManager};
+#[cfg(unix)]
+use crate::actors::config_manager::messages::ReloadConfig;
 use crate::actors::config_manager::ConfigManager;
-use crate::actors::connections_manager::ConnectionsManager;
+use crate::actors::connections_manager::{messages::StopConnections, ConnectionsManager};
 use crate::actors::epoch_manager::EpochManager;
 use crate::actors::json_rpc::JsonRpcServer;
 use crate::actors::mempool_manager::MempoolManager;
-use crate::actors::peers_manager::PeersManager;
-use crate::actors::sessions_manager::SessionsManager;
-use crate::actors::storage_manager::StorageManager;
+use crate::actors::peers_manager::{messages::PersistPeers, PeersManager};
+use crate::actors::rest::RestServer;
+use crate::actors::sessions_manager::{
+    messages::{CloseSessions, GetMetrics},
+    SessionsManager,
+};
+use crate::actors::storage_manager::{messages::Flush, StorageManager};
 use crate::actors::utxo_manager::UtxoManager;
 
+/// Maximum time to wait for the sessions to close and the state to be flushed before exiting
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Period with which the sessions manager is checked while the sessions close
+const DRAIN_CHECK_PERIOD: Duration = Duration::from_millis(100);
+
+/// Whether the node is already shutting down
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
 /// Function to run the main system
-pub fn run(config: Option<PathBuf>, callback: fn()) -> Result<(), io::Error> {
+pub fn run(
+    config: Option<PathBuf>,
+    profile: Option<String>,
+    overrides: Vec<String>,
+    callback: fn(Addr<Arbiter>),
+) -> Result<(), io::Error> {
     // Init system
     let system = System::new("node");
 
-    // Call cb function (register interrupt handlers)
-    callback();
+    // Call cb function (register interrupt handlers), with the address of the arbiter of the
+    // system, which `close` needs to shut the node down from another thread
+    callback(Arbiter::current());
+
+    // Shut the node down gracefully when receiving SIGTERM, e.g.: from `kill` or a service manager
+    #[cfg(unix)]
+    close_on_sigterm(Arbiter::current())?;
 
     // Start config manager actor
-    let config_manager_addr = ConfigManager::new(config).start();
+    let config_manager_addr = ConfigManager::new(config)
+        .with_profile(profile)
+        .with_overrides(overrides)
+        .start();
     System::current().registry().set(config_manager_addr);
 
     // Start storage manager actor
@@ -61,8 +100,17 @@ pub fn run(config: Option<PathBuf>, callback: fn()) -> Result<(), io::Error> {
     let utxo_manager_addr = UtxoManager::start_default();
     System::current().registry().set(utxo_manager_addr);
 
-    // Start JSON RPC server (this doesn't need to be in the registry)
-    let _json_rpc_server_addr = JsonRpcServer::default().start();
+    // Start JSON RPC server
+    let json_rpc_server_addr = JsonRpcServer::default().start();
+    System::current().registry().set(json_rpc_server_addr);
+
+    // Start REST server
+    let rest_server_addr = RestServer::default().start();
+    System::current().registry().set(rest_server_addr);
+
+    // Reload the configuration when receiving SIGHUP
+    #[cfg(unix)]
+    reload_config_on_sighup()?;
 
     // Run system
     system.run();
@@ -70,13 +118,115 @@ pub fn run(config: Option<PathBuf>, callback: fn()) -> Result<(), io::Error> {
     Ok(())
 }
 
-/// Function to close the main system
-pub fn close() {
+/// Function to send a `ReloadConfig` message to the config manager
+/// every time the process receives a SIGHUP signal
+#[cfg(unix)]
+fn reload_config_on_sighup() -> Result<(), io::Error> {
+    let signals = Signals::new(&[SIGHUP])?;
+    let config_manager_addr = System::current().registry().get::<ConfigManager>();
+
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            info!("SIGHUP received, reloading configuration");
+            config_manager_addr.do_send(ReloadConfig);
+        }
+    });
+
+    Ok(())
+}
+
+/// Function to close the node every time the process receives a SIGTERM signal, the second one
+/// exiting right away as with any other call to `close`
+#[cfg(unix)]
+fn close_on_sigterm(arbiter: Addr<Arbiter>) -> Result<(), io::Error> {
+    let signals = Signals::new(&[SIGTERM])?;
+
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            info!("SIGTERM received");
+            close(&arbiter);
+        }
+    });
+
+    Ok(())
+}
+
+/// Function to close the main system gracefully. It can be called from any thread, e.g.: from the
+/// handler of the interrupt signals, with the address of the arbiter of the system. Calling it
+/// again while the node is shutting down exits right away
+pub fn close(arbiter: &Addr<Arbiter>) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        warn!("Closing node without waiting for the shutdown to complete");
+        exit(1);
+    }
     info!("Closing node");
 
-    // FIXME(#72): find out how to gracefully stop the system
-    // System::current().stop();
+    // The actors can only be reached from the thread of the system
+    arbiter.do_send(Execute::new(|| -> Result<(), ()> {
+        Arbiter::spawn(shutdown());
+        Ok(())
+    }));
+}
+
+/// Function to shut the node down in order: stop accepting connections, close the sessions with
+/// the peers, flush the chain info and the peers to storage, and stop the system. The system is
+/// stopped anyway if the shutdown takes longer than `SHUTDOWN_TIMEOUT`
+fn shutdown() -> impl Future<Item = (), Error = ()> {
+    let registry = System::current().registry();
+    let connections_manager_addr = registry.get::<ConnectionsManager>();
+    let sessions_manager_addr = registry.get::<SessionsManager>();
+    let peers_manager_addr = registry.get::<PeersManager>();
+    let blocks_manager_addr = registry.get::<BlocksManager>();
+    let storage_manager_addr = registry.get::<StorageManager>();
+
+    let sequence = connections_manager_addr
+        .send(StopConnections)
+        .and_then(move |_| {
+            sessions_manager_addr
+                .send(CloseSessions)
+                .and_then(move |_| drain_sessions(sessions_manager_addr))
+        })
+        // The sessions have already sent the updates of the reputations of their peers, so they
+        // are persisted along with the peers
+        .and_then(move |_| peers_manager_addr.send(PersistPeers))
+        .and_then(move |_| blocks_manager_addr.send(PersistChainInfo))
+        // The writes sent so far are applied before the flush
+        .and_then(move |_| storage_manager_addr.send(Flush))
+        .map(|res| {
+            if let Err(e) = res {
+                error!("Error while flushing the storage: {}", e);
+            }
+        })
+        .map_err(|e| error!("Unsuccessful communication with the actors: {}", e));
+
+    Timeout::new(sequence, SHUTDOWN_TIMEOUT).then(|res| {
+        if res.is_err() {
+            warn!("The node did not shut down gracefully");
+        }
+        info!("Node closed");
+        System::current().stop();
+
+        Ok(())
+    })
+}
 
-    // Process exit
-    exit(0);
+/// Function to wait until every session has stopped and unregistered from the sessions manager
+fn drain_sessions(
+    sessions_manager_addr: Addr<SessionsManager>,
+) -> impl Future<Item = (), Error = MailboxError> {
+    future::loop_fn((), move |()| {
+        sessions_manager_addr.send(GetMetrics).and_then(|metrics| {
+            let open_sessions =
+                metrics.inbound_sessions + metrics.outbound_sessions + metrics.pending_sessions;
+            if open_sessions == 0 {
+                Either::A(future::ok(Loop::Break(())))
+            } else {
+                debug!("Waiting for {} sessions to close", open_sessions);
+                Either::B(
+                    Delay::new(Instant::now() + DRAIN_CHECK_PERIOD)
+                        .then(|_| Ok(Loop::Continue(()))),
+                )
+            }
+        })
+    })
 }
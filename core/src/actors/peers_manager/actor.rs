@@ -26,6 +26,7 @@ impl Actor for PeersManager {
 
             // Get storage peers period
             let storage_peers_period = config.connections.storage_peers_period;
+            let storage_peers_ttl = config.connections.storage_peers_ttl;
 
             // Add all peers
             info!(
@@ -37,6 +38,25 @@ impl Actor for PeersManager {
                 Err(e) => error!("Error when adding peer addresses from config: {}", e),
             }
 
+            // Add peers from the DNS seeds, now and periodically
+            let dns_seeds: Vec<_> = config
+                .connections
+                .dns_seeds
+                .iter()
+                .filter_map(|seed| config.connections.parse_dns_seed(seed).ok())
+                .collect();
+            if !dns_seeds.is_empty() {
+                act.resolve_dns_seeds(ctx, dns_seeds, config.connections.dns_seeds_period);
+            }
+
+            // Add peers from the local network, if enabled
+            if config.connections.mdns {
+                match config.connections.server_addrs.first() {
+                    Some(server_addr) => PeersManager::discover_local_peers(*server_addr),
+                    None => error!("Local peer discovery needs a server address to announce"),
+                }
+            }
+
             // Add peers from storage:
             // Get storage manager actor address
             let storage_manager_addr = System::current().registry().get::<StorageManager>();
@@ -63,18 +83,29 @@ impl Actor for PeersManager {
                 .and_then(|peers_from_storage, act, _ctx| {
                     // peers_from_storage can be None if the storage does not contain that key
                     if let Some(peers_from_storage) = peers_from_storage {
-                        // Add all the peers from storage
-                        // The add method handles duplicates by overwriting the old values
-                        let peers = peers_from_storage.get_all().unwrap();
+                        // Add all the peers from storage, keeping the tried ones apart, and their
+                        // reputations
+                        // The add methods handle duplicates by overwriting the old values
+                        let peers = peers_from_storage.get_new().unwrap();
+                        let tried_peers = peers_from_storage.get_tried().unwrap();
+                        let reputations = peers_from_storage.get_reputations().unwrap();
                         info!(
-                            "Adding the following peer addresses from storage: {:?}",
-                            peers
+                            "Adding the following peer addresses from storage: {:?} (tried: {:?})",
+                            peers, tried_peers
                         );
-                        match act.peers.add(peers) {
-                            Ok(_duplicated_peers) => {}
-                            Err(e) => {
-                                error!("Error when adding peer addresses from storage: {}", e);
-                            }
+                        let result = act
+                            .peers
+                            .add(peers)
+                            .and_then(|_| act.peers.add_tried(tried_peers))
+                            .and_then(|_| {
+                                for (address, reputation) in reputations {
+                                    act.peers.update_reputation(address, reputation)?;
+                                }
+
+                                Ok(())
+                            });
+                        if let Err(e) = result {
+                            error!("Error when adding peer addresses from storage: {}", e);
                         }
                     }
 
@@ -82,8 +113,12 @@ impl Actor for PeersManager {
                 })
                 .wait(ctx);
 
-            // Start the storage peers process on SessionsManager start
-            act.persist_peers(ctx, storage_peers_period);
+            // Start the storage peers process on SessionsManager start, unless the storage is
+            // read-only
+            if !config.storage.read_only {
+                act.storage_peers_ttl = Some(storage_peers_ttl);
+                act.persist_peers(ctx, storage_peers_period, storage_peers_ttl);
+            }
         });
     }
 }
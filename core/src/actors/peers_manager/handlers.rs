@@ -1,9 +1,17 @@
-use actix::{Context, Handler};
+use actix::{Context, Handler, MessageResult};
 use log::{debug, info};
 
+use witnet_p2p::peers::{MAX_PEERS_PER_MESSAGE, MAX_SHARED_PEER_AGE};
+use witnet_util::timestamp::get_timestamp;
+
+use witnet_p2p::peers::{error::PeersResult, Reputation};
+
 use super::messages::{
-    AddPeers, GetPeers, GetRandomPeer, PeersSocketAddrResult, PeersSocketAddrsResult, RemovePeers,
+    AddFailedPeers, AddPeers, AddPeersFrom, AddTriedPeers, GetMetrics, GetPeers, GetRandomNewPeer,
+    GetRandomPeer, GetRecentPeers, GetReputation, PeersSocketAddrResult, PeersSocketAddrsResult,
+    PersistPeers, RemovePeers, UpdateReputation,
 };
+use super::metrics::PeersMetrics;
 
 use super::PeersManager;
 
@@ -18,6 +26,39 @@ impl Handler<AddPeers> for PeersManager {
     }
 }
 
+/// Handler for AddPeersFrom message
+impl Handler<AddPeersFrom> for PeersManager {
+    type Result = PeersSocketAddrsResult;
+
+    fn handle(&mut self, msg: AddPeersFrom, _: &mut Context<Self>) -> Self::Result {
+        debug!(
+            "Add peer handle for addresses gossiped by {}: {:?}",
+            msg.source, msg.addresses
+        );
+        self.peers.add_from(msg.addresses, msg.source)
+    }
+}
+
+/// Handler for AddTriedPeers message
+impl Handler<AddTriedPeers> for PeersManager {
+    type Result = PeersSocketAddrsResult;
+
+    fn handle(&mut self, msg: AddTriedPeers, _: &mut Context<Self>) -> Self::Result {
+        debug!("Add tried peer handle for addresses: {:?}", msg.addresses);
+        self.peers.add_tried(msg.addresses)
+    }
+}
+
+/// Handler for AddFailedPeers message
+impl Handler<AddFailedPeers> for PeersManager {
+    type Result = PeersSocketAddrsResult;
+
+    fn handle(&mut self, msg: AddFailedPeers, _: &mut Context<Self>) -> Self::Result {
+        debug!("Add failed peer handle for addresses: {:?}", msg.addresses);
+        self.peers.add_failed(msg.addresses)
+    }
+}
+
 /// Handler for RemovePeers message
 impl Handler<RemovePeers> for PeersManager {
     type Result = PeersSocketAddrsResult;
@@ -29,6 +70,28 @@ impl Handler<RemovePeers> for PeersManager {
     }
 }
 
+/// Handler for UpdateReputation message
+impl Handler<UpdateReputation> for PeersManager {
+    type Result = PeersResult<Option<Reputation>>;
+
+    fn handle(&mut self, msg: UpdateReputation, _: &mut Context<Self>) -> Self::Result {
+        debug!(
+            "Update reputation of peer {}: {:?}",
+            msg.address, msg.update
+        );
+        self.peers.update_reputation(msg.address, msg.update)
+    }
+}
+
+/// Handler for GetReputation message
+impl Handler<GetReputation> for PeersManager {
+    type Result = MessageResult<GetReputation>;
+
+    fn handle(&mut self, msg: GetReputation, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.peers.get_reputation(&msg.address))
+    }
+}
+
 /// Handler for GetRandomPeer message
 impl Handler<GetRandomPeer> for PeersManager {
     type Result = PeersSocketAddrResult;
@@ -39,6 +102,16 @@ impl Handler<GetRandomPeer> for PeersManager {
     }
 }
 
+/// Handler for GetRandomNewPeer message
+impl Handler<GetRandomNewPeer> for PeersManager {
+    type Result = PeersSocketAddrResult;
+
+    fn handle(&mut self, _msg: GetRandomNewPeer, _: &mut Context<Self>) -> Self::Result {
+        debug!("Get random new peer");
+        self.peers.get_random_new()
+    }
+}
+
 /// Handler for GetPeers message
 impl Handler<GetPeers> for PeersManager {
     type Result = PeersSocketAddrsResult;
@@ -48,3 +121,38 @@ impl Handler<GetPeers> for PeersManager {
         self.peers.get_all()
     }
 }
+
+/// Handler for GetRecentPeers message
+impl Handler<GetRecentPeers> for PeersManager {
+    type Result = PeersSocketAddrsResult;
+
+    fn handle(&mut self, _msg: GetRecentPeers, _: &mut Context<Self>) -> Self::Result {
+        debug!("Get recent peers");
+        self.peers.get_recent(
+            MAX_PEERS_PER_MESSAGE,
+            get_timestamp().saturating_sub(MAX_SHARED_PEER_AGE),
+        )
+    }
+}
+
+/// Handler for GetMetrics message
+impl Handler<GetMetrics> for PeersManager {
+    type Result = PeersResult<PeersMetrics>;
+
+    fn handle(&mut self, _msg: GetMetrics, _: &mut Context<Self>) -> Self::Result {
+        PeersMetrics::new(&self.peers, get_timestamp())
+    }
+}
+
+/// Handler for PersistPeers message
+impl Handler<PersistPeers> for PeersManager {
+    type Result = ();
+
+    fn handle(&mut self, _msg: PersistPeers, ctx: &mut Context<Self>) {
+        // Nothing is persisted into a read-only storage
+        if let Some(storage_peers_ttl) = self.storage_peers_ttl {
+            debug!("Persist peers");
+            self.write_peers(ctx, storage_peers_ttl);
+        }
+    }
+}
@@ -1,8 +1,10 @@
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use actix::Message;
 
-use witnet_p2p::peers::error::PeersResult;
+use witnet_p2p::peers::{error::PeersResult, Reputation};
+
+use super::metrics::PeersMetrics;
 
 /// One peer
 pub type PeersSocketAddrResult = PeersResult<Option<SocketAddr>>;
@@ -19,6 +21,39 @@ impl Message for AddPeers {
     type Result = PeersSocketAddrsResult;
 }
 
+/// Message to add one or more peer addresses gossiped by a peer to the list
+pub struct AddPeersFrom {
+    /// Address of the peer
+    pub addresses: Vec<SocketAddr>,
+    /// IP address of the peer which gossiped the addresses
+    pub source: IpAddr,
+}
+
+impl Message for AddPeersFrom {
+    type Result = PeersSocketAddrsResult;
+}
+
+/// Message to mark one or more peer addresses as tried, after connecting to them successfully
+pub struct AddTriedPeers {
+    /// Address of the peer
+    pub addresses: Vec<SocketAddr>,
+}
+
+impl Message for AddTriedPeers {
+    type Result = PeersSocketAddrsResult;
+}
+
+/// Message to record a failed connection to one or more peer addresses, which are not returned as
+/// random peers until their backoff expires
+pub struct AddFailedPeers {
+    /// Address of the peer
+    pub addresses: Vec<SocketAddr>,
+}
+
+impl Message for AddFailedPeers {
+    type Result = PeersSocketAddrsResult;
+}
+
 /// Message to remove one or more peer addresses from the list
 pub struct RemovePeers {
     /// Address of the peer
@@ -29,16 +64,69 @@ impl Message for RemovePeers {
     type Result = PeersSocketAddrsResult;
 }
 
+/// Message to add the uptime, useful blocks and misbehavior score observed in a session to the
+/// reputation of a known peer
+pub struct UpdateReputation {
+    /// Address of the peer
+    pub address: SocketAddr,
+    /// Amounts to add to the reputation of the peer
+    pub update: Reputation,
+}
+
+impl Message for UpdateReputation {
+    type Result = PeersResult<Option<Reputation>>;
+}
+
+/// Message to get the reputation of a known peer
+pub struct GetReputation {
+    /// Address of the peer
+    pub address: SocketAddr,
+}
+
+impl Message for GetReputation {
+    type Result = Reputation;
+}
+
 /// Message to get a (random) peer address from the list
+/// The peers with the best reputation are preferred among the tried ones
 pub struct GetRandomPeer;
 
 impl Message for GetRandomPeer {
     type Result = PeersSocketAddrResult;
 }
 
+/// Message to get a (random) peer address from the new peers, to which the node has not connected
+/// yet
+pub struct GetRandomNewPeer;
+
+impl Message for GetRandomNewPeer {
+    type Result = PeersSocketAddrResult;
+}
+
 /// Message to get all the peer addresses from the list
 pub struct GetPeers;
 
 impl Message for GetPeers {
     type Result = PeersSocketAddrsResult;
 }
+
+/// Message to obtain the metrics of the known peers
+pub struct GetMetrics;
+
+impl Message for GetMetrics {
+    type Result = PeersResult<PeersMetrics>;
+}
+
+/// Message to persist the peers into storage right away, e.g.: when the node is shutting down
+pub struct PersistPeers;
+
+impl Message for PersistPeers {
+    type Result = ();
+}
+
+/// Message to get a sample of the peer addresses heard of recently, to be shared with a peer
+pub struct GetRecentPeers;
+
+impl Message for GetRecentPeers {
+    type Result = PeersSocketAddrsResult;
+}
@@ -0,0 +1,32 @@
+//! # Peers metrics
+//!
+//! The peers manager reports the size of its tables of peers and the
+//! number of peers the node is backing off from after failed
+//! connections, so operators can tell whether the node knows enough
+//! reachable peers.
+use serde_derive::Serialize;
+
+use witnet_p2p::peers::{error::PeersResult, Peers};
+
+/// Snapshot of the metrics of the known peers
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PeersMetrics {
+    /// Number of peers heard of, to which the node has not connected yet
+    pub new_peers: usize,
+    /// Number of peers to which the node has connected successfully
+    pub tried_peers: usize,
+    /// Number of peers whose last connections failed, which are not connected to until their
+    /// backoff expires
+    pub backing_off_peers: usize,
+}
+
+impl PeersMetrics {
+    /// Take a snapshot of the metrics of `peers` at timestamp `now`
+    pub fn new(peers: &Peers, now: i64) -> PeersResult<Self> {
+        Ok(PeersMetrics {
+            new_peers: peers.get_new()?.len(),
+            tried_peers: peers.get_tried()?.len(),
+            backing_off_peers: peers.get_backing_off(now)?.len(),
+        })
+    }
+}
@@ -1,16 +1,31 @@
-use std::time::Duration;
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
 
 use crate::actors::{
+    peers_manager::messages::AddPeers,
     storage_keys::PEERS_KEY,
     storage_manager::{messages::Put, StorageManager},
 };
 use actix::{
+    actors::resolver::{Resolve, Resolver},
     ActorFuture, AsyncContext, Context, ContextFutureSpawner, Supervised, System, SystemService,
     WrapFuture,
 };
-use log::{error, info};
+use log::{debug, error, info, warn};
+use net2::UdpBuilder;
+use rand::{thread_rng, Rng};
+
+use witnet_p2p::{mdns, peers::Peers};
 
-use witnet_p2p::peers::Peers;
+/// Period of the mDNS queries looking for other nodes in the local network
+const MDNS_QUERY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Maximum size of the mDNS packets received
+const MDNS_MAX_PACKET_LEN: usize = 9000;
 
 // Internal Actor implementation for PeersManager
 mod actor;
@@ -29,6 +44,9 @@ mod handlers;
 /// * Get all peers
 pub mod messages;
 
+/// Metrics of the known peers
+pub mod metrics;
+
 /// Peers manager actor: manages a list of available peers to connect
 ///
 /// During the execuion of the node, there are at least 2 ways in which peers can be discovered:
@@ -40,37 +58,196 @@ pub mod messages;
 pub struct PeersManager {
     /// Known peers
     peers: Peers,
+    /// Time to live of the peers persisted into storage, `None` if they are not persisted
+    storage_peers_ttl: Option<Duration>,
 }
 
 impl PeersManager {
     /// Method to periodically persist peers into storage
-    fn persist_peers(&self, ctx: &mut Context<Self>, storage_peers_period: Duration) {
+    fn persist_peers(
+        &self,
+        ctx: &mut Context<Self>,
+        storage_peers_period: Duration,
+        storage_peers_ttl: Duration,
+    ) {
         // Schedule the discovery_peers with a given period
         ctx.run_later(storage_peers_period, move |act, ctx| {
-            // Get StorageManager address
-            let storage_manager_addr = System::current().registry().get::<StorageManager>();
-
-            // Persist peers into storage. `AsyncContext::wait` registers
-            // future within context, but context waits until this future resolves
-            // before processing any other events.
-            storage_manager_addr
-                .send(Put::from_value(PEERS_KEY, &act.peers).unwrap())
-                .into_actor(act)
-                .then(|res, _act, _ctx| {
+            act.write_peers(ctx, storage_peers_ttl);
+
+            act.persist_peers(ctx, storage_peers_period, storage_peers_ttl);
+        });
+    }
+
+    /// Method to write the peers into storage right away
+    fn write_peers(&self, ctx: &mut Context<Self>, storage_peers_ttl: Duration) {
+        // Get StorageManager address
+        let storage_manager_addr = System::current().registry().get::<StorageManager>();
+
+        // Persist peers into storage. `AsyncContext::wait` registers
+        // future within context, but context waits until this future resolves
+        // before processing any other events.
+        storage_manager_addr
+            // The peers expire if they are not persisted again, so peers
+            // stored by a node which has been offline for long are dropped
+            .send(
+                Put::from_value(PEERS_KEY, &self.peers)
+                    .unwrap()
+                    .with_ttl(storage_peers_ttl),
+            )
+            .into_actor(self)
+            .then(|res, _act, _ctx| {
+                match res {
+                    Ok(Ok(_)) => info!("PeersManager successfully persist peers to storage"),
+                    _ => {
+                        error!("Peers manager persist peers to storage failed");
+                        // FIXME(#72): handle errors
+                    }
+                }
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    /// Method to resolve the DNS seeds, given as host and port, adding the resolved addresses to
+    /// the known peers, and to resolve them again with a given period
+    fn resolve_dns_seeds(
+        &self,
+        ctx: &mut Context<Self>,
+        dns_seeds: Vec<(String, u16)>,
+        dns_seeds_period: Duration,
+    ) {
+        for (host, port) in dns_seeds.iter().cloned() {
+            Resolver::from_registry()
+                // Send a message to resolve the seed
+                .send(Resolve::host_port(host.clone(), port))
+                .into_actor(self)
+                // Process the response
+                .then(move |res, act, _ctx| {
                     match res {
-                        Ok(Ok(_)) => info!("PeersManager successfully persist peers to storage"),
-                        _ => {
-                            error!("Peers manager persist peers to storage failed");
-                            // FIXME(#72): handle errors
+                        Ok(Ok(addresses)) => {
+                            let addresses: Vec<_> = addresses.into_iter().collect();
+                            info!(
+                                "Adding the following peer addresses from DNS seed {}: {:?}",
+                                host, addresses
+                            );
+                            if let Err(e) = act.peers.add(addresses) {
+                                error!("Error when adding peer addresses from DNS seed: {}", e);
+                            }
                         }
+                        Ok(Err(e)) => warn!("Error when resolving DNS seed {}: {}", host, e),
+                        Err(e) => error!("Unsuccessful communication with resolver: {}", e),
                     }
                     actix::fut::ok(())
                 })
                 .wait(ctx);
+        }
 
-            act.persist_peers(ctx, storage_peers_period);
+        // Schedule the next resolution of the seeds
+        ctx.run_later(dns_seeds_period, move |act, ctx| {
+            act.resolve_dns_seeds(ctx, dns_seeds, dns_seeds_period);
         });
     }
+
+    /// Method to discover the nodes in the local network with multicast DNS, adding their
+    /// addresses to the known peers, and to announce `server_addr` to them
+    ///
+    /// The mDNS socket is blocking, so it is handled in its own thread, which queries the local
+    /// network periodically and answers the queries of the other nodes.
+    fn discover_local_peers(server_addr: SocketAddr) {
+        let peers_manager_addr = System::current().registry().get::<PeersManager>();
+        // Random instance name, to recognize the announcements of this node
+        let instance = format!("witnet-{:08x}", thread_rng().gen::<u32>());
+
+        thread::spawn(move || {
+            let socket = match PeersManager::mdns_socket() {
+                Ok(socket) => socket,
+                Err(e) => {
+                    error!("Local peer discovery could not be started: {}", e);
+                    return;
+                }
+            };
+            let group = SocketAddr::new(IpAddr::V4(mdns::multicast_group()), mdns::MDNS_PORT);
+            let announcement = mdns::response(&instance, server_addr);
+            let mut last_query: Option<Instant> = None;
+            let mut buf = [0; MDNS_MAX_PACKET_LEN];
+
+            loop {
+                if last_query.map_or(true, |last| last.elapsed() >= MDNS_QUERY_PERIOD) {
+                    if let Err(e) = socket.send_to(&mdns::query(), group) {
+                        warn!("Error when sending mDNS query: {}", e);
+                    }
+                    last_query = Some(Instant::now());
+                }
+
+                let (len, source) = match socket.recv_from(&mut buf) {
+                    Ok(received) => received,
+                    Err(ref e)
+                        if e.kind() == io::ErrorKind::WouldBlock
+                            || e.kind() == io::ErrorKind::TimedOut =>
+                    {
+                        continue
+                    }
+                    Err(e) => {
+                        warn!("Error when receiving mDNS packet: {}", e);
+                        thread::sleep(MDNS_QUERY_PERIOD);
+                        continue;
+                    }
+                };
+                let packet = &buf[..len];
+
+                match mdns::is_query(packet, source.ip()) {
+                    Ok(true) => {
+                        if let Err(e) = socket.send_to(&announcement, group) {
+                            warn!("Error when sending mDNS response: {}", e);
+                        }
+                        continue;
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        debug!("Ignoring mDNS packet from {}: {}", source, e);
+                        continue;
+                    }
+                }
+
+                match mdns::parse_response(packet, source.ip()) {
+                    Ok(services) => {
+                        let addresses: Vec<_> = services
+                            .into_iter()
+                            .filter(|service| service.instance != instance)
+                            .map(|service| service.address)
+                            .collect();
+                        if !addresses.is_empty() {
+                            info!(
+                                "Adding the following peer addresses from the local network: {:?}",
+                                addresses
+                            );
+                            peers_manager_addr.do_send(AddPeers { addresses });
+                        }
+                    }
+                    Err(e) => debug!("Ignoring mDNS packet from {}: {}", source, e),
+                }
+            }
+        });
+    }
+
+    /// Method to bind the mDNS port, shared with the other mDNS responders of the host, and to
+    /// join the mDNS multicast group
+    fn mdns_socket() -> io::Result<UdpSocket> {
+        let builder = UdpBuilder::new_v4()?;
+        builder.reuse_address(true)?;
+        #[cfg(unix)]
+        {
+            use net2::unix::UnixUdpBuilderExt;
+            builder.reuse_port(true)?;
+        }
+        let socket = builder.bind((Ipv4Addr::new(0, 0, 0, 0), mdns::MDNS_PORT))?;
+        socket.join_multicast_v4(&mdns::multicast_group(), &Ipv4Addr::new(0, 0, 0, 0))?;
+        socket.set_multicast_loop_v4(true)?;
+        // Wake up periodically to send the queries
+        socket.set_read_timeout(Some(MDNS_QUERY_PERIOD))?;
+
+        Ok(socket)
+    }
 }
 
 /// Required traits for being able to retrieve SessionsManager address from registry
@@ -0,0 +1,73 @@
+use super::cors::{allow_origin, check_origin};
+use super::http_codec::{HttpCodec, HttpRequest};
+use super::routes::{handle, route};
+use super::server::{RestServer, Unregister};
+use actix::{
+    io::FramedWrite, io::WriteHandler, Actor, ActorFuture, Addr, AsyncContext, Context, Running,
+    StreamHandler, WrapFuture,
+};
+use futures::future::{self, Either};
+use log::*;
+use std::io;
+use std::rc::Rc;
+use tokio::io::WriteHalf;
+use tokio::net::TcpStream;
+use witnet_config::config::Cors;
+
+/// A single HTTP connection with a client of the REST interface
+pub struct RestConnection {
+    /// Stream
+    pub framed: FramedWrite<WriteHalf<TcpStream>, HttpCodec>,
+    /// Reference to parent
+    // Needed to send the `Unregister` message when the connection closes
+    pub parent: Addr<RestServer>,
+    /// Origins of the web pages allowed to read the responses
+    pub cors: Rc<Cors>,
+}
+
+impl Actor for RestConnection {
+    type Context = Context<Self>;
+
+    /// Method to be executed when the actor is stopping
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        // Unregister connection from RestServer
+        self.parent.do_send(Unregister {
+            addr: ctx.address(),
+        });
+
+        Running::Stop
+    }
+}
+
+impl WriteHandler<io::Error> for RestConnection {}
+
+/// Implement `StreamHandler` trait in order to use `Framed` with an actor
+impl StreamHandler<HttpRequest, io::Error> for RestConnection {
+    /// This is main event loop for client requests
+    fn handle(&mut self, request: HttpRequest, ctx: &mut Self::Context) {
+        debug!("Got REST request: {} {}", request.method, request.path);
+        let keep_alive = request.keep_alive;
+        let response = match check_origin(&request, &self.cors).and_then(|()| route(&request)) {
+            Ok(route) => Either::A(handle(route)),
+            Err(response) => Either::B(future::ok(response)),
+        };
+        // The web page of the request can read the response only if its origin is allowed
+        let origin = request.origin.filter(|origin| self.cors.allows(origin));
+
+        // Wait for the response before handling the next request, so the responses are sent in
+        // the same order as the requests
+        let response = response
+            .into_actor(self)
+            .map(move |mut response, act, _ctx| {
+                response.keep_alive = keep_alive;
+                if let Some(ref origin) = origin {
+                    allow_origin(&mut response, origin);
+                }
+                act.framed.write(response);
+                if !keep_alive {
+                    act.framed.close();
+                }
+            });
+        ctx.wait(response);
+    }
+}
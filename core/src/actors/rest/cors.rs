@@ -0,0 +1,130 @@
+use super::http_codec::{HttpRequest, HttpResponse};
+use super::routes::error_response;
+use witnet_config::config::Cors;
+
+/// Seconds during which browsers can cache the answer to a preflight request
+const PREFLIGHT_MAX_AGE: u32 = 600;
+
+/// Answer right away the requests from web pages whose origin is not allowed, and the preflight
+/// requests made by browsers before a cross-origin request. Requests without an origin do not come
+/// from a web page and are always let through.
+pub fn check_origin(request: &HttpRequest, cors: &Cors) -> Result<(), HttpResponse> {
+    match request.origin {
+        Some(ref origin) if !cors.allows(origin) => Err(error_response(403, "Origin not allowed")),
+        Some(_) if request.method == "OPTIONS" => {
+            let mut response = HttpResponse::new(204, String::new());
+            response.headers = vec![
+                (
+                    "Access-Control-Allow-Methods".to_string(),
+                    "GET".to_string(),
+                ),
+                (
+                    "Access-Control-Max-Age".to_string(),
+                    PREFLIGHT_MAX_AGE.to_string(),
+                ),
+            ];
+            Err(response)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Let the web page of the given origin read the response. The origin is sent back instead of
+/// `*`, so caches have to tell the responses apart by origin
+pub fn allow_origin(response: &mut HttpResponse, origin: &str) {
+    response.headers.push((
+        "Access-Control-Allow-Origin".to_string(),
+        origin.to_string(),
+    ));
+    response
+        .headers
+        .push(("Vary".to_string(), "Origin".to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, origin: Option<&str>) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            path: "/blocks".to_string(),
+            origin: origin.map(ToString::to_string),
+            keep_alive: true,
+        }
+    }
+
+    fn cors() -> Cors {
+        Cors {
+            allowed_origins: vec!["http://localhost:8080".to_string()],
+        }
+    }
+
+    #[test]
+    fn requests_without_origin() {
+        assert_eq!(
+            check_origin(&request("GET", None), &Cors::default()),
+            Ok(())
+        );
+        // Preflight requests always have an origin, so this one is routed as any other
+        assert_eq!(check_origin(&request("OPTIONS", None), &cors()), Ok(()));
+    }
+
+    #[test]
+    fn requests_from_web_pages() {
+        assert_eq!(
+            check_origin(&request("GET", Some("http://localhost:8080")), &cors()),
+            Ok(())
+        );
+        assert_eq!(
+            check_origin(&request("GET", Some("http://localhost:3000")), &cors())
+                .unwrap_err()
+                .status,
+            403
+        );
+        assert_eq!(
+            check_origin(
+                &request("GET", Some("http://localhost:8080")),
+                &Cors::default()
+            )
+            .unwrap_err()
+            .status,
+            403
+        );
+    }
+
+    #[test]
+    fn preflight_requests() {
+        let response =
+            check_origin(&request("OPTIONS", Some("http://localhost:8080")), &cors()).unwrap_err();
+        assert_eq!(response.status, 204);
+        assert!(response.body.is_empty());
+        assert!(response.headers.contains(&(
+            "Access-Control-Allow-Methods".to_string(),
+            "GET".to_string()
+        )));
+
+        assert_eq!(
+            check_origin(&request("OPTIONS", Some("http://localhost:3000")), &cors())
+                .unwrap_err()
+                .status,
+            403
+        );
+    }
+
+    #[test]
+    fn allow_origin_headers() {
+        let mut response = HttpResponse::new(200, "{}".to_string());
+        allow_origin(&mut response, "http://localhost:8080");
+        assert_eq!(
+            response.headers,
+            vec![
+                (
+                    "Access-Control-Allow-Origin".to_string(),
+                    "http://localhost:8080".to_string()
+                ),
+                ("Vary".to_string(), "Origin".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use bytes::BytesMut;
+use std::io;
+use tokio::codec::{Decoder, Encoder};
+
+/// Maximum size of the request line and the headers of a request
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// Maximum size of the body of a request. The REST endpoints take no body, so it is discarded
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// HTTP request, without its body
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct HttpRequest {
+    /// Method, e.g.: `GET`
+    pub method: String,
+    /// Path of the target, without the query string
+    pub path: String,
+    /// Value of the `Origin` header, if sent by a browser
+    pub origin: Option<String>,
+    /// Whether the connection is kept open after the response
+    pub keep_alive: bool,
+}
+
+/// HTTP response with a JSON body
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct HttpResponse {
+    /// Status code
+    pub status: u16,
+    /// Additional headers
+    pub headers: Vec<(String, String)>,
+    /// JSON body
+    pub body: String,
+    /// Whether the connection is kept open after the response
+    pub keep_alive: bool,
+}
+
+impl HttpResponse {
+    /// Response with the given status and JSON body
+    pub fn new(status: u16, body: String) -> Self {
+        HttpResponse {
+            status,
+            headers: vec![],
+            body,
+            keep_alive: true,
+        }
+    }
+
+    /// Reason phrase of the status code
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            429 => "Too Many Requests",
+            501 => "Not Implemented",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Codec for the HTTP/1.1 transport of the REST interface
+///
+/// Only the request line and the headers are decoded. The body of a request, if any, is read and
+/// discarded, and chunked bodies are not supported.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct HttpCodec {
+    /// Bytes of the body of the last request still to be discarded
+    discard: usize,
+}
+
+/// Implement decoder trait for HttpCodec
+impl Decoder for HttpCodec {
+    type Item = HttpRequest;
+    type Error = io::Error;
+
+    /// Method to decode bytes to a request
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.discard > 0 {
+            let discarded = self.discard.min(src.len());
+            src.advance(discarded);
+            self.discard -= discarded;
+            if self.discard > 0 {
+                return Ok(None);
+            }
+        }
+
+        let end = match src.windows(4).position(|window| window == b"\r\n\r\n") {
+            Some(end) => end,
+            None if src.len() > MAX_HEAD_SIZE => return Err(invalid_data("Request too big")),
+            None => return Ok(None),
+        };
+        let head = src.split_to(end + 4);
+        let head =
+            std::str::from_utf8(&head).map_err(|_| invalid_data("Request is not valid UTF-8"))?;
+
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or("").split(' ');
+        let (method, target, version) = match (
+            request_line.next(),
+            request_line.next(),
+            request_line.next(),
+        ) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+                (method, target, version)
+            }
+            _ => return Err(invalid_data("Invalid request line")),
+        };
+
+        // HTTP/1.1 connections are kept open unless the client asks otherwise
+        let mut keep_alive = version != "HTTP/1.0";
+        let mut origin = None;
+        let mut content_length = 0;
+        for line in lines {
+            let mut header = line.splitn(2, ':');
+            let (name, value) = match (header.next(), header.next()) {
+                (Some(name), Some(value)) => (name.trim(), value.trim()),
+                _ => continue,
+            };
+            if name.eq_ignore_ascii_case("Connection") {
+                if value.eq_ignore_ascii_case("close") {
+                    keep_alive = false;
+                } else if value.eq_ignore_ascii_case("keep-alive") {
+                    keep_alive = true;
+                }
+            } else if name.eq_ignore_ascii_case("Origin") {
+                origin = Some(value.to_string());
+            } else if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value
+                    .parse()
+                    .map_err(|_| invalid_data("Invalid Content-Length"))?;
+            } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+                return Err(invalid_data("Transfer-Encoding is not supported"));
+            }
+        }
+        if content_length > MAX_BODY_SIZE {
+            return Err(invalid_data("Body too big"));
+        }
+        let discarded = content_length.min(src.len());
+        src.advance(discarded);
+        self.discard = content_length - discarded;
+
+        let path = target.split('?').next().unwrap_or("").to_string();
+
+        Ok(Some(HttpRequest {
+            method: method.to_string(),
+            path,
+            origin,
+            keep_alive,
+        }))
+    }
+}
+
+/// Implement encoder trait for HttpCodec
+impl Encoder for HttpCodec {
+    type Item = HttpResponse;
+    type Error = io::Error;
+
+    /// Method to encode a response into bytes
+    fn encode(&mut self, response: HttpResponse, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+            response.status,
+            response.reason(),
+            response.body.len()
+        );
+        for (name, value) in &response.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if !response.keep_alive {
+            head.push_str("Connection: close\r\n");
+        }
+        head.push_str("\r\n");
+
+        dst.extend_from_slice(head.as_bytes());
+        dst.extend_from_slice(response.body.as_bytes());
+
+        Ok(())
+    }
+}
+
+/// Error for the bytes that do not follow the HTTP protocol
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_request() {
+        let mut input = BytesMut::from(
+            &b"GET /blocks/00?full=true HTTP/1.1\r\nHost: 127.0.0.1\r\nOrigin: http://localhost:8080\r\n\r\nGET"[..],
+        );
+        let mut codec = HttpCodec::default();
+
+        assert_eq!(
+            codec.decode(&mut input).unwrap(),
+            Some(HttpRequest {
+                method: "GET".to_string(),
+                path: "/blocks/00".to_string(),
+                origin: Some("http://localhost:8080".to_string()),
+                keep_alive: true,
+            })
+        );
+        // The next request is incomplete
+        assert_eq!(codec.decode(&mut input).unwrap(), None);
+        assert_eq!(&input[..], b"GET");
+    }
+
+    #[test]
+    fn decode_connection_close() {
+        let mut input = BytesMut::from(&b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n"[..]);
+        let mut codec = HttpCodec::default();
+        assert!(!codec.decode(&mut input).unwrap().unwrap().keep_alive);
+
+        let mut input = BytesMut::from(&b"GET / HTTP/1.0\r\n\r\n"[..]);
+        assert!(!codec.decode(&mut input).unwrap().unwrap().keep_alive);
+    }
+
+    #[test]
+    fn discard_body() {
+        let mut codec = HttpCodec::default();
+        let mut input =
+            BytesMut::from(&b"POST /blocks HTTP/1.1\r\nContent-Length: 10\r\n\r\n01234"[..]);
+        assert_eq!(codec.decode(&mut input).unwrap().unwrap().method, "POST");
+        assert!(input.is_empty());
+
+        // The rest of the body is discarded when it arrives
+        input.extend_from_slice(b"56789GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(codec.decode(&mut input).unwrap().unwrap().path, "/");
+    }
+
+    #[test]
+    fn reject_invalid_requests() {
+        let mut codec = HttpCodec::default();
+        let mut input = BytesMut::from(&b"hello\r\n\r\n"[..]);
+        assert!(codec.decode(&mut input).is_err());
+
+        let mut input = BytesMut::from(vec![b'a'; MAX_HEAD_SIZE + 1]);
+        assert!(codec.decode(&mut input).is_err());
+
+        let mut input =
+            BytesMut::from(&b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"[..]);
+        assert!(codec.decode(&mut input).is_err());
+    }
+
+    #[test]
+    fn encode_response() {
+        let mut codec = HttpCodec::default();
+        let mut buf = BytesMut::new();
+        let mut response = HttpResponse::new(404, r#"{"error":"Not found"}"#.to_string());
+        response.keep_alive = false;
+        codec.encode(response, &mut buf).unwrap();
+
+        assert_eq!(
+            &buf[..],
+            &b"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 21\r\nConnection: close\r\n\r\n{\"error\":\"Not found\"}"[..]
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! # REST server
+//!
+//! Optional HTTP server exposing read-only endpoints for the integrators which cannot speak
+//! JSON-RPC, enabled with `rest.enabled`. Each request is translated into a message to the actor
+//! holding the data, and answered with its JSON serialization:
+//!
+//! * `GET /blocks/{hash}`: a block with its transactions, from the `BlocksManager`.
+//! * `GET /transactions/{hash}`: a transaction, from the `StorageManager`.
+//! * `GET /address/{address}/utxos`: the unspent outputs of an address, not available until the
+//! node keeps the UTXO set.
+//!
+//! Web pages can only read the responses if their origin is listed in `rest.cors.allowed_origins`.
+mod connection;
+mod cors;
+mod http_codec;
+/// Endpoints of the REST interface
+pub mod routes;
+mod server;
+
+pub use self::server::RestServer;
@@ -0,0 +1,224 @@
+#[cfg(test)]
+use self::mock_actix::System;
+use super::http_codec::{HttpRequest, HttpResponse};
+use crate::actors::blocks_manager::{messages::GetBlock, BlocksManager, BlocksManagerError};
+use crate::actors::storage_keys::transaction_key;
+use crate::actors::storage_manager::{messages::Get, StorageManager};
+#[cfg(not(test))]
+use actix::System;
+use futures::{future, Future};
+use serde::Serialize;
+use serde_json::json;
+use std::fmt;
+use witnet_data_structures::chain::{Hash, Transaction};
+
+/// Response to a request, built once the actors answer
+pub type RestResponseAsync = Box<dyn Future<Item = HttpResponse, Error = ()>>;
+
+/// Endpoints of the REST interface
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Route {
+    /// `GET /blocks/{hash}`: a block with its transactions
+    Block(Hash),
+    /// `GET /transactions/{hash}`: a transaction
+    Transaction(Hash),
+    /// `GET /address/{address}/utxos`: the unspent outputs of an address
+    AddressUtxos(String),
+}
+
+/// Find the endpoint of a request, or the error response if there is none
+pub fn route(request: &HttpRequest) -> Result<Route, HttpResponse> {
+    let segments: Vec<&str> = request
+        .path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let route = match segments.as_slice() {
+        ["blocks", hash] => Route::Block(parse_hash(hash)?),
+        ["transactions", hash] => Route::Transaction(parse_hash(hash)?),
+        ["address", address, "utxos"] => Route::AddressUtxos(address.to_string()),
+        _ => return Err(error_response(404, "Not found")),
+    };
+    if request.method != "GET" {
+        return Err(error_response(405, "Only GET requests are supported"));
+    }
+
+    Ok(route)
+}
+
+/// Answer a request to an endpoint, asking the actors which hold the data
+pub fn handle(route: Route) -> RestResponseAsync {
+    match route {
+        Route::Block(hash) => {
+            let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+
+            Box::new(
+                blocks_manager_addr
+                    .send(GetBlock { hash })
+                    .then(|response| match response {
+                        Ok(Ok(block)) => Ok(json_response(&block)),
+                        Ok(Err(BlocksManagerError::BlockDoesNotExist)) => {
+                            Ok(error_response(404, "Block not found"))
+                        }
+                        Ok(Err(BlocksManagerError::BlockPruned)) => {
+                            Ok(error_response(404, "Block pruned, only its header is kept"))
+                        }
+                        Ok(Err(e)) => Ok(internal_error(format!("{:?}", e))),
+                        Err(e) => Ok(internal_error(e)),
+                    }),
+            )
+        }
+        Route::Transaction(hash) => {
+            let storage_manager_addr = System::current().registry().get::<StorageManager>();
+
+            Box::new(
+                storage_manager_addr
+                    .send(Get::<Transaction>::new(transaction_key(&hash)))
+                    .then(|response| match response {
+                        Ok(Ok(Some(transaction))) => Ok(json_response(&transaction)),
+                        Ok(Ok(None)) => Ok(error_response(404, "Transaction not found")),
+                        Ok(Err(e)) => Ok(internal_error(e)),
+                        Err(e) => Ok(internal_error(e)),
+                    }),
+            )
+        }
+        Route::AddressUtxos(_) => Box::new(future::ok(error_response(
+            501,
+            "The node does not keep the UTXO set yet",
+        ))),
+    }
+}
+
+/// Response with the JSON serialization of a value
+fn json_response<T: Serialize>(value: &T) -> HttpResponse {
+    match serde_json::to_string(value) {
+        Ok(body) => HttpResponse::new(200, body),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Response with the given status and an error message
+pub fn error_response(status: u16, message: &str) -> HttpResponse {
+    HttpResponse::new(status, json!({ "error": message }).to_string())
+}
+
+/// Internal error with the given message
+fn internal_error<E: fmt::Display>(e: E) -> HttpResponse {
+    error_response(500, &e.to_string())
+}
+
+/// Parse a SHA256 hash written in hexadecimal
+fn parse_hash(hex: &str) -> Result<Hash, HttpResponse> {
+    let invalid_hash = || error_response(400, "Hashes must be 64 hexadecimal digits");
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid_hash());
+    }
+
+    let mut hash = [0; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid_hash())?;
+    }
+
+    Ok(Hash::SHA256(hash))
+}
+
+#[cfg(test)]
+mod mock_actix {
+    pub struct System;
+
+    pub struct SystemRegistry;
+
+    pub struct Addr;
+
+    impl System {
+        pub fn current() -> Self {
+            System
+        }
+        pub fn registry(&self) -> &SystemRegistry {
+            &SystemRegistry
+        }
+    }
+
+    impl SystemRegistry {
+        pub fn get<T>(&self) -> Addr {
+            Addr
+        }
+    }
+
+    impl Addr {
+        pub fn send<M: actix::Message>(
+            &self,
+            _msg: M,
+        ) -> futures::future::Empty<M::Result, actix::MailboxError> {
+            futures::future::empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(path: &str) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            origin: None,
+            keep_alive: true,
+        }
+    }
+
+    #[test]
+    fn route_endpoints() {
+        let hex = "0102030405060708091011121314151617181920212223242526272829303132";
+        let mut hash = [0; 32];
+        for (i, byte) in hash.iter_mut().enumerate() {
+            *byte = ((i + 1) / 10 * 16 + (i + 1) % 10) as u8;
+        }
+
+        assert_eq!(
+            route(&get(&format!("/blocks/{}", hex))),
+            Ok(Route::Block(Hash::SHA256(hash)))
+        );
+        assert_eq!(
+            route(&get(&format!("/transactions/{}/", hex.to_uppercase()))),
+            Ok(Route::Transaction(Hash::SHA256(hash)))
+        );
+        assert_eq!(
+            route(&get("/address/twit1abc/utxos")),
+            Ok(Route::AddressUtxos("twit1abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn route_errors() {
+        assert_eq!(route(&get("/blocks")).unwrap_err().status, 404);
+        assert_eq!(route(&get("/blocks/xyz")).unwrap_err().status, 400);
+        assert_eq!(
+            route(&get(&format!("/blocks/{}", "g".repeat(64))))
+                .unwrap_err()
+                .status,
+            400
+        );
+
+        let mut request = get(&format!("/blocks/{}", "0".repeat(64)));
+        request.method = "POST".to_string();
+        assert_eq!(route(&request).unwrap_err().status, 405);
+    }
+
+    #[test]
+    fn utxos_not_implemented() {
+        let response = handle(Route::AddressUtxos("twit1abc".to_string()))
+            .wait()
+            .unwrap();
+        assert_eq!(response.status, 501);
+    }
+
+    #[test]
+    fn error_json() {
+        assert_eq!(
+            error_response(404, "Block not found").body,
+            r#"{"error":"Block not found"}"#
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use super::connection::RestConnection;
+use super::http_codec::HttpCodec;
+use crate::actors::config_manager::send_get_config_request;
+use crate::actors::connections_manager::messages::InboundTcpConnect;
+use actix::{
+    io::FramedWrite, Actor, Addr, AsyncContext, Context, Handler, Message, StreamHandler,
+    Supervised, SystemService,
+};
+use futures::Stream;
+use log::*;
+use std::collections::HashSet;
+use std::io;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::{codec::FramedRead, io::AsyncRead};
+use witnet_config::config::{Config, Cors};
+
+/// REST server
+#[derive(Default)]
+pub struct RestServer {
+    /// Open connections, stored as instances of the `RestConnection` actor
+    open_connections: HashSet<Addr<RestConnection>>,
+    /// Origins of the web pages allowed to read the responses
+    cors: Rc<Cors>,
+}
+
+impl RestServer {
+    /// Method to process the configuration received from ConfigManager
+    fn process_config(&mut self, ctx: &mut <Self as Actor>::Context, config: &Config) {
+        // Do not start the server unless enabled = true
+        if !config.rest.enabled {
+            debug!("REST interface disabled by configuration.");
+            return;
+        }
+
+        self.cors = Rc::new(config.rest.cors.clone());
+
+        if let Err(e) = self.start_listening(ctx, config.rest.server_address) {
+            // Shutdown the entire system on error
+            // For example, when the server_address is already in use
+            error!("Could not start REST server: {:?}", e);
+            panic!("Could not start REST server: {:?}", e);
+        }
+    }
+
+    /// Start accepting connections
+    fn start_listening(
+        &mut self,
+        ctx: &mut <Self as Actor>::Context,
+        server_address: SocketAddr,
+    ) -> io::Result<()> {
+        debug!("Starting REST interface.");
+        let listener = TcpListener::bind(&server_address)?;
+
+        // Add stream which will return a InboundTcpConnect for each incoming TCP connection
+        ctx.add_stream(
+            listener
+                .incoming()
+                .map_err(|_| ())
+                .map(InboundTcpConnect::new),
+        );
+
+        debug!("REST interface is now running at {}", server_address);
+
+        Ok(())
+    }
+
+    fn add_connection(&mut self, parent: Addr<RestServer>, stream: TcpStream) {
+        debug!(
+            "Add REST connection (currently {} open connections)",
+            1 + self.open_connections.len()
+        );
+
+        // Create a new `RestConnection` actor which will listen to this stream
+        let cors = Rc::clone(&self.cors);
+        let addr = RestConnection::create(|ctx| {
+            let (r, w) = stream.split();
+            RestConnection::add_stream(FramedRead::new(r, HttpCodec::default()), ctx);
+            RestConnection {
+                framed: FramedWrite::new(w, HttpCodec::default(), ctx),
+                parent,
+                cors,
+            }
+        });
+
+        // Store the actor address
+        self.open_connections.insert(addr);
+    }
+}
+
+impl Actor for RestServer {
+    type Context = Context<Self>;
+
+    /// Method to be executed when the actor is started
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // Send message to config manager and process its response
+        send_get_config_request(self, ctx, RestServer::process_config);
+    }
+}
+
+/// Stream handler for InboundTcpConnect messages (built from inbound connections)
+impl StreamHandler<InboundTcpConnect, ()> for RestServer {
+    /// Method to handle the InboundTcpConnect message
+    fn handle(&mut self, msg: InboundTcpConnect, ctx: &mut Self::Context) {
+        self.add_connection(ctx.address(), msg.stream);
+    }
+
+    /// Keep the server running when the listener stops
+    fn finished(&mut self, _ctx: &mut Self::Context) {}
+}
+
+#[derive(Message)]
+/// Unregister a closed connection from the list of open connections
+pub struct Unregister {
+    pub addr: Addr<RestConnection>,
+}
+
+impl Handler<Unregister> for RestServer {
+    type Result = ();
+
+    /// Method to remove a finished connection
+    fn handle(&mut self, msg: Unregister, _ctx: &mut Context<Self>) -> Self::Result {
+        self.open_connections.remove(&msg.addr);
+    }
+}
+
+/// Required traits for being able to retrieve the actor address from
+/// the registry.
+impl Supervised for RestServer {}
+
+impl SystemService for RestServer {}
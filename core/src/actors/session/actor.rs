@@ -1,18 +1,28 @@
 use actix::{
-    Actor, ActorContext, ActorFuture, AsyncContext, Context, ContextFutureSpawner, Running, System,
-    WrapFuture,
+    Actor, ActorFuture, AsyncContext, Context, ContextFutureSpawner, Running, System, WrapFuture,
 };
 use log::{debug, error, info};
+use std::time::Duration;
 
-use crate::actors::sessions_manager::{
-    messages::{Register, Unregister},
-    SessionsManager,
+use crate::actors::{
+    blocks_manager::{messages::ReleaseBlocks, BlocksManager},
+    peers_manager::{messages::UpdateReputation, PeersManager},
+    sessions_manager::{
+        messages::{Register, Unregister},
+        SessionsManager,
+    },
 };
 
 use witnet_data_structures::types::Message as WitnetMessage;
-use witnet_p2p::sessions::{SessionStatus, SessionType};
+use witnet_p2p::{
+    peers::Reputation,
+    sessions::{events::DisconnectReason, SessionStatus, SessionType},
+};
 
-use super::Session;
+use super::{
+    handlers::{check_response_timeouts, heartbeat_keepalive},
+    Session,
+};
 
 /// Implement actor trait for Session
 impl Actor for Session {
@@ -29,11 +39,34 @@ impl Actor for Session {
                     act.remote_addr
                 );
                 if let SessionStatus::Unconsolidated = act.status {
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::HandshakeTimeout);
                 }
             }
         });
 
+        // Reset the count of received messages every second, to detect the peers spamming
+        ctx.run_interval(Duration::from_secs(1), |act, _ctx| {
+            act.received_messages = 0;
+        });
+
+        // Check every second whether the consolidated session is idle and must be pinged, or
+        // whether the peer did not answer a ping in time, unless the pings are disabled
+        if self.keepalive.period.as_secs() > 0 {
+            ctx.run_interval(Duration::from_secs(1), |act, ctx| {
+                if let SessionStatus::Consolidated = act.status {
+                    heartbeat_keepalive(act, ctx);
+                }
+            });
+        }
+
+        // Check every second whether the peer did not answer a request in time, unless the
+        // response timeouts are disabled
+        if self.response_timeouts.get_peers.as_secs() > 0
+            || self.response_timeouts.get_headers.as_secs() > 0
+        {
+            ctx.run_interval(Duration::from_secs(1), check_response_timeouts);
+        }
+
         // Get SessionsManager address
         let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
 
@@ -47,7 +80,7 @@ impl Actor for Session {
                 session_type: self.session_type,
             })
             .into_actor(self)
-            .then(|res, _act, ctx| {
+            .then(|res, act, ctx| {
                 match res {
                     Ok(Ok(_)) => {
                         debug!("Session successfully registered into the Session Manager");
@@ -58,21 +91,30 @@ impl Actor for Session {
                         error!("Session register into Session Manager failed");
                         // FIXME(#72): a full stop of the session is not correct (unregister should
                         // be skipped)
-                        ctx.stop();
+                        act.disconnect(ctx, DisconnectReason::Error);
 
                         actix::fut::err(())
                     }
                 }
             })
             .and_then(|_, act, _ctx| {
-                // Send version if outbound session
-                if let SessionType::Outbound = act.session_type {
-                    // FIXME(#142): include the checkpoint of the current tip of the local blockchain
-                    let version_msg =
-                        WitnetMessage::build_version(act.server_addr, act.remote_addr, 0);
-                    act.send_message(version_msg);
-                    // Set HandshakeFlag of sent version message
-                    act.handshake_flags.version_tx = true;
+                // Send version if outbound or feeler session
+                match act.session_type {
+                    SessionType::Outbound | SessionType::Feeler => {
+                        // FIXME(#142): include the checkpoint of the current tip of the local
+                        // blockchain
+                        let version_msg = WitnetMessage::build_version(
+                            act.server_addr,
+                            act.remote_addr,
+                            0,
+                            act.features,
+                            act.network.genesis,
+                        );
+                        act.send_message(version_msg);
+                        // Set HandshakeFlag of sent version message
+                        act.handshake_flags.version_tx = true;
+                    }
+                    SessionType::Inbound => {}
                 }
 
                 actix::fut::ok(())
@@ -90,8 +132,34 @@ impl Actor for Session {
             address: self.remote_addr,
             session_type: self.session_type,
             status: self.status,
+            reason: self
+                .disconnect_reason
+                .unwrap_or(DisconnectReason::ClosedByPeer),
         });
 
+        // Record the uptime and the useful blocks of the peer in its reputation. Only outbound
+        // sessions are recorded, as the remote address of an inbound session is not the address
+        // of the peer's server
+        if let (SessionType::Outbound, Some(consolidated_at)) =
+            (self.session_type, self.consolidated_at)
+        {
+            // The blocks requested to the peer and not received are requested to other peers
+            let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+            blocks_manager_addr.do_send(ReleaseBlocks {
+                address: self.remote_addr,
+            });
+
+            let peers_manager_addr = System::current().registry().get::<PeersManager>();
+            peers_manager_addr.do_send(UpdateReputation {
+                address: self.remote_addr,
+                update: Reputation {
+                    uptime: consolidated_at.elapsed().as_secs(),
+                    useful_blocks: self.useful_blocks,
+                    misbehavior: 0,
+                },
+            });
+        }
+
         Running::Stop
     }
 }
@@ -2,34 +2,56 @@ use std::io::Error;
 
 use actix::io::WriteHandler;
 use actix::{
-    ActorContext, ActorFuture, Context, ContextFutureSpawner, Handler, StreamHandler, System,
-    WrapFuture,
+    ActorFuture, Context, ContextFutureSpawner, Handler, Running, StreamHandler, System, WrapFuture,
 };
 
 use log::{debug, error, info, warn};
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
 
 use crate::actors::{
     blocks_manager::{
-        messages::{AddNewBlock, GetBlocksEpochRange, GetHighestCheckpointBeacon},
-        BlocksManager,
+        block_hash,
+        headers::{HeadersError, MAX_HEADERS_PER_MESSAGE},
+        messages::{
+            AddHeaders, AddNewBlock, AssignBlocks, GetBlocksEpochRange, GetHeadersAfter,
+            GetHeadersBeacon, GetHighestCheckpointBeacon,
+        },
+        BlocksManager, BlocksManagerError,
     },
     codec::BytesMut,
-    peers_manager,
-    sessions_manager::{messages::Consolidate, SessionsManager},
+    peers_manager::{self, messages::GetReputation, PeersManager},
+    sessions_manager::{
+        messages::{Consolidate, ReportMisbehavior, SetLatency},
+        SessionsManager,
+    },
     storage_manager::{messages::Get, StorageManager},
 };
 
 use super::{
-    messages::{AnnounceItems, GetPeers, SessionUnitResult},
-    Session,
+    messages::{
+        AnnounceItems, CloseSession, GetPeers, RequestBlocks, RequestHeaders, SessionUnitResult,
+    },
+    Session, MAX_MESSAGES_PER_SECOND,
 };
 use witnet_data_structures::{
-    builders::from_address,
-    chain::{Block, CheckpointBeacon, Hash, InvVector},
+    builders::{from_address, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+    chain::{Block, CheckpointBeacon, Hash, HashedBlockHeader, InvVector},
     serializers::TryFrom,
-    types::{Address, Command, GetBlocks, GetData, Inv, Message as WitnetMessage, Peers, Version},
+    types::{
+        Address, Command, GetBlocks, GetData, GetHeaders, Headers, Inv, Message as WitnetMessage,
+        Peers, Ping, Pong, Version,
+    },
+};
+use witnet_p2p::{
+    bans::Misbehavior,
+    compression,
+    limits::SizedMessage,
+    peers::MAX_PEERS_PER_MESSAGE,
+    rate_limits::LimitedMessage,
+    relay::ItemOrigin,
+    sessions::{capabilities::Capabilities, events::DisconnectReason, SessionStatus, SessionType},
 };
-use witnet_p2p::sessions::{SessionStatus, SessionType};
 
 /// Implement WriteHandler for Session
 impl WriteHandler<Error> for Session {}
@@ -38,29 +60,90 @@ impl WriteHandler<Error> for Session {}
 impl StreamHandler<BytesMut, Error> for Session {
     /// This is main event loop for client requests
     fn handle(&mut self, bytes: BytesMut, ctx: &mut Self::Context) {
-        let result = WitnetMessage::try_from(bytes.to_vec());
+        // Any message shows that the connection is alive
+        self.last_received = Instant::now();
+
+        // Report the peer once per second if it sends too many messages
+        self.received_messages += 1;
+        if self.received_messages == MAX_MESSAGES_PER_SECOND + 1 {
+            warn!("Too many messages received from peer {}", self.remote_addr);
+            report_misbehavior(self, ctx, Misbehavior::Spam);
+        }
+
+        // Decompress the message if the compression was negotiated
+        let bytes = if self.is_compression_enabled() {
+            match compression::decompress(&bytes, self.message_limits.max_message_size) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    error!("Error decompressing message: {}", err);
+                    report_misbehavior(self, ctx, Misbehavior::InvalidMessage);
+                    return;
+                }
+            }
+        } else {
+            bytes.to_vec()
+        };
+
+        let size = bytes.len();
+        let result = WitnetMessage::try_from(bytes);
         match result {
-            Err(err) => error!("Error decoding message: {:?}", err),
+            Err(err) => {
+                error!("Error decoding message: {:?}", err);
+                report_misbehavior(self, ctx, Misbehavior::InvalidMessage);
+            }
+            // Close the session with the peers of other networks
+            Ok(ref msg) if msg.magic != self.network.magic => {
+                warn!(
+                    "Peer {} uses the magic number {:#06x}, which is not the magic number {:#06x} \
+                     of this network",
+                    self.remote_addr, msg.magic, self.network.magic
+                );
+                self.disconnect(ctx, DisconnectReason::Incompatible);
+            }
             Ok(msg) => {
                 info!(
                     "<----- Session ({}) received message: {}",
                     self.remote_addr, msg.kind
                 );
+                // Drop the messages over the size limit of their kind, penalizing the peer
+                let sized = sized_message(&msg.kind);
+                if !self.message_limits.allows(sized, size) {
+                    warn!(
+                        "Peer {} sent a message of {} bytes, over the limit of {} bytes",
+                        self.remote_addr,
+                        size,
+                        self.message_limits.max_size(sized)
+                    );
+                    report_misbehavior(self, ctx, Misbehavior::OversizedMessage);
+                    return;
+                }
+                // Drop the messages over the rate limit of their kind, penalizing the peer
+                if let Some(kind) = limited_message(&msg.kind) {
+                    if !self.rate_limiter.check(kind, Instant::now()) {
+                        warn!(
+                            "Peer {} exceeded the rate limit of {:?} messages",
+                            self.remote_addr, kind
+                        );
+                        report_misbehavior(self, ctx, Misbehavior::RateLimitExceeded);
+                        return;
+                    }
+                }
                 match (self.session_type, self.status, msg.kind) {
                     ////////////////////
                     //   HANDSHAKE    //
                     ////////////////////
                     // Handle Version message
-                    (
-                        _,
-                        SessionStatus::Unconsolidated,
-                        Command::Version(Version { sender_address, .. }),
-                    ) => {
-                        let msgs = handshake_version(self, &sender_address);
-                        for msg in msgs {
-                            self.send_message(msg);
+                    (_, SessionStatus::Unconsolidated, Command::Version(version)) => {
+                        match handshake_version(self, &version) {
+                            Some(msgs) => {
+                                for msg in msgs {
+                                    self.send_message(msg);
+                                }
+                                try_consolidate_session(self, ctx);
+                            }
+                            // The session cannot be established with the peer
+                            None => self.disconnect(ctx, DisconnectReason::Incompatible),
                         }
-                        try_consolidate_session(self, ctx);
                     }
                     // Handler Verack message
                     (_, SessionStatus::Unconsolidated, Command::Verack(_)) => {
@@ -68,6 +151,17 @@ impl StreamHandler<BytesMut, Error> for Session {
                         try_consolidate_session(self, ctx);
                     }
                     ////////////////////
+                    //   HEARTBEAT    //
+                    ////////////////////
+                    // Handle Ping message
+                    (_, SessionStatus::Consolidated, Command::Ping(Ping { nonce })) => {
+                        self.send_message(WitnetMessage::build_pong(nonce));
+                    }
+                    // Handle Pong message
+                    (_, SessionStatus::Consolidated, Command::Pong(Pong { nonce })) => {
+                        heartbeat_pong(self, nonce);
+                    }
+                    ////////////////////
                     // PEER DISCOVERY //
                     ////////////////////
                     // Handle GetPeers message
@@ -80,7 +174,7 @@ impl StreamHandler<BytesMut, Error> for Session {
                         SessionStatus::Consolidated,
                         Command::Peers(Peers { peers }),
                     ) => {
-                        peer_discovery_peers(&peers);
+                        peer_discovery_peers(self, ctx, &peers);
                     }
                     //////////////
                     // GET DATA //
@@ -128,6 +222,27 @@ impl StreamHandler<BytesMut, Error> for Session {
                         todo_outbound_session_getblocks(self, ctx, highest_block_checkpoint);
                     }
 
+                    /////////////////
+                    // GET HEADERS //
+                    /////////////////
+                    (
+                        _,
+                        SessionStatus::Consolidated,
+                        Command::GetHeaders(GetHeaders {
+                            highest_block_checkpoint,
+                        }),
+                    ) => {
+                        inventory_send_headers(self, ctx, highest_block_checkpoint);
+                    }
+                    (
+                        SessionType::Outbound,
+                        SessionStatus::Consolidated,
+                        Command::Headers(Headers { headers }),
+                    ) => {
+                        self.headers_requested = None;
+                        inventory_process_headers(self, ctx, headers);
+                    }
+
                     ////////////////////
                     // INVENTORY      //
                     ////////////////////
@@ -144,11 +259,35 @@ impl StreamHandler<BytesMut, Error> for Session {
                              not supported",
                             msg_type, session_type, session_status
                         );
+                        report_misbehavior(self, ctx, Misbehavior::ProtocolViolation);
                     }
                 };
             }
         }
     }
+
+    /// Method called when the stream fails, e.g. when the peer sends a frame bigger than the
+    /// maximum message size
+    fn error(&mut self, err: Error, _ctx: &mut Self::Context) -> Running {
+        if err.kind() == ErrorKind::InvalidData {
+            warn!(
+                "Invalid frame received from peer {}, closing its session: {}",
+                self.remote_addr, err
+            );
+            self.disconnect_reason
+                .get_or_insert(DisconnectReason::OversizedMessage);
+
+            // Get session manager address
+            let session_manager_addr = System::current().registry().get::<SessionsManager>();
+
+            session_manager_addr.do_send(ReportMisbehavior {
+                address: self.remote_addr,
+                misbehavior: Misbehavior::OversizedMessage,
+            });
+        }
+
+        Running::Stop
+    }
 }
 
 /// Handler for GetPeers message (sent by other actors)
@@ -161,6 +300,41 @@ impl Handler<GetPeers> for Session {
         let get_peers_msg = WitnetMessage::build_get_peers();
         // Write get peers message in session
         self.send_message(get_peers_msg);
+        // Only the Peers messages requested by the node are processed
+        self.peers_requested = Some(Instant::now());
+    }
+}
+
+/// Handler for CloseSession message (sent by SessionsManager)
+impl Handler<CloseSession> for Session {
+    type Result = SessionUnitResult;
+
+    fn handle(&mut self, _msg: CloseSession, ctx: &mut Context<Self>) {
+        debug!("Closing session with peer {:?}", self.remote_addr);
+        // Stopping the actor unregisters the session and closes the connection
+        self.disconnect(ctx, DisconnectReason::ClosedByNode);
+    }
+}
+
+/// Handler for RequestBlocks message (sent by other actors)
+impl Handler<RequestBlocks> for Session {
+    type Result = SessionUnitResult;
+
+    fn handle(&mut self, _msg: RequestBlocks, ctx: &mut Context<Self>) {
+        if self.session_type == SessionType::Outbound && self.supports_headers() {
+            inventory_request_blocks(self, ctx);
+        }
+    }
+}
+
+/// Handler for RequestHeaders message (sent by other actors)
+impl Handler<RequestHeaders> for Session {
+    type Result = SessionUnitResult;
+
+    fn handle(&mut self, _msg: RequestHeaders, ctx: &mut Context<Self>) {
+        if self.session_type == SessionType::Outbound && self.supports_headers() {
+            inventory_get_headers(self, ctx);
+        }
     }
 }
 
@@ -180,16 +354,78 @@ impl Handler<AnnounceItems> for Session {
     }
 }
 
+/// Function to report a misbehavior of the peer to the SessionsManager, closing the session if the
+/// peer gets banned
+fn report_misbehavior(session: &Session, ctx: &mut Context<Session>, misbehavior: Misbehavior) {
+    // Get session manager address
+    let session_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    session_manager_addr
+        .send(ReportMisbehavior {
+            address: session.remote_addr,
+            misbehavior,
+        })
+        .into_actor(session)
+        .then(|res, act, ctx| {
+            match res {
+                Ok(true) => {
+                    info!("Peer {} was banned, closing its session", act.remote_addr);
+                    act.disconnect(ctx, DisconnectReason::Banned);
+                }
+                Ok(false) => {}
+                Err(_) => warn!("Unsuccessful communication with sessions manager"),
+            }
+
+            actix::fut::ok(())
+        })
+        .wait(ctx);
+}
+
+/// Kind of rate limited message of a command, if its rate is limited
+fn limited_message(command: &Command) -> Option<LimitedMessage> {
+    match command {
+        Command::Inv(_) => Some(LimitedMessage::Inv),
+        Command::GetData(_) => Some(LimitedMessage::GetData),
+        Command::GetPeers(_) | Command::Peers(_) => Some(LimitedMessage::Peers),
+        _ => None,
+    }
+}
+
+/// Kind of size limited message of a command, if its size is limited on its own
+fn sized_message(command: &Command) -> Option<SizedMessage> {
+    match command {
+        Command::Block(_) => Some(SizedMessage::Block),
+        Command::Headers(_) => Some(SizedMessage::Headers),
+        Command::Inv(_) | Command::GetData(_) => Some(SizedMessage::Inventory),
+        Command::Peers(_) => Some(SizedMessage::Peers),
+        _ => None,
+    }
+}
+
 /// Function to try to consolidate session if handshake conditions are met
 fn try_consolidate_session(session: &mut Session, ctx: &mut Context<Session>) {
     // Check if HandshakeFlags are all set to true
-    if session.handshake_flags.all_true() && session.remote_sender_addr.is_some() {
+    if session.handshake_flags.all_true()
+        && session.remote_sender_addr.is_some()
+        && session.capabilities.is_some()
+    {
         // Update session to consolidate status
         update_consolidate(session, ctx);
 
-        // If session type is Outbound, start initial block synchronization
+        // Get the reputation of the peer, which the relay policy may require for relaying its
+        // blocks. The peers of inbound sessions are not known
         if let SessionType::Outbound = session.session_type {
-            inventory_get_blocks(session, ctx);
+            update_reputation(session, ctx);
+        }
+
+        // If session type is Outbound, start initial block synchronization, downloading the
+        // headers first if the peer supports it
+        if let SessionType::Outbound = session.session_type {
+            if session.supports_headers() {
+                inventory_get_headers(session, ctx);
+            } else {
+                inventory_get_blocks(session, ctx);
+            }
         }
     }
 }
@@ -216,7 +452,7 @@ fn inventory_get_blocks(session: &Session, ctx: &mut Context<Session>) {
                     warn!("Get highest checkpoint beacon in Blocks Manager failed");
                     // FIXME(#72): a full stop of the session is not correct (unregister should
                     // be skipped)
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::Error);
 
                     actix::fut::err(())
                 }
@@ -225,6 +461,142 @@ fn inventory_get_blocks(session: &Session, ctx: &mut Context<Session>) {
         .wait(ctx);
 }
 
+/// Function to request the headers following the tip of the header chain, falling back to
+/// GetBlocks if headers-first synchronization is disabled
+fn inventory_get_headers(session: &Session, ctx: &mut Context<Session>) {
+    let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+    blocks_manager_addr
+        .send(GetHeadersBeacon)
+        .into_actor(session)
+        .then(|res, act, ctx| {
+            match res {
+                Ok(Some(beacon)) => {
+                    act.headers_requested = Some(Instant::now());
+                    act.send_message(WitnetMessage::build_get_headers(beacon));
+                }
+                Ok(None) => inventory_get_blocks(act, ctx),
+                Err(e) => error!("Unsuccessful communication with blocks manager: {}", e),
+            }
+
+            actix::fut::ok(())
+        })
+        .wait(ctx);
+}
+
+/// Function called when GetHeaders message is received
+fn inventory_send_headers(session: &Session, ctx: &mut Context<Session>, beacon: CheckpointBeacon) {
+    let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+    blocks_manager_addr
+        .send(GetHeadersAfter { beacon })
+        .into_actor(session)
+        .then(|res, act, _ctx| {
+            match res {
+                Ok(headers) => act.send_message(WitnetMessage::build_headers(headers)),
+                Err(e) => error!("Unsuccessful communication with blocks manager: {}", e),
+            }
+
+            actix::fut::ok(())
+        })
+        .wait(ctx);
+}
+
+/// Function called when Headers message is received: the blocks of the new headers are queued for
+/// download, and the following headers are requested if the message was full
+fn inventory_process_headers(
+    session: &Session,
+    ctx: &mut Context<Session>,
+    headers: Vec<HashedBlockHeader>,
+) {
+    let full = headers.len() == MAX_HEADERS_PER_MESSAGE;
+    let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+    blocks_manager_addr
+        .send(AddHeaders {
+            headers,
+            address: session.remote_addr,
+        })
+        .into_actor(session)
+        .then(move |res, act, ctx| {
+            match res {
+                Ok(Ok(hashes)) => {
+                    if !hashes.is_empty() {
+                        inventory_request_blocks(act, ctx);
+                    }
+                    if full {
+                        inventory_get_headers(act, ctx);
+                    }
+                }
+                // The headers were sent for an older tip, e.g. another peer sent them first, or
+                // the peer follows another chain
+                Ok(Err(BlocksManagerError::InvalidHeaders(HeadersError::DoesNotConnect(_)))) => {
+                    debug!(
+                        "Headers from peer {} do not extend the header chain",
+                        act.remote_addr
+                    );
+                }
+                Ok(Err(e)) => {
+                    warn!(
+                        "Invalid headers received from peer {}: {:?}",
+                        act.remote_addr, e
+                    );
+                    report_misbehavior(act, ctx, Misbehavior::InvalidHeaders);
+                }
+                Err(e) => error!("Unsuccessful communication with blocks manager: {}", e),
+            }
+
+            actix::fut::ok(())
+        })
+        .wait(ctx);
+}
+
+/// Function to ask the BlocksManager for a range of blocks to download from the peer, and to
+/// request them with a GetData message
+fn inventory_request_blocks(session: &Session, ctx: &mut Context<Session>) {
+    let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
+    blocks_manager_addr
+        .send(AssignBlocks {
+            address: session.remote_addr,
+        })
+        .into_actor(session)
+        .then(|res, act, _ctx| {
+            match res {
+                Ok(Some(hashes)) => {
+                    debug!(
+                        "Requesting {} blocks to peer {}",
+                        hashes.len(),
+                        act.remote_addr
+                    );
+                    act.remember_requested_blocks(&hashes);
+                    let inventory = hashes.into_iter().map(InvVector::Block).collect();
+                    act.send_message(WitnetMessage::build_get_data(inventory));
+                }
+                Ok(None) => {}
+                Err(e) => error!("Unsuccessful communication with blocks manager: {}", e),
+            }
+
+            actix::fut::ok(())
+        })
+        .spawn(ctx);
+}
+
+/// Function to get the reputation score of the peer from the PeersManager
+fn update_reputation(session: &Session, ctx: &mut Context<Session>) {
+    let peers_manager_addr = System::current().registry().get::<PeersManager>();
+    peers_manager_addr
+        .send(GetReputation {
+            address: session.remote_addr,
+        })
+        .into_actor(session)
+        .then(|res, act, _ctx| {
+            match res {
+                Ok(reputation) => act.reputation = reputation.score(),
+                Err(e) => error!("Unsuccessful communication with peers manager: {}", e),
+            }
+
+            actix::fut::ok(())
+        })
+        .spawn(ctx);
+}
+
 // Function to notify the SessionsManager that the session has been consolidated
 fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
     // Get session manager address
@@ -238,6 +610,7 @@ fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
             address: session.remote_addr,
             potential_new_peer: session.remote_sender_addr.unwrap(),
             session_type: session.session_type,
+            capabilities: session.capabilities.unwrap(),
         })
         .into_actor(session)
         .then(|res, act, ctx| {
@@ -246,6 +619,13 @@ fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
                     debug!("Session successfully consolidated in the Session Manager");
                     // Set status to consolidate
                     act.status = SessionStatus::Consolidated;
+                    act.consolidated_at = Some(Instant::now());
+
+                    // A feeler session is only needed to complete the handshake
+                    if let SessionType::Feeler = act.session_type {
+                        debug!("Feeler session with peer {} completed", act.remote_addr);
+                        act.disconnect(ctx, DisconnectReason::FeelerCompleted);
+                    }
 
                     actix::fut::ok(())
                 }
@@ -253,7 +633,7 @@ fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
                     warn!("Session consolidate in Session Manager failed");
                     // FIXME(#72): a full stop of the session is not correct (unregister should
                     // be skipped)
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::Error);
 
                     actix::fut::err(())
                 }
@@ -271,9 +651,9 @@ fn peer_discovery_get_peers(session: &mut Session, ctx: &mut Context<Session>) {
 
     // Start chain of actions
     peers_manager_addr
-        // Send GetPeer message to PeersManager actor
+        // Send GetRecentPeers message to PeersManager actor
         // This returns a Request Future, representing an asynchronous message sending process
-        .send(peers_manager::messages::GetPeers)
+        .send(peers_manager::messages::GetRecentPeers)
         // Convert a normal future into an ActorFuture
         .into_actor(session)
         // Process the response from PeersManager
@@ -292,7 +672,7 @@ fn peer_discovery_get_peers(session: &mut Session, ctx: &mut Context<Session>) {
                     warn!("Failed to receive peers from PeersManager");
                     // FIXME(#72): a full stop of the session is not correct (unregister should
                     // be skipped)
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::Error);
                 }
             }
             actix::fut::ok(())
@@ -301,7 +681,29 @@ fn peer_discovery_get_peers(session: &mut Session, ctx: &mut Context<Session>) {
 }
 
 /// Function called when Peers message is received
-fn peer_discovery_peers(peers: &[Address]) {
+fn peer_discovery_peers(session: &mut Session, ctx: &mut Context<Session>, peers: &[Address]) {
+    // Ignore the Peers messages which were not requested, so a peer cannot keep pushing addresses
+    if session.peers_requested.is_none() {
+        warn!(
+            "Ignoring unrequested Peers message from peer {}",
+            session.remote_addr
+        );
+        return;
+    }
+    session.peers_requested = None;
+
+    // Oversized batches are not processed at all
+    if peers.len() > MAX_PEERS_PER_MESSAGE {
+        warn!(
+            "Peer {} sent {} peer addresses, more than the maximum of {}",
+            session.remote_addr,
+            peers.len(),
+            MAX_PEERS_PER_MESSAGE
+        );
+        report_misbehavior(session, ctx, Misbehavior::ProtocolViolation);
+        return;
+    }
+
     // Get peers manager address
     let peers_manager_addr = System::current()
         .registry()
@@ -310,20 +712,129 @@ fn peer_discovery_peers(peers: &[Address]) {
     // Convert array of address to vector of socket addresses
     let addresses = peers.iter().map(from_address).collect();
 
-    // Send AddPeers message to the peers manager
-    peers_manager_addr.do_send(peers_manager::messages::AddPeers {
-        // TODO: convert Vec<Address> to Vec<SocketAddr>
+    // Send AddPeersFrom message to the peers manager, which tracks the peer as the source of the
+    // addresses
+    peers_manager_addr.do_send(peers_manager::messages::AddPeersFrom {
         addresses,
+        source: session.remote_addr.ip(),
     });
 }
 
+/// Function called periodically on consolidated sessions to ping the peer when it has been idle for
+/// the ping period, and to close the session when the peer does not answer a ping in time
+pub(super) fn heartbeat_keepalive(session: &mut Session, ctx: &mut Context<Session>) {
+    let now = Instant::now();
+    match session.pending_ping {
+        Some((_, sent)) => {
+            if now.duration_since(sent) >= session.keepalive.timeout {
+                warn!(
+                    "Peer {} did not answer a ping in time, closing its session",
+                    session.remote_addr
+                );
+                session.disconnect(ctx, DisconnectReason::PingTimeout);
+            }
+        }
+        None => {
+            if now.duration_since(session.last_received) >= session.keepalive.period {
+                let ping_msg = WitnetMessage::build_ping();
+                if let Command::Ping(Ping { nonce }) = ping_msg.kind {
+                    session.pending_ping = Some((nonce, now));
+                }
+                session.send_message(ping_msg);
+            }
+        }
+    }
+}
+
+/// Function called periodically to close the session when the peer did not answer a request of
+/// peers or headers in time
+pub(super) fn check_response_timeouts(session: &mut Session, ctx: &mut Context<Session>) {
+    let now = Instant::now();
+    let expired = |requested: Option<Instant>, timeout: Duration| {
+        timeout.as_secs() > 0 && requested.map_or(false, |at| now.duration_since(at) >= timeout)
+    };
+
+    let request = if expired(session.peers_requested, session.response_timeouts.get_peers) {
+        Some("GetPeers")
+    } else if expired(
+        session.headers_requested,
+        session.response_timeouts.get_headers,
+    ) {
+        Some("GetHeaders")
+    } else {
+        None
+    };
+    if let Some(request) = request {
+        warn!(
+            "Peer {} did not answer a {} message in time, closing its session",
+            session.remote_addr, request
+        );
+        session.disconnect(ctx, DisconnectReason::ResponseTimeout);
+    }
+}
+
+/// Function called when Pong message is received
+fn heartbeat_pong(session: &mut Session, nonce: u64) {
+    match session.pending_ping {
+        Some((ping_nonce, sent)) if ping_nonce == nonce => {
+            session.pending_ping = None;
+            let latency = sent.elapsed();
+            debug!(
+                "Peer {} answered a ping in {:?}",
+                session.remote_addr, latency
+            );
+
+            // Get session manager address
+            let session_manager_addr = System::current().registry().get::<SessionsManager>();
+
+            session_manager_addr.do_send(SetLatency {
+                address: session.remote_addr,
+                latency,
+            });
+        }
+        _ => debug!(
+            "Ignoring unexpected Pong message from peer {}",
+            session.remote_addr
+        ),
+    }
+}
+
 /// Function called when Block message is received
-fn inventory_process_block(_session: &mut Session, _ctx: &mut Context<Session>, block: Block) {
+fn inventory_process_block(session: &mut Session, ctx: &mut Context<Session>, block: Block) {
     // Get BlocksManager address
     let blocks_manager_addr = System::current().registry().get::<BlocksManager>();
 
-    // Send a message to the BlocksManager to try to add a new block
-    blocks_manager_addr.do_send(AddNewBlock { block });
+    // Blocks which were not requested from the peer may not be relayed, depending on the relay
+    // policy
+    let solicited = block_hash(&block)
+        .map(|hash| session.requested_blocks.remove(&hash))
+        .unwrap_or(false);
+    let origin = ItemOrigin {
+        address: session.remote_addr,
+        solicited,
+        reputation: session.reputation,
+    };
+
+    // Send a message to the BlocksManager to try to add a new block, counting the blocks which
+    // were new to the node in the reputation of the peer
+    blocks_manager_addr
+        .send(AddNewBlock {
+            block,
+            origin: Some(origin),
+        })
+        .into_actor(session)
+        .then(|res, act, ctx| {
+            if let Ok(Ok(_)) = res {
+                act.useful_blocks += 1;
+            }
+            // Ask for more blocks once the range of the peer is complete
+            if act.session_type == SessionType::Outbound && act.supports_headers() {
+                inventory_request_blocks(act, ctx);
+            }
+
+            actix::fut::ok(())
+        })
+        .spawn(ctx);
 }
 
 /// Function to process an Inv message
@@ -336,6 +847,13 @@ fn inventory_process_inv(session: &mut Session, _ctx: &mut Context<Session>, inv
 
     // Check if there are any vectors to be requested
     if !missing_inv_vectors.is_empty() {
+        session.remember_requested_blocks(missing_inv_vectors.iter().filter_map(|inv_vector| {
+            match inv_vector {
+                InvVector::Block(hash) => Some(hash),
+                _ => None,
+            }
+        }));
+
         // Create GetData message with requested inventory vectors
         let get_data_msg = WitnetMessage::build_get_data(missing_inv_vectors.to_vec());
 
@@ -357,16 +875,52 @@ fn handshake_verack(session: &mut Session) {
 }
 
 /// Function called when Version message is received
-fn handshake_version(session: &mut Session, sender_address: &Address) -> Vec<WitnetMessage> {
+/// Returns `None` if the protocol version of the peer is not supported or the peer belongs to
+/// another network
+fn handshake_version(session: &mut Session, version: &Version) -> Option<Vec<WitnetMessage>> {
+    // Check that the peer starts its chain from the same genesis block
+    if version.genesis != session.network.genesis {
+        warn!(
+            "Peer {} uses the genesis block {:#018x}, which is not the genesis block {:#018x} of \
+             this network",
+            session.remote_addr, version.genesis, session.network.genesis
+        );
+        return None;
+    }
+
+    // Negotiate the protocol version and the features used in the session
+    let local = Capabilities {
+        version: PROTOCOL_VERSION,
+        features: session.features,
+    };
+    let remote = Capabilities {
+        version: version.version,
+        features: version.capabilities,
+    };
+    let capabilities = match Capabilities::negotiate(local, remote, MIN_PROTOCOL_VERSION) {
+        Some(capabilities) => capabilities,
+        None => {
+            warn!(
+                "Peer {} uses the protocol version {}, older than the minimum supported version {}",
+                session.remote_addr, version.version, MIN_PROTOCOL_VERSION
+            );
+            return None;
+        }
+    };
+    debug!(
+        "Negotiated capabilities with peer {}: {:?}",
+        session.remote_addr, capabilities
+    );
+    session.capabilities = Some(capabilities);
+
+    session.remote_sender_addr = Some(from_address(&version.sender_address));
+
     let flags = &mut session.handshake_flags;
 
     if flags.version_rx {
         debug!("Version message already received");
     }
 
-    // Placeholder for version fields verification
-    session.remote_sender_addr = Some(from_address(sender_address));
-
     // Set version_rx flag, indicating reception of a version message from the peer
     flags.version_rx = true;
 
@@ -378,11 +932,17 @@ fn handshake_version(session: &mut Session, sender_address: &Address) -> Vec<Wit
     }
     if !flags.version_tx {
         flags.version_tx = true;
-        let version = WitnetMessage::build_version(session.server_addr, session.remote_addr, 0);
+        let version = WitnetMessage::build_version(
+            session.server_addr,
+            session.remote_addr,
+            0,
+            session.features,
+            session.network.genesis,
+        );
         responses.push(version);
     }
 
-    responses
+    Some(responses)
 }
 /// Function called when GetData message is received
 fn send_block_msg(session: &mut Session, ctx: &mut Context<Session>, hash: &Hash) {
@@ -393,7 +953,8 @@ fn send_block_msg(session: &mut Session, ctx: &mut Context<Session>, hash: &Hash
     // Get storage manager actor address
     let storage_manager_addr = System::current().registry().get::<StorageManager>();
     storage_manager_addr
-        // Send a message to read the block from the storage
+        // Send a message to read the block from the storage. The storage manager decodes it from
+        // a view of the stored bytes, so the value is not copied before decoding
         .send(Get::<Block>::new(block_key.to_vec()))
         .into_actor(session)
         // Process the response
@@ -486,7 +1047,7 @@ fn todo_inbound_session_getblocks(
                     warn!("Get highest checkpoint beacon in Blocks Manager failed");
                     // FIXME(#72): a full stop of the session is not correct (unregister should
                     // be skipped)
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::Error);
 
                     actix::fut::err(())
                 }
@@ -11,9 +11,26 @@ impl Message for GetPeers {
     type Result = SessionUnitResult;
 }
 
+/// Message to close the session, e.g.: when the node is shutting down
+pub struct CloseSession;
+
+impl Message for CloseSession {
+    type Result = SessionUnitResult;
+}
+
 /// Message to announce new inventory items through the network
 #[derive(Clone, Message)]
 pub struct AnnounceItems {
     /// Inventory items
     pub items: Vec<InvVector>,
 }
+
+/// Message to indicate that the session can request blocks to download from its peer, e.g.: when
+/// new blocks are pending or the blocks of a stalled peer can be requested to others
+#[derive(Clone, Message)]
+pub struct RequestBlocks;
+
+/// Message to indicate that the session must request the headers following the tip of the header
+/// chain again, e.g.: when the headers of a peer were discarded
+#[derive(Clone, Message)]
+pub struct RequestHeaders;
@@ -1,15 +1,24 @@
+use std::collections::HashSet;
 use std::net::SocketAddr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use actix::io::FramedWrite;
+use actix::{io::FramedWrite, ActorContext, Context};
 
 use log::info;
 use tokio::io::WriteHalf;
-use tokio::net::TcpStream;
 
-use crate::actors::codec::P2PCodec;
-use witnet_data_structures::types::Message as WitnetMessage;
-use witnet_p2p::sessions::{SessionStatus, SessionType};
+use crate::actors::{codec::P2PCodec, stream::PeerStream, throttle::Throttled};
+use witnet_data_structures::{
+    builders::{NODE_HEADERS, NODE_SNAPPY},
+    chain::Hash,
+    types::Message as WitnetMessage,
+};
+use witnet_p2p::{
+    compression,
+    limits::MessageLimits,
+    rate_limits::RateLimiter,
+    sessions::{capabilities::Capabilities, events::DisconnectReason, SessionStatus, SessionType},
+};
 
 mod actor;
 
@@ -17,6 +26,13 @@ mod handlers;
 /// Messages for session
 pub mod messages;
 
+/// Maximum number of messages per second a peer can send before being reported as spamming
+const MAX_MESSAGES_PER_SECOND: u32 = 1000;
+
+/// Maximum number of blocks requested from the peer and not received yet which are remembered, the
+/// blocks requested over this number are considered unsolicited when they arrive
+const MAX_REQUESTED_BLOCKS: usize = 10_000;
+
 /// HandshakeFlags
 #[derive(Default)]
 struct HandshakeFlags {
@@ -38,6 +54,35 @@ impl HandshakeFlags {
     }
 }
 
+/// Settings of the pings sent to an idle peer to check that the connection is still alive
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Keepalive {
+    /// Time without receiving messages from the peer after which it is pinged, zero disables the
+    /// pings
+    pub period: Duration,
+    /// Time to wait for the answer to a ping before closing the session
+    pub timeout: Duration,
+}
+
+/// Time the peer has to answer each kind of request before its session is closed, zero disables
+/// the timeout
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResponseTimeouts {
+    /// Time to answer a `GetPeers` message with a `Peers` message
+    pub get_peers: Duration,
+    /// Time to answer a `GetHeaders` message with a `Headers` message
+    pub get_headers: Duration,
+}
+
+/// Identifiers of the network of the node, which must match the ones of its peers
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Network {
+    /// Magic number stamped on every message
+    pub magic: u16,
+    /// Identifier of the genesis block, announced in the handshake
+    pub genesis: u64,
+}
+
 /// Session representing a TCP connection
 pub struct Session {
     /// Server socket address (local peer)
@@ -49,12 +94,27 @@ pub struct Session {
     /// Session type
     session_type: SessionType,
 
-    /// Framed wrapper to send messages through the TCP connection
-    framed: FramedWrite<WriteHalf<TcpStream>, P2PCodec>,
+    /// Framed wrapper to send messages through the connection
+    framed: FramedWrite<WriteHalf<Throttled<PeerStream>>, P2PCodec>,
 
     /// Handshake timeout
     handshake_timeout: Duration,
 
+    /// Settings of the pings sent to the peer when it is idle
+    keepalive: Keepalive,
+
+    /// Feature bits advertised to the peer in the handshake
+    features: u64,
+
+    /// Network of the node, whose identifiers the peer must share
+    network: Network,
+
+    /// Last time a message was received from the peer
+    last_received: Instant,
+
+    /// Nonce and sending time of the ping waiting for an answer from the peer, if any
+    pending_ping: Option<(u64, Instant)>,
+
     /// Session status
     status: SessionStatus,
 
@@ -63,17 +123,61 @@ pub struct Session {
 
     /// Remote sender address
     remote_sender_addr: Option<SocketAddr>,
+
+    /// Protocol version and features negotiated with the peer
+    capabilities: Option<Capabilities>,
+
+    /// Number of messages received in the current second
+    received_messages: u32,
+
+    /// Rate limits of the kinds of messages received from the peer
+    rate_limiter: RateLimiter,
+
+    /// Maximum sizes of the messages received from the peer
+    message_limits: MessageLimits,
+
+    /// Time the peer has to answer the requests of the node
+    response_timeouts: ResponseTimeouts,
+
+    /// Time at which a Peers message was requested from the peer, if it was not received yet
+    peers_requested: Option<Instant>,
+
+    /// Time at which a Headers message was requested from the peer, if it was not received yet
+    headers_requested: Option<Instant>,
+
+    /// Time at which the session was consolidated, if it was
+    consolidated_at: Option<Instant>,
+
+    /// Number of blocks received from the peer which were new to the node
+    useful_blocks: u64,
+
+    /// Hashes of the blocks requested from the peer and not received yet
+    requested_blocks: HashSet<Hash>,
+
+    /// Reputation score of the peer when the session was consolidated, zero for the inbound
+    /// sessions, whose peers are not known
+    reputation: i64,
+
+    /// Why the session is being closed, if the node closed it
+    disconnect_reason: Option<DisconnectReason>,
 }
 
 /// Session helper methods
 impl Session {
     /// Method to create a new session
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         server_addr: SocketAddr,
         remote_addr: SocketAddr,
         session_type: SessionType,
-        framed: FramedWrite<WriteHalf<TcpStream>, P2PCodec>,
+        framed: FramedWrite<WriteHalf<Throttled<PeerStream>>, P2PCodec>,
         handshake_timeout: Duration,
+        keepalive: Keepalive,
+        features: u64,
+        network: Network,
+        rate_limiter: RateLimiter,
+        message_limits: MessageLimits,
+        response_timeouts: ResponseTimeouts,
     ) -> Session {
         Session {
             server_addr,
@@ -81,19 +185,74 @@ impl Session {
             session_type,
             framed,
             handshake_timeout,
+            keepalive,
+            features,
+            network,
+            last_received: Instant::now(),
+            pending_ping: None,
             status: SessionStatus::Unconsolidated,
             handshake_flags: HandshakeFlags::default(),
             remote_sender_addr: None,
+            capabilities: None,
+            received_messages: 0,
+            rate_limiter,
+            message_limits,
+            response_timeouts,
+            peers_requested: None,
+            headers_requested: None,
+            consolidated_at: None,
+            useful_blocks: 0,
+            requested_blocks: HashSet::new(),
+            reputation: 0,
+            disconnect_reason: None,
+        }
+    }
+    /// Method to close the session, recording the reason reported when it is unregistered. The
+    /// first reason recorded is kept
+    fn disconnect(&mut self, ctx: &mut Context<Self>, reason: DisconnectReason) {
+        self.disconnect_reason.get_or_insert(reason);
+        ctx.stop();
+    }
+    /// Method to check whether the messages exchanged with the peer are compressed, which happens
+    /// once the session is consolidated if both nodes support it
+    fn is_compression_enabled(&self) -> bool {
+        self.status == SessionStatus::Consolidated
+            && self
+                .capabilities
+                .map_or(false, |capabilities| capabilities.supports(NODE_SNAPPY))
+    }
+    /// Method to check whether the peer serves block headers, so the chain can be synchronized
+    /// with it headers first
+    fn supports_headers(&self) -> bool {
+        self.capabilities
+            .map_or(false, |capabilities| capabilities.supports(NODE_HEADERS))
+    }
+    /// Method to remember the blocks requested from the peer, so they are not considered
+    /// unsolicited when they arrive
+    fn remember_requested_blocks<'a, I>(&mut self, hashes: I)
+    where
+        I: IntoIterator<Item = &'a Hash>,
+    {
+        for hash in hashes {
+            if self.requested_blocks.len() >= MAX_REQUESTED_BLOCKS {
+                break;
+            }
+            self.requested_blocks.insert(*hash);
         }
     }
     /// Method to send a Witnet message to the remote peer
-    fn send_message(&mut self, msg: WitnetMessage) {
+    fn send_message(&mut self, mut msg: WitnetMessage) {
+        // Stamp the message with the magic number of the network
+        msg.magic = self.network.magic;
         info!(
             "-----> Session ({:?}) sending message: {:?}",
             self.remote_addr, msg
         );
         // Convert WitnetMessage into a vector of bytes
-        let bytes: Vec<u8> = msg.into();
+        let mut bytes: Vec<u8> = msg.into();
+        if self.is_compression_enabled() {
+            bytes = compression::compress(&bytes);
+        }
         // Convert bytes into BytestMut and send them
         self.framed.write(bytes.into());
     }
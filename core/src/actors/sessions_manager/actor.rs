@@ -1,7 +1,27 @@
-use actix::{Actor, Context};
-use log::debug;
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use crate::actors::config_manager::send_get_config_request;
+use actix::{Actor, ActorFuture, Context, ContextFutureSpawner, System, WrapFuture};
+use log::{debug, error, info};
+
+use crate::actors::{
+    config_manager::send_get_config_request,
+    session::{Keepalive, Network, ResponseTimeouts},
+    storage_keys::BANS_KEY,
+    storage_manager::{messages::Get, StorageManager},
+};
+
+use witnet_config::config::MessageCompression;
+use witnet_data_structures::builders::{genesis_id, CAPABILITIES, NODE_SNAPPY};
+use witnet_p2p::{
+    bans::Bans,
+    limits::MessageLimits,
+    rate_limits::{BandwidthLimit, LimitedMessage},
+    sessions::diversity::{AsMap, OutboundDiversity},
+};
+use witnet_util::timestamp::get_timestamp;
 
 use super::SessionsManager;
 
@@ -19,22 +39,169 @@ impl Actor for SessionsManager {
             // Get periods for peers bootstrapping and discovery tasks
             let bootstrap_peers_period = config.connections.bootstrap_peers_period;
             let discovery_peers_period = config.connections.discovery_peers_period;
+            let feeler_peers_period = config.connections.feeler_peers_period;
 
-            // Set server address, connections limits and handshake timeout
+            // Set server and listening addresses, connections limits, handshake timeout, caps of
+            // inbound connections from the same IP address or subnet and priority peers
+            act.sessions
+                .set_server_address(config.connections.server_addr());
             act.sessions
-                .set_server_address(config.connections.server_addr);
+                .set_listening_addresses(config.connections.server_addrs.clone());
             act.sessions.set_limits(
                 config.connections.inbound_limit,
                 config.connections.outbound_limit,
             );
             act.sessions
                 .set_handshake_timeout(config.connections.handshake_timeout);
+            act.sessions.set_inbound_caps(
+                config.connections.max_connections_per_ip,
+                config.connections.max_connections_per_subnet,
+            );
+            act.sessions
+                .set_priority_networks(config.connections.priority_peers.iter().cloned().collect());
+
+            // Set the cap of outbound sessions with peers of the same network group. Without a
+            // valid AS map, the peers are grouped by network only
+            let diversity = &config.connections.diversity;
+            let asmap = match &diversity.asmap {
+                Some(path) => SessionsManager::load_asmap(path).unwrap_or_else(|e| {
+                    error!("The AS map has not been loaded: {}", e);
+                    AsMap::default()
+                }),
+                None => AsMap::default(),
+            };
+            act.sessions.set_outbound_diversity(OutboundDiversity {
+                max_per_group: Some(diversity.max_outbound_per_group)
+                    .filter(|max_per_group| *max_per_group > 0),
+                asmap,
+            });
+
+            // Set the period and timeout of the pings sent to the idle peers
+            act.keepalive = Keepalive {
+                period: config.connections.ping_period,
+                timeout: config.connections.ping_timeout,
+            };
+
+            // Set the feature bits advertised to the peers, adding the optional features enabled
+            act.features = match config.connections.compression {
+                MessageCompression::None => CAPABILITIES,
+                MessageCompression::Snappy => CAPABILITIES | NODE_SNAPPY,
+            };
+
+            // Set the identifiers of the network, which the peers must share
+            act.network = Network {
+                magic: config.connections.magic_number,
+                genesis: genesis_id(&config.consensus_constants.genesis_hash),
+            };
+
+            // Set the misbehavior score and duration of the bans
+            act.ban_threshold = config.connections.ban_threshold;
+            act.ban_duration = config.connections.ban_duration;
+
+            // Set the allowed and blacklisted networks. The blacklist is merged with the one
+            // persisted in storage
+            act.bans
+                .set_whitelist(config.connections.whitelist.iter().cloned());
+            act.bans
+                .blacklist(config.connections.blacklist.iter().cloned());
+
+            // Set the rate limits of the messages received by the sessions
+            act.rate_limits = vec![
+                (LimitedMessage::Inv, config.connections.rate_limits.inv),
+                (
+                    LimitedMessage::GetData,
+                    config.connections.rate_limits.get_data,
+                ),
+                (LimitedMessage::Peers, config.connections.rate_limits.peers),
+            ];
+
+            // Set the maximum sizes of the messages received by the sessions, and the time their
+            // peers have to answer their requests
+            let limits = &config.connections.limits;
+            act.message_limits = MessageLimits {
+                max_message_size: limits.max_message_size as usize,
+                max_block_size: limits.max_block_size as usize,
+                max_headers_size: limits.max_headers_size as usize,
+                max_inventory_size: limits.max_inventory_size as usize,
+                max_peers_size: limits.max_peers_size as usize,
+            };
+            act.response_timeouts = ResponseTimeouts {
+                get_peers: limits.get_peers_timeout,
+                get_headers: limits.get_headers_timeout,
+            };
+
+            // Set the bandwidth limits of all the sessions and of each session
+            let shared_limit = |kbps| {
+                if kbps > 0 {
+                    Some(Arc::new(Mutex::new(BandwidthLimit::new(
+                        kbps,
+                        Instant::now(),
+                    ))))
+                } else {
+                    None
+                }
+            };
+            act.upload_limit = shared_limit(config.connections.max_upload_kbps);
+            act.download_limit = shared_limit(config.connections.max_download_kbps);
+            act.peer_upload_kbps = config.connections.max_peer_upload_kbps;
+            act.peer_download_kbps = config.connections.max_peer_download_kbps;
+
+            // Nothing is persisted into a read-only storage
+            act.read_only = config.storage.read_only;
+
+            // Get the bans which have not expired yet from storage
+            let storage_manager_addr = System::current().registry().get::<StorageManager>();
+            storage_manager_addr
+                // Send a message to read the bans from the storage
+                .send(Get::<Bans>::new(BANS_KEY))
+                .into_actor(act)
+                // Process the response
+                .then(|res, _act, _ctx| match res {
+                    Err(e) => {
+                        // Error when sending message
+                        error!("Unsuccessful communication with storage manager: {}", e);
+                        actix::fut::err(())
+                    }
+                    Ok(res) => match res {
+                        Err(e) => {
+                            // Storage error
+                            error!("Error while getting bans from storage: {}", e);
+                            actix::fut::err(())
+                        }
+                        Ok(res) => actix::fut::ok(res),
+                    },
+                })
+                .and_then(|bans_from_storage, act, _ctx| {
+                    // bans_from_storage can be None if the storage does not contain that key
+                    if let Some(mut bans_from_storage) = bans_from_storage {
+                        bans_from_storage.remove_expired(get_timestamp());
+                        let bans = bans_from_storage.get_all();
+                        info!("Restoring the following bans from storage: {:?}", bans);
+                        for (ip, until) in bans {
+                            act.bans.ban(ip, until);
+                        }
+                        let blacklist = bans_from_storage.get_blacklist();
+                        info!(
+                            "Restoring the following blacklist from storage: {:?}",
+                            blacklist
+                        );
+                        act.bans.blacklist(blacklist);
+                    }
+
+                    actix::fut::ok(())
+                })
+                .wait(ctx);
 
             // The peers bootstrapping process begins upon SessionsManager's start
             act.bootstrap_peers(ctx, bootstrap_peers_period);
 
             // The peers discovery process begins upon SessionsManager's start
             act.discovery_peers(ctx, discovery_peers_period);
+
+            // The feeler connections begin upon SessionsManager's start, unless disabled
+            if feeler_peers_period.as_secs() > 0 {
+                act.feeler_peers(ctx, feeler_peers_period);
+            }
         });
     }
 }
@@ -1,55 +1,175 @@
-use std::marker::Send;
+use std::{
+    convert::TryFrom,
+    marker::Send,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use actix::{
     io::FramedWrite, Actor, ActorFuture, Context, ContextFutureSpawner, Handler, Message,
-    StreamHandler, System, WrapFuture,
+    MessageResult, StreamHandler, System, WrapFuture,
 };
 use log::{debug, info, warn};
 use tokio::{codec::FramedRead, io::AsyncRead};
 
 use crate::actors::{
     codec::P2PCodec,
-    peers_manager::{messages::AddPeers, PeersManager},
-    session::Session,
+    peers_manager::{
+        messages::{AddFailedPeers, AddPeers, AddTriedPeers, RemovePeers, UpdateReputation},
+        PeersManager,
+    },
+    session::{messages::CloseSession, Session},
+    throttle::{SharedBandwidthLimit, Throttled},
+};
+use witnet_p2p::{
+    peers::Reputation,
+    rate_limits::{BandwidthLimit, RateLimiter},
+    sessions::{events::SessionEvent, ConnectedPeer, SessionStatus, SessionType},
 };
+use witnet_util::{net::IpNetwork, timestamp::get_timestamp};
 
 use super::{
-    messages::{Anycast, Broadcast, Consolidate, Create, Register, SessionsUnitResult, Unregister},
+    messages::{
+        Anycast, BanPeer, Broadcast, CloseSessions, Consolidate, Create, DisconnectPeers,
+        GetConnectedPeers, GetMetrics, Register, ReportMisbehavior, SessionsUnitResult,
+        SetExternalAddress, SetLatency, SetLimits, SubscribeSessionEvents, Unregister,
+        UnsubscribeSessionEvents, UpdateBlacklist,
+    },
+    metrics::SessionsMetrics,
     SessionsManager,
 };
 
+/// Handler for SetLimits message.
+impl Handler<SetLimits> for SessionsManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetLimits, _ctx: &mut Context<Self>) {
+        debug!(
+            "Setting sessions limits: {} inbound, {} outbound",
+            msg.inbound_limit, msg.outbound_limit
+        );
+        self.sessions
+            .set_limits(msg.inbound_limit, msg.outbound_limit);
+    }
+}
+
+/// Handler for SetExternalAddress message.
+impl Handler<SetExternalAddress> for SessionsManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetExternalAddress, _ctx: &mut Context<Self>) {
+        if self.external_address != Some(msg.address) {
+            info!("Announcing external address {} to peers", msg.address);
+            self.external_address = Some(msg.address);
+        }
+    }
+}
+
 /// Handler for Create message.
 impl Handler<Create> for SessionsManager {
     type Result = ();
 
     fn handle(&mut self, msg: Create, _ctx: &mut Context<Self>) {
-        // Get handshake timeout
+        if self.closed {
+            debug!("Refusing connection, the node is shutting down");
+            return;
+        }
+
+        // Refuse the connections from banned, blacklisted or not whitelisted peers, or from
+        // crowded IP addresses or subnets, dropping the stream closes them
+        if let Ok(remote_addr) = msg.stream.peer_addr() {
+            if !self.bans.is_allowed(&remote_addr, get_timestamp()) {
+                debug!("Refusing connection with not allowed peer {}", remote_addr);
+                return;
+            }
+            // The session would fail to register anyway, so it is not even created
+            if let SessionType::Inbound = msg.session_type {
+                if !self.sessions.is_inbound_address_eligible(remote_addr) {
+                    debug!(
+                        "Refusing connection with peer {}: too many connections from its IP \
+                         address or subnet",
+                        remote_addr
+                    );
+                    return;
+                }
+            }
+        }
+
+        // Get handshake timeout and ping settings
         let handshake_timeout = self.sessions.handshake_timeout;
+        let keepalive = self.keepalive;
+
+        // Get the feature bits advertised by the sessions and the network they belong to
+        let features = self.features;
+        let network = self.network;
+
+        // Every session limits the rate of the messages of its peer on its own
+        let rate_limiter = RateLimiter::new(&self.rate_limits, Instant::now());
+
+        // The frames of the messages are rejected as soon as their header shows that they are
+        // too big. The frames of compressed sessions have a prefix byte before the message
+        let message_limits = self.message_limits;
+        let response_timeouts = self.response_timeouts;
+        let max_frame_size = message_limits.max_message_size.saturating_add(1);
 
-        // Get server address
-        let server_addr = self.sessions.server_address;
+        // The bandwidth of the session is limited by the limits shared by all the sessions and by
+        // its own limits
+        let session_limits = |shared_limit: &Option<SharedBandwidthLimit>, kbps| {
+            let mut limits: Vec<SharedBandwidthLimit> = shared_limit.iter().cloned().collect();
+            if kbps > 0 {
+                limits.push(Arc::new(Mutex::new(BandwidthLimit::new(
+                    kbps,
+                    Instant::now(),
+                ))));
+            }
+            limits
+        };
+        let download_limits = session_limits(&self.download_limit, self.peer_download_kbps);
+        let upload_limits = session_limits(&self.upload_limit, self.peer_upload_kbps);
+        let traffic = self.traffic.clone();
+
+        // Get the address announced to the peers: the external address of the router if it
+        // forwards connections to this node, or the server address
+        let server_addr = self.external_address.or(self.sessions.server_address);
 
         // Create a Session actor
         Session::create(move |ctx| {
-            // Get server address (if not present, send local address instead)
-            let server_addr = server_addr.unwrap_or_else(|| msg.stream.local_addr().unwrap());
+            // Get server address (if not present, send local address instead). A server bound to
+            // all the interfaces (`0.0.0.0` or `[::]`) is announced with the local IP address of
+            // the connection, which is reachable by the peer
+            let local_addr = msg.stream.local_addr().unwrap();
+            let server_addr = match server_addr {
+                Some(addr) if addr.ip().is_unspecified() => {
+                    SocketAddr::new(local_addr.ip(), addr.port())
+                }
+                Some(addr) => addr,
+                None => local_addr,
+            };
 
             // Get remote peer address
             let remote_addr = msg.stream.peer_addr().unwrap();
 
-            // Split TCP stream into read and write parts
-            let (r, w) = msg.stream.split();
+            // Throttle the stream and split it into read and write parts
+            let (r, w) =
+                Throttled::new(msg.stream, download_limits, upload_limits, traffic).split();
 
-            // Add stream in session actor from the read part of the tcp stream
-            Session::add_stream(FramedRead::new(r, P2PCodec), ctx);
+            // Add stream in session actor from the read part of the stream
+            Session::add_stream(FramedRead::new(r, P2PCodec::new(max_frame_size)), ctx);
 
-            // Create the session actor and store in its state the write part of the tcp stream
+            // Create the session actor and store in its state the write part of the stream
             Session::new(
                 server_addr,
                 remote_addr,
                 msg.session_type,
-                FramedWrite::new(w, P2PCodec, ctx),
+                FramedWrite::new(w, P2PCodec::default(), ctx),
                 handshake_timeout,
+                keepalive,
+                features,
+                network,
+                rate_limiter,
+                message_limits,
+                response_timeouts,
             )
         });
     }
@@ -60,16 +180,38 @@ impl Handler<Register> for SessionsManager {
     type Result = SessionsUnitResult;
 
     fn handle(&mut self, msg: Register, _: &mut Context<Self>) -> Self::Result {
+        // A priority peer takes the slot of the inbound session of lowest value when all of them
+        // are taken
+        if msg.session_type == SessionType::Inbound
+            && self.sessions.is_inbound_full()
+            && self.sessions.is_priority_address(msg.address)
+        {
+            if let Some((address, session_addr)) = self.sessions.evict_inbound_session() {
+                info!(
+                    "Closing inbound session with peer {} to make room for priority peer {}",
+                    address, msg.address
+                );
+                self.evicted.insert(address);
+                session_addr.do_send(CloseSession);
+            }
+        }
+
         // Call method register session from sessions library
         let result = self
             .sessions
             .register_session(msg.session_type, msg.address, msg.actor);
 
         match &result {
-            Ok(_) => info!(
-                "Session (type {:?}) registered for peer {}",
-                msg.session_type, msg.address
-            ),
+            Ok(_) => {
+                info!(
+                    "Session (type {:?}) registered for peer {}",
+                    msg.session_type, msg.address
+                );
+                self.notify_event(SessionEvent::Connected {
+                    address: msg.address,
+                    session_type: msg.session_type,
+                });
+            }
             Err(error) => warn!(
                 "Error while registering peer {} (session type {:?}): {}",
                 msg.address, msg.session_type, error
@@ -85,16 +227,58 @@ impl Handler<Unregister> for SessionsManager {
     type Result = SessionsUnitResult;
 
     fn handle(&mut self, msg: Unregister, _: &mut Context<Self>) -> Self::Result {
+        let disconnected = SessionEvent::Disconnected {
+            address: msg.address,
+            session_type: msg.session_type,
+            reason: msg.reason,
+        };
+
+        // Evicted sessions were unregistered when they were evicted
+        if msg.session_type == SessionType::Inbound && self.evicted.remove(&msg.address) {
+            debug!("Evicted session with peer {} stopped", msg.address);
+            self.notify_event(disconnected);
+            return Ok(());
+        }
+
         // Call method register session from sessions library
         let result = self
             .sessions
             .unregister_session(msg.session_type, msg.status, msg.address);
 
+        // The peer of a feeler session which did not complete the handshake is not a valid
+        // Witnet peer, so the node stops trying to connect to it. The node backs off from the
+        // peer of an outbound session which did not complete it
+        if let (Ok(_), SessionStatus::Unconsolidated) = (&result, msg.status) {
+            self.counters.handshakes_failed += 1;
+
+            let peers_manager_addr = System::current().registry().get::<PeersManager>();
+            match msg.session_type {
+                SessionType::Feeler => {
+                    info!(
+                        "Feeler session with peer {} failed, removing it from the peers list",
+                        msg.address
+                    );
+                    peers_manager_addr.do_send(RemovePeers {
+                        addresses: vec![msg.address],
+                    });
+                }
+                SessionType::Outbound => peers_manager_addr.do_send(AddFailedPeers {
+                    addresses: vec![msg.address],
+                }),
+                SessionType::Inbound => {}
+            }
+        }
+
         match &result {
-            Ok(_) => info!(
-                "Session (type {:?}) unregistered for peer {}",
-                msg.session_type, msg.address
-            ),
+            Ok(_) => {
+                info!(
+                    "Session (type {:?}) unregistered for peer {}: {}",
+                    msg.session_type,
+                    msg.address,
+                    msg.reason.name()
+                );
+                self.notify_event(disconnected);
+            }
             Err(error) => warn!(
                 "Error while unregistering peer {} (session type {:?}): {}",
                 msg.address, msg.session_type, error
@@ -111,9 +295,9 @@ impl Handler<Consolidate> for SessionsManager {
 
     fn handle(&mut self, msg: Consolidate, _: &mut Context<Self>) -> Self::Result {
         // Call method register session from sessions library
-        let result = self
-            .sessions
-            .consolidate_session(msg.session_type, msg.address);
+        let result =
+            self.sessions
+                .consolidate_session(msg.session_type, msg.address, msg.capabilities);
 
         // Get peers manager address
         let peers_manager_addr = System::current().registry().get::<PeersManager>();
@@ -126,11 +310,29 @@ impl Handler<Consolidate> for SessionsManager {
             addresses: vec![msg.potential_new_peer],
         });
 
+        // The peer of a consolidated outbound or feeler session is reachable, so it is kept
+        // among the tried peers, which are persisted and reused after a restart
+        match (&result, msg.session_type) {
+            (Ok(_), SessionType::Outbound) | (Ok(_), SessionType::Feeler) => {
+                peers_manager_addr.do_send(AddTriedPeers {
+                    addresses: vec![msg.address],
+                });
+            }
+            _ => {}
+        }
+
         match &result {
-            Ok(_) => info!(
-                "Session (type {:?}) status consolidated for peer {}",
-                msg.session_type, msg.address
-            ),
+            Ok(_) => {
+                info!(
+                    "Session (type {:?}) status consolidated for peer {}",
+                    msg.session_type, msg.address
+                );
+                self.notify_event(SessionEvent::Handshaked {
+                    address: msg.address,
+                    session_type: msg.session_type,
+                    capabilities: msg.capabilities,
+                });
+            }
             Err(error) => warn!(
                 "Error while consolidating peer {} (session type {:?}): {}",
                 msg.address, msg.session_type, error
@@ -141,6 +343,212 @@ impl Handler<Consolidate> for SessionsManager {
     }
 }
 
+/// Handler for ReportMisbehavior message.
+impl Handler<ReportMisbehavior> for SessionsManager {
+    type Result = bool;
+
+    fn handle(&mut self, msg: ReportMisbehavior, ctx: &mut Context<Self>) -> Self::Result {
+        debug!("Peer {} misbehaved: {:?}", msg.address, msg.misbehavior);
+
+        // Lower the reputation of the peer, so it is picked less often for outbound sessions
+        let peers_manager_addr = System::current().registry().get::<PeersManager>();
+        peers_manager_addr.do_send(UpdateReputation {
+            address: msg.address,
+            update: Reputation {
+                misbehavior: u64::from(msg.misbehavior.score()),
+                ..Reputation::default()
+            },
+        });
+
+        let banned = self.bans.report(
+            msg.address,
+            msg.misbehavior,
+            self.ban_threshold,
+            self.ban_duration,
+            get_timestamp(),
+        );
+        if banned {
+            self.counters.bans += 1;
+            warn!(
+                "Banning peer {} for {} seconds",
+                msg.address,
+                self.ban_duration.as_secs()
+            );
+
+            // Stop trying to connect to the banned peer
+            peers_manager_addr.do_send(RemovePeers {
+                addresses: vec![msg.address],
+            });
+
+            self.notify_event(SessionEvent::Banned {
+                address: msg.address,
+                until: ban_expiry(self.ban_duration),
+            });
+            self.persist_bans(ctx);
+        }
+
+        banned
+    }
+}
+
+/// Handler for SetLatency message.
+impl Handler<SetLatency> for SessionsManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetLatency, _: &mut Context<Self>) {
+        if let Err(error) = self.sessions.set_latency(msg.address, msg.latency) {
+            debug!(
+                "Error while setting the latency of peer {}: {}",
+                msg.address, error
+            );
+        }
+    }
+}
+
+/// Handler for GetConnectedPeers message.
+impl Handler<GetConnectedPeers> for SessionsManager {
+    type Result = Vec<ConnectedPeer>;
+
+    fn handle(&mut self, _msg: GetConnectedPeers, _: &mut Context<Self>) -> Self::Result {
+        self.sessions.get_connected_peers()
+    }
+}
+
+/// Handler for GetMetrics message.
+impl Handler<GetMetrics> for SessionsManager {
+    type Result = MessageResult<GetMetrics>;
+
+    fn handle(&mut self, _msg: GetMetrics, _: &mut Context<Self>) -> Self::Result {
+        let traffic = *self.traffic.lock().unwrap();
+
+        MessageResult(SessionsMetrics::new(&self.sessions, self.counters, traffic))
+    }
+}
+
+/// Handler for CloseSessions message.
+impl Handler<CloseSessions> for SessionsManager {
+    type Result = usize;
+
+    fn handle(&mut self, _msg: CloseSessions, _: &mut Context<Self>) -> Self::Result {
+        self.closed = true;
+
+        // Each session unregisters itself when it stops
+        let mut closed_sessions = 0;
+        for session_addr in self.sessions.get_all_sessions() {
+            session_addr.do_send(CloseSession);
+            closed_sessions += 1;
+        }
+        info!("Closing {} sessions", closed_sessions);
+
+        closed_sessions
+    }
+}
+
+/// Handler for DisconnectPeers message.
+impl Handler<DisconnectPeers> for SessionsManager {
+    type Result = usize;
+
+    fn handle(&mut self, msg: DisconnectPeers, _: &mut Context<Self>) -> Self::Result {
+        // Each session unregisters itself when it stops
+        let mut closed_sessions = 0;
+        for session_addr in self
+            .sessions
+            .get_sessions_by_address(|address| msg.addresses.contains(address))
+        {
+            session_addr.do_send(CloseSession);
+            closed_sessions += 1;
+        }
+        info!(
+            "Closing {} sessions with peers {:?}",
+            closed_sessions, msg.addresses
+        );
+
+        closed_sessions
+    }
+}
+
+/// Handler for BanPeer message.
+impl Handler<BanPeer> for SessionsManager {
+    type Result = i64;
+
+    fn handle(&mut self, msg: BanPeer, ctx: &mut Context<Self>) -> Self::Result {
+        let duration = msg.duration.unwrap_or(self.ban_duration);
+        let until = ban_expiry(duration);
+        self.bans.ban(msg.address.ip(), until);
+        self.counters.bans += 1;
+        warn!(
+            "Banning peer {} for {} seconds",
+            msg.address,
+            duration.as_secs()
+        );
+
+        // Stop trying to connect to the banned peer
+        let peers_manager_addr = System::current().registry().get::<PeersManager>();
+        peers_manager_addr.do_send(RemovePeers {
+            addresses: vec![msg.address],
+        });
+
+        // Close the sessions with the banned IP address, as the bans are checked when connecting
+        let ip = msg.address.ip();
+        for session_addr in self
+            .sessions
+            .get_sessions_by_address(|address| address.ip() == ip)
+        {
+            session_addr.do_send(CloseSession);
+        }
+
+        self.notify_event(SessionEvent::Banned {
+            address: msg.address,
+            until,
+        });
+        self.persist_bans(ctx);
+
+        until
+    }
+}
+
+/// Handler for UpdateBlacklist message.
+impl Handler<UpdateBlacklist> for SessionsManager {
+    type Result = Vec<IpNetwork>;
+
+    fn handle(&mut self, msg: UpdateBlacklist, ctx: &mut Context<Self>) -> Self::Result {
+        let added = self.bans.blacklist(msg.add);
+        let removed = self.bans.unblacklist(msg.remove);
+        if !added.is_empty() || !removed.is_empty() {
+            info!(
+                "Blacklist updated, added: {:?}, removed: {:?}",
+                added, removed
+            );
+            self.persist_bans(ctx);
+        }
+
+        self.bans.get_blacklist()
+    }
+}
+
+/// Handler for SubscribeSessionEvents message.
+impl Handler<SubscribeSessionEvents> for SessionsManager {
+    type Result = u64;
+
+    fn handle(&mut self, msg: SubscribeSessionEvents, _: &mut Context<Self>) -> Self::Result {
+        let subscription = self.next_subscription;
+        self.next_subscription += 1;
+        self.event_subscribers.insert(subscription, msg.recipient);
+        debug!("Subscription {} to the session events", subscription);
+
+        subscription
+    }
+}
+
+/// Handler for UnsubscribeSessionEvents message.
+impl Handler<UnsubscribeSessionEvents> for SessionsManager {
+    type Result = bool;
+
+    fn handle(&mut self, msg: UnsubscribeSessionEvents, _: &mut Context<Self>) -> Self::Result {
+        self.event_subscribers.remove(&msg.subscription).is_some()
+    }
+}
+
 /// Handler for Anycast message
 impl<T: 'static> Handler<Anycast<T>> for SessionsManager
 where
@@ -198,3 +606,11 @@ where
             });
     }
 }
+
+/// Timestamp at which a ban starting now expires, saturating for durations that do not fit in a
+/// timestamp
+fn ban_expiry(duration: Duration) -> i64 {
+    let seconds = i64::try_from(duration.as_secs()).unwrap_or_else(|_| i64::max_value());
+
+    get_timestamp().saturating_add(seconds)
+}
@@ -1,19 +1,28 @@
-use std::{marker::Send, net::SocketAddr};
+use std::{marker::Send, net::SocketAddr, time::Duration};
 
-use actix::{Addr, Handler, Message};
-use tokio::net::TcpStream;
+use actix::{Addr, Handler, Message, Recipient};
 
-use witnet_p2p::sessions::{error::SessionsResult, SessionStatus, SessionType};
+use witnet_p2p::{
+    bans::Misbehavior,
+    sessions::{
+        capabilities::Capabilities,
+        error::SessionsResult,
+        events::{DisconnectReason, SessionEvent},
+        ConnectedPeer, SessionStatus, SessionType,
+    },
+};
 
-use crate::actors::session::Session;
+use super::metrics::SessionsMetrics;
+use crate::actors::{session::Session, stream::PeerStream};
+use witnet_util::net::IpNetwork;
 
 /// Message result of unit
 pub type SessionsUnitResult = SessionsResult<()>;
 
 /// Message indicating a new session needs to be created
 pub struct Create {
-    /// TCP stream
-    pub stream: TcpStream,
+    /// Stream of the connection, TCP or TLS over TCP
+    pub stream: PeerStream,
 
     /// Session type
     pub session_type: SessionType,
@@ -49,12 +58,29 @@ pub struct Unregister {
 
     /// Session status
     pub status: SessionStatus,
+
+    /// Why the session was closed
+    pub reason: DisconnectReason,
 }
 
 impl Message for Unregister {
     type Result = SessionsUnitResult;
 }
 
+/// Message to change the limits of inbound and outbound sessions.
+/// Sessions already open above the new limits are kept.
+pub struct SetLimits {
+    /// Maximum number of inbound sessions
+    pub inbound_limit: u16,
+
+    /// Maximum number of consolidated outbound sessions
+    pub outbound_limit: u16,
+}
+
+impl Message for SetLimits {
+    type Result = ();
+}
+
 /// Message indicating a session needs to be consolidated
 pub struct Consolidate {
     /// Socket address which identifies the peer
@@ -67,12 +93,154 @@ pub struct Consolidate {
 
     /// Session type
     pub session_type: SessionType,
+
+    /// Protocol version and features negotiated with the peer in the handshake
+    pub capabilities: Capabilities,
 }
 
 impl Message for Consolidate {
     type Result = SessionsUnitResult;
 }
 
+/// Message indicating the external address of the router forwarding connections to this node,
+/// which is announced to the peers instead of the server address
+pub struct SetExternalAddress {
+    /// External socket address
+    pub address: SocketAddr,
+}
+
+impl Message for SetExternalAddress {
+    type Result = ();
+}
+
+/// Message indicating the round-trip time of a ping answered by the peer of a consolidated session
+pub struct SetLatency {
+    /// Socket address identifying the peer
+    pub address: SocketAddr,
+
+    /// Round-trip time of the ping
+    pub latency: Duration,
+}
+
+impl Message for SetLatency {
+    type Result = ();
+}
+
+/// Message to get the consolidated sessions, with the capabilities and the latency of their peers
+pub struct GetConnectedPeers;
+
+impl Message for GetConnectedPeers {
+    type Result = Vec<ConnectedPeer>;
+}
+
+/// Message to obtain the metrics of the sessions
+pub struct GetMetrics;
+
+impl Message for GetMetrics {
+    type Result = SessionsMetrics;
+}
+
+/// Message to close all the sessions and refuse new ones, when the node is shutting down. Returns
+/// the number of sessions being closed
+pub struct CloseSessions;
+
+impl Message for CloseSessions {
+    type Result = usize;
+}
+
+/// Message indicating that a peer misbehaved, which increments its score and bans it if the score
+/// reaches `connections.ban_threshold`. Returns whether the peer got banned, in which case its
+/// session should be closed
+pub struct ReportMisbehavior {
+    /// Socket address identifying the peer
+    pub address: SocketAddr,
+
+    /// Kind of misbehavior
+    pub misbehavior: Misbehavior,
+}
+
+impl Message for ReportMisbehavior {
+    type Result = bool;
+}
+
+/// Message to close the sessions with one or more peer addresses, e.g.: when the peers are removed
+/// at runtime. Returns the number of sessions closed
+pub struct DisconnectPeers {
+    /// Socket addresses identifying the peers
+    pub addresses: Vec<SocketAddr>,
+}
+
+impl Message for DisconnectPeers {
+    type Result = usize;
+}
+
+/// Message to ban a peer right away, whatever its score, for `duration` or for
+/// `connections.ban_duration` if missing. The sessions with the banned IP address are closed.
+/// Returns the timestamp at which the ban expires
+pub struct BanPeer {
+    /// Socket address identifying the peer
+    pub address: SocketAddr,
+
+    /// Duration of the ban
+    pub duration: Option<Duration>,
+}
+
+impl Message for BanPeer {
+    type Result = i64;
+}
+
+/// Message to add networks to the blacklist and to remove them from it. The blacklist is persisted
+/// along with the bans. Returns the resulting blacklist
+pub struct UpdateBlacklist {
+    /// Networks to add to the blacklist
+    pub add: Vec<IpNetwork>,
+
+    /// Networks to remove from the blacklist
+    pub remove: Vec<IpNetwork>,
+}
+
+impl Message for UpdateBlacklist {
+    type Result = Vec<IpNetwork>;
+}
+
+/// Message to subscribe to the lifecycle events of the sessions. The subscription ends when the
+/// recipient stops. Returns the identifier of the subscription
+pub struct SubscribeSessionEvents {
+    /// Recipient of the events
+    pub recipient: Recipient<SessionEventNotification>,
+}
+
+impl Message for SubscribeSessionEvents {
+    type Result = u64;
+}
+
+/// Message to end a subscription to the session events. Returns whether the subscription existed
+pub struct UnsubscribeSessionEvents {
+    /// Identifier of the subscription
+    pub subscription: u64,
+}
+
+impl Message for UnsubscribeSessionEvents {
+    type Result = bool;
+}
+
+/// Lifecycle event of a session, sent to the subscribers of the session events
+#[derive(Clone, Debug)]
+pub struct SessionEventNotification {
+    /// Identifier of the subscription
+    pub subscription: u64,
+
+    /// Timestamp of the event
+    pub timestamp: i64,
+
+    /// Event
+    pub event: SessionEvent,
+}
+
+impl Message for SessionEventNotification {
+    type Result = ();
+}
+
 /// Message indicating a message is to be forwarded to a random consolidated outbound session
 pub struct Anycast<T> {
     /// Command to be sent to the session
@@ -0,0 +1,100 @@
+//! # Sessions metrics
+//!
+//! The sessions manager counts its sessions, the handshakes which
+//! failed, the peers it banned and the bytes transferred by all the
+//! sessions, so operators can graph the health of the network.
+use serde_derive::Serialize;
+
+use witnet_p2p::sessions::Sessions;
+
+use crate::actors::throttle::Traffic;
+
+/// Snapshot of the metrics of the sessions
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionsMetrics {
+    /// Number of inbound sessions
+    pub inbound_sessions: usize,
+    /// Number of consolidated outbound sessions
+    pub outbound_sessions: usize,
+    /// Number of outbound and feeler sessions which have not completed the handshake yet
+    pub pending_sessions: usize,
+    /// Counters since the node started
+    pub counters: SessionsCounters,
+    /// Bytes received from the peers since the node started
+    pub bytes_received: u64,
+    /// Bytes sent to the peers since the node started
+    pub bytes_sent: u64,
+}
+
+/// Events counted by the sessions manager since the node started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct SessionsCounters {
+    /// Sessions closed before completing the handshake
+    pub handshakes_failed: u64,
+    /// Peers banned for misbehaving
+    pub bans: u64,
+}
+
+impl SessionsMetrics {
+    /// Take a snapshot of the metrics from the sessions, the counters and the traffic of the
+    /// sessions
+    pub fn new<T: Clone>(
+        sessions: &Sessions<T>,
+        counters: SessionsCounters,
+        traffic: Traffic,
+    ) -> Self {
+        SessionsMetrics {
+            inbound_sessions: sessions.inbound.collection.len(),
+            outbound_sessions: sessions.outbound_consolidated.collection.len(),
+            pending_sessions: sessions.outbound_unconsolidated.collection.len()
+                + sessions.feeler.collection.len(),
+            counters,
+            bytes_received: traffic.received,
+            bytes_sent: traffic.sent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_p2p::sessions::SessionType;
+
+    #[test]
+    fn count_sessions() {
+        let mut sessions = Sessions::<u32>::default();
+        let address = |port| format!("127.0.0.1:{}", port).parse().unwrap();
+        sessions
+            .register_session(SessionType::Inbound, address(1), 1)
+            .unwrap();
+        sessions
+            .register_session(SessionType::Outbound, address(2), 2)
+            .unwrap();
+        sessions
+            .register_session(SessionType::Outbound, address(3), 3)
+            .unwrap();
+        sessions
+            .consolidate_session(SessionType::Outbound, address(3), Default::default())
+            .unwrap();
+        let counters = SessionsCounters {
+            handshakes_failed: 4,
+            bans: 1,
+        };
+        let traffic = Traffic {
+            received: 100,
+            sent: 50,
+        };
+
+        assert_eq!(
+            SessionsMetrics::new(&sessions, counters, traffic),
+            SessionsMetrics {
+                inbound_sessions: 1,
+                outbound_sessions: 1,
+                pending_sessions: 1,
+                counters,
+                bytes_received: 100,
+                bytes_sent: 50,
+            }
+        );
+    }
+}
@@ -1,32 +1,113 @@
 use log::{debug, error, info, warn};
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    net::SocketAddr,
+    path::Path,
+    time::Duration,
+};
 
 use actix::{
     fut::FutureResult, ActorFuture, Addr, AsyncContext, Context, ContextFutureSpawner, Handler,
-    MailboxError, Message, System, SystemService, WrapFuture,
+    MailboxError, Message, Recipient, System, SystemService, WrapFuture,
 };
 
 use crate::actors::{
     connections_manager::{messages::OutboundTcpConnect, ConnectionsManager},
     peers_manager::{
-        messages::{GetRandomPeer, PeersSocketAddrResult},
+        messages::{GetRandomNewPeer, GetRandomPeer, PeersSocketAddrResult},
         PeersManager,
     },
-    session::{messages::GetPeers, Session},
+    session::{messages::GetPeers, Keepalive, Network, ResponseTimeouts, Session},
+    storage_keys::BANS_KEY,
+    storage_manager::{messages::Put, StorageManager},
+    throttle::{SharedBandwidthLimit, SharedTraffic},
 };
 
-use witnet_p2p::sessions::Sessions;
+use self::{messages::SessionEventNotification, metrics::SessionsCounters};
+use witnet_p2p::{
+    bans::Bans,
+    limits::MessageLimits,
+    rate_limits::LimitedMessage,
+    sessions::{diversity::AsMap, events::SessionEvent, SessionType, Sessions},
+};
+use witnet_util::timestamp::get_timestamp;
 
 mod actor;
 mod handlers;
 /// Messages for sessions manager
 pub mod messages;
+/// Metrics of the sessions
+pub mod metrics;
 
 /// SessionsManager actor
 #[derive(Default)]
 pub struct SessionsManager {
     // Registered Sessions
     sessions: Sessions<Addr<Session>>,
+
+    // Misbehavior scores and bans of the peers, and lists of allowed and blacklisted networks
+    bans: Bans,
+
+    // Misbehavior score at which a peer gets banned, zero disables banning
+    ban_threshold: u32,
+
+    // Time during which a banned peer cannot connect
+    ban_duration: Duration,
+
+    // Whether the storage is read-only, so the bans are not persisted
+    read_only: bool,
+
+    // External address of the router forwarding connections to this node, if any
+    external_address: Option<SocketAddr>,
+
+    // Settings of the pings sent by the sessions to their idle peers
+    keepalive: Keepalive,
+
+    // Feature bits advertised by the sessions in the handshake
+    features: u64,
+
+    // Network of the node, whose identifiers the peers of the sessions must share
+    network: Network,
+
+    // Peers to which an outbound connection was requested in the current bootstrap round
+    requested_peers: HashSet<SocketAddr>,
+
+    // Messages per minute of each kind that a peer can send
+    rate_limits: Vec<(LimitedMessage, u32)>,
+
+    // Maximum sizes of the messages that a peer can send
+    message_limits: MessageLimits,
+
+    // Time the peers have to answer the requests of the sessions
+    response_timeouts: ResponseTimeouts,
+
+    // Upload and download bandwidth limits shared by all the sessions, if any
+    upload_limit: Option<SharedBandwidthLimit>,
+    download_limit: Option<SharedBandwidthLimit>,
+
+    // Upload and download bandwidth limits of each session in kilobits per second, 0 means no limit
+    peer_upload_kbps: u32,
+    peer_download_kbps: u32,
+
+    // Events counted since the node started, reported in the metrics
+    counters: SessionsCounters,
+
+    // Bytes transferred by all the sessions
+    traffic: SharedTraffic,
+
+    // Whether the node is shutting down, so no sessions are created
+    closed: bool,
+
+    // Addresses of the inbound sessions evicted to make room for priority peers, which are
+    // already unregistered when the sessions stop
+    evicted: HashSet<SocketAddr>,
+
+    // Recipients of the session events, by subscription identifier
+    event_subscribers: HashMap<u64, Recipient<SessionEventNotification>>,
+
+    // Identifier of the next subscription to the session events
+    next_subscription: u64,
 }
 
 impl SessionsManager {
@@ -43,8 +124,11 @@ impl SessionsManager {
                 act.sessions.get_num_inbound_sessions()
             );
 
-            // Check if bootstrap is needed
-            if act.sessions.is_outbound_bootstrap_needed() {
+            // Request a peer for each free outbound slot, so the outbound sessions are topped up
+            // to the limit in a single round
+            let missing_outbound_sessions = act.sessions.get_num_missing_outbound_sessions();
+            act.requested_peers.clear();
+            for _ in 0..missing_outbound_sessions {
                 // Get peers manager address
                 let peers_manager_addr = System::current().registry().get::<PeersManager>();
 
@@ -59,17 +143,21 @@ impl SessionsManager {
                     // This returns a FutureResult containing the socket address if present
                     .then(|res, act, _ctx| {
                         // Process the response from peers manager
-                        act.process_get_peer_response(res)
+                        act.process_get_peer_response(res, SessionType::Outbound)
                     })
                     // Process the socket address received
                     // This returns a FutureResult containing a success or error
-                    .and_then(|address, _act, _ctx| {
+                    .and_then(|address, act, _ctx| {
                         debug!("Trying to create a new outbound connection to {}", address);
+                        act.requested_peers.insert(address);
 
                         // Get ConnectionsManager from registry and send an OutboundTcpConnect message to it
                         let connections_manager_addr =
                             System::current().registry().get::<ConnectionsManager>();
-                        connections_manager_addr.do_send(OutboundTcpConnect { address });
+                        connections_manager_addr.do_send(OutboundTcpConnect {
+                            address,
+                            session_type: SessionType::Outbound,
+                        });
 
                         actix::fut::ok(())
                     })
@@ -81,6 +169,46 @@ impl SessionsManager {
         });
     }
 
+    /// Method to periodically test a peer which the node has only heard of, through a short-lived
+    /// feeler session. The peer is promoted to the tried peers if the handshake succeeds, or
+    /// removed from the peers list otherwise
+    fn feeler_peers(&self, ctx: &mut Context<Self>, feeler_peers_period: Duration) {
+        // Schedule the feeler connection with a given period
+        ctx.run_later(feeler_peers_period, move |act, ctx| {
+            // Only one feeler session runs at a time
+            if act.sessions.is_feeler_needed() {
+                // Get peers manager address
+                let peers_manager_addr = System::current().registry().get::<PeersManager>();
+
+                peers_manager_addr
+                    // Send GetRandomNewPeer message to peers manager actor
+                    .send(GetRandomNewPeer)
+                    .into_actor(act)
+                    // Process the response from peers manager
+                    .then(|res, act, _ctx| act.process_get_peer_response(res, SessionType::Feeler))
+                    // Process the socket address received
+                    .and_then(|address, _act, _ctx| {
+                        debug!("Trying to create a new feeler connection to {}", address);
+
+                        // Get ConnectionsManager from registry and send an OutboundTcpConnect
+                        // message to it
+                        let connections_manager_addr =
+                            System::current().registry().get::<ConnectionsManager>();
+                        connections_manager_addr.do_send(OutboundTcpConnect {
+                            address,
+                            session_type: SessionType::Feeler,
+                        });
+
+                        actix::fut::ok(())
+                    })
+                    .wait(ctx);
+            }
+
+            // Reschedule the feeler peers task
+            act.feeler_peers(ctx, feeler_peers_period);
+        });
+    }
+
     /// Method to periodically discover peers
     fn discovery_peers(&self, ctx: &mut Context<Self>, discovery_peers_period: Duration) {
         // Schedule the discovery_peers with a given period
@@ -93,10 +221,11 @@ impl SessionsManager {
         });
     }
 
-    /// Method to process peers manager GetPeer response
+    /// Method to process peers manager GetPeer response, for a session of type `session_type`
     fn process_get_peer_response(
         &mut self,
         response: Result<PeersSocketAddrResult, MailboxError>,
+        session_type: SessionType,
     ) -> FutureResult<SocketAddr, (), Self> {
         response
             // Unwrap the Result<PeersSocketAddrResult, MailboxError>
@@ -117,6 +246,14 @@ impl SessionsManager {
             // Filter the result checking if outbound address is eligible as new peer
             .filter(|address: &SocketAddr| {
                 self.sessions.is_outbound_address_eligible(address.clone())
+                    && self.bans.is_allowed(address, get_timestamp())
+                    && Some(*address) != self.external_address
+                    && !self.requested_peers.contains(address)
+                    // Feeler sessions are short-lived, so they are not counted for the diversity
+                    && (session_type != SessionType::Outbound
+                        || self
+                            .sessions
+                            .is_outbound_address_diverse(*address, &self.requested_peers))
             })
             // Check if there is a peer after filter
             .or_else(|| {
@@ -128,6 +265,66 @@ impl SessionsManager {
             .unwrap_or_else(|| actix::fut::err(()))
     }
 
+    /// Method to read the AS map used to group the peers of the outbound sessions
+    fn load_asmap(path: &Path) -> Result<AsMap, String> {
+        let asmap = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?
+            .parse::<AsMap>()
+            .map_err(|e| format!("Invalid AS map {}: {}", path.display(), e))?;
+        info!(
+            "Loaded an AS map with {} networks from {}",
+            asmap.len(),
+            path.display()
+        );
+
+        Ok(asmap)
+    }
+
+    /// Method to send a session event to its subscribers, dropping the subscriptions whose
+    /// recipients stopped
+    fn notify_event(&mut self, event: SessionEvent) {
+        if self.event_subscribers.is_empty() {
+            return;
+        }
+
+        let timestamp = get_timestamp();
+        self.event_subscribers.retain(|subscription, recipient| {
+            recipient
+                .do_send(SessionEventNotification {
+                    subscription: *subscription,
+                    timestamp,
+                    event: event.clone(),
+                })
+                .is_ok()
+        });
+    }
+
+    /// Method to persist the bans into storage, so the banned peers cannot connect again after a
+    /// restart of the node
+    fn persist_bans(&mut self, ctx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+
+        // Bans are only kept until they expire
+        self.bans.remove_expired(get_timestamp());
+
+        // Get StorageManager address
+        let storage_manager_addr = System::current().registry().get::<StorageManager>();
+
+        storage_manager_addr
+            .send(Put::from_value(BANS_KEY, &self.bans).unwrap())
+            .into_actor(self)
+            .then(|res, _act, _ctx| {
+                match res {
+                    Ok(Ok(_)) => debug!("SessionsManager successfully persist bans to storage"),
+                    _ => error!("Sessions manager persist bans to storage failed"),
+                }
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+
     /// Method to process Session SendMessage response
     fn process_command_response<T>(
         &mut self,
@@ -1,5 +1,66 @@
+use witnet_data_structures::chain::Hash;
+
 /// Constant to specify the peers key for the storage
 pub static PEERS_KEY: &'static [u8] = b"peers";
 
+/// Constant to specify the key of the banned peers for the storage
+pub static BANS_KEY: &'static [u8] = b"bans";
+
 /// Constant to specify the chain key for the storage
 pub static CHAIN_KEY: &'static [u8] = b"chain";
+
+/// Constant to specify the key of the version of the storage layout
+pub static SCHEMA_VERSION_KEY: &'static [u8] = b"schema_version";
+
+/// Constant to specify the prefix of the keys of the blocks in the storage, which is followed by
+/// the block hash
+pub static BLOCKS_PREFIX: &'static [u8] = b"block-";
+
+/// Constant to specify the prefix of the keys of the transactions in the storage, which is
+/// followed by the transaction hash
+pub static TRANSACTIONS_PREFIX: &'static [u8] = b"transaction-";
+
+/// Constant to specify the prefix of the keys of the unspent transaction outputs in the storage
+pub static UTXOS_PREFIX: &'static [u8] = b"utxo-";
+
+/// Constant to specify the prefix of the keys of the reputation of the identities in the storage
+pub static REPUTATION_PREFIX: &'static [u8] = b"reputation-";
+
+/// Column families in which the data is stored, with the prefix of the keys stored in each of them
+pub fn column_families() -> [(&'static str, &'static [u8]); 5] {
+    [
+        ("blocks", BLOCKS_PREFIX),
+        ("transactions", TRANSACTIONS_PREFIX),
+        ("utxos", UTXOS_PREFIX),
+        ("peers", PEERS_KEY),
+        ("reputation", REPUTATION_PREFIX),
+    ]
+}
+
+/// Whether the entry with the given key is needed to follow the chain, so it must be written even
+/// when the database exceeds its maximum size. Other entries, such as the known peers, can be
+/// rebuilt at any time.
+pub fn is_essential(key: &[u8]) -> bool {
+    key == CHAIN_KEY
+        || key == SCHEMA_VERSION_KEY
+        || [
+            BLOCKS_PREFIX,
+            TRANSACTIONS_PREFIX,
+            UTXOS_PREFIX,
+            REPUTATION_PREFIX,
+        ]
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+/// Key of the block with the given hash in the storage
+pub fn block_key(hash: &Hash) -> Vec<u8> {
+    let Hash::SHA256(hash) = hash;
+    [BLOCKS_PREFIX, &hash[..]].concat()
+}
+
+/// Key of the transaction with the given hash in the storage
+pub fn transaction_key(hash: &Hash) -> Vec<u8> {
+    let Hash::SHA256(hash) = hash;
+    [TRANSACTIONS_PREFIX, &hash[..]].concat()
+}
@@ -1,9 +1,13 @@
-use actix::{Actor, ActorContext, Context};
-use log::{debug, error};
+use actix::{Actor, ActorContext, AsyncContext, Context, System};
+use log::{debug, error, info, warn};
+use std::time::Duration;
+use witnet_config::dirs;
 
 use crate::actors::config_manager::send_get_config_request;
 
-use super::StorageManager;
+use super::quota::{self, QuotaStatus};
+use super::{StorageManager, PURGE_EXPIRED_PERIOD_SECONDS};
+use crate::actors::blocks_manager::{messages::PruneBlocks, BlocksManager};
 
 /// Make actor from `StorageManager`
 impl Actor for StorageManager {
@@ -16,17 +20,64 @@ impl Actor for StorageManager {
 
         // Send message to config manager and process response
         send_get_config_request(self, ctx, |s, ctx, config| {
-            // Get db path from configuration
-            let db_path = &config.storage.db_path;
+            info!("Using storage backend: {}", config.storage.backend);
 
-            // Override actor
-            *s = Self::new(&db_path.to_string_lossy());
+            // Older releases kept the database in a directory shared by
+            // every environment
+            let legacy_path = dirs::legacy_data_path(config.environment.name());
+            if legacy_path.exists() && !config.storage.db_path.exists() {
+                warn!(
+                    "Found a database in {}, which is no longer used by default. \
+                     Move it to {} or set `storage.db_path` to keep using it",
+                    legacy_path.display(),
+                    config.storage.db_path.display()
+                );
+            }
 
-            // Stop context if the storage is not properly initialized
+            // Override actor, stopping the context if the storage
+            // cannot be initialized
             // FIXME(#72): check error handling
-            if s.storage.is_none() {
-                error!("Error initializing storage");
-                ctx.stop();
+            match Self::new(&config.storage) {
+                Ok(storage_manager) => {
+                    *s = storage_manager;
+
+                    // Delete the expired entries, unless the storage is read-only
+                    if !config.storage.read_only {
+                        s.purge_expired();
+                        let period = Duration::from_secs(PURGE_EXPIRED_PERIOD_SECONDS);
+                        ctx.run_interval(period, |act, _ctx| act.purge_expired());
+                    }
+
+                    // Check the size of the database, pruning old data when it approaches
+                    // `max_db_size`
+                    if config.storage.max_db_size > 0 && !config.storage.read_only {
+                        let period = Duration::from_secs(quota::CHECK_PERIOD_SECONDS);
+                        ctx.run_interval(period, |act, _ctx| {
+                            if act.check_quota() != QuotaStatus::Ok {
+                                act.purge_expired();
+                                System::current()
+                                    .registry()
+                                    .get::<BlocksManager>()
+                                    .do_send(PruneBlocks);
+                            }
+                        });
+                    }
+
+                    // Schedule the backups of the storage
+                    let backup = config.storage.backup.clone();
+                    if backup.enabled {
+                        info!(
+                            "Writing a storage backup every {} seconds to {}",
+                            backup.interval.as_secs(),
+                            backup.destination.display()
+                        );
+                        ctx.run_interval(backup.interval, move |act, _ctx| act.backup(&backup));
+                    }
+                }
+                Err(e) => {
+                    error!("Error initializing storage: {}", e);
+                    ctx.stop();
+                }
             }
         });
     }
@@ -0,0 +1,278 @@
+use crate::actors::storage_keys::{
+    BLOCKS_PREFIX, PEERS_KEY, REPUTATION_PREFIX, TRANSACTIONS_PREFIX, UTXOS_PREFIX,
+};
+use std::fs;
+use witnet_config::config::{
+    self, Archive, ArchiveBackend, Encryption, Storage as StorageConfig, StorageBackend,
+};
+use witnet_storage::backends::archive::ArchiveStorage;
+use witnet_storage::backends::cached::{CacheStats, CachedStorage};
+use witnet_storage::backends::directory::DirectoryStorage;
+use witnet_storage::backends::encrypted::EncryptedStorage;
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::backends::read_only::ReadOnlyStorage;
+use witnet_storage::backends::rocks::{
+    ColumnFamilyOptions, CompactionStyle, Compression, RocksOptions, RocksStorage,
+};
+use witnet_storage::error::{StorageError, StorageErrorKind, StorageResult};
+use witnet_storage::storage::{Storage, StorageIterator, ValueView, WriteBatch};
+use witnet_util::error::WitnetError;
+
+/// Storage backend selected in the configuration (`storage.backend`)
+pub enum Backend {
+    /// RocksDB database in `storage.db_path`
+    RocksDB(RocksStorage),
+    /// Volatile memory
+    Memory(MemoryStorage),
+    /// Another backend whose old blocks are moved to an archive (`storage.archive`)
+    Archived(Box<ArchiveStorage<StorageConfig, Backend>>),
+    /// Another backend whose values are encrypted (`storage.encryption`)
+    Encrypted(Box<EncryptedStorage<StorageConfig, Backend>>),
+    /// Another backend which cannot be written (`storage.read_only`)
+    ReadOnly(Box<ReadOnlyStorage<StorageConfig, Backend>>),
+    /// Another backend whose values are cached in memory (`storage.cache_size`)
+    Cached(Box<CachedStorage<StorageConfig, Backend>>),
+}
+
+impl Backend {
+    /// Usage statistics of the cache, if the values are cached
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        match self {
+            Backend::Cached(storage) => Some(storage.stats()),
+            _ => None,
+        }
+    }
+
+    /// Move an entry to the archive, returning whether it was moved. Nothing is moved if the
+    /// storage is read-only or there is no archive.
+    pub fn archive(&mut self, key: &[u8]) -> StorageResult<bool> {
+        match self {
+            Backend::Archived(storage) => storage.offload(key),
+            Backend::Encrypted(storage) => storage.inner_mut().archive(key),
+            Backend::Cached(storage) => storage.inner_mut().archive(key),
+            Backend::RocksDB(_) | Backend::Memory(_) | Backend::ReadOnly(_) => Ok(false),
+        }
+    }
+
+    /// Move the entries of the default RocksDB column family to the column families they belong
+    /// to, at most `batch_size` at a time, returning how many were moved. Nothing is moved if the
+    /// storage is read-only or is not a RocksDB database.
+    pub fn move_to_column_families(&mut self, batch_size: usize) -> StorageResult<usize> {
+        match self {
+            Backend::RocksDB(storage) => storage.move_to_column_families(batch_size),
+            Backend::Archived(storage) => storage.inner_mut().move_to_column_families(batch_size),
+            Backend::Encrypted(storage) => storage.inner_mut().move_to_column_families(batch_size),
+            Backend::Cached(storage) => storage.inner_mut().move_to_column_families(batch_size),
+            Backend::Memory(_) | Backend::ReadOnly(_) => Ok(0),
+        }
+    }
+}
+
+/// Implement the Storage generic trait by forwarding every operation to the selected backend
+impl<'a> Storage<StorageConfig, &'a [u8], Vec<u8>> for Backend {
+    #[allow(clippy::new_ret_no_self)]
+    fn new(config: StorageConfig) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        let backend = match config.backend {
+            StorageBackend::RocksDB => Backend::RocksDB(RocksStorage::open(
+                &config.db_path.to_string_lossy(),
+                &rocks_options(&config.rocksdb, config.read_only, config.sync_writes),
+            )?),
+            StorageBackend::Memory => Backend::Memory(*MemoryStorage::new(())?),
+        };
+
+        let backend = if config.archive.enabled {
+            Backend::Archived(Box::new(ArchiveStorage::wrap(
+                backend,
+                archive_storage(&config.archive)?,
+            )))
+        } else {
+            backend
+        };
+
+        let backend = match encryption_secret(&config.encryption)? {
+            Some(secret) => Backend::Encrypted(Box::new(EncryptedStorage::wrap(backend, &secret)?)),
+            None => backend,
+        };
+
+        let backend = if config.read_only {
+            Backend::ReadOnly(Box::new(ReadOnlyStorage::wrap(backend)))
+        } else {
+            backend
+        };
+
+        // Caching the memory backend would only duplicate its data
+        if config.cache_size > 0 && config.backend != StorageBackend::Memory {
+            Ok(Box::new(Backend::Cached(Box::new(CachedStorage::wrap(
+                backend,
+                config.cache_size as usize,
+            )))))
+        } else {
+            Ok(Box::new(backend))
+        }
+    }
+
+    fn put(&mut self, key: &'a [u8], value: Vec<u8>) -> StorageResult<()> {
+        match self {
+            Backend::RocksDB(storage) => storage.put(key, value),
+            Backend::Memory(storage) => storage.put(key, value),
+            Backend::Archived(storage) => storage.put(key, value),
+            Backend::Encrypted(storage) => storage.put(key, value),
+            Backend::ReadOnly(storage) => storage.put(key, value),
+            Backend::Cached(storage) => storage.put(key, value),
+        }
+    }
+
+    fn get(&self, key: &'a [u8]) -> StorageResult<Option<Vec<u8>>> {
+        match self {
+            Backend::RocksDB(storage) => storage.get(key),
+            Backend::Memory(storage) => storage.get(key),
+            Backend::Archived(storage) => storage.get(key),
+            Backend::Encrypted(storage) => storage.get(key),
+            Backend::ReadOnly(storage) => storage.get(key),
+            Backend::Cached(storage) => storage.get(key),
+        }
+    }
+
+    fn get_view<'s>(&'s self, key: &'a [u8]) -> StorageResult<Option<ValueView<'s>>> {
+        match self {
+            Backend::RocksDB(storage) => storage.get_view(key),
+            Backend::Memory(storage) => storage.get_view(key),
+            Backend::Archived(storage) => storage.get_view(key),
+            Backend::Encrypted(storage) => storage.get_view(key),
+            Backend::ReadOnly(storage) => storage.get_view(key),
+            Backend::Cached(storage) => storage.get_view(key),
+        }
+    }
+
+    fn delete(&mut self, key: &'a [u8]) -> StorageResult<()> {
+        match self {
+            Backend::RocksDB(storage) => storage.delete(key),
+            Backend::Memory(storage) => storage.delete(key),
+            Backend::Archived(storage) => storage.delete(key),
+            Backend::Encrypted(storage) => storage.delete(key),
+            Backend::ReadOnly(storage) => storage.delete(key),
+            Backend::Cached(storage) => storage.delete(key),
+        }
+    }
+
+    fn write(&mut self, batch: WriteBatch<&'a [u8], Vec<u8>>) -> StorageResult<()> {
+        match self {
+            Backend::RocksDB(storage) => storage.write(batch),
+            Backend::Memory(storage) => storage.write(batch),
+            Backend::Archived(storage) => storage.write(batch),
+            Backend::Encrypted(storage) => storage.write(batch),
+            Backend::ReadOnly(storage) => storage.write(batch),
+            Backend::Cached(storage) => storage.write(batch),
+        }
+    }
+
+    fn prefix_iterator<'s>(
+        &'s self,
+        prefix: &'a [u8],
+    ) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        match self {
+            Backend::RocksDB(storage) => storage.prefix_iterator(prefix),
+            Backend::Memory(storage) => storage.prefix_iterator(prefix),
+            Backend::Archived(storage) => storage.prefix_iterator(prefix),
+            Backend::Encrypted(storage) => storage.prefix_iterator(prefix),
+            Backend::ReadOnly(storage) => storage.prefix_iterator(prefix),
+            Backend::Cached(storage) => storage.prefix_iterator(prefix),
+        }
+    }
+
+    fn flush(&mut self) -> StorageResult<()> {
+        match self {
+            Backend::RocksDB(storage) => storage.flush(),
+            Backend::Memory(storage) => storage.flush(),
+            Backend::Archived(storage) => storage.flush(),
+            Backend::Encrypted(storage) => storage.flush(),
+            Backend::ReadOnly(storage) => storage.flush(),
+            Backend::Cached(storage) => storage.flush(),
+        }
+    }
+}
+
+/// Storage of the archived blocks
+fn archive_storage(config: &Archive) -> StorageResult<DirectoryStorage> {
+    match config.backend {
+        ArchiveBackend::Directory => DirectoryStorage::open(&config.path),
+    }
+}
+
+/// Secret from which the encryption key is derived, if the values have to be encrypted
+fn encryption_secret(config: &Encryption) -> StorageResult<Option<Vec<u8>>> {
+    if let Some(passphrase) = &config.passphrase {
+        return Ok(Some(passphrase.expose().as_bytes().to_vec()));
+    }
+    match &config.keyfile {
+        Some(path) => fs::read(path).map(Some).map_err(|e| {
+            WitnetError::from(StorageError::new(
+                StorageErrorKind::Connection,
+                path.to_string_lossy().to_string(),
+                format!("Cannot read the encryption key file: {}", e),
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// RocksDB tuning options from the configuration, with a column family for each data type
+fn rocks_options(config: &config::RocksDB, read_only: bool, sync_writes: bool) -> RocksOptions {
+    let column_families = &config.column_families;
+
+    RocksOptions {
+        block_cache_size: config.block_cache_size as usize,
+        write_buffer_size: config.write_buffer_size as usize,
+        max_open_files: config.max_open_files,
+        compression: compression(config.compression),
+        compaction_style: compaction_style(config.compaction_style),
+        column_families: vec![
+            column_family("blocks", BLOCKS_PREFIX, &column_families.blocks),
+            column_family(
+                "transactions",
+                TRANSACTIONS_PREFIX,
+                &column_families.transactions,
+            ),
+            column_family("utxos", UTXOS_PREFIX, &column_families.utxos),
+            column_family("peers", PEERS_KEY, &column_families.peers),
+            column_family("reputation", REPUTATION_PREFIX, &column_families.reputation),
+        ],
+        read_only,
+        sync_writes,
+        wal: config.wal,
+    }
+}
+
+/// Column family storing the keys starting with `prefix`
+fn column_family(name: &str, prefix: &[u8], config: &config::ColumnFamily) -> ColumnFamilyOptions {
+    ColumnFamilyOptions {
+        name: name.to_string(),
+        prefix: prefix.to_vec(),
+        write_buffer_size: config.write_buffer_size as usize,
+        compression: compression(config.compression),
+        compaction_style: compaction_style(config.compaction_style),
+    }
+}
+
+fn compression(compression: config::Compression) -> Compression {
+    match compression {
+        config::Compression::None => Compression::None,
+        config::Compression::Snappy => Compression::Snappy,
+        config::Compression::Zlib => Compression::Zlib,
+        config::Compression::Bz2 => Compression::Bz2,
+        config::Compression::Lz4 => Compression::Lz4,
+        config::Compression::Lz4hc => Compression::Lz4hc,
+        config::Compression::Zstd => Compression::Zstd,
+    }
+}
+
+fn compaction_style(compaction_style: config::CompactionStyle) -> CompactionStyle {
+    match compaction_style {
+        config::CompactionStyle::Level => CompactionStyle::Level,
+        config::CompactionStyle::Universal => CompactionStyle::Universal,
+        config::CompactionStyle::Fifo => CompactionStyle::Fifo,
+    }
+}
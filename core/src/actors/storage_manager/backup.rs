@@ -0,0 +1,125 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use witnet_storage::error::{StorageError, StorageErrorKind, StorageResult};
+use witnet_storage::snapshot;
+use witnet_util::error::WitnetError;
+
+use super::backend::Backend;
+
+/// Prefix of the names of the backup files
+const BACKUP_PREFIX: &str = "backup-";
+
+/// Extension of the backup files, which are storage snapshots
+const BACKUP_EXTENSION: &str = "snapshot";
+
+/// Write a backup of the storage to `destination`, named after the current time, and delete the
+/// oldest backups so only the last `retention` ones are kept. Returns the path of the new backup.
+///
+/// Backups are snapshots (see `witnet_storage::snapshot`), so they can be restored with
+/// `witnet import-snapshot`.
+pub fn write_backup(
+    storage: &Backend,
+    destination: &Path,
+    retention: u32,
+) -> StorageResult<PathBuf> {
+    fs::create_dir_all(destination).map_err(|e| backup_error(destination, &e))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = destination.join(format!(
+        "{}{:020}.{}",
+        BACKUP_PREFIX, timestamp, BACKUP_EXTENSION
+    ));
+
+    // Write to a temporary file first, so an interrupted backup is never taken for a good one
+    let partial_path = path.with_extension("partial");
+    let file = fs::File::create(&partial_path).map_err(|e| backup_error(&partial_path, &e))?;
+    snapshot::export(storage, io::BufWriter::new(file))?;
+    fs::rename(&partial_path, &path).map_err(|e| backup_error(&path, &e))?;
+
+    remove_old_backups(destination, retention as usize)
+        .map_err(|e| backup_error(destination, &e))?;
+
+    Ok(path)
+}
+
+/// Delete the oldest backups in `destination`, keeping the last `retention` ones
+fn remove_old_backups(destination: &Path, retention: usize) -> io::Result<()> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(destination)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| is_backup(path))
+        .collect();
+    // Backup names contain a zero-padded timestamp, so they sort by age
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(retention);
+    for old_backup in &backups[..excess] {
+        fs::remove_file(old_backup)?;
+    }
+
+    Ok(())
+}
+
+fn is_backup(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new(BACKUP_EXTENSION))
+        && path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .map_or(false, |name| name.starts_with(BACKUP_PREFIX))
+}
+
+fn backup_error(path: &Path, e: &io::Error) -> WitnetError<StorageError> {
+    WitnetError::from(StorageError::new(
+        StorageErrorKind::Backup,
+        path.to_string_lossy().to_string(),
+        e.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_storage::backends::memory::MemoryStorage;
+    use witnet_storage::storage::Storage;
+
+    #[test]
+    fn backup_and_retention() {
+        let destination =
+            std::env::temp_dir().join(format!("witnet-backups-{}", std::process::id()));
+        fs::create_dir_all(&destination).unwrap();
+        for old_backup in &[
+            "backup-00000000000000000001.snapshot",
+            "backup-00000000000000000002.snapshot",
+        ] {
+            fs::write(destination.join(old_backup), b"").unwrap();
+        }
+        fs::write(destination.join("notes.txt"), b"").unwrap();
+
+        let mut memory = MemoryStorage::default();
+        memory.put(b"chain", b"chain info".to_vec()).unwrap();
+        let path = write_backup(&Backend::Memory(memory), &destination, 2).unwrap();
+
+        let mut files: Vec<_> = fs::read_dir(&destination)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "backup-00000000000000000002.snapshot".to_string(),
+                path.file_name().unwrap().to_string_lossy().to_string(),
+                "notes.txt".to_string(),
+            ]
+        );
+
+        fs::remove_dir_all(&destination).unwrap();
+    }
+}
@@ -1,11 +1,16 @@
 use actix::{Context, Handler};
+use log::warn;
+use std::time::Instant;
 
-use witnet_storage::error::{StorageError, StorageErrorKind};
-use witnet_storage::storage::{Storable, Storage, StorageHelper};
+use witnet_storage::error::{StorageError, StorageErrorKind, StorageResult};
+use witnet_storage::expiry;
+use witnet_storage::storage::{Storable, Storage, StorageHelper, WriteBatch, WriteOp};
 use witnet_util::error::WitnetError;
+use witnet_util::timestamp::get_timestamp;
 
 use super::{
-    messages::{Delete, Get, Put},
+    messages::{Archive, Delete, Flush, Get, GetMetrics, Put, Write},
+    metrics::{self, StorageMetrics},
     StorageManager, UnitStorageResult, ValueStorageResult,
 };
 
@@ -14,14 +19,18 @@ impl<T: Storable + 'static> Handler<Get<T>> for StorageManager {
     type Result = ValueStorageResult<T>;
 
     fn handle(&mut self, msg: Get<T>, _: &mut Context<Self>) -> Self::Result {
-        self.storage.as_ref().map_or(
+        let start = Instant::now();
+        let result = self.storage.as_ref().map_or(
             Err(WitnetError::from(StorageError::new(
                 StorageErrorKind::Get,
                 String::from_utf8(msg.key.to_vec()).unwrap(),
                 "Storage was not properly initialised".to_string(),
             ))),
             |storage| storage.get_t(&msg.key),
-        )
+        );
+        self.operations.get.record(start.elapsed());
+
+        result
     }
 }
 
@@ -30,14 +39,25 @@ impl Handler<Put> for StorageManager {
     type Result = UnitStorageResult;
 
     fn handle(&mut self, msg: Put, _: &mut Context<Self>) -> Self::Result {
-        self.storage.as_mut().map_or(
+        self.check_quota_for(&msg.key)?;
+
+        let start = Instant::now();
+        let result = self.storage.as_mut().map_or(
             Err(WitnetError::from(StorageError::new(
                 StorageErrorKind::Put,
                 String::from_utf8(msg.key.to_vec()).unwrap(),
                 "Storage was not properly initialised".to_string(),
             ))),
-            |storage| storage.put(&msg.key, msg.value),
-        )
+            |storage| match msg.ttl {
+                Some(ttl) => {
+                    expiry::put_with_ttl(storage, &msg.key, msg.value, ttl, get_timestamp())
+                }
+                None => storage.put(&msg.key, msg.value),
+            },
+        );
+        self.operations.put.record(start.elapsed());
+
+        result
     }
 }
 
@@ -46,13 +66,132 @@ impl Handler<Delete> for StorageManager {
     type Result = UnitStorageResult;
 
     fn handle(&mut self, msg: Delete, _: &mut Context<Self>) -> Self::Result {
-        self.storage.as_mut().map_or(
+        let start = Instant::now();
+        let result = self.storage.as_mut().map_or(
             Err(WitnetError::from(StorageError::new(
                 StorageErrorKind::Delete,
                 String::from_utf8(msg.key.to_vec()).unwrap(),
                 "Storage was not properly initialised".to_string(),
             ))),
             |storage| storage.delete(&msg.key),
+        );
+        self.operations.delete.record(start.elapsed());
+
+        result
+    }
+}
+
+/// Handler for Write message.
+impl Handler<Write> for StorageManager {
+    type Result = UnitStorageResult;
+
+    fn handle(&mut self, msg: Write, _: &mut Context<Self>) -> Self::Result {
+        let storage = self.storage.as_mut().ok_or_else(|| {
+            WitnetError::from(StorageError::new(
+                StorageErrorKind::Write,
+                "batch".to_string(),
+                "Storage was not properly initialised".to_string(),
+            ))
+        })?;
+        for op in msg.batch.iter() {
+            if let WriteOp::Put(key, _) = op {
+                self.check_quota_for(key)?;
+            }
+        }
+
+        // The storage works on borrowed keys, so keep the owned ones while the batch is written
+        let (keys, values): (Vec<Vec<u8>>, Vec<Option<Vec<u8>>>) = msg
+            .batch
+            .into_iter()
+            .map(|op| match op {
+                WriteOp::Put(key, value) => (key, Some(value)),
+                WriteOp::Delete(key) => (key, None),
+            })
+            .unzip();
+        let mut batch = WriteBatch::new();
+        for (key, value) in keys.iter().zip(values) {
+            match value {
+                Some(value) => batch.put(key.as_slice(), value),
+                None => batch.delete(key.as_slice()),
+            }
+        }
+
+        let start = Instant::now();
+        let result = storage.write(batch);
+        self.operations.write.record(start.elapsed());
+
+        result
+    }
+}
+
+/// Handler for Archive message.
+impl Handler<Archive> for StorageManager {
+    type Result = StorageResult<usize>;
+
+    fn handle(&mut self, msg: Archive, _: &mut Context<Self>) -> Self::Result {
+        let storage = self.storage.as_mut().ok_or_else(|| {
+            WitnetError::from(StorageError::new(
+                StorageErrorKind::Write,
+                "archive".to_string(),
+                "Storage was not properly initialised".to_string(),
+            ))
+        })?;
+
+        let mut archived = 0;
+        for key in &msg.keys {
+            if storage.archive(key)? {
+                archived += 1;
+            }
+        }
+
+        Ok(archived)
+    }
+}
+
+/// Handler for Flush message.
+impl Handler<Flush> for StorageManager {
+    type Result = UnitStorageResult;
+
+    fn handle(&mut self, _msg: Flush, _: &mut Context<Self>) -> Self::Result {
+        self.storage.as_mut().map_or(
+            Err(WitnetError::from(StorageError::new(
+                StorageErrorKind::Write,
+                "flush".to_string(),
+                "Storage was not properly initialised".to_string(),
+            ))),
+            |storage| storage.flush(),
         )
     }
 }
+
+/// Handler for GetMetrics message.
+impl Handler<GetMetrics> for StorageManager {
+    type Result = StorageResult<StorageMetrics>;
+
+    fn handle(&mut self, _msg: GetMetrics, _: &mut Context<Self>) -> Self::Result {
+        let storage = self.storage.as_ref().ok_or_else(|| {
+            WitnetError::from(StorageError::new(
+                StorageErrorKind::Get,
+                "metrics".to_string(),
+                "Storage was not properly initialised".to_string(),
+            ))
+        })?;
+        let size_on_disk =
+            self.db_path
+                .as_ref()
+                .and_then(|path| match metrics::size_on_disk(path) {
+                    Ok(size) => Some(size),
+                    Err(e) => {
+                        warn!("Cannot compute the size of {}: {}", path.display(), e);
+                        None
+                    }
+                });
+
+        Ok(StorageMetrics {
+            size_on_disk,
+            keys: metrics::count_keys(storage)?,
+            operations: self.operations.clone(),
+            cache: storage.cache_stats().map(Into::into),
+        })
+    }
+}
@@ -2,9 +2,11 @@ use actix::Message;
 use std::borrow::Cow;
 
 use std::marker::PhantomData;
+use std::time::Duration;
 use witnet_storage::error::StorageResult;
-use witnet_storage::storage::Storable;
+use witnet_storage::storage::{Storable, WriteBatch};
 
+use super::metrics::StorageMetrics;
 use super::{UnitStorageResult, ValueStorageResult};
 /// Message to indicate that a value is requested from the storage
 pub struct Get<T> {
@@ -36,13 +38,20 @@ pub struct Put {
 
     /// Value to be inserted
     pub value: Vec<u8>,
+
+    /// Time after which the entry expires, if any (see `witnet_storage::expiry`)
+    pub ttl: Option<Duration>,
 }
 
 impl Put {
     /// Create a `Put` message from raw bytes
     pub fn new<K: Into<Cow<'static, [u8]>>>(key: K, value: Vec<u8>) -> Self {
         let key = key.into();
-        Put { key, value }
+        Put {
+            key,
+            value,
+            ttl: None,
+        }
     }
     /// Create a `Put` message by converting the value into bytes
     pub fn from_value<T, K>(key: K, value: &T) -> StorageResult<Self>
@@ -52,7 +61,16 @@ impl Put {
     {
         let value = value.to_bytes()?;
         let key = key.into();
-        Ok(Put { key, value })
+        Ok(Put {
+            key,
+            value,
+            ttl: None,
+        })
+    }
+    /// Make the entry expire after `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
     }
 }
 
@@ -77,3 +95,54 @@ impl Delete {
 impl Message for Delete {
     type Result = UnitStorageResult;
 }
+
+/// Message to indicate that several key-value pairs need to be inserted in or removed from the
+/// storage atomically: either all the operations in the batch are applied or none of them
+pub struct Write {
+    /// Operations to be applied
+    pub batch: WriteBatch<Vec<u8>, Vec<u8>>,
+}
+
+impl Write {
+    /// Create a `Write` message
+    pub fn new(batch: WriteBatch<Vec<u8>, Vec<u8>>) -> Self {
+        Write { batch }
+    }
+}
+
+impl Message for Write {
+    type Result = UnitStorageResult;
+}
+
+/// Message to indicate that some entries need to be moved to the archive (`storage.archive`),
+/// where they can still be read. Returns the number of moved entries.
+pub struct Archive {
+    /// Keys of the entries to be moved
+    pub keys: Vec<Vec<u8>>,
+}
+
+impl Archive {
+    /// Create an `Archive` message
+    pub fn new(keys: Vec<Vec<u8>>) -> Self {
+        Archive { keys }
+    }
+}
+
+impl Message for Archive {
+    type Result = StorageResult<usize>;
+}
+
+/// Message to indicate that every write applied so far needs to reach the disk, even if
+/// `storage.sync_writes` is disabled
+pub struct Flush;
+
+impl Message for Flush {
+    type Result = UnitStorageResult;
+}
+
+/// Message to obtain the metrics of the storage
+pub struct GetMetrics;
+
+impl Message for GetMetrics {
+    type Result = StorageResult<StorageMetrics>;
+}
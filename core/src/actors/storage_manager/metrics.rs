@@ -0,0 +1,200 @@
+//! # Storage metrics
+//!
+//! The storage manager measures the latency of every operation it
+//! handles, and reports them together with the size of the database
+//! files, the number of keys stored in each column family and the
+//! hit rate of the cache, so operators can spot database bloat and IO
+//! bottlenecks.
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use witnet_storage::backends::cached::CacheStats;
+use witnet_storage::error::StorageResult;
+use witnet_storage::storage::Storage;
+
+use crate::actors::storage_keys::column_families;
+
+use super::backend::Backend;
+
+/// Name under which the keys not belonging to any column family are counted
+pub const DEFAULT_COLUMN_FAMILY: &str = "default";
+
+/// Latencies of one kind of operation
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OperationMetrics {
+    /// Number of operations
+    pub count: u64,
+    /// Total time spent in the operations, in microseconds
+    pub total_micros: u64,
+    /// Time spent in the slowest operation, in microseconds
+    pub max_micros: u64,
+}
+
+impl OperationMetrics {
+    /// Record an operation which took `elapsed`
+    pub fn record(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_micros());
+        self.count += 1;
+        self.total_micros = self.total_micros.saturating_add(micros);
+        self.max_micros = self.max_micros.max(micros);
+    }
+
+    /// Mean time spent in an operation, in microseconds
+    pub fn mean_micros(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            self.total_micros / self.count
+        }
+    }
+}
+
+/// Latencies of every kind of operation handled by the storage manager
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OperationsMetrics {
+    /// Reads
+    pub get: OperationMetrics,
+    /// Single writes
+    pub put: OperationMetrics,
+    /// Single deletions
+    pub delete: OperationMetrics,
+    /// Batches of writes
+    pub write: OperationMetrics,
+}
+
+/// Snapshot of the metrics of the storage
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageMetrics {
+    /// Size of the database files, in bytes, if the data is stored on disk
+    pub size_on_disk: Option<u64>,
+    /// Number of keys stored in each column family
+    pub keys: BTreeMap<String, u64>,
+    /// Latencies of the operations since the node started
+    pub operations: OperationsMetrics,
+    /// Usage of the cache, if enabled (`storage.cache_size`)
+    pub cache: Option<CacheMetrics>,
+}
+
+/// Usage of the cache in front of the storage backend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CacheMetrics {
+    /// Reads served from the cache
+    pub hits: u64,
+    /// Reads which had to go to the backend
+    pub misses: u64,
+    /// Fraction of the reads served from the cache
+    pub hit_rate: f64,
+    /// Number of cached entries
+    pub entries: usize,
+    /// Total size of the cached keys and values, in bytes
+    pub size: usize,
+    /// Maximum size of the cached keys and values, in bytes
+    pub capacity: usize,
+}
+
+impl From<CacheStats> for CacheMetrics {
+    fn from(stats: CacheStats) -> Self {
+        CacheMetrics {
+            hits: stats.hits,
+            misses: stats.misses,
+            hit_rate: stats.hit_rate(),
+            entries: stats.entries,
+            size: stats.size,
+            capacity: stats.capacity,
+        }
+    }
+}
+
+/// Count the keys stored in each column family, reading the whole storage
+pub fn count_keys(storage: &Backend) -> StorageResult<BTreeMap<String, u64>> {
+    let column_families = column_families();
+    let mut keys: BTreeMap<String, u64> = column_families
+        .iter()
+        .map(|(name, _)| (name.to_string(), 0))
+        .collect();
+    keys.insert(DEFAULT_COLUMN_FAMILY.to_string(), 0);
+
+    for (key, _) in storage.prefix_iterator(&[])? {
+        let name = column_families
+            .iter()
+            .find(|(_, prefix)| key.starts_with(prefix))
+            .map_or(DEFAULT_COLUMN_FAMILY, |(name, _)| name);
+        *keys.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    Ok(keys)
+}
+
+/// Total size of the files inside a directory, in bytes
+pub fn size_on_disk(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            size_on_disk(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::storage_keys::{block_key, PEERS_KEY};
+    use witnet_data_structures::chain::Hash;
+    use witnet_storage::backends::memory::MemoryStorage;
+
+    #[test]
+    fn record_latencies() {
+        let mut metrics = OperationMetrics::default();
+        assert_eq!(metrics.mean_micros(), 0);
+
+        metrics.record(Duration::from_micros(10));
+        metrics.record(Duration::from_micros(30));
+
+        assert_eq!(metrics.count, 2);
+        assert_eq!(metrics.total_micros, 40);
+        assert_eq!(metrics.max_micros, 30);
+        assert_eq!(metrics.mean_micros(), 20);
+    }
+
+    #[test]
+    fn count_keys_per_column_family() {
+        let mut memory = MemoryStorage::default();
+        memory
+            .put(&block_key(&Hash::SHA256([1; 32])), vec![])
+            .unwrap();
+        memory
+            .put(&block_key(&Hash::SHA256([2; 32])), vec![])
+            .unwrap();
+        memory.put(PEERS_KEY, vec![]).unwrap();
+        memory.put(b"chain", vec![]).unwrap();
+
+        let keys = count_keys(&Backend::Memory(memory)).unwrap();
+
+        assert_eq!(keys["blocks"], 2);
+        assert_eq!(keys["peers"], 1);
+        assert_eq!(keys["utxos"], 0);
+        assert_eq!(keys[DEFAULT_COLUMN_FAMILY], 1);
+    }
+
+    #[test]
+    fn size_of_directory() {
+        let path = std::env::temp_dir().join(format!("witnet-metrics-{}", std::process::id()));
+        fs::create_dir_all(path.join("nested")).unwrap();
+        fs::write(path.join("a"), [0; 10]).unwrap();
+        fs::write(path.join("nested").join("b"), [0; 5]).unwrap();
+
+        assert_eq!(size_on_disk(&path).unwrap(), 15);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+//! # Storage migrations
+//!
+//! The layout of the data persisted by the node changes over time: keys
+//! get renamed and values get encoded differently. The storage keeps the
+//! version of its layout under `SCHEMA_VERSION_KEY` (storages without it
+//! are considered to be version `0`), and this module upgrades storages
+//! written by older releases to the current layout when the node starts.
+//!
+//! When making changes to the layout of the persisted data, bump
+//! `CURRENT_VERSION` and add a migration for it to `MIGRATIONS`.
+//! Storages written for a version newer than the current one are
+//! refused, since this node does not know how to read them.
+use log::warn;
+
+use witnet_storage::error::{StorageError, StorageErrorKind, StorageResult};
+use witnet_storage::storage::{Storage, StorageHelper};
+use witnet_util::error::WitnetError;
+
+use crate::actors::storage_keys::SCHEMA_VERSION_KEY;
+
+use super::backend::Backend;
+
+/// Current version of the storage layout
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Maximum number of entries written at once when moving entries around
+const MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// A migration modifies a storage written for a version so it follows
+/// the layout of the next version, recording the changes it applies.
+type Migration = fn(&mut Backend, &mut Vec<String>) -> StorageResult<()>;
+
+/// Migrations from every version to the next one: the migration at
+/// index `n` upgrades from version `n` to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// Summary of a migration
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    /// Version of the storage before the migration
+    pub from_version: u32,
+    /// Version of the storage after the migration
+    pub to_version: u32,
+    /// Human-readable description of every change applied
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    /// Whether the storage was upgraded by the migration
+    pub fn is_empty(&self) -> bool {
+        self.from_version == self.to_version
+    }
+
+    /// Log a warning listing the changes applied, if any
+    pub fn log(&self) {
+        if self.is_empty() {
+            return;
+        }
+        warn!(
+            "The storage has been migrated from version {} to version {}",
+            self.from_version, self.to_version
+        );
+        for change in &self.changes {
+            warn!("    {}", change);
+        }
+    }
+}
+
+/// Version of the layout of the storage. Empty storages are considered
+/// to be in the current version.
+fn version(storage: &Backend) -> StorageResult<u32> {
+    match storage.get_t::<u32>(SCHEMA_VERSION_KEY)? {
+        Some(version) => Ok(version),
+        None if storage.prefix_iterator(&[])?.next().is_none() => Ok(CURRENT_VERSION),
+        None => Ok(0),
+    }
+}
+
+/// Check that a storage which cannot be migrated, such as a read-only
+/// one, already follows the current version of the storage layout.
+pub fn check_version(storage: &Backend) -> StorageResult<()> {
+    match version(storage)? {
+        CURRENT_VERSION => Ok(()),
+        version => Err(WitnetError::from(StorageError::new(
+            StorageErrorKind::Migration,
+            format!("Version {}", version),
+            format!(
+                "The storage layout is not the supported version {}, and a read-only storage \
+                 cannot be migrated",
+                CURRENT_VERSION
+            ),
+        ))),
+    }
+}
+
+/// Upgrade the storage to the current version of the storage layout.
+/// Empty storages are marked with the current version.
+pub fn migrate(storage: &mut Backend) -> StorageResult<MigrationReport> {
+    let from_version = version(storage)?;
+    if from_version > CURRENT_VERSION {
+        return Err(WitnetError::from(StorageError::new(
+            StorageErrorKind::Migration,
+            format!("Version {}", from_version),
+            format!(
+                "The storage layout is newer than the supported version {}, \
+                 please upgrade the node",
+                CURRENT_VERSION
+            ),
+        )));
+    }
+
+    let mut changes = vec![];
+    for migration in &MIGRATIONS[from_version as usize..] {
+        migration(storage, &mut changes)?;
+    }
+    storage.put_t(SCHEMA_VERSION_KEY, CURRENT_VERSION)?;
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: CURRENT_VERSION,
+        changes,
+    })
+}
+
+/// Version 1: the version of the layout is persisted, the rest of the
+/// layout does not change.
+fn migrate_v0_to_v1(_storage: &mut Backend, changes: &mut Vec<String>) -> StorageResult<()> {
+    changes.push(format!(
+        "Added the storage layout version under `{}`",
+        String::from_utf8_lossy(SCHEMA_VERSION_KEY)
+    ));
+
+    Ok(())
+}
+
+/// Version 2: each data type is stored in its own RocksDB column family.
+/// The entries of the default column family are moved to their column
+/// families in batches, older releases would not find them.
+fn migrate_v1_to_v2(storage: &mut Backend, changes: &mut Vec<String>) -> StorageResult<()> {
+    let moved = storage.move_to_column_families(MIGRATION_BATCH_SIZE)?;
+    changes.push(format!(
+        "Moved {} entries of blocks, transactions, UTXOs, peers and reputation to their own \
+         column families",
+        moved
+    ));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_storage::backends::memory::MemoryStorage;
+
+    #[test]
+    fn migrate_empty_storage() {
+        let mut storage = Backend::Memory(MemoryStorage::default());
+
+        let report = migrate(&mut storage).unwrap();
+
+        assert!(report.is_empty());
+        assert_eq!(
+            storage.get_t::<u32>(SCHEMA_VERSION_KEY).unwrap(),
+            Some(CURRENT_VERSION)
+        );
+    }
+
+    #[test]
+    fn migrate_unversioned_storage() {
+        let mut memory = MemoryStorage::default();
+        memory.put(b"chain", vec![1, 2, 3]).unwrap();
+        let mut storage = Backend::Memory(memory);
+
+        let report = migrate(&mut storage).unwrap();
+
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, CURRENT_VERSION);
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(storage.get(b"chain").unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(migrate(&mut storage).unwrap().from_version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn check_version_of_unversioned_storage() {
+        let mut memory = MemoryStorage::default();
+        memory.put(b"chain", vec![1, 2, 3]).unwrap();
+        let mut storage = Backend::Memory(memory);
+
+        assert!(check_version(&storage).is_err());
+        migrate(&mut storage).unwrap();
+        assert!(check_version(&storage).is_ok());
+    }
+
+    #[test]
+    fn migrate_newer_storage() {
+        let mut storage = Backend::Memory(MemoryStorage::default());
+        storage
+            .put_t(SCHEMA_VERSION_KEY, CURRENT_VERSION + 1)
+            .unwrap();
+
+        assert!(migrate(&mut storage).is_err());
+    }
+}
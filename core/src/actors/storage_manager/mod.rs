@@ -1,32 +1,155 @@
 use actix::{Context, Supervised, SystemService};
 
-use witnet_storage::backends::rocks::RocksStorage;
-use witnet_storage::error::StorageResult;
+use log::{error, info, warn};
+use std::path::PathBuf;
+use witnet_config::config::{Backup as BackupConfig, Storage as StorageConfig, StorageBackend};
+use witnet_storage::error::{StorageError, StorageErrorKind, StorageResult};
+use witnet_storage::expiry;
 use witnet_storage::storage::Storage;
+use witnet_util::error::WitnetError;
+use witnet_util::timestamp::get_timestamp;
+
+use self::backend::Backend;
+use self::metrics::OperationsMetrics;
+use self::quota::QuotaStatus;
+use crate::actors::storage_keys::is_essential;
 
 /// Type aliases for the storage manager results returned
 type ValueStorageResult<T> = StorageResult<Option<T>>;
 type UnitStorageResult = StorageResult<()>;
 
+/// Period of the deletion of the expired entries, in seconds
+const PURGE_EXPIRED_PERIOD_SECONDS: u64 = 60;
+
 mod actor;
+/// Storage backend selected in the configuration
+pub mod backend;
+/// Scheduled backups of the storage
+pub mod backup;
 mod handlers;
 /// Messages for StorageManager
 pub mod messages;
+/// Metrics of the storage
+pub mod metrics;
+/// Upgrades of the layout of the stored data
+pub mod migrations;
+/// Disk quota of the database
+pub mod quota;
+/// Verification of the integrity of the storage
+pub mod verify;
 
 /// Storage manager actor
 #[derive(Default)]
 pub struct StorageManager {
     /// DB storage
-    storage: Option<RocksStorage>,
+    storage: Option<Backend>,
+    /// Directory containing the database files, if the data is stored on disk
+    db_path: Option<PathBuf>,
+    /// Latencies of the operations handled
+    operations: OperationsMetrics,
+    /// Maximum size of the database files, in bytes (`0` means no limit)
+    max_db_size: u64,
+    /// Size of the database files relative to `max_db_size`
+    quota: QuotaStatus,
 }
 
 impl StorageManager {
-    /// Method to create a new storage manager
-    pub fn new(db_root: &str) -> StorageManager {
-        // Build rocks db storage
-        match RocksStorage::new(db_root.to_string()) {
-            Ok(db) => StorageManager { storage: Some(*db) },
-            Err(_) => StorageManager { storage: None },
+    /// Method to create a new storage manager using the backend
+    /// selected in the configuration, upgrading the layout of the
+    /// stored data if needed. Read-only storages must already follow
+    /// the current layout.
+    pub fn new(config: &StorageConfig) -> StorageResult<StorageManager> {
+        let mut storage = Backend::new(config.clone())?;
+        if config.read_only {
+            migrations::check_version(&storage)?;
+        } else {
+            migrations::migrate(&mut storage)?.log();
+        }
+
+        let db_path = match config.backend {
+            StorageBackend::Memory => None,
+            _ => Some(config.db_path.clone()),
+        };
+
+        Ok(StorageManager {
+            storage: Some(*storage),
+            db_path,
+            operations: OperationsMetrics::default(),
+            max_db_size: config.max_db_size,
+            quota: QuotaStatus::Ok,
+        })
+    }
+
+    /// Method to write a backup of the storage, see `backup::write_backup`
+    fn backup(&self, config: &BackupConfig) {
+        if let Some(storage) = &self.storage {
+            match backup::write_backup(storage, &config.destination, config.retention) {
+                Ok(path) => info!("Storage backup written to {}", path.display()),
+                Err(e) => error!("Error writing storage backup: {}", e),
+            }
+        }
+    }
+
+    /// Method to check the size of the database files against `max_db_size`, raising an alert
+    /// when it changes. Returns the new status.
+    fn check_quota(&mut self) -> QuotaStatus {
+        let size = match &self.db_path {
+            Some(path) if self.max_db_size > 0 => match metrics::size_on_disk(path) {
+                Ok(size) => size,
+                Err(e) => {
+                    warn!("Cannot compute the size of {}: {}", path.display(), e);
+                    return self.quota;
+                }
+            },
+            _ => return QuotaStatus::Ok,
+        };
+
+        let status = quota::status(size, self.max_db_size);
+        if status != self.quota {
+            match status {
+                QuotaStatus::Ok => info!(
+                    "The database uses {} bytes, below its maximum size of {} bytes",
+                    size, self.max_db_size
+                ),
+                QuotaStatus::Approaching => warn!(
+                    "The database uses {} bytes and is approaching its maximum size of {} bytes, \
+                     pruning old data",
+                    size, self.max_db_size
+                ),
+                QuotaStatus::Exceeded => error!(
+                    "The database uses {} bytes and exceeds its maximum size of {} bytes, \
+                     refusing the writes of non-essential data. Free some disk space or increase \
+                     `storage.max_db_size`",
+                    size, self.max_db_size
+                ),
+            }
+        }
+        self.quota = status;
+
+        status
+    }
+
+    /// Method to refuse writing non-essential data when the database exceeds its maximum size
+    fn check_quota_for(&self, key: &[u8]) -> StorageResult<()> {
+        if self.quota == QuotaStatus::Exceeded && !is_essential(key) {
+            Err(WitnetError::from(StorageError::new(
+                StorageErrorKind::QuotaExceeded,
+                String::from_utf8_lossy(key).to_string(),
+                "The database exceeds its maximum size (`storage.max_db_size`)".to_string(),
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Method to delete the entries whose TTL has expired, see `expiry::purge_expired`
+    fn purge_expired(&mut self) {
+        if let Some(storage) = &mut self.storage {
+            match expiry::purge_expired(storage, get_timestamp()) {
+                Ok(0) => {}
+                Ok(purged) => info!("Deleted {} expired entries from the storage", purged),
+                Err(e) => error!("Error deleting expired entries from the storage: {}", e),
+            }
         }
     }
 }
@@ -0,0 +1,71 @@
+//! # Disk quota
+//!
+//! When `storage.max_db_size` is set, the storage manager periodically
+//! checks the size of the database files. Once the size approaches
+//! the limit, it raises an alert and triggers the pruning of old
+//! data, and once the limit is exceeded it also refuses the writes of
+//! non-essential data (see `storage_keys::is_essential`), so an
+//! unsupervised node does not fill its disk.
+
+/// Fraction of `max_db_size` from which the size is considered to be
+/// approaching the limit
+pub const APPROACHING_RATIO: f64 = 0.9;
+
+/// Period of the check of the size of the database files, in seconds
+pub const CHECK_PERIOD_SECONDS: u64 = 60;
+
+/// Size of the database files relative to `storage.max_db_size`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaStatus {
+    /// There is no limit, or the size is far from it
+    Ok,
+    /// The size is approaching the limit: old data should be pruned
+    Approaching,
+    /// The size exceeds the limit: only essential data can be written
+    Exceeded,
+}
+
+impl Default for QuotaStatus {
+    fn default() -> Self {
+        QuotaStatus::Ok
+    }
+}
+
+/// Status of a database of `size` bytes whose limit is `max_db_size`
+/// bytes (`0` means no limit)
+pub fn status(size: u64, max_db_size: u64) -> QuotaStatus {
+    if max_db_size == 0 {
+        QuotaStatus::Ok
+    } else if size >= max_db_size {
+        QuotaStatus::Exceeded
+    } else if size as f64 >= max_db_size as f64 * APPROACHING_RATIO {
+        QuotaStatus::Approaching
+    } else {
+        QuotaStatus::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::storage_keys::{block_key, is_essential, CHAIN_KEY, PEERS_KEY};
+    use witnet_data_structures::chain::Hash;
+
+    #[test]
+    fn quota_status() {
+        assert_eq!(status(1 << 40, 0), QuotaStatus::Ok);
+        assert_eq!(status(899, 1000), QuotaStatus::Ok);
+        assert_eq!(status(900, 1000), QuotaStatus::Approaching);
+        assert_eq!(status(999, 1000), QuotaStatus::Approaching);
+        assert_eq!(status(1000, 1000), QuotaStatus::Exceeded);
+        assert_eq!(status(2000, 1000), QuotaStatus::Exceeded);
+    }
+
+    #[test]
+    fn essential_keys() {
+        assert!(is_essential(CHAIN_KEY));
+        assert!(is_essential(&block_key(&Hash::SHA256([1; 32]))));
+        assert!(!is_essential(PEERS_KEY));
+        assert!(!is_essential(b"expiry-peers"));
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use witnet_crypto::hash::calculate_sha256;
+use witnet_data_structures::chain::{Block, ChainInfo, Hash};
+use witnet_p2p::peers::Peers;
+use witnet_storage::error::StorageResult;
+use witnet_storage::storage::{Storable, Storage};
+
+use crate::actors::storage_keys::{block_key, BLOCKS_PREFIX, CHAIN_KEY, PEERS_KEY};
+
+use super::backend::Backend;
+
+/// A corrupted entry found in the storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// Key of the entry
+    pub key: Vec<u8>,
+    /// Description of the problem
+    pub reason: String,
+}
+
+/// Printable keys are written as they are, and the hash of block keys in hexadecimal
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.key.starts_with(BLOCKS_PREFIX) {
+            write!(f, "{}", String::from_utf8_lossy(BLOCKS_PREFIX))?;
+            for byte in &self.key[BLOCKS_PREFIX.len()..] {
+                write!(f, "{:02x}", byte)?;
+            }
+        } else {
+            write!(f, "{}", String::from_utf8_lossy(&self.key))?;
+        }
+
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Result of verifying the storage
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VerificationReport {
+    /// Number of entries checked
+    pub entries: usize,
+    /// Number of blocks checked
+    pub blocks: usize,
+    /// Corrupted entries
+    pub problems: Vec<Problem>,
+}
+
+impl VerificationReport {
+    /// Whether no corrupted entries were found
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn problem(&mut self, key: &[u8], reason: String) {
+        self.problems.push(Problem {
+            key: key.to_vec(),
+            reason,
+        });
+    }
+}
+
+/// Walk all the persisted entries checking that they can be decoded, that every block is stored
+/// under its hash and that it is linked to a stored block or to the genesis block.
+pub fn verify(storage: &Backend) -> StorageResult<VerificationReport> {
+    let mut report = VerificationReport::default();
+    let mut genesis_hash = None;
+    let mut blocks = HashMap::new();
+
+    for (key, value) in storage.prefix_iterator(&[])? {
+        report.entries += 1;
+
+        if key == CHAIN_KEY {
+            match ChainInfo::from_bytes(&value) {
+                Ok(chain_info) => genesis_hash = Some(chain_info.consensus_constants.genesis_hash),
+                Err(e) => report.problem(&key, format!("Cannot decode the chain info: {}", e)),
+            }
+        } else if key == PEERS_KEY {
+            if let Err(e) = Peers::from_bytes(&value) {
+                report.problem(&key, format!("Cannot decode the peers: {}", e));
+            }
+        } else if key.starts_with(BLOCKS_PREFIX) {
+            report.blocks += 1;
+            let hash = calculate_sha256(&value);
+            if key != block_key(&hash) {
+                report.problem(
+                    &key,
+                    "The hash of the block does not match its key".to_string(),
+                );
+            }
+            match Block::from_bytes(&value) {
+                Ok(block) => {
+                    blocks.insert(hash, block);
+                }
+                Err(e) => report.problem(&key, format!("Cannot decode the block: {}", e)),
+            }
+        }
+    }
+
+    for (hash, block) in &blocks {
+        let beacon = &block.header.block_header.beacon;
+        if Some(beacon.hash_prev_block) == genesis_hash {
+            continue;
+        }
+        match blocks.get(&beacon.hash_prev_block) {
+            Some(previous) => {
+                if previous.header.block_header.beacon.checkpoint >= beacon.checkpoint {
+                    report.problem(
+                        &block_key(hash),
+                        "The previous block is not from an earlier checkpoint".to_string(),
+                    );
+                }
+            }
+            None => report.problem(
+                &block_key(hash),
+                "The previous block is missing".to_string(),
+            ),
+        }
+    }
+    report.problems.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_data_structures::chain::*;
+    use witnet_storage::backends::memory::MemoryStorage;
+
+    fn block(checkpoint: Epoch, hash_prev_block: Hash) -> Block {
+        Block {
+            header: BlockHeaderWithProof {
+                block_header: BlockHeader {
+                    version: 1,
+                    beacon: CheckpointBeacon {
+                        checkpoint,
+                        hash_prev_block,
+                    },
+                    hash_merkle_root: Hash::SHA256([3; 32]),
+                },
+                proof: LeadershipProof {
+                    block_sig: None,
+                    influence: 99999,
+                },
+            },
+            txn_count: 1,
+            txns: vec![Transaction],
+        }
+    }
+
+    fn put_block(storage: &mut MemoryStorage, block: &Block) -> Hash {
+        let bytes = block.to_bytes().unwrap();
+        let hash = calculate_sha256(&bytes);
+        storage.put(&block_key(&hash), bytes).unwrap();
+
+        hash
+    }
+
+    #[test]
+    fn verify_storage() {
+        let genesis_hash = Hash::SHA256([4; 32]);
+        let mut storage = MemoryStorage::default();
+        let first = put_block(&mut storage, &block(1, genesis_hash));
+        put_block(&mut storage, &block(2, first));
+        let orphan = put_block(&mut storage, &block(3, Hash::SHA256([5; 32])));
+        let chain_info = ChainInfo {
+            environment: Environment::Testnet1,
+            consensus_constants: ConsensusConstants {
+                checkpoint_zero_timestamp: 0,
+                checkpoints_period: 90,
+                genesis_hash,
+                reputation_demurrage: 0.0,
+                reputation_punishment: 0.0,
+            },
+            highest_block_checkpoint: CheckpointBeacon {
+                checkpoint: 0,
+                hash_prev_block: genesis_hash,
+            },
+        };
+        storage
+            .put(CHAIN_KEY, chain_info.to_bytes().unwrap())
+            .unwrap();
+        storage.put(PEERS_KEY, b"not peers".to_vec()).unwrap();
+
+        let report = verify(&Backend::Memory(storage)).unwrap();
+
+        assert_eq!(report.entries, 5);
+        assert_eq!(report.blocks, 3);
+        let mut problem_keys: Vec<_> = report.problems.iter().map(|p| p.key.clone()).collect();
+        problem_keys.sort();
+        let mut expected = vec![block_key(&orphan), PEERS_KEY.to_vec()];
+        expected.sort();
+        assert_eq!(problem_keys, expected);
+    }
+}
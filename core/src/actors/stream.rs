@@ -0,0 +1,159 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::Poll;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls::{
+        internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
+        ClientConfig, ClientSession, NoClientAuth, ServerConfig, ServerSession,
+    },
+    TlsAcceptor, TlsConnector, TlsStream,
+};
+
+use witnet_config::config::Tls;
+use witnet_util::net::canonical_socket_addr;
+
+/// Stream of a connection with a peer, which is either a plain TCP stream, a TLS stream over TCP
+/// if the node terminates TLS on its P2P listener or the peer does on its own, or a TCP stream to a
+/// SOCKS5 proxy if the node connects to its peers through a proxy. The connections of the JSON-RPC
+/// clients are either plain TCP streams or TLS streams too
+pub enum PeerStream {
+    /// Plain TCP stream
+    Tcp(TcpStream),
+    /// TLS stream of an inbound connection
+    Tls(TlsStream<TcpStream, ServerSession>),
+    /// TLS stream of an outbound connection to a peer terminating TLS, either direct or through
+    /// the proxy, with the address of the peer
+    TlsClient(TlsStream<TcpStream, ClientSession>, SocketAddr),
+    /// TCP stream of an outbound connection to a SOCKS5 proxy, with the address of the peer to
+    /// which the proxy is connected
+    Socks(TcpStream, SocketAddr),
+}
+
+impl PeerStream {
+    /// Underlying TCP stream
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            PeerStream::Tcp(stream) => stream,
+            PeerStream::Tls(stream) => stream.get_ref().0,
+            PeerStream::TlsClient(stream, _) => stream.get_ref().0,
+            PeerStream::Socks(stream, _) => stream,
+        }
+    }
+
+    /// Socket address of the remote peer, which is not the address of the proxy for connections
+    /// through a proxy. IPv4 peers connected to a dual-stack listener get their IPv4 address
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            PeerStream::Socks(_, peer_addr) | PeerStream::TlsClient(_, peer_addr) => Ok(*peer_addr),
+            _ => self.tcp().peer_addr().map(canonical_socket_addr),
+        }
+    }
+
+    /// Socket address of the local end of the connection
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.tcp().local_addr().map(canonical_socket_addr)
+    }
+}
+
+impl Read for PeerStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            PeerStream::Tcp(stream) | PeerStream::Socks(stream, _) => stream.read(buf),
+            PeerStream::Tls(stream) => stream.read(buf),
+            PeerStream::TlsClient(stream, _) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for PeerStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            PeerStream::Tcp(stream) | PeerStream::Socks(stream, _) => stream.write(buf),
+            PeerStream::Tls(stream) => stream.write(buf),
+            PeerStream::TlsClient(stream, _) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            PeerStream::Tcp(stream) | PeerStream::Socks(stream, _) => stream.flush(),
+            PeerStream::Tls(stream) => stream.flush(),
+            PeerStream::TlsClient(stream, _) => stream.flush(),
+        }
+    }
+}
+
+impl AsyncRead for PeerStream {}
+
+impl AsyncWrite for PeerStream {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        match self {
+            PeerStream::Tcp(stream) | PeerStream::Socks(stream, _) => AsyncWrite::shutdown(stream),
+            PeerStream::Tls(stream) => stream.shutdown(),
+            PeerStream::TlsClient(stream, _) => stream.shutdown(),
+        }
+    }
+}
+
+/// Build the acceptor of the TLS connections from the PEM files of the certificate chain and the
+/// private key of a server
+pub fn load_tls_acceptor(tls: &Tls) -> Result<TlsAcceptor, String> {
+    let read =
+        |path: &Path| fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e));
+    let (cert_path, key_path) = match (&tls.cert, &tls.key) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        _ => return Err("TLS needs both a certificate and a private key".to_string()),
+    };
+
+    let cert_chain = certs(&mut read(cert_path)?.as_slice())
+        .ok()
+        .filter(|certs| !certs.is_empty())
+        .ok_or_else(|| format!("No certificates found in {}", cert_path.display()))?;
+
+    // Keys can be encoded either as PKCS#8 or as PKCS#1 (RSA)
+    let key_pem = read(key_path)?;
+    let key = pkcs8_private_keys(&mut key_pem.as_slice())
+        .ok()
+        .filter(|keys| !keys.is_empty())
+        .or_else(|| rsa_private_keys(&mut key_pem.as_slice()).ok())
+        .and_then(|mut keys| keys.pop())
+        .ok_or_else(|| format!("No private key found in {}", key_path.display()))?;
+
+    let mut server_config = ServerConfig::new(NoClientAuth::new());
+    server_config
+        .set_single_cert(cert_chain, key)
+        .map_err(|e| format!("Invalid TLS certificate or key: {}", e))?;
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Build the connector of the TLS connections to the peers from the PEM file of the certificates of
+/// the authorities that sign their certificates
+pub fn load_tls_connector(ca_path: &Path) -> Result<TlsConnector, String> {
+    let ca_pem =
+        fs::read(ca_path).map_err(|e| format!("Cannot read {}: {}", ca_path.display(), e))?;
+
+    let mut client_config = ClientConfig::new();
+    match client_config
+        .root_store
+        .add_pem_file(&mut ca_pem.as_slice())
+    {
+        Ok((valid, _)) if valid > 0 => {}
+        _ => {
+            return Err(format!(
+                "No valid certificates found in {}",
+                ca_path.display()
+            ))
+        }
+    }
+
+    Ok(TlsConnector::from(Arc::new(client_config)))
+}
@@ -0,0 +1,140 @@
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use futures::{Async, Future, Poll};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    timer::Delay,
+};
+
+use witnet_p2p::rate_limits::BandwidthLimit;
+
+/// Bandwidth limit which can be shared by several streams
+pub type SharedBandwidthLimit = Arc<Mutex<BandwidthLimit>>;
+
+/// Bytes transferred by several streams
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Traffic {
+    /// Number of bytes read
+    pub received: u64,
+    /// Number of bytes written
+    pub sent: u64,
+}
+
+/// Traffic counter which can be shared by several streams
+pub type SharedTraffic = Arc<Mutex<Traffic>>;
+
+/// Stream whose reads and writes are throttled to stay under some bandwidth limits, such as the
+/// limits of all the connections with the peers and the limits of each connection
+///
+/// When no more bytes can be transferred, the reads and writes return `WouldBlock` and the current
+/// task is woken up when the limits allow transferring bytes again.
+pub struct Throttled<S> {
+    /// Throttled stream
+    stream: S,
+    /// Limits of the bytes read
+    download: Vec<SharedBandwidthLimit>,
+    /// Limits of the bytes written
+    upload: Vec<SharedBandwidthLimit>,
+    /// Counter of the bytes read and written
+    traffic: SharedTraffic,
+    /// Timer waking up the task waiting to read
+    read_delay: Option<Delay>,
+    /// Timer waking up the task waiting to write
+    write_delay: Option<Delay>,
+}
+
+impl<S> Throttled<S> {
+    /// Throttle `stream` under the `download` limits when reading and the `upload` limits when
+    /// writing, counting the bytes transferred in `traffic`
+    pub fn new(
+        stream: S,
+        download: Vec<SharedBandwidthLimit>,
+        upload: Vec<SharedBandwidthLimit>,
+        traffic: SharedTraffic,
+    ) -> Self {
+        Throttled {
+            stream,
+            download,
+            upload,
+            traffic,
+            read_delay: None,
+            write_delay: None,
+        }
+    }
+}
+
+/// Number of bytes, up to `wanted`, which can be transferred now under `limits`. If none, returns
+/// `WouldBlock` and sets `delay` to wake up the current task when some bytes can be transferred
+fn allowance(
+    limits: &[SharedBandwidthLimit],
+    delay: &mut Option<Delay>,
+    wanted: usize,
+) -> io::Result<usize> {
+    let now = Instant::now();
+    let allowed = limits
+        .iter()
+        .map(|limit| limit.lock().unwrap().available(now))
+        .min()
+        .map_or(wanted, |allowed| allowed.min(wanted));
+    if allowed > 0 || wanted == 0 {
+        *delay = None;
+        return Ok(allowed);
+    }
+
+    let next_available = limits
+        .iter()
+        .map(|limit| limit.lock().unwrap().next_available())
+        .max()
+        .unwrap_or(now);
+    let mut timer = Delay::new(next_available);
+    if let Ok(Async::Ready(())) = timer.poll() {
+        // The timer already expired, so the task is woken up right away
+        futures::task::current().notify();
+    }
+    *delay = Some(timer);
+
+    Err(io::ErrorKind::WouldBlock.into())
+}
+
+/// Count the bytes transferred in all the limits
+fn consume(limits: &[SharedBandwidthLimit], bytes: usize) {
+    for limit in limits {
+        limit.lock().unwrap().consume(bytes);
+    }
+}
+
+impl<S: Read> Read for Throttled<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let allowed = allowance(&self.download, &mut self.read_delay, buf.len())?;
+        let read = self.stream.read(&mut buf[..allowed])?;
+        consume(&self.download, read);
+        self.traffic.lock().unwrap().received += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for Throttled<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let allowed = allowance(&self.upload, &mut self.write_delay, buf.len())?;
+        let written = self.stream.write(&buf[..allowed])?;
+        consume(&self.upload, written);
+        self.traffic.lock().unwrap().sent += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for Throttled<S> {}
+
+impl<S: AsyncWrite> AsyncWrite for Throttled<S> {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.stream.shutdown()
+    }
+}
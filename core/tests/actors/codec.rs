@@ -20,7 +20,7 @@ fn core_actors_codec_p2p_decoder() {
 
     assert_eq!(
         msg,
-        P2PCodec::decode(&mut P2PCodec {}, &mut buf)
+        P2PCodec::decode(&mut P2PCodec::default(), &mut buf)
             .unwrap()
             .unwrap()
     );
@@ -45,6 +45,24 @@ fn core_actors_codec_p2p_encoder() {
     );
 
     let mut dst = BytesMut::with_capacity(1024);
-    P2PCodec::encode(&mut P2PCodec {}, decoded, &mut dst).unwrap();
+    P2PCodec::encode(&mut P2PCodec::default(), decoded, &mut dst).unwrap();
     assert_eq!(dst, encoded);
 }
+
+#[test]
+fn core_actors_codec_p2p_decoder_rejects_oversized_frames() {
+    let mut codec = P2PCodec::new(16);
+
+    // Only the header of a frame bigger than the limit has arrived
+    let mut buf: BytesMut = BytesMut::from([0, 17, 1, 2].to_vec());
+    assert!(codec.decode(&mut buf).is_err());
+
+    // A frame within the limit is decoded once it is complete
+    let mut buf: BytesMut = BytesMut::from([0, 2, 1].to_vec());
+    assert_eq!(codec.decode(&mut buf).unwrap(), None);
+    buf.extend_from_slice(&[2]);
+    assert_eq!(
+        codec.decode(&mut buf).unwrap(),
+        Some(BytesMut::from([1, 2].to_vec()))
+    );
+}
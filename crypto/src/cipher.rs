@@ -0,0 +1,105 @@
+//! Authenticated encryption with keys derived from a secret
+
+use std::fmt;
+
+use crypto::aead::{AeadDecryptor, AeadEncryptor};
+use crypto::chacha20poly1305::ChaCha20Poly1305;
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::Sha256;
+use rand::{thread_rng, Rng};
+
+/// Length of the encryption key, in bytes
+pub const KEY_LENGTH: usize = 32;
+
+/// Length of the random nonce prepended to every ciphertext, in bytes. The ChaCha20-Poly1305 of
+/// `rust-crypto` is the original construction, whose nonces are 8 bytes long
+pub const NONCE_LENGTH: usize = 8;
+
+/// Length of the authentication tag appended to every ciphertext, in bytes
+pub const TAG_LENGTH: usize = 16;
+
+/// Recommended length of the salt used to derive keys, in bytes
+pub const SALT_LENGTH: usize = 16;
+
+/// Number of PBKDF2 iterations used to derive keys
+pub const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Error returned when a ciphertext cannot be decrypted, because it is malformed, it was
+/// modified or it was encrypted with a different key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecryptionError;
+
+impl fmt::Display for DecryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cannot decrypt the data: wrong key or corrupted data")
+    }
+}
+
+impl std::error::Error for DecryptionError {}
+
+/// ChaCha20-Poly1305 cipher whose key is derived from a secret, such as a passphrase, using
+/// PBKDF2-HMAC-SHA256
+#[derive(Clone)]
+pub struct Cipher {
+    key: [u8; KEY_LENGTH],
+}
+
+/// The key is never printed
+impl fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cipher")
+    }
+}
+
+impl Cipher {
+    /// Derive the key from a secret and a salt
+    pub fn from_secret(secret: &[u8], salt: &[u8]) -> Self {
+        let mut mac = Hmac::new(Sha256::new(), secret);
+        let mut key = [0; KEY_LENGTH];
+        pbkdf2(&mut mac, salt, PBKDF2_ITERATIONS, &mut key);
+
+        Cipher { key }
+    }
+
+    /// Generate a random salt to derive a new key
+    pub fn generate_salt() -> [u8; SALT_LENGTH] {
+        let mut salt = [0; SALT_LENGTH];
+        thread_rng().fill(&mut salt);
+
+        salt
+    }
+
+    /// Encrypt some data, returning the random nonce, the ciphertext and the authentication tag
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let mut nonce = [0; NONCE_LENGTH];
+        thread_rng().fill(&mut nonce);
+
+        let mut ciphertext = vec![0; plaintext.len()];
+        let mut tag = [0; TAG_LENGTH];
+        ChaCha20Poly1305::new(&self.key, &nonce, &[]).encrypt(plaintext, &mut ciphertext, &mut tag);
+
+        let mut output = Vec::with_capacity(NONCE_LENGTH + plaintext.len() + TAG_LENGTH);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        output.extend_from_slice(&tag);
+
+        output
+    }
+
+    /// Decrypt data returned by `encrypt`
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, DecryptionError> {
+        if data.len() < NONCE_LENGTH + TAG_LENGTH {
+            return Err(DecryptionError);
+        }
+        let (nonce, rest) = data.split_at(NONCE_LENGTH);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+
+        let mut plaintext = vec![0; ciphertext.len()];
+        if ChaCha20Poly1305::new(&self.key, nonce, &[]).decrypt(ciphertext, &mut plaintext, tag) {
+            Ok(plaintext)
+        } else {
+            Err(DecryptionError)
+        }
+    }
+}
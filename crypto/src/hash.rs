@@ -13,3 +13,31 @@ pub fn calculate_sha256(bytes: &[u8]) -> Hash {
     hasher.result(&mut hash);
     Hash::SHA256(hash)
 }
+
+/// Incremental SHA256 hasher, for data that is not available at once
+pub struct Sha256Hasher(Sha256);
+
+impl Default for Sha256Hasher {
+    fn default() -> Self {
+        Sha256Hasher(Sha256::new())
+    }
+}
+
+impl Sha256Hasher {
+    /// Create a new hasher
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add some bytes to the hashed data
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.input(bytes);
+    }
+
+    /// Calculate the SHA256 hash of all the data added so far
+    pub fn finish(mut self) -> Hash {
+        let mut hash = [0; 32];
+        self.0.result(&mut hash);
+        Hash::SHA256(hash)
+    }
+}
@@ -9,3 +9,6 @@
 
 /// Hash functions
 pub mod hash;
+
+/// Authenticated encryption
+pub mod cipher;
@@ -1,6 +1,7 @@
 use witnet_data_structures::chain::Hash;
 
-use witnet_crypto::hash::calculate_sha256;
+use witnet_crypto::cipher::{Cipher, DecryptionError, NONCE_LENGTH, TAG_LENGTH};
+use witnet_crypto::hash::{calculate_sha256, Sha256Hasher};
 
 #[test]
 fn sha256() {
@@ -12,3 +13,44 @@ fn sha256() {
     let witnet_hash = calculate_sha256(b"WITNET");
     assert_eq!(witnet_hash, Hash::SHA256(expected_witnet_hash));
 }
+
+#[test]
+fn sha256_incremental() {
+    let mut hasher = Sha256Hasher::new();
+    hasher.update(b"WIT");
+    hasher.update(b"NET");
+
+    assert_eq!(hasher.finish(), calculate_sha256(b"WITNET"));
+}
+
+#[test]
+fn cipher_roundtrip() {
+    let cipher = Cipher::from_secret(b"passphrase", b"salt");
+    let ciphertext = cipher.encrypt(b"WITNET");
+
+    assert_eq!(ciphertext.len(), NONCE_LENGTH + 6 + TAG_LENGTH);
+    assert_ne!(&ciphertext[NONCE_LENGTH..NONCE_LENGTH + 6], b"WITNET");
+    assert_eq!(cipher.decrypt(&ciphertext), Ok(b"WITNET".to_vec()));
+    // Every encryption uses a new nonce
+    assert_ne!(cipher.encrypt(b"WITNET"), ciphertext);
+}
+
+#[test]
+fn cipher_wrong_key() {
+    let ciphertext = Cipher::from_secret(b"passphrase", b"salt").encrypt(b"WITNET");
+
+    let other_secret = Cipher::from_secret(b"other", b"salt");
+    let other_salt = Cipher::from_secret(b"passphrase", b"pepper");
+    assert_eq!(other_secret.decrypt(&ciphertext), Err(DecryptionError));
+    assert_eq!(other_salt.decrypt(&ciphertext), Err(DecryptionError));
+}
+
+#[test]
+fn cipher_tampered_data() {
+    let cipher = Cipher::from_secret(b"passphrase", b"salt");
+    let mut ciphertext = cipher.encrypt(b"WITNET");
+    ciphertext[NONCE_LENGTH] ^= 1;
+
+    assert_eq!(cipher.decrypt(&ciphertext), Err(DecryptionError));
+    assert_eq!(cipher.decrypt(b"short"), Err(DecryptionError));
+}
@@ -3,30 +3,59 @@ use std::u32::MAX as U32_MAX;
 
 use rand::{thread_rng, Rng};
 
-use crate::chain::{Block, BlockHeaderWithProof, CheckpointBeacon, InvVector, Transaction};
+use crate::chain::{
+    Block, BlockHeaderWithProof, CheckpointBeacon, Hash, HashedBlockHeader, InvVector, Transaction,
+};
 use crate::types::{
-    Address, Command, GetBlocks, GetData, GetPeers, Inv, IpAddress, Message, Peers, Ping, Pong,
-    Verack, Version,
+    Address, Command, GetBlocks, GetData, GetHeaders, GetPeers, Headers, Inv, IpAddress, Message,
+    Peers, Ping, Pong, Verack, Version,
 };
 
-use witnet_util::timestamp::get_timestamp;
+use witnet_util::{net::canonical_socket_addr, timestamp::get_timestamp};
 
 ////////////////////////////////////////////////////////////////////////////////////////
 // PROTOCOL MESSAGES CONSTANTS
 ////////////////////////////////////////////////////////////////////////////////////////
-/// Magic number
+/// Magic number, which is the default of `connections.magic_number`. Sessions stamp their
+/// messages with the magic number of the network of the node
 pub const MAGIC: u16 = 0xABCD;
 
-/// Protocol version (used in handshake)
+/// Protocol version (used in handshake). It is not configurable, not even for the custom
+/// environments
 pub const PROTOCOL_VERSION: u32 = 0x0000_0001;
 
-/// Capabilities
-pub const CAPABILITIES: u64 = 0x0000_0000_0000_0001;
+/// Oldest protocol version of the peers the node can establish sessions with
+pub const MIN_PROTOCOL_VERSION: u32 = 0x0000_0001;
+
+/// Feature bit of the nodes storing and serving the full block chain
+pub const NODE_NETWORK: u64 = 0x0000_0000_0000_0001;
+
+/// Feature bit reserved for the relay of compact blocks: block headers with short transaction
+/// identifiers instead of full transactions. Only the bit is reserved: the relay itself is
+/// deferred, and the bit is not advertised, until `Transaction` stops being a unit struct with no
+/// contents to identify it by
+// FIXME(#99): implement the compact block relay and advertise the feature once transactions are
+// defined
+pub const NODE_COMPACT_BLOCKS: u64 = 0x0000_0000_0000_0002;
+
+/// Feature bit of the nodes compressing the messages with Snappy, if `connections.compression` is
+/// set to `snappy`
+pub const NODE_SNAPPY: u64 = 0x0000_0000_0000_0004;
+
+/// Feature bit of the nodes serving block headers (`GET_HEADERS` and `HEADERS` messages), from
+/// which the headers-first synchronization can download the chain
+pub const NODE_HEADERS: u64 = 0x0000_0000_0000_0008;
+
+/// Capabilities: bitfield of the features supported by every node. Optional features are added to
+/// them when enabled, and the result is advertised in the handshake. Only the features supported by
+/// both ends of a session are used in it
+pub const CAPABILITIES: u64 = NODE_NETWORK | NODE_HEADERS;
 
 /// User agent
 pub const USER_AGENT: &str = "full-node-desktop-edition";
 
-/// Genesis block
+/// Genesis block identifier used when the node does not know the hash of the genesis block. The
+/// sessions announce the identifier of the genesis block of their network, see `genesis_id`
 pub const GENESIS: u64 = 0x0123_4567_89AB_CDEF;
 
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -48,6 +77,18 @@ impl Message {
         }))
     }
 
+    /// Function to build GetHeaders messages
+    pub fn build_get_headers(highest_block_checkpoint: CheckpointBeacon) -> Message {
+        Message::build_message(Command::GetHeaders(GetHeaders {
+            highest_block_checkpoint,
+        }))
+    }
+
+    /// Function to build Headers messages
+    pub fn build_headers(headers: Vec<HashedBlockHeader>) -> Message {
+        Message::build_message(Command::Headers(Headers { headers }))
+    }
+
     /// Function to build GetPeers messages
     pub fn build_get_peers() -> Message {
         Message::build_message(Command::GetPeers(GetPeers))
@@ -83,16 +124,18 @@ impl Message {
         sender_addr: SocketAddr,
         receiver_addr: SocketAddr,
         last_epoch: u32,
+        capabilities: u64,
+        genesis: u64,
     ) -> Message {
         Message::build_message(Command::Version(Version {
             version: PROTOCOL_VERSION,
             timestamp: get_timestamp(),
-            capabilities: CAPABILITIES,
+            capabilities,
             sender_address: to_address(sender_addr),
             receiver_address: to_address(receiver_addr),
             user_agent: USER_AGENT.to_string(),
             last_epoch,
-            genesis: GENESIS,
+            genesis,
             nonce: random_nonce(),
         }))
     }
@@ -128,14 +171,25 @@ impl Message {
 ////////////////////////////////////////////////////////////////////////////////////////
 // AUX FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////////////
+/// Function to get the identifier of a genesis block announced in the Version messages, which is
+/// made of the first 8 bytes of its hash
+pub fn genesis_id(genesis_hash: &Hash) -> u64 {
+    let Hash::SHA256(bytes) = *genesis_hash;
+
+    bytes[..8]
+        .iter()
+        .fold(0, |id, byte| (id << 8) | u64::from(*byte))
+}
+
 /// Function to get a random nonce
 fn random_nonce() -> u64 {
     thread_rng().gen()
 }
 
-/// Function to build address witnet type from socket addr
+/// Function to build address witnet type from socket addr. IPv4-mapped IPv6 addresses are
+/// written as IPv4 addresses
 fn to_address(socket_addr: SocketAddr) -> Address {
-    match socket_addr {
+    match canonical_socket_addr(socket_addr) {
         SocketAddr::V4(addr) => Address {
             ip: {
                 let ip = u32::from(addr.ip().to_owned());
@@ -159,10 +213,11 @@ fn to_address(socket_addr: SocketAddr) -> Address {
 }
 
 /// Function to build a [SocketAddr](std::net::SocketAddr) from a
-/// Witnet [Address](types::Address)
+/// Witnet [Address](types::Address). IPv4-mapped IPv6 addresses are
+/// converted into IPv4 addresses
 pub fn from_address(addr: &Address) -> SocketAddr {
     let ip: IpAddr = addr.ip.into();
-    SocketAddr::from((ip, addr.port))
+    canonical_socket_addr(SocketAddr::from((ip, addr.port)))
 }
 
 impl From<IpAddress> for IpAddr {
@@ -238,4 +293,27 @@ mod tests {
 
         assert_eq!(socket_addr, expected);
     }
+
+    #[test]
+    fn test_ipv4_mapped_address() {
+        // IPv4 peers seen through a dual-stack socket are sent as IPv4 addresses
+        let socket_addr: SocketAddr = "[::ffff:127.0.0.1]:3000".parse().unwrap();
+        let witnet_addr: Address = to_address(socket_addr);
+
+        assert_eq!(witnet_addr.ip, IpAddress::Ipv4 { ip: 2130706433 });
+
+        // And received as IPv4 addresses
+        let witnet_addr: Address = Address {
+            ip: IpAddress::Ipv6 {
+                ip0: 0,
+                ip1: 0,
+                ip2: 0xffff,
+                ip3: 2130706433,
+            },
+            port: 3000,
+        };
+        let expected: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+
+        assert_eq!(from_address(&witnet_addr), expected);
+    }
 }
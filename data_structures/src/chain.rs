@@ -1,3 +1,7 @@
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+
 /// Data structure holding critical information about the chain state and protocol constants
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ChainInfo {
@@ -12,14 +16,47 @@ pub struct ChainInfo {
 }
 
 /// Possible values for the "environment" configuration param.
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Environment {
     /// "mainnet" environment
-    #[serde(rename = "mainnet")]
     Mainnet,
     /// "testnet" environment
-    #[serde(rename = "testnet-1")]
     Testnet1,
+    /// User-defined environment, identified by its name and written as
+    /// `custom:<name>` in the configuration. All the environment-specific
+    /// params must be given in the configuration.
+    Custom(String),
+}
+
+/// Prefix of the custom environments in the configuration, which makes
+/// them explicit so that a typo in the name of a built-in environment
+/// is not taken as a custom one
+pub const CUSTOM_ENVIRONMENT_PREFIX: &str = "custom:";
+
+impl Environment {
+    /// Name of the environment, e.g.: `testnet-1` or `devnet` for the
+    /// custom environment `custom:devnet`
+    pub fn name(&self) -> &str {
+        match self {
+            Environment::Mainnet => "mainnet",
+            Environment::Testnet1 => "testnet-1",
+            Environment::Custom(name) => name,
+        }
+    }
+
+    /// Environment as written in the configuration: `mainnet`,
+    /// `testnet-1` or `custom:<name>`. Returns `None` for unknown
+    /// environments and custom environments without a name.
+    pub fn from_name(name: &str) -> Option<Environment> {
+        match name {
+            "mainnet" => Some(Environment::Mainnet),
+            "testnet-1" => Some(Environment::Testnet1),
+            _ => name
+                .strip_prefix(CUSTOM_ENVIRONMENT_PREFIX)
+                .filter(|custom| !custom.is_empty())
+                .map(|custom| Environment::Custom(custom.to_string())),
+        }
+    }
 }
 
 impl Default for Environment {
@@ -28,6 +65,41 @@ impl Default for Environment {
     }
 }
 
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Environment::Custom(name) => write!(f, "{}{}", CUSTOM_ENVIRONMENT_PREFIX, name),
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+// Environments are (de)serialized as written in the configuration
+impl Serialize for Environment {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Environment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+
+        Environment::from_name(&name).ok_or_else(|| {
+            de::Error::custom(format!(
+                "unknown environment `{}`, expected `mainnet`, `testnet-1` or `{}<name>`",
+                name, CUSTOM_ENVIRONMENT_PREFIX
+            ))
+        })
+    }
+}
+
 /// Consensus-critical configuration
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConsensusConstants {
@@ -103,6 +175,25 @@ pub struct BlockHeaderWithProof {
     pub proof: LeadershipProof,
 }
 
+/// Block header along with the hash of its block, which cannot be computed from the header alone
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct HashedBlockHeader {
+    /// The hash of the block
+    pub hash: Hash,
+    /// The header of the block
+    pub header: BlockHeaderWithProof,
+}
+
+/// Block known to be part of the chain, which the block headers received from the peers must
+/// contain
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The epoch of the block
+    pub epoch: Epoch,
+    /// The hash of the block
+    pub hash: Hash,
+}
+
 /// Proof of leadership structure
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LeadershipProof {
@@ -28,11 +28,13 @@ pub enum Command {
   Inv = 8,
   GetData = 9,
   GetBlocks = 10,
+  GetHeaders = 11,
+  Headers = 12,
 
 }
 
 const ENUM_MIN_COMMAND: u8 = 0;
-const ENUM_MAX_COMMAND: u8 = 10;
+const ENUM_MAX_COMMAND: u8 = 12;
 
 impl<'a> flatbuffers::Follow<'a> for Command {
   type Inner = Self;
@@ -66,7 +68,7 @@ impl flatbuffers::Push for Command {
 }
 
 #[allow(non_camel_case_types)]
-const ENUM_VALUES_COMMAND:[Command; 11] = [
+const ENUM_VALUES_COMMAND:[Command; 13] = [
   Command::NONE,
   Command::Version,
   Command::Verack,
@@ -77,11 +79,13 @@ const ENUM_VALUES_COMMAND:[Command; 11] = [
   Command::Block,
   Command::Inv,
   Command::GetData,
-  Command::GetBlocks
+  Command::GetBlocks,
+  Command::GetHeaders,
+  Command::Headers
 ];
 
 #[allow(non_camel_case_types)]
-const ENUM_NAMES_COMMAND:[&'static str; 11] = [
+const ENUM_NAMES_COMMAND:[&'static str; 13] = [
     "NONE",
     "Version",
     "Verack",
@@ -92,7 +96,9 @@ const ENUM_NAMES_COMMAND:[&'static str; 11] = [
     "Block",
     "Inv",
     "GetData",
-    "GetBlocks"
+    "GetBlocks",
+    "GetHeaders",
+    "Headers"
 ];
 
 pub fn enum_name_command(e: Command) -> &'static str {
@@ -505,6 +511,26 @@ impl<'a> Message<'a> {
     }
   }
 
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn command_as_get_headers(&'a self) -> Option<GetHeaders> {
+    if self.command_type() == Command::GetHeaders {
+      Some(GetHeaders::init_from_table(self.command()))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn command_as_headers(&'a self) -> Option<Headers> {
+    if self.command_type() == Command::Headers {
+      Some(Headers::init_from_table(self.command()))
+    } else {
+      None
+    }
+  }
+
 }
 
 pub struct MessageArgs {
@@ -1524,6 +1550,96 @@ impl<'a: 'b, 'b> BlockHeaderBuilder<'a, 'b> {
 
 /////////////////////////////////////////////////////////
 /////////////////////////////////////////////////////////
+pub enum HashedBlockHeaderOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct HashedBlockHeader<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for HashedBlockHeader<'a> {
+    type Inner = HashedBlockHeader<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> HashedBlockHeader<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        HashedBlockHeader {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args HashedBlockHeaderArgs<'args>) -> flatbuffers::WIPOffset<HashedBlockHeader<'bldr>> {
+      let mut builder = HashedBlockHeaderBuilder::new(_fbb);
+      if let Some(x) = args.header { builder.add_header(x); }
+      if let Some(x) = args.hash { builder.add_hash(x); }
+      builder.finish()
+    }
+
+    pub const VT_HASH: flatbuffers::VOffsetT = 4;
+    pub const VT_HEADER: flatbuffers::VOffsetT = 6;
+
+  #[inline]
+  pub fn hash(&self) -> Hash<'a> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<Hash<'a>>>(HashedBlockHeader::VT_HASH, None).unwrap()
+  }
+  #[inline]
+  pub fn header(&self) -> BlockHeader<'a> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<BlockHeader<'a>>>(HashedBlockHeader::VT_HEADER, None).unwrap()
+  }
+}
+
+pub struct HashedBlockHeaderArgs<'a> {
+    pub hash: Option<flatbuffers::WIPOffset<Hash<'a >>>,
+    pub header: Option<flatbuffers::WIPOffset<BlockHeader<'a >>>,
+}
+impl<'a> Default for HashedBlockHeaderArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        HashedBlockHeaderArgs {
+            hash: None, // required field
+            header: None, // required field
+        }
+    }
+}
+pub struct HashedBlockHeaderBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> HashedBlockHeaderBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_hash(&mut self, hash: flatbuffers::WIPOffset<Hash<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<Hash>>(HashedBlockHeader::VT_HASH, hash);
+  }
+  #[inline]
+  pub fn add_header(&mut self, header: flatbuffers::WIPOffset<BlockHeader<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<BlockHeader>>(HashedBlockHeader::VT_HEADER, header);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> HashedBlockHeaderBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    HashedBlockHeaderBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<HashedBlockHeader<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    self.fbb_.required(o, HashedBlockHeader::VT_HASH,"hash");
+    self.fbb_.required(o, HashedBlockHeader::VT_HEADER,"header");
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
 pub enum VersionOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
@@ -2370,6 +2486,160 @@ impl<'a: 'b, 'b> GetDataBuilder<'a, 'b> {
   }
 }
 
+pub enum GetHeadersOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct GetHeaders<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for GetHeaders<'a> {
+    type Inner = GetHeaders<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> GetHeaders<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        GetHeaders {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args GetHeadersArgs<'args>) -> flatbuffers::WIPOffset<GetHeaders<'bldr>> {
+      let mut builder = GetHeadersBuilder::new(_fbb);
+      if let Some(x) = args.highest_block_checkpoint { builder.add_highest_block_checkpoint(x); }
+      builder.finish()
+    }
+
+    pub const VT_HIGHEST_BLOCK_CHECKPOINT: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn highest_block_checkpoint(&self) -> CheckpointBeacon<'a> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<CheckpointBeacon<'a>>>(GetHeaders::VT_HIGHEST_BLOCK_CHECKPOINT, None).unwrap()
+  }
+}
+
+pub struct GetHeadersArgs<'a> {
+    pub highest_block_checkpoint: Option<flatbuffers::WIPOffset<CheckpointBeacon<'a >>>,
+}
+impl<'a> Default for GetHeadersArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        GetHeadersArgs {
+            highest_block_checkpoint: None, // required field
+        }
+    }
+}
+pub struct GetHeadersBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> GetHeadersBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_highest_block_checkpoint(&mut self, highest_block_checkpoint: flatbuffers::WIPOffset<CheckpointBeacon<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<CheckpointBeacon>>(GetHeaders::VT_HIGHEST_BLOCK_CHECKPOINT, highest_block_checkpoint);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> GetHeadersBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    GetHeadersBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<GetHeaders<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    self.fbb_.required(o, GetHeaders::VT_HIGHEST_BLOCK_CHECKPOINT,"highest_block_checkpoint");
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum HeadersOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct Headers<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for Headers<'a> {
+    type Inner = Headers<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> Headers<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        Headers {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args HeadersArgs<'args>) -> flatbuffers::WIPOffset<Headers<'bldr>> {
+      let mut builder = HeadersBuilder::new(_fbb);
+      if let Some(x) = args.headers { builder.add_headers(x); }
+      builder.finish()
+    }
+
+    pub const VT_HEADERS: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn headers(&self) -> flatbuffers::Vector<flatbuffers::ForwardsUOffset<HashedBlockHeader<'a>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<HashedBlockHeader<'a>>>>>(Headers::VT_HEADERS, None).unwrap()
+  }
+}
+
+pub struct HeadersArgs<'a> {
+    pub headers: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<HashedBlockHeader<'a >>>>>,
+}
+impl<'a> Default for HeadersArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        HeadersArgs {
+            headers: None, // required field
+        }
+    }
+}
+pub struct HeadersBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> HeadersBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_headers(&mut self, headers: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<HashedBlockHeader<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Headers::VT_HEADERS, headers);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> HeadersBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    HeadersBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Headers<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    self.fbb_.required(o, Headers::VT_HEADERS,"headers");
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
 #[inline]
 pub fn get_root_as_message<'a>(buf: &'a [u8]) -> Message<'a> {
   flatbuffers::get_root::<Message<'a>>(buf)
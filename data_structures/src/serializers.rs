@@ -3,13 +3,13 @@ extern crate flatbuffers;
 use std::convert::Into;
 
 use crate::chain::{
-    Block, BlockHeader, BlockHeaderWithProof, CheckpointBeacon, Hash, InvVector, LeadershipProof,
-    Secp256k1Signature, Signature, Transaction, SHA256,
+    Block, BlockHeader, BlockHeaderWithProof, CheckpointBeacon, Hash, HashedBlockHeader, InvVector,
+    LeadershipProof, Secp256k1Signature, Signature, Transaction, SHA256,
 };
 use crate::flatbuffers::protocol_generated::protocol;
 
 use crate::types::{
-    Address, Command, GetBlocks, GetData, GetPeers, Inv,
+    Address, Command, GetBlocks, GetData, GetHeaders, GetPeers, Headers, Inv,
     IpAddress::{Ipv4, Ipv6},
     Message, Peers, Ping, Pong, Verack, Version,
 };
@@ -81,6 +81,12 @@ struct InventoryArgs<'a> {
     inventory: &'a [InvVector],
 }
 
+#[derive(Debug, Clone, Copy)]
+struct HeadersArgs<'a> {
+    magic: u16,
+    headers: &'a [HashedBlockHeader],
+}
+
 #[derive(Debug, Clone, Copy)]
 struct GetDataWitnetArgs<'a> {
     magic: u16,
@@ -187,65 +193,7 @@ impl TryFrom<Vec<u8>> for Message {
                 .command_as_block()
                 .map(|block| {
                     // Get Header
-                    let header_ftb = block.header();
-                    let version = header_ftb.version();
-                    // Get CheckpointBeacon
-                    let hash: Hash = match header_ftb.beacon().hash_prev_block().type_() {
-                        protocol::HashType::SHA256 => {
-                            let mut sha256: SHA256 = [0; 32];
-                            let sha256_bytes = header_ftb.beacon().hash_prev_block().bytes();
-                            sha256.copy_from_slice(sha256_bytes);
-
-                            Hash::SHA256(sha256)
-                        }
-                    };
-                    let beacon = CheckpointBeacon {
-                        checkpoint: header_ftb.beacon().checkpoint(),
-                        hash_prev_block: hash,
-                    };
-                    // Get hash merkle root
-                    let hash_merkle_root: Hash = match header_ftb.hash_merkle_root().type_() {
-                        protocol::HashType::SHA256 => {
-                            let mut sha256: SHA256 = [0; 32];
-                            let sha256_bytes = header_ftb.hash_merkle_root().bytes();
-                            sha256.copy_from_slice(sha256_bytes);
-
-                            Hash::SHA256(sha256)
-                        }
-                    };
-                    // Get proof of leadership
-                    let block_sig = match header_ftb.proof().block_sig_type() {
-                        protocol::Signature::Secp256k1Signature => header_ftb
-                            .proof()
-                            .block_sig_as_secp_256k_1signature()
-                            .and_then(|signature_ftb| {
-                                let mut signature = Secp256k1Signature {
-                                    r: [0; 32],
-                                    s: [0; 32],
-                                    v: 0,
-                                };
-                                signature.r.copy_from_slice(&signature_ftb.r()[0..32]);
-                                signature.s.copy_from_slice(&signature_ftb.s()[0..32]);
-                                signature.v = signature_ftb.s()[32];
-
-                                Some(Signature::Secp256k1(signature))
-                            }),
-                        _ => None,
-                    };
-                    let influence = header_ftb.proof().influence();
-                    let proof = LeadershipProof {
-                        block_sig,
-                        influence,
-                    };
-                    // Create BlockHeaderWithProof
-                    let header = BlockHeaderWithProof {
-                        block_header: BlockHeader {
-                            version,
-                            beacon,
-                            hash_merkle_root,
-                        },
-                        proof,
-                    };
+                    let header = create_block_header(block.header());
                     // Get transaction count
                     let txn_count = block.txn_count();
                     // Get transactions
@@ -288,6 +236,38 @@ impl TryFrom<Vec<u8>> for Message {
                     }))
                 })
                 .ok_or(""),
+            protocol::Command::GetHeaders => message
+                .command_as_get_headers()
+                .map(|get_headers| Message {
+                    kind: Command::GetHeaders(GetHeaders {
+                        highest_block_checkpoint: create_checkpoint_beacon(
+                            get_headers.highest_block_checkpoint(),
+                        ),
+                    }),
+                    magic,
+                })
+                .ok_or(""),
+            protocol::Command::Headers => message
+                .command_as_headers()
+                .map(|headers| {
+                    // Create all the hashed block headers (witnet's types)
+                    let ftb_headers = headers.headers();
+                    let headers = (0..ftb_headers.len())
+                        .map(|i| {
+                            let hashed_header = ftb_headers.get(i);
+                            HashedBlockHeader {
+                                hash: create_hash(hashed_header.hash()),
+                                header: create_block_header(hashed_header.header()),
+                            }
+                        })
+                        .collect();
+
+                    Message {
+                        kind: Command::Headers(Headers { headers }),
+                        magic,
+                    }
+                })
+                .ok_or(""),
             protocol::Command::NONE => Err(""),
         }
     }
@@ -387,6 +367,22 @@ impl Into<Vec<u8>> for Message {
                     inventory: &inventory,
                 },
             ),
+            Command::GetHeaders(GetHeaders {
+                highest_block_checkpoint,
+            }) => create_get_headers_flatbuffer(
+                &mut builder,
+                GetBlocksCommandArgs {
+                    magic: self.magic,
+                    highest_block_checkpoint,
+                },
+            ),
+            Command::Headers(Headers { headers }) => create_headers_flatbuffer(
+                &mut builder,
+                HeadersArgs {
+                    magic: self.magic,
+                    headers: &headers,
+                },
+            ),
         }
     }
 }
@@ -790,75 +786,8 @@ fn create_block_flatbuffer(
     builder: &mut FlatBufferBuilder,
     block_args: BlockCommandArgs,
 ) -> Vec<u8> {
-    // Create checkpoint beacon flatbuffer
-    let hash_prev_block_args = match block_args.header.block_header.beacon.hash_prev_block {
-        Hash::SHA256(hash) => protocol::HashArgs {
-            type_: protocol::HashType::SHA256,
-            bytes: Some(builder.create_vector(&hash)),
-        },
-    };
-    let hash_prev_block = Some(protocol::Hash::create(builder, &hash_prev_block_args));
-    let beacon = Some(protocol::CheckpointBeacon::create(
-        builder,
-        &protocol::CheckpointBeaconArgs {
-            checkpoint: block_args.header.block_header.beacon.checkpoint,
-            hash_prev_block,
-        },
-    ));
-    // Create hash merkle root flatbuffer
-    let hash_merkle_root_args = match block_args.header.block_header.hash_merkle_root {
-        Hash::SHA256(hash) => protocol::HashArgs {
-            type_: protocol::HashType::SHA256,
-            bytes: Some(builder.create_vector(&hash)),
-        },
-    };
-    let hash_merkle_root = Some(protocol::Hash::create(builder, &hash_merkle_root_args));
-    // Create proof of leadership flatbuffer
-    let block_sig_type =
-        block_args
-            .header
-            .proof
-            .block_sig
-            .clone()
-            .map(|signature| match signature {
-                Signature::Secp256k1(_) => protocol::Signature::Secp256k1Signature,
-            });
-    let block_sig = block_args
-        .header
-        .proof
-        .block_sig
-        .map(|signature| match signature {
-            Signature::Secp256k1(secp256k1) => {
-                let mut s = secp256k1.s.to_vec();
-                s.push(secp256k1.v);
-                let r_ftb = Some(builder.create_vector(&secp256k1.r));
-                let s_ftb = Some(builder.create_vector(&s));
-
-                protocol::Secp256k1Signature::create(
-                    builder,
-                    &protocol::Secp256k1SignatureArgs { r: r_ftb, s: s_ftb },
-                )
-                .as_union_value()
-            }
-        });
-    let proof = Some(protocol::LeadershipProof::create(
-        builder,
-        &protocol::LeadershipProofArgs {
-            block_sig_type: block_sig_type.unwrap_or(protocol::Signature::NONE),
-            block_sig,
-            influence: block_args.header.proof.influence,
-        },
-    ));
     // Create block header flatbuffer
-    let header = Some(protocol::BlockHeader::create(
-        builder,
-        &protocol::BlockHeaderArgs {
-            version: block_args.header.block_header.version,
-            beacon,
-            hash_merkle_root,
-            proof,
-        },
-    ));
+    let header = Some(create_block_header_flatbuffer(builder, &block_args.header));
     // Create transaction array flatbuffer
     let txns: Vec<flatbuffers::WIPOffset<protocol::Transaction>> = block_args
         .txns
@@ -1121,3 +1050,219 @@ fn create_hash(hash: protocol::Hash) -> Hash {
         protocol::HashType::SHA256 => Hash::SHA256(hash_bytes),
     }
 }
+
+// Create a witnet's checkpoint beacon from a flatbuffers' checkpoint beacon
+fn create_checkpoint_beacon(beacon: protocol::CheckpointBeacon) -> CheckpointBeacon {
+    CheckpointBeacon {
+        checkpoint: beacon.checkpoint(),
+        hash_prev_block: create_hash(beacon.hash_prev_block()),
+    }
+}
+
+// Create a witnet's block header from a flatbuffers' block header
+fn create_block_header(header_ftb: protocol::BlockHeader) -> BlockHeaderWithProof {
+    let version = header_ftb.version();
+    // Get CheckpointBeacon
+    let beacon = create_checkpoint_beacon(header_ftb.beacon());
+    // Get hash merkle root
+    let hash_merkle_root = create_hash(header_ftb.hash_merkle_root());
+    // Get proof of leadership
+    let block_sig = match header_ftb.proof().block_sig_type() {
+        protocol::Signature::Secp256k1Signature => header_ftb
+            .proof()
+            .block_sig_as_secp_256k_1signature()
+            .and_then(|signature_ftb| {
+                let mut signature = Secp256k1Signature {
+                    r: [0; 32],
+                    s: [0; 32],
+                    v: 0,
+                };
+                signature.r.copy_from_slice(&signature_ftb.r()[0..32]);
+                signature.s.copy_from_slice(&signature_ftb.s()[0..32]);
+                signature.v = signature_ftb.s()[32];
+
+                Some(Signature::Secp256k1(signature))
+            }),
+        _ => None,
+    };
+    let influence = header_ftb.proof().influence();
+    let proof = LeadershipProof {
+        block_sig,
+        influence,
+    };
+
+    BlockHeaderWithProof {
+        block_header: BlockHeader {
+            version,
+            beacon,
+            hash_merkle_root,
+        },
+        proof,
+    }
+}
+
+// Create a block header flatbuffer to encode a witnet's block header
+fn create_block_header_flatbuffer<'a>(
+    builder: &mut FlatBufferBuilder<'a>,
+    header: &BlockHeaderWithProof,
+) -> flatbuffers::WIPOffset<protocol::BlockHeader<'a>> {
+    // Create checkpoint beacon flatbuffer
+    let hash_prev_block_args = match header.block_header.beacon.hash_prev_block {
+        Hash::SHA256(hash) => protocol::HashArgs {
+            type_: protocol::HashType::SHA256,
+            bytes: Some(builder.create_vector(&hash)),
+        },
+    };
+    let hash_prev_block = Some(protocol::Hash::create(builder, &hash_prev_block_args));
+    let beacon = Some(protocol::CheckpointBeacon::create(
+        builder,
+        &protocol::CheckpointBeaconArgs {
+            checkpoint: header.block_header.beacon.checkpoint,
+            hash_prev_block,
+        },
+    ));
+    // Create hash merkle root flatbuffer
+    let hash_merkle_root_args = match header.block_header.hash_merkle_root {
+        Hash::SHA256(hash) => protocol::HashArgs {
+            type_: protocol::HashType::SHA256,
+            bytes: Some(builder.create_vector(&hash)),
+        },
+    };
+    let hash_merkle_root = Some(protocol::Hash::create(builder, &hash_merkle_root_args));
+    // Create proof of leadership flatbuffer
+    let block_sig_type = header
+        .proof
+        .block_sig
+        .clone()
+        .map(|signature| match signature {
+            Signature::Secp256k1(_) => protocol::Signature::Secp256k1Signature,
+        });
+    let block_sig = header
+        .proof
+        .block_sig
+        .clone()
+        .map(|signature| match signature {
+            Signature::Secp256k1(secp256k1) => {
+                let mut s = secp256k1.s.to_vec();
+                s.push(secp256k1.v);
+                let r_ftb = Some(builder.create_vector(&secp256k1.r));
+                let s_ftb = Some(builder.create_vector(&s));
+
+                protocol::Secp256k1Signature::create(
+                    builder,
+                    &protocol::Secp256k1SignatureArgs { r: r_ftb, s: s_ftb },
+                )
+                .as_union_value()
+            }
+        });
+    let proof = Some(protocol::LeadershipProof::create(
+        builder,
+        &protocol::LeadershipProofArgs {
+            block_sig_type: block_sig_type.unwrap_or(protocol::Signature::NONE),
+            block_sig,
+            influence: header.proof.influence,
+        },
+    ));
+
+    protocol::BlockHeader::create(
+        builder,
+        &protocol::BlockHeaderArgs {
+            version: header.block_header.version,
+            beacon,
+            hash_merkle_root,
+            proof,
+        },
+    )
+}
+
+// Create a get headers flatbuffer to encode a witnet's get headers message
+fn create_get_headers_flatbuffer(
+    builder: &mut FlatBufferBuilder,
+    get_headers_args: GetBlocksCommandArgs,
+) -> Vec<u8> {
+    let Hash::SHA256(hash) = get_headers_args.highest_block_checkpoint.hash_prev_block;
+    let ftb_hash = builder.create_vector(&hash);
+    let hash_command = protocol::Hash::create(
+        builder,
+        &protocol::HashArgs {
+            type_: protocol::HashType::SHA256,
+            bytes: Some(ftb_hash),
+        },
+    );
+
+    let beacon = protocol::CheckpointBeacon::create(
+        builder,
+        &protocol::CheckpointBeaconArgs {
+            checkpoint: get_headers_args.highest_block_checkpoint.checkpoint,
+            hash_prev_block: Some(hash_command),
+        },
+    );
+
+    let get_headers_command = protocol::GetHeaders::create(
+        builder,
+        &protocol::GetHeadersArgs {
+            highest_block_checkpoint: Some(beacon),
+        },
+    );
+    let message = protocol::Message::create(
+        builder,
+        &protocol::MessageArgs {
+            magic: get_headers_args.magic,
+            command_type: protocol::Command::GetHeaders,
+            command: Some(get_headers_command.as_union_value()),
+        },
+    );
+    build_flatbuffer(builder, message)
+}
+
+// Create a headers flatbuffer to encode a witnet's headers message
+fn create_headers_flatbuffer(
+    builder: &mut FlatBufferBuilder,
+    headers_args: HeadersArgs,
+) -> Vec<u8> {
+    // Create vector of flatbuffers' hashed block headers
+    let ftb_headers: Vec<flatbuffers::WIPOffset<protocol::HashedBlockHeader>> = headers_args
+        .headers
+        .iter()
+        .map(|hashed_header: &HashedBlockHeader| {
+            let Hash::SHA256(hash) = hashed_header.hash;
+            let ftb_hash = builder.create_vector(&hash);
+            let hash = protocol::Hash::create(
+                builder,
+                &protocol::HashArgs {
+                    type_: protocol::HashType::SHA256,
+                    bytes: Some(ftb_hash),
+                },
+            );
+            let header = create_block_header_flatbuffer(builder, &hashed_header.header);
+
+            protocol::HashedBlockHeader::create(
+                builder,
+                &protocol::HashedBlockHeaderArgs {
+                    hash: Some(hash),
+                    header: Some(header),
+                },
+            )
+        })
+        .collect();
+    let ftb_headers = builder.create_vector(&ftb_headers);
+
+    // Create headers command flatbuffer
+    let headers_command = protocol::Headers::create(
+        builder,
+        &protocol::HeadersArgs {
+            headers: Some(ftb_headers),
+        },
+    );
+    // Create message flatbuffer
+    let message = protocol::Message::create(
+        builder,
+        &protocol::MessageArgs {
+            magic: headers_args.magic,
+            command_type: protocol::Command::Headers,
+            command: Some(headers_command.as_union_value()),
+        },
+    );
+
+    build_flatbuffer(builder, message)
+}
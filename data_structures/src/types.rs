@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::chain::{Block, CheckpointBeacon, InvVector};
+use crate::chain::{Block, CheckpointBeacon, HashedBlockHeader, InvVector};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Command {
@@ -14,6 +14,8 @@ pub enum Command {
     Inv(Inv),
     GetData(GetData),
     GetBlocks(GetBlocks),
+    GetHeaders(GetHeaders),
+    Headers(Headers),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -34,6 +36,16 @@ pub struct GetBlocks {
     pub highest_block_checkpoint: CheckpointBeacon,
 }
 
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct GetHeaders {
+    pub highest_block_checkpoint: CheckpointBeacon,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Headers {
+    pub headers: Vec<HashedBlockHeader>,
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Pong {
     pub nonce: u64,
@@ -72,6 +84,8 @@ impl fmt::Display for Command {
             "{}",
             match self {
                 Command::GetBlocks(_) => "GET_BLOCKS",
+                Command::GetHeaders(_) => "GET_HEADERS",
+                Command::Headers(_) => "HEADERS",
                 Command::GetPeers(_) => "GET_PEERS",
                 Command::Peers(_) => "PEERS",
                 Command::Ping(_) => "PING",
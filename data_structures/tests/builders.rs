@@ -18,6 +18,49 @@ fn builders_build_get_blocks() {
     assert_eq!(msg, Message::build_get_blocks(highest_block_checkpoint));
 }
 
+#[test]
+fn builders_build_get_headers() {
+    let highest_block_checkpoint = CheckpointBeacon {
+        checkpoint: 3,
+        hash_prev_block: Hash::SHA256([1; 32]),
+    };
+    let msg = Message {
+        kind: Command::GetHeaders(GetHeaders {
+            highest_block_checkpoint,
+        }),
+        magic: MAGIC,
+    };
+    assert_eq!(msg, Message::build_get_headers(highest_block_checkpoint));
+}
+
+#[test]
+fn builders_build_headers() {
+    let headers = vec![HashedBlockHeader {
+        hash: Hash::SHA256([1; 32]),
+        header: BlockHeaderWithProof {
+            block_header: BlockHeader {
+                version: 0x0000_0001,
+                beacon: CheckpointBeacon {
+                    checkpoint: 1,
+                    hash_prev_block: Hash::SHA256([0; 32]),
+                },
+                hash_merkle_root: Hash::SHA256([0; 32]),
+            },
+            proof: LeadershipProof {
+                block_sig: None,
+                influence: 0,
+            },
+        },
+    }];
+    let msg = Message {
+        kind: Command::Headers(Headers {
+            headers: headers.clone(),
+        }),
+        magic: MAGIC,
+    };
+    assert_eq!(msg, Message::build_headers(headers));
+}
+
 #[test]
 fn builders_build_block() {
     // Prepare block header
@@ -157,8 +200,13 @@ fn builders_build_version() {
     // Build message
     let sender_sock_addr = "192.168.1.1:8000".parse().unwrap();
     let receiver_sock_addr = "192.168.1.2:8001".parse().unwrap();
-    let built_msg =
-        Message::build_version(sender_sock_addr, receiver_sock_addr, hardcoded_last_epoch);
+    let built_msg = Message::build_version(
+        sender_sock_addr,
+        receiver_sock_addr,
+        hardcoded_last_epoch,
+        CAPABILITIES,
+        GENESIS,
+    );
 
     // Check that the build_version function builds the expected message
     assert_eq!(built_msg.magic, msg.magic);
@@ -242,3 +290,12 @@ fn builders_build_get_data() {
     // Check that the build_inv function builds the expected message
     assert_eq!(msg, Message::build_get_data(inventory));
 }
+
+#[test]
+fn builders_genesis_id() {
+    let mut hash = [0; 32];
+    hash[..8].copy_from_slice(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
+    hash[8] = 0xFF;
+
+    assert_eq!(genesis_id(&Hash::SHA256(hash)), 0x0123_4567_89AB_CDEF);
+}
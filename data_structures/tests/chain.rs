@@ -0,0 +1,38 @@
+use serde_derive::{Deserialize, Serialize};
+use witnet_data_structures::chain::*;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Config {
+    environment: Environment,
+}
+
+#[test]
+fn chain_environment_names() {
+    for name in &["mainnet", "testnet-1", "custom:devnet"] {
+        assert_eq!(Environment::from_name(name).unwrap().to_string(), *name);
+    }
+    assert_eq!(
+        Environment::from_name("custom:devnet"),
+        Some(Environment::Custom("devnet".to_string()))
+    );
+    assert_eq!(Environment::Custom("devnet".to_string()).name(), "devnet");
+    assert_eq!(Environment::from_name("devnet"), None);
+    assert_eq!(Environment::from_name("testnet1"), None);
+    assert_eq!(Environment::from_name("custom:"), None);
+}
+
+#[test]
+fn chain_environment_serde() {
+    let config: Config = toml::from_str("environment = \"custom:devnet\"").unwrap();
+
+    assert_eq!(
+        config.environment,
+        Environment::Custom("devnet".to_string())
+    );
+    assert_eq!(
+        toml::to_string(&config).unwrap(),
+        "environment = \"custom:devnet\"\n"
+    );
+    assert!(toml::from_str::<Config>("environment = \"devnet\"").is_err());
+    assert!(toml::from_str::<Config>("environment = \"\"").is_err());
+}
@@ -716,3 +716,78 @@ fn message_get_data_encode_decode() {
 
     assert_eq!(cloned_msg, Message::try_from(result).unwrap());
 }
+
+#[test]
+fn message_get_headers_encode_decode() {
+    let msg = Message {
+        kind: Command::GetHeaders(GetHeaders {
+            highest_block_checkpoint: CheckpointBeacon {
+                checkpoint: 7,
+                hash_prev_block: Hash::SHA256([3; 32]),
+            },
+        }),
+        magic: 1,
+    };
+
+    let cloned_msg = msg.clone();
+    let result: Vec<u8> = msg.into();
+
+    assert_eq!(cloned_msg, Message::try_from(result).unwrap());
+}
+
+#[test]
+fn message_headers_encode_decode() {
+    let header = |checkpoint, hash_prev_block, block_sig| BlockHeaderWithProof {
+        block_header: BlockHeader {
+            version: 0,
+            beacon: CheckpointBeacon {
+                checkpoint,
+                hash_prev_block,
+            },
+            hash_merkle_root: Hash::SHA256([4; 32]),
+        },
+        proof: LeadershipProof {
+            block_sig,
+            influence: 0,
+        },
+    };
+    let signature = Signature::Secp256k1(Secp256k1Signature {
+        r: [1; 32],
+        s: [2; 32],
+        v: 1,
+    });
+
+    let msg = Message {
+        kind: Command::Headers(Headers {
+            headers: vec![
+                HashedBlockHeader {
+                    hash: Hash::SHA256([1; 32]),
+                    header: header(1, Hash::SHA256([0; 32]), Some(signature)),
+                },
+                HashedBlockHeader {
+                    hash: Hash::SHA256([2; 32]),
+                    header: header(2, Hash::SHA256([1; 32]), None),
+                },
+            ],
+        }),
+        magic: 1,
+    };
+
+    let cloned_msg = msg.clone();
+    let result: Vec<u8> = msg.into();
+
+    assert_eq!(cloned_msg, Message::try_from(result).unwrap());
+}
+
+#[test]
+fn message_empty_headers_encode_decode() {
+    let msg = Message {
+        kind: Command::Headers(Headers { headers: vec![] }),
+        magic: 1,
+    };
+
+    let cloned_msg = msg.clone();
+    let result: Vec<u8> = msg.into();
+
+    assert_eq!(cloned_msg, Message::try_from(result).unwrap());
+}
@@ -0,0 +1,184 @@
+//! Library for scoring the misbehavior of the peers and banning the ones whose score gets too high,
+//! and for restricting the peers to the allowed IP addresses
+
+use serde_derive::{Deserialize, Serialize};
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use witnet_util::net::IpNetwork;
+
+/// Kinds of misbehavior of a peer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// The peer sent a message which could not be decoded
+    InvalidMessage,
+    /// The peer sent a message which is not expected in the current state of the session
+    ProtocolViolation,
+    /// The peer sent too many messages in a short time
+    Spam,
+    /// The peer sent a message over the rate limit of its kind
+    RateLimitExceeded,
+    /// The peer sent block headers which do not form a chain, contradict a checkpoint or do not
+    /// have the hash of their blocks
+    InvalidHeaders,
+    /// The peer sent a message bigger than the size limit of its kind
+    OversizedMessage,
+    /// The peer sent block headers whose blocks no peer could send
+    UnavailableBlocks,
+}
+
+impl Misbehavior {
+    /// Score added to a peer each time it misbehaves this way
+    pub fn score(self) -> u32 {
+        match self {
+            Misbehavior::InvalidMessage => 20,
+            Misbehavior::ProtocolViolation => 10,
+            Misbehavior::Spam => 5,
+            Misbehavior::RateLimitExceeded => 1,
+            Misbehavior::InvalidHeaders => 100,
+            Misbehavior::OversizedMessage => 20,
+            Misbehavior::UnavailableBlocks => 50,
+        }
+    }
+}
+
+/// Misbehavior scores of the peers, bans of the peers whose score reached the threshold, and the
+/// lists of IP networks which are always allowed or never allowed.
+///
+/// Peers are identified by their IP address, as a peer reconnecting to this node does it from a
+/// different port. Only the bans and the blacklist are serialized, the scores start from zero on
+/// each run and the whitelist is only taken from the configuration.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Bans {
+    /// Banned addresses, with the timestamp at which their ban expires
+    banned: HashMap<IpAddr, i64>,
+    /// Scores of the addresses which are not banned
+    #[serde(skip)]
+    scores: HashMap<IpAddr, u32>,
+    /// Networks which are never allowed
+    #[serde(default)]
+    blacklist: BTreeSet<IpNetwork>,
+    /// Networks which are allowed, if empty every network not in the blacklist is allowed
+    #[serde(skip)]
+    whitelist: Vec<IpNetwork>,
+}
+
+impl Bans {
+    /// Add the score of a misbehavior of the peer at `address`. If its score reaches `threshold`,
+    /// the peer is banned until `now` plus `duration` and its score is reset. A `threshold` of
+    /// zero disables banning.
+    /// Returns whether the peer got banned
+    pub fn report(
+        &mut self,
+        address: SocketAddr,
+        misbehavior: Misbehavior,
+        threshold: u32,
+        duration: Duration,
+        now: i64,
+    ) -> bool {
+        if threshold == 0 {
+            return false;
+        }
+
+        let ip = address.ip();
+        let score = self.scores.entry(ip).or_insert(0);
+        *score = score.saturating_add(misbehavior.score());
+        if *score < threshold {
+            return false;
+        }
+
+        self.scores.remove(&ip);
+        self.ban(ip, now.saturating_add(duration.as_secs() as i64));
+
+        true
+    }
+
+    /// Ban an address until the `until` timestamp. If the address was already banned, the latest
+    /// expiration is kept
+    pub fn ban(&mut self, ip: IpAddr, until: i64) {
+        let expiration = self.banned.entry(ip).or_insert(until);
+        *expiration = (*expiration).max(until);
+    }
+
+    /// Check whether the peer at `address` is banned at the `now` timestamp
+    pub fn is_banned(&self, address: &SocketAddr, now: i64) -> bool {
+        self.banned
+            .get(&address.ip())
+            .map_or(false, |until| *until > now)
+    }
+
+    /// Check whether the peer at `address` is allowed to connect to this node and to be connected
+    /// to at the `now` timestamp: it is not banned, not blacklisted and whitelisted if there is a
+    /// whitelist
+    pub fn is_allowed(&self, address: &SocketAddr, now: i64) -> bool {
+        let ip = address.ip();
+
+        !self.is_banned(address, now)
+            && !self.blacklist.iter().any(|network| network.contains(ip))
+            && (self.whitelist.is_empty()
+                || self.whitelist.iter().any(|network| network.contains(ip)))
+    }
+
+    /// Replace the whitelist. An empty whitelist allows every network
+    pub fn set_whitelist<I: IntoIterator<Item = IpNetwork>>(&mut self, networks: I) {
+        self.whitelist = networks.into_iter().collect();
+        self.whitelist.sort();
+    }
+
+    /// Add networks to the blacklist
+    /// Returns the networks which were not blacklisted yet
+    pub fn blacklist<I: IntoIterator<Item = IpNetwork>>(&mut self, networks: I) -> Vec<IpNetwork> {
+        networks
+            .into_iter()
+            .filter(|network| self.blacklist.insert(*network))
+            .collect()
+    }
+
+    /// Remove networks from the blacklist
+    /// Returns the networks which were blacklisted
+    pub fn unblacklist<I: IntoIterator<Item = IpNetwork>>(
+        &mut self,
+        networks: I,
+    ) -> Vec<IpNetwork> {
+        networks
+            .into_iter()
+            .filter(|network| self.blacklist.remove(network))
+            .collect()
+    }
+
+    /// Get all the blacklisted networks, sorted
+    pub fn get_blacklist(&self) -> Vec<IpNetwork> {
+        self.blacklist.iter().cloned().collect()
+    }
+
+    /// Current misbehavior score of the peer at `address`
+    pub fn get_score(&self, address: &SocketAddr) -> u32 {
+        self.scores.get(&address.ip()).cloned().unwrap_or(0)
+    }
+
+    /// Remove the bans which have expired at the `now` timestamp
+    /// Returns the addresses which are not banned anymore
+    pub fn remove_expired(&mut self, now: i64) -> Vec<IpAddr> {
+        let expired: Vec<IpAddr> = self
+            .banned
+            .iter()
+            .filter(|(_, until)| **until <= now)
+            .map(|(ip, _)| *ip)
+            .collect();
+        for ip in &expired {
+            self.banned.remove(ip);
+        }
+
+        expired
+    }
+
+    /// Get all the banned addresses, with the timestamp at which their ban expires
+    pub fn get_all(&self) -> Vec<(IpAddr, i64)> {
+        self.banned
+            .iter()
+            .map(|(ip, until)| (*ip, *until))
+            .collect()
+    }
+}
@@ -0,0 +1,42 @@
+//! Error type definitions for the compression module.
+
+use failure::Fail;
+use std::fmt;
+use witnet_util::error::WitnetResult;
+
+/// Compression Error
+#[derive(Debug, Fail)]
+#[fail(display = "{} : at \"{}\", msg {}", kind, info, msg)]
+pub struct CompressionError {
+    /// Error kind
+    kind: CompressionErrorKind,
+    /// Error parameter
+    info: String,
+    /// Error message
+    msg: String,
+}
+
+impl CompressionError {
+    /// Create a compression error based on operation kind and related info.
+    pub fn new(kind: CompressionErrorKind, info: String, msg: String) -> Self {
+        Self { kind, info, msg }
+    }
+}
+
+/// Compression Errors under different operations
+#[derive(Debug)]
+pub enum CompressionErrorKind {
+    /// Errors when decoding a message
+    Malformed,
+    /// Errors when a message decompresses into too many bytes
+    TooBig,
+}
+
+impl fmt::Display for CompressionErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CompressionError::{:?}", self)
+    }
+}
+
+/// Result type for the compression module.
+pub type CompressionResult<T> = WitnetResult<T, CompressionError>;
@@ -0,0 +1,82 @@
+//! Library for compressing the messages exchanged with the peers which negotiated it in the
+//! handshake, to speed up the synchronization on constrained links
+//!
+//! Once negotiated, every message is prefixed by a byte telling whether the rest of the message is
+//! compressed with Snappy. Only the messages of at least `MIN_COMPRESSED_SIZE` bytes are
+//! compressed, and only if compressing them makes them smaller.
+
+use witnet_util::error::WitnetError;
+
+use crate::compression::error::{CompressionError, CompressionErrorKind, CompressionResult};
+
+pub mod error;
+
+/// Prefix of the messages sent as they are
+const RAW: u8 = 0;
+
+/// Prefix of the messages compressed with Snappy
+const SNAPPY: u8 = 1;
+
+/// Messages smaller than this number of bytes are not compressed
+pub const MIN_COMPRESSED_SIZE: usize = 1024;
+
+/// Prefix `message` with its compression, compressing it if it is worth it
+pub fn compress(message: &[u8]) -> Vec<u8> {
+    if message.len() >= MIN_COMPRESSED_SIZE {
+        if let Ok(compressed) = snap::Encoder::new().compress_vec(message) {
+            if compressed.len() < message.len() {
+                let mut frame = Vec::with_capacity(compressed.len() + 1);
+                frame.push(SNAPPY);
+                frame.extend_from_slice(&compressed);
+
+                return frame;
+            }
+        }
+    }
+
+    let mut frame = Vec::with_capacity(message.len() + 1);
+    frame.push(RAW);
+    frame.extend_from_slice(message);
+
+    frame
+}
+
+/// Get the message of a frame built by `compress`, decompressing it if needed. The frames which
+/// decompress into more than `max_size` bytes are rejected before decompressing them, so a peer
+/// cannot exhaust the memory of the node with a small message which decompresses into a huge one
+pub fn decompress(frame: &[u8], max_size: usize) -> CompressionResult<Vec<u8>> {
+    match frame.split_first() {
+        Some((&RAW, message)) => Ok(message.to_vec()),
+        Some((&SNAPPY, compressed)) => {
+            let len =
+                snap::decompress_len(compressed).map_err(|e| malformed(frame, &e.to_string()))?;
+            if len > max_size {
+                return Err(WitnetError::from(CompressionError::new(
+                    CompressionErrorKind::TooBig,
+                    format!("{} bytes", frame.len()),
+                    format!(
+                        "The message decompresses into {} bytes, more than the maximum of {}",
+                        len, max_size
+                    ),
+                )));
+            }
+
+            snap::Decoder::new()
+                .decompress_vec(compressed)
+                .map_err(|e| malformed(frame, &e.to_string()))
+        }
+        Some((prefix, _)) => Err(malformed(
+            frame,
+            &format!("Unknown compression prefix {}", prefix),
+        )),
+        None => Err(malformed(frame, "Empty message")),
+    }
+}
+
+fn malformed(frame: &[u8], msg: &str) -> WitnetError<CompressionError> {
+    WitnetError::from(CompressionError::new(
+        CompressionErrorKind::Malformed,
+        format!("{} bytes", frame.len()),
+        msg.to_string(),
+    ))
+}
@@ -7,6 +7,22 @@
 #![deny(unused_mut)]
 #![deny(missing_docs)]
 
+pub mod bans;
+
+pub mod compression;
+
+pub mod limits;
+
+pub mod mdns;
+
+pub mod nat;
+
 pub mod peers;
 
+pub mod rate_limits;
+
+pub mod relay;
+
 pub mod sessions;
+
+pub mod socks;
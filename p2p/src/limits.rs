@@ -0,0 +1,69 @@
+//! Library for limiting the size of the messages received from a peer, so a hostile peer cannot
+//! exhaust the memory of the node with huge payloads
+//!
+//! Every message is limited by the maximum message size, which is checked as soon as the length
+//! prefix of its frame arrives and again before decompressing it. The kinds of messages whose size
+//! grows with their contents have their own limits, checked once the message is decoded and its
+//! kind is known.
+
+/// Kinds of messages whose size is limited on their own
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SizedMessage {
+    /// Blocks (`Block`)
+    Block,
+    /// Block headers (`Headers`)
+    Headers,
+    /// Inventory announcements and requests (`Inv` and `GetData`)
+    Inventory,
+    /// Address gossip (`Peers`)
+    Peers,
+}
+
+/// Maximum sizes of the messages received from a peer, in bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageLimits {
+    /// Maximum size of any message
+    pub max_message_size: usize,
+    /// Maximum size of `Block` messages
+    pub max_block_size: usize,
+    /// Maximum size of `Headers` messages
+    pub max_headers_size: usize,
+    /// Maximum size of `Inv` and `GetData` messages
+    pub max_inventory_size: usize,
+    /// Maximum size of `Peers` messages
+    pub max_peers_size: usize,
+}
+
+/// No limits
+impl Default for MessageLimits {
+    fn default() -> Self {
+        MessageLimits {
+            max_message_size: usize::max_value(),
+            max_block_size: usize::max_value(),
+            max_headers_size: usize::max_value(),
+            max_inventory_size: usize::max_value(),
+            max_peers_size: usize::max_value(),
+        }
+    }
+}
+
+impl MessageLimits {
+    /// Maximum size of a message of kind `kind`, or of any message if `kind` is `None`. No kind
+    /// of message can be bigger than the maximum message size
+    pub fn max_size(&self, kind: Option<SizedMessage>) -> usize {
+        let max_kind_size = match kind {
+            Some(SizedMessage::Block) => self.max_block_size,
+            Some(SizedMessage::Headers) => self.max_headers_size,
+            Some(SizedMessage::Inventory) => self.max_inventory_size,
+            Some(SizedMessage::Peers) => self.max_peers_size,
+            None => self.max_message_size,
+        };
+
+        max_kind_size.min(self.max_message_size)
+    }
+
+    /// Check whether a message of kind `kind` and `size` bytes is within the limits
+    pub fn allows(&self, kind: Option<SizedMessage>, size: usize) -> bool {
+        size <= self.max_size(kind)
+    }
+}
@@ -0,0 +1,40 @@
+//! Error type definitions for the mDNS module.
+
+use failure::Fail;
+use std::fmt;
+use witnet_util::error::WitnetResult;
+
+/// mDNS Error
+#[derive(Debug, Fail)]
+#[fail(display = "{} : at \"{}\", msg {}", kind, info, msg)]
+pub struct MdnsError {
+    /// Error kind
+    kind: MdnsErrorKind,
+    /// Error parameter
+    info: String,
+    /// Error message
+    msg: String,
+}
+
+impl MdnsError {
+    /// Create an mDNS error based on operation kind and related info.
+    pub fn new(kind: MdnsErrorKind, info: String, msg: String) -> Self {
+        Self { kind, info, msg }
+    }
+}
+
+/// mDNS Errors under different operations
+#[derive(Debug)]
+pub enum MdnsErrorKind {
+    /// Errors when decoding the packets of other hosts
+    Malformed,
+}
+
+impl fmt::Display for MdnsErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MdnsError::{:?}", self)
+    }
+}
+
+/// Result type for the mDNS module.
+pub type MdnsResult<T> = WitnetResult<T, MdnsError>;
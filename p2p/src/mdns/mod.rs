@@ -0,0 +1,338 @@
+//! Library for discovering the peers in the local network, and announcing this node to them, using
+//! multicast DNS (RFC 6762)
+//!
+//! Each node announces its server address as an instance of the `_witnet._tcp.local` service, with
+//! a PTR, an SRV and an A or AAAA record. Only the packets are built and decoded here, so they can
+//! be sent through any kind of socket.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use witnet_util::error::WitnetError;
+
+use crate::mdns::error::{MdnsError, MdnsErrorKind, MdnsResult};
+
+pub mod error;
+
+/// Port of mDNS
+pub const MDNS_PORT: u16 = 5353;
+
+/// Name of the service announced by the Witnet nodes
+pub const SERVICE: &str = "_witnet._tcp.local";
+
+/// Time during which the other hosts keep the records of this node (in seconds)
+const TTL: u32 = 120;
+
+/// Length of the header of a packet
+const HEADER_LEN: usize = 12;
+
+/// Flags of an authoritative response
+const RESPONSE_FLAGS: u16 = 0x8400;
+
+/// Bit of the flags set in the responses
+const RESPONSE_BIT: u16 = 0x8000;
+
+/// Types of the records
+const A: u16 = 1;
+const PTR: u16 = 12;
+const AAAA: u16 = 28;
+const SRV: u16 = 33;
+const ANY: u16 = 255;
+
+/// Internet class
+const IN: u16 = 1;
+
+/// Maximum number of compression pointers followed in a name, so a malicious packet cannot make
+/// the decoding loop forever
+const MAX_POINTERS: usize = 16;
+
+/// Instance of the service announced by a node
+#[derive(Clone, Debug, PartialEq)]
+pub struct Service {
+    /// Name of the instance, unique to each node
+    pub instance: String,
+    /// Server address of the node
+    pub address: SocketAddr,
+}
+
+/// Multicast group of mDNS over IPv4
+pub fn multicast_group() -> Ipv4Addr {
+    Ipv4Addr::new(224, 0, 0, 251)
+}
+
+/// Query for the instances of the service
+pub fn query() -> Vec<u8> {
+    let mut packet = header(0, 1, 0);
+    push_name(&mut packet, SERVICE);
+    push_u16(&mut packet, PTR);
+    push_u16(&mut packet, IN);
+
+    packet
+}
+
+/// Response announcing that `instance` of the service listens at `address`
+pub fn response(instance: &str, address: SocketAddr) -> Vec<u8> {
+    let instance_name = format!("{}.{}", instance, SERVICE);
+    let host_name = format!("{}.local", instance);
+
+    let mut packet = header(RESPONSE_FLAGS, 0, 3);
+
+    let mut ptr = vec![];
+    push_name(&mut ptr, &instance_name);
+    push_record(&mut packet, SERVICE, PTR, &ptr);
+
+    let mut srv = vec![];
+    // Priority and weight
+    push_u16(&mut srv, 0);
+    push_u16(&mut srv, 0);
+    push_u16(&mut srv, address.port());
+    push_name(&mut srv, &host_name);
+    push_record(&mut packet, &instance_name, SRV, &srv);
+
+    match address.ip() {
+        IpAddr::V4(ip) => push_record(&mut packet, &host_name, A, &ip.octets()),
+        IpAddr::V6(ip) => push_record(&mut packet, &host_name, AAAA, &ip.octets()),
+    }
+
+    packet
+}
+
+/// Check whether `packet`, received from `source`, is a query for the instances of the service
+pub fn is_query(packet: &[u8], source: IpAddr) -> MdnsResult<bool> {
+    let mut reader = Reader::new(packet, source);
+    let (flags, questions, _) = reader.header()?;
+    if flags & RESPONSE_BIT != 0 {
+        return Ok(false);
+    }
+
+    for _ in 0..questions {
+        let name = reader.name()?;
+        let record_type = reader.u16()?;
+        // Class, whose top bit asks for a unicast response, which is not supported
+        reader.u16()?;
+
+        if is_service(&name) && (record_type == PTR || record_type == ANY) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Decode the instances of the service announced in `packet`, received from `source`. The
+/// instances whose address is not announced, or is unspecified, get the address of `source`
+/// Returns no instances if `packet` is not a response
+pub fn parse_response(packet: &[u8], source: IpAddr) -> MdnsResult<Vec<Service>> {
+    let mut reader = Reader::new(packet, source);
+    let (flags, questions, records) = reader.header()?;
+    if flags & RESPONSE_BIT == 0 {
+        return Ok(vec![]);
+    }
+
+    for _ in 0..questions {
+        reader.name()?;
+        // Type and class
+        reader.bytes(4)?;
+    }
+
+    let suffix = format!(".{}", SERVICE);
+    let mut instances = vec![];
+    let mut addresses = HashMap::new();
+    for _ in 0..records {
+        let name = reader.name()?.to_lowercase();
+        let record_type = reader.u16()?;
+        // Class
+        reader.u16()?;
+        let ttl = reader.u32()?;
+        let len = usize::from(reader.u16()?);
+        let end = reader.pos + len;
+
+        match record_type {
+            // A TTL of zero announces that the instance is leaving
+            SRV if ttl > 0 && name.ends_with(&suffix) => {
+                // Priority and weight
+                reader.bytes(4)?;
+                let port = reader.u16()?;
+                let target = reader.name()?.to_lowercase();
+                let instance = name[..name.len() - suffix.len()].to_string();
+                instances.push((instance, target, port));
+            }
+            A if len == 4 => {
+                let octets = reader.bytes(4)?;
+                let ip = Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]);
+                addresses.insert(name, IpAddr::V4(ip));
+            }
+            AAAA if len == 16 => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(reader.bytes(16)?);
+                addresses.insert(name, IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            _ => {}
+        }
+
+        // Skip the rest of the data of the record
+        reader.pos = end;
+    }
+
+    Ok(instances
+        .into_iter()
+        .map(|(instance, target, port)| {
+            let ip = addresses
+                .get(&target)
+                .cloned()
+                .filter(|ip| !ip.is_unspecified())
+                .unwrap_or(source);
+
+            Service {
+                instance,
+                address: SocketAddr::new(ip, port),
+            }
+        })
+        .collect())
+}
+
+/// Whether `name` is the name of the service, which is case insensitive
+fn is_service(name: &str) -> bool {
+    name.eq_ignore_ascii_case(SERVICE)
+}
+
+/// Header of a packet with an identifier of zero, as required by mDNS
+fn header(flags: u16, questions: u16, answers: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(512);
+    push_u16(&mut packet, 0);
+    push_u16(&mut packet, flags);
+    push_u16(&mut packet, questions);
+    push_u16(&mut packet, answers);
+    // Authority and additional records
+    push_u16(&mut packet, 0);
+    push_u16(&mut packet, 0);
+
+    packet
+}
+
+/// Write a record of the Internet class, with the TTL of the records of this node
+fn push_record(packet: &mut Vec<u8>, name: &str, record_type: u16, data: &[u8]) {
+    push_name(packet, name);
+    push_u16(packet, record_type);
+    push_u16(packet, IN);
+    push_u16(packet, (TTL >> 16) as u16);
+    push_u16(packet, TTL as u16);
+    push_u16(packet, data.len() as u16);
+    packet.extend_from_slice(data);
+}
+
+/// Write a name as a sequence of labels, without compression
+fn push_name(packet: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+}
+
+fn push_u16(packet: &mut Vec<u8>, value: u16) {
+    packet.push((value >> 8) as u8);
+    packet.push(value as u8);
+}
+
+/// Decoder of the fields of a packet
+struct Reader<'a> {
+    packet: &'a [u8],
+    pos: usize,
+    source: IpAddr,
+}
+
+impl<'a> Reader<'a> {
+    fn new(packet: &'a [u8], source: IpAddr) -> Self {
+        Reader {
+            packet,
+            pos: 0,
+            source,
+        }
+    }
+
+    /// Flags, number of questions and number of records of all the sections
+    fn header(&mut self) -> MdnsResult<(u16, u16, u32)> {
+        if self.packet.len() < HEADER_LEN {
+            return Err(self.malformed("Truncated header"));
+        }
+        // Identifier
+        self.u16()?;
+        let flags = self.u16()?;
+        let questions = self.u16()?;
+        let records = u32::from(self.u16()?) + u32::from(self.u16()?) + u32::from(self.u16()?);
+
+        Ok((flags, questions, records))
+    }
+
+    fn bytes(&mut self, len: usize) -> MdnsResult<&'a [u8]> {
+        let bytes = self
+            .packet
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| self.malformed("Truncated packet"))?;
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> MdnsResult<u16> {
+        let bytes = self.bytes(2)?;
+
+        Ok((u16::from(bytes[0]) << 8) | u16::from(bytes[1]))
+    }
+
+    fn u32(&mut self) -> MdnsResult<u32> {
+        Ok((u32::from(self.u16()?) << 16) | u32::from(self.u16()?))
+    }
+
+    /// Name, following the compression pointers to the names earlier in the packet
+    fn name(&mut self) -> MdnsResult<String> {
+        let mut labels = vec![];
+        let mut pos = self.pos;
+        let mut end = None;
+        let mut pointers = 0;
+        loop {
+            let len = usize::from(
+                *self
+                    .packet
+                    .get(pos)
+                    .ok_or_else(|| self.malformed("Truncated name"))?,
+            );
+            if len & 0xc0 == 0xc0 {
+                let low = usize::from(
+                    *self
+                        .packet
+                        .get(pos + 1)
+                        .ok_or_else(|| self.malformed("Truncated name"))?,
+                );
+                end = end.or(Some(pos + 2));
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return Err(self.malformed("Too many compression pointers"));
+                }
+                pos = ((len & 0x3f) << 8) | low;
+            } else if len == 0 {
+                end = end.or(Some(pos + 1));
+                break;
+            } else {
+                let label = self
+                    .packet
+                    .get(pos + 1..pos + 1 + len)
+                    .ok_or_else(|| self.malformed("Truncated name"))?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + len;
+            }
+        }
+        self.pos = end.unwrap_or(pos);
+
+        Ok(labels.join("."))
+    }
+
+    fn malformed(&self, msg: &str) -> WitnetError<MdnsError> {
+        WitnetError::from(MdnsError::new(
+            MdnsErrorKind::Malformed,
+            self.source.to_string(),
+            msg.to_string(),
+        ))
+    }
+}
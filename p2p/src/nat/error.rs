@@ -0,0 +1,46 @@
+//! Error type definitions for the NAT module.
+
+use failure::Fail;
+use std::fmt;
+use witnet_util::error::WitnetResult;
+
+/// NAT Error
+#[derive(Debug, Fail)]
+#[fail(display = "{} : at \"{}\", msg {}", kind, info, msg)]
+pub struct NatError {
+    /// Error kind
+    kind: NatErrorKind,
+    /// Error parameter
+    info: String,
+    /// Error message
+    msg: String,
+}
+
+impl NatError {
+    /// Create a NAT error based on operation kind and related info.
+    pub fn new(kind: NatErrorKind, info: String, msg: String) -> Self {
+        Self { kind, info, msg }
+    }
+}
+
+/// NAT Errors under different operations
+#[derive(Debug)]
+pub enum NatErrorKind {
+    /// Errors when looking for the gateway
+    Gateway,
+    /// Errors when communicating with the gateway
+    Io,
+    /// Errors reported by the gateway
+    Refused,
+    /// Errors when decoding the responses of the gateway
+    Malformed,
+}
+
+impl fmt::Display for NatErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NatError::{:?}", self)
+    }
+}
+
+/// Result type for the NAT module.
+pub type NatResult<T> = WitnetResult<T, NatError>;
@@ -0,0 +1,206 @@
+//! Library for mapping a port of the router in front of this node to the node itself, so peers
+//! outside of the local network can connect to it
+//!
+//! The mapping can be requested using NAT-PMP (RFC 6886) to the default gateway of the host, which
+//! is only found on Linux, or using the UPnP Internet Gateway Device protocol (see `upnp`) to the
+//! gateway found in the local network.
+
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use witnet_util::error::WitnetError;
+
+use crate::nat::error::{NatError, NatErrorKind, NatResult};
+
+pub mod error;
+pub mod upnp;
+
+/// Port on which the gateways listen to NAT-PMP requests
+pub const NAT_PMP_PORT: u16 = 5351;
+
+/// Number of times a request is sent before giving up, doubling the timeout each time
+const ATTEMPTS: u32 = 4;
+
+/// Timeout of the first attempt of a request
+const INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Port mapped by the gateway
+#[derive(Debug, PartialEq)]
+pub struct Mapping {
+    /// External port of the gateway forwarded to this node
+    pub external_port: u16,
+    /// Time after which the gateway removes the mapping if it is not requested again
+    pub lifetime: Duration,
+}
+
+/// Client of the NAT-PMP server of a gateway
+pub struct NatPmp {
+    gateway: SocketAddr,
+}
+
+impl NatPmp {
+    /// Create a client of the NAT-PMP server at `gateway`
+    pub fn new(gateway: SocketAddr) -> Self {
+        NatPmp { gateway }
+    }
+
+    /// Create a client of the NAT-PMP server of the default gateway of this host, which is read
+    /// from the routing table of Linux
+    pub fn discover() -> NatResult<Self> {
+        let routes = fs::read_to_string("/proc/net/route").map_err(|e| {
+            NatError::new(
+                NatErrorKind::Gateway,
+                "/proc/net/route".to_string(),
+                e.to_string(),
+            )
+        })?;
+        let gateway = parse_default_gateway(&routes).ok_or_else(|| {
+            NatError::new(
+                NatErrorKind::Gateway,
+                "/proc/net/route".to_string(),
+                "No default gateway found".to_string(),
+            )
+        })?;
+
+        Ok(NatPmp::new(SocketAddr::new(
+            IpAddr::V4(gateway),
+            NAT_PMP_PORT,
+        )))
+    }
+
+    /// Address of the NAT-PMP server
+    pub fn gateway(&self) -> SocketAddr {
+        self.gateway
+    }
+
+    /// Request the external address of the gateway
+    pub fn external_address(&self) -> NatResult<Ipv4Addr> {
+        let response = self.request(&[0, 0], 12)?;
+
+        Ok(Ipv4Addr::new(
+            response[8],
+            response[9],
+            response[10],
+            response[11],
+        ))
+    }
+
+    /// Request the gateway to forward its TCP port `internal_port` to the same port of this node
+    /// during `lifetime`. The gateway may choose another external port or lifetime.
+    pub fn map_tcp_port(&self, internal_port: u16, lifetime: Duration) -> NatResult<Mapping> {
+        let mut request = vec![0, 2, 0, 0];
+        request.extend_from_slice(&u16_to_bytes(internal_port));
+        request.extend_from_slice(&u16_to_bytes(internal_port));
+        request.extend_from_slice(&u32_to_bytes(lifetime.as_secs() as u32));
+        let response = self.request(&request, 16)?;
+
+        Ok(Mapping {
+            external_port: u16_from_bytes(&response[10..12]),
+            lifetime: Duration::from_secs(u64::from(u32_from_bytes(&response[12..16]))),
+        })
+    }
+
+    /// Send a request to the gateway and wait for its response, checking that the response is
+    /// for the opcode of the request and reports success
+    fn request(&self, request: &[u8], response_len: usize) -> NatResult<Vec<u8>> {
+        let io_error = |e: io::Error| {
+            WitnetError::from(NatError::new(
+                NatErrorKind::Io,
+                self.gateway.to_string(),
+                e.to_string(),
+            ))
+        };
+        let malformed = |msg: &str| {
+            WitnetError::from(NatError::new(
+                NatErrorKind::Malformed,
+                self.gateway.to_string(),
+                msg.to_string(),
+            ))
+        };
+
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(io_error)?;
+        socket.connect(self.gateway).map_err(io_error)?;
+
+        let mut buf = [0; 16];
+        let mut timeout = INITIAL_TIMEOUT;
+        let mut attempt = 0;
+        let len = loop {
+            socket.send(request).map_err(io_error)?;
+            socket.set_read_timeout(Some(timeout)).map_err(io_error)?;
+            match socket.recv(&mut buf) {
+                Ok(len) => break len,
+                Err(ref e)
+                    if (e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut)
+                        && attempt + 1 < ATTEMPTS =>
+                {
+                    attempt += 1;
+                    timeout *= 2;
+                }
+                Err(e) => return Err(io_error(e)),
+            }
+        };
+
+        if len < response_len {
+            return Err(malformed("Response too short"));
+        }
+        if buf[0] != 0 || buf[1] != 128 + request[1] {
+            return Err(malformed("Unexpected response"));
+        }
+        let result_code = u16_from_bytes(&buf[2..4]);
+        if result_code != 0 {
+            return Err(WitnetError::from(NatError::new(
+                NatErrorKind::Refused,
+                self.gateway.to_string(),
+                format!("Result code {}", result_code),
+            )));
+        }
+
+        Ok(buf[..response_len].to_vec())
+    }
+}
+
+/// Find the gateway of the default route in the contents of `/proc/net/route`
+pub fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[1] != "00000000" {
+            return None;
+        }
+        // The addresses are written as hexadecimal integers in the byte order of the host
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        let octets = if cfg!(target_endian = "little") {
+            gateway.swap_bytes()
+        } else {
+            gateway
+        };
+
+        Some(Ipv4Addr::from(octets)).filter(|ip| !ip.is_unspecified())
+    })
+}
+
+fn u16_to_bytes(value: u16) -> [u8; 2] {
+    [(value >> 8) as u8, value as u8]
+}
+
+fn u32_to_bytes(value: u32) -> [u8; 4] {
+    [
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ]
+}
+
+fn u16_from_bytes(bytes: &[u8]) -> u16 {
+    (u16::from(bytes[0]) << 8) | u16::from(bytes[1])
+}
+
+fn u32_from_bytes(bytes: &[u8]) -> u32 {
+    (u32::from(bytes[0]) << 24)
+        | (u32::from(bytes[1]) << 16)
+        | (u32::from(bytes[2]) << 8)
+        | u32::from(bytes[3])
+}
@@ -0,0 +1,373 @@
+//! Client of the WAN connection service of UPnP Internet Gateway Devices (IGD)
+//!
+//! The gateway is found by sending an SSDP search to the local network, so it does not depend on
+//! the routing table of the host. Its description is then downloaded to find the control URL of
+//! the WAN connection service, to which the SOAP actions are sent.
+
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+use witnet_util::error::WitnetError;
+
+use crate::nat::error::{NatError, NatErrorKind, NatResult};
+use crate::nat::Mapping;
+
+/// Multicast address of SSDP
+pub const SSDP_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+
+/// Port of SSDP
+pub const SSDP_PORT: u16 = 1900;
+
+/// Type of device searched with SSDP
+pub const GATEWAY_DEVICE: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+
+/// Types of the services able to map ports, in order of preference
+pub const WAN_SERVICES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+
+/// Timeout of the HTTP requests to the gateway
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Error code of the gateways which only accept port mappings without lifetime
+const ONLY_PERMANENT_LEASES_SUPPORTED: u32 = 725;
+
+/// Client of the WAN connection service of an Internet Gateway Device
+#[derive(Debug, PartialEq)]
+pub struct Igd {
+    /// Address of the HTTP server of the gateway
+    address: SocketAddr,
+    /// Path of the control URL of the service
+    control_path: String,
+    /// Type of the service
+    service: String,
+}
+
+impl Igd {
+    /// Create a client of the service of type `service` controlled at `control_path` in the HTTP
+    /// server at `address`
+    pub fn new(address: SocketAddr, control_path: &str, service: &str) -> Self {
+        Igd {
+            address,
+            control_path: control_path.to_string(),
+            service: service.to_string(),
+        }
+    }
+
+    /// Search the local network for an Internet Gateway Device during `timeout`, returning the
+    /// first one offering a WAN connection service
+    pub fn discover(timeout: Duration) -> NatResult<Self> {
+        let ssdp = SocketAddr::new(IpAddr::V4(SSDP_ADDR), SSDP_PORT);
+        let io_error = |e: io::Error| {
+            WitnetError::from(NatError::new(
+                NatErrorKind::Io,
+                ssdp.to_string(),
+                e.to_string(),
+            ))
+        };
+
+        let search = format!(
+            "M-SEARCH * HTTP/1.1\r\n\
+             HOST: {}\r\n\
+             MAN: \"ssdp:discover\"\r\n\
+             MX: 2\r\n\
+             ST: {}\r\n\r\n",
+            ssdp, GATEWAY_DEVICE
+        );
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(io_error)?;
+        socket.send_to(search.as_bytes(), ssdp).map_err(io_error)?;
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0; 2048];
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(WitnetError::from(NatError::new(
+                    NatErrorKind::Gateway,
+                    ssdp.to_string(),
+                    "No Internet Gateway Device with a WAN connection service found".to_string(),
+                )));
+            }
+            socket
+                .set_read_timeout(Some(deadline - now))
+                .map_err(io_error)?;
+            let len = match socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(ref e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    continue;
+                }
+                Err(e) => return Err(io_error(e)),
+            };
+
+            let response = String::from_utf8_lossy(&buf[..len]);
+            let igd = parse_ssdp_location(&response)
+                .and_then(|location| parse_url(&location))
+                .and_then(|(address, path)| {
+                    let description = http_request(address, "GET", &path, &[], "").ok()?;
+                    let (service, control) = parse_wan_service(&description)?;
+                    let control_path = match parse_url(&control) {
+                        Some((_, path)) => path,
+                        None if control.starts_with('/') => control,
+                        None => format!("/{}", control),
+                    };
+
+                    Some(Igd::new(address, &control_path, &service))
+                });
+            if let Some(igd) = igd {
+                return Ok(igd);
+            }
+        }
+    }
+
+    /// Address of the HTTP server of the gateway
+    pub fn gateway(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Request the external address of the gateway
+    pub fn external_address(&self) -> NatResult<Ipv4Addr> {
+        let response = self.action("GetExternalIPAddress", &[])?;
+
+        xml_text(&response, "NewExternalIPAddress")
+            .and_then(|address| address.trim().parse().ok())
+            .ok_or_else(|| self.malformed("Missing external address"))
+    }
+
+    /// Request the gateway to forward its TCP port `internal_port` to the same port of this node
+    /// during `lifetime`. Gateways which only support mappings without lifetime are asked for one,
+    /// which is requested again like the others.
+    pub fn map_tcp_port(&self, internal_port: u16, lifetime: Duration) -> NatResult<Mapping> {
+        let io_error = |e: io::Error| {
+            WitnetError::from(NatError::new(
+                NatErrorKind::Io,
+                self.address.to_string(),
+                e.to_string(),
+            ))
+        };
+        // The address of this node in the network of the gateway
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(io_error)?;
+        socket.connect(self.address).map_err(io_error)?;
+        let internal_client = socket.local_addr().map_err(io_error)?.ip();
+
+        let add_port_mapping = |lease: u64| {
+            self.soap(
+                "AddPortMapping",
+                &[
+                    ("NewRemoteHost", String::new()),
+                    ("NewExternalPort", internal_port.to_string()),
+                    ("NewProtocol", "TCP".to_string()),
+                    ("NewInternalPort", internal_port.to_string()),
+                    ("NewInternalClient", internal_client.to_string()),
+                    ("NewEnabled", "1".to_string()),
+                    ("NewPortMappingDescription", "witnet".to_string()),
+                    ("NewLeaseDuration", lease.to_string()),
+                ],
+            )
+        };
+        let response = match add_port_mapping(lifetime.as_secs())? {
+            Err(ONLY_PERMANENT_LEASES_SUPPORTED) => add_port_mapping(0)?,
+            response => response,
+        };
+        response.map_err(|code| self.refused(code))?;
+
+        Ok(Mapping {
+            external_port: internal_port,
+            lifetime,
+        })
+    }
+
+    /// Send an action to the service, failing if the gateway reports an error
+    fn action(&self, action: &str, arguments: &[(&str, String)]) -> NatResult<String> {
+        self.soap(action, arguments)?
+            .map_err(|code| self.refused(code))
+    }
+
+    /// Send an action to the service, returning the body of the response or the error code of the
+    /// UPnP fault reported by the gateway
+    fn soap(&self, action: &str, arguments: &[(&str, String)]) -> NatResult<Result<String, u32>> {
+        let arguments: String = arguments
+            .iter()
+            .map(|(name, value)| format!("<{0}>{1}</{0}>", name, value))
+            .collect();
+        let body = format!(
+            "<?xml version=\"1.0\"?>\
+             <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+             s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+             <s:Body><u:{0} xmlns:u=\"{1}\">{2}</u:{0}></s:Body></s:Envelope>",
+            action, self.service, arguments
+        );
+        let soap_action = format!("\"{}#{}\"", self.service, action);
+        let headers = [
+            ("Content-Type", "text/xml; charset=\"utf-8\""),
+            ("SOAPAction", soap_action.as_str()),
+        ];
+
+        match http_request(self.address, "POST", &self.control_path, &headers, &body) {
+            Ok(response) => Ok(Ok(response)),
+            // Faults are answered with a 500 status and the error code in the body
+            Err(HttpError::Status(500, response)) => xml_text(&response, "errorCode")
+                .and_then(|code| code.trim().parse().ok())
+                .map(Err)
+                .ok_or_else(|| self.malformed("Missing UPnP error code")),
+            Err(HttpError::Status(status, _)) => {
+                Err(self.malformed(&format!("Unexpected HTTP status {}", status)))
+            }
+            Err(HttpError::Io(e)) => Err(WitnetError::from(NatError::new(
+                NatErrorKind::Io,
+                self.address.to_string(),
+                e.to_string(),
+            ))),
+            Err(HttpError::Malformed) => Err(self.malformed("Malformed HTTP response")),
+        }
+    }
+
+    fn refused(&self, code: u32) -> WitnetError<NatError> {
+        WitnetError::from(NatError::new(
+            NatErrorKind::Refused,
+            self.address.to_string(),
+            format!("UPnP error {}", code),
+        ))
+    }
+
+    fn malformed(&self, msg: &str) -> WitnetError<NatError> {
+        WitnetError::from(NatError::new(
+            NatErrorKind::Malformed,
+            self.address.to_string(),
+            msg.to_string(),
+        ))
+    }
+}
+
+/// Errors of the HTTP requests to the gateway
+enum HttpError {
+    Io(io::Error),
+    Status(u16, String),
+    Malformed,
+}
+
+/// Send an HTTP request to `address`, returning the body of the response. HTTP/1.0 is used so the
+/// gateway closes the connection after the response, which is never chunked.
+fn http_request(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> Result<String, HttpError> {
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Length: {}\r\n",
+        method,
+        path,
+        address,
+        body.len()
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+
+    let mut stream = TcpStream::connect_timeout(&address, HTTP_TIMEOUT).map_err(HttpError::Io)?;
+    stream
+        .set_read_timeout(Some(HTTP_TIMEOUT))
+        .map_err(HttpError::Io)?;
+    stream
+        .write_all(request.as_bytes())
+        .map_err(HttpError::Io)?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).map_err(HttpError::Io)?;
+    let response = String::from_utf8_lossy(&response);
+
+    let status = response
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or(HttpError::Malformed)?;
+    let body = response
+        .find("\r\n\r\n")
+        .map(|start| response[start + 4..].to_string())
+        .ok_or(HttpError::Malformed)?;
+
+    if status == 200 {
+        Ok(body)
+    } else {
+        Err(HttpError::Status(status, body))
+    }
+}
+
+/// Find the URL of the description of the gateway in an SSDP response
+pub fn parse_ssdp_location(response: &str) -> Option<String> {
+    response.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+
+        if name.trim().eq_ignore_ascii_case("location") {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Split an `http://` URL whose host is an IP address into the address of the server and the
+/// path. Gateways always use the IP address of their local network interface in their URLs.
+pub fn parse_url(url: &str) -> Option<(SocketAddr, String)> {
+    let rest = url.trim().strip_prefix("http://")?;
+    let (host, path) = match rest.find('/') {
+        Some(start) => (&rest[..start], rest[start..].to_string()),
+        None => (rest, "/".to_string()),
+    };
+    let address = host.parse::<SocketAddr>().ok().or_else(|| {
+        host.parse::<IpAddr>()
+            .ok()
+            .map(|ip| SocketAddr::new(ip, 80))
+    })?;
+
+    Some((address, path))
+}
+
+/// Find the type and the control URL of the preferred WAN connection service in the description
+/// of a gateway
+pub fn parse_wan_service(description: &str) -> Option<(String, String)> {
+    let services: Vec<(&str, &str)> = description
+        .split("<service>")
+        .skip(1)
+        .filter_map(|service| {
+            Some((
+                xml_text(service, "serviceType")?.trim(),
+                xml_text(service, "controlURL")?.trim(),
+            ))
+        })
+        .collect();
+
+    WAN_SERVICES.iter().find_map(|wan_service| {
+        services
+            .iter()
+            .find(|(service, _)| service == wan_service)
+            .map(|(service, control)| (service.to_string(), control.to_string()))
+    })
+}
+
+/// Text inside the first element named `tag`, ignoring its namespace prefix and attributes
+fn xml_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = xml.match_indices('<').find_map(|(start, _)| {
+        let element = &xml[start + 1..];
+        let name_len = element.find(|c: char| c == '>' || c.is_whitespace())?;
+        let name = &element[..name_len];
+        let local_name = name.rsplit(':').next()?;
+        if local_name == tag {
+            Some((start + 1 + element.find('>')? + 1, name))
+        } else {
+            None
+        }
+    });
+    let (start, name) = open?;
+    let end = xml[start..].find(&format!("</{}>", name))?;
+
+    Some(&xml[start..start + end])
+}
@@ -3,7 +3,7 @@
 use serde_derive::{Deserialize, Serialize};
 
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use rand::{thread_rng, Rng};
 
@@ -13,23 +13,91 @@ use crate::peers::error::PeersResult;
 
 pub mod error;
 
+/// Maximum number of peer addresses in a `Peers` message
+pub const MAX_PEERS_PER_MESSAGE: usize = 1000;
+
+/// Maximum number of new peers heard of from the same source, so a single peer cannot fill the
+/// new peers table with its own addresses
+pub const MAX_NEW_PEERS_PER_SOURCE: usize = 256;
+
+/// Time after which a peer is not shared with other peers if it has not been heard of again
+/// (in seconds)
+pub const MAX_SHARED_PEER_AGE: i64 = 24 * 60 * 60;
+
+/// Delay before connecting again to a peer after its first failed connection (in seconds). The
+/// delay doubles with each failed connection
+pub const BACKOFF_BASE: i64 = 10;
+
+/// Maximum delay before connecting again to a peer after a failed connection (in seconds)
+pub const BACKOFF_CAP: i64 = 60 * 60;
+
 /// Peer information being used while listing available Witnet peers
 #[derive(Serialize, Deserialize)]
 struct PeerInfo {
     address: SocketAddr,
-    _timestamp: i64,
+    /// Last time the peer was heard of or connected to
+    #[serde(rename = "_timestamp")]
+    timestamp: i64,
+    /// IP address of the peer which gossiped this peer, if it was heard of through gossip
+    #[serde(default)]
+    source: Option<IpAddr>,
 }
 
-/// Peers TBD
+/// Number of seconds of uptime worth a point of reputation
+pub const UPTIME_PER_POINT: u64 = 60 * 60;
+
+/// Record of the behavior of a peer in past sessions, persisted along with the peers so the node
+/// prefers the historically reliable peers after a restart
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reputation {
+    /// Time connected to the peer in consolidated outbound sessions (in seconds)
+    pub uptime: u64,
+    /// Blocks received from the peer which were new to the node
+    pub useful_blocks: u64,
+    /// Sum of the misbehavior scores of the peer
+    pub misbehavior: u64,
+}
+
+impl Reputation {
+    /// Score of the peer: a point per `UPTIME_PER_POINT` seconds of uptime and per useful block,
+    /// minus a point per misbehavior point
+    pub fn score(&self) -> i64 {
+        let merits = (self.uptime / UPTIME_PER_POINT).saturating_add(self.useful_blocks);
+
+        merits.min(i64::max_value() as u64) as i64
+            - self.misbehavior.min(i64::max_value() as u64) as i64
+    }
+}
+
+/// Peers known to the node, split in two tables: the "new" peers, whose addresses have been heard
+/// of, and the "tried" peers, to which the node has connected successfully
 #[derive(Default, Serialize, Deserialize)]
 pub struct Peers {
-    /// Server sessions
+    /// New peers
     peers: HashMap<SocketAddr, PeerInfo>,
+    /// Tried peers (peers serialized by older releases have none)
+    #[serde(default)]
+    tried: HashMap<SocketAddr, PeerInfo>,
+    /// Peers whose last connections failed, which are not returned as random peers until their
+    /// backoff expires
+    #[serde(skip)]
+    backoffs: HashMap<SocketAddr, Backoff>,
+    /// Reputation of the known peers (peers serialized by older releases have none)
+    #[serde(default)]
+    reputations: HashMap<SocketAddr, Reputation>,
+}
+
+/// Failed connections to a peer since the last successful one
+struct Backoff {
+    /// Number of failed connections
+    failures: u32,
+    /// Timestamp after which the peer can be connected to again
+    retry_at: i64,
 }
 
 impl Peers {
     /// Add multiple peer addresses and save timestamp
-    /// If an address did already exist, it gets overwritten
+    /// If an address did already exist, it gets overwritten, in the table it was in
     /// Returns all the overwritten addresses
     pub fn add(&mut self, addrs: Vec<SocketAddr>) -> PeersResult<Vec<SocketAddr>> {
         // Insert address
@@ -37,12 +105,18 @@ impl Peers {
         Ok(addrs
             .into_iter()
             .filter_map(|address| {
-                self.peers
+                let table = if self.tried.contains_key(&address) {
+                    &mut self.tried
+                } else {
+                    &mut self.peers
+                };
+                table
                     .insert(
                         address,
                         PeerInfo {
                             address,
-                            _timestamp: get_timestamp(), //msg.timestamp,
+                            timestamp: get_timestamp(), //msg.timestamp,
+                            source: None,
                         },
                     )
                     .map(|v| v.address)
@@ -50,38 +124,264 @@ impl Peers {
             .collect())
     }
 
+    /// Add multiple peer addresses gossiped by the peer at `source` and save timestamp
+    /// The known addresses only get their timestamp updated, and the unknown ones are added to the
+    /// new peers as long as `source` has not reached `MAX_NEW_PEERS_PER_SOURCE`
+    /// Returns the added addresses
+    pub fn add_from(
+        &mut self,
+        addrs: Vec<SocketAddr>,
+        source: IpAddr,
+    ) -> PeersResult<Vec<SocketAddr>> {
+        let timestamp = get_timestamp();
+        let mut from_source = self
+            .peers
+            .values()
+            .filter(|info| info.source == Some(source))
+            .count();
+
+        let mut added = vec![];
+        for address in addrs {
+            let known = match self.tried.get_mut(&address) {
+                Some(info) => Some(info),
+                None => self.peers.get_mut(&address),
+            };
+            if let Some(info) = known {
+                info.timestamp = timestamp;
+            } else if from_source < MAX_NEW_PEERS_PER_SOURCE {
+                from_source += 1;
+                self.peers.insert(
+                    address,
+                    PeerInfo {
+                        address,
+                        timestamp,
+                        source: Some(source),
+                    },
+                );
+                added.push(address);
+            }
+        }
+
+        Ok(added)
+    }
+
+    /// Add multiple peer addresses to the tried peers, moving them from the new peers if needed,
+    /// and save timestamp
+    /// Returns the addresses which were not tried before
+    pub fn add_tried(&mut self, addrs: Vec<SocketAddr>) -> PeersResult<Vec<SocketAddr>> {
+        Ok(addrs
+            .into_iter()
+            .filter_map(|address| {
+                self.peers.remove(&address);
+                self.backoffs.remove(&address);
+                match self.tried.insert(
+                    address,
+                    PeerInfo {
+                        address,
+                        timestamp: get_timestamp(),
+                        source: None,
+                    },
+                ) {
+                    Some(_) => None,
+                    None => Some(address),
+                }
+            })
+            .collect())
+    }
+
+    /// Record a failed connection to multiple peer addresses, so they are not returned as random
+    /// peers until their backoff expires. The backoff starts at `BACKOFF_BASE` seconds and doubles
+    /// with each failed connection up to `BACKOFF_CAP`, with a random jitter of up to half of it
+    /// so the peers failing at once are not retried at once. It is reset by `add_tried`
+    /// Returns the known addresses, unknown ones are ignored
+    pub fn add_failed(&mut self, addrs: Vec<SocketAddr>) -> PeersResult<Vec<SocketAddr>> {
+        let now = get_timestamp();
+        let mut rng = thread_rng();
+
+        let mut known = vec![];
+        for address in addrs {
+            if !self.peers.contains_key(&address) && !self.tried.contains_key(&address) {
+                continue;
+            }
+
+            let backoff = self.backoffs.entry(address).or_insert(Backoff {
+                failures: 0,
+                retry_at: now,
+            });
+            backoff.failures = backoff.failures.saturating_add(1);
+
+            // The delay reaches the cap long before the shift could overflow
+            let delay = if backoff.failures > 32 {
+                BACKOFF_CAP
+            } else {
+                std::cmp::min(BACKOFF_CAP, BACKOFF_BASE << (backoff.failures - 1))
+            };
+            backoff.retry_at = now + delay - rng.gen_range(0, delay / 2 + 1);
+
+            known.push(address);
+        }
+
+        Ok(known)
+    }
+
+    /// Timestamp after which a peer whose last connections failed can be connected to again
+    pub fn get_retry_time(&self, address: &SocketAddr) -> Option<i64> {
+        self.backoffs.get(address).map(|backoff| backoff.retry_at)
+    }
+
+    /// Add the uptime, useful blocks and misbehavior score of `update` to the reputation of a
+    /// known peer
+    /// Returns the updated reputation, or `None` if the peer is unknown
+    pub fn update_reputation(
+        &mut self,
+        address: SocketAddr,
+        update: Reputation,
+    ) -> PeersResult<Option<Reputation>> {
+        if !self.peers.contains_key(&address) && !self.tried.contains_key(&address) {
+            return Ok(None);
+        }
+
+        let reputation = self.reputations.entry(address).or_default();
+        reputation.uptime = reputation.uptime.saturating_add(update.uptime);
+        reputation.useful_blocks = reputation
+            .useful_blocks
+            .saturating_add(update.useful_blocks);
+        reputation.misbehavior = reputation.misbehavior.saturating_add(update.misbehavior);
+
+        Ok(Some(*reputation))
+    }
+
+    /// Get the reputation of a peer, which is the default one if nothing is known about it
+    pub fn get_reputation(&self, address: &SocketAddr) -> Reputation {
+        self.reputations.get(address).cloned().unwrap_or_default()
+    }
+
+    /// Get the reputations of all the peers with a reputation
+    pub fn get_reputations(&self) -> PeersResult<Vec<(SocketAddr, Reputation)>> {
+        Ok(self
+            .reputations
+            .iter()
+            .map(|(address, reputation)| (*address, *reputation))
+            .collect())
+    }
+
+    /// Get the peers whose last connections failed and which cannot be connected to again yet at
+    /// timestamp `now`
+    pub fn get_backing_off(&self, now: i64) -> PeersResult<Vec<SocketAddr>> {
+        Ok(self
+            .backoffs
+            .iter()
+            .filter(|(_, backoff)| backoff.retry_at > now)
+            .map(|(address, _)| *address)
+            .collect())
+    }
+
     /// Remove a peer given an address
     /// Returns the removed addresses
     pub fn remove(&mut self, addrs: &[SocketAddr]) -> PeersResult<Vec<SocketAddr>> {
         Ok(addrs
             .iter()
-            .filter_map(|address| self.peers.remove(&address).map(|info| info.address))
+            .filter_map(|address| {
+                self.backoffs.remove(&address);
+                self.reputations.remove(&address);
+                let new = self.peers.remove(&address);
+                let tried = self.tried.remove(&address);
+                new.or(tried).map(|info| info.address)
+            })
             .collect())
     }
 
     /// Get a random socket address from the peers list
+    /// When there are both tried and new peers, each table is chosen half of the times. The tried
+    /// peers are picked from two random ones, the one with the best reputation, so the reliable
+    /// peers are preferred
+    /// The peers whose backoff has not expired are skipped
     pub fn get_random(&mut self) -> PeersResult<Option<SocketAddr>> {
-        // Random index with range [0, len) of the peers vector
-        let index = thread_rng().gen_range(0, std::cmp::max(self.peers.len(), 1));
+        let now = get_timestamp();
+        let new = self.get_available(&self.peers, now);
+        let tried = self.get_available(&self.tried, now);
 
-        // Get element at index
-        let random_addr = self
-            .peers
-            // get peer infos
-            .values()
-            // enumerate them -> (indices, peer info)
-            .enumerate()
-            // filter by index and get address -> Iterator<Option<SocketAddr>>
-            .filter_map(|(i, v)| if i == index { Some(v.address) } else { None })
-            // Get first one, because
-            .next()
-            .map(|v| v.to_owned());
+        if tried.is_empty() || (!new.is_empty() && thread_rng().gen()) {
+            return Ok(random_address(&new));
+        }
+
+        let best = match (random_address(&tried), random_address(&tried)) {
+            (Some(a), Some(b)) => {
+                if self.get_reputation(&b).score() > self.get_reputation(&a).score() {
+                    Some(b)
+                } else {
+                    Some(a)
+                }
+            }
+            (a, _) => a,
+        };
 
-        Ok(random_addr)
+        Ok(best)
+    }
+
+    /// Get a random socket address from the new peers, which the node has not connected to yet
+    /// The peers whose backoff has not expired are skipped
+    pub fn get_random_new(&self) -> PeersResult<Option<SocketAddr>> {
+        let available = self.get_available(&self.peers, get_timestamp());
+
+        Ok(random_address(&available))
+    }
+
+    /// Get the addresses of a table of peers which are not backing off at `now`
+    fn get_available(&self, table: &HashMap<SocketAddr, PeerInfo>, now: i64) -> Vec<SocketAddr> {
+        table
+            .keys()
+            .filter(|address| {
+                self.backoffs
+                    .get(address)
+                    .map_or(true, |backoff| backoff.retry_at <= now)
+            })
+            .cloned()
+            .collect()
     }
 
     /// Get all the peers from the list
     pub fn get_all(&self) -> PeersResult<Vec<SocketAddr>> {
+        Ok(self
+            .tried
+            .values()
+            .chain(self.peers.values())
+            .map(|v| v.address)
+            .collect())
+    }
+
+    /// Get up to `max` random peers, tried or new, which have been heard of or connected to since
+    /// the timestamp `since`
+    pub fn get_recent(&self, max: usize, since: i64) -> PeersResult<Vec<SocketAddr>> {
+        let mut addresses: Vec<SocketAddr> = self
+            .tried
+            .values()
+            .chain(self.peers.values())
+            .filter(|info| info.timestamp >= since)
+            .map(|info| info.address)
+            .collect();
+        thread_rng().shuffle(&mut addresses);
+        addresses.truncate(max);
+
+        Ok(addresses)
+    }
+
+    /// Get all the new peers
+    pub fn get_new(&self) -> PeersResult<Vec<SocketAddr>> {
         Ok(self.peers.values().map(|v| v.address).collect())
     }
+
+    /// Get all the tried peers
+    pub fn get_tried(&self) -> PeersResult<Vec<SocketAddr>> {
+        Ok(self.tried.values().map(|v| v.address).collect())
+    }
+}
+
+/// Get a random socket address from a list of addresses
+fn random_address(addresses: &[SocketAddr]) -> Option<SocketAddr> {
+    // Random index with range [0, len) of the addresses vector
+    let index = thread_rng().gen_range(0, std::cmp::max(addresses.len(), 1));
+
+    // Get element at index
+    addresses.get(index).cloned()
 }
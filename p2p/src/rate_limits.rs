@@ -0,0 +1,152 @@
+//! Library for limiting the rate at which a peer can send each kind of message, so a chatty peer
+//! cannot monopolize the processing of messages of the node, and for limiting the bandwidth used
+//! by the connections with the peers
+//!
+//! Each kind of message has its own token bucket: a message takes a token from the bucket, and the
+//! bucket is refilled at the allowed rate, up to the number of messages allowed in a minute. The
+//! bandwidth limits are buckets of bytes, refilled up to the number of bytes allowed in a second.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Number of bytes per second in a kilobit per second
+const BYTES_PER_KBPS: f64 = 125.0;
+
+/// Kinds of messages whose rate is limited
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LimitedMessage {
+    /// Inventory announcements (`Inv`)
+    Inv,
+    /// Inventory requests (`GetData`)
+    GetData,
+    /// Address gossip (`GetPeers` and `Peers`)
+    Peers,
+}
+
+/// Bucket of tokens, refilled at a constant rate up to its capacity
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    /// Maximum number of tokens, which is also the number of tokens added per minute
+    capacity: f64,
+    /// Number of tokens currently in the bucket
+    tokens: f64,
+    /// Last time the bucket was refilled
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket allowing `per_minute` tokens per minute
+    pub fn new(per_minute: u32, now: Instant) -> Self {
+        TokenBucket {
+            capacity: f64::from(per_minute),
+            tokens: f64::from(per_minute),
+            last_refill: now,
+        }
+    }
+
+    /// Take a token from the bucket, after refilling it with the tokens earned since the last call
+    /// Returns false if the bucket is empty
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        if now > self.last_refill {
+            let elapsed = as_secs_f64(now.duration_since(self.last_refill));
+            self.tokens = (self.tokens + elapsed * self.capacity / 60.0).min(self.capacity);
+            self.last_refill = now;
+        }
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Token buckets of the kinds of messages received from a peer
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    buckets: HashMap<LimitedMessage, TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter allowing the given number of messages per minute of each kind. The
+    /// kinds with a limit of zero, or not listed, are not limited
+    pub fn new(limits: &[(LimitedMessage, u32)], now: Instant) -> Self {
+        RateLimiter {
+            buckets: limits
+                .iter()
+                .filter(|(_, per_minute)| *per_minute > 0)
+                .map(|(kind, per_minute)| (*kind, TokenBucket::new(*per_minute, now)))
+                .collect(),
+        }
+    }
+
+    /// Check whether a message of kind `kind` received at `now` is within the limits, counting it
+    pub fn check(&mut self, kind: LimitedMessage, now: Instant) -> bool {
+        self.buckets
+            .get_mut(&kind)
+            .map_or(true, |bucket| bucket.try_take(now))
+    }
+}
+
+/// Bandwidth limit: bucket of the bytes that can be transferred, refilled at a constant rate up to
+/// a second worth of bytes
+#[derive(Clone, Debug)]
+pub struct BandwidthLimit {
+    /// Number of bytes added per second, which is also the maximum number of bytes
+    bytes_per_second: f64,
+    /// Number of bytes that can be transferred, negative if more bytes were transferred
+    available: f64,
+    /// Last time the bucket was refilled
+    last_refill: Instant,
+}
+
+impl BandwidthLimit {
+    /// Create a limit of `kbps` kilobits per second, which allows a second worth of bytes at once
+    pub fn new(kbps: u32, now: Instant) -> Self {
+        let bytes_per_second = f64::from(kbps) * BYTES_PER_KBPS;
+
+        BandwidthLimit {
+            bytes_per_second,
+            available: bytes_per_second,
+            last_refill: now,
+        }
+    }
+
+    /// Number of bytes that can be transferred at `now`
+    pub fn available(&mut self, now: Instant) -> usize {
+        if now > self.last_refill {
+            let elapsed = as_secs_f64(now.duration_since(self.last_refill));
+            self.available =
+                (self.available + elapsed * self.bytes_per_second).min(self.bytes_per_second);
+            self.last_refill = now;
+        }
+
+        if self.available >= 1.0 {
+            self.available as usize
+        } else {
+            0
+        }
+    }
+
+    /// Count `bytes` transferred
+    pub fn consume(&mut self, bytes: usize) {
+        self.available -= bytes as f64;
+    }
+
+    /// Time at which some bytes can be transferred again
+    pub fn next_available(&self) -> Instant {
+        if self.available >= 1.0 {
+            self.last_refill
+        } else {
+            let missing_secs = (1.0 - self.available) / self.bytes_per_second;
+
+            self.last_refill + Duration::from_nanos((missing_secs * 1_000_000_000.0).ceil() as u64)
+        }
+    }
+}
+
+/// Number of seconds of a duration, with their fractional part
+fn as_secs_f64(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0
+}
@@ -0,0 +1,57 @@
+//! Library for deciding which of the items received from the peers are relayed to the other peers
+//!
+//! The items added by the node itself are always relayed. The items received from a peer are
+//! relayed unless the policy rejects them because the node did not request them, because the
+//! reputation of the peer is too low, or because they have not been validated.
+
+use std::net::SocketAddr;
+
+/// Policy for relaying the items received from the peers to the other peers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RelayPolicy {
+    /// Relay the items that the peers send without the node requesting them
+    pub unsolicited: bool,
+    /// Minimum reputation score of the peers whose items are relayed, if any
+    pub min_reputation: Option<i64>,
+    /// Relay the items which have not been validated yet
+    pub unvalidated: bool,
+}
+
+/// Relay every item
+impl Default for RelayPolicy {
+    fn default() -> Self {
+        RelayPolicy {
+            unsolicited: true,
+            min_reputation: None,
+            unvalidated: true,
+        }
+    }
+}
+
+/// Peer from which an item was received, and how
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemOrigin {
+    /// Socket address of the peer
+    pub address: SocketAddr,
+    /// Whether the node requested the item from the peer
+    pub solicited: bool,
+    /// Reputation score of the peer
+    pub reputation: i64,
+}
+
+impl RelayPolicy {
+    /// Check whether an item received from `origin`, or added by the node itself if `origin` is
+    /// `None`, can be relayed
+    pub fn allows(&self, origin: Option<&ItemOrigin>, validated: bool) -> bool {
+        match origin {
+            None => true,
+            Some(origin) => {
+                (origin.solicited || self.unsolicited)
+                    && (validated || self.unvalidated)
+                    && self
+                        .min_reputation
+                        .map_or(true, |min_reputation| origin.reputation >= min_reputation)
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 //! Library for managing the sessions
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::time::Duration;
 
+use crate::sessions::capabilities::Capabilities;
 use crate::sessions::error::{SessionsError, SessionsErrorKind, SessionsResult};
 use witnet_util::error::WitnetError;
 
@@ -9,6 +11,10 @@ use witnet_util::error::WitnetError;
 pub struct SessionInfo<T> {
     /// Session reference (e.g. actor address)
     pub reference: T,
+    /// Protocol version and features negotiated with the peer, once the session is consolidated
+    pub capabilities: Option<Capabilities>,
+    /// Round-trip time of the last ping answered by the peer
+    pub latency: Option<Duration>,
 }
 
 /// Sessions struct contains:
@@ -59,7 +65,14 @@ impl<T> BoundedSessions<T> {
             )));
         }
         // Insert session into the right collection
-        self.collection.insert(address, SessionInfo { reference });
+        self.collection.insert(
+            address,
+            SessionInfo {
+                reference,
+                capabilities: None,
+                latency: None,
+            },
+        );
 
         // Return success
         Ok(())
@@ -0,0 +1,35 @@
+//! Protocol version and features negotiated with a peer during the handshake
+
+/// Protocol version and features of a node, or negotiated with a peer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Protocol version
+    pub version: u32,
+    /// Bitfield of the supported features
+    pub features: u64,
+}
+
+impl Capabilities {
+    /// Negotiate the capabilities used in a session: the lowest of both protocol versions and the
+    /// features supported by both nodes. Returns `None` if the protocol version of the remote node
+    /// is older than `min_version`, as the session cannot be established
+    pub fn negotiate(
+        local: Capabilities,
+        remote: Capabilities,
+        min_version: u32,
+    ) -> Option<Capabilities> {
+        if remote.version < min_version {
+            return None;
+        }
+
+        Some(Capabilities {
+            version: local.version.min(remote.version),
+            features: local.features & remote.features,
+        })
+    }
+
+    /// Check whether all the bits of `features` are supported
+    pub fn supports(&self, features: u64) -> bool {
+        self.features & features == features
+    }
+}
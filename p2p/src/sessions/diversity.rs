@@ -0,0 +1,144 @@
+//! Network groups of the peers, used to spread the outbound sessions over many networks so that an
+//! attacker controlling a few networks cannot take all the outbound slots of a node
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use witnet_util::net::IpNetwork;
+
+/// Length of the prefix grouping the IPv4 addresses not found in the AS map
+pub const IPV4_GROUP_PREFIX_LEN: u8 = 16;
+
+/// Length of the prefix grouping the IPv6 addresses not found in the AS map
+pub const IPV6_GROUP_PREFIX_LEN: u8 = 32;
+
+/// Group of peers assumed to be under the control of the same operator
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NetGroup {
+    /// Autonomous system announcing the address of the peer
+    Asn(u32),
+    /// Network of the address of the peer: /16 for IPv4 and /32 for IPv6
+    Network(IpNetwork),
+}
+
+/// Map from IP networks to the numbers of the autonomous systems announcing them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AsMap {
+    /// Networks and their autonomous system numbers, the most specific networks first
+    networks: Vec<(IpNetwork, u32)>,
+}
+
+impl AsMap {
+    /// Create a map from a list of networks and their autonomous system numbers
+    pub fn new(mut networks: Vec<(IpNetwork, u32)>) -> Self {
+        networks.sort_by(|(a, _), (b, _)| b.prefix_len().cmp(&a.prefix_len()));
+
+        AsMap { networks }
+    }
+
+    /// Number of the autonomous system announcing `ip`, taken from the most specific network
+    /// containing it
+    pub fn lookup(&self, ip: IpAddr) -> Option<u32> {
+        self.networks
+            .iter()
+            .find(|(network, _)| network.contains(ip))
+            .map(|(_, asn)| *asn)
+    }
+
+    /// Number of networks in the map
+    pub fn len(&self) -> usize {
+        self.networks.len()
+    }
+
+    /// Check whether the map has no networks
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+}
+
+/// Parse a map written as one network per line followed by its autonomous system number, e.g.:
+/// `192.0.2.0/24 64496`. Empty lines and lines starting with `#` are skipped
+impl FromStr for AsMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut networks = vec![];
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let (network, asn) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(network), Some(asn), None) => (network, asn),
+                _ => {
+                    return Err(format!(
+                        "Line {}: expected a network and an AS number",
+                        number + 1
+                    ))
+                }
+            };
+            let network = network
+                .parse::<IpNetwork>()
+                .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+            let asn = asn
+                .trim_start_matches("AS")
+                .parse::<u32>()
+                .map_err(|e| format!("Line {}: invalid AS number {:?}: {}", number + 1, asn, e))?;
+
+            networks.push((network, asn));
+        }
+
+        Ok(AsMap::new(networks))
+    }
+}
+
+/// Cap of outbound sessions with peers of the same network group
+#[derive(Clone, Debug, Default)]
+pub struct OutboundDiversity {
+    /// Maximum number of outbound sessions with peers of the same group, `None` for no cap
+    pub max_per_group: Option<u16>,
+    /// Autonomous systems of the networks, the addresses not found are grouped by network
+    pub asmap: AsMap,
+}
+
+impl OutboundDiversity {
+    /// Network group of a peer: its autonomous system if found in the AS map, or its network
+    /// otherwise
+    pub fn group(&self, ip: IpAddr) -> NetGroup {
+        match self.asmap.lookup(ip) {
+            Some(asn) => NetGroup::Asn(asn),
+            None => {
+                let prefix_len = match ip {
+                    IpAddr::V4(_) => IPV4_GROUP_PREFIX_LEN,
+                    IpAddr::V6(_) => IPV6_GROUP_PREFIX_LEN,
+                };
+                NetGroup::Network(
+                    IpNetwork::new(ip, prefix_len).unwrap_or_else(|_| IpNetwork::from(ip)),
+                )
+            }
+        }
+    }
+
+    /// Check whether an outbound session with a peer at `ip` can be opened, given the addresses
+    /// of the peers of the outbound sessions already opened or requested.
+    /// The cap does not apply to loopback addresses, used when running several nodes in one host
+    pub fn allows<I>(&self, ip: IpAddr, outbound: I) -> bool
+    where
+        I: IntoIterator<Item = IpAddr>,
+    {
+        let max_per_group = match self.max_per_group {
+            Some(max_per_group) if !ip.is_loopback() => max_per_group as usize,
+            _ => return true,
+        };
+
+        let group = self.group(ip);
+        let same_group = outbound
+            .into_iter()
+            .filter(|outbound_ip| self.group(*outbound_ip) == group)
+            .count();
+
+        same_group < max_per_group
+    }
+}
@@ -0,0 +1,108 @@
+//! Lifecycle events of the sessions, reported to the subscribers of the session events
+
+use std::net::SocketAddr;
+
+use crate::sessions::{capabilities::Capabilities, SessionType};
+
+/// Reason why a session was closed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The peer closed the connection, or the connection failed
+    ClosedByPeer,
+    /// The node closed the session: the peer was removed, the node is shutting down or a priority
+    /// peer took the slot of the session
+    ClosedByNode,
+    /// The handshake did not complete before the handshake timeout
+    HandshakeTimeout,
+    /// The peer belongs to another network or its protocol version is not supported
+    Incompatible,
+    /// The peer did not answer a ping in time
+    PingTimeout,
+    /// The peer did not answer a request for peers or headers in time
+    ResponseTimeout,
+    /// The peer sent a frame bigger than the maximum message size
+    OversizedMessage,
+    /// The peer was banned for misbehaving
+    Banned,
+    /// The handshake of a feeler session completed, so it is no longer needed
+    FeelerCompleted,
+    /// The session failed to communicate with the other actors of the node
+    Error,
+}
+
+impl DisconnectReason {
+    /// Name of the reason, as reported to the subscribers
+    pub fn name(self) -> &'static str {
+        match self {
+            DisconnectReason::ClosedByPeer => "closed_by_peer",
+            DisconnectReason::ClosedByNode => "closed_by_node",
+            DisconnectReason::HandshakeTimeout => "handshake_timeout",
+            DisconnectReason::Incompatible => "incompatible",
+            DisconnectReason::PingTimeout => "ping_timeout",
+            DisconnectReason::ResponseTimeout => "response_timeout",
+            DisconnectReason::OversizedMessage => "oversized_message",
+            DisconnectReason::Banned => "banned",
+            DisconnectReason::FeelerCompleted => "feeler_completed",
+            DisconnectReason::Error => "error",
+        }
+    }
+}
+
+/// Lifecycle event of a session
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionEvent {
+    /// A session was registered, before the handshake
+    Connected {
+        /// Socket address of the peer
+        address: SocketAddr,
+        /// Session type
+        session_type: SessionType,
+    },
+    /// The handshake of a session completed
+    Handshaked {
+        /// Socket address of the peer
+        address: SocketAddr,
+        /// Session type
+        session_type: SessionType,
+        /// Protocol version and features negotiated with the peer
+        capabilities: Capabilities,
+    },
+    /// A session was closed
+    Disconnected {
+        /// Socket address of the peer
+        address: SocketAddr,
+        /// Session type
+        session_type: SessionType,
+        /// Why the session was closed
+        reason: DisconnectReason,
+    },
+    /// A peer was banned, so its IP address cannot connect to the node until the ban expires
+    Banned {
+        /// Socket address of the peer
+        address: SocketAddr,
+        /// Timestamp at which the ban expires
+        until: i64,
+    },
+}
+
+impl SessionEvent {
+    /// Name of the event, as reported to the subscribers
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionEvent::Connected { .. } => "connected",
+            SessionEvent::Handshaked { .. } => "handshaked",
+            SessionEvent::Disconnected { .. } => "disconnected",
+            SessionEvent::Banned { .. } => "banned",
+        }
+    }
+
+    /// Socket address of the peer of the event
+    pub fn address(&self) -> SocketAddr {
+        match self {
+            SessionEvent::Connected { address, .. }
+            | SessionEvent::Handshaked { address, .. }
+            | SessionEvent::Disconnected { address, .. }
+            | SessionEvent::Banned { address, .. } => *address,
+        }
+    }
+}
@@ -6,21 +6,37 @@ pub mod error;
 /// Bounded sessions module
 pub mod bounded_sessions;
 
-use std::net::SocketAddr;
+/// Capabilities module
+pub mod capabilities;
+
+/// Outbound diversity module
+pub mod diversity;
+
+/// Session events module
+pub mod events;
+
+use std::cmp::Reverse;
+use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
 use rand::{thread_rng, Rng};
 
 use crate::sessions::bounded_sessions::BoundedSessions;
-use crate::sessions::error::SessionsResult;
+use crate::sessions::capabilities::Capabilities;
+use crate::sessions::diversity::OutboundDiversity;
+use crate::sessions::error::{SessionsError, SessionsErrorKind, SessionsResult};
+use witnet_util::{error::WitnetError, net::IpNetwork};
 
 /// Session type
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum SessionType {
     /// Inbound session
     Inbound,
     /// Outbound session
     Outbound,
+    /// Short-lived outbound session to a peer the node has only heard of, which is closed right
+    /// after the handshake. It only checks whether the peer is reachable
+    Feeler,
 }
 
 /// Session Status (used for bootstrapping)
@@ -32,17 +48,34 @@ pub enum SessionStatus {
     Consolidated,
 }
 
+/// Consolidated session with a peer
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectedPeer {
+    /// Socket address of the peer
+    pub address: SocketAddr,
+    /// Session type
+    pub session_type: SessionType,
+    /// Protocol version and features negotiated with the peer
+    pub capabilities: Capabilities,
+    /// Round-trip time of the last ping answered by the peer
+    pub latency: Option<Duration>,
+}
+
 /// Sessions struct contains:
 /// - server address used to listen to incoming connections
+/// - other addresses listening to incoming connections
 /// - list of inbound sessions parametrized with their reference (T)
 /// - list of consolidated outbound sessions parametrized with their reference(T)
 /// - list of unconsolidated outbound sessions parametrized with their reference(T)
+/// - list of feeler sessions parametrized with their reference(T)
 pub struct Sessions<T>
 where
     T: Clone,
 {
     /// Server address listening to incoming connections
     pub server_address: Option<SocketAddr>,
+    /// Every address listening to incoming connections, including the server address
+    pub listening_addresses: Vec<SocketAddr>,
     /// Inbound sessions: __untrusted__ peers that connect to the server
     pub inbound: BoundedSessions<T>,
     /// Outbound consolidated sessions: __known__ peer sessions that the node is connected to (in
@@ -51,8 +84,20 @@ where
     /// Outbound unconsolidated sessions: __known__ peer sessions that the node is connected to
     /// (in unconsolidated status)
     pub outbound_unconsolidated: BoundedSessions<T>,
+    /// Feeler sessions, consolidated or not: __heard of__ peer sessions that are closed after the
+    /// handshake (one at a time)
+    pub feeler: BoundedSessions<T>,
     /// Handshake timeout
     pub handshake_timeout: Duration,
+    /// Maximum number of inbound sessions from the same IP address
+    pub max_inbound_per_ip: Option<u16>,
+    /// Maximum number of inbound sessions from the same subnet (/24 for IPv4, /64 for IPv6)
+    pub max_inbound_per_subnet: Option<u16>,
+    /// Networks of the priority peers, which can take the slot of another inbound session when
+    /// all of them are taken
+    pub priority_networks: Vec<IpNetwork>,
+    /// Cap of outbound sessions with peers of the same network group
+    pub outbound_diversity: OutboundDiversity,
 }
 
 /// Default trait implementation
@@ -61,12 +106,21 @@ where
     T: Clone,
 {
     fn default() -> Self {
+        let mut feeler = BoundedSessions::default();
+        feeler.set_limit(1);
+
         Self {
             server_address: None,
+            listening_addresses: vec![],
             inbound: BoundedSessions::default(),
             outbound_consolidated: BoundedSessions::default(),
             outbound_unconsolidated: BoundedSessions::default(),
+            feeler,
             handshake_timeout: Duration::default(),
+            max_inbound_per_ip: None,
+            max_inbound_per_subnet: None,
+            priority_networks: vec![],
+            outbound_diversity: OutboundDiversity::default(),
         }
     }
 }
@@ -87,18 +141,43 @@ where
                 SessionStatus::Unconsolidated => &mut self.outbound_unconsolidated,
                 SessionStatus::Consolidated => &mut self.outbound_consolidated,
             },
+            SessionType::Feeler => &mut self.feeler,
         }
     }
     /// Method to set the server address
     pub fn set_server_address(&mut self, server_address: SocketAddr) {
         self.server_address = Some(server_address);
     }
+    /// Method to set every address listening to incoming connections
+    pub fn set_listening_addresses(&mut self, listening_addresses: Vec<SocketAddr>) {
+        self.listening_addresses = listening_addresses;
+    }
     /// Method to set the sessions limits
     pub fn set_limits(&mut self, inbound_limit: u16, outbound_consolidated_limit: u16) {
         self.inbound.set_limit(inbound_limit);
         self.outbound_consolidated
             .set_limit(outbound_consolidated_limit);
     }
+    /// Method to set the maximum number of inbound sessions from the same IP address and from the
+    /// same subnet
+    pub fn set_inbound_caps(&mut self, max_per_ip: u16, max_per_subnet: u16) {
+        self.max_inbound_per_ip = Some(max_per_ip);
+        self.max_inbound_per_subnet = Some(max_per_subnet);
+    }
+    /// Method to set the networks of the priority peers
+    pub fn set_priority_networks(&mut self, priority_networks: Vec<IpNetwork>) {
+        self.priority_networks = priority_networks;
+    }
+    /// Method to set the cap of outbound sessions with peers of the same network group
+    pub fn set_outbound_diversity(&mut self, outbound_diversity: OutboundDiversity) {
+        self.outbound_diversity = outbound_diversity;
+    }
+    /// Method to check if a socket address belongs to a priority peer
+    pub fn is_priority_address(&self, address: SocketAddr) -> bool {
+        self.priority_networks
+            .iter()
+            .any(|network| network.contains(address.ip()))
+    }
     /// Method to set the handshake timeout
     pub fn set_handshake_timeout(&mut self, handshake_timeout: Duration) {
         self.handshake_timeout = handshake_timeout;
@@ -115,23 +194,122 @@ where
             .collection
             .contains_key(&candidate_addr);
 
-        // Check if address is the server address
+        // Check if address is being tested by a feeler session
+        let is_feeler = self.feeler.collection.contains_key(&candidate_addr);
+
+        // Check if address is the server address or any other listening address
         let is_server = self
             .server_address
             .map(|address| address == candidate_addr)
-            .unwrap_or(false);
+            .unwrap_or(false)
+            || self.listening_addresses.contains(&candidate_addr);
 
-        // Return true if the address has not been used as outbound session or server address
-        !is_outbound_consolidated && !is_outbound_unconsolidated && !is_server
+        // Return true if the address has not been used as outbound or feeler session or server
+        // address
+        !is_outbound_consolidated && !is_outbound_unconsolidated && !is_feeler && !is_server
+    }
+    /// Method to check if a socket address keeps the outbound sessions diverse, that is, there are
+    /// less outbound sessions with peers of its network group than the cap. `pending` are the
+    /// addresses of the outbound connections requested which have no session yet
+    pub fn is_outbound_address_diverse<'a, I>(&self, candidate_addr: SocketAddr, pending: I) -> bool
+    where
+        I: IntoIterator<Item = &'a SocketAddr>,
+    {
+        let registered = |address: &SocketAddr| {
+            self.outbound_consolidated.collection.contains_key(address)
+                || self
+                    .outbound_unconsolidated
+                    .collection
+                    .contains_key(address)
+        };
+        let pending = pending
+            .into_iter()
+            .filter(|address| !registered(address))
+            .map(SocketAddr::ip);
+        let outbound = self
+            .outbound_consolidated
+            .collection
+            .keys()
+            .chain(self.outbound_unconsolidated.collection.keys())
+            .map(SocketAddr::ip)
+            .chain(pending);
+
+        self.outbound_diversity
+            .allows(candidate_addr.ip(), outbound)
+    }
+    /// Method to check if a socket address is eligible as inbound peer, that is, there are less
+    /// inbound sessions from its IP address and from its subnet than the caps.
+    /// The caps do not apply to loopback addresses, used when running several nodes in one host
+    pub fn is_inbound_address_eligible(&self, candidate_addr: SocketAddr) -> bool {
+        let ip = candidate_addr.ip();
+        if ip.is_loopback() {
+            return true;
+        }
+
+        let (same_ip, same_subnet) =
+            self.inbound
+                .collection
+                .keys()
+                .fold((0, 0), |(same_ip, same_subnet), address| {
+                    if address.ip() == ip {
+                        (same_ip + 1, same_subnet + 1)
+                    } else if is_same_subnet(&address.ip(), &ip) {
+                        (same_ip, same_subnet + 1)
+                    } else {
+                        (same_ip, same_subnet)
+                    }
+                });
+        let below = |count: usize, cap: Option<u16>| cap.map_or(true, |cap| count < cap as usize);
+
+        below(same_ip, self.max_inbound_per_ip) && below(same_subnet, self.max_inbound_per_subnet)
     }
     /// Method to get total number of outbound peers
     pub fn get_num_outbound_sessions(&self) -> usize {
         self.outbound_consolidated.collection.len() + self.outbound_unconsolidated.collection.len()
     }
+    /// Method to check if a new feeler session can be started, as only one runs at a time
+    pub fn is_feeler_needed(&self) -> bool {
+        self.feeler
+            .limit
+            .map(|limit| self.feeler.collection.len() < limit as usize)
+            .unwrap_or(true)
+    }
     /// Method to get number of inbound peers
     pub fn get_num_inbound_sessions(&self) -> usize {
         self.inbound.collection.len()
     }
+    /// Method to check if the inbound limit is reached
+    pub fn is_inbound_full(&self) -> bool {
+        self.inbound.limit.map_or(false, |limit| {
+            self.inbound.collection.len() >= limit as usize
+        })
+    }
+    /// Method to remove the inbound session of lowest value with a peer which is not a priority
+    /// peer, to make room for a priority peer. The sessions which have not completed the handshake
+    /// go first, then the ones with the highest latency, unknown latencies being the highest
+    /// Returns the address and the reference of the removed session, if any
+    pub fn evict_inbound_session(&mut self) -> Option<(SocketAddr, T)> {
+        let address = self
+            .inbound
+            .collection
+            .iter()
+            .filter(|(address, _)| !self.is_priority_address(**address))
+            .min_by_key(|(_, info)| {
+                (
+                    info.capabilities.is_some(),
+                    Reverse(
+                        info.latency
+                            .unwrap_or_else(|| Duration::from_secs(u64::max_value())),
+                    ),
+                )
+            })
+            .map(|(address, _)| *address)?;
+
+        self.inbound
+            .unregister_session(address)
+            .ok()
+            .map(|info| (address, info.reference))
+    }
     /// Method to check if outbound bootstrap is needed
     pub fn is_outbound_bootstrap_needed(&self) -> bool {
         let num_outbound_sessions = self.get_num_outbound_sessions();
@@ -141,6 +319,16 @@ where
             .map(|limit| num_outbound_sessions < limit as usize)
             .unwrap_or(true)
     }
+    /// Method to get the number of outbound sessions needed to reach the outbound limit
+    /// When there is no limit, one more session is always needed
+    pub fn get_num_missing_outbound_sessions(&self) -> usize {
+        let num_outbound_sessions = self.get_num_outbound_sessions();
+
+        self.outbound_consolidated
+            .limit
+            .map(|limit| (limit as usize).saturating_sub(num_outbound_sessions))
+            .unwrap_or(1)
+    }
     /// Method to get a random consolidated outbound session
     pub fn get_random_anycast_session(&self) -> Option<T> {
         // Get iterator over the values of the hashmap
@@ -168,6 +356,31 @@ where
             .values()
             .map(|info| &info.reference)
     }
+    /// Method to get all the sessions, whatever their type and status
+    pub fn get_all_sessions<'a>(&'a self) -> impl Iterator<Item = &T> + 'a {
+        self.inbound
+            .collection
+            .values()
+            .chain(self.outbound_consolidated.collection.values())
+            .chain(self.outbound_unconsolidated.collection.values())
+            .chain(self.feeler.collection.values())
+            .map(|info| &info.reference)
+    }
+    /// Method to get the sessions whose peer address satisfies `filter`, whatever their type and
+    /// status
+    pub fn get_sessions_by_address<'a, F>(&'a self, filter: F) -> impl Iterator<Item = &T> + 'a
+    where
+        F: Fn(&SocketAddr) -> bool + 'a,
+    {
+        self.inbound
+            .collection
+            .iter()
+            .chain(self.outbound_consolidated.collection.iter())
+            .chain(self.outbound_unconsolidated.collection.iter())
+            .chain(self.feeler.collection.iter())
+            .filter(move |&(address, _)| filter(address))
+            .map(|(_, info)| &info.reference)
+    }
     /// Method to insert a new session
     pub fn register_session(
         &mut self,
@@ -175,6 +388,17 @@ where
         address: SocketAddr,
         reference: T,
     ) -> SessionsResult<()> {
+        // Check the caps of inbound sessions from the same IP address or subnet
+        if let SessionType::Inbound = session_type {
+            if !self.is_inbound_address_eligible(address) {
+                return Err(WitnetError::from(SessionsError::new(
+                    SessionsErrorKind::Register,
+                    address.to_string(),
+                    "Max number of peers from the same IP address or subnet reached".to_string(),
+                )));
+            }
+        }
+
         // Get map to insert session to
         let sessions = self.get_sessions(session_type, SessionStatus::Unconsolidated);
 
@@ -194,11 +418,73 @@ where
         // Remove session and return result
         sessions.unregister_session(address).map(|_| ())
     }
-    /// Method to consolidate a session
+    /// Method to get the capabilities negotiated with the peer of a consolidated session
+    pub fn get_capabilities(&self, address: SocketAddr) -> Option<Capabilities> {
+        [&self.inbound, &self.outbound_consolidated, &self.feeler]
+            .iter()
+            .filter_map(|sessions| sessions.collection.get(&address))
+            .filter_map(|info| info.capabilities)
+            .next()
+    }
+    /// Method to get all the consolidated sessions
+    pub fn get_connected_peers(&self) -> Vec<ConnectedPeer> {
+        let sessions = [
+            (SessionType::Inbound, &self.inbound),
+            (SessionType::Outbound, &self.outbound_consolidated),
+            (SessionType::Feeler, &self.feeler),
+        ];
+
+        sessions
+            .iter()
+            .flat_map(|(session_type, sessions)| {
+                sessions
+                    .collection
+                    .iter()
+                    .filter_map(move |(address, info)| {
+                        info.capabilities.map(|capabilities| ConnectedPeer {
+                            address: *address,
+                            session_type: *session_type,
+                            capabilities,
+                            latency: info.latency,
+                        })
+                    })
+            })
+            .collect()
+    }
+    /// Method to record the round-trip time of a ping answered by the peer of a consolidated
+    /// session
+    pub fn set_latency(&mut self, address: SocketAddr, latency: Duration) -> SessionsResult<()> {
+        let Sessions {
+            inbound,
+            outbound_consolidated,
+            feeler,
+            ..
+        } = self;
+        let info = inbound
+            .collection
+            .get_mut(&address)
+            .or_else(|| outbound_consolidated.collection.get_mut(&address))
+            .or_else(|| feeler.collection.get_mut(&address))
+            .filter(|info| info.capabilities.is_some());
+
+        match info {
+            Some(info) => {
+                info.latency = Some(latency);
+                Ok(())
+            }
+            None => Err(WitnetError::from(SessionsError::new(
+                SessionsErrorKind::Update,
+                address.to_string(),
+                "Address not found in consolidated sessions".to_string(),
+            ))),
+        }
+    }
+    /// Method to consolidate a session, storing the capabilities negotiated with the peer
     pub fn consolidate_session(
         &mut self,
         session_type: SessionType,
         address: SocketAddr,
+        capabilities: Capabilities,
     ) -> SessionsResult<()> {
         // Get map to remove session from
         let uncons_sessions = self.get_sessions(session_type, SessionStatus::Unconsolidated);
@@ -210,6 +496,22 @@ where
         let cons_sessions = self.get_sessions(session_type, SessionStatus::Consolidated);
 
         // Register session into consolidated collection
-        cons_sessions.register_session(address, session_info.reference)
+        cons_sessions.register_session(address, session_info.reference)?;
+
+        // Store the negotiated capabilities
+        if let Some(info) = cons_sessions.collection.get_mut(&address) {
+            info.capabilities = Some(capabilities);
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether two IP addresses are in the same subnet: /24 for IPv4 and /64 for IPv6
+fn is_same_subnet(a: &IpAddr, b: &IpAddr) -> bool {
+    match (a, b) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => a.octets()[..3] == b.octets()[..3],
+        (IpAddr::V6(a), IpAddr::V6(b)) => a.segments()[..4] == b.segments()[..4],
+        _ => false,
     }
 }
@@ -0,0 +1,42 @@
+//! Error type definitions for the SOCKS5 module.
+
+use failure::Fail;
+use std::fmt;
+use witnet_util::error::WitnetResult;
+
+/// SOCKS5 Error
+#[derive(Debug, Fail)]
+#[fail(display = "{} : at \"{}\", msg {}", kind, info, msg)]
+pub struct SocksError {
+    /// Error kind
+    kind: SocksErrorKind,
+    /// Error parameter
+    info: String,
+    /// Error message
+    msg: String,
+}
+
+impl SocksError {
+    /// Create a SOCKS5 error based on operation kind and related info.
+    pub fn new(kind: SocksErrorKind, info: String, msg: String) -> Self {
+        Self { kind, info, msg }
+    }
+}
+
+/// SOCKS5 Errors under different operations
+#[derive(Debug)]
+pub enum SocksErrorKind {
+    /// Errors reported by the proxy
+    Refused,
+    /// Errors when decoding the responses of the proxy
+    Malformed,
+}
+
+impl fmt::Display for SocksErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SocksError::{:?}", self)
+    }
+}
+
+/// Result type for the SOCKS5 module.
+pub type SocksResult<T> = WitnetResult<T, SocksError>;
@@ -0,0 +1,118 @@
+//! Library for connecting to peers through a SOCKS5 proxy (RFC 1928), such as the one of a Tor
+//! client
+//!
+//! Only the messages are built and decoded here, so they can be sent through any kind of
+//! connection. The proxy must not require authentication.
+
+use std::net::{IpAddr, SocketAddr};
+
+use witnet_util::error::WitnetError;
+
+use crate::socks::error::{SocksError, SocksErrorKind, SocksResult};
+
+pub mod error;
+
+/// Version of the SOCKS protocol
+const VERSION: u8 = 5;
+
+/// Authentication method which does not require authentication
+const NO_AUTHENTICATION: u8 = 0;
+
+/// Command to open a TCP connection
+const CONNECT: u8 = 1;
+
+/// Types of the addresses in the requests and replies
+const IPV4: u8 = 1;
+const DOMAIN_NAME: u8 = 3;
+const IPV6: u8 = 4;
+
+/// Length of the method selection message of the proxy
+pub const METHOD_SELECTION_LEN: usize = 2;
+
+/// Length of the beginning of a reply, which tells the length of the rest of the reply: the
+/// version, the reply code, a reserved byte, the address type and the first byte of the address
+pub const REPLY_HEADER_LEN: usize = 5;
+
+/// Greeting sent to the proxy, offering to use no authentication
+pub fn greeting() -> Vec<u8> {
+    vec![VERSION, 1, NO_AUTHENTICATION]
+}
+
+/// Check that the proxy accepted to use no authentication
+pub fn check_method_selection(proxy: SocketAddr, selection: &[u8]) -> SocksResult<()> {
+    match selection {
+        [VERSION, NO_AUTHENTICATION] => Ok(()),
+        [VERSION, _] => Err(WitnetError::from(SocksError::new(
+            SocksErrorKind::Refused,
+            proxy.to_string(),
+            "The proxy requires authentication".to_string(),
+        ))),
+        _ => Err(malformed(proxy, "Unexpected method selection")),
+    }
+}
+
+/// Request to the proxy to open a TCP connection to `address`
+pub fn connect_request(address: SocketAddr) -> Vec<u8> {
+    let mut request = vec![VERSION, CONNECT, 0];
+    match address.ip() {
+        IpAddr::V4(ip) => {
+            request.push(IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            request.push(IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+    }
+    request.push((address.port() >> 8) as u8);
+    request.push(address.port() as u8);
+
+    request
+}
+
+/// Check that the proxy opened the connection, from the beginning of its reply
+/// Returns the number of bytes of the rest of the reply, which must be read before using the
+/// connection
+pub fn check_reply_header(proxy: SocketAddr, header: &[u8]) -> SocksResult<usize> {
+    if header.len() < REPLY_HEADER_LEN || header[0] != VERSION {
+        return Err(malformed(proxy, "Unexpected reply"));
+    }
+    if header[1] != 0 {
+        return Err(WitnetError::from(SocksError::new(
+            SocksErrorKind::Refused,
+            proxy.to_string(),
+            reply_message(header[1]).to_string(),
+        )));
+    }
+
+    // The rest of the bound address, and the bound port
+    match header[3] {
+        IPV4 => Ok(4 - 1 + 2),
+        IPV6 => Ok(16 - 1 + 2),
+        DOMAIN_NAME => Ok(usize::from(header[4]) + 2),
+        _ => Err(malformed(proxy, "Unknown address type")),
+    }
+}
+
+/// Description of the reply codes of the proxy
+fn reply_message(code: u8) -> &'static str {
+    match code {
+        1 => "General SOCKS server failure",
+        2 => "Connection not allowed by ruleset",
+        3 => "Network unreachable",
+        4 => "Host unreachable",
+        5 => "Connection refused",
+        6 => "TTL expired",
+        7 => "Command not supported",
+        8 => "Address type not supported",
+        _ => "Unknown error",
+    }
+}
+
+fn malformed(proxy: SocketAddr, msg: &str) -> WitnetError<SocksError> {
+    WitnetError::from(SocksError::new(
+        SocksErrorKind::Malformed,
+        proxy.to_string(),
+        msg.to_string(),
+    ))
+}
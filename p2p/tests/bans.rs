@@ -0,0 +1,161 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use witnet_p2p::bans::*;
+use witnet_util::net::IpNetwork;
+
+#[test]
+fn p2p_bans_report() {
+    // Create bans struct
+    let mut bans = Bans::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let duration = Duration::from_secs(100);
+
+    // The score grows with every misbehavior until it reaches the threshold
+    assert!(!bans.report(address, Misbehavior::ProtocolViolation, 25, duration, 0));
+    assert_eq!(bans.get_score(&address), 10);
+    assert!(!bans.report(address, Misbehavior::Spam, 25, duration, 0));
+    assert_eq!(bans.get_score(&address), 15);
+    assert!(!bans.is_banned(&address, 0));
+
+    // Reaching the threshold bans the address and resets its score
+    assert!(bans.report(address, Misbehavior::InvalidMessage, 25, duration, 0));
+    assert_eq!(bans.get_score(&address), 0);
+    assert_eq!(bans.get_all(), vec![(address.ip(), 100)]);
+}
+
+#[test]
+fn p2p_bans_any_port() {
+    // Create bans struct
+    let mut bans = Bans::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let other_port = SocketAddr::new(address.ip(), 50000);
+    let other_ip = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+
+    assert!(bans.report(address, Misbehavior::Spam, 5, Duration::from_secs(100), 0));
+
+    // The peer cannot reconnect from another port
+    assert!(bans.is_banned(&address, 10));
+    assert!(bans.is_banned(&other_port, 10));
+    assert!(!bans.is_banned(&other_ip, 10));
+}
+
+#[test]
+fn p2p_bans_disabled() {
+    // Create bans struct
+    let mut bans = Bans::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+
+    // A threshold of zero never bans
+    for _ in 0..100 {
+        assert!(!bans.report(
+            address,
+            Misbehavior::InvalidMessage,
+            0,
+            Duration::from_secs(100),
+            0
+        ));
+    }
+    assert!(!bans.is_banned(&address, 0));
+    assert_eq!(bans.get_score(&address), 0);
+}
+
+#[test]
+fn p2p_bans_expire() {
+    // Create bans struct
+    let mut bans = Bans::default();
+
+    let first = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+    let second = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+    bans.ban(first, 100);
+    bans.ban(second, 200);
+
+    // A ban is not shortened by a shorter one
+    bans.ban(second, 150);
+
+    assert!(bans.is_banned(&SocketAddr::new(first, 8080), 99));
+    assert!(!bans.is_banned(&SocketAddr::new(first, 8080), 100));
+
+    // Expired bans are removed
+    assert_eq!(bans.remove_expired(150), vec![first]);
+    assert_eq!(bans.get_all(), vec![(second, 200)]);
+    assert_eq!(bans.remove_expired(200), vec![second]);
+    assert!(bans.get_all().is_empty());
+}
+
+#[test]
+fn p2p_bans_blacklist() {
+    // Create bans struct
+    let mut bans = Bans::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 8080);
+    let other_network = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 1, 1)), 8080);
+    let network: IpNetwork = "10.0.0.0/24".parse().unwrap();
+
+    // Every address is allowed by default
+    assert!(bans.is_allowed(&address, 0));
+
+    // Blacklisting a network twice only adds it once
+    assert_eq!(bans.blacklist(vec![network]), vec![network]);
+    assert!(bans.blacklist(vec![network]).is_empty());
+    assert_eq!(bans.get_blacklist(), vec![network]);
+    assert!(!bans.is_allowed(&address, 0));
+    assert!(bans.is_allowed(&other_network, 0));
+
+    // The network is allowed again when removed from the blacklist
+    assert_eq!(bans.unblacklist(vec![network]), vec![network]);
+    assert!(bans.unblacklist(vec![network]).is_empty());
+    assert!(bans.is_allowed(&address, 0));
+}
+
+#[test]
+fn p2p_bans_whitelist() {
+    // Create bans struct
+    let mut bans = Bans::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10)), 8080);
+    let other_network = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 8080);
+    bans.set_whitelist(vec!["192.168.0.0/16".parse().unwrap()]);
+
+    // Only the whitelisted networks are allowed
+    assert!(bans.is_allowed(&address, 0));
+    assert!(!bans.is_allowed(&other_network, 0));
+
+    // Whitelisted peers can still be blacklisted or banned
+    bans.blacklist(vec!["192.168.1.10".parse().unwrap()]);
+    assert!(!bans.is_allowed(&address, 0));
+    bans.unblacklist(vec!["192.168.1.10".parse().unwrap()]);
+    bans.ban(address.ip(), 100);
+    assert!(!bans.is_allowed(&address, 0));
+    assert!(bans.is_allowed(&address, 100));
+
+    // An empty whitelist allows every network
+    bans.set_whitelist(vec![]);
+    assert!(bans.is_allowed(&other_network, 0));
+}
+
+#[test]
+fn p2p_bans_ip_network() {
+    let network: IpNetwork = "10.1.2.3/8".parse().unwrap();
+
+    // The bits outside the prefix are ignored
+    assert_eq!(network.to_string(), "10.0.0.0/8");
+    assert!(network.contains("10.255.0.1".parse().unwrap()));
+    assert!(!network.contains("11.0.0.1".parse().unwrap()));
+    assert!(!network.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+    // A single address is a network with the full prefix
+    let single: IpNetwork = "2001:db8::1".parse().unwrap();
+    assert_eq!(single.prefix_len(), 128);
+    assert_eq!(single.to_string(), "2001:db8::1");
+    assert!(single.contains("2001:db8::1".parse().unwrap()));
+    assert!(!single.contains("2001:db8::2".parse().unwrap()));
+
+    // Invalid networks
+    assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+    assert!("10.0.0.0/".parse::<IpNetwork>().is_err());
+    assert!("seed.witnet.io".parse::<IpNetwork>().is_err());
+}
@@ -0,0 +1,52 @@
+use witnet_p2p::compression::*;
+
+const MAX_SIZE: usize = 8 * 1024 * 1024;
+
+#[test]
+fn p2p_compression_small_messages_are_raw() {
+    let message = vec![7; MIN_COMPRESSED_SIZE - 1];
+    let frame = compress(&message);
+
+    assert_eq!(frame[0], 0);
+    assert_eq!(&frame[1..], &message[..]);
+    assert_eq!(decompress(&frame, MAX_SIZE).unwrap(), message);
+}
+
+#[test]
+fn p2p_compression_big_messages_are_compressed() {
+    let message = vec![7; 64 * 1024];
+    let frame = compress(&message);
+
+    assert_eq!(frame[0], 1);
+    assert!(frame.len() < message.len());
+    assert_eq!(decompress(&frame, MAX_SIZE).unwrap(), message);
+}
+
+#[test]
+fn p2p_compression_invalid_frames() {
+    // Empty frame
+    assert!(decompress(&[], MAX_SIZE).is_err());
+    // Unknown prefix
+    assert!(decompress(&[2, 1, 2, 3], MAX_SIZE).is_err());
+    // Invalid Snappy data
+    assert!(decompress(&[1, 0xff, 0xff, 0xff, 0xff, 0xff], MAX_SIZE).is_err());
+    // Snappy data decompressing into more than the maximum size: the length of the decompressed
+    // data is a varint at the beginning of the compressed data
+    let mut frame = vec![1];
+    let mut len = MAX_SIZE + 1;
+    while len >= 0x80 {
+        frame.push((len as u8) | 0x80);
+        len >>= 7;
+    }
+    frame.push(len as u8);
+    assert!(decompress(&frame, MAX_SIZE).is_err());
+}
+
+#[test]
+fn p2p_compression_max_size() {
+    let message = vec![7; 64 * 1024];
+    let frame = compress(&message);
+
+    assert_eq!(decompress(&frame, message.len()).unwrap(), message);
+    assert!(decompress(&frame, message.len() - 1).is_err());
+}
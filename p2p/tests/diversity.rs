@@ -0,0 +1,68 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use witnet_p2p::sessions::diversity::*;
+
+fn ip(a: u8, b: u8, c: u8, d: u8) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+}
+
+/// Check the parsing of an AS map and the lookup of the most specific network
+#[test]
+fn p2p_diversity_asmap() {
+    let asmap = "# network asn\n\
+                 10.0.0.0/8 64496\n\
+                 \n\
+                 10.1.0.0/16 AS64497\n\
+                 2001:db8::/32 64498\n"
+        .parse::<AsMap>()
+        .unwrap();
+
+    assert_eq!(asmap.len(), 3);
+    assert_eq!(asmap.lookup(ip(10, 2, 0, 1)), Some(64496));
+    assert_eq!(asmap.lookup(ip(10, 1, 0, 1)), Some(64497));
+    assert_eq!(
+        asmap.lookup(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+        Some(64498)
+    );
+    assert_eq!(asmap.lookup(ip(192, 168, 0, 1)), None);
+}
+
+/// Check that malformed lines of an AS map are reported
+#[test]
+fn p2p_diversity_asmap_malformed() {
+    assert!("10.0.0.0/8".parse::<AsMap>().is_err());
+    assert!("10.0.0.0/8 64496 64497".parse::<AsMap>().is_err());
+    assert!("10.0.0.0/33 64496".parse::<AsMap>().is_err());
+    assert!("10.0.0.0/8 ASN".parse::<AsMap>().is_err());
+}
+
+/// Check that the peers are grouped by autonomous system, or by network when not in the AS map
+#[test]
+fn p2p_diversity_group() {
+    let diversity = OutboundDiversity {
+        max_per_group: Some(1),
+        asmap: "10.0.0.0/8 64496".parse().unwrap(),
+    };
+
+    assert_eq!(diversity.group(ip(10, 1, 0, 1)), NetGroup::Asn(64496));
+    assert_eq!(
+        diversity.group(ip(192, 168, 5, 1)),
+        NetGroup::Network("192.168.0.0/16".parse().unwrap())
+    );
+    assert_eq!(
+        diversity.group(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 1, 2, 3, 4, 5, 6))),
+        NetGroup::Network("2001:db8::/32".parse().unwrap())
+    );
+
+    // Addresses of different networks announced by the same autonomous system share the cap
+    assert!(!diversity.allows(ip(10, 1, 0, 1), vec![ip(10, 200, 0, 1)]));
+    assert!(diversity.allows(ip(192, 168, 5, 1), vec![ip(10, 200, 0, 1)]));
+}
+
+/// Check that there is no cap without a maximum
+#[test]
+fn p2p_diversity_disabled() {
+    let diversity = OutboundDiversity::default();
+
+    assert!(diversity.allows(ip(10, 1, 0, 1), vec![ip(10, 1, 0, 2), ip(10, 1, 0, 3)]));
+}
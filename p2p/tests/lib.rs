@@ -1,8 +1,35 @@
+/// Bans library tests
+pub mod bans;
+
+/// Compression library tests
+pub mod compression;
+
+/// Outbound diversity library tests
+pub mod diversity;
+
+/// Message limits library tests
+pub mod limits;
+
+/// mDNS library tests
+pub mod mdns;
+
+/// NAT library tests
+pub mod nat;
+
 /// Peers library tests
 pub mod peers;
 
+/// Rate limits library tests
+pub mod rate_limits;
+
+/// Relay policy library tests
+pub mod relay;
+
 /// Bounded Sessions library tests
 pub mod bounded_sessions;
 
 /// Sesssions library tests
 pub mod sessions;
+
+/// SOCKS5 library tests
+pub mod socks;
@@ -0,0 +1,42 @@
+use witnet_p2p::limits::*;
+
+fn limits() -> MessageLimits {
+    MessageLimits {
+        max_message_size: 1000,
+        max_block_size: 800,
+        max_headers_size: 2000,
+        max_inventory_size: 100,
+        max_peers_size: 50,
+    }
+}
+
+#[test]
+fn p2p_limits_max_size_of_each_kind() {
+    let limits = limits();
+
+    assert_eq!(limits.max_size(None), 1000);
+    assert_eq!(limits.max_size(Some(SizedMessage::Block)), 800);
+    assert_eq!(limits.max_size(Some(SizedMessage::Inventory)), 100);
+    assert_eq!(limits.max_size(Some(SizedMessage::Peers)), 50);
+    // No kind of message can be bigger than the maximum message size
+    assert_eq!(limits.max_size(Some(SizedMessage::Headers)), 1000);
+}
+
+#[test]
+fn p2p_limits_allows() {
+    let limits = limits();
+
+    assert!(limits.allows(None, 1000));
+    assert!(!limits.allows(None, 1001));
+    assert!(limits.allows(Some(SizedMessage::Peers), 50));
+    assert!(!limits.allows(Some(SizedMessage::Peers), 51));
+    assert!(!limits.allows(Some(SizedMessage::Headers), 1001));
+}
+
+#[test]
+fn p2p_limits_default_allows_everything() {
+    let limits = MessageLimits::default();
+
+    assert!(limits.allows(None, usize::max_value()));
+    assert!(limits.allows(Some(SizedMessage::Block), usize::max_value()));
+}
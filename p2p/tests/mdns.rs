@@ -0,0 +1,92 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use witnet_p2p::mdns::*;
+
+fn source() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20))
+}
+
+#[test]
+fn p2p_mdns_query() {
+    let query = query();
+    assert!(is_query(&query, source()).unwrap());
+    // A query is not a response
+    assert_eq!(parse_response(&query, source()).unwrap(), vec![]);
+
+    // Queries for other services are ignored
+    let mut other = query.clone();
+    other[13] = b'X';
+    assert!(!is_query(&other, source()).unwrap());
+}
+
+#[test]
+fn p2p_mdns_response() {
+    let address: SocketAddr = "192.168.1.10:21337".parse().unwrap();
+    let packet = response("witnet-1234", address);
+    assert!(!is_query(&packet, source()).unwrap());
+    assert_eq!(
+        parse_response(&packet, source()).unwrap(),
+        vec![Service {
+            instance: "witnet-1234".to_string(),
+            address,
+        }]
+    );
+
+    let address: SocketAddr = "[fe80::1]:21337".parse().unwrap();
+    let packet = response("witnet-1234", address);
+    assert_eq!(
+        parse_response(&packet, source()).unwrap()[0].address,
+        address
+    );
+}
+
+#[test]
+fn p2p_mdns_response_unspecified_address() {
+    // A node listening on all the interfaces is reached at the address it sent the response from
+    let address: SocketAddr = "0.0.0.0:21337".parse().unwrap();
+    let response = response("witnet-1234", address);
+    assert_eq!(
+        parse_response(&response, source()).unwrap()[0].address,
+        SocketAddr::new(source(), 21337)
+    );
+}
+
+#[test]
+fn p2p_mdns_compressed_names() {
+    // Response with the PTR, SRV and A records of an instance, whose names are compressed
+    let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 3, 0, 0, 0, 0];
+    // PTR record, the service name is at offset 12
+    packet.extend_from_slice(b"\x07_witnet\x04_tcp\x05local\x00");
+    packet.extend_from_slice(&[0, 12, 0, 1, 0, 0, 0, 120, 0, 7]);
+    // The instance name is at offset 42
+    packet.extend_from_slice(b"\x04node\xc0\x0c");
+    // SRV record of the instance, pointing to the host name at offset 67
+    packet.extend_from_slice(&[0xc0, 42, 0, 33, 0, 1, 0, 0, 0, 120, 0, 13]);
+    packet.extend_from_slice(&[0, 0, 0, 0, 0x53, 0x59]);
+    packet.extend_from_slice(b"\x04host\xc0\x19");
+    // A record of the host
+    packet.extend_from_slice(&[0xc0, 67, 0, 1, 0, 1, 0, 0, 0, 120, 0, 4, 10, 0, 0, 7]);
+
+    assert_eq!(
+        parse_response(&packet, source()).unwrap(),
+        vec![Service {
+            instance: "node".to_string(),
+            address: "10.0.0.7:21337".parse().unwrap(),
+        }]
+    );
+}
+
+#[test]
+fn p2p_mdns_malformed() {
+    assert!(is_query(&[0, 0], source()).is_err());
+
+    // Truncated response
+    let address: SocketAddr = "192.168.1.10:21337".parse().unwrap();
+    let response = response("witnet-1234", address);
+    assert!(parse_response(&response[..response.len() - 10], source()).is_err());
+
+    // Compression pointers pointing to themselves
+    let mut packet = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    packet.extend_from_slice(&[0xc0, 12, 0, 12, 0, 1]);
+    assert!(is_query(&packet, source()).is_err());
+}
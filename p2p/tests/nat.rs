@@ -0,0 +1,206 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+use witnet_p2p::nat::upnp::*;
+use witnet_p2p::nat::*;
+
+/// Start a gateway answering a single request with `response`, returning the received request
+fn fake_gateway(response: Vec<u8>) -> (NatPmp, thread::JoinHandle<Vec<u8>>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = NatPmp::new(socket.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut buf = [0; 16];
+        let (len, from) = socket.recv_from(&mut buf).unwrap();
+        socket.send_to(&response, from).unwrap();
+
+        buf[..len].to_vec()
+    });
+
+    (client, handle)
+}
+
+#[test]
+fn p2p_nat_external_address() {
+    let (client, gateway) = fake_gateway(vec![0, 128, 0, 0, 0, 0, 0, 1, 203, 0, 113, 7]);
+
+    assert_eq!(
+        client.external_address().unwrap(),
+        Ipv4Addr::new(203, 0, 113, 7)
+    );
+    assert_eq!(gateway.join().unwrap(), vec![0, 0]);
+}
+
+#[test]
+fn p2p_nat_map_tcp_port() {
+    let (client, gateway) = fake_gateway(vec![
+        0, 130, 0, 0, 0, 0, 0, 1, 0x5b, 0xa0, 0x5b, 0xa1, 0, 0, 0x0e, 0x10,
+    ]);
+
+    assert_eq!(
+        client
+            .map_tcp_port(23456, Duration::from_secs(7200))
+            .unwrap(),
+        Mapping {
+            external_port: 23457,
+            lifetime: Duration::from_secs(3600),
+        }
+    );
+    assert_eq!(
+        gateway.join().unwrap(),
+        vec![0, 2, 0, 0, 0x5b, 0xa0, 0x5b, 0xa0, 0, 0, 0x1c, 0x20]
+    );
+}
+
+#[test]
+fn p2p_nat_refused() {
+    // Result code 2: not authorized
+    let (client, gateway) = fake_gateway(vec![0, 128, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0]);
+
+    assert!(client.external_address().is_err());
+    gateway.join().unwrap();
+}
+
+#[test]
+fn p2p_nat_default_gateway() {
+    let routes =
+        "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+                  eth0\t0001A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n\
+                  eth0\t00000000\t0101A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n";
+
+    let expected = if cfg!(target_endian = "little") {
+        Some(Ipv4Addr::new(192, 168, 1, 1))
+    } else {
+        Some(Ipv4Addr::new(1, 1, 168, 192))
+    };
+    assert_eq!(parse_default_gateway(routes), expected);
+    assert_eq!(parse_default_gateway("Iface\tDestination\tGateway\n"), None);
+}
+
+/// Start a UPnP gateway answering each of the requests with the HTTP `responses`, returning the
+/// received requests
+fn fake_igd(responses: Vec<&'static str>) -> (Igd, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = Igd::new(
+        listener.local_addr().unwrap(),
+        "/ctl/IPConn",
+        WAN_SERVICES[0],
+    );
+    let handle = thread::spawn(move || {
+        responses
+            .into_iter()
+            .map(|response| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = vec![];
+                let mut buf = [0; 1024];
+                // The client waits for the response after sending the whole envelope
+                while !String::from_utf8_lossy(&request).contains("</s:Envelope>") {
+                    let len = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..len]);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+
+                String::from_utf8(request).unwrap()
+            })
+            .collect()
+    });
+
+    (client, handle)
+}
+
+#[test]
+fn p2p_upnp_external_address() {
+    let (client, gateway) = fake_igd(vec![
+        "HTTP/1.0 200 OK\r\nContent-Type: text/xml\r\n\r\n\
+         <s:Envelope><s:Body><u:GetExternalIPAddressResponse>\
+         <NewExternalIPAddress>203.0.113.7</NewExternalIPAddress>\
+         </u:GetExternalIPAddressResponse></s:Body></s:Envelope>",
+    ]);
+
+    assert_eq!(
+        client.external_address().unwrap(),
+        Ipv4Addr::new(203, 0, 113, 7)
+    );
+    let requests = gateway.join().unwrap();
+    assert!(requests[0].starts_with("POST /ctl/IPConn HTTP/1.0\r\n"));
+    assert!(requests[0].contains(
+        "SOAPAction: \"urn:schemas-upnp-org:service:WANIPConnection:1#GetExternalIPAddress\""
+    ));
+}
+
+#[test]
+fn p2p_upnp_map_tcp_port_only_permanent_leases() {
+    let (client, gateway) = fake_igd(vec![
+        "HTTP/1.0 500 Internal Server Error\r\n\r\n\
+         <s:Envelope><s:Body><s:Fault><detail><UPnPError>\
+         <errorCode>725</errorCode><errorDescription>OnlyPermanentLeasesSupported</errorDescription>\
+         </UPnPError></detail></s:Fault></s:Body></s:Envelope>",
+        "HTTP/1.0 200 OK\r\n\r\n<s:Envelope><s:Body><u:AddPortMappingResponse/></s:Body></s:Envelope>",
+    ]);
+
+    assert_eq!(
+        client
+            .map_tcp_port(23456, Duration::from_secs(7200))
+            .unwrap(),
+        Mapping {
+            external_port: 23456,
+            lifetime: Duration::from_secs(7200),
+        }
+    );
+    let requests = gateway.join().unwrap();
+    assert!(requests[0].contains("<NewInternalPort>23456</NewInternalPort>"));
+    assert!(requests[0].contains("<NewInternalClient>127.0.0.1</NewInternalClient>"));
+    assert!(requests[0].contains("<NewLeaseDuration>7200</NewLeaseDuration>"));
+    assert!(requests[1].contains("<NewLeaseDuration>0</NewLeaseDuration>"));
+}
+
+#[test]
+fn p2p_upnp_refused() {
+    let (client, gateway) = fake_igd(vec![
+        "HTTP/1.0 500 Internal Server Error\r\n\r\n\
+         <s:Envelope><s:Body><s:Fault><detail><UPnPError>\
+         <errorCode>718</errorCode></UPnPError></detail></s:Fault></s:Body></s:Envelope>",
+    ]);
+
+    assert!(client
+        .map_tcp_port(23456, Duration::from_secs(7200))
+        .is_err());
+    gateway.join().unwrap();
+}
+
+#[test]
+fn p2p_upnp_parse_discovery() {
+    let response = "HTTP/1.1 200 OK\r\n\
+                    CACHE-CONTROL: max-age=120\r\n\
+                    Location: http://192.168.1.1:5000/rootDesc.xml\r\n\
+                    ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n";
+    let location = parse_ssdp_location(response).unwrap();
+
+    assert_eq!(location, "http://192.168.1.1:5000/rootDesc.xml");
+    assert_eq!(
+        parse_url(&location),
+        Some((
+            "192.168.1.1:5000".parse().unwrap(),
+            "/rootDesc.xml".to_string()
+        ))
+    );
+    assert_eq!(
+        parse_url("http://192.168.1.1"),
+        Some(("192.168.1.1:80".parse().unwrap(), "/".to_string()))
+    );
+    assert_eq!(parse_url("https://192.168.1.1/"), None);
+
+    let description = "<root><device><serviceList>\
+         <service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>\
+         <controlURL>/ctl/L3F</controlURL></service>\
+         <service><serviceType>urn:schemas-upnp-org:service:WANPPPConnection:1</serviceType>\
+         <controlURL>/ctl/PPPConn</controlURL></service>\
+         <service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>\
+         <controlURL>/ctl/IPConn</controlURL></service>\
+         </serviceList></device></root>";
+    assert_eq!(
+        parse_wan_service(description),
+        Some((WAN_SERVICES[0].to_string(), "/ctl/IPConn".to_string()))
+    );
+}
@@ -1,6 +1,7 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 use witnet_p2p::peers::*;
+use witnet_util::timestamp::get_timestamp;
 
 #[test]
 fn p2p_peers_add() {
@@ -110,3 +111,258 @@ fn p2p_peers_get_all() {
     added_peers.sort_by(sort_by_ip_then_port);
     assert_eq!(many_peers, added_peers);
 }
+
+#[test]
+fn p2p_peers_tried() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let address2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add(vec![address1, address2]).unwrap();
+
+    // Trying a new peer moves it to the tried peers
+    assert_eq!(peers.add_tried(vec![address1]).unwrap(), vec![address1]);
+    assert_eq!(peers.add_tried(vec![address1]).unwrap(), vec![]);
+    assert_eq!(peers.get_tried().unwrap(), vec![address1]);
+    assert_eq!(peers.get_new().unwrap(), vec![address2]);
+    assert_eq!(peers.get_all().unwrap(), vec![address1, address2]);
+
+    // Adding a tried peer again keeps it in the tried peers
+    assert_eq!(peers.add(vec![address1]).unwrap(), vec![address1]);
+    assert_eq!(peers.get_tried().unwrap(), vec![address1]);
+    assert_eq!(peers.get_new().unwrap(), vec![address2]);
+
+    // Tried peers can be removed too
+    assert_eq!(peers.remove(&[address1]).unwrap(), vec![address1]);
+    assert!(peers.get_tried().unwrap().is_empty());
+}
+
+#[test]
+fn p2p_peers_get_random_tried() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let address2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add_tried(vec![address1]).unwrap();
+
+    // With no new peers, the tried ones are returned
+    assert_eq!(peers.get_random().unwrap(), Some(address1));
+
+    // With both, each table is chosen about half of the times
+    peers.add(vec![address2]).unwrap();
+    let mut diff: i16 = 0;
+    for _ in 0..100000 {
+        match peers.get_random().unwrap() {
+            Some(addr) if addr == address1 => diff = diff + 1,
+            Some(addr) if addr == address2 => diff = diff - 1,
+            _ => assert!(
+                false,
+                "Get random function should retrieve a random address"
+            ),
+        }
+    }
+    assert!(
+        diff < 1000 && diff > -1000,
+        "Get random seems not to be choosing both tables"
+    );
+}
+
+#[test]
+fn p2p_peers_get_random_new() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let address2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+
+    // No new peers
+    assert_eq!(peers.get_random_new().unwrap(), None);
+    peers.add_tried(vec![address1]).unwrap();
+    assert_eq!(peers.get_random_new().unwrap(), None);
+
+    // Only the new peers are returned
+    peers.add(vec![address2]).unwrap();
+    for _ in 0..100 {
+        assert_eq!(peers.get_random_new().unwrap(), Some(address2));
+    }
+
+    // Once tried, a peer is not new anymore
+    peers.add_tried(vec![address2]).unwrap();
+    assert_eq!(peers.get_random_new().unwrap(), None);
+}
+
+#[test]
+fn p2p_peers_add_from() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let source = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let other_source = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    let addresses: Vec<_> = (0..MAX_NEW_PEERS_PER_SOURCE + 10)
+        .map(|i| SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), i as u16))
+        .collect();
+
+    // A source cannot add more than MAX_NEW_PEERS_PER_SOURCE new peers
+    let added = peers.add_from(addresses.clone(), source).unwrap();
+    assert_eq!(added.len(), MAX_NEW_PEERS_PER_SOURCE);
+    assert_eq!(peers.get_new().unwrap().len(), MAX_NEW_PEERS_PER_SOURCE);
+    assert!(peers
+        .add_from(addresses.clone(), source)
+        .unwrap()
+        .is_empty());
+
+    // Other sources can add the rest, and the known peers are not added again
+    let added = peers.add_from(addresses.clone(), other_source).unwrap();
+    assert_eq!(added, addresses[MAX_NEW_PEERS_PER_SOURCE..].to_vec());
+
+    // Gossiped tried peers stay tried
+    let tried = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add_tried(vec![tried]).unwrap();
+    assert!(peers
+        .add_from(vec![tried], other_source)
+        .unwrap()
+        .is_empty());
+    assert_eq!(peers.get_tried().unwrap(), vec![tried]);
+}
+
+#[test]
+fn p2p_peers_get_recent() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let address2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add(vec![address1]).unwrap();
+    peers.add_tried(vec![address2]).unwrap();
+
+    // Both tried and new peers are shared, up to the maximum
+    let mut recent = peers.get_recent(10, 0).unwrap();
+    recent.sort();
+    assert_eq!(recent, vec![address1, address2]);
+    assert_eq!(peers.get_recent(1, 0).unwrap().len(), 1);
+
+    // Peers not heard of since the given timestamp are not shared
+    assert!(peers.get_recent(10, i64::max_value()).unwrap().is_empty());
+}
+
+#[test]
+fn p2p_peers_add_failed() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let unknown = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add(vec![address]).unwrap();
+
+    // Unknown addresses are ignored
+    assert!(peers.add_failed(vec![unknown]).unwrap().is_empty());
+    assert_eq!(peers.get_retry_time(&unknown), None);
+
+    // A peer backing off is not returned as random peer
+    let before = get_timestamp();
+    assert_eq!(peers.add_failed(vec![address]).unwrap(), vec![address]);
+    let retry_at = peers.get_retry_time(&address).unwrap();
+    assert!(retry_at >= before + BACKOFF_BASE / 2);
+    assert!(retry_at <= get_timestamp() + BACKOFF_BASE);
+    assert_eq!(peers.get_random().unwrap(), None);
+    assert_eq!(peers.get_random_new().unwrap(), None);
+    assert_eq!(peers.get_backing_off(before).unwrap(), vec![address]);
+    assert!(peers.get_backing_off(retry_at).unwrap().is_empty());
+
+    // The backoff doubles with each failure, up to the cap
+    peers.add_failed(vec![address]).unwrap();
+    assert!(peers.get_retry_time(&address).unwrap() >= before + BACKOFF_BASE);
+    for _ in 0..100 {
+        peers.add_failed(vec![address]).unwrap();
+    }
+    let retry_at = peers.get_retry_time(&address).unwrap();
+    assert!(retry_at >= before + BACKOFF_CAP / 2);
+    assert!(retry_at <= get_timestamp() + BACKOFF_CAP);
+
+    // A successful connection resets the backoff
+    peers.add_tried(vec![address]).unwrap();
+    assert_eq!(peers.get_retry_time(&address), None);
+    assert!(peers.get_backing_off(before).unwrap().is_empty());
+    assert_eq!(peers.get_random().unwrap(), Some(address));
+}
+
+#[test]
+fn p2p_peers_reputation() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let unknown = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add(vec![address]).unwrap();
+
+    let update = Reputation {
+        uptime: 2 * UPTIME_PER_POINT,
+        useful_blocks: 3,
+        misbehavior: 1,
+    };
+
+    // The reputation of unknown peers is not recorded
+    assert_eq!(peers.update_reputation(unknown, update).unwrap(), None);
+    assert_eq!(peers.get_reputation(&unknown), Reputation::default());
+
+    // The updates are added to the reputation of known peers
+    peers.update_reputation(address, update).unwrap();
+    let reputation = peers.update_reputation(address, update).unwrap().unwrap();
+    assert_eq!(
+        reputation,
+        Reputation {
+            uptime: 4 * UPTIME_PER_POINT,
+            useful_blocks: 6,
+            misbehavior: 2,
+        }
+    );
+    assert_eq!(reputation.score(), 4 + 6 - 2);
+    assert_eq!(peers.get_reputation(&address), reputation);
+    assert_eq!(
+        peers.get_reputations().unwrap(),
+        vec![(address, reputation)]
+    );
+
+    // Removing a peer forgets its reputation
+    peers.remove(&[address]).unwrap();
+    assert_eq!(peers.get_reputation(&address), Reputation::default());
+    assert!(peers.get_reputations().unwrap().is_empty());
+}
+
+#[test]
+fn p2p_peers_get_random_prefers_reputation() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let reliable = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let misbehaving = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add_tried(vec![reliable, misbehaving]).unwrap();
+    peers
+        .update_reputation(
+            reliable,
+            Reputation {
+                uptime: 10 * UPTIME_PER_POINT,
+                ..Reputation::default()
+            },
+        )
+        .unwrap();
+    peers
+        .update_reputation(
+            misbehaving,
+            Reputation {
+                misbehavior: 100,
+                ..Reputation::default()
+            },
+        )
+        .unwrap();
+
+    // The best of two random tried peers is returned, so the reliable peer is returned at least
+    // three quarters of the times
+    let picks = 1000;
+    let reliable_picks = (0..picks)
+        .filter(|_| peers.get_random().unwrap() == Some(reliable))
+        .count();
+    assert!(reliable_picks > picks / 2);
+}
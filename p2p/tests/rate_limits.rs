@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use witnet_p2p::rate_limits::*;
+
+#[test]
+fn p2p_rate_limits_token_bucket() {
+    let start = Instant::now();
+    let mut bucket = TokenBucket::new(60, start);
+
+    // A full bucket allows a burst of a minute worth of messages
+    for _ in 0..60 {
+        assert!(bucket.try_take(start));
+    }
+    assert!(!bucket.try_take(start));
+
+    // One token is added every second
+    assert!(!bucket.try_take(start + Duration::from_millis(500)));
+    assert!(bucket.try_take(start + Duration::from_secs(1)));
+    assert!(!bucket.try_take(start + Duration::from_secs(1)));
+
+    // The bucket is never filled over its capacity
+    let later = start + Duration::from_secs(3600);
+    for _ in 0..60 {
+        assert!(bucket.try_take(later));
+    }
+    assert!(!bucket.try_take(later));
+}
+
+#[test]
+fn p2p_rate_limits_rate_limiter() {
+    let now = Instant::now();
+    let mut limiter = RateLimiter::new(
+        &[(LimitedMessage::Inv, 2), (LimitedMessage::GetData, 0)],
+        now,
+    );
+
+    // Each kind of message has its own limit
+    assert!(limiter.check(LimitedMessage::Inv, now));
+    assert!(limiter.check(LimitedMessage::Inv, now));
+    assert!(!limiter.check(LimitedMessage::Inv, now));
+
+    // A limit of zero, or no limit, does not limit the messages
+    for _ in 0..100 {
+        assert!(limiter.check(LimitedMessage::GetData, now));
+        assert!(limiter.check(LimitedMessage::Peers, now));
+    }
+}
+
+#[test]
+fn p2p_rate_limits_bandwidth_limit() {
+    let start = Instant::now();
+
+    // 8 kbps are 1000 bytes per second, available at once
+    let mut limit = BandwidthLimit::new(8, start);
+    assert_eq!(limit.available(start), 1000);
+    limit.consume(600);
+    assert_eq!(limit.available(start), 400);
+    assert_eq!(limit.next_available(), start);
+
+    // Transferring more bytes than available delays the next transfer
+    limit.consume(500);
+    assert_eq!(limit.available(start), 0);
+    assert!(limit.next_available() > start + Duration::from_millis(100));
+    assert!(limit.next_available() < start + Duration::from_millis(102));
+
+    // The bytes are refilled at the limit rate, up to a second worth of bytes
+    assert_eq!(limit.available(start + Duration::from_secs(1)), 900);
+    assert_eq!(limit.available(start + Duration::from_secs(60)), 1000);
+}
@@ -0,0 +1,55 @@
+use std::net::SocketAddr;
+
+use witnet_p2p::relay::*;
+
+fn origin(solicited: bool, reputation: i64) -> ItemOrigin {
+    ItemOrigin {
+        address: SocketAddr::from(([127, 0, 0, 1], 21337)),
+        solicited,
+        reputation,
+    }
+}
+
+#[test]
+fn p2p_relay_default_policy_relays_everything() {
+    let policy = RelayPolicy::default();
+
+    assert!(policy.allows(None, false));
+    assert!(policy.allows(Some(&origin(false, -100)), false));
+}
+
+#[test]
+fn p2p_relay_unsolicited() {
+    let policy = RelayPolicy {
+        unsolicited: false,
+        ..RelayPolicy::default()
+    };
+
+    assert!(policy.allows(Some(&origin(true, 0)), true));
+    assert!(!policy.allows(Some(&origin(false, 0)), true));
+    // The items of the node itself are always relayed
+    assert!(policy.allows(None, true));
+}
+
+#[test]
+fn p2p_relay_min_reputation() {
+    let policy = RelayPolicy {
+        min_reputation: Some(10),
+        ..RelayPolicy::default()
+    };
+
+    assert!(policy.allows(Some(&origin(true, 10)), true));
+    assert!(!policy.allows(Some(&origin(true, 9)), true));
+}
+
+#[test]
+fn p2p_relay_unvalidated() {
+    let policy = RelayPolicy {
+        unvalidated: false,
+        ..RelayPolicy::default()
+    };
+
+    assert!(policy.allows(Some(&origin(false, 0)), true));
+    assert!(!policy.allows(Some(&origin(true, 0)), false));
+    assert!(policy.allows(None, false));
+}
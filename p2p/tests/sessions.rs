@@ -1,7 +1,7 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::time::Duration;
 
-use witnet_p2p::sessions::*;
+use witnet_p2p::sessions::{capabilities::Capabilities, *};
 
 /// Check if the sessions default initializes with empty state
 #[test]
@@ -87,6 +87,10 @@ fn p2p_sessions_is_outbound_address_eligible() {
     let server_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000);
     sessions.set_server_address(server_address);
 
+    // Set another listening address
+    let listening_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8004);
+    sessions.set_listening_addresses(vec![server_address, listening_address]);
+
     // Register an outbound session and check if result is Ok(())
     let outbound_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
     assert!(sessions
@@ -109,6 +113,7 @@ fn p2p_sessions_is_outbound_address_eligible() {
 
     // Check invalid addresses
     assert!(!sessions.is_outbound_address_eligible(server_address));
+    assert!(!sessions.is_outbound_address_eligible(listening_address));
     assert!(!sessions.is_outbound_address_eligible(outbound_address));
 
     // Check inbound address as valid address
@@ -151,7 +156,11 @@ fn p2p_sessions_get_num_outbound_sessions() {
         )
         .is_ok());
     assert!(sessions
-        .consolidate_session(SessionType::Outbound, outbound_cons_address)
+        .consolidate_session(
+            SessionType::Outbound,
+            outbound_cons_address,
+            Capabilities::default()
+        )
         .is_ok());
 
     // Check that the function to be tested returns the total number of outbound sessions
@@ -175,7 +184,11 @@ fn p2p_sessions_is_outbound_bootstrap_needed() {
             )
             .unwrap_or(());
         sessions
-            .consolidate_session(SessionType::Outbound, outbound_address)
+            .consolidate_session(
+                SessionType::Outbound,
+                outbound_address,
+                Capabilities::default(),
+            )
             .unwrap_or(());
     }
 
@@ -196,6 +209,7 @@ fn p2p_sessions_is_outbound_bootstrap_needed() {
 
     // Bootstrap is always needed when there is no limit
     assert!(sessions.is_outbound_bootstrap_needed());
+    assert_eq!(sessions.get_num_missing_outbound_sessions(), 1);
 
     // Set limits
     let limit_inbound = 1;
@@ -204,6 +218,15 @@ fn p2p_sessions_is_outbound_bootstrap_needed() {
 
     // Bootstrap is needed when the limit is higher than the number of outbound sessions
     assert!(sessions.is_outbound_bootstrap_needed());
+    assert_eq!(sessions.get_num_missing_outbound_sessions(), 1);
+
+    // Set limits
+    let limit_inbound = 1;
+    let limit_outbound = 10;
+    sessions.set_limits(limit_inbound, limit_outbound);
+
+    // All the free outbound slots are reported
+    assert_eq!(sessions.get_num_missing_outbound_sessions(), 4);
 
     // Set limits
     let limit_inbound = 1;
@@ -220,6 +243,7 @@ fn p2p_sessions_is_outbound_bootstrap_needed() {
 
     // Bootstrap is not needed when the limit is smaller than the number of outbound sessions
     assert!(!sessions.is_outbound_bootstrap_needed());
+    assert_eq!(sessions.get_num_missing_outbound_sessions(), 0);
 }
 
 /// Check the function to get a random outbound consolidated session
@@ -247,7 +271,11 @@ fn p2p_sessions_get_random_anycast_session() {
 
     // Consolidate outbound session
     assert!(sessions
-        .consolidate_session(SessionType::Outbound, outbound_address)
+        .consolidate_session(
+            SessionType::Outbound,
+            outbound_address,
+            Capabilities::default()
+        )
         .is_ok());
 
     // Check that the function returns Some(T) when there is one valid session in the collection
@@ -266,7 +294,11 @@ fn p2p_sessions_get_random_anycast_session() {
         )
         .is_ok());
     assert!(sessions
-        .consolidate_session(SessionType::Outbound, outbound_address)
+        .consolidate_session(
+            SessionType::Outbound,
+            outbound_address,
+            Capabilities::default()
+        )
         .is_ok());
 
     // Get random session for a "big" number
@@ -410,10 +442,18 @@ fn p2p_sessions_consolidate() {
 
     // Consolidate session
     assert!(sessions
-        .consolidate_session(SessionType::Outbound, outbound_address)
+        .consolidate_session(
+            SessionType::Outbound,
+            outbound_address,
+            Capabilities::default()
+        )
         .is_ok());
     assert!(sessions
-        .consolidate_session(SessionType::Inbound, inbound_address)
+        .consolidate_session(
+            SessionType::Inbound,
+            inbound_address,
+            Capabilities::default()
+        )
         .is_ok());
 
     // Check if sessions were consolidated
@@ -425,3 +465,363 @@ fn p2p_sessions_consolidate() {
     assert_eq!(sessions.outbound_unconsolidated.collection.len(), 0);
     assert!(sessions.inbound.collection.get(&inbound_address).is_some());
 }
+
+/// Check the caps of inbound sessions from the same IP address or subnet
+#[test]
+fn p2p_sessions_inbound_caps() {
+    // Create sessions struct and set the caps
+    let mut sessions = Sessions::<String>::default();
+    sessions.set_inbound_caps(2, 3);
+
+    // Two sessions from the same IP address are allowed
+    let ip = Ipv4Addr::new(192, 168, 1, 1);
+    for port in 8000..8002 {
+        assert!(sessions
+            .register_session(
+                SessionType::Inbound,
+                SocketAddr::new(IpAddr::V4(ip), port),
+                "reference".to_string()
+            )
+            .is_ok());
+    }
+
+    // A third one is refused
+    let address = SocketAddr::new(IpAddr::V4(ip), 8002);
+    assert!(!sessions.is_inbound_address_eligible(address));
+    assert!(sessions
+        .register_session(SessionType::Inbound, address, "reference".to_string())
+        .is_err());
+
+    // Another IP address of the same /24 subnet is allowed until the subnet cap is reached
+    let same_subnet = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)), 8000);
+    assert!(sessions
+        .register_session(SessionType::Inbound, same_subnet, "reference".to_string())
+        .is_ok());
+    let same_subnet = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 3)), 8000);
+    assert!(!sessions.is_inbound_address_eligible(same_subnet));
+
+    // Other subnets and loopback addresses are not affected
+    let other_subnet = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 2, 1)), 8000);
+    assert!(sessions.is_inbound_address_eligible(other_subnet));
+    let loopback = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000);
+    assert!(sessions.is_inbound_address_eligible(loopback));
+
+    // Outbound sessions are not affected either
+    assert!(sessions
+        .register_session(SessionType::Outbound, address, "reference".to_string())
+        .is_ok());
+
+    // Unregistering a session frees its slot
+    sessions
+        .unregister_session(
+            SessionType::Inbound,
+            SessionStatus::Unconsolidated,
+            SocketAddr::new(IpAddr::V4(ip), 8000),
+        )
+        .unwrap();
+    assert!(sessions.is_inbound_address_eligible(address));
+}
+
+/// Check the cap of outbound sessions with peers of the same network group
+#[test]
+fn p2p_sessions_outbound_diversity() {
+    // Create sessions struct and set the cap
+    let mut sessions = Sessions::<String>::default();
+    sessions.set_outbound_diversity(diversity::OutboundDiversity {
+        max_per_group: Some(2),
+        asmap: diversity::AsMap::default(),
+    });
+
+    // One session and one requested connection in the same /16 network reach the cap
+    let registered = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 1, 1, 1)), 8000);
+    sessions
+        .register_session(SessionType::Outbound, registered, "reference".to_string())
+        .unwrap();
+    let requested = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 1)), 8000);
+    let candidate = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 1, 3, 1)), 8000);
+    assert!(sessions.is_outbound_address_diverse(candidate, &[]));
+    assert!(!sessions.is_outbound_address_diverse(candidate, &[requested]));
+
+    // A requested connection which already has a session is only counted once
+    assert!(sessions.is_outbound_address_diverse(candidate, &[registered]));
+
+    // Other networks and loopback addresses are not affected
+    let other_network = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 2, 1, 1)), 8000);
+    assert!(sessions.is_outbound_address_diverse(other_network, &[requested]));
+    let loopback = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000);
+    sessions
+        .register_session(SessionType::Outbound, loopback, "reference".to_string())
+        .unwrap();
+    let loopback = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    assert!(sessions.is_outbound_address_diverse(loopback, &[requested]));
+}
+
+/// Check that only one feeler session runs at a time, and that it is not an outbound session
+#[test]
+fn p2p_sessions_feeler() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+    sessions.set_limits(1, 1);
+
+    // Register a feeler session
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    assert!(sessions.is_feeler_needed());
+    assert!(sessions
+        .register_session(SessionType::Feeler, address, "reference".to_string())
+        .is_ok());
+    assert!(!sessions.is_feeler_needed());
+    assert!(!sessions.is_outbound_address_eligible(address));
+    assert_eq!(sessions.get_num_outbound_sessions(), 0);
+
+    // A second one is refused
+    let other_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8002);
+    assert!(sessions
+        .register_session(SessionType::Feeler, other_address, "reference".to_string())
+        .is_err());
+
+    // The feeler session can be consolidated and unregistered
+    assert!(sessions
+        .consolidate_session(SessionType::Feeler, address, Capabilities::default())
+        .is_ok());
+    assert_eq!(sessions.get_all_consolidated_outbound_sessions().count(), 0);
+    assert!(sessions
+        .unregister_session(SessionType::Feeler, SessionStatus::Consolidated, address)
+        .is_ok());
+    assert!(sessions.is_feeler_needed());
+    assert!(sessions.is_outbound_address_eligible(address));
+}
+
+/// Check the negotiation of the capabilities of a session and their storage
+#[test]
+fn p2p_sessions_capabilities() {
+    let local = Capabilities {
+        version: 2,
+        features: 0b011,
+    };
+    let remote = Capabilities {
+        version: 3,
+        features: 0b110,
+    };
+
+    // The lowest version and the common features are negotiated
+    let negotiated = Capabilities::negotiate(local, remote, 1).unwrap();
+    assert_eq!(
+        negotiated,
+        Capabilities {
+            version: 2,
+            features: 0b010,
+        }
+    );
+    assert!(negotiated.supports(0b010));
+    assert!(!negotiated.supports(0b001));
+    assert!(!negotiated.supports(0b110));
+
+    // Peers older than the minimum version are rejected
+    assert!(Capabilities::negotiate(local, remote, 4).is_none());
+
+    // The capabilities are only known once the session is consolidated
+    let mut sessions = Sessions::<String>::default();
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    assert!(sessions
+        .register_session(SessionType::Outbound, address, "reference".to_string())
+        .is_ok());
+    assert_eq!(sessions.get_capabilities(address), None);
+    assert!(sessions
+        .consolidate_session(SessionType::Outbound, address, negotiated)
+        .is_ok());
+    assert_eq!(sessions.get_capabilities(address), Some(negotiated));
+
+    // And forgotten when it is unregistered
+    assert!(sessions
+        .unregister_session(SessionType::Outbound, SessionStatus::Consolidated, address)
+        .is_ok());
+    assert_eq!(sessions.get_capabilities(address), None);
+}
+
+/// Check the listing of the consolidated sessions and the latency of their peers
+#[test]
+fn p2p_sessions_get_connected_peers() {
+    let mut sessions = Sessions::<String>::default();
+    let capabilities = Capabilities {
+        version: 1,
+        features: 1,
+    };
+
+    // Only the consolidated sessions are listed
+    let inbound_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    let outbound_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8002);
+    let pending_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 8003);
+    for (session_type, address) in &[
+        (SessionType::Inbound, inbound_address),
+        (SessionType::Outbound, outbound_address),
+        (SessionType::Inbound, pending_address),
+    ] {
+        assert!(sessions
+            .register_session(*session_type, *address, "reference".to_string())
+            .is_ok());
+    }
+    assert!(sessions
+        .consolidate_session(SessionType::Inbound, inbound_address, capabilities)
+        .is_ok());
+    assert!(sessions
+        .consolidate_session(SessionType::Outbound, outbound_address, capabilities)
+        .is_ok());
+
+    // The latency can only be set for consolidated sessions
+    let latency = Duration::from_millis(150);
+    assert!(sessions.set_latency(outbound_address, latency).is_ok());
+    assert!(sessions.set_latency(pending_address, latency).is_err());
+
+    let mut peers = sessions.get_connected_peers();
+    peers.sort_by_key(|peer| peer.address);
+    assert_eq!(
+        peers,
+        vec![
+            ConnectedPeer {
+                address: inbound_address,
+                session_type: SessionType::Inbound,
+                capabilities,
+                latency: None,
+            },
+            ConnectedPeer {
+                address: outbound_address,
+                session_type: SessionType::Outbound,
+                capabilities,
+                latency: Some(latency),
+            },
+        ]
+    );
+}
+
+/// Check that all the sessions are returned, whatever their type and status
+#[test]
+fn p2p_sessions_get_all_sessions() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+    assert_eq!(sessions.get_all_sessions().count(), 0);
+
+    let inbound = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    let outbound = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8001);
+    let unconsolidated = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 8001);
+    let feeler = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 4)), 8001);
+    for (session_type, address) in &[
+        (SessionType::Inbound, inbound),
+        (SessionType::Outbound, outbound),
+        (SessionType::Outbound, unconsolidated),
+        (SessionType::Feeler, feeler),
+    ] {
+        sessions
+            .register_session(*session_type, *address, address.to_string())
+            .unwrap();
+    }
+    sessions
+        .consolidate_session(SessionType::Inbound, inbound, Capabilities::default())
+        .unwrap();
+    sessions
+        .consolidate_session(SessionType::Outbound, outbound, Capabilities::default())
+        .unwrap();
+
+    let mut all: Vec<_> = sessions.get_all_sessions().cloned().collect();
+    all.sort();
+    let mut expected: Vec<_> = [inbound, outbound, unconsolidated, feeler]
+        .iter()
+        .map(|address| address.to_string())
+        .collect();
+    expected.sort();
+    assert_eq!(all, expected);
+}
+
+/// Check that only the sessions whose address satisfies the filter are returned
+#[test]
+fn p2p_sessions_get_sessions_by_address() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+
+    let inbound = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    let outbound = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8002);
+    let other = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8001);
+    sessions
+        .register_session(SessionType::Inbound, inbound, inbound.to_string())
+        .unwrap();
+    sessions
+        .register_session(SessionType::Outbound, outbound, outbound.to_string())
+        .unwrap();
+    sessions
+        .register_session(SessionType::Outbound, other, other.to_string())
+        .unwrap();
+    sessions
+        .consolidate_session(SessionType::Outbound, outbound, Capabilities::default())
+        .unwrap();
+
+    // Filter by address
+    let found: Vec<_> = sessions
+        .get_sessions_by_address(|address| *address == other)
+        .cloned()
+        .collect();
+    assert_eq!(found, vec![other.to_string()]);
+
+    // Filter by IP address
+    let mut found: Vec<_> = sessions
+        .get_sessions_by_address(|address| address.ip() == inbound.ip())
+        .cloned()
+        .collect();
+    found.sort();
+    assert_eq!(found, vec![inbound.to_string(), outbound.to_string()]);
+
+    // No matches
+    assert_eq!(sessions.get_sessions_by_address(|_| false).count(), 0);
+}
+
+/// Check the eviction of inbound sessions to make room for priority peers
+#[test]
+fn p2p_sessions_evict_inbound_session() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+    sessions.set_limits(4, 1);
+    sessions.set_priority_networks(vec!["10.0.0.0/8".parse().unwrap()]);
+
+    let priority = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 8001);
+    let unconsolidated = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    let slow = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8001);
+    let fast = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 8001);
+    assert!(sessions.is_priority_address(priority));
+    assert!(!sessions.is_priority_address(slow));
+
+    // Nothing to evict without sessions
+    assert_eq!(sessions.evict_inbound_session(), None);
+
+    for address in &[priority, unconsolidated, slow, fast] {
+        sessions
+            .register_session(SessionType::Inbound, *address, address.to_string())
+            .unwrap();
+    }
+    assert!(sessions.is_inbound_full());
+    for address in &[priority, slow, fast] {
+        sessions
+            .consolidate_session(SessionType::Inbound, *address, Capabilities::default())
+            .unwrap();
+    }
+    sessions
+        .set_latency(slow, Duration::from_millis(500))
+        .unwrap();
+    sessions
+        .set_latency(fast, Duration::from_millis(50))
+        .unwrap();
+
+    // The unconsolidated session goes first, then the slowest peer, but never a priority peer
+    assert_eq!(
+        sessions.evict_inbound_session(),
+        Some((unconsolidated, unconsolidated.to_string()))
+    );
+    assert!(!sessions.is_inbound_full());
+    assert_eq!(
+        sessions.evict_inbound_session(),
+        Some((slow, slow.to_string()))
+    );
+    assert_eq!(
+        sessions.evict_inbound_session(),
+        Some((fast, fast.to_string()))
+    );
+    assert_eq!(sessions.evict_inbound_session(), None);
+    assert_eq!(sessions.get_num_inbound_sessions(), 1);
+}
@@ -0,0 +1,49 @@
+use std::net::SocketAddr;
+
+use witnet_p2p::socks::*;
+
+fn proxy() -> SocketAddr {
+    "127.0.0.1:9050".parse().unwrap()
+}
+
+#[test]
+fn p2p_socks_method_selection() {
+    assert_eq!(greeting(), vec![5, 1, 0]);
+
+    // The proxy accepts no authentication
+    assert!(check_method_selection(proxy(), &[5, 0]).is_ok());
+    // The proxy requires authentication
+    assert!(check_method_selection(proxy(), &[5, 0xff]).is_err());
+    // Not a SOCKS5 proxy
+    assert!(check_method_selection(proxy(), &[4, 0]).is_err());
+}
+
+#[test]
+fn p2p_socks_connect_request() {
+    let ipv4: SocketAddr = "203.0.113.7:21337".parse().unwrap();
+    assert_eq!(
+        connect_request(ipv4),
+        vec![5, 1, 0, 1, 203, 0, 113, 7, 0x53, 0x59]
+    );
+
+    let ipv6: SocketAddr = "[2001:db8::1]:21337".parse().unwrap();
+    let request = connect_request(ipv6);
+    assert_eq!(request.len(), 4 + 16 + 2);
+    assert_eq!(&request[..6], &[5, 1, 0, 4, 0x20, 0x01]);
+    assert_eq!(&request[19..], &[1, 0x53, 0x59]);
+}
+
+#[test]
+fn p2p_socks_reply_header() {
+    // The length of the rest of the reply depends on the type of the bound address
+    assert_eq!(check_reply_header(proxy(), &[5, 0, 0, 1, 0]).unwrap(), 5);
+    assert_eq!(check_reply_header(proxy(), &[5, 0, 0, 4, 0]).unwrap(), 17);
+    assert_eq!(check_reply_header(proxy(), &[5, 0, 0, 3, 10]).unwrap(), 12);
+
+    // The proxy could not connect to the peer
+    assert!(check_reply_header(proxy(), &[5, 5, 0, 1, 0]).is_err());
+    // Unknown address type
+    assert!(check_reply_header(proxy(), &[5, 0, 0, 2, 0]).is_err());
+    // Too short
+    assert!(check_reply_header(proxy(), &[5, 0, 0]).is_err());
+}
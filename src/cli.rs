@@ -6,9 +6,18 @@
 #![deny(missing_docs)]
 
 use crate::core::actors;
+use crate::core::actors::storage_manager::{backend::Backend, verify};
 use ctrlc;
 use failure;
+use witnet_config::config::Config;
+use witnet_config::{defaults, dirs, docs, loaders};
+use witnet_data_structures::chain::Environment;
+use witnet_storage::snapshot;
+use witnet_storage::storage::Storage;
 
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::result::Result;
 use structopt::StructOpt;
@@ -42,23 +51,373 @@ pub(crate) enum Command {
         )]
         #[structopt(parse(from_os_str))]
         config: Option<PathBuf>,
+
+        // Overrides for single configuration params
+        #[structopt(
+            name = "set",
+            long = "set",
+            help = "Override a configuration param, e.g.: --set connections.inbound_limit=16",
+            raw(number_of_values = "1")
+        )]
+        overrides: Vec<String>,
+
+        // Profile to select from the configuration file
+        #[structopt(
+            name = "profile",
+            long = "profile",
+            help = "Name of the configuration profile to merge over the base configuration"
+        )]
+        profile: Option<String>,
         // Run the server in the background
         // #[structopt(name = "background", short = "b")]
         // background: bool,
     },
+    #[structopt(
+        name = "export-snapshot",
+        about = "Write the chain state kept in the storage to a snapshot file"
+    )]
+    ExportSnapshot {
+        // Snapshot file path
+        #[structopt(name = "file", help = "Path of the snapshot file to write")]
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+
+        // Config file path
+        #[structopt(
+            name = "config",
+            long = "config",
+            short = "c",
+            help = "Path to the configuration file"
+        )]
+        #[structopt(parse(from_os_str))]
+        config: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "import-snapshot",
+        about = "Bootstrap an empty storage from a snapshot file"
+    )]
+    ImportSnapshot {
+        // Snapshot file path
+        #[structopt(name = "file", help = "Path of the snapshot file to read")]
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+
+        // Config file path
+        #[structopt(
+            name = "config",
+            long = "config",
+            short = "c",
+            help = "Path to the configuration file"
+        )]
+        #[structopt(parse(from_os_str))]
+        config: Option<PathBuf>,
+    },
+    #[structopt(name = "db", about = "Manage the storage of the node")]
+    Db {
+        /// `witnet db cmd ...`
+        #[structopt(subcommand)]
+        cmd: DbCommand,
+    },
+    #[structopt(name = "config", about = "Manage the configuration")]
+    Config {
+        /// `witnet config cmd ...`
+        #[structopt(subcommand)]
+        cmd: ConfigCommand,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub(crate) enum DbCommand {
+    #[structopt(
+        name = "verify",
+        about = "Check the integrity of the blocks and other data in the storage"
+    )]
+    Verify {
+        // Config file path
+        #[structopt(
+            name = "config",
+            long = "config",
+            short = "c",
+            help = "Path to the configuration file"
+        )]
+        #[structopt(parse(from_os_str))]
+        config: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub(crate) enum ConfigCommand {
+    #[structopt(name = "init", about = "Write a starter configuration file")]
+    Init {
+        // Environment whose defaults are written in the file
+        #[structopt(
+            name = "environment",
+            long = "environment",
+            short = "e",
+            default_value = "testnet-1",
+            help = "Environment of the configuration, e.g.: testnet-1"
+        )]
+        environment: String,
+
+        // Path of the file to write
+        #[structopt(
+            name = "output",
+            long = "output",
+            short = "o",
+            default_value = "witnet.toml",
+            help = "Path of the configuration file to write"
+        )]
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+
+        // Ask for the most common params
+        #[structopt(
+            name = "interactive",
+            long = "interactive",
+            short = "i",
+            help = "Ask for the database path and the server address"
+        )]
+        interactive: bool,
+
+        // Overwrite the file if it already exists
+        #[structopt(
+            name = "force",
+            long = "force",
+            short = "f",
+            help = "Overwrite the configuration file if it already exists"
+        )]
+        force: bool,
+    },
+    #[structopt(
+        name = "validate",
+        about = "Check a configuration file and print the effective configuration"
+    )]
+    Validate {
+        // Config file path
+        #[structopt(name = "config", help = "Path to the configuration file")]
+        #[structopt(parse(from_os_str))]
+        config: PathBuf,
+
+        // Overrides for single configuration params
+        #[structopt(
+            name = "set",
+            long = "set",
+            help = "Override a configuration param, e.g.: --set connections.inbound_limit=16",
+            raw(number_of_values = "1")
+        )]
+        overrides: Vec<String>,
+
+        // Profile to select from the configuration file
+        #[structopt(
+            name = "profile",
+            long = "profile",
+            help = "Name of the configuration profile to merge over the base configuration"
+        )]
+        profile: Option<String>,
+
+        // Reject unknown sections and params
+        #[structopt(
+            name = "strict",
+            long = "strict",
+            help = "Fail if the file contains unknown sections or params"
+        )]
+        strict: bool,
+    },
 }
 
 pub(crate) fn exec(command: Command) -> Result<(), failure::Error> {
     match command {
-        Command::Node { config, .. } => {
-            actors::node::run(config, || {
-                // FIXME(#72): decide what to do when interrupt signals are received
+        Command::Node {
+            config,
+            overrides,
+            profile,
+            ..
+        } => {
+            // Fail early if any of the overrides is malformed
+            loaders::cli::from_overrides(&overrides)?;
+
+            actors::node::run(config, profile, overrides, |arbiter| {
+                // Shut the node down gracefully when SIGINT is received, the node handles SIGTERM
                 ctrlc::set_handler(move || {
-                    actors::node::close();
+                    actors::node::close(&arbiter);
                 })
-                .expect("Error setting handler for both SIGINT (Ctrl+C) and SIGTERM (kill)");
+                .expect("Error setting handler for SIGINT (Ctrl+C)");
             })?;
         }
+        Command::ExportSnapshot { file, config } => {
+            let storage = open_storage(config)?;
+            let writer = io::BufWriter::new(fs::File::create(&file)?);
+            let summary = snapshot::export(&*storage, writer)?;
+            println!(
+                "Exported {} entries to {} (checksum {:?})",
+                summary.entries,
+                file.display(),
+                summary.checksum
+            );
+        }
+        Command::ImportSnapshot { file, config } => {
+            let mut storage = open_storage(config)?;
+            let reader = io::BufReader::new(fs::File::open(&file)?);
+            let summary = snapshot::import(&mut *storage, reader)?;
+            println!(
+                "Imported {} entries from {} (checksum {:?})",
+                summary.entries,
+                file.display(),
+                summary.checksum
+            );
+        }
+        Command::Db { cmd } => exec_db(cmd)?,
+        Command::Config { cmd } => exec_config(cmd)?,
+    }
+    Ok(())
+}
+
+fn exec_db(command: DbCommand) -> Result<(), failure::Error> {
+    match command {
+        DbCommand::Verify { config } => {
+            let storage = open_storage(config)?;
+            let report = verify::verify(&storage)?;
+            for problem in &report.problems {
+                println!("{}", problem);
+            }
+            if !report.is_ok() {
+                failure::bail!(
+                    "Found {} problems in {} entries ({} blocks)",
+                    report.problems.len(),
+                    report.entries,
+                    report.blocks
+                );
+            }
+            println!(
+                "OK: checked {} entries ({} blocks)",
+                report.entries, report.blocks
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Open the storage of the node using the given configuration file,
+/// or the one found in the standard locations. The node must not be
+/// running.
+fn open_storage(config: Option<PathBuf>) -> Result<Box<Backend>, failure::Error> {
+    let config_file = dirs::find_config_file(config.as_ref().map(PathBuf::as_path)).path();
+    let partial_config = loaders::toml::from_file(&config_file)?;
+    let config = Config::try_from_partial(&partial_config)?;
+
+    Ok(Backend::new(config.storage)?)
+}
+
+fn exec_config(command: ConfigCommand) -> Result<(), failure::Error> {
+    match command {
+        ConfigCommand::Init {
+            environment,
+            output,
+            interactive,
+            force,
+        } => {
+            if output.exists() && !force {
+                failure::bail!(
+                    "{} already exists, use --force to overwrite it",
+                    output.display()
+                );
+            }
+            let environment = match Environment::from_name(&environment) {
+                Some(environment) => environment,
+                None => failure::bail!(
+                    "Unknown environment `{}`, expected `mainnet` or `testnet-1`",
+                    environment
+                ),
+            };
+            let defaults = match defaults::for_environment(&environment) {
+                Some(defaults) => defaults,
+                None => failure::bail!(
+                    "Starter configuration files can only be written for built-in environments, \
+                     `{}` is not one of them",
+                    environment
+                ),
+            };
+
+            let mut values = vec![("environment", toml::Value::String(environment.to_string()))];
+            if interactive {
+                let stdin = io::stdin();
+                let mut input = stdin.lock();
+
+                let db_path = prompt(
+                    &mut input,
+                    "Database path",
+                    defaults.storage_db_path().to_string_lossy().into_owned(),
+                    |answer| Ok(answer.to_string()),
+                )?;
+                values.push(("storage.db_path", toml::Value::String(db_path)));
+
+                let server_addr = prompt(
+                    &mut input,
+                    "Server address",
+                    defaults.connections_server_addr(),
+                    |answer| answer.parse::<SocketAddr>().map_err(|e| e.to_string()),
+                )?;
+                values.push((
+                    "connections.server_addr",
+                    toml::Value::String(server_addr.to_string()),
+                ));
+            }
+
+            fs::write(&output, docs::sample_toml_for(&environment, &values))?;
+            println!("Configuration written to {}", output.display());
+        }
+        ConfigCommand::Validate {
+            config,
+            overrides,
+            profile,
+            strict,
+        } => {
+            // Load the configuration the same way the node does
+            let mut partial_config = if strict {
+                loaders::toml::from_file_strict(&config)?
+            } else {
+                loaders::toml::from_file(&config)?
+            };
+            if let Some(profile) = &profile {
+                partial_config = loaders::select_profile(partial_config, profile)?;
+            }
+            let partial_config = loaders::cli::apply(partial_config, &overrides)?;
+            let config = Config::try_from_partial(&partial_config)?;
+
+            println!("OK");
+            println!("{}", config.to_redacted_toml_string()?);
+        }
     }
     Ok(())
 }
+
+/// Ask for a value in the terminal until a valid one is given. An
+/// empty answer selects the default value.
+fn prompt<T, F>(
+    input: &mut dyn BufRead,
+    question: &str,
+    default: T,
+    parse: F,
+) -> Result<T, failure::Error>
+where
+    T: std::fmt::Display,
+    F: Fn(&str) -> Result<T, String>,
+{
+    loop {
+        print!("{} [{}]: ", question, default);
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Ok(default);
+        }
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match parse(answer) {
+            Ok(value) => return Ok(value),
+            Err(e) => println!("Invalid value: {}", e),
+        }
+    }
+}
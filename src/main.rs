@@ -4,12 +4,13 @@
 #![deny(non_snake_case)]
 #![deny(unused_mut)]
 
+use std::env;
 use std::process::exit;
 use std::result::Result;
 
 use env_logger::Builder;
 use failure;
-use log::error;
+use log::{error, LevelFilter};
 use structopt::StructOpt;
 
 use witnet_core as core;
@@ -18,11 +19,20 @@ mod cli;
 
 fn main() {
     // Init app logger
-    Builder::from_default_env()
-        // Remove comments to sprint demo
-        //.default_format_timestamp(false)
-        //.default_format_module_path(false)
-        .init();
+    let mut builder = Builder::from_default_env();
+    // Remove comments to sprint demo
+    //builder.default_format_timestamp(false);
+    //builder.default_format_module_path(false);
+    let use_config_level = env::var_os("RUST_LOG").is_none();
+    if use_config_level {
+        // Let everything through the logger, the maximum level is set
+        // from the `log.level` configuration param once it is loaded
+        builder.filter_level(LevelFilter::Trace);
+    }
+    builder.init();
+    if use_config_level {
+        log::set_max_level(LevelFilter::Info);
+    }
 
     if let Err(e) = run() {
         error!("Error: {}", e);
@@ -2,6 +2,12 @@
 //! These modules implement the Storage trait for whatever struct containing state for specific
 //! storage solutions (databases, volatile memory, flat files, etc.).
 
+pub mod archive;
+pub mod cached;
+pub mod directory;
+pub mod encrypted;
 pub mod in_memory;
+pub mod memory;
+pub mod read_only;
 #[cfg(feature = "rocksdb-backend")]
 pub mod rocks;
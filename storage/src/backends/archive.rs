@@ -0,0 +1,171 @@
+//! Storage wrapper that moves old entries of another storage backend to an archive directory (see
+//! `directory`), keeping the wrapped storage small while the archived entries can still be read.
+//!
+//! Writes go to the wrapped storage, and entries are moved to the archive with `offload`. Reads
+//! look up the wrapped storage first and then the archive, so callers do not need to know where an
+//! entry is. Deleting an entry removes it from both.
+use crate::backends::directory::DirectoryStorage;
+use crate::error::StorageResult;
+use crate::storage::{Storage, StorageIterator, ValueView, WriteBatch, WriteOp};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+/// Storage wrapper archiving the entries of the storage `S`, whose connection data is `C`
+pub struct ArchiveStorage<C, S> {
+    storage: S,
+    archive: Option<DirectoryStorage>,
+    conn_data: PhantomData<C>,
+}
+
+impl<C: Debug, S> ArchiveStorage<C, S> {
+    /// Wrap an already opened storage, archiving its entries to `archive`
+    pub fn wrap(storage: S, archive: DirectoryStorage) -> Self {
+        ArchiveStorage {
+            storage,
+            archive: Some(archive),
+            conn_data: PhantomData,
+        }
+    }
+
+    /// The wrapped storage
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Mutable reference to the wrapped storage
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// The archive directory, if any
+    pub fn archive(&self) -> Option<&DirectoryStorage> {
+        self.archive.as_ref()
+    }
+}
+
+impl<'a, C: Debug, S> ArchiveStorage<C, S>
+where
+    S: Storage<C, &'a [u8], Vec<u8>>,
+{
+    /// Move an entry from the wrapped storage to the archive, returning whether it was moved.
+    /// Nothing is moved when the entry is not in the wrapped storage, or there is no archive.
+    ///
+    /// The entry is written to the archive before being deleted from the wrapped storage, so it
+    /// can be read at any moment.
+    pub fn offload(&mut self, key: &'a [u8]) -> StorageResult<bool> {
+        let archive = match &mut self.archive {
+            Some(archive) => archive,
+            None => return Ok(false),
+        };
+        match self.storage.get(key)? {
+            Some(value) => {
+                Storage::<PathBuf, _, _>::put(archive, key, value)?;
+                self.storage.delete(key)?;
+
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Implement the Storage generic trait for the wrapper
+impl<'a, C: Debug, S> Storage<C, &'a [u8], Vec<u8>> for ArchiveStorage<C, S>
+where
+    S: Storage<C, &'a [u8], Vec<u8>>,
+{
+    /// A new storage has no archive, use `ArchiveStorage::wrap` to set it
+    #[allow(clippy::new_ret_no_self)]
+    fn new(conn_data: C) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        let storage = S::new(conn_data)?;
+
+        Ok(Box::new(ArchiveStorage {
+            storage: *storage,
+            archive: None,
+            conn_data: PhantomData,
+        }))
+    }
+
+    fn put(&mut self, key: &'a [u8], value: Vec<u8>) -> StorageResult<()> {
+        self.storage.put(key, value)
+    }
+
+    fn get(&self, key: &'a [u8]) -> StorageResult<Option<Vec<u8>>> {
+        match self.storage.get(key)? {
+            Some(value) => Ok(Some(value)),
+            None => self.get_archived(key),
+        }
+    }
+
+    fn get_view<'s>(&'s self, key: &'a [u8]) -> StorageResult<Option<ValueView<'s>>> {
+        match self.storage.get_view(key)? {
+            Some(value) => Ok(Some(value)),
+            None => Ok(self
+                .get_archived(key)?
+                .map(|value| Box::new(value) as ValueView<'s>)),
+        }
+    }
+
+    fn delete(&mut self, key: &'a [u8]) -> StorageResult<()> {
+        self.storage.delete(key)?;
+        match &mut self.archive {
+            Some(archive) => Storage::<PathBuf, _, _>::delete(archive, key),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, batch: WriteBatch<&'a [u8], Vec<u8>>) -> StorageResult<()> {
+        let deleted: Vec<&'a [u8]> = batch
+            .iter()
+            .filter_map(|op| match op {
+                WriteOp::Delete(key) => Some(*key),
+                WriteOp::Put(..) => None,
+            })
+            .collect();
+        self.storage.write(batch)?;
+        if let Some(archive) = &mut self.archive {
+            for key in deleted {
+                Storage::<PathBuf, _, _>::delete(archive, key)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Entries of the wrapped storage and of the archive, sorted by key. Archived entries are
+    /// read eagerly.
+    fn prefix_iterator<'s>(
+        &'s self,
+        prefix: &'a [u8],
+    ) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        let mut entries: BTreeMap<Vec<u8>, Vec<u8>> = match &self.archive {
+            Some(archive) => Storage::<PathBuf, _, _>::prefix_iterator(archive, prefix)?.collect(),
+            None => BTreeMap::new(),
+        };
+        if entries.is_empty() {
+            return self.storage.prefix_iterator(prefix);
+        }
+        // The entries of the wrapped storage take precedence
+        entries.extend(self.storage.prefix_iterator(prefix)?);
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn flush(&mut self) -> StorageResult<()> {
+        self.storage.flush()
+    }
+}
+
+impl<C: Debug, S> ArchiveStorage<C, S> {
+    fn get_archived(&self, key: &[u8]) -> StorageResult<Option<Vec<u8>>> {
+        match &self.archive {
+            Some(archive) => Storage::<PathBuf, _, _>::get(archive, key),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Storage wrapper that keeps the most recently read values of another storage backend in memory,
+//! so hot entries (e.g.: recent blocks) do not hit the wrapped storage on every read.
+//!
+//! The cache is bounded by the total size of the cached keys and values, and the least recently
+//! used entries are evicted first. Writes go to the wrapped storage and invalidate the cached
+//! value of the keys they modify.
+use crate::error::StorageResult;
+use crate::storage::{Storage, StorageIterator, ValueView, WriteBatch, WriteOp};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Storage wrapper caching the values read from the storage `S`, whose connection data is `C`
+pub struct CachedStorage<C, S> {
+    storage: S,
+    cache: RefCell<LruCache>,
+    conn_data: PhantomData<C>,
+}
+
+impl<C: Debug, S> CachedStorage<C, S> {
+    /// Wrap an already opened storage, caching up to `capacity` bytes of keys and values
+    pub fn wrap(storage: S, capacity: usize) -> Self {
+        CachedStorage {
+            storage,
+            cache: RefCell::new(LruCache::new(capacity)),
+            conn_data: PhantomData,
+        }
+    }
+
+    /// The wrapped storage
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Mutable reference to the wrapped storage. Values written through it are not invalidated
+    /// in the cache.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Usage statistics of the cache
+    pub fn stats(&self) -> CacheStats {
+        self.cache.borrow().stats()
+    }
+}
+
+/// Implement the Storage generic trait for the wrapper
+impl<'a, C: Debug, S> Storage<C, &'a [u8], Vec<u8>> for CachedStorage<C, S>
+where
+    S: Storage<C, &'a [u8], Vec<u8>>,
+{
+    /// The cache of a new storage is disabled, use `CachedStorage::wrap` to set its capacity
+    #[allow(clippy::new_ret_no_self)]
+    fn new(conn_data: C) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        let storage = S::new(conn_data)?;
+
+        Ok(Box::new(Self::wrap(*storage, 0)))
+    }
+
+    fn put(&mut self, key: &'a [u8], value: Vec<u8>) -> StorageResult<()> {
+        self.cache.get_mut().remove(key);
+        self.storage.put(key, value)
+    }
+
+    fn get(&self, key: &'a [u8]) -> StorageResult<Option<Vec<u8>>> {
+        Ok(self.get_cached(key)?.map(|value| value.to_vec()))
+    }
+
+    fn get_view<'s>(&'s self, key: &'a [u8]) -> StorageResult<Option<ValueView<'s>>> {
+        Ok(self
+            .get_cached(key)?
+            .map(|value| Box::new(value) as ValueView<'s>))
+    }
+
+    fn delete(&mut self, key: &'a [u8]) -> StorageResult<()> {
+        self.cache.get_mut().remove(key);
+        self.storage.delete(key)
+    }
+
+    fn write(&mut self, batch: WriteBatch<&'a [u8], Vec<u8>>) -> StorageResult<()> {
+        let cache = self.cache.get_mut();
+        for op in batch.iter() {
+            match op {
+                WriteOp::Put(key, _) | WriteOp::Delete(key) => cache.remove(key),
+            }
+        }
+
+        self.storage.write(batch)
+    }
+
+    fn prefix_iterator<'s>(
+        &'s self,
+        prefix: &'a [u8],
+    ) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        self.storage.prefix_iterator(prefix)
+    }
+
+    fn flush(&mut self) -> StorageResult<()> {
+        self.storage.flush()
+    }
+}
+
+impl<'a, C: Debug, S> CachedStorage<C, S>
+where
+    S: Storage<C, &'a [u8], Vec<u8>>,
+{
+    /// Read a value from the cache, or from the wrapped storage if it is not cached
+    fn get_cached(&self, key: &'a [u8]) -> StorageResult<Option<Arc<[u8]>>> {
+        if let Some(value) = self.cache.borrow_mut().get(key) {
+            return Ok(Some(value));
+        }
+
+        let value = self
+            .storage
+            .get_view(key)?
+            .map(|value| Arc::<[u8]>::from(&value[..]));
+        if let Some(value) = &value {
+            self.cache.borrow_mut().insert(key, value.clone());
+        }
+
+        Ok(value)
+    }
+}
+
+/// Usage statistics of a cache
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    /// Reads served from the cache
+    pub hits: u64,
+    /// Reads which had to go to the wrapped storage
+    pub misses: u64,
+    /// Number of cached entries
+    pub entries: usize,
+    /// Total size of the cached keys and values, in bytes
+    pub size: usize,
+    /// Maximum size of the cached keys and values, in bytes
+    pub capacity: usize,
+}
+
+impl CacheStats {
+    /// Fraction of the reads served from the cache, between `0` and `1`
+    pub fn hit_rate(&self) -> f64 {
+        let reads = self.hits + self.misses;
+        if reads == 0 {
+            0.0
+        } else {
+            self.hits as f64 / reads as f64
+        }
+    }
+}
+
+/// Cache bounded by the size of its entries, evicting the least recently used ones
+struct LruCache {
+    /// Cached values, with the tick of their last use
+    entries: HashMap<Vec<u8>, (Arc<[u8]>, u64)>,
+    /// Keys sorted by the tick of their last use
+    recency: BTreeMap<u64, Vec<u8>>,
+    tick: u64,
+    size: usize,
+    capacity: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> Self {
+        LruCache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            size: 0,
+            capacity,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, key: &[u8]) -> Option<Arc<[u8]>> {
+        self.tick += 1;
+        let tick = self.tick;
+        match self.entries.get_mut(key) {
+            Some((value, last_use)) => {
+                self.hits += 1;
+                let key = self
+                    .recency
+                    .remove(last_use)
+                    .unwrap_or_else(|| key.to_vec());
+                self.recency.insert(tick, key);
+                *last_use = tick;
+
+                Some(value.clone())
+            }
+            None => {
+                self.misses += 1;
+
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: &[u8], value: Arc<[u8]>) {
+        let entry_size = key.len() + value.len();
+        if entry_size > self.capacity {
+            return;
+        }
+        self.remove(key);
+        while self.size + entry_size > self.capacity {
+            let oldest = match self.recency.keys().next() {
+                Some(tick) => *tick,
+                None => break,
+            };
+            if let Some(key) = self.recency.remove(&oldest) {
+                self.remove(&key);
+            }
+        }
+
+        self.tick += 1;
+        self.size += entry_size;
+        self.recency.insert(self.tick, key.to_vec());
+        self.entries.insert(key.to_vec(), (value, self.tick));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        if let Some((value, last_use)) = self.entries.remove(key) {
+            self.recency.remove(&last_use);
+            self.size -= key.len() + value.len();
+        }
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            size: self.size,
+            capacity: self.capacity,
+        }
+    }
+}
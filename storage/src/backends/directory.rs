@@ -0,0 +1,136 @@
+//! Storage backend that keeps every entry in its own file inside a directory.
+//!
+//! It is meant for data which is rarely read, such as archived blocks (see `archive`), and can be
+//! used with any filesystem, including network mounts. File names are the hex-encoded keys, and
+//! values are written to a temporary file which is then renamed, so readers never see partially
+//! written values. Batches of writes are not atomic.
+use crate::error::{StorageError, StorageErrorKind, StorageResult};
+use crate::storage::{Storage, StorageIterator};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use witnet_util::error::WitnetError;
+
+/// Suffix of the files being written
+const TMP_SUFFIX: &str = ".tmp";
+
+/// Data structure for the directory storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryStorage {
+    path: PathBuf,
+}
+
+impl DirectoryStorage {
+    /// Open the storage in `path`, creating the directory if it does not exist
+    pub fn open<P: AsRef<Path>>(path: P) -> StorageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        fs::create_dir_all(&path)
+            .map_err(|e| directory_error(StorageErrorKind::Connection, &path, &e))?;
+
+        Ok(DirectoryStorage { path })
+    }
+
+    /// Directory containing the files of the entries
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn entry_path(&self, key: &[u8]) -> PathBuf {
+        self.path.join(encode_key(key))
+    }
+}
+
+/// Implement the Storage generic trait for the DirectoryStorage storage data structure.
+impl<'a> Storage<PathBuf, &'a [u8], Vec<u8>> for DirectoryStorage {
+    #[allow(clippy::new_ret_no_self)]
+    fn new(path: PathBuf) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        Self::open(path).map(Box::new)
+    }
+
+    fn put(&mut self, key: &[u8], value: Vec<u8>) -> StorageResult<()> {
+        let path = self.entry_path(key);
+        let tmp_path = path.with_file_name(format!("{}{}", encode_key(key), TMP_SUFFIX));
+
+        fs::write(&tmp_path, value)
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|e| directory_error(StorageErrorKind::Put, &path, &e))
+    }
+
+    fn get(&self, key: &[u8]) -> StorageResult<Option<Vec<u8>>> {
+        let path = self.entry_path(key);
+        match fs::read(&path) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(directory_error(StorageErrorKind::Get, &path, &e)),
+        }
+    }
+
+    fn delete(&mut self, key: &[u8]) -> StorageResult<()> {
+        let path = self.entry_path(key);
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(directory_error(StorageErrorKind::Delete, &path, &e)),
+        }
+    }
+
+    fn prefix_iterator<'s>(&'s self, prefix: &[u8]) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        let read_error = |e: &io::Error| directory_error(StorageErrorKind::Get, &self.path, e);
+        let encoded_prefix = encode_key(prefix);
+
+        let mut keys = vec![];
+        for entry in fs::read_dir(&self.path).map_err(|e| read_error(&e))? {
+            let name = entry.map_err(|e| read_error(&e))?.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with(&encoded_prefix) || name.ends_with(TMP_SUFFIX) {
+                continue;
+            }
+            if let Some(key) = decode_key(&name) {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            // Entries deleted while iterating are skipped
+            if let Some(value) = self.get(&key)? {
+                entries.push((key, value));
+            }
+        }
+
+        Ok(Box::new(entries.into_iter()))
+    }
+}
+
+/// Hex encoding of a key, used as file name
+fn encode_key(key: &[u8]) -> String {
+    key.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Key encoded in a file name, if it is valid hex
+fn decode_key(name: &str) -> Option<Vec<u8>> {
+    if name.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..name.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(name.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn directory_error(
+    kind: StorageErrorKind,
+    path: &Path,
+    e: &io::Error,
+) -> WitnetError<StorageError> {
+    WitnetError::from(StorageError::new(
+        kind,
+        path.to_string_lossy().to_string(),
+        e.to_string(),
+    ))
+}
@@ -0,0 +1,172 @@
+//! Storage wrapper that encrypts the values before writing them to another storage backend, and
+//! decrypts them on read. Keys are stored in plain, so prefix iteration keeps working.
+//!
+//! The encryption key is derived from a secret (a passphrase or the contents of a key file) and a
+//! random salt, which is generated the first time the storage is opened and kept in the wrapped
+//! storage together with an encrypted check value. The check value allows refusing to open the
+//! storage with a wrong secret, instead of failing on the first read.
+use crate::error::{StorageError, StorageErrorKind, StorageResult};
+use crate::storage::{Storage, StorageIterator, WriteBatch, WriteOp};
+use log::error;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use witnet_crypto::cipher::Cipher;
+use witnet_util::error::WitnetError;
+
+/// Prefix of the keys used by the wrapper itself, which are skipped when iterating
+pub const RESERVED_PREFIX: &[u8] = b"__encryption";
+
+/// Key of the salt used to derive the encryption key
+pub const SALT_KEY: &[u8] = b"__encryption_salt";
+
+/// Key of the value used to check the encryption key
+pub const CHECK_KEY: &[u8] = b"__encryption_check";
+
+/// Plain value stored encrypted under `CHECK_KEY`
+const CHECK_VALUE: &[u8] = b"witnet";
+
+/// Storage wrapper encrypting the values written to the storage `S`, whose connection data is `C`
+pub struct EncryptedStorage<C, S> {
+    storage: S,
+    cipher: Cipher,
+    conn_data: PhantomData<C>,
+}
+
+impl<C: Debug, S> EncryptedStorage<C, S> {
+    /// Wrap an already opened storage, deriving the encryption key from `secret`.
+    ///
+    /// Fails if the secret does not match the one used when the storage was first encrypted, or if
+    /// the storage already contains unencrypted data.
+    pub fn wrap<'a>(mut storage: S, secret: &[u8]) -> StorageResult<Self>
+    where
+        S: Storage<C, &'a [u8], Vec<u8>>,
+    {
+        let cipher = match storage.get(SALT_KEY)? {
+            Some(salt) => {
+                let cipher = Cipher::from_secret(secret, &salt);
+                let check = storage.get(CHECK_KEY)?.unwrap_or_default();
+                if cipher.decrypt(&check).ok().as_ref().map(Vec::as_slice) != Some(CHECK_VALUE) {
+                    return Err(connection_error(
+                        "The encryption passphrase or key file does not match the one used \
+                         to encrypt the storage",
+                    ));
+                }
+
+                cipher
+            }
+            None => {
+                if storage.prefix_iterator(&[])?.next().is_some() {
+                    return Err(connection_error(
+                        "The storage contains unencrypted data, it cannot be encrypted",
+                    ));
+                }
+                let salt = Cipher::generate_salt();
+                let cipher = Cipher::from_secret(secret, &salt);
+                let mut batch = WriteBatch::new();
+                batch.put(SALT_KEY, salt.to_vec());
+                batch.put(CHECK_KEY, cipher.encrypt(CHECK_VALUE));
+                storage.write(batch)?;
+
+                cipher
+            }
+        };
+
+        Ok(EncryptedStorage {
+            storage,
+            cipher,
+            conn_data: PhantomData,
+        })
+    }
+
+    /// The wrapped storage, which holds the encrypted values
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Mutable reference to the wrapped storage. Values written through it are not encrypted.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+}
+
+/// Implement the Storage generic trait for the wrapper. The connection data is the one of the
+/// wrapped storage and the secret from which the encryption key is derived.
+impl<'a, C: Debug, S> Storage<(C, Vec<u8>), &'a [u8], Vec<u8>> for EncryptedStorage<C, S>
+where
+    S: Storage<C, &'a [u8], Vec<u8>>,
+{
+    #[allow(clippy::new_ret_no_self)]
+    fn new((conn_data, secret): (C, Vec<u8>)) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        let storage = S::new(conn_data)?;
+
+        Self::wrap(*storage, &secret).map(Box::new)
+    }
+
+    fn put(&mut self, key: &'a [u8], value: Vec<u8>) -> StorageResult<()> {
+        self.storage.put(key, self.cipher.encrypt(&value))
+    }
+
+    fn get(&self, key: &'a [u8]) -> StorageResult<Option<Vec<u8>>> {
+        match self.storage.get_view(key)? {
+            Some(value) => self.cipher.decrypt(&value).map(Some).map_err(|e| {
+                WitnetError::from(StorageError::new(
+                    StorageErrorKind::Decode,
+                    format!("Key: {:?}", key),
+                    e.to_string(),
+                ))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn delete(&mut self, key: &'a [u8]) -> StorageResult<()> {
+        self.storage.delete(key)
+    }
+
+    fn write(&mut self, batch: WriteBatch<&'a [u8], Vec<u8>>) -> StorageResult<()> {
+        let mut encrypted = WriteBatch::new();
+        for op in batch {
+            match op {
+                WriteOp::Put(key, value) => encrypted.put(key, self.cipher.encrypt(&value)),
+                WriteOp::Delete(key) => encrypted.delete(key),
+            }
+        }
+
+        self.storage.write(encrypted)
+    }
+
+    fn prefix_iterator<'s>(
+        &'s self,
+        prefix: &'a [u8],
+    ) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        let cipher = &self.cipher;
+        let iterator = self
+            .storage
+            .prefix_iterator(prefix)?
+            .filter(|(key, _)| !key.starts_with(RESERVED_PREFIX))
+            .filter_map(move |(key, value)| match cipher.decrypt(&value) {
+                Ok(value) => Some((key, value)),
+                Err(e) => {
+                    error!("Skipping entry with key {:?}: {}", key, e);
+                    None
+                }
+            });
+
+        Ok(Box::new(iterator))
+    }
+
+    fn flush(&mut self) -> StorageResult<()> {
+        self.storage.flush()
+    }
+}
+
+fn connection_error(msg: &str) -> WitnetError<StorageError> {
+    WitnetError::from(StorageError::new(
+        StorageErrorKind::Connection,
+        "Encryption".to_string(),
+        msg.to_string(),
+    ))
+}
@@ -3,7 +3,7 @@
 //! Please note that this backend lacks persistence. Data is preserved only for the lifetime of
 //! references to the storage object.
 use crate::error::StorageResult;
-use crate::storage::Storage;
+use crate::storage::{Storage, StorageIterator, ValueView};
 use std::collections::HashMap;
 
 /// Data structure for the in-memory storage.
@@ -17,7 +17,10 @@ pub struct InMemoryStorage<'a> {
 /// Implement the Storage generic trait for the InMemoryStorage storage data structure.
 impl<'a> Storage<(), &'a [u8], Vec<u8>> for InMemoryStorage<'a> {
     #[allow(clippy::new_ret_no_self)]
-    fn new(_: ()) -> StorageResult<Box<Self>> {
+    fn new(_: ()) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
         Ok(Box::new(InMemoryStorage {
             memory: HashMap::new(),
         }))
@@ -32,8 +35,27 @@ impl<'a> Storage<(), &'a [u8], Vec<u8>> for InMemoryStorage<'a> {
         Ok(self.memory.get(key).map(|value| value.to_owned()))
     }
 
+    fn get_view<'s>(&'s self, key: &[u8]) -> StorageResult<Option<ValueView<'s>>> {
+        Ok(self
+            .memory
+            .get(key)
+            .map(|value| Box::new(value.as_slice()) as ValueView<'s>))
+    }
+
     fn delete(&mut self, key: &[u8]) -> StorageResult<()> {
         self.memory.remove(key);
         Ok(())
     }
+
+    fn prefix_iterator<'s>(&'s self, prefix: &[u8]) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        let mut entries: Vec<(Vec<u8>, Vec<u8>)> = self
+            .memory
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.to_vec(), value.to_owned()))
+            .collect();
+        entries.sort();
+
+        Ok(Box::new(entries.into_iter()))
+    }
 }
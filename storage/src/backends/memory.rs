@@ -0,0 +1,59 @@
+//! Storage backend that keeps data in a heap-allocated BTreeMap with owned keys.
+//!
+//! Unlike `InMemoryStorage`, keys do not need to outlive the storage, so this backend can be used
+//! by a node instead of a database, e.g.: for integration tests and ephemeral regtest nodes that
+//! should not touch the disk. Data is lost when the storage is dropped.
+use crate::error::StorageResult;
+use crate::storage::{Storage, StorageIterator, ValueView};
+use std::collections::BTreeMap;
+
+/// Data structure for the memory storage.
+/// Only member is a BTreeMap, which keeps the keys sorted for prefix iteration.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct MemoryStorage {
+    /// A BTreeMap holding the entries sorted by key
+    pub memory: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+/// Implement the Storage generic trait for the MemoryStorage storage data structure.
+impl<'a> Storage<(), &'a [u8], Vec<u8>> for MemoryStorage {
+    #[allow(clippy::new_ret_no_self)]
+    fn new(_: ()) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        Ok(Box::new(MemoryStorage::default()))
+    }
+
+    fn put(&mut self, key: &[u8], value: Vec<u8>) -> StorageResult<()> {
+        self.memory.insert(key.to_vec(), value);
+        Ok(())
+    }
+
+    fn get(&self, key: &[u8]) -> StorageResult<Option<Vec<u8>>> {
+        Ok(self.memory.get(key).cloned())
+    }
+
+    fn get_view<'s>(&'s self, key: &[u8]) -> StorageResult<Option<ValueView<'s>>> {
+        Ok(self
+            .memory
+            .get(key)
+            .map(|value| Box::new(value.as_slice()) as ValueView<'s>))
+    }
+
+    fn delete(&mut self, key: &[u8]) -> StorageResult<()> {
+        self.memory.remove(key);
+        Ok(())
+    }
+
+    fn prefix_iterator<'s>(&'s self, prefix: &[u8]) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        let owned_prefix = prefix.to_vec();
+        let iterator = self
+            .memory
+            .range(owned_prefix.clone()..)
+            .take_while(move |(key, _)| key.starts_with(&owned_prefix))
+            .map(|(key, value)| (key.clone(), value.clone()));
+
+        Ok(Box::new(iterator))
+    }
+}
@@ -0,0 +1,85 @@
+//! Storage wrapper that refuses every write to another storage backend, so an existing storage can
+//! be read without modifying it.
+use crate::error::{StorageError, StorageErrorKind, StorageResult};
+use crate::storage::{Storage, StorageIterator, ValueView, WriteBatch};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use witnet_util::error::WitnetError;
+
+/// Storage wrapper refusing the writes to the storage `S`, whose connection data is `C`
+pub struct ReadOnlyStorage<C, S> {
+    storage: S,
+    conn_data: PhantomData<C>,
+}
+
+impl<C: Debug, S> ReadOnlyStorage<C, S> {
+    /// Wrap an already opened storage
+    pub fn wrap(storage: S) -> Self {
+        ReadOnlyStorage {
+            storage,
+            conn_data: PhantomData,
+        }
+    }
+
+    /// The wrapped storage
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+}
+
+/// Implement the Storage generic trait for the wrapper. Reads are forwarded to the wrapped storage
+/// and writes fail with a `ReadOnly` error.
+impl<'a, C: Debug, S> Storage<C, &'a [u8], Vec<u8>> for ReadOnlyStorage<C, S>
+where
+    S: Storage<C, &'a [u8], Vec<u8>>,
+{
+    #[allow(clippy::new_ret_no_self)]
+    fn new(conn_data: C) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        let storage = S::new(conn_data)?;
+
+        Ok(Box::new(Self::wrap(*storage)))
+    }
+
+    fn put(&mut self, key: &'a [u8], _value: Vec<u8>) -> StorageResult<()> {
+        Err(read_only_error(key))
+    }
+
+    fn get(&self, key: &'a [u8]) -> StorageResult<Option<Vec<u8>>> {
+        self.storage.get(key)
+    }
+
+    fn get_view<'s>(&'s self, key: &'a [u8]) -> StorageResult<Option<ValueView<'s>>> {
+        self.storage.get_view(key)
+    }
+
+    fn delete(&mut self, key: &'a [u8]) -> StorageResult<()> {
+        Err(read_only_error(key))
+    }
+
+    fn write(&mut self, _batch: WriteBatch<&'a [u8], Vec<u8>>) -> StorageResult<()> {
+        Err(read_only_error(b"batch"))
+    }
+
+    fn prefix_iterator<'s>(
+        &'s self,
+        prefix: &'a [u8],
+    ) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        self.storage.prefix_iterator(prefix)
+    }
+
+    /// Nothing is written, so there is nothing to flush
+    fn flush(&mut self) -> StorageResult<()> {
+        Ok(())
+    }
+}
+
+fn read_only_error(key: &[u8]) -> WitnetError<StorageError> {
+    WitnetError::from(StorageError::new(
+        StorageErrorKind::ReadOnly,
+        String::from_utf8_lossy(key).to_string(),
+        "The storage is read-only".to_string(),
+    ))
+}
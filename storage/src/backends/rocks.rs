@@ -2,74 +2,508 @@
 
 use crate::error::{StorageError, StorageErrorKind, StorageResult};
 
-use crate::storage::Storage;
-use rocksdb::DB;
-
-use std::str;
+use crate::storage::{Storage, StorageIterator, ValueView, WriteBatch, WriteOp};
+use rocksdb::{
+    BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, DBCompactionStyle, DBCompressionType,
+    DBIterator, Direction, IteratorMode, Options, WriteOptions, DB,
+};
 
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use witnet_util::error::WitnetError;
 
-/// Data structure for the RocksDB storage whose only member is a rocksdb::DB object.
+/// Data structure for the RocksDB storage: a rocksdb::DB object, the key prefixes routed to each
+/// of its column families and how the writes are persisted.
 pub struct RocksStorage {
     db: DB,
+    column_families: Vec<(Vec<u8>, String)>,
+    sync_writes: bool,
+    wal: bool,
+}
+
+/// Compression algorithms that can be used for the database files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// No compression
+    None,
+    /// Snappy
+    Snappy,
+    /// Zlib
+    Zlib,
+    /// Bzip2
+    Bz2,
+    /// LZ4
+    Lz4,
+    /// LZ4 with high compression
+    Lz4hc,
+    /// Zstandard
+    Zstd,
+}
+
+/// Strategies that can be used to compact the database files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompactionStyle {
+    /// Leveled compaction
+    Level,
+    /// Universal compaction
+    Universal,
+    /// FIFO compaction
+    Fifo,
+}
+
+/// Tuning options used when opening a RocksDB database.
+/// The default values are the ones used by RocksDB.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RocksOptions {
+    /// Size of the block cache, in bytes
+    pub block_cache_size: usize,
+    /// Size of the memory buffer used for writes, in bytes
+    pub write_buffer_size: usize,
+    /// Maximum number of open files (`-1` means no limit)
+    pub max_open_files: i32,
+    /// Compression algorithm
+    pub compression: Compression,
+    /// Compaction style
+    pub compaction_style: CompactionStyle,
+    /// Column families in which the keys starting with a given prefix are stored, instead of the
+    /// default one
+    pub column_families: Vec<ColumnFamilyOptions>,
+    /// Open an existing database in the read-only mode of RocksDB, without modifying it: neither
+    /// the database nor its column families are created, and the database lock is not taken, so
+    /// another process may be writing to it.
+    /// The database is seen as it was when it was opened.
+    pub read_only: bool,
+    /// Wait for every write to reach the disk before returning
+    pub sync_writes: bool,
+    /// Write the changes to the write-ahead log before applying them, so they are not lost if the
+    /// process crashes before they are flushed
+    pub wal: bool,
+}
+
+/// A column family storing the keys starting with `prefix`, with its own tuning
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnFamilyOptions {
+    /// Name of the column family
+    pub name: String,
+    /// Prefix of the keys stored in the column family
+    pub prefix: Vec<u8>,
+    /// Size of the memory buffer used for writes, in bytes
+    pub write_buffer_size: usize,
+    /// Compression algorithm
+    pub compression: Compression,
+    /// Compaction style
+    pub compaction_style: CompactionStyle,
+}
+
+impl Default for RocksOptions {
+    fn default() -> Self {
+        RocksOptions {
+            block_cache_size: 8 * 1024 * 1024,
+            write_buffer_size: 64 * 1024 * 1024,
+            max_open_files: -1,
+            compression: Compression::Snappy,
+            compaction_style: CompactionStyle::Level,
+            column_families: vec![],
+            read_only: false,
+            sync_writes: false,
+            wal: true,
+        }
+    }
+}
+
+impl RocksOptions {
+    /// Convert to the options of the RocksDB library
+    fn to_rocksdb(&self) -> Options {
+        let mut options = table_options(
+            self.block_cache_size,
+            self.write_buffer_size,
+            self.compression,
+            self.compaction_style,
+        );
+        options.create_if_missing(!self.read_only);
+        options.create_missing_column_families(!self.read_only);
+        options.set_max_open_files(self.max_open_files);
+
+        options
+    }
+
+    /// A column family with the tuning of the whole database, which can then be changed
+    pub fn column_family(&self, name: &str, prefix: &[u8]) -> ColumnFamilyOptions {
+        ColumnFamilyOptions {
+            name: name.to_string(),
+            prefix: prefix.to_vec(),
+            write_buffer_size: self.write_buffer_size,
+            compression: self.compression,
+            compaction_style: self.compaction_style,
+        }
+    }
+}
+
+/// Options of the RocksDB library shared by the database and its column families
+fn table_options(
+    block_cache_size: usize,
+    write_buffer_size: usize,
+    compression: Compression,
+    compaction_style: CompactionStyle,
+) -> Options {
+    let mut block_options = BlockBasedOptions::default();
+    block_options.set_lru_cache(block_cache_size);
+
+    let mut options = Options::default();
+    options.set_block_based_table_factory(&block_options);
+    options.set_write_buffer_size(write_buffer_size);
+    options.set_compression_type(match compression {
+        Compression::None => DBCompressionType::None,
+        Compression::Snappy => DBCompressionType::Snappy,
+        Compression::Zlib => DBCompressionType::Zlib,
+        Compression::Bz2 => DBCompressionType::Bz2,
+        Compression::Lz4 => DBCompressionType::Lz4,
+        Compression::Lz4hc => DBCompressionType::Lz4hc,
+        Compression::Zstd => DBCompressionType::Zstd,
+    });
+    options.set_compaction_style(match compaction_style {
+        CompactionStyle::Level => DBCompactionStyle::Level,
+        CompactionStyle::Universal => DBCompactionStyle::Universal,
+        CompactionStyle::Fifo => DBCompactionStyle::Fifo,
+    });
+
+    options
+}
+
+impl RocksStorage {
+    /// Open the database in `path`, creating it and its column families if they do not exist
+    /// (unless the options are read-only), with the given tuning options.
+    ///
+    /// Keys stored in the default column family that belong to another column family, such as the
+    /// ones written before the column family was configured, are not moved to it: they are only
+    /// found once moved with `move_to_column_families`.
+    pub fn open(path: &str, options: &RocksOptions) -> StorageResult<Self> {
+        if options.read_only {
+            return Self::open_read_only(path, options);
+        }
+
+        let descriptors = options
+            .column_families
+            .iter()
+            .map(|cf| {
+                ColumnFamilyDescriptor::new(
+                    cf.name.as_str(),
+                    table_options(
+                        options.block_cache_size,
+                        cf.write_buffer_size,
+                        cf.compression,
+                        cf.compaction_style,
+                    ),
+                )
+            })
+            .collect();
+        let db = DB::open_cf_descriptors(&options.to_rocksdb(), path, descriptors)
+            .map_err(|e| connection_error(path, &e))?;
+        Ok(RocksStorage::with_db(db, options))
+    }
+
+    /// Open an existing database in the read-only mode of RocksDB, which does not take the lock of
+    /// the database, so it can be read while another process, e.g. a node, writes to it. Only the
+    /// writes flushed before opening it are seen. The column families which do not exist yet are
+    /// not opened, their keys are read from the default column family.
+    fn open_read_only(path: &str, options: &RocksOptions) -> StorageResult<Self> {
+        let db_options = options.to_rocksdb();
+        let existing = DB::list_cf(&db_options, path).map_err(|e| connection_error(path, &e))?;
+        let column_families = options
+            .column_families
+            .iter()
+            .filter(|cf| existing.contains(&cf.name))
+            .map(|cf| cf.name.as_str());
+        let db = DB::open_cf_for_read_only(&db_options, path, column_families, false)
+            .map_err(|e| connection_error(path, &e))?;
+
+        Ok(RocksStorage::with_db(db, options))
+    }
+
+    /// Storage over an opened database, routing the keys to the column families of `options`
+    fn with_db(db: DB, options: &RocksOptions) -> Self {
+        RocksStorage {
+            db,
+            column_families: options
+                .column_families
+                .iter()
+                .map(|cf| (cf.prefix.clone(), cf.name.clone()))
+                .collect(),
+            sync_writes: options.sync_writes,
+            wal: options.wal,
+        }
+    }
+
+    /// Column family in which a key is stored, `None` for the default one
+    fn column_family(&self, key: &[u8]) -> Option<&ColumnFamily> {
+        self.column_families
+            .iter()
+            .find(|(prefix, _)| key.starts_with(prefix))
+            .and_then(|(_, name)| self.db.cf_handle(name))
+    }
+
+    /// Options of every write, according to the durability chosen when opening the database
+    fn write_options(&self) -> WriteOptions {
+        let mut options = WriteOptions::default();
+        options.set_sync(self.sync_writes);
+        options.disable_wal(!self.wal);
+
+        options
+    }
+
+    /// Iterate over the keys starting with `prefix` in a column family
+    fn iterate_column_family(
+        &self,
+        column_family: Option<&ColumnFamily>,
+        prefix: &[u8],
+    ) -> DBIterator<'_> {
+        let mode = IteratorMode::From(prefix, Direction::Forward);
+        match column_family {
+            Some(cf) => self.db.iterator_cf(cf, mode),
+            None => self.db.iterator(mode),
+        }
+    }
+
+    /// Move the keys of the default column family to the column families they belong to, writing
+    /// at most `batch_size` entries at a time so the keyspace is never held in memory. Returns the
+    /// number of entries moved.
+    pub fn move_to_column_families(&self, batch_size: usize) -> StorageResult<usize> {
+        let mut moved = 0;
+        for (prefix, name) in &self.column_families {
+            let cf = match self.db.cf_handle(name) {
+                Some(cf) => cf,
+                None => continue,
+            };
+            // Each batch starts after the last key moved, whose entry has already been deleted
+            let mut start = prefix.clone();
+            loop {
+                let mut batch = rocksdb::WriteBatch::default();
+                let mut entries = 0;
+                let iterator = self
+                    .db
+                    .iterator(IteratorMode::From(&start, Direction::Forward))
+                    .take_while(|(key, _)| key.starts_with(prefix))
+                    .take(batch_size);
+                for (key, value) in iterator {
+                    batch.put_cf(cf, &key, &value);
+                    batch.delete(&key);
+                    start = key.to_vec();
+                    entries += 1;
+                }
+                if entries == 0 {
+                    break;
+                }
+
+                self.db
+                    .write_opt(batch, &self.write_options())
+                    .map_err(|e| {
+                        WitnetError::from(StorageError::new(
+                            StorageErrorKind::Write,
+                            name.clone(),
+                            e.to_string(),
+                        ))
+                    })?;
+                moved += entries;
+            }
+        }
+
+        Ok(moved)
+    }
+}
+
+/// Iterator over the entries of several iterators sorted by key, in key order. Only the next entry
+/// of each iterator is kept in memory.
+struct MergeByKey<I> {
+    iterators: Vec<I>,
+    /// Next entry of each iterator which is not exhausted, with the index of the iterator
+    heads: BinaryHeap<Reverse<(Vec<u8>, usize, Vec<u8>)>>,
+}
+
+impl<I> MergeByKey<I>
+where
+    I: Iterator<Item = (Vec<u8>, Vec<u8>)>,
+{
+    fn new(mut iterators: Vec<I>) -> Self {
+        let heads = iterators
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, iterator)| {
+                iterator
+                    .next()
+                    .map(|(key, value)| Reverse((key, index, value)))
+            })
+            .collect();
+
+        MergeByKey { iterators, heads }
+    }
+}
+
+impl<I> Iterator for MergeByKey<I>
+where
+    I: Iterator<Item = (Vec<u8>, Vec<u8>)>,
+{
+    type Item = (Vec<u8>, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((key, index, value)) = self.heads.pop()?;
+        if let Some((next_key, next_value)) = self.iterators[index].next() {
+            self.heads.push(Reverse((next_key, index, next_value)));
+        }
+
+        Some((key, value))
+    }
+}
+
+fn connection_error(path: &str, e: &rocksdb::Error) -> WitnetError<StorageError> {
+    WitnetError::from(StorageError::new(
+        StorageErrorKind::Connection,
+        path.to_string(),
+        e.to_string(),
+    ))
 }
 
 /// Implement the Storage generic trait for the RocksStorage storage data structure.
 impl<'a> Storage<String, &'a [u8], Vec<u8>> for RocksStorage {
     #[allow(clippy::new_ret_no_self)]
-    fn new(path: String) -> StorageResult<Box<Self>> {
-        match DB::open_default(&path) {
-            Ok(db) => {
-                let storage = RocksStorage { db };
-                Ok(Box::new(storage))
-            }
+    fn new(path: String) -> StorageResult<Box<Self>>
+    where
+        Self: Sized,
+    {
+        RocksStorage::open(&path, &RocksOptions::default()).map(Box::new)
+    }
+
+    fn put(&mut self, key: &[u8], value: Vec<u8>) -> StorageResult<()> {
+        let options = self.write_options();
+        let result = match self.column_family(key) {
+            Some(cf) => self.db.put_cf_opt(cf, key, value.as_slice(), &options),
+            None => self.db.put_opt(key, value.as_slice(), &options),
+        };
+        match result {
+            Ok(_) => Ok(()),
             Err(e) => Err(WitnetError::from(StorageError::new(
-                StorageErrorKind::Connection,
-                path,
+                StorageErrorKind::Put,
+                String::from_utf8_lossy(key).to_string(),
                 e.to_string(),
             ))),
         }
     }
 
-    fn put(&mut self, key: &[u8], value: Vec<u8>) -> StorageResult<()> {
-        match self.db.put(key, value.as_slice()) {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                let key = str::from_utf8(key).unwrap();
-                Err(WitnetError::from(StorageError::new(
-                    StorageErrorKind::Put,
-                    key.to_string(),
-                    e.to_string(),
-                )))
-            }
+    fn get(&self, key: &[u8]) -> StorageResult<Option<Vec<u8>>> {
+        let result = match self.column_family(key) {
+            Some(cf) => self.db.get_cf(cf, key),
+            None => self.db.get(key),
+        };
+        match result {
+            Ok(option) => Ok(option),
+            Err(e) => Err(WitnetError::from(StorageError::new(
+                StorageErrorKind::Get,
+                String::from_utf8_lossy(key).to_string(),
+                e.to_string(),
+            ))),
         }
     }
 
-    fn get(&self, key: &[u8]) -> StorageResult<Option<Vec<u8>>> {
-        match self.db.get(key) {
-            Ok(option) => Ok(option.map(|value| value.to_vec())),
-            Err(e) => {
-                let key = str::from_utf8(key).unwrap();
-                Err(WitnetError::from(StorageError::new(
-                    StorageErrorKind::Get,
-                    key.to_string(),
-                    e.to_string(),
-                )))
-            }
+    fn get_view<'s>(&'s self, key: &[u8]) -> StorageResult<Option<ValueView<'s>>> {
+        let result = match self.column_family(key) {
+            Some(cf) => self.db.get_pinned_cf(cf, key),
+            None => self.db.get_pinned(key),
+        };
+        match result {
+            // The value stays in the buffer allocated by RocksDB
+            Ok(option) => Ok(option.map(|value| Box::new(value) as ValueView<'s>)),
+            Err(e) => Err(WitnetError::from(StorageError::new(
+                StorageErrorKind::Get,
+                String::from_utf8_lossy(key).to_string(),
+                e.to_string(),
+            ))),
         }
     }
 
     fn delete(&mut self, key: &[u8]) -> StorageResult<()> {
-        match self.db.delete(key) {
+        let options = self.write_options();
+        let result = match self.column_family(key) {
+            Some(cf) => self.db.delete_cf_opt(cf, key, &options),
+            None => self.db.delete_opt(key, &options),
+        };
+        match result {
             Ok(_) => Ok(()),
-            Err(e) => {
-                let key = str::from_utf8(key).unwrap();
-                Err(WitnetError::from(StorageError::new(
-                    StorageErrorKind::Delete,
-                    key.to_string(),
-                    e.to_string(),
-                )))
+            Err(e) => Err(WitnetError::from(StorageError::new(
+                StorageErrorKind::Delete,
+                String::from_utf8_lossy(key).to_string(),
+                e.to_string(),
+            ))),
+        }
+    }
+
+    fn write(&mut self, batch: WriteBatch<&'a [u8], Vec<u8>>) -> StorageResult<()> {
+        let mut rocks_batch = rocksdb::WriteBatch::default();
+        for op in batch {
+            match op {
+                WriteOp::Put(key, value) => match self.column_family(key) {
+                    Some(cf) => rocks_batch.put_cf(cf, key, &value),
+                    None => rocks_batch.put(key, &value),
+                },
+                WriteOp::Delete(key) => match self.column_family(key) {
+                    Some(cf) => rocks_batch.delete_cf(cf, key),
+                    None => rocks_batch.delete(key),
+                },
             }
         }
+
+        self.db
+            .write_opt(rocks_batch, &self.write_options())
+            .map_err(|e| {
+                WitnetError::from(StorageError::new(
+                    StorageErrorKind::Write,
+                    "batch".to_string(),
+                    e.to_string(),
+                ))
+            })
+    }
+
+    fn prefix_iterator<'s>(&'s self, prefix: &[u8]) -> StorageResult<StorageIterator<'s, Vec<u8>>> {
+        // All the keys starting with the prefix are in the same column family, unless the prefix
+        // is shorter than the prefixes of the column families. The column families which were not
+        // opened are skipped, as their keys are still in the default one
+        let column_families: Vec<Option<&ColumnFamily>> = match self.column_family(prefix) {
+            Some(cf) => vec![Some(cf)],
+            None => std::iter::once(None)
+                .chain(
+                    self.column_families
+                        .iter()
+                        .filter(|(cf_prefix, _)| cf_prefix.starts_with(prefix))
+                        .filter_map(|(_, name)| self.db.cf_handle(name).map(Some)),
+                )
+                .collect(),
+        };
+
+        let mut iterators = vec![];
+        for cf in column_families {
+            let owned_prefix = prefix.to_vec();
+            let iterator = self
+                .iterate_column_family(cf, prefix)
+                .take_while(move |(key, _)| key.starts_with(&owned_prefix))
+                .map(|(key, value)| (key.to_vec(), value.to_vec()));
+            iterators.push(iterator);
+        }
+
+        if iterators.len() == 1 {
+            Ok(Box::new(iterators.remove(0)))
+        } else {
+            // Merge the entries of every column family, which are sorted by key
+            Ok(Box::new(MergeByKey::new(iterators)))
+        }
+    }
+
+    /// Flush the memory buffers of the database to its files, so no write depends on the
+    /// write-ahead log, or is lost if it is disabled
+    fn flush(&mut self) -> StorageResult<()> {
+        self.db.flush().map_err(|e| {
+            WitnetError::from(StorageError::new(
+                StorageErrorKind::Write,
+                "flush".to_string(),
+                e.to_string(),
+            ))
+        })
     }
 }
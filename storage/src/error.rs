@@ -34,10 +34,22 @@ pub enum StorageErrorKind {
     Get,
     /// Errors when deleting a key/value pair
     Delete,
+    /// Errors when applying a batch of writes
+    Write,
     /// Errors when converting a value into bytes
     Encode,
     /// Errors when creating a value from bytes
     Decode,
+    /// Errors when exporting or importing a snapshot
+    Snapshot,
+    /// Errors when writing a backup
+    Backup,
+    /// Errors when upgrading the layout of the stored data
+    Migration,
+    /// Errors when writing to a read-only storage
+    ReadOnly,
+    /// Errors when writing non-essential data to a storage which exceeds its maximum size
+    QuotaExceeded,
 }
 
 impl fmt::Display for StorageErrorKind {
@@ -0,0 +1,80 @@
+//! Expiry of transient entries of a storage, such as the addresses of known peers, so stale
+//! entries age out instead of accumulating forever.
+//!
+//! Writing an entry with a time to live (TTL) also writes its expiry time, as a Unix timestamp in
+//! seconds, under the same key prefixed with `EXPIRY_PREFIX`. Expired entries are not hidden from
+//! reads, they are deleted together with their expiry time by `purge_expired`, which should be
+//! called periodically. Writing an entry without a TTL keeps its previous expiry time, if any.
+use crate::error::StorageResult;
+use crate::storage::{Storable, Storage, WriteBatch};
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// Prefix of the keys storing the expiry time of the entries with a TTL
+pub const EXPIRY_PREFIX: &[u8] = b"expiry-";
+
+/// Key storing the expiry time of the entry with key `key`
+pub fn expiry_key(key: &[u8]) -> Vec<u8> {
+    [EXPIRY_PREFIX, key].concat()
+}
+
+/// Create / update an entry which expires `ttl` after `now` (a Unix timestamp in seconds). The
+/// entry and its expiry time are written atomically.
+pub fn put_with_ttl<C, S>(
+    storage: &mut S,
+    key: &[u8],
+    value: Vec<u8>,
+    ttl: Duration,
+    now: i64,
+) -> StorageResult<()>
+where
+    C: Debug,
+    S: for<'k> Storage<C, &'k [u8], Vec<u8>> + ?Sized,
+{
+    let expiry_key = expiry_key(key);
+    let expires_at = now.saturating_add(ttl.as_secs() as i64);
+    let mut batch = WriteBatch::new();
+    batch.put(key, value);
+    batch.put_t(expiry_key.as_slice(), &expires_at)?;
+
+    storage.write(batch)
+}
+
+/// Expiry time of an entry, as a Unix timestamp in seconds, if it was written with a TTL
+pub fn expires_at<C, S>(storage: &S, key: &[u8]) -> StorageResult<Option<i64>>
+where
+    C: Debug,
+    S: for<'k> Storage<C, &'k [u8], Vec<u8>> + ?Sized,
+{
+    match storage.get(&expiry_key(key))? {
+        Some(value) => i64::from_bytes(&value).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Delete the entries which expired at or before `now` (a Unix timestamp in seconds), returning
+/// the number of deleted entries. Entries with an expiry time which cannot be decoded are deleted
+/// too.
+pub fn purge_expired<C, S>(storage: &mut S, now: i64) -> StorageResult<usize>
+where
+    C: Debug,
+    S: for<'k> Storage<C, &'k [u8], Vec<u8>> + ?Sized,
+{
+    let expired: Vec<Vec<u8>> = storage
+        .prefix_iterator(EXPIRY_PREFIX)?
+        .filter(|(_, value)| i64::from_bytes(value).map_or(true, |expires_at| expires_at <= now))
+        .map(|(expiry_key, _)| expiry_key)
+        .collect();
+    if expired.is_empty() {
+        return Ok(0);
+    }
+
+    let mut batch = WriteBatch::new();
+    for expiry_key in &expired {
+        batch.delete(&expiry_key[EXPIRY_PREFIX.len()..]);
+        batch.delete(expiry_key.as_slice());
+    }
+    storage.write(batch)?;
+
+    Ok(expired.len())
+}
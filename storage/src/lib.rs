@@ -9,4 +9,6 @@
 
 pub mod backends;
 pub mod error;
+pub mod expiry;
+pub mod snapshot;
 pub mod storage;
@@ -0,0 +1,251 @@
+//! Snapshots of the whole contents of a storage, used to bootstrap new nodes from the state of a
+//! synchronized one instead of syncing from the genesis block.
+//!
+//! A snapshot is a binary file with the following layout, where integers are big endian:
+//!
+//! * `MAGIC` and the format version (1 byte)
+//! * Every entry, sorted by key: key length (4 bytes), key, value length (4 bytes), value
+//! * `END_MARKER` (4 bytes) and the number of entries (8 bytes)
+//! * The SHA256 checksum of everything above (32 bytes)
+//!
+//! Imported snapshots are fully read and checked before writing anything to the storage.
+use crate::error::{StorageError, StorageErrorKind, StorageResult};
+use crate::storage::{Storage, WriteBatch};
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+use witnet_crypto::hash::Sha256Hasher;
+use witnet_data_structures::chain::Hash;
+use witnet_util::error::WitnetError;
+
+/// Bytes at the start of every snapshot
+pub const MAGIC: &[u8; 8] = b"WITSNAP\0";
+
+/// Version of the snapshot format
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Written instead of a key length after the last entry
+const END_MARKER: u32 = u32::max_value();
+
+/// Summary of an exported or imported snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotSummary {
+    /// Number of entries in the snapshot
+    pub entries: u64,
+    /// Checksum of the snapshot
+    pub checksum: Hash,
+}
+
+/// Write every entry of the storage to a snapshot
+pub fn export<C, S, W>(storage: &S, writer: W) -> StorageResult<SnapshotSummary>
+where
+    C: Debug,
+    S: for<'k> Storage<C, &'k [u8], Vec<u8>> + ?Sized,
+    W: Write,
+{
+    let entries = storage.prefix_iterator(&[])?;
+
+    write_snapshot(entries, writer).map_err(|e| snapshot_error("Cannot write the snapshot", &e))
+}
+
+/// Read a snapshot and write all its entries to an empty storage
+pub fn import<C, S, R>(storage: &mut S, reader: R) -> StorageResult<SnapshotSummary>
+where
+    C: Debug,
+    S: for<'k> Storage<C, &'k [u8], Vec<u8>> + ?Sized,
+    R: Read,
+{
+    if storage.prefix_iterator(&[])?.next().is_some() {
+        return Err(WitnetError::from(StorageError::new(
+            StorageErrorKind::Snapshot,
+            "Import".to_string(),
+            "The storage is not empty, snapshots can only be imported into an empty storage"
+                .to_string(),
+        )));
+    }
+
+    let (keys, values, summary) =
+        read_snapshot(reader).map_err(|e| snapshot_error("Cannot read the snapshot", &e))?;
+    let mut batch = WriteBatch::new();
+    for (key, value) in keys.iter().zip(values) {
+        batch.put(key.as_slice(), value);
+    }
+    storage.write(batch)?;
+
+    Ok(summary)
+}
+
+fn write_snapshot<W: Write>(
+    entries: impl Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    writer: W,
+) -> io::Result<SnapshotSummary> {
+    let mut writer = HashingWriter {
+        inner: writer,
+        hasher: Sha256Hasher::new(),
+    };
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[FORMAT_VERSION])?;
+
+    let mut count: u64 = 0;
+    for (key, value) in entries {
+        write_chunk(&mut writer, &key)?;
+        write_chunk(&mut writer, &value)?;
+        count += 1;
+    }
+    writer.write_all(&END_MARKER.to_be_bytes())?;
+    writer.write_all(&count.to_be_bytes())?;
+
+    let HashingWriter { mut inner, hasher } = writer;
+    let checksum = hasher.finish();
+    let Hash::SHA256(checksum_bytes) = checksum;
+    inner.write_all(&checksum_bytes)?;
+    inner.flush()?;
+
+    Ok(SnapshotSummary {
+        entries: count,
+        checksum,
+    })
+}
+
+type Entries = (Vec<Vec<u8>>, Vec<Vec<u8>>, SnapshotSummary);
+
+fn read_snapshot<R: Read>(reader: R) -> io::Result<Entries> {
+    let mut reader = HashingReader {
+        inner: reader,
+        hasher: Sha256Hasher::new(),
+    };
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a Witnet snapshot".to_string()));
+    }
+    let mut version = [0; 1];
+    reader.read_exact(&mut version)?;
+    if version[0] != FORMAT_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported snapshot format version {}",
+            version[0]
+        )));
+    }
+
+    let mut keys = vec![];
+    let mut values = vec![];
+    loop {
+        let key_length = read_u32(&mut reader)?;
+        if key_length == END_MARKER {
+            break;
+        }
+        keys.push(read_bytes(&mut reader, key_length)?);
+        let value_length = read_u32(&mut reader)?;
+        if value_length == END_MARKER {
+            return Err(invalid_data("missing value".to_string()));
+        }
+        values.push(read_bytes(&mut reader, value_length)?);
+    }
+    let mut count = [0; 8];
+    reader.read_exact(&mut count)?;
+    let count = u64::from_be_bytes(count);
+    if count != keys.len() as u64 {
+        return Err(invalid_data(format!(
+            "expected {} entries but found {}",
+            count,
+            keys.len()
+        )));
+    }
+
+    let HashingReader { mut inner, hasher } = reader;
+    let checksum = hasher.finish();
+    let mut expected = [0; 32];
+    inner.read_exact(&mut expected)?;
+    if checksum != Hash::SHA256(expected) {
+        return Err(invalid_data(
+            "checksum mismatch, the snapshot is corrupted".to_string(),
+        ));
+    }
+
+    Ok((
+        keys,
+        values,
+        SnapshotSummary {
+            entries: count,
+            checksum,
+        },
+    ))
+}
+
+fn write_chunk<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    if bytes.len() >= END_MARKER as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "entry too large for a snapshot",
+        ));
+    }
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(bytes)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u32::from_be_bytes(bytes))
+}
+
+fn read_bytes<R: Read>(reader: &mut R, length: u32) -> io::Result<Vec<u8>> {
+    // Do not trust the length to allocate the buffer, the snapshot may be truncated
+    let mut bytes = vec![];
+    reader.take(u64::from(length)).read_to_end(&mut bytes)?;
+    if bytes.len() != length as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the snapshot is truncated",
+        ));
+    }
+
+    Ok(bytes)
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn snapshot_error(info: &str, e: &io::Error) -> WitnetError<StorageError> {
+    WitnetError::from(StorageError::new(
+        StorageErrorKind::Snapshot,
+        info.to_string(),
+        e.to_string(),
+    ))
+}
+
+/// Writer calculating the checksum of the written data
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256Hasher,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader calculating the checksum of the read data
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256Hasher,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+
+        Ok(read)
+    }
+}
@@ -4,16 +4,22 @@
 use crate::error::{StorageError, StorageErrorKind, StorageResult};
 use serde::{de::DeserializeOwned, Serialize};
 use std::fmt::Debug;
+use std::ops::Deref;
 use witnet_util::error::WitnetError;
 
 /// This is a generic trait that exposes a very simple key/value CRUD API for data storage.
 /// This trait can be easily implemented for any specific storage backend solution (databases,
 /// volatile memory, flat files, etc.)
+///
+/// Besides single reads and writes, backends support applying several writes at once (see
+/// `WriteBatch`) and iterating over all the entries whose key starts with a given prefix.
 pub trait Storage<ConnData: Debug, Key, Value> {
     /// Storage object constructor.
     /// `connection_data` can be used for passing credentials, urls, paths, etc. down to the storage
     /// backend.
-    fn new(connection_data: ConnData) -> StorageResult<Box<Self>>;
+    fn new(connection_data: ConnData) -> StorageResult<Box<Self>>
+    where
+        Self: Sized;
 
     /// Create / update entries in the storage, identified by a key.
     fn put(&mut self, key: Key, value: Value) -> StorageResult<()>;
@@ -21,8 +27,123 @@ pub trait Storage<ConnData: Debug, Key, Value> {
     /// Retrieve an entry from the storage, identified by its key.
     fn get(&self, key: Key) -> StorageResult<Option<Value>>;
 
+    /// Retrieve a view of the bytes of an entry, identified by its key.
+    ///
+    /// The default implementation returns the value retrieved by `get`, backends which can lend
+    /// their data should override it to avoid copying the value into an owned vector.
+    fn get_view<'s>(&'s self, key: Key) -> StorageResult<Option<ValueView<'s>>>
+    where
+        Value: Deref<Target = [u8]> + 'static,
+    {
+        Ok(self.get(key)?.map(|value| Box::new(value) as ValueView<'s>))
+    }
+
     /// Delete an entry from the storage, identified by its key.
     fn delete(&mut self, key: Key) -> StorageResult<()>;
+
+    /// Apply all the operations in a batch, in order.
+    ///
+    /// The default implementation applies them one by one, backends supporting atomic writes
+    /// should override it so either all of them or none are applied.
+    fn write(&mut self, batch: WriteBatch<Key, Value>) -> StorageResult<()> {
+        for op in batch {
+            match op {
+                WriteOp::Put(key, value) => self.put(key, value)?,
+                WriteOp::Delete(key) => self.delete(key)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Iterate over all the entries whose key starts with `prefix`, sorted by key.
+    fn prefix_iterator<'s>(&'s self, prefix: Key) -> StorageResult<StorageIterator<'s, Value>>;
+
+    /// Make sure that every write applied so far has reached the disk.
+    ///
+    /// The default implementation does nothing, backends which may buffer writes in memory should
+    /// override it.
+    fn flush(&mut self) -> StorageResult<()> {
+        Ok(())
+    }
+}
+
+/// Bytes of an entry of a storage, which may be borrowed from the storage (see
+/// `Storage::get_view`)
+pub type ValueView<'a> = Box<dyn Deref<Target = [u8]> + 'a>;
+
+/// Iterator over `(key, value)` entries of a storage
+pub type StorageIterator<'a, Value> = Box<dyn Iterator<Item = (Vec<u8>, Value)> + 'a>;
+
+/// A write operation in a `WriteBatch`
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteOp<Key, Value> {
+    /// Create / update an entry
+    Put(Key, Value),
+    /// Delete an entry
+    Delete(Key),
+}
+
+/// A list of write operations to be applied together, see `Storage::write`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteBatch<Key, Value> {
+    ops: Vec<WriteOp<Key, Value>>,
+}
+
+impl<Key, Value> Default for WriteBatch<Key, Value> {
+    fn default() -> Self {
+        WriteBatch { ops: vec![] }
+    }
+}
+
+impl<Key, Value> WriteBatch<Key, Value> {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a put operation to the batch
+    pub fn put(&mut self, key: Key, value: Value) {
+        self.ops.push(WriteOp::Put(key, value));
+    }
+
+    /// Add a delete operation to the batch
+    pub fn delete(&mut self, key: Key) {
+        self.ops.push(WriteOp::Delete(key));
+    }
+
+    /// Number of operations in the batch
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether the batch has no operations
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Iterate over the operations of the batch, in order
+    pub fn iter(&self) -> std::slice::Iter<'_, WriteOp<Key, Value>> {
+        self.ops.iter()
+    }
+}
+
+impl<Key> WriteBatch<Key, Vec<u8>> {
+    /// Add a put operation to the batch, converting the value into bytes
+    pub fn put_t<T: Storable>(&mut self, key: Key, value: &T) -> StorageResult<()> {
+        self.put(key, value.to_bytes()?);
+
+        Ok(())
+    }
+}
+
+impl<Key, Value> IntoIterator for WriteBatch<Key, Value> {
+    type Item = WriteOp<Key, Value>;
+    type IntoIter = std::vec::IntoIter<WriteOp<Key, Value>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.into_iter()
+    }
 }
 
 /// Trait which marks a type as storable.
@@ -108,7 +229,7 @@ pub trait StorageHelper<'a, ConnData: Debug>: Storage<ConnData, &'a [u8], Vec<u8
     }
     /// Get an element from the storage
     fn get_t<T: Storable>(&self, key: &'a [u8]) -> StorageResult<Option<T>> {
-        let value = self.get(key)?;
+        let value = self.get_view(key)?;
         if value.is_none() {
             return Ok(None);
         }
@@ -126,6 +247,6 @@ pub trait StorageHelper<'a, ConnData: Debug>: Storage<ConnData, &'a [u8], Vec<u8
 
 // Implement the above helper trait for all the storage backends that work on raw bytes
 impl<'a, ConnData: Debug, T> StorageHelper<'a, ConnData> for T where
-    T: Storage<ConnData, &'a [u8], Vec<u8>>
+    T: Storage<ConnData, &'a [u8], Vec<u8>> + ?Sized
 {
 }
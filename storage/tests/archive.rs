@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+use witnet_storage::backends::archive::ArchiveStorage;
+use witnet_storage::backends::directory::DirectoryStorage;
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::storage::Storage;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("witnet-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+
+    path
+}
+
+#[test]
+fn archive_storage_offload_read_through() {
+    let path = temp_dir("archive-offload");
+    let archive = DirectoryStorage::open(&path).unwrap();
+    let mut storage = ArchiveStorage::<(), _>::wrap(MemoryStorage::default(), archive);
+
+    storage.put(b"block-1", b"a".to_vec()).unwrap();
+    storage.put(b"block-2", b"b".to_vec()).unwrap();
+    assert!(storage.offload(b"block-1").unwrap());
+    assert!(!storage.offload(b"block-3").unwrap());
+
+    // The entry is no longer in the wrapped storage, but can still be read
+    assert_eq!(storage.inner().get(b"block-1").unwrap(), None);
+    assert_eq!(storage.get(b"block-1").unwrap(), Some(b"a".to_vec()));
+    assert_eq!(
+        storage
+            .get_view(b"block-1")
+            .unwrap()
+            .map(|view| view.to_vec()),
+        Some(b"a".to_vec())
+    );
+
+    let entries: Vec<_> = storage.prefix_iterator(b"block-").unwrap().collect();
+    assert_eq!(
+        entries,
+        vec![
+            (b"block-1".to_vec(), b"a".to_vec()),
+            (b"block-2".to_vec(), b"b".to_vec())
+        ]
+    );
+
+    storage.delete(b"block-1").unwrap();
+    assert_eq!(storage.get(b"block-1").unwrap(), None);
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn archive_storage_without_archive() {
+    let mut storage =
+        *<ArchiveStorage<(), MemoryStorage> as Storage<(), &[u8], Vec<u8>>>::new(()).unwrap();
+
+    storage.put(b"block-1", b"a".to_vec()).unwrap();
+    assert!(!storage.offload(b"block-1").unwrap());
+    assert_eq!(storage.get(b"block-1").unwrap(), Some(b"a".to_vec()));
+}
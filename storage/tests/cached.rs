@@ -0,0 +1,74 @@
+use witnet_storage::backends::cached::CachedStorage;
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::storage::{Storage, WriteBatch};
+
+#[test]
+fn cached_storage_hits() {
+    let mut memory = MemoryStorage::default();
+    memory.put(b"block-1", b"a".to_vec()).unwrap();
+    let storage = CachedStorage::<(), _>::wrap(memory, 1024);
+
+    assert_eq!(storage.get(b"block-1").unwrap(), Some(b"a".to_vec()));
+    assert_eq!(storage.get(b"block-1").unwrap(), Some(b"a".to_vec()));
+    assert_eq!(storage.get(b"block-2").unwrap(), None);
+
+    let stats = storage.stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 2);
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.size, b"block-1".len() + 1);
+}
+
+#[test]
+fn cached_storage_evicts_least_recently_used() {
+    let mut memory = MemoryStorage::default();
+    memory.put(b"a", vec![0; 9]).unwrap();
+    memory.put(b"b", vec![1; 9]).unwrap();
+    memory.put(b"c", vec![2; 9]).unwrap();
+    // Room for two entries
+    let storage = CachedStorage::<(), _>::wrap(memory, 20);
+
+    storage.get(b"a").unwrap();
+    storage.get(b"b").unwrap();
+    storage.get(b"a").unwrap();
+    // Evicts `b`
+    storage.get(b"c").unwrap();
+    assert_eq!(storage.stats().entries, 2);
+
+    let hits = storage.stats().hits;
+    storage.get(b"a").unwrap();
+    assert_eq!(storage.stats().hits, hits + 1);
+    storage.get(b"b").unwrap();
+    assert_eq!(storage.stats().hits, hits + 1);
+}
+
+#[test]
+fn cached_storage_invalidates_writes() {
+    let mut storage = CachedStorage::<(), _>::wrap(MemoryStorage::default(), 1024);
+    storage.put(b"foo", b"bar".to_vec()).unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"bar".to_vec()));
+
+    storage.put(b"foo", b"baz".to_vec()).unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"baz".to_vec()));
+
+    let mut batch = WriteBatch::new();
+    batch.put(&b"foo"[..], b"qux".to_vec());
+    storage.write(batch).unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"qux".to_vec()));
+
+    storage.delete(b"foo").unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), None);
+    assert_eq!(storage.inner().get(b"foo").unwrap(), None);
+}
+
+#[test]
+fn cached_storage_disabled() {
+    let mut memory = MemoryStorage::default();
+    memory.put(b"foo", b"bar".to_vec()).unwrap();
+    let storage = CachedStorage::<(), _>::wrap(memory, 0);
+
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"bar".to_vec()));
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"bar".to_vec()));
+    assert_eq!(storage.stats().hits, 0);
+    assert_eq!(storage.stats().entries, 0);
+}
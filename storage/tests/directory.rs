@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use witnet_storage::backends::directory::DirectoryStorage;
+use witnet_storage::storage::{Storage, WriteBatch};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("witnet-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+
+    path
+}
+
+#[test]
+fn directory_storage_put_get_delete() {
+    let path = temp_dir("directory-put-get");
+    let mut storage = DirectoryStorage::open(&path).unwrap();
+
+    storage.put(b"block-1", b"a".to_vec()).unwrap();
+    assert_eq!(storage.get(b"block-1").unwrap(), Some(b"a".to_vec()));
+    assert_eq!(storage.get(b"block-2").unwrap(), None);
+    // The file name is the hex-encoded key
+    assert!(path.join("626c6f636b2d31").is_file());
+
+    storage.put(b"block-1", b"b".to_vec()).unwrap();
+    assert_eq!(storage.get(b"block-1").unwrap(), Some(b"b".to_vec()));
+
+    storage.delete(b"block-1").unwrap();
+    assert_eq!(storage.get(b"block-1").unwrap(), None);
+    // Deleting a missing entry is not an error
+    storage.delete(b"block-1").unwrap();
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn directory_storage_prefix_iterator() {
+    let path = temp_dir("directory-prefix");
+    let mut storage = DirectoryStorage::open(&path).unwrap();
+
+    let mut batch = WriteBatch::new();
+    batch.put(&[1u8, 0xff][..], b"c".to_vec());
+    batch.put(&[1u8, 0][..], b"a".to_vec());
+    batch.put(&[2u8][..], b"b".to_vec());
+    storage.write(batch).unwrap();
+
+    let entries: Vec<_> = storage.prefix_iterator(&[1]).unwrap().collect();
+    assert_eq!(
+        entries,
+        vec![(vec![1, 0], b"a".to_vec()), (vec![1, 0xff], b"c".to_vec())]
+    );
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
@@ -0,0 +1,63 @@
+use witnet_storage::backends::encrypted::{EncryptedStorage, CHECK_KEY, SALT_KEY};
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::storage::{Storage, WriteBatch};
+
+#[test]
+fn encrypted_storage_crud() {
+    let mut storage =
+        *EncryptedStorage::<(), MemoryStorage>::new(((), b"secret".to_vec())).unwrap();
+
+    storage.put(b"foo", b"bar".to_vec()).unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"bar".to_vec()));
+
+    // The wrapped storage only holds the encrypted value
+    let raw = storage.inner().get(b"foo").unwrap().unwrap();
+    assert!(!raw.windows(3).any(|w| w == b"bar"));
+
+    storage.delete(b"foo").unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), None);
+}
+
+#[test]
+fn encrypted_storage_write_batch_and_prefix_iterator() {
+    let mut storage = EncryptedStorage::<(), _>::wrap(MemoryStorage::default(), b"secret").unwrap();
+
+    let mut batch = WriteBatch::new();
+    batch.put(&b"peer-1"[..], b"a".to_vec());
+    batch.put(&b"peer-2"[..], b"b".to_vec());
+    batch.put(&b"block-1"[..], b"c".to_vec());
+    storage.write(batch).unwrap();
+
+    let peers: Vec<_> = storage.prefix_iterator(b"peer-").unwrap().collect();
+    assert_eq!(
+        peers,
+        vec![
+            (b"peer-1".to_vec(), b"a".to_vec()),
+            (b"peer-2".to_vec(), b"b".to_vec()),
+        ]
+    );
+    // The salt and the check value are not visible
+    assert_eq!(storage.prefix_iterator(b"").unwrap().count(), 3);
+}
+
+#[test]
+fn encrypted_storage_reopen() {
+    let mut storage = EncryptedStorage::<(), _>::wrap(MemoryStorage::default(), b"secret").unwrap();
+    storage.put(b"foo", b"bar".to_vec()).unwrap();
+    let memory = storage.inner().clone();
+    assert!(memory.get(SALT_KEY).unwrap().is_some());
+    assert!(memory.get(CHECK_KEY).unwrap().is_some());
+
+    let reopened = EncryptedStorage::<(), _>::wrap(memory.clone(), b"secret").unwrap();
+    assert_eq!(reopened.get(b"foo").unwrap(), Some(b"bar".to_vec()));
+
+    assert!(EncryptedStorage::<(), _>::wrap(memory, b"wrong").is_err());
+}
+
+#[test]
+fn encrypted_storage_refuses_unencrypted_data() {
+    let mut memory = MemoryStorage::default();
+    memory.put(b"foo", b"bar".to_vec()).unwrap();
+
+    assert!(EncryptedStorage::<(), _>::wrap(memory, b"secret").is_err());
+}
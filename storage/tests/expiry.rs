@@ -0,0 +1,82 @@
+use std::time::Duration;
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::expiry::{expires_at, expiry_key, purge_expired, put_with_ttl};
+use witnet_storage::storage::Storage;
+
+#[test]
+fn expiry_put_with_ttl() {
+    let mut storage = MemoryStorage::default();
+
+    put_with_ttl(
+        &mut storage,
+        b"peers",
+        b"a".to_vec(),
+        Duration::from_secs(60),
+        1000,
+    )
+    .unwrap();
+
+    assert_eq!(storage.get(b"peers").unwrap(), Some(b"a".to_vec()));
+    assert_eq!(expires_at(&storage, b"peers").unwrap(), Some(1060));
+    assert_eq!(expires_at(&storage, b"other").unwrap(), None);
+}
+
+#[test]
+fn expiry_purge_expired() {
+    let mut storage = MemoryStorage::default();
+    storage.put(b"block-1", b"b".to_vec()).unwrap();
+    put_with_ttl(
+        &mut storage,
+        b"peers",
+        b"a".to_vec(),
+        Duration::from_secs(60),
+        1000,
+    )
+    .unwrap();
+    put_with_ttl(
+        &mut storage,
+        b"peer-2",
+        b"c".to_vec(),
+        Duration::from_secs(10),
+        1000,
+    )
+    .unwrap();
+
+    // Nothing has expired yet
+    assert_eq!(purge_expired(&mut storage, 1005).unwrap(), 0);
+
+    // Only `peer-2` has expired
+    assert_eq!(purge_expired(&mut storage, 1010).unwrap(), 1);
+    assert_eq!(storage.get(b"peer-2").unwrap(), None);
+    assert_eq!(storage.get(&expiry_key(b"peer-2")).unwrap(), None);
+    assert_eq!(storage.get(b"peers").unwrap(), Some(b"a".to_vec()));
+
+    // Entries without a TTL never expire
+    assert_eq!(purge_expired(&mut storage, 5000).unwrap(), 1);
+    assert_eq!(storage.get(b"peers").unwrap(), None);
+    assert_eq!(storage.get(b"block-1").unwrap(), Some(b"b".to_vec()));
+}
+
+#[test]
+fn expiry_refreshed_by_put_with_ttl() {
+    let mut storage = MemoryStorage::default();
+    put_with_ttl(
+        &mut storage,
+        b"peers",
+        b"a".to_vec(),
+        Duration::from_secs(60),
+        1000,
+    )
+    .unwrap();
+    put_with_ttl(
+        &mut storage,
+        b"peers",
+        b"b".to_vec(),
+        Duration::from_secs(60),
+        1050,
+    )
+    .unwrap();
+
+    assert_eq!(purge_expired(&mut storage, 1100).unwrap(), 0);
+    assert_eq!(storage.get(b"peers").unwrap(), Some(b"b".to_vec()));
+}
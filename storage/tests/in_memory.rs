@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use witnet_storage::backends::in_memory::InMemoryStorage;
-use witnet_storage::storage::Storage;
+use witnet_storage::storage::{Storage, WriteBatch};
 
 #[test]
 fn storage_instantiation() {
@@ -152,3 +152,50 @@ fn storage_delete_nonexistent() {
     // The value returned by `delete` should be the unit (`()`).
     assert_eq!(value, ());
 }
+
+#[test]
+fn storage_write_batch() {
+    // Recreate an `InMemoryStorage` with data in it.
+    let mut memory: HashMap<&[u8], Vec<u8>> = HashMap::new();
+    memory.insert(b"foo", b"bar".to_vec());
+    let mut storage = InMemoryStorage { memory };
+
+    // Put a new entry and delete the existing one in a single batch.
+    let mut batch = WriteBatch::new();
+    batch.put(&b"beer"[..], b"ale".to_vec());
+    batch.delete(&b"foo"[..]);
+    assert_eq!(batch.len(), 2);
+    storage.write(batch).unwrap();
+
+    // Recreate the expected final state.
+    let mut expected_memory: HashMap<&[u8], Vec<u8>> = HashMap::new();
+    expected_memory.insert(b"beer", b"ale".to_vec());
+    let expected_storage = InMemoryStorage {
+        memory: expected_memory,
+    };
+
+    // The final state of the storage should equal the expected value.
+    assert_eq!(storage, expected_storage);
+}
+
+#[test]
+fn storage_prefix_iterator() {
+    // Recreate an `InMemoryStorage` with data in it.
+    let mut memory: HashMap<&[u8], Vec<u8>> = HashMap::new();
+    memory.insert(b"peer-2", b"b".to_vec());
+    memory.insert(b"peer-1", b"a".to_vec());
+    memory.insert(b"block-1", b"c".to_vec());
+    let storage = InMemoryStorage { memory };
+
+    // Iterate over the entries starting with "peer-".
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = storage.prefix_iterator(b"peer-").unwrap().collect();
+
+    // Only the matching entries should be returned, sorted by key.
+    assert_eq!(
+        entries,
+        vec![
+            (b"peer-1".to_vec(), b"a".to_vec()),
+            (b"peer-2".to_vec(), b"b".to_vec()),
+        ]
+    );
+}
@@ -0,0 +1,66 @@
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::storage::{Storage, StorageHelper, WriteBatch};
+
+#[test]
+fn memory_storage_crud() {
+    // Instantiate a new `MemoryStorage` through the constructor of the `Storage` trait.
+    let mut storage = *MemoryStorage::new(()).unwrap();
+
+    // Keys are copied, so they do not need to outlive the storage.
+    let key = b"foo".to_vec();
+    storage.put(&key, b"bar".to_vec()).unwrap();
+    drop(key);
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"bar".to_vec()));
+
+    // Update and delete the entry.
+    storage.put(b"foo", b"beer".to_vec()).unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"beer".to_vec()));
+    storage.delete(b"foo").unwrap();
+    assert_eq!(storage.get(b"foo").unwrap(), None);
+    assert_eq!(storage, MemoryStorage::default());
+}
+
+#[test]
+fn memory_storage_helper() {
+    let mut storage = MemoryStorage::default();
+
+    storage.put_t(b"int", 54i32).unwrap();
+
+    assert_eq!(storage.get_t::<i32>(b"int").unwrap(), Some(54));
+}
+
+#[test]
+fn memory_storage_get_view() {
+    let mut storage = MemoryStorage::default();
+    storage.put(b"foo", b"bar".to_vec()).unwrap();
+
+    let view = storage.get_view(b"foo").unwrap().unwrap();
+    assert_eq!(&view[..], b"bar");
+    // The view borrows the value kept in the storage
+    assert_eq!(view.as_ptr(), storage.memory[&b"foo".to_vec()].as_ptr());
+    assert!(storage.get_view(b"baz").unwrap().is_none());
+}
+
+#[test]
+fn memory_storage_write_batch_and_iterate() {
+    let mut storage = MemoryStorage::default();
+    storage.put(b"peer-3", b"c".to_vec()).unwrap();
+
+    // Apply several writes at once.
+    let mut batch = WriteBatch::new();
+    batch.put(&b"peer-2"[..], b"b".to_vec());
+    batch.put(&b"peer-1"[..], b"a".to_vec());
+    batch.put(&b"block-1"[..], b"x".to_vec());
+    batch.delete(&b"peer-3"[..]);
+    storage.write(batch).unwrap();
+
+    // Only the entries starting with the prefix are returned, sorted by key.
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = storage.prefix_iterator(b"peer-").unwrap().collect();
+    assert_eq!(
+        entries,
+        vec![
+            (b"peer-1".to_vec(), b"a".to_vec()),
+            (b"peer-2".to_vec(), b"b".to_vec()),
+        ]
+    );
+}
@@ -0,0 +1,38 @@
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::backends::read_only::ReadOnlyStorage;
+use witnet_storage::storage::{Storage, WriteBatch};
+
+#[test]
+fn read_only_storage_reads() {
+    let mut memory = MemoryStorage::default();
+    memory.put(b"peer-1", b"a".to_vec()).unwrap();
+    memory.put(b"block-1", b"b".to_vec()).unwrap();
+    let storage = ReadOnlyStorage::<(), _>::wrap(memory);
+
+    assert_eq!(storage.get(b"peer-1").unwrap(), Some(b"a".to_vec()));
+    assert_eq!(storage.get(b"peer-2").unwrap(), None);
+    assert_eq!(
+        storage
+            .prefix_iterator(b"peer-")
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec![(b"peer-1".to_vec(), b"a".to_vec())]
+    );
+}
+
+#[test]
+fn read_only_storage_refuses_writes() {
+    let mut memory = MemoryStorage::default();
+    memory.put(b"foo", b"bar".to_vec()).unwrap();
+    let mut storage = ReadOnlyStorage::<(), _>::wrap(memory);
+
+    assert!(storage.put(b"foo", b"baz".to_vec()).is_err());
+    assert!(storage.delete(b"foo").is_err());
+    let mut batch = WriteBatch::new();
+    batch.put(&b"other"[..], b"baz".to_vec());
+    assert!(storage.write(batch).is_err());
+
+    // Nothing was modified
+    assert_eq!(storage.get(b"foo").unwrap(), Some(b"bar".to_vec()));
+    assert_eq!(storage.inner().get(b"other").unwrap(), None);
+}
@@ -0,0 +1,55 @@
+use witnet_storage::backends::memory::MemoryStorage;
+use witnet_storage::snapshot::{export, import, MAGIC};
+use witnet_storage::storage::Storage;
+
+fn storage_with_entries() -> MemoryStorage {
+    let mut storage = MemoryStorage::default();
+    storage.put(b"chain", b"chain info".to_vec()).unwrap();
+    storage.put(b"peers", vec![]).unwrap();
+    storage.put(b"utxo-1", vec![1; 100]).unwrap();
+
+    storage
+}
+
+#[test]
+fn snapshot_roundtrip() {
+    let storage = storage_with_entries();
+    let mut snapshot = vec![];
+    let exported = export(&storage, &mut snapshot).unwrap();
+    assert_eq!(exported.entries, 3);
+    assert!(snapshot.starts_with(MAGIC));
+
+    let mut imported_storage = MemoryStorage::default();
+    let imported = import(&mut imported_storage, snapshot.as_slice()).unwrap();
+
+    assert_eq!(imported, exported);
+    assert_eq!(imported_storage, storage);
+}
+
+#[test]
+fn snapshot_corrupted() {
+    let mut snapshot = vec![];
+    export(&storage_with_entries(), &mut snapshot).unwrap();
+
+    // Modify a byte of a value
+    let mut corrupted = snapshot.clone();
+    let position = corrupted.len() - 50;
+    corrupted[position] ^= 1;
+    let mut storage = MemoryStorage::default();
+    assert!(import(&mut storage, corrupted.as_slice()).is_err());
+    assert_eq!(storage, MemoryStorage::default());
+
+    // Truncate it
+    let truncated = &snapshot[..snapshot.len() - 1];
+    assert!(import(&mut storage, truncated).is_err());
+    assert!(import(&mut storage, &b"not a snapshot"[..]).is_err());
+}
+
+#[test]
+fn snapshot_import_into_non_empty_storage() {
+    let mut snapshot = vec![];
+    export(&MemoryStorage::default(), &mut snapshot).unwrap();
+
+    let mut storage = storage_with_entries();
+    assert!(import(&mut storage, snapshot.as_slice()).is_err());
+}
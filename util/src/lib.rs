@@ -10,5 +10,8 @@
 
 pub mod error;
 
+/// IP address ranges and conversions of IP addresses
+pub mod net;
+
 /// Timestamp as UTC
 pub mod timestamp;
@@ -0,0 +1,160 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Range of IP addresses, written in CIDR notation (`10.0.0.0/8`, `2001:db8::/32`) or as a single
+/// address, which is a range with a single element
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IpNetwork {
+    /// First address of the range, with all the bits outside of the prefix set to zero
+    address: IpAddr,
+    /// Number of leading bits shared by all the addresses of the range
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Create the range of the addresses sharing the first `prefix_len` bits with `address`
+    pub fn new(address: IpAddr, prefix_len: u8) -> Result<IpNetwork, String> {
+        let max_prefix_len = max_prefix_len(address);
+        if prefix_len > max_prefix_len {
+            return Err(format!(
+                "Prefix length {} is greater than {}",
+                prefix_len, max_prefix_len
+            ));
+        }
+
+        Ok(IpNetwork {
+            address: mask(address, prefix_len),
+            prefix_len,
+        })
+    }
+
+    /// First address of the range
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// Length of the prefix of the range
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Check whether `ip` is in this range
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.address, ip) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                mask(ip, self.prefix_len) == self.address
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpNetwork {
+    fn from(address: IpAddr) -> Self {
+        IpNetwork {
+            address,
+            prefix_len: max_prefix_len(address),
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let address = parts
+            .next()
+            .unwrap_or("")
+            .parse::<IpAddr>()
+            .map_err(|e| format!("Invalid address in {:?}: {}", s, e))?;
+
+        match parts.next() {
+            None => Ok(IpNetwork::from(address)),
+            Some(prefix_len) => {
+                let prefix_len = prefix_len
+                    .parse::<u8>()
+                    .map_err(|e| format!("Invalid prefix length in {:?}: {}", s, e))?;
+
+                IpNetwork::new(address, prefix_len).map_err(|e| format!("{} in {:?}", e, s))
+            }
+        }
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.prefix_len == max_prefix_len(self.address) {
+            write!(f, "{}", self.address)
+        } else {
+            write!(f, "{}/{}", self.address, self.prefix_len)
+        }
+    }
+}
+
+impl Serialize for IpNetwork {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpNetwork {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Convert IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`), which is how a dual-stack socket sees
+/// the IPv4 peers, into IPv4 addresses, so each peer has a single address. Other addresses are
+/// returned unchanged
+pub fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ip) if ip.segments()[..6] == [0, 0, 0, 0, 0, 0xffff] => {
+            let octets = ip.octets();
+            IpAddr::V4(Ipv4Addr::new(
+                octets[12], octets[13], octets[14], octets[15],
+            ))
+        }
+        ip => ip,
+    }
+}
+
+/// Socket address with its IP address converted by `canonical_ip`
+pub fn canonical_socket_addr(addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(canonical_ip(addr.ip()), addr.port())
+}
+
+/// Number of bits of the addresses of the family of `address`
+fn max_prefix_len(address: IpAddr) -> u8 {
+    match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Set to zero all the bits of `address` after the first `prefix_len`
+fn mask(address: IpAddr, prefix_len: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(ip) => {
+            let bits = u32::from(ip)
+                .checked_shr(32 - u32::from(prefix_len))
+                .and_then(|bits| bits.checked_shl(32 - u32::from(prefix_len)))
+                .unwrap_or(0);
+
+            IpAddr::V4(Ipv4Addr::from(bits))
+        }
+        IpAddr::V6(ip) => {
+            let bits = u128::from(ip)
+                .checked_shr(128 - u32::from(prefix_len))
+                .and_then(|bits| bits.checked_shl(128 - u32::from(prefix_len)))
+                .unwrap_or(0);
+
+            IpAddr::V6(Ipv6Addr::from(bits))
+        }
+    }
+}